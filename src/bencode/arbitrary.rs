@@ -0,0 +1,131 @@
+use super::BencodeElem;
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+use std::collections::HashMap;
+
+// Limits that keep generated elements small enough to be useful
+// for fuzzing (and prevent unbounded recursion).
+const MAX_DEPTH: usize = 4;
+const MAX_LEN: usize = 8;
+
+impl<'a> Arbitrary<'a> for BencodeElem {
+    /// Generate an element in its canonical form, i.e. the form that
+    /// [`BencodeElem::from_bytes()`] returns after encoding it:
+    /// `Bytes` is only generated if the bytes are not valid UTF-8, and
+    /// `RawDictionary` only if some of its keys are not valid UTF-8.
+    ///
+    /// [`BencodeElem::from_bytes()`]: enum.BencodeElem.html#method.from_bytes
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_elem(u, 0)
+    }
+}
+
+pub(crate) fn arbitrary_elem(u: &mut Unstructured, depth: usize) -> Result<BencodeElem> {
+    // only scalars once `MAX_DEPTH` is reached
+    let num_variants = if depth < MAX_DEPTH { 5 } else { 3 };
+
+    Ok(match u.choose_index(num_variants)? {
+        0 => BencodeElem::Integer(u.arbitrary()?),
+        1 => BencodeElem::String(u.arbitrary()?),
+        2 => bytes_to_elem(u.arbitrary()?),
+        3 => {
+            let len = u.int_in_range(0..=MAX_LEN)?;
+            BencodeElem::List(
+                (0..len)
+                    .map(|_| arbitrary_elem(u, depth + 1))
+                    .collect::<Result<_>>()?,
+            )
+        }
+        _ => {
+            let len = u.int_in_range(0..=MAX_LEN)?;
+            let entries = (0..len)
+                .map(|_| Ok((u.arbitrary::<Vec<u8>>()?, arbitrary_elem(u, depth + 1)?)))
+                .collect::<Result<HashMap<_, _>>>()?;
+            entries_to_elem(entries)
+        }
+    })
+}
+
+// Like `arbitrary_elem()`, but returns a dictionary with UTF-8 keys,
+// none of which is in `reserved`. Returns `None` if it would be empty.
+pub(crate) fn arbitrary_dictionary(
+    u: &mut Unstructured,
+    reserved: &[&str],
+) -> Result<Option<HashMap<String, BencodeElem>>> {
+    let len = u.int_in_range(0..=MAX_LEN)?;
+    let mut dict = HashMap::new();
+    for _ in 0..len {
+        let key: String = u.arbitrary()?;
+        let value = arbitrary_elem(u, 1)?;
+        if !reserved.contains(&key.as_str()) {
+            dict.insert(key, value);
+        }
+    }
+
+    if dict.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(dict))
+    }
+}
+
+fn bytes_to_elem(bytes: Vec<u8>) -> BencodeElem {
+    match String::from_utf8(bytes) {
+        Ok(string) => BencodeElem::String(string),
+        Err(e) => BencodeElem::Bytes(e.into_bytes()),
+    }
+}
+
+fn entries_to_elem(entries: HashMap<Vec<u8>, BencodeElem>) -> BencodeElem {
+    if entries.keys().all(|key| std::str::from_utf8(key).is_ok()) {
+        BencodeElem::Dictionary(
+            entries
+                .into_iter()
+                .map(|(key, value)| (String::from_utf8(key).unwrap(), value))
+                .collect(),
+        )
+    } else {
+        BencodeElem::RawDictionary(entries)
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod bencode_elem_arbitrary_tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{RngCore, SeedableRng};
+
+    #[test]
+    fn arbitrary_round_trip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut data = vec![0; 4096];
+
+        for _ in 0..256 {
+            rng.fill_bytes(&mut data);
+            let elem = BencodeElem::arbitrary(&mut Unstructured::new(&data)).unwrap();
+
+            assert_eq!(BencodeElem::from_bytes(elem.encode()).unwrap(), vec![elem]);
+        }
+    }
+
+    #[test]
+    fn arbitrary_empty_input() {
+        let elem = BencodeElem::arbitrary(&mut Unstructured::new(&[])).unwrap();
+        assert_eq!(BencodeElem::from_bytes(elem.encode()).unwrap(), vec![elem]);
+    }
+
+    #[test]
+    fn arbitrary_dictionary_reserved() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut data = vec![0; 1024];
+
+        for _ in 0..64 {
+            rng.fill_bytes(&mut data);
+            let dict = arbitrary_dictionary(&mut Unstructured::new(&data), &[""]).unwrap();
+
+            if let Some(dict) = dict {
+                assert!(!dict.is_empty());
+                assert!(!dict.contains_key(""));
+            }
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use super::*;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+impl BencodeElem {
+    /// Compare `self` with `other` and return the paths at which they differ.
+    ///
+    /// Dictionaries are compared by key and lists by index, recursively.
+    /// Elements of different types (e.g. a `String` and a `List`) are reported
+    /// as changed without descending into them. Keys of `RawDictionary`s and
+    /// `SortedDictionary`s are converted with `String::from_utf8_lossy()`, so paths through keys that
+    /// are not valid utf8 can't be looked up again. All paths are sorted.
+    ///
+    /// This is useful for finding out what a client or tracker
+    /// changed when it re-encoded a torrent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lava_torrent::bencode::BencodeElem;
+    ///
+    /// let before = BencodeElem::from_bytes("d4:infod4:name6:sampleee").unwrap().remove(0);
+    /// let after = BencodeElem::from_bytes("d4:infod4:name4:spam7:privatei1eee")
+    ///     .unwrap()
+    ///     .remove(0);
+    /// let diff = before.diff(&after);
+    ///
+    /// assert_eq!(diff.added, vec![vec!["info", "private"]]);
+    /// assert!(diff.removed.is_empty());
+    /// assert_eq!(diff.changed, vec![vec!["info", "name"]]);
+    /// ```
+    pub fn diff(&self, other: &BencodeElem) -> BencodeDiff {
+        let mut diff = BencodeDiff::default();
+        self.diff_into(other, &mut Vec::new(), &mut diff);
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        diff
+    }
+
+    fn diff_into(&self, other: &BencodeElem, path: &mut Vec<String>, diff: &mut BencodeDiff) {
+        match (self, other) {
+            (BencodeElem::Dictionary(dict), BencodeElem::Dictionary(other_dict)) => {
+                diff_dictionaries(dict, other_dict, |key| key.clone(), path, diff)
+            }
+            (BencodeElem::RawDictionary(dict), BencodeElem::RawDictionary(other_dict)) => {
+                diff_dictionaries(
+                    dict,
+                    other_dict,
+                    |key| String::from_utf8_lossy(key).into_owned(),
+                    path,
+                    diff,
+                )
+            }
+            (BencodeElem::SortedDictionary(dict), BencodeElem::SortedDictionary(other_dict)) => {
+                diff_dictionaries(
+                    dict,
+                    other_dict,
+                    |key| String::from_utf8_lossy(key).into_owned(),
+                    path,
+                    diff,
+                )
+            }
+            (BencodeElem::List(list), BencodeElem::List(other_list)) => {
+                for (index, elem) in list.iter().enumerate() {
+                    path.push(index.to_string());
+                    match other_list.get(index) {
+                        Some(other_elem) => elem.diff_into(other_elem, path, diff),
+                        None => diff.removed.push(path.clone()),
+                    }
+                    path.pop();
+                }
+                for index in list.len()..other_list.len() {
+                    path.push(index.to_string());
+                    diff.added.push(path.clone());
+                    path.pop();
+                }
+            }
+            _ => {
+                if self != other {
+                    diff.changed.push(path.clone());
+                }
+            }
+        }
+    }
+}
+
+impl BencodeDiff {
+    /// Check if there are no differences.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+fn diff_dictionaries<'a, K, D, F>(
+    dict: D,
+    other: D,
+    key_to_string: F,
+    path: &mut Vec<String>,
+    diff: &mut BencodeDiff,
+) where
+    K: Eq + Hash + 'a,
+    D: IntoIterator<Item = (&'a K, &'a BencodeElem)>,
+    F: Fn(&K) -> String,
+{
+    let other: HashMap<&K, &BencodeElem> = other.into_iter().collect();
+    let mut keys: HashSet<&K> = HashSet::new();
+    for (key, value) in dict {
+        keys.insert(key);
+        path.push(key_to_string(key));
+        match other.get(key) {
+            Some(other_value) => value.diff_into(other_value, path, diff),
+            None => diff.removed.push(path.clone()),
+        }
+        path.pop();
+    }
+
+    for key in other.keys().filter(|key| !keys.contains(*key)) {
+        path.push(key_to_string(key));
+        diff.added.push(path.clone());
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod bencode_elem_diff_tests {
+    use super::*;
+
+    #[test]
+    fn diff_same() {
+        let elem = bencode_elem!({ ("info", { ("name", "sample") }), ("list", [1_i64]) });
+        assert!(elem.diff(&elem.clone()).is_empty());
+    }
+
+    #[test]
+    fn diff_dictionary() {
+        let elem = bencode_elem!({ ("info", { ("name", "sample"), ("length", 1_i64) }) });
+        let other = bencode_elem!({
+            ("announce", "url"),
+            ("info", { ("name", "spam"), ("private", 1_i64) })
+        });
+        assert_eq!(
+            elem.diff(&other),
+            BencodeDiff {
+                added: vec![
+                    vec!["announce".to_owned()],
+                    vec!["info".to_owned(), "private".to_owned()]
+                ],
+                removed: vec![vec!["info".to_owned(), "length".to_owned()]],
+                changed: vec![vec!["info".to_owned(), "name".to_owned()]],
+            }
+        );
+    }
+
+    #[test]
+    fn diff_list() {
+        let elem = bencode_elem!([1_i64, 2_i64]);
+        let other = bencode_elem!([1_i64, 3_i64, 4_i64]);
+        assert_eq!(
+            elem.diff(&other),
+            BencodeDiff {
+                added: vec![vec!["2".to_owned()]],
+                removed: vec![],
+                changed: vec![vec!["1".to_owned()]],
+            }
+        );
+        assert_eq!(
+            other.diff(&elem),
+            BencodeDiff {
+                added: vec![],
+                removed: vec![vec!["2".to_owned()]],
+                changed: vec![vec!["1".to_owned()]],
+            }
+        );
+    }
+
+    #[test]
+    fn diff_raw_dictionary() {
+        let elem = bencode_elem!(r{ ([0xff], 1_i64) });
+        let other = bencode_elem!(r{ ([0xff], 2_i64), ([0x61], 3_i64) });
+        assert_eq!(
+            elem.diff(&other),
+            BencodeDiff {
+                added: vec![vec!["a".to_owned()]],
+                removed: vec![],
+                changed: vec![vec!["\u{fffd}".to_owned()]],
+            }
+        );
+    }
+
+    #[test]
+    fn diff_type_changed() {
+        let elem = bencode_elem!({ ("info", { ("name", "sample") }) });
+        let other = bencode_elem!({ ("info", ["sample"]) });
+        assert_eq!(
+            elem.diff(&other),
+            BencodeDiff {
+                added: vec![],
+                removed: vec![],
+                changed: vec![vec!["info".to_owned()]],
+            }
+        );
+        assert_eq!(
+            bencode_elem!(1_i64).diff(&bencode_elem!("1")),
+            BencodeDiff {
+                added: vec![],
+                removed: vec![],
+                changed: vec![vec![]],
+            }
+        );
+    }
+}
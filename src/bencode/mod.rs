@@ -3,15 +3,22 @@
 //! Most of methods are associated methods of `BencodeElem`. Some general methods
 //! are placed at the module level, and they can be found in [`write`](write/index.html).
 
+use crate::LavaTorrentError;
 use itertools;
 use itertools::Itertools;
-use std::collections::HashMap;
-use std::convert::From;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::{From, TryFrom};
 use std::fmt;
+use std::ops::Index;
 
 #[cfg(test)]
 #[macro_use]
 mod macros;
+#[cfg(feature = "arbitrary")]
+pub(crate) mod arbitrary;
+mod diff;
+mod path;
 mod read;
 pub mod write;
 
@@ -25,11 +32,11 @@ const STRING_DELIMITER: u8 = b':';
 
 /// Represent a single bencode element.
 ///
-/// There are 4 variants in the [spec], but this enum has 6 variants. The extra variants are
+/// There are 4 variants in the [spec], but this enum has 7 variants. The extra variants are
 /// `Bytes` (a sequence of bytes that does not represent a valid utf8
 /// string, e.g. a SHA1 block hash), which is considered to be the
-/// same as `String` in the [spec], and `RawDictionary`, which has keys that are not
-/// valid utf8 strings. They are best treated differently
+/// same as `String` in the [spec], `RawDictionary`, which has keys that are not
+/// valid utf8 strings, and `SortedDictionary` (see below). They are best treated differently
 /// in actual implementations to make things easier.
 ///
 /// Note that the `Integer` variant here uses `i64` explicitly instead of using a type alias like
@@ -37,7 +44,18 @@ const STRING_DELIMITER: u8 = b':';
 /// bencode directly then what you are doing is relatively low-level. In this case, exposing the
 /// underlying type might actually be better.
 ///
+/// `Dictionary` and `RawDictionary` are backed by `HashMap`s, so their iteration order
+/// is unspecified. Encoding always writes keys in the canonical (sorted) order, so
+/// re-encoding is deterministic. Use [`sorted_entries()`] to iterate in the same order.
+///
+/// `SortedDictionary` is backed by a `BTreeMap` instead, so iterating over it
+/// also yields keys in the canonical order. It is only produced when
+/// [`ReadOptions::sorted_dictionaries`] is set, or by [`into_sorted()`].
+///
 /// [`Integer`]: ../torrent/v1/type.Integer.html
+/// [`sorted_entries()`]: #method.sorted_entries
+/// [`ReadOptions::sorted_dictionaries`]: struct.ReadOptions.html#structfield.sorted_dictionaries
+/// [`into_sorted()`]: #method.into_sorted
 /// [spec]: http://bittorrent.org/beps/bep_0003.html
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum BencodeElem {
@@ -47,6 +65,255 @@ pub enum BencodeElem {
     List(Vec<BencodeElem>),
     Dictionary(HashMap<String, BencodeElem>),
     RawDictionary(HashMap<Vec<u8>, BencodeElem>),
+    SortedDictionary(BTreeMap<Vec<u8>, BencodeElem>),
+}
+
+/// A limit on the input accepted by [`BencodeElem::from_bytes_with_limits()`]
+/// and [`BencodeElem::from_file_with_limits()`].
+///
+/// Limits can be combined, and parsing fails with
+/// [`LavaTorrentError::MalformedBencode`] as soon as any of them is exceeded.
+/// This is useful when parsing untrusted input (e.g. user uploads),
+/// which could otherwise exhaust memory.
+///
+/// [`BencodeElem::from_bytes_with_limits()`]: enum.BencodeElem.html#method.from_bytes_with_limits
+/// [`BencodeElem::from_file_with_limits()`]: enum.BencodeElem.html#method.from_file_with_limits
+/// [`LavaTorrentError::MalformedBencode`]: ../enum.LavaTorrentError.html#variant.MalformedBencode
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReadLimit {
+    /// Maximum number of top-level elements.
+    TopLevelElements(usize),
+    /// Maximum size of the input in bytes.
+    Bytes(usize),
+    /// Maximum number of elements, including nested ones (e.g. a list
+    /// with 2 integers counts as 3 elements). Dictionary keys are not counted.
+    Elements(usize),
+}
+
+/// How duplicate keys in a dictionary are handled when parsing.
+///
+/// Clients disagree on this, so it can be configured through [`ReadOptions`].
+///
+/// [`ReadOptions`]: struct.ReadOptions.html
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DuplicateKeyPolicy {
+    /// Fail with [`LavaTorrentError::MalformedBencode`].
+    ///
+    /// [`LavaTorrentError::MalformedBencode`]: ../enum.LavaTorrentError.html#variant.MalformedBencode
+    Error,
+    /// Keep the value of the first occurrence.
+    FirstWins,
+    /// Keep the value of the last occurrence.
+    #[default]
+    LastWins,
+}
+
+/// Options for [`BencodeElem::from_bytes_with_options()`]
+/// and [`BencodeElem::from_file_with_options()`].
+///
+/// The default options are the ones used by [`BencodeElem::from_bytes()`].
+///
+/// [`BencodeElem::from_bytes_with_options()`]: enum.BencodeElem.html#method.from_bytes_with_options
+/// [`BencodeElem::from_file_with_options()`]: enum.BencodeElem.html#method.from_file_with_options
+/// [`BencodeElem::from_bytes()`]: enum.BencodeElem.html#method.from_bytes
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReadOptions {
+    /// Limits on the input. See [`ReadLimit`].
+    ///
+    /// [`ReadLimit`]: enum.ReadLimit.html
+    pub limits: Vec<ReadLimit>,
+    /// Whether non-canonical input is rejected. See [`BencodeElem::from_bytes_strict()`].
+    ///
+    /// [`BencodeElem::from_bytes_strict()`]: enum.BencodeElem.html#method.from_bytes_strict
+    pub strict: bool,
+    /// How duplicate keys are handled. Ignored if `strict` is `true`,
+    /// in which case duplicate keys are always an error.
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// Whether all dictionaries are parsed as `SortedDictionary`s, whose
+    /// iteration order is the order in which the keys appear in the input.
+    pub sorted_dictionaries: bool,
+}
+
+/// Options for [`BencodeElem::write_into_file_with_options()`]
+/// and [`Torrent::write_into_file_with_options()`].
+///
+/// The default options are the ones used by [`BencodeElem::write_into_file()`].
+///
+/// [`BencodeElem::write_into_file_with_options()`]: enum.BencodeElem.html#method.write_into_file_with_options
+/// [`Torrent::write_into_file_with_options()`]: ../torrent/v1/struct.Torrent.html#method.write_into_file_with_options
+/// [`BencodeElem::write_into_file()`]: enum.BencodeElem.html#method.write_into_file
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct WriteOptions {
+    /// Whether the directory containing the file is also synced to disk,
+    /// so that a newly created file survives power loss immediately
+    /// after the write returns. The file itself is always synced.
+    ///
+    /// This has no effect on platforms that don't support
+    /// syncing directories (e.g. Windows).
+    pub durable: bool,
+}
+
+/// Structural differences between two [`BencodeElem`]s.
+///
+/// See [`BencodeElem::diff()`]. Each difference is reported as a path that
+/// can be passed to [`BencodeElem::get_path()`].
+///
+/// [`BencodeElem`]: enum.BencodeElem.html
+/// [`BencodeElem::diff()`]: enum.BencodeElem.html#method.diff
+/// [`BencodeElem::get_path()`]: enum.BencodeElem.html#method.get_path
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BencodeDiff {
+    /// Paths only found in the other element.
+    pub added: Vec<Vec<String>>,
+    /// Paths only found in this element.
+    pub removed: Vec<Vec<String>>,
+    /// Paths found in both elements but with different values.
+    pub changed: Vec<Vec<String>>,
+}
+
+impl BencodeElem {
+    /// Get the string if `self` is a `String`.
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            BencodeElem::String(ref string) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Get the integer if `self` is an `Integer`.
+    pub fn as_int(&self) -> Option<i64> {
+        match *self {
+            BencodeElem::Integer(int) => Some(int),
+            _ => None,
+        }
+    }
+
+    /// Get the bytes if `self` is `Bytes` or a `String`
+    /// (they are both byte strings in bencode).
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match *self {
+            BencodeElem::Bytes(ref bytes) => Some(bytes),
+            BencodeElem::String(ref string) => Some(string.as_bytes()),
+            _ => None,
+        }
+    }
+
+    /// Get the elements if `self` is a `List`.
+    pub fn as_list(&self) -> Option<&[BencodeElem]> {
+        match *self {
+            BencodeElem::List(ref list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Get the dictionary if `self` is a `Dictionary`.
+    pub fn as_dict(&self) -> Option<&HashMap<String, BencodeElem>> {
+        match *self {
+            BencodeElem::Dictionary(ref dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// Get the dictionary if `self` is a `RawDictionary`.
+    pub fn as_raw_dict(&self) -> Option<&HashMap<Vec<u8>, BencodeElem>> {
+        match *self {
+            BencodeElem::RawDictionary(ref dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// Get the dictionary if `self` is a `SortedDictionary`.
+    pub fn as_sorted_dict(&self) -> Option<&BTreeMap<Vec<u8>, BencodeElem>> {
+        match *self {
+            BencodeElem::SortedDictionary(ref dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// Convert all dictionaries in `self` (including nested ones)
+    /// into `SortedDictionary`s.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lava_torrent::bencode::BencodeElem;
+    ///
+    /// let dict = BencodeElem::from_bytes("d3:cowi2e4:spami1ee").unwrap().remove(0);
+    /// let sorted = dict.into_sorted();
+    /// let keys: Vec<&[u8]> = sorted
+    ///     .as_sorted_dict()
+    ///     .unwrap()
+    ///     .keys()
+    ///     .map(Vec::as_slice)
+    ///     .collect();
+    ///
+    /// assert_eq!(keys, vec![&b"cow"[..], &b"spam"[..]]);
+    /// ```
+    pub fn into_sorted(self) -> BencodeElem {
+        match self {
+            BencodeElem::List(list) => {
+                BencodeElem::List(list.into_iter().map(BencodeElem::into_sorted).collect())
+            }
+            BencodeElem::Dictionary(dict) => BencodeElem::SortedDictionary(
+                dict.into_iter()
+                    .map(|(k, v)| (k.into_bytes(), v.into_sorted()))
+                    .collect(),
+            ),
+            BencodeElem::RawDictionary(dict) => BencodeElem::SortedDictionary(
+                dict.into_iter()
+                    .map(|(k, v)| (k, v.into_sorted()))
+                    .collect(),
+            ),
+            BencodeElem::SortedDictionary(dict) => BencodeElem::SortedDictionary(
+                dict.into_iter()
+                    .map(|(k, v)| (k, v.into_sorted()))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    /// Get the entries of a `Dictionary`, `RawDictionary` or `SortedDictionary`, sorted by key.
+    ///
+    /// The first 2 variants are backed by `HashMap`s, whose iteration order is
+    /// unspecified. This returns the entries in the canonical order
+    /// required by the [spec] (keys sorted as raw strings), which is
+    /// also the order used by [`encode()`] and `Display`.
+    /// Keys of `Dictionary`s are returned as their utf8 bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lava_torrent::bencode::BencodeElem;
+    ///
+    /// let dict = BencodeElem::from_bytes("d3:cowi2e4:spami1ee").unwrap().remove(0);
+    /// let keys: Vec<&[u8]> = dict
+    ///     .sorted_entries()
+    ///     .unwrap()
+    ///     .into_iter()
+    ///     .map(|(key, _)| key)
+    ///     .collect();
+    ///
+    /// assert_eq!(keys, vec![&b"cow"[..], &b"spam"[..]]);
+    /// ```
+    ///
+    /// [spec]: http://bittorrent.org/beps/bep_0003.html
+    /// [`encode()`]: #method.encode
+    pub fn sorted_entries(&self) -> Option<Vec<(&[u8], &BencodeElem)>> {
+        let entries: Vec<(&[u8], &BencodeElem)> = match *self {
+            BencodeElem::Dictionary(ref dict) => {
+                dict.iter().map(|(k, v)| (k.as_bytes(), v)).collect()
+            }
+            BencodeElem::RawDictionary(ref dict) => {
+                dict.iter().map(|(k, v)| (k.as_slice(), v)).collect()
+            }
+            BencodeElem::SortedDictionary(ref dict) => {
+                return Some(dict.iter().map(|(k, v)| (k.as_slice(), v)).collect());
+            }
+            _ => return None,
+        };
+        Some(entries.into_iter().sorted_by_key(|&(key, _)| key).collect())
+    }
 }
 
 impl From<u8> for BencodeElem {
@@ -115,6 +382,199 @@ impl From<Vec<u8>> for BencodeElem {
     }
 }
 
+impl From<Vec<BencodeElem>> for BencodeElem {
+    fn from(val: Vec<BencodeElem>) -> BencodeElem {
+        BencodeElem::List(val)
+    }
+}
+
+impl<T> From<HashMap<String, T>> for BencodeElem
+where
+    T: Into<BencodeElem>,
+{
+    fn from(val: HashMap<String, T>) -> BencodeElem {
+        BencodeElem::Dictionary(val.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+impl<T> From<HashMap<Vec<u8>, T>> for BencodeElem
+where
+    T: Into<BencodeElem>,
+{
+    fn from(val: HashMap<Vec<u8>, T>) -> BencodeElem {
+        BencodeElem::RawDictionary(val.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+impl<T> From<BTreeMap<Vec<u8>, T>> for BencodeElem
+where
+    T: Into<BencodeElem>,
+{
+    fn from(val: BTreeMap<Vec<u8>, T>) -> BencodeElem {
+        BencodeElem::SortedDictionary(val.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+/// Collect elements into a `List`.
+///
+/// This is the generic counterpart of `From<Vec<BencodeElem>>`
+/// (a generic `From<Vec<T>>` would conflict with `From<Vec<u8>>`).
+///
+/// # Example
+///
+/// ```
+/// use lava_torrent::bencode::BencodeElem;
+///
+/// let list = vec!["spam", "eggs"].into_iter().collect::<BencodeElem>();
+/// assert_eq!(list.encode(), b"l4:spam4:eggse");
+/// ```
+impl<T> FromIterator<T> for BencodeElem
+where
+    T: Into<BencodeElem>,
+{
+    fn from_iter<I>(iter: I) -> BencodeElem
+    where
+        I: IntoIterator<Item = T>,
+    {
+        BencodeElem::List(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl TryFrom<BencodeElem> for i64 {
+    type Error = LavaTorrentError;
+
+    fn try_from(val: BencodeElem) -> Result<i64, LavaTorrentError> {
+        match val {
+            BencodeElem::Integer(int) => Ok(int),
+            _ => Err(LavaTorrentError::InvalidArgument(Cow::Borrowed(
+                "element is not an integer.",
+            ))),
+        }
+    }
+}
+
+impl TryFrom<BencodeElem> for String {
+    type Error = LavaTorrentError;
+
+    fn try_from(val: BencodeElem) -> Result<String, LavaTorrentError> {
+        match val {
+            BencodeElem::String(string) => Ok(string),
+            _ => Err(LavaTorrentError::InvalidArgument(Cow::Borrowed(
+                "element is not a string.",
+            ))),
+        }
+    }
+}
+
+/// Both `Bytes` and `String` can be converted,
+/// as they are both byte strings in bencode.
+impl TryFrom<BencodeElem> for Vec<u8> {
+    type Error = LavaTorrentError;
+
+    fn try_from(val: BencodeElem) -> Result<Vec<u8>, LavaTorrentError> {
+        match val {
+            BencodeElem::Bytes(bytes) => Ok(bytes),
+            BencodeElem::String(string) => Ok(string.into_bytes()),
+            _ => Err(LavaTorrentError::InvalidArgument(Cow::Borrowed(
+                "element is not a byte string.",
+            ))),
+        }
+    }
+}
+
+impl TryFrom<BencodeElem> for Vec<BencodeElem> {
+    type Error = LavaTorrentError;
+
+    fn try_from(val: BencodeElem) -> Result<Vec<BencodeElem>, LavaTorrentError> {
+        match val {
+            BencodeElem::List(list) => Ok(list),
+            _ => Err(LavaTorrentError::InvalidArgument(Cow::Borrowed(
+                "element is not a list.",
+            ))),
+        }
+    }
+}
+
+impl TryFrom<BencodeElem> for HashMap<String, BencodeElem> {
+    type Error = LavaTorrentError;
+
+    fn try_from(val: BencodeElem) -> Result<HashMap<String, BencodeElem>, LavaTorrentError> {
+        match val {
+            BencodeElem::Dictionary(dict) => Ok(dict),
+            _ => Err(LavaTorrentError::InvalidArgument(Cow::Borrowed(
+                "element is not a dictionary.",
+            ))),
+        }
+    }
+}
+
+impl TryFrom<BencodeElem> for HashMap<Vec<u8>, BencodeElem> {
+    type Error = LavaTorrentError;
+
+    fn try_from(val: BencodeElem) -> Result<HashMap<Vec<u8>, BencodeElem>, LavaTorrentError> {
+        match val {
+            BencodeElem::RawDictionary(dict) => Ok(dict),
+            _ => Err(LavaTorrentError::InvalidArgument(Cow::Borrowed(
+                "element is not a raw dictionary.",
+            ))),
+        }
+    }
+}
+
+impl TryFrom<BencodeElem> for BTreeMap<Vec<u8>, BencodeElem> {
+    type Error = LavaTorrentError;
+
+    fn try_from(val: BencodeElem) -> Result<BTreeMap<Vec<u8>, BencodeElem>, LavaTorrentError> {
+        match val {
+            BencodeElem::SortedDictionary(dict) => Ok(dict),
+            _ => Err(LavaTorrentError::InvalidArgument(Cow::Borrowed(
+                "element is not a sorted dictionary.",
+            ))),
+        }
+    }
+}
+
+/// Look up `key` in a `Dictionary`, `RawDictionary` or `SortedDictionary`.
+///
+/// # Panics
+///
+/// Panics if `self` is not a dictionary or does not contain `key`.
+/// Use [`get_path()`] for a non-panicking alternative.
+///
+/// [`get_path()`]: enum.BencodeElem.html#method.get_path
+impl<'a> Index<&'a str> for BencodeElem {
+    type Output = BencodeElem;
+
+    fn index(&self, key: &'a str) -> &BencodeElem {
+        let value = match *self {
+            BencodeElem::Dictionary(ref dict) => dict.get(key),
+            BencodeElem::RawDictionary(ref dict) => dict.get(key.as_bytes()),
+            BencodeElem::SortedDictionary(ref dict) => dict.get(key.as_bytes()),
+            _ => panic!("cannot index a non-dictionary element with [{}]", key),
+        };
+        value.unwrap_or_else(|| panic!("key [{}] does not exist", key))
+    }
+}
+
+/// Get the element at `index` in a `List`.
+///
+/// # Panics
+///
+/// Panics if `self` is not a list or `index` is out of bounds.
+/// Use [`get_path()`] for a non-panicking alternative.
+///
+/// [`get_path()`]: enum.BencodeElem.html#method.get_path
+impl Index<usize> for BencodeElem {
+    type Output = BencodeElem;
+
+    fn index(&self, index: usize) -> &BencodeElem {
+        match *self {
+            BencodeElem::List(ref list) => &list[index],
+            _ => panic!("cannot index a non-list element with [{}]", index),
+        }
+    }
+}
+
 impl fmt::Display for BencodeElem {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -136,10 +596,23 @@ impl fmt::Display for BencodeElem {
                     .sorted_by_key(|&(key, _)| key)
                     .format_with(", ", |(k, v), f| f(&format_args!(
                         r#"("{}", {})"#,
-                        k.iter().map(|b| format!("{:x}", b)).format(""),
+                        k.iter().map(|b| format!("{:02x}", b)).format(""),
                         v
                     )))
             ),
+            BencodeElem::SortedDictionary(ref dict) => write!(
+                f,
+                "{{ {} }}",
+                dict.iter()
+                    .format_with(", ", |(k, v), f| match std::str::from_utf8(k) {
+                        Ok(k) => f(&format_args!(r#"("{}", {})"#, k, v)),
+                        Err(_) => f(&format_args!(
+                            r#"("{}", {})"#,
+                            k.iter().map(|b| format!("{:02x}", b)).format(""),
+                            v
+                        )),
+                    })
+            ),
         }
     }
 }
@@ -179,4 +652,241 @@ mod bencode_elem_display_tests {
             r#"{ ("cow", { ("moo", 4) }), ("spam", "eggs") }"#,
         )
     }
+
+    #[test]
+    fn display_test_raw_dictionary() {
+        assert_eq!(
+            BencodeElem::RawDictionary(HashMap::from_iter(vec![
+                (vec![0xff, 0x0a], BencodeElem::Integer(1)),
+                (vec![0x01], BencodeElem::Integer(2)),
+            ]))
+            .to_string(),
+            r#"{ ("01", 2), ("ff0a", 1) }"#,
+        )
+    }
+}
+
+#[cfg(test)]
+mod bencode_elem_access_tests {
+    use super::*;
+    use std::iter::FromIterator;
+
+    #[test]
+    fn as_str_ok() {
+        assert_eq!(bencode_elem!("spam").as_str(), Some("spam"));
+        assert_eq!(bencode_elem!(1_i64).as_str(), None);
+    }
+
+    #[test]
+    fn as_int_ok() {
+        assert_eq!(bencode_elem!(1_i64).as_int(), Some(1));
+        assert_eq!(bencode_elem!("spam").as_int(), None);
+    }
+
+    #[test]
+    fn as_bytes_ok() {
+        assert_eq!(
+            bencode_elem!((0xff, 0xfe)).as_bytes(),
+            Some(&[0xff, 0xfe][..])
+        );
+        assert_eq!(bencode_elem!("spam").as_bytes(), Some(&b"spam"[..]));
+        assert_eq!(bencode_elem!(1_i64).as_bytes(), None);
+    }
+
+    #[test]
+    fn as_list_ok() {
+        assert_eq!(
+            bencode_elem!(["spam", 1_i64]).as_list(),
+            Some(&[bencode_elem!("spam"), bencode_elem!(1_i64)][..])
+        );
+        assert_eq!(bencode_elem!("spam").as_list(), None);
+    }
+
+    #[test]
+    fn as_dict_ok() {
+        assert_eq!(
+            bencode_elem!({ ("spam", 1_i64) }).as_dict(),
+            Some(&HashMap::from_iter(vec![(
+                "spam".to_owned(),
+                bencode_elem!(1_i64)
+            )]))
+        );
+        assert_eq!(bencode_elem!(r{ ([0xff], 1_i64) }).as_dict(), None);
+        assert_eq!(
+            bencode_elem!(r{ ([0xff], 1_i64) }).as_raw_dict(),
+            Some(&HashMap::from_iter(vec![(
+                vec![0xff],
+                bencode_elem!(1_i64)
+            )]))
+        );
+    }
+
+    #[test]
+    fn sorted_entries_ok() {
+        let dict = bencode_elem!({ ("spam", 1_i64), ("cow", 2_i64), ("Zebra", 3_i64) });
+        assert_eq!(
+            dict.sorted_entries(),
+            Some(vec![
+                (&b"Zebra"[..], &bencode_elem!(3_i64)),
+                (&b"cow"[..], &bencode_elem!(2_i64)),
+                (&b"spam"[..], &bencode_elem!(1_i64)),
+            ])
+        );
+
+        let dict = bencode_elem!(r{ ([0xff], 1_i64), ([0x00, 0x01], 2_i64) });
+        assert_eq!(
+            dict.sorted_entries(),
+            Some(vec![
+                (&[0x00, 0x01][..], &bencode_elem!(2_i64)),
+                (&[0xff][..], &bencode_elem!(1_i64)),
+            ])
+        );
+        assert_eq!(bencode_elem!([1_i64]).sorted_entries(), None);
+    }
+
+    #[test]
+    fn into_sorted_ok() {
+        let dict = bencode_elem!({ ("spam", [{ ("b", 1_i64), ("a", 2_i64) }]), ("cow", "moo") });
+        let sorted = dict.clone().into_sorted();
+
+        assert_eq!(
+            sorted,
+            BencodeElem::SortedDictionary(BTreeMap::from([
+                (b"cow".to_vec(), bencode_elem!("moo")),
+                (
+                    b"spam".to_vec(),
+                    BencodeElem::List(vec![BencodeElem::SortedDictionary(BTreeMap::from([
+                        (b"a".to_vec(), bencode_elem!(2_i64)),
+                        (b"b".to_vec(), bencode_elem!(1_i64)),
+                    ]))])
+                ),
+            ]))
+        );
+        assert_eq!(
+            sorted.as_sorted_dict().unwrap().keys().collect::<Vec<_>>(),
+            vec![&b"cow".to_vec(), &b"spam".to_vec()]
+        );
+        assert_eq!(sorted.encode(), dict.encode());
+        assert_eq!(sorted["cow"], bencode_elem!("moo"));
+        assert_eq!(
+            sorted.get_path(&["spam", "0", "a"]),
+            Some(&bencode_elem!(2_i64))
+        );
+        assert!(sorted.diff(&sorted.clone()).is_empty());
+    }
+
+    #[test]
+    fn encode_is_deterministic() {
+        let keys = (0..64).map(|i| format!("key{}", i)).collect::<Vec<_>>();
+        let dict = BencodeElem::from(HashMap::from_iter(
+            keys.iter().map(|key| (key.clone(), key.as_str())),
+        ));
+        let reversed = BencodeElem::from(HashMap::from_iter(
+            keys.iter().rev().map(|key| (key.clone(), key.as_str())),
+        ));
+        assert_eq!(dict.encode(), reversed.encode());
+        assert_eq!(dict.to_string(), reversed.to_string());
+    }
+
+    #[test]
+    fn index_ok() {
+        let elem = bencode_elem!({ ("info", { ("files", [{ ("length", 42_i64) }]), ("name", "sample") }) });
+
+        assert_eq!(elem["info"]["name"].as_str(), Some("sample"));
+        assert_eq!(elem["info"]["files"][0]["length"].as_int(), Some(42));
+        assert_eq!(bencode_elem!(r{ ([b'a'], 1_i64) })["a"].as_int(), Some(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "key [comment] does not exist")]
+    fn index_missing_key() {
+        let _ = &bencode_elem!({ ("name", "sample") })["comment"];
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot index a non-list element with [0]")]
+    fn index_not_list() {
+        let _ = &bencode_elem!({ ("name", "sample") })[0];
+    }
+}
+
+#[cfg(test)]
+mod bencode_elem_conversion_tests {
+    use super::*;
+    use std::iter::FromIterator;
+
+    #[test]
+    fn from_vec_ok() {
+        assert_eq!(
+            BencodeElem::from(vec![bencode_elem!(1_i64), bencode_elem!("spam")]),
+            bencode_elem!([1_i64, "spam"])
+        );
+    }
+
+    #[test]
+    fn from_iter_ok() {
+        assert_eq!(
+            vec![1, 2].into_iter().collect::<BencodeElem>(),
+            bencode_elem!([1_i64, 2_i64])
+        );
+    }
+
+    #[test]
+    fn from_hash_map_ok() {
+        assert_eq!(
+            BencodeElem::from(HashMap::from_iter(vec![
+                ("spam".to_owned(), "eggs"),
+                ("cow".to_owned(), "moo"),
+            ])),
+            bencode_elem!({ ("cow", "moo"), ("spam", "eggs") })
+        );
+        assert_eq!(
+            BencodeElem::from(HashMap::from_iter(vec![(vec![0xff], 1)])),
+            bencode_elem!(r{ ([0xff], 1_i64) })
+        );
+    }
+
+    #[test]
+    fn try_from_ok() {
+        assert_eq!(i64::try_from(bencode_elem!(1_i64)).unwrap(), 1);
+        assert_eq!(String::try_from(bencode_elem!("spam")).unwrap(), "spam");
+        assert_eq!(
+            Vec::<u8>::try_from(bencode_elem!((0xff, 0xfe))).unwrap(),
+            vec![0xff, 0xfe]
+        );
+        assert_eq!(
+            Vec::<u8>::try_from(bencode_elem!("spam")).unwrap(),
+            b"spam".to_vec()
+        );
+        assert_eq!(
+            Vec::<BencodeElem>::try_from(bencode_elem!([1_i64])).unwrap(),
+            vec![bencode_elem!(1_i64)]
+        );
+        assert_eq!(
+            HashMap::<String, BencodeElem>::try_from(bencode_elem!({ ("spam", 1_i64) })).unwrap(),
+            HashMap::from_iter(vec![("spam".to_owned(), bencode_elem!(1_i64))])
+        );
+        assert_eq!(
+            HashMap::<Vec<u8>, BencodeElem>::try_from(bencode_elem!(r{ ([0xff], 1_i64) })).unwrap(),
+            HashMap::from_iter(vec![(vec![0xff], bencode_elem!(1_i64))])
+        );
+    }
+
+    #[test]
+    fn try_from_wrong_type() {
+        match i64::try_from(bencode_elem!("spam")) {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, "element is not an integer.")
+            }
+            _ => panic!(),
+        }
+        match String::try_from(bencode_elem!((0xff, 0xfe))) {
+            Err(LavaTorrentError::InvalidArgument(m)) => assert_eq!(m, "element is not a string."),
+            _ => panic!(),
+        }
+        assert!(Vec::<u8>::try_from(bencode_elem!(1_i64)).is_err());
+        assert!(Vec::<BencodeElem>::try_from(bencode_elem!(1_i64)).is_err());
+        assert!(HashMap::<String, BencodeElem>::try_from(bencode_elem!([])).is_err());
+        assert!(HashMap::<Vec<u8>, BencodeElem>::try_from(bencode_elem!({})).is_err());
+    }
 }
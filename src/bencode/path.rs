@@ -0,0 +1,360 @@
+use super::*;
+use crate::LavaTorrentError;
+use std::borrow::Cow;
+
+impl BencodeElem {
+    /// Get the element at `path`, or `None` if there is no such element.
+    ///
+    /// Each component of `path` is either a dictionary key or,
+    /// if the current element is a list, an index into the list.
+    /// Keys of `RawDictionary`s and `SortedDictionary`s are matched against
+    /// the bytes of the component.
+    /// An empty `path` refers to `self`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lava_torrent::bencode::BencodeElem;
+    ///
+    /// let torrent = BencodeElem::from_bytes("d4:infod5:filesld6:lengthi42eeeee").unwrap();
+    ///
+    /// assert_eq!(
+    ///     torrent[0].get_path(&["info", "files", "0", "length"]),
+    ///     Some(&BencodeElem::Integer(42)),
+    /// );
+    /// assert_eq!(torrent[0].get_path(&["info", "files", "1", "length"]), None);
+    /// ```
+    pub fn get_path<S>(&self, path: &[S]) -> Option<&BencodeElem>
+    where
+        S: AsRef<str>,
+    {
+        path.iter()
+            .try_fold(self, |elem, component| elem.get_child(component.as_ref()))
+    }
+
+    /// Like [`get_path()`], but returns a mutable reference.
+    ///
+    /// [`get_path()`]: #method.get_path
+    pub fn get_path_mut<S>(&mut self, path: &[S]) -> Option<&mut BencodeElem>
+    where
+        S: AsRef<str>,
+    {
+        path.iter().try_fold(self, |elem, component| {
+            elem.get_child_mut(component.as_ref())
+        })
+    }
+
+    /// Set the element at `path` to `value`, and return the element previously there.
+    ///
+    /// Missing dictionaries along `path` are created as needed. List indices,
+    /// however, must refer to existing elements. `path` is interpreted the
+    /// same way as in [`get_path()`].
+    ///
+    /// `Err(LavaTorrentError::InvalidArgument)` is returned if `path` is empty,
+    /// goes through an element that is neither a dictionary nor a list,
+    /// or contains an invalid list index.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lava_torrent::bencode::BencodeElem;
+    ///
+    /// let mut torrent = BencodeElem::from_bytes("d4:infod4:name6:sampleee").unwrap().remove(0);
+    /// torrent
+    ///     .set_path(&["info", "private"], BencodeElem::Integer(1))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     torrent.get_path(&["info", "private"]),
+    ///     Some(&BencodeElem::Integer(1)),
+    /// );
+    /// ```
+    ///
+    /// [`get_path()`]: #method.get_path
+    pub fn set_path<S>(
+        &mut self,
+        path: &[S],
+        value: BencodeElem,
+    ) -> Result<Option<BencodeElem>, LavaTorrentError>
+    where
+        S: AsRef<str>,
+    {
+        let (last, parents) =
+            path.split_last()
+                .ok_or(LavaTorrentError::InvalidArgument(Cow::Borrowed(
+                    "path is empty.",
+                )))?;
+        let mut elem = self;
+        for component in parents {
+            elem = elem.get_or_insert_child(component.as_ref())?;
+        }
+
+        let last = last.as_ref();
+        match *elem {
+            BencodeElem::Dictionary(ref mut dict) => Ok(dict.insert(last.to_owned(), value)),
+            BencodeElem::RawDictionary(ref mut dict) => {
+                Ok(dict.insert(last.as_bytes().to_vec(), value))
+            }
+            BencodeElem::SortedDictionary(ref mut dict) => {
+                Ok(dict.insert(last.as_bytes().to_vec(), value))
+            }
+            BencodeElem::List(ref mut list) => match list.get_mut(Self::list_index(last)?) {
+                Some(old) => Ok(Some(std::mem::replace(old, value))),
+                None => Err(Self::index_out_of_bounds(last)),
+            },
+            _ => Err(Self::not_a_container(last)),
+        }
+    }
+
+    /// Remove the element at `path` and return it, or `None` if there is no such element.
+    ///
+    /// Removing an element from a list shifts all elements after it.
+    /// An empty `path` removes nothing.
+    pub fn remove_path<S>(&mut self, path: &[S]) -> Option<BencodeElem>
+    where
+        S: AsRef<str>,
+    {
+        let (last, parents) = path.split_last()?;
+        let last = last.as_ref();
+
+        match *self.get_path_mut(parents)? {
+            BencodeElem::Dictionary(ref mut dict) => dict.remove(last),
+            BencodeElem::RawDictionary(ref mut dict) => dict.remove(last.as_bytes()),
+            BencodeElem::SortedDictionary(ref mut dict) => dict.remove(last.as_bytes()),
+            BencodeElem::List(ref mut list) => {
+                let index = last.parse::<usize>().ok()?;
+                if index < list.len() {
+                    Some(list.remove(index))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn get_or_insert_child(
+        &mut self,
+        component: &str,
+    ) -> Result<&mut BencodeElem, LavaTorrentError> {
+        match *self {
+            BencodeElem::Dictionary(ref mut dict) => Ok(dict
+                .entry(component.to_owned())
+                .or_insert_with(|| BencodeElem::Dictionary(HashMap::new()))),
+            BencodeElem::RawDictionary(ref mut dict) => Ok(dict
+                .entry(component.as_bytes().to_vec())
+                .or_insert_with(|| BencodeElem::Dictionary(HashMap::new()))),
+            BencodeElem::SortedDictionary(ref mut dict) => Ok(dict
+                .entry(component.as_bytes().to_vec())
+                .or_insert_with(|| BencodeElem::SortedDictionary(BTreeMap::new()))),
+            BencodeElem::List(ref mut list) => list
+                .get_mut(Self::list_index(component)?)
+                .ok_or_else(|| Self::index_out_of_bounds(component)),
+            _ => Err(Self::not_a_container(component)),
+        }
+    }
+
+    fn list_index(component: &str) -> Result<usize, LavaTorrentError> {
+        component.parse().map_err(|_| {
+            LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                "[{}] is not a valid list index.",
+                component
+            )))
+        })
+    }
+
+    fn index_out_of_bounds(component: &str) -> LavaTorrentError {
+        LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+            "list index [{}] is out of bounds.",
+            component
+        )))
+    }
+
+    fn not_a_container(component: &str) -> LavaTorrentError {
+        LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+            "parent of [{}] is neither a dictionary nor a list.",
+            component
+        )))
+    }
+
+    fn get_child(&self, component: &str) -> Option<&BencodeElem> {
+        match *self {
+            BencodeElem::Dictionary(ref dict) => dict.get(component),
+            BencodeElem::RawDictionary(ref dict) => dict.get(component.as_bytes()),
+            BencodeElem::SortedDictionary(ref dict) => dict.get(component.as_bytes()),
+            BencodeElem::List(ref list) => list.get(component.parse::<usize>().ok()?),
+            _ => None,
+        }
+    }
+
+    fn get_child_mut(&mut self, component: &str) -> Option<&mut BencodeElem> {
+        match *self {
+            BencodeElem::Dictionary(ref mut dict) => dict.get_mut(component),
+            BencodeElem::RawDictionary(ref mut dict) => dict.get_mut(component.as_bytes()),
+            BencodeElem::SortedDictionary(ref mut dict) => dict.get_mut(component.as_bytes()),
+            BencodeElem::List(ref mut list) => list.get_mut(component.parse::<usize>().ok()?),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod bencode_elem_path_tests {
+    use super::*;
+    use std::iter::FromIterator;
+
+    fn sample() -> BencodeElem {
+        bencode_elem!({
+            ("announce", "url"),
+            ("info", {
+                ("files", [{ ("length", 42_i64) }, { ("length", 7_i64) }]),
+                ("name", "sample")
+            })
+        })
+    }
+
+    #[test]
+    fn get_path_ok() {
+        let elem = sample();
+
+        assert_eq!(
+            elem.get_path(&["info", "files", "1", "length"]),
+            Some(&bencode_elem!(7_i64))
+        );
+        assert_eq!(
+            elem.get_path(&["info", "name"]),
+            Some(&bencode_elem!("sample"))
+        );
+        assert_eq!(elem.get_path::<&str>(&[]), Some(&elem));
+    }
+
+    #[test]
+    fn get_path_missing() {
+        let elem = sample();
+
+        assert_eq!(elem.get_path(&["info", "files", "2"]), None);
+        assert_eq!(elem.get_path(&["info", "files", "first"]), None);
+        assert_eq!(elem.get_path(&["info", "comment"]), None);
+        assert_eq!(elem.get_path(&["announce", "url"]), None);
+    }
+
+    #[test]
+    fn get_path_raw_dictionary() {
+        let elem = bencode_elem!(r{ ([0xff], 1_i64), ([b'a'], 2_i64) });
+
+        assert_eq!(elem.get_path(&["a"]), Some(&bencode_elem!(2_i64)));
+        assert_eq!(elem.get_path(&["b"]), None);
+    }
+
+    #[test]
+    fn get_path_mut_ok() {
+        let mut elem = sample();
+        *elem.get_path_mut(&["info", "name"]).unwrap() = bencode_elem!("renamed");
+
+        assert_eq!(
+            elem.get_path(&["info", "name"]),
+            Some(&bencode_elem!("renamed"))
+        );
+        assert!(elem.get_path_mut(&["info", "comment"]).is_none());
+    }
+
+    #[test]
+    fn set_path_ok() {
+        let mut elem = sample();
+
+        assert_eq!(
+            elem.set_path(&["info", "name"], bencode_elem!("renamed"))
+                .unwrap(),
+            Some(bencode_elem!("sample"))
+        );
+        assert_eq!(
+            elem.set_path(&["info", "files", "0", "length"], bencode_elem!(1_i64))
+                .unwrap(),
+            Some(bencode_elem!(42_i64))
+        );
+        assert_eq!(
+            elem.get_path(&["info", "name"]),
+            Some(&bencode_elem!("renamed"))
+        );
+        assert_eq!(
+            elem.get_path(&["info", "files", "0", "length"]),
+            Some(&bencode_elem!(1_i64))
+        );
+    }
+
+    #[test]
+    fn set_path_creates_dictionaries() {
+        let mut elem = sample();
+
+        assert_eq!(
+            elem.set_path(&["info", "x", "y"], bencode_elem!(1_i64))
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            elem.get_path(&["info", "x"]),
+            Some(&bencode_elem!({ ("y", 1_i64) }))
+        );
+    }
+
+    #[test]
+    fn set_path_empty() {
+        match sample().set_path::<&str>(&[], bencode_elem!(1_i64)) {
+            Err(LavaTorrentError::InvalidArgument(m)) => assert_eq!(m, "path is empty."),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn set_path_invalid_index() {
+        match sample().set_path(&["info", "files", "2", "length"], bencode_elem!(1_i64)) {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, "list index [2] is out of bounds.")
+            }
+            _ => panic!(),
+        }
+        match sample().set_path(&["info", "files", "first"], bencode_elem!(1_i64)) {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, "[first] is not a valid list index.")
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn set_path_not_a_container() {
+        match sample().set_path(&["announce", "x"], bencode_elem!(1_i64)) {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, "parent of [x] is neither a dictionary nor a list.")
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn remove_path_ok() {
+        let mut elem = sample();
+
+        assert_eq!(
+            elem.remove_path(&["info", "files", "0"]),
+            Some(bencode_elem!({ ("length", 42_i64) }))
+        );
+        assert_eq!(
+            elem.get_path(&["info", "files", "0", "length"]),
+            Some(&bencode_elem!(7_i64))
+        );
+        assert_eq!(elem.remove_path(&["announce"]), Some(bencode_elem!("url")));
+        assert_eq!(elem.get_path(&["announce"]), None);
+    }
+
+    #[test]
+    fn remove_path_missing() {
+        let mut elem = sample();
+
+        assert_eq!(elem.remove_path(&["info", "files", "2"]), None);
+        assert_eq!(elem.remove_path(&["info", "comment"]), None);
+        assert_eq!(elem.remove_path(&["announce", "url"]), None);
+        assert_eq!(elem.remove_path::<&str>(&[]), None);
+        assert_eq!(elem, sample());
+    }
+}
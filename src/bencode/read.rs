@@ -1,12 +1,16 @@
 use super::*;
 use crate::util;
 use crate::util::ByteBuffer;
-use crate::LavaTorrentError;
-use std::borrow::Cow;
+use crate::{BencodeErrorKind, LavaTorrentError};
 use std::collections::HashMap;
+#[cfg(feature = "fs")]
+use std::convert::TryFrom;
+#[cfg(feature = "fs")]
 use std::fs::File;
+#[cfg(feature = "fs")]
 use std::io::{BufReader, Read};
 use std::iter::FromIterator;
+#[cfg(feature = "fs")]
 use std::path::Path;
 
 impl BencodeElem {
@@ -22,15 +26,96 @@ impl BencodeElem {
     where
         B: AsRef<[u8]>,
     {
-        let mut bytes = ByteBuffer::new(bytes.as_ref());
-        let mut elements = Vec::new();
+        Self::from_bytes_with_limits(bytes, &[])
+    }
 
-        while !bytes.is_empty() {
-            let element = BencodeElem::parse(&mut bytes)?;
-            elements.push(element);
-        }
+    /// Like [`from_bytes()`], but `Err(error)` is also returned
+    /// if `bytes` exceeds any of the `limits`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lava_torrent::bencode::{BencodeElem, ReadLimit};
+    ///
+    /// let limits = [ReadLimit::Bytes(1 << 20), ReadLimit::Elements(2)];
+    ///
+    /// assert!(BencodeElem::from_bytes_with_limits("li1ee", &limits).is_ok());
+    /// assert!(BencodeElem::from_bytes_with_limits("li1ei2ee", &limits).is_err());
+    /// ```
+    ///
+    /// [`from_bytes()`]: #method.from_bytes
+    pub fn from_bytes_with_limits<B>(
+        bytes: B,
+        limits: &[ReadLimit],
+    ) -> Result<Vec<BencodeElem>, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        Self::read(
+            bytes.as_ref(),
+            &ReadOptions {
+                limits: limits.to_vec(),
+                ..ReadOptions::default()
+            },
+        )
+    }
 
-        Ok(elements)
+    /// Like [`from_bytes_with_limits()`], but `Err(error)` is also returned
+    /// if `bytes` is not in canonical form, i.e. the same data would be
+    /// encoded differently. In particular, dictionaries with duplicate keys
+    /// and integers with a `+` sign are rejected.
+    ///
+    /// Dictionaries with unsorted keys and integers with leading zeros
+    /// or negative zero are always rejected, even when not using this function.
+    ///
+    /// This is useful when the hash of (part of) `bytes` has to be trusted,
+    /// e.g. when computing the info hash of a torrent.
+    ///
+    /// [`from_bytes_with_limits()`]: #method.from_bytes_with_limits
+    pub fn from_bytes_strict<B>(
+        bytes: B,
+        limits: &[ReadLimit],
+    ) -> Result<Vec<BencodeElem>, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        Self::read(
+            bytes.as_ref(),
+            &ReadOptions {
+                limits: limits.to_vec(),
+                strict: true,
+                ..ReadOptions::default()
+            },
+        )
+    }
+
+    /// Like [`from_bytes()`], but parsing is configured by `options`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lava_torrent::bencode::{BencodeElem, DuplicateKeyPolicy, ReadOptions};
+    ///
+    /// let options = ReadOptions {
+    ///     duplicate_keys: DuplicateKeyPolicy::FirstWins,
+    ///     ..ReadOptions::default()
+    /// };
+    ///
+    /// assert_eq!(
+    ///     BencodeElem::from_bytes_with_options("d1:ai1e1:ai2ee", &options).unwrap(),
+    ///     BencodeElem::from_bytes("d1:ai1ee").unwrap(),
+    /// );
+    /// ```
+    ///
+    /// [`from_bytes()`]: #method.from_bytes
+    pub fn from_bytes_with_options<B>(
+        bytes: B,
+        options: &ReadOptions,
+    ) -> Result<Vec<BencodeElem>, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        Self::read(bytes.as_ref(), options)
     }
 
     /// Parse the content of the file at `path` and return all `BencodeElem` found.
@@ -41,27 +126,191 @@ impl BencodeElem {
     /// If the file at `path` contains any malformed bencode, or if any other
     /// error is encountered (e.g. `IOError`), then `Err(error)`
     /// will be returned.
+    #[cfg(feature = "fs")]
     pub fn from_file<P>(path: P) -> Result<Vec<BencodeElem>, LavaTorrentError>
     where
         P: AsRef<Path>,
     {
-        let file = File::open(&path)?;
-        let mut bytes = Vec::new();
+        Self::from_file_with_limits(path, &[])
+    }
+
+    /// Like [`from_file()`], but `Err(error)` is also returned if the
+    /// content of the file at `path` exceeds any of the `limits`.
+    ///
+    /// If a [`ReadLimit::Bytes`] limit is given, then no more than
+    /// that many bytes (plus 1) are read from the file.
+    ///
+    /// [`from_file()`]: #method.from_file
+    /// [`ReadLimit::Bytes`]: enum.ReadLimit.html#variant.Bytes
+    #[cfg(feature = "fs")]
+    pub fn from_file_with_limits<P>(
+        path: P,
+        limits: &[ReadLimit],
+    ) -> Result<Vec<BencodeElem>, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::read_file(
+            path.as_ref(),
+            &ReadOptions {
+                limits: limits.to_vec(),
+                ..ReadOptions::default()
+            },
+        )
+    }
+
+    /// Like [`from_file_with_limits()`], but `Err(error)` is also returned if the
+    /// content of the file at `path` is not in canonical form.
+    /// See [`from_bytes_strict()`] for details.
+    ///
+    /// [`from_file_with_limits()`]: #method.from_file_with_limits
+    /// [`from_bytes_strict()`]: #method.from_bytes_strict
+    #[cfg(feature = "fs")]
+    pub fn from_file_strict<P>(
+        path: P,
+        limits: &[ReadLimit],
+    ) -> Result<Vec<BencodeElem>, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::read_file(
+            path.as_ref(),
+            &ReadOptions {
+                limits: limits.to_vec(),
+                strict: true,
+                ..ReadOptions::default()
+            },
+        )
+    }
+
+    /// Like [`from_file()`], but parsing is configured by `options`.
+    ///
+    /// [`from_file()`]: #method.from_file
+    #[cfg(feature = "fs")]
+    pub fn from_file_with_options<P>(
+        path: P,
+        options: &ReadOptions,
+    ) -> Result<Vec<BencodeElem>, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::read_file(path.as_ref(), options)
+    }
+
+    /// Like [`from_file()`], but the file at `path` is memory-mapped
+    /// instead of being read into memory first.
+    ///
+    /// This avoids copying the whole file, which helps when parsing
+    /// very large files. Requires the `mmap` feature.
+    ///
+    /// # Safety
+    ///
+    /// The file at `path` must not be modified or truncated (by this or any
+    /// other process) while it is being parsed, as that is undefined behavior.
+    ///
+    /// [`from_file()`]: #method.from_file
+    #[cfg(feature = "mmap")]
+    pub unsafe fn from_file_mmap<P>(path: P) -> Result<Vec<BencodeElem>, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(util::file_io_err(path, None))?;
+        let bytes = memmap2::Mmap::map(&file).map_err(util::file_io_err(path, None))?;
 
-        BufReader::new(file).read_to_end(&mut bytes)?;
         Self::from_bytes(bytes)
     }
 
+    fn read(bytes: &[u8], options: &ReadOptions) -> Result<Vec<BencodeElem>, LavaTorrentError> {
+        let mut max_top_level_elements = usize::MAX;
+        let mut max_bytes = usize::MAX;
+        let mut max_elements = usize::MAX;
+        for limit in &options.limits {
+            match *limit {
+                ReadLimit::TopLevelElements(n) => {
+                    max_top_level_elements = max_top_level_elements.min(n)
+                }
+                ReadLimit::Bytes(n) => max_bytes = max_bytes.min(n),
+                ReadLimit::Elements(n) => max_elements = max_elements.min(n),
+            }
+        }
+
+        let length = bytes.len();
+        let mut bytes = ByteBuffer::new(bytes);
+        bytes.set_element_limit(max_elements);
+        bytes.set_strict(options.strict);
+        bytes.set_duplicate_keys(options.duplicate_keys);
+        bytes.set_sorted_dictionaries(options.sorted_dictionaries);
+        if length > max_bytes {
+            return Err(Self::malformed(
+                &bytes,
+                max_bytes,
+                BencodeErrorKind::TooManyBytes { limit: max_bytes },
+            ));
+        }
+
+        let mut elements = Vec::new();
+        while !bytes.is_empty() {
+            if elements.len() >= max_top_level_elements {
+                return Err(Self::malformed(
+                    &bytes,
+                    bytes.pos(),
+                    BencodeErrorKind::TooManyTopLevelElements {
+                        limit: max_top_level_elements,
+                    },
+                ));
+            }
+            let element = BencodeElem::parse(&mut bytes)?;
+            elements.push(element);
+        }
+
+        Ok(elements)
+    }
+
+    #[cfg(feature = "fs")]
+    fn read_file(path: &Path, options: &ReadOptions) -> Result<Vec<BencodeElem>, LavaTorrentError> {
+        let max_bytes = options
+            .limits
+            .iter()
+            .filter_map(|limit| match *limit {
+                ReadLimit::Bytes(n) => Some(n),
+                _ => None,
+            })
+            .min();
+        let file = File::open(path).map_err(util::file_io_err(path, None))?;
+        let mut bytes = Vec::new();
+
+        match max_bytes {
+            // read 1 more byte so that `read()` can detect oversized input
+            Some(n) => BufReader::new(file)
+                .take(u64::try_from(n).unwrap_or(u64::MAX).saturating_add(1))
+                .read_to_end(&mut bytes),
+            None => BufReader::new(file).read_to_end(&mut bytes),
+        }
+        .map_err(util::file_io_err(path, None))?;
+        Self::read(&bytes, options)
+    }
+
     fn peek_byte(bytes: &mut ByteBuffer) -> Result<u8, LavaTorrentError> {
         match bytes.peek() {
             Some(&byte) => Ok(byte),
-            None => Err(LavaTorrentError::MalformedBencode(Cow::Borrowed(
-                "Expected more bytes, but none found.",
-            ))),
+            None => Err(Self::malformed(
+                bytes,
+                bytes.pos(),
+                BencodeErrorKind::UnexpectedEnd,
+            )),
         }
     }
 
     fn parse(bytes: &mut ByteBuffer) -> Result<BencodeElem, LavaTorrentError> {
+        if !bytes.take_element() {
+            return Err(Self::malformed(
+                bytes,
+                bytes.pos(),
+                BencodeErrorKind::TooManyElements,
+            ));
+        }
+
         match Self::peek_byte(bytes)? {
             DICTIONARY_PREFIX => {
                 bytes.advance(1);
@@ -80,16 +329,20 @@ impl BencodeElem {
     }
 
     fn decode_dictionary(bytes: &mut ByteBuffer) -> Result<BencodeElem, LavaTorrentError> {
+        let start = bytes.pos().saturating_sub(1); // the prefix has been consumed
         let mut entries = Vec::new();
 
         while Self::peek_byte(bytes)? != DICTIONARY_POSTFIX {
             // more to parse
+            let key_start = bytes.pos();
             match Self::decode_bytes(bytes) {
                 Ok(BencodeElem::Bytes(key)) => entries.push((key, Self::parse(bytes)?)),
                 Ok(_) => {
-                    return Err(LavaTorrentError::MalformedBencode(Cow::Borrowed(
-                        "Non-string dictionary key.",
-                    )));
+                    return Err(Self::malformed(
+                        bytes,
+                        key_start,
+                        BencodeErrorKind::NonStringKey,
+                    ));
                 }
                 Err(e) => return Err(e),
             }
@@ -100,13 +353,31 @@ impl BencodeElem {
         for (i, j) in (1..entries.len()).enumerate() {
             let ((k1, _), (k2, _)) = (&entries[i], &entries[j]);
             // "sorted as raw strings, not alphanumerics"
-            if k1 > k2 {
-                return Err(LavaTorrentError::MalformedBencode(Cow::Borrowed(
-                    "A dictionary is not properly sorted.",
-                )));
+            if (k1 == k2) && (bytes.duplicate_keys() == DuplicateKeyPolicy::Error) {
+                return Err(Self::malformed(
+                    bytes,
+                    start,
+                    BencodeErrorKind::DuplicateKeys,
+                ));
+            } else if k1 > k2 {
+                return Err(Self::malformed(
+                    bytes,
+                    start,
+                    BencodeErrorKind::UnsortedKeys,
+                ));
             }
         }
 
+        // entries with the same key are adjacent since they are sorted,
+        // and `HashMap::from_iter()` keeps the last one
+        if bytes.duplicate_keys() == DuplicateKeyPolicy::FirstWins {
+            entries.dedup_by(|(k2, _), (k1, _)| k1 == k2);
+        }
+
+        if bytes.sorted_dictionaries() {
+            return Ok(BencodeElem::SortedDictionary(BTreeMap::from_iter(entries)));
+        }
+
         // convert to Dictionary if possible
         let mut entries2 = Vec::new();
         for (k, v) in &entries {
@@ -145,33 +416,44 @@ impl BencodeElem {
         let bytes_read = bytes.pos() - old_pos;
 
         if read.len() == bytes_read {
-            Err(LavaTorrentError::MalformedBencode(Cow::Borrowed(
-                "Integer delimiter not found.",
-            )))
+            Err(Self::malformed(
+                bytes,
+                old_pos,
+                BencodeErrorKind::MissingIntegerDelimiter,
+            ))
         } else {
             match String::from_utf8(read) {
                 Ok(int_string) => {
                     if int_string.starts_with("-0") {
-                        Err(LavaTorrentError::MalformedBencode(Cow::Borrowed(
-                            "-0 found.",
-                        )))
+                        Err(Self::malformed(
+                            bytes,
+                            old_pos,
+                            BencodeErrorKind::NegativeZero,
+                        ))
+                    } else if int_string.starts_with('+') && bytes.is_strict() {
+                        Err(Self::malformed(bytes, old_pos, BencodeErrorKind::PlusSign))
                     } else if (int_string.starts_with('0')) && (int_string.len() != 1) {
-                        Err(LavaTorrentError::MalformedBencode(Cow::Borrowed(
-                            "Integer with leading zero(s) found.",
-                        )))
+                        Err(Self::malformed(
+                            bytes,
+                            old_pos,
+                            BencodeErrorKind::LeadingZeros,
+                        ))
                     } else {
                         match int_string.parse() {
                             Ok(int) => Ok(BencodeElem::Integer(int)),
-                            Err(_) => Err(LavaTorrentError::MalformedBencode(Cow::Owned(format!(
-                                "Input contains invalid integer: {}.",
-                                int_string
-                            )))),
+                            Err(_) => Err(Self::malformed(
+                                bytes,
+                                old_pos,
+                                BencodeErrorKind::InvalidInteger(int_string.to_owned()),
+                            )),
                         }
                     }
                 }
-                Err(_) => Err(LavaTorrentError::MalformedBencode(Cow::Borrowed(
-                    "Input contains invalid UTF-8.",
-                ))),
+                Err(_) => Err(Self::malformed(
+                    bytes,
+                    old_pos,
+                    BencodeErrorKind::InvalidUtf8,
+                )),
             }
         }
     }
@@ -188,20 +470,31 @@ impl BencodeElem {
     }
 
     fn decode_bytes(bytes: &mut ByteBuffer) -> Result<BencodeElem, LavaTorrentError> {
+        let start = bytes.pos();
         match Self::decode_integer(bytes, STRING_DELIMITER) {
             Ok(BencodeElem::Integer(len)) => {
                 if let Ok(len) = util::i64_to_usize(len) {
                     Ok(BencodeElem::Bytes(bytes.take(len).cloned().collect()))
                 } else {
-                    Err(LavaTorrentError::MalformedBencode(Cow::Borrowed(
-                        "A string's length does not fit into `usize`.",
-                    )))
+                    Err(Self::malformed(
+                        bytes,
+                        start,
+                        BencodeErrorKind::StringLengthOverflow,
+                    ))
                 }
             }
             Ok(_) => panic!("decode_integer() did not return an integer."),
             Err(e) => Err(e),
         }
     }
+
+    fn malformed(bytes: &ByteBuffer, offset: usize, message: BencodeErrorKind) -> LavaTorrentError {
+        LavaTorrentError::MalformedBencode {
+            message,
+            offset,
+            context: bytes.context(offset),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -225,7 +518,7 @@ mod bencode_elem_read_tests {
     fn peek_byte_err() {
         let bytes = "".as_bytes();
         match BencodeElem::peek_byte(&mut ByteBuffer::new(bytes)) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "Expected more bytes, but none found.");
             }
             _ => panic!(),
@@ -254,7 +547,7 @@ mod bencode_elem_read_tests {
     fn decode_integer_invalid_int() {
         let bytes = "4ae".as_bytes();
         match BencodeElem::decode_integer(&mut ByteBuffer::new(bytes), INTEGER_POSTFIX) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "Input contains invalid integer: 4a.");
             }
             _ => panic!(),
@@ -265,7 +558,7 @@ mod bencode_elem_read_tests {
     fn decode_integer_invalid_int_2() {
         let bytes = "--1e".as_bytes();
         match BencodeElem::decode_integer(&mut ByteBuffer::new(bytes), INTEGER_POSTFIX) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "Input contains invalid integer: --1.");
             }
             _ => panic!(),
@@ -276,7 +569,7 @@ mod bencode_elem_read_tests {
     fn decode_integer_invalid_int_3() {
         let bytes = "03e".as_bytes();
         match BencodeElem::decode_integer(&mut ByteBuffer::new(bytes), INTEGER_POSTFIX) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "Integer with leading zero(s) found.");
             }
             _ => panic!(),
@@ -287,7 +580,9 @@ mod bencode_elem_read_tests {
     fn decode_integer_invalid_int_4() {
         let bytes = "-0e".as_bytes();
         match BencodeElem::decode_integer(&mut ByteBuffer::new(bytes), INTEGER_POSTFIX) {
-            Err(LavaTorrentError::MalformedBencode(m)) => assert_eq!(m, "-0 found."),
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
+                assert_eq!(m, "-0 found.")
+            }
             _ => panic!(),
         }
     }
@@ -296,7 +591,9 @@ mod bencode_elem_read_tests {
     fn decode_integer_invalid_int_5() {
         let bytes = "-01e".as_bytes();
         match BencodeElem::decode_integer(&mut ByteBuffer::new(bytes), INTEGER_POSTFIX) {
-            Err(LavaTorrentError::MalformedBencode(m)) => assert_eq!(m, "-0 found."),
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
+                assert_eq!(m, "-0 found.")
+            }
             _ => panic!(),
         }
     }
@@ -305,7 +602,7 @@ mod bencode_elem_read_tests {
     fn decode_integer_overflow() {
         let bytes = "9223372036854775808e".as_bytes();
         match BencodeElem::decode_integer(&mut ByteBuffer::new(bytes), INTEGER_POSTFIX) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "Input contains invalid integer: 9223372036854775808.");
             }
             _ => panic!(),
@@ -316,7 +613,7 @@ mod bencode_elem_read_tests {
     fn decode_integer_no_delimiter() {
         let bytes = "9223372036854775807".as_bytes();
         match BencodeElem::decode_integer(&mut ByteBuffer::new(bytes), INTEGER_POSTFIX) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "Integer delimiter not found.");
             }
             _ => panic!(),
@@ -327,7 +624,7 @@ mod bencode_elem_read_tests {
     fn decode_integer_bad_utf8() {
         let bytes = vec![b'4', 0xff, 0xf8, INTEGER_POSTFIX];
         match BencodeElem::decode_integer(&mut ByteBuffer::new(&bytes), INTEGER_POSTFIX) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "Input contains invalid UTF-8.");
             }
             _ => panic!(),
@@ -347,7 +644,7 @@ mod bencode_elem_read_tests {
     fn decode_string_invalid_len() {
         let bytes = "a:spam".as_bytes();
         match BencodeElem::decode_string(&mut ByteBuffer::new(bytes)) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "Input contains invalid integer: a.");
             }
             _ => panic!(),
@@ -358,7 +655,7 @@ mod bencode_elem_read_tests {
     fn decode_string_no_len() {
         let bytes = ":spam".as_bytes();
         match BencodeElem::decode_string(&mut ByteBuffer::new(bytes)) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "Input contains invalid integer: .");
             }
             _ => panic!(),
@@ -369,7 +666,7 @@ mod bencode_elem_read_tests {
     fn decode_string_negative_len() {
         let bytes = "-1:spam".as_bytes();
         match BencodeElem::decode_string(&mut ByteBuffer::new(bytes)) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "A string's length does not fit into `usize`.");
             }
             _ => panic!(),
@@ -380,7 +677,7 @@ mod bencode_elem_read_tests {
     fn decode_string_no_delimiter() {
         let bytes = "4spam".as_bytes();
         match BencodeElem::decode_string(&mut ByteBuffer::new(bytes)) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "Integer delimiter not found.");
             }
             _ => panic!(),
@@ -391,7 +688,7 @@ mod bencode_elem_read_tests {
     fn decode_string_no_delimiter_2() {
         let bytes = "456".as_bytes();
         match BencodeElem::decode_string(&mut ByteBuffer::new(bytes)) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "Integer delimiter not found.");
             }
             _ => panic!(),
@@ -438,7 +735,7 @@ mod bencode_elem_read_tests {
     fn decode_list_bad_structure() {
         let bytes = "4:spaml6:cheese4:eggse".as_bytes();
         match BencodeElem::decode_list(&mut ByteBuffer::new(bytes)) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "Expected more bytes, but none found.");
             }
             _ => panic!(),
@@ -476,7 +773,7 @@ mod bencode_elem_read_tests {
     fn decode_dictionary_bad_structure() {
         let bytes = "3:cow3:moo4:spame".as_bytes();
         match BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes)) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "Integer delimiter not found.");
             }
             _ => panic!(),
@@ -487,7 +784,7 @@ mod bencode_elem_read_tests {
     fn decode_dictionary_non_string_key_1() {
         let bytes = "i4e3:moo4:spam4:eggse".as_bytes();
         match BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes)) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "Input contains invalid integer: i4e3.");
             }
             _ => panic!(),
@@ -498,7 +795,7 @@ mod bencode_elem_read_tests {
     fn decode_dictionary_not_sorted() {
         let bytes = "3:zoo3:moo4:spam4:eggse".as_bytes();
         match BencodeElem::decode_dictionary(&mut ByteBuffer::new(bytes)) {
-            Err(LavaTorrentError::MalformedBencode(m)) => {
+            Err(LavaTorrentError::MalformedBencode { message: m, .. }) => {
                 assert_eq!(m, "A dictionary is not properly sorted.");
             }
             _ => panic!(),
@@ -576,4 +873,249 @@ mod bencode_elem_read_tests {
             bencode_elem!({ ("cow", "moo"), ("spam", "eggs") })
         );
     }
+
+    #[test]
+    fn parse_error_offset() {
+        let bytes = "d3:fooi03ee".as_bytes();
+        match BencodeElem::parse(&mut ByteBuffer::new(bytes)) {
+            Err(LavaTorrentError::MalformedBencode {
+                message,
+                offset,
+                context,
+            }) => {
+                assert_eq!(message, "Integer with leading zero(s) found.");
+                assert_eq!(offset, 7);
+                assert_eq!(context, "d3:fooi03ee");
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn parse_error_context() {
+        let mut bytes = vec![b'l'];
+        bytes.extend(b"i1e".repeat(10));
+        bytes.extend(b"i-0e\x00e");
+        match BencodeElem::parse(&mut ByteBuffer::new(&bytes)) {
+            Err(LavaTorrentError::MalformedBencode {
+                message,
+                offset,
+                context,
+            }) => {
+                assert_eq!(message, "-0 found.");
+                assert_eq!(offset, 32);
+                assert_eq!(context, "i1ei1ei1ei1ei1ei-0e\\x00e");
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn parse_error_display() {
+        let bytes = "d1:bi1e1:ai2ee".as_bytes();
+        assert_eq!(
+            BencodeElem::parse(&mut ByteBuffer::new(bytes))
+                .unwrap_err()
+                .to_string(),
+            r#"malformed bencode at offset 0 (near "d1:bi1e1:ai2ee"): A dictionary is not properly sorted."#
+        );
+    }
+
+    #[test]
+    fn from_bytes_with_limits_ok() {
+        let limits = [
+            ReadLimit::TopLevelElements(2),
+            ReadLimit::Bytes(11),
+            ReadLimit::Elements(4),
+        ];
+        assert_eq!(
+            BencodeElem::from_bytes_with_limits("li1ei2eei3e", &limits).unwrap(),
+            vec![bencode_elem!([1_i64, 2_i64]), bencode_elem!(3_i64)]
+        );
+    }
+
+    #[test]
+    fn from_bytes_with_limits_top_level_elements() {
+        match BencodeElem::from_bytes_with_limits("i1ei2e", &[ReadLimit::TopLevelElements(1)]) {
+            Err(LavaTorrentError::MalformedBencode {
+                message, offset, ..
+            }) => {
+                assert_eq!(message, "Input contains more than 1 top-level elements.");
+                assert_eq!(offset, 3);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn from_bytes_with_limits_bytes() {
+        match BencodeElem::from_bytes_with_limits("i1ei2e", &[ReadLimit::Bytes(5)]) {
+            Err(LavaTorrentError::MalformedBencode {
+                message, offset, ..
+            }) => {
+                assert_eq!(message, "Input is larger than the limit of 5 bytes.");
+                assert_eq!(offset, 5);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn from_bytes_with_limits_elements() {
+        let limits = [ReadLimit::Elements(4), ReadLimit::Elements(3)];
+        match BencodeElem::from_bytes_with_limits("d1:ai1e1:bli2eee", &limits) {
+            Err(LavaTorrentError::MalformedBencode {
+                message, offset, ..
+            }) => {
+                assert_eq!(message, "Input contains more elements than allowed.");
+                assert_eq!(offset, 11);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn from_bytes_strict_ok() {
+        assert_eq!(
+            BencodeElem::from_bytes_strict("d1:ai0e1:bi1ee", &[]).unwrap(),
+            vec![bencode_elem!({ ("a", 0_i64), ("b", 1_i64) })]
+        );
+    }
+
+    #[test]
+    fn from_bytes_strict_duplicate_keys() {
+        let bytes = "d1:ai1e1:ai2ee";
+        assert_eq!(
+            BencodeElem::from_bytes(bytes).unwrap(),
+            vec![bencode_elem!({ ("a", 2_i64) })]
+        );
+        match BencodeElem::from_bytes_strict(bytes, &[]) {
+            Err(LavaTorrentError::MalformedBencode {
+                message, offset, ..
+            }) => {
+                assert_eq!(message, "A dictionary contains duplicate keys.");
+                assert_eq!(offset, 0);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn from_bytes_strict_plus_sign() {
+        assert_eq!(
+            BencodeElem::from_bytes("i+1e").unwrap(),
+            vec![bencode_elem!(1_i64)]
+        );
+        match BencodeElem::from_bytes_strict("i+1e", &[]) {
+            Err(LavaTorrentError::MalformedBencode { message, .. }) => {
+                assert_eq!(message, "Integer with a plus sign found.");
+            }
+            _ => panic!(),
+        }
+        match BencodeElem::from_bytes_strict("+1:a", &[]) {
+            Err(LavaTorrentError::MalformedBencode { message, .. }) => {
+                assert_eq!(message, "Integer with a plus sign found.");
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn from_bytes_strict_duplicate_keys_kind() {
+        match BencodeElem::from_bytes_strict("d1:ai1e1:ai2ee", &[]) {
+            Err(LavaTorrentError::MalformedBencode { message, .. }) => {
+                assert_eq!(message, BencodeErrorKind::DuplicateKeys);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn from_bytes_invalid_integer_kind() {
+        match BencodeElem::from_bytes("i99999999999999999999e") {
+            Err(LavaTorrentError::MalformedBencode { message, .. }) => {
+                assert_eq!(
+                    message,
+                    BencodeErrorKind::InvalidInteger("99999999999999999999".to_owned())
+                );
+                assert_eq!(
+                    message,
+                    "Input contains invalid integer: 99999999999999999999."
+                );
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn from_bytes_with_options_duplicate_keys() {
+        let bytes = "d1:ai1e1:ai2e1:bi3ee";
+        let options = |duplicate_keys| ReadOptions {
+            duplicate_keys,
+            ..ReadOptions::default()
+        };
+
+        assert_eq!(
+            BencodeElem::from_bytes_with_options(bytes, &options(DuplicateKeyPolicy::FirstWins))
+                .unwrap(),
+            vec![bencode_elem!({ ("a", 1_i64), ("b", 3_i64) })]
+        );
+        assert_eq!(
+            BencodeElem::from_bytes_with_options(bytes, &options(DuplicateKeyPolicy::LastWins))
+                .unwrap(),
+            vec![bencode_elem!({ ("a", 2_i64), ("b", 3_i64) })]
+        );
+        match BencodeElem::from_bytes_with_options(bytes, &options(DuplicateKeyPolicy::Error)) {
+            Err(LavaTorrentError::MalformedBencode { message, .. }) => {
+                assert_eq!(message, "A dictionary contains duplicate keys.");
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn from_bytes_with_options_sorted_dictionaries() {
+        let options = ReadOptions {
+            sorted_dictionaries: true,
+            ..ReadOptions::default()
+        };
+        let bytes = b"d3:cowd1:ai1e1:bi2ee4:spaml2:\xff\xfeee";
+
+        let parsed = BencodeElem::from_bytes_with_options(bytes, &options).unwrap();
+        assert_eq!(
+            parsed,
+            vec![BencodeElem::SortedDictionary(BTreeMap::from([
+                (
+                    b"cow".to_vec(),
+                    BencodeElem::SortedDictionary(BTreeMap::from([
+                        (b"a".to_vec(), bencode_elem!(1_i64)),
+                        (b"b".to_vec(), bencode_elem!(2_i64)),
+                    ]))
+                ),
+                (b"spam".to_vec(), bencode_elem!([(0xff, 0xfe)])),
+            ]))]
+        );
+        assert_eq!(parsed[0].encode(), bytes);
+        assert_eq!(parsed[0].encoded_len(), bytes.len());
+        assert_eq!(
+            parsed[0].to_string(),
+            r#"{ ("cow", { ("a", 1), ("b", 2) }), ("spam", [[0xff, 0xfe]]) }"#
+        );
+    }
+
+    #[test]
+    fn from_bytes_with_options_strict_overrides_duplicate_keys() {
+        let options = ReadOptions {
+            strict: true,
+            duplicate_keys: DuplicateKeyPolicy::FirstWins,
+            ..ReadOptions::default()
+        };
+
+        match BencodeElem::from_bytes_with_options("d1:ai1e1:ai2ee", &options) {
+            Err(LavaTorrentError::MalformedBencode { message, .. }) => {
+                assert_eq!(message, "A dictionary contains duplicate keys.");
+            }
+            _ => panic!(),
+        }
+    }
 }
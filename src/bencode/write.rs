@@ -1,10 +1,16 @@
 //! Module for bencode-related encoding.
 
 use super::*;
+#[cfg(feature = "fs")]
+use crate::util;
 use crate::LavaTorrentError;
+#[cfg(feature = "fs")]
 use std::fs::File;
 use std::hash::BuildHasher;
-use std::io::{BufWriter, Write};
+#[cfg(feature = "fs")]
+use std::io::BufWriter;
+use std::io::Write;
+#[cfg(feature = "fs")]
 use std::path::Path;
 
 /// Encode `string` and write the result to `dst`.
@@ -105,6 +111,29 @@ where
     Ok(())
 }
 
+/// Encode `dict` and write the result to `dst`.
+///
+/// Unlike [`write_dictionary()`] and [`write_raw_dictionary()`],
+/// `dict` is already sorted, so no intermediate `Vec` is needed.
+///
+/// [`write_dictionary()`]: fn.write_dictionary.html
+/// [`write_raw_dictionary()`]: fn.write_raw_dictionary.html
+pub fn write_sorted_dictionary<W>(
+    dict: &BTreeMap<Vec<u8>, BencodeElem>,
+    dst: &mut W,
+) -> Result<(), LavaTorrentError>
+where
+    W: Write,
+{
+    dst.write_all(&[DICTIONARY_PREFIX])?;
+    for (key, val) in dict {
+        write_bytes(key, dst)?;
+        val.write_into(dst)?;
+    }
+    dst.write_all(&[DICTIONARY_POSTFIX])?;
+    Ok(())
+}
+
 /// Encode `string` and return the result in a `Vec`.
 pub fn encode_string<S>(string: S) -> Vec<u8>
 where
@@ -164,6 +193,66 @@ where
     encoded
 }
 
+/// Encode `dict` and return the result in a `Vec`.
+pub fn encode_sorted_dictionary(dict: &BTreeMap<Vec<u8>, BencodeElem>) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    write_sorted_dictionary(dict, &mut encoded).expect("Write to vec failed!");
+    encoded
+}
+
+// Create the file at `path`, fill it with `write` and sync it according to `options`.
+#[cfg(feature = "fs")]
+pub(crate) fn write_file<F>(
+    path: &Path,
+    options: &WriteOptions,
+    write: F,
+) -> Result<(), LavaTorrentError>
+where
+    F: FnOnce(&mut BufWriter<&File>) -> Result<(), LavaTorrentError>,
+{
+    let file = File::create(path).map_err(util::file_io_err(path, None))?;
+    let mut writer = BufWriter::new(&file);
+    write(&mut writer).map_err(|e| match e {
+        LavaTorrentError::Io(source) => util::file_io_err(path, None)(source),
+        e => e,
+    })?;
+    writer.flush().map_err(util::file_io_err(path, None))?;
+    drop(writer);
+
+    file.sync_all().map_err(util::file_io_err(path, None))?;
+    if options.durable {
+        util::sync_parent_dir(path)?;
+    }
+    Ok(())
+}
+
+// Number of bytes needed to encode a byte string of length `len`.
+pub(crate) fn string_len(len: usize) -> usize {
+    digits(len as u64) + 1 + len
+}
+
+// Number of bytes needed to encode `int`.
+pub(crate) fn integer_len(int: i64) -> usize {
+    let sign = if int < 0 { 1 } else { 0 };
+    2 + sign + digits(int.unsigned_abs())
+}
+
+// Number of bytes needed to encode a dictionary, given the
+// `(key length, encoded value length)` of each entry.
+pub(crate) fn dictionary_len<I>(entries: I) -> usize
+where
+    I: IntoIterator<Item = (usize, usize)>,
+{
+    2 + entries
+        .into_iter()
+        .map(|(key_len, value_len)| string_len(key_len) + value_len)
+        .sum::<usize>()
+}
+
+fn digits(n: u64) -> usize {
+    n.checked_ilog10().map_or(1, |log| log as usize + 1)
+}
+
 impl BencodeElem {
     /// Encode `self` and write the result to `dst`.
     pub fn write_into<W>(&self, dst: &mut W) -> Result<(), LavaTorrentError>
@@ -177,6 +266,7 @@ impl BencodeElem {
             BencodeElem::List(ref list) => write_list(list, dst),
             BencodeElem::Dictionary(ref dict) => write_dictionary(dict, dst),
             BencodeElem::RawDictionary(ref dict) => write_raw_dictionary(dict, dst),
+            BencodeElem::SortedDictionary(ref dict) => write_sorted_dictionary(dict, dst),
         }
     }
 
@@ -190,14 +280,65 @@ impl BencodeElem {
     /// Note: it is the client's responsibility to ensure
     /// that all directories in `path` actually exist (e.g.
     /// by calling [`create_dir_all`](https://doc.rust-lang.org/std/fs/fn.create_dir_all.html)).
+    #[cfg(feature = "fs")]
     pub fn write_into_file<P>(&self, path: P) -> Result<(), LavaTorrentError>
     where
         P: AsRef<Path>,
     {
-        let file = File::create(&path)?;
-        self.write_into(&mut BufWriter::new(&file))?;
-        file.sync_all()?;
-        Ok(())
+        self.write_into_file_with_options(path, &WriteOptions::default())
+    }
+
+    /// Like [`write_into_file()`], but with custom options.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use lava_torrent::bencode::{BencodeElem, WriteOptions};
+    ///
+    /// let options = WriteOptions { durable: true };
+    /// BencodeElem::Integer(42)
+    ///     .write_into_file_with_options("sample.bencode", &options)
+    ///     .unwrap();
+    /// ```
+    ///
+    /// [`write_into_file()`]: #method.write_into_file
+    #[cfg(feature = "fs")]
+    pub fn write_into_file_with_options<P>(
+        &self,
+        path: P,
+        options: &WriteOptions,
+    ) -> Result<(), LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        write_file(path.as_ref(), options, |dst| self.write_into(dst))
+    }
+
+    /// Compute the length of the encoding of `self`, without encoding it.
+    ///
+    /// This is equal to `self.encode().len()`, and is useful for preallocating
+    /// buffers or enforcing size limits before encoding.
+    pub fn encoded_len(&self) -> usize {
+        match *self {
+            BencodeElem::String(ref string) => string_len(string.len()),
+            BencodeElem::Bytes(ref bytes) => string_len(bytes.len()),
+            BencodeElem::Integer(int) => integer_len(int),
+            BencodeElem::List(ref list) => {
+                2 + list.iter().map(BencodeElem::encoded_len).sum::<usize>()
+            }
+            BencodeElem::Dictionary(ref dict) => dictionary_len(
+                dict.iter()
+                    .map(|(key, value)| (key.len(), value.encoded_len())),
+            ),
+            BencodeElem::RawDictionary(ref dict) => dictionary_len(
+                dict.iter()
+                    .map(|(key, value)| (key.len(), value.encoded_len())),
+            ),
+            BencodeElem::SortedDictionary(ref dict) => dictionary_len(
+                dict.iter()
+                    .map(|(key, value)| (key.len(), value.encoded_len())),
+            ),
+        }
     }
 
     /// Encode `self` and return the result in a `Vec`.
@@ -209,6 +350,7 @@ impl BencodeElem {
             BencodeElem::List(ref list) => encode_list(list),
             BencodeElem::Dictionary(ref dict) => encode_dictionary(dict),
             BencodeElem::RawDictionary(ref dict) => encode_raw_dictionary(dict),
+            BencodeElem::SortedDictionary(ref dict) => encode_sorted_dictionary(dict),
         }
     }
 }
@@ -275,6 +417,40 @@ mod bencode_elem_write_tests {
         );
     }
 
+    #[test]
+    fn integer_len_ok() {
+        for int in [0, 1, 9, 10, 42, -1, -10, i64::MAX, i64::MIN] {
+            assert_eq!(integer_len(int), encode_integer(int).len());
+        }
+    }
+
+    #[test]
+    fn string_len_ok() {
+        for len in [0, 1, 9, 10, 100] {
+            assert_eq!(string_len(len), encode_bytes(vec![0; len]).len());
+        }
+    }
+
+    #[test]
+    fn encoded_len_ok() {
+        let elems = vec![
+            bencode_elem!("spam"),
+            bencode_elem!((0xff, 0xfe)),
+            bencode_elem!(-42_i64),
+            bencode_elem!([]),
+            bencode_elem!({}),
+            bencode_elem!([1_i64, "spam", [2_i64], { ("cow", "moo") }]),
+            bencode_elem!({
+                ("spam", [1_i64, 1000_i64]),
+                ("info", { ("name", "sample"), ("pieces", (0xff, 0xfe)) })
+            }),
+            bencode_elem!(r{ ([0xff, 0xfe], 1_i64), ([0x00], 1234567890_i64) }),
+        ];
+        for elem in elems {
+            assert_eq!(elem.encoded_len(), elem.encode().len());
+        }
+    }
+
     #[test]
     fn encode_string_ok() {
         assert_eq!(encode_string("spam"), "4:spam".as_bytes().to_vec(),)
@@ -366,6 +542,17 @@ mod bencode_elem_write_tests {
         );
     }
 
+    #[test]
+    fn bencode_elem_encode_sorted_dictionary_ok() {
+        let dict = BencodeElem::SortedDictionary(BTreeMap::from([
+            (b"spam".to_vec(), bencode_elem!(42)),
+            (vec![0xff], bencode_elem!("moo")),
+        ]));
+
+        assert_eq!(dict.encode(), b"d4:spami42e1:\xff3:mooe".to_vec());
+        assert_eq!(dict.encoded_len(), dict.encode().len());
+    }
+
     #[test]
     fn bencode_elem_encode_string_ok() {
         assert_eq!(bencode_elem!("spam").encode(), "4:spam".as_bytes().to_vec(),)
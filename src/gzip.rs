@@ -0,0 +1,503 @@
+//! Minimal gzip ([RFC 1952](https://www.rfc-editor.org/rfc/rfc1952)) decoder,
+//! used to read compressed *.torrent* files.
+//!
+//! DEFLATE ([RFC 1951](https://www.rfc-editor.org/rfc/rfc1951)) is decoded
+//! bit by bit, in the same way as zlib's reference decoder `puff`. This is
+//! far from the fastest approach, but torrents are small and this keeps
+//! the implementation short.
+
+use crate::{LavaTorrentError, TorrentErrorKind};
+use std::borrow::Cow;
+
+const MAX_BITS: usize = 15;
+const MAX_LIT_CODES: usize = 288;
+const MAX_DIST_CODES: usize = 30;
+
+const LEN_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LEN_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// Order in which code length code lengths are stored in a dynamic block.
+const CODE_LEN_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const FLAG_HCRC: u8 = 0x02;
+const FLAG_EXTRA: u8 = 0x04;
+const FLAG_NAME: u8 = 0x08;
+const FLAG_COMMENT: u8 = 0x10;
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    buf: u32,
+    len: u32,
+}
+
+// Canonical Huffman code: number of codes of each length,
+// and symbols ordered by code.
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+/// Check if `bytes` starts with the gzip magic number.
+pub(crate) fn is_gzip(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0x1f, 0x8b])
+}
+
+/// Decompress the gzip members in `bytes`. Decompressed data
+/// larger than `max_len` bytes is rejected.
+pub(crate) fn decompress(bytes: &[u8], max_len: usize) -> Result<Vec<u8>, LavaTorrentError> {
+    let mut output = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let member_start = output.len();
+        pos = skip_header(bytes, pos)?;
+
+        let mut reader = BitReader {
+            bytes,
+            pos,
+            buf: 0,
+            len: 0,
+        };
+        inflate(&mut reader, &mut output, max_len)?;
+        pos = reader.pos; // partial byte at the end is discarded
+
+        let trailer = bytes
+            .get(pos..pos + 8)
+            .ok_or_else(|| malformed("gzip trailer is missing."))?;
+        let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+        let member = &output[member_start..];
+        if crc32(member) != crc || member.len() as u32 != size {
+            return Err(malformed("gzip checksum mismatch."));
+        }
+        pos += 8;
+    }
+
+    Ok(output)
+}
+
+// Return the position of the compressed data.
+fn skip_header(bytes: &[u8], mut pos: usize) -> Result<usize, LavaTorrentError> {
+    let header = bytes
+        .get(pos..pos + 10)
+        .ok_or_else(|| malformed("gzip header is truncated."))?;
+    if !is_gzip(header) || header[2] != 8 {
+        return Err(malformed(
+            "Not a gzip member, or not compressed with DEFLATE.",
+        ));
+    }
+    let flags = header[3];
+    pos += 10;
+
+    if flags & FLAG_EXTRA != 0 {
+        let len = bytes
+            .get(pos..pos + 2)
+            .ok_or_else(|| malformed("gzip header is truncated."))?;
+        pos += 2 + usize::from(u16::from_le_bytes([len[0], len[1]]));
+    }
+    for flag in [FLAG_NAME, FLAG_COMMENT] {
+        if flags & flag != 0 {
+            // zero-terminated string
+            pos += bytes
+                .get(pos..)
+                .and_then(|rest| rest.iter().position(|&b| b == 0))
+                .ok_or_else(|| malformed("gzip header is truncated."))?
+                + 1;
+        }
+    }
+    if flags & FLAG_HCRC != 0 {
+        pos += 2;
+    }
+
+    if pos > bytes.len() {
+        Err(malformed("gzip header is truncated."))
+    } else {
+        Ok(pos)
+    }
+}
+
+fn inflate(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    max_len: usize,
+) -> Result<(), LavaTorrentError> {
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => inflate_stored(reader, output, max_len)?,
+            1 => {
+                let (lit, dist) = fixed_codes();
+                inflate_codes(reader, output, max_len, &lit, &dist)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_codes(reader)?;
+                inflate_codes(reader, output, max_len, &lit, &dist)?;
+            }
+            _ => return Err(malformed("Invalid DEFLATE block type.")),
+        }
+
+        if last {
+            return Ok(());
+        }
+    }
+}
+
+fn inflate_stored(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    max_len: usize,
+) -> Result<(), LavaTorrentError> {
+    // discard the remaining bits of the current byte
+    reader.buf = 0;
+    reader.len = 0;
+
+    let header = reader
+        .bytes
+        .get(reader.pos..reader.pos + 4)
+        .ok_or_else(|| malformed("DEFLATE stream is truncated."))?;
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    if len != !u16::from_le_bytes([header[2], header[3]]) {
+        return Err(malformed("Invalid DEFLATE stored block length."));
+    }
+    reader.pos += 4;
+
+    let data = reader
+        .bytes
+        .get(reader.pos..reader.pos + usize::from(len))
+        .ok_or_else(|| malformed("DEFLATE stream is truncated."))?;
+    if output.len() + data.len() > max_len {
+        return Err(too_large(max_len));
+    }
+    output.extend_from_slice(data);
+    reader.pos += data.len();
+    Ok(())
+}
+
+fn inflate_codes(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    max_len: usize,
+    lit: &Huffman,
+    dist: &Huffman,
+) -> Result<(), LavaTorrentError> {
+    loop {
+        let symbol = usize::from(reader.decode(lit)?);
+        if symbol < 256 {
+            if output.len() >= max_len {
+                return Err(too_large(max_len));
+            }
+            output.push(symbol as u8);
+            continue;
+        } else if symbol == 256 {
+            return Ok(());
+        }
+
+        let symbol = symbol - 257;
+        if symbol >= LEN_BASE.len() {
+            return Err(malformed("Invalid DEFLATE length code."));
+        }
+        let len = usize::from(LEN_BASE[symbol]) + reader.bits(LEN_EXTRA[symbol].into())? as usize;
+
+        let symbol = usize::from(reader.decode(dist)?);
+        if symbol >= DIST_BASE.len() {
+            return Err(malformed("Invalid DEFLATE distance code."));
+        }
+        let distance =
+            usize::from(DIST_BASE[symbol]) + reader.bits(DIST_EXTRA[symbol].into())? as usize;
+        if distance > output.len() {
+            return Err(malformed("DEFLATE distance is too far back."));
+        }
+        if output.len() + len > max_len {
+            return Err(too_large(max_len));
+        }
+
+        // the source and destination may overlap, so copy byte by byte
+        let start = output.len() - distance;
+        for i in 0..len {
+            output.push(output[start + i]);
+        }
+    }
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0; MAX_LIT_CODES];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+
+    // both tables are complete, so construction can't fail
+    (
+        Huffman::new(&lengths).expect("Fixed literal/length code should be valid."),
+        Huffman::new(&[5; MAX_DIST_CODES]).expect("Fixed distance code should be valid."),
+    )
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), LavaTorrentError> {
+    let lit_count = reader.bits(5)? as usize + 257;
+    let dist_count = reader.bits(5)? as usize + 1;
+    let code_len_count = reader.bits(4)? as usize + 4;
+    if lit_count > 286 || dist_count > MAX_DIST_CODES {
+        return Err(malformed("Invalid DEFLATE dynamic block header."));
+    }
+
+    let mut code_lens = [0; 19];
+    for &index in &CODE_LEN_ORDER[..code_len_count] {
+        code_lens[index] = reader.bits(3)? as u8;
+    }
+    let code_len_code = Huffman::new(&code_lens)?;
+
+    let mut lengths = vec![0; lit_count + dist_count];
+    let mut index = 0;
+    while index < lengths.len() {
+        let symbol = reader.decode(&code_len_code)?;
+        let (len, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 if index > 0 => (lengths[index - 1], 3 + reader.bits(2)?),
+            16 => return Err(malformed("DEFLATE code length repeat without a length.")),
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        let end = index + repeat as usize;
+        if end > lengths.len() {
+            return Err(malformed("Too many DEFLATE code lengths."));
+        }
+        lengths[index..end].fill(len);
+        index = end;
+    }
+    if lengths[256] == 0 {
+        return Err(malformed("DEFLATE block has no end-of-block code."));
+    }
+
+    Ok((
+        Huffman::new(&lengths[..lit_count])?,
+        Huffman::new(&lengths[lit_count..])?,
+    ))
+}
+
+impl BitReader<'_> {
+    // Read `count` (<= 16) bits, LSB first.
+    fn bits(&mut self, count: u32) -> Result<u32, LavaTorrentError> {
+        while self.len < count {
+            let byte = *self
+                .bytes
+                .get(self.pos)
+                .ok_or_else(|| malformed("DEFLATE stream is truncated."))?;
+            self.buf |= u32::from(byte) << self.len;
+            self.pos += 1;
+            self.len += 8;
+        }
+
+        let val = self.buf & ((1 << count) - 1);
+        self.buf >>= count;
+        self.len -= count;
+        Ok(val)
+    }
+
+    fn decode(&mut self, huffman: &Huffman) -> Result<u16, LavaTorrentError> {
+        let (mut code, mut first, mut index) = (0, 0, 0);
+
+        for &count in &huffman.counts[1..] {
+            code |= self.bits(1)? as usize;
+            let count = usize::from(count);
+            if code < first + count {
+                return Ok(huffman.symbols[index + code - first]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(malformed("Invalid DEFLATE Huffman code."))
+    }
+}
+
+impl Huffman {
+    // Build a code from the code length of each symbol (0 = unused).
+    // Incomplete codes are allowed, but over-subscribed ones are not.
+    fn new(lengths: &[u8]) -> Result<Huffman, LavaTorrentError> {
+        let mut counts = [0; MAX_BITS + 1];
+        for &len in lengths {
+            counts[usize::from(len)] += 1;
+        }
+
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err(malformed("Over-subscribed DEFLATE Huffman code."));
+            }
+        }
+
+        let mut offsets = [0; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offsets[len + 1] = offsets[len] + usize::from(counts[len]);
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[usize::from(len)]] = symbol as u16;
+                offsets[usize::from(len)] += 1;
+            }
+        }
+
+        Ok(Huffman { counts, symbols })
+    }
+}
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut crc = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            crc = if crc & 1 != 0 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            k += 1;
+        }
+        table[n] = crc;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &b| {
+        CRC_TABLE[((crc ^ u32::from(b)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+fn malformed(message: &'static str) -> LavaTorrentError {
+    LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(Cow::Borrowed(message)))
+}
+
+fn too_large(max_len: usize) -> LavaTorrentError {
+    LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(Cow::Owned(format!(
+        "Decompressed data exceeds {} bytes.",
+        max_len
+    ))))
+}
+
+#[cfg(test)]
+mod gzip_tests {
+    use super::*;
+
+    // `gzip` output for "spam", compressed with a stored block
+    const STORED: [u8; 27] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x01, 0x04, 0x00, 0xfb, 0xff,
+        0x73, 0x70, 0x61, 0x6d, 0x3d, 0xff, 0xda, 0x43, 0x04, 0x00, 0x00, 0x00,
+    ];
+    // "spamspamspam", compressed with fixed Huffman codes
+    const FIXED: [u8; 26] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff, 0x2b, 0x2e, 0x48, 0xcc, 0x2d,
+        0x86, 0x62, 0x00, 0x5f, 0xc7, 0x09, 0x4b, 0x0c, 0x00, 0x00, 0x00,
+    ];
+    // "spam" with the file name "a.torrent" in the header
+    const NAMED: [u8; 34] = [
+        0x1f, 0x8b, 0x08, 0x08, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff, 0x61, 0x2e, 0x74, 0x6f, 0x72,
+        0x72, 0x65, 0x6e, 0x74, 0x00, 0x2b, 0x2e, 0x48, 0xcc, 0x05, 0x00, 0x3d, 0xff, 0xda, 0x43,
+        0x04, 0x00, 0x00, 0x00,
+    ];
+    // `dynamic_input()`, compressed with dynamic Huffman codes
+    const DYNAMIC: [u8; 71] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff, 0xed, 0xc9, 0xb1, 0x0d, 0x00,
+        0x20, 0x08, 0x04, 0xc0, 0x99, 0x0c, 0x56, 0x6c, 0x83, 0x42, 0x61, 0x41, 0x30, 0xb1, 0x70,
+        0x7d, 0xf3, 0x5b, 0x58, 0xfc, 0xb5, 0xe7, 0x5d, 0xcf, 0xb6, 0x5c, 0x2d, 0x44, 0x67, 0x5d,
+        0xd1, 0xac, 0x0a, 0x83, 0x01, 0x13, 0x1c, 0x02, 0x18, 0x0c, 0x06, 0x83, 0xc1, 0x60, 0x30,
+        0xbe, 0x8f, 0x07, 0xe8, 0x2b, 0x6c, 0x4e, 0xdf, 0x08, 0x00, 0x00,
+    ];
+
+    fn dynamic_input() -> Vec<u8> {
+        let mut input = b"d4:spami1e3:cow3:mooe".to_vec();
+        input.extend_from_slice(&b"aaaaabbbbbcccccdddddeeeee".repeat(90));
+        input
+    }
+
+    #[test]
+    fn is_gzip_ok() {
+        assert!(is_gzip(&STORED));
+        assert!(!is_gzip(b"d4:spam4:eggse"));
+        assert!(!is_gzip(&[0x1f]));
+    }
+
+    #[test]
+    fn decompress_stored() {
+        assert_eq!(decompress(&STORED, 4).unwrap(), b"spam");
+    }
+
+    #[test]
+    fn decompress_fixed() {
+        assert_eq!(decompress(&FIXED, 12).unwrap(), b"spamspamspam");
+    }
+
+    #[test]
+    fn decompress_named() {
+        assert_eq!(decompress(&NAMED, 4).unwrap(), b"spam");
+    }
+
+    #[test]
+    fn decompress_dynamic() {
+        assert_eq!(decompress(&DYNAMIC, 4096).unwrap(), dynamic_input());
+    }
+
+    #[test]
+    fn decompress_multiple_members() {
+        let mut bytes = STORED.to_vec();
+        bytes.extend_from_slice(&FIXED);
+
+        assert_eq!(decompress(&bytes, 16).unwrap(), b"spamspamspamspam");
+    }
+
+    #[test]
+    fn decompress_too_large() {
+        for bytes in [&STORED[..], &FIXED, &DYNAMIC] {
+            match decompress(bytes, 3) {
+                Err(LavaTorrentError::MalformedTorrent(m)) => {
+                    assert_eq!(m, "Decompressed data exceeds 3 bytes.")
+                }
+                _ => panic!(),
+            }
+        }
+    }
+
+    #[test]
+    fn decompress_checksum_mismatch() {
+        let mut bytes = FIXED;
+        bytes[18] ^= 1;
+
+        match decompress(&bytes, 12) {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, "gzip checksum mismatch.")
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn decompress_truncated() {
+        for len in 1..DYNAMIC.len() {
+            assert!(decompress(&DYNAMIC[..len], 4096).is_err());
+        }
+    }
+}
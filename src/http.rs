@@ -0,0 +1,681 @@
+//! Minimal blocking HTTP/1.1 client backing the `http` feature.
+//!
+//! `https://` URLs are handled with `rustls`, and servers are
+//! authenticated with the Mozilla root certificates from `webpki-roots`.
+
+use crate::LavaTorrentError;
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use std::borrow::Cow;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+const MAX_REDIRECTS: usize = 5;
+
+#[derive(Debug)]
+pub(crate) struct Response {
+    pub(crate) status: u16,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+struct Url<'a> {
+    https: bool,
+    host: &'a str,
+    port: u16,
+    target: &'a str,
+}
+
+impl Response {
+    /// Get the value of the first header named `name` (case-insensitive).
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, val)| val.as_str())
+    }
+}
+
+/// Send a `GET` request to `url`, following redirects (including those
+/// between `http://` and `https://`), and return the response.
+///
+/// Bodies larger than `max_body_len` bytes are rejected.
+pub(crate) fn get(url: &str, max_body_len: usize) -> Result<Response, LavaTorrentError> {
+    get_with(url, max_body_len, &tls_config())
+}
+
+// Like `get()`, but servers are authenticated according to `tls_config`.
+fn get_with(
+    url: &str,
+    max_body_len: usize,
+    tls_config: &Arc<ClientConfig>,
+) -> Result<Response, LavaTorrentError> {
+    let mut url = url.to_owned();
+
+    for _ in 0..=MAX_REDIRECTS {
+        let response = get_once(&url, max_body_len, tls_config)?;
+        match (response.status, response.header("location")) {
+            (301 | 302 | 303 | 307 | 308, Some(location)) => {
+                url = if location.starts_with("//") {
+                    // same scheme, different host
+                    format!("{}:{}", parse_url(&url)?.scheme(), location)
+                } else if location.starts_with('/') {
+                    let parsed = parse_url(&url)?;
+                    format!(
+                        "{}://{}:{}{}",
+                        parsed.scheme(),
+                        parsed.host,
+                        parsed.port,
+                        location
+                    )
+                } else {
+                    location.to_owned()
+                };
+            }
+            _ => return Ok(response),
+        }
+    }
+
+    Err(LavaTorrentError::MalformedResponse(Cow::Borrowed(
+        "Too many HTTP redirects.",
+    )))
+}
+
+fn get_once(
+    url: &str,
+    max_body_len: usize,
+    tls_config: &Arc<ClientConfig>,
+) -> Result<Response, LavaTorrentError> {
+    let parsed = parse_url(url)?;
+    let stream = connect(&parsed)?;
+
+    if parsed.https {
+        let server_name = ServerName::try_from(parsed.bare_host().to_owned()).map_err(|_| {
+            LavaTorrentError::InvalidArgument(Cow::Owned(format!("Invalid host in URL: {}", url)))
+        })?;
+        let connection = ClientConnection::new(tls_config.clone(), server_name)
+            .map_err(|e| malformed(format!("TLS error: {}", e)))?;
+        exchange(StreamOwned::new(connection, stream), &parsed, max_body_len)
+    } else {
+        exchange(stream, &parsed, max_body_len)
+    }
+}
+
+// Send the request for `url` over `stream` and read the response.
+fn exchange<S>(mut stream: S, url: &Url, max_body_len: usize) -> Result<Response, LavaTorrentError>
+where
+    S: Read + Write,
+{
+    let host = if url.port == url.default_port() {
+        Cow::Borrowed(url.host)
+    } else {
+        Cow::Owned(format!("{}:{}", url.host, url.port))
+    };
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: lava_torrent/{}\r\n\
+         Accept-Encoding: identity\r\nConnection: close\r\n\r\n",
+        url.target,
+        host,
+        env!("CARGO_PKG_VERSION")
+    )?;
+    stream.flush()?;
+
+    read_response(BufReader::new(stream), max_body_len)
+}
+
+// The TLS configuration used by `get()`, which is only built once.
+fn tls_config() -> Arc<ClientConfig> {
+    static CONFIG: OnceLock<Arc<ClientConfig>> = OnceLock::new();
+
+    CONFIG
+        .get_or_init(|| {
+            let roots = RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            };
+            Arc::new(client_config(roots))
+        })
+        .clone()
+}
+
+fn client_config(roots: RootCertStore) -> ClientConfig {
+    ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .expect("The default protocol versions should be supported by ring.")
+        .with_root_certificates(roots)
+        .with_no_client_auth()
+}
+
+impl Url<'_> {
+    fn scheme(&self) -> &'static str {
+        if self.https {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    fn default_port(&self) -> u16 {
+        if self.https {
+            443
+        } else {
+            80
+        }
+    }
+
+    // the host without the brackets around IPv6 literals
+    fn bare_host(&self) -> &str {
+        self.host.trim_start_matches('[').trim_end_matches(']')
+    }
+}
+
+fn connect(url: &Url) -> Result<TcpStream, LavaTorrentError> {
+    let mut last_err = None;
+
+    for addr in (url.bare_host(), url.port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, TIMEOUT) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(TIMEOUT))?;
+                stream.set_write_timeout(Some(TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(e) => last_err = Some(e),
+        }
+    }
+
+    Err(match last_err {
+        Some(e) => LavaTorrentError::Io(e),
+        None => LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+            "Failed to resolve {}.",
+            url.host
+        ))),
+    })
+}
+
+fn parse_url(url: &str) -> Result<Url<'_>, LavaTorrentError> {
+    let (https, rest) = match url.split_once("://") {
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("http") => (false, rest),
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("https") => (true, rest),
+        Some(_) => {
+            return Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                "Unsupported URL scheme (only http and https are supported): {}",
+                url
+            ))));
+        }
+        None => {
+            return Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                "Invalid URL: {}",
+                url
+            ))));
+        }
+    };
+
+    let (authority, target) = match rest.find(['/', '?']) {
+        Some(index) if rest[index..].starts_with('/') => (&rest[..index], &rest[index..]),
+        // a target must begin with '/', so "http://host?a" is not handled
+        Some(_) => {
+            return Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                "Invalid URL: {}",
+                url
+            ))));
+        }
+        None => (rest, "/"),
+    };
+    let target = target.split('#').next().unwrap_or("/");
+
+    // IPv6 literals are enclosed in brackets, e.g. "[::1]:8080"
+    let port_sep = match authority.rfind(']') {
+        Some(end) => authority[end..].find(':').map(|index| end + index),
+        None => authority.rfind(':'),
+    };
+    let (host, port) = match port_sep {
+        Some(index) => match authority[index + 1..].parse() {
+            Ok(port) => (&authority[..index], port),
+            Err(_) => {
+                return Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                    "Invalid port in URL: {}",
+                    url
+                ))));
+            }
+        },
+        None => (authority, if https { 443 } else { 80 }),
+    };
+    if host.is_empty() || host.contains('@') {
+        return Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+            "Invalid host in URL: {}",
+            url
+        ))));
+    }
+
+    Ok(Url {
+        https,
+        host,
+        port,
+        target,
+    })
+}
+
+fn read_response<R>(mut reader: R, max_body_len: usize) -> Result<Response, LavaTorrentError>
+where
+    R: BufRead,
+{
+    let mut header_len = 0;
+    let status_line = read_line(&mut reader, &mut header_len)?;
+    let status = match status_line.split(' ').collect::<Vec<_>>()[..] {
+        [version, code, ..] if version.starts_with("HTTP/1.") => code.parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| malformed(format!("Invalid HTTP status line: {}", status_line)))?;
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(&mut reader, &mut header_len)?;
+        if line.is_empty() {
+            break;
+        }
+        match line.split_once(':') {
+            Some((key, val)) => headers.push((key.trim().to_owned(), val.trim().to_owned())),
+            None => return Err(malformed(format!("Invalid HTTP header: {}", line))),
+        }
+    }
+
+    let mut response = Response {
+        status,
+        headers,
+        body: Vec::new(),
+    };
+    let chunked = response
+        .header("transfer-encoding")
+        .is_some_and(|val| val.eq_ignore_ascii_case("chunked"));
+
+    response.body = if chunked {
+        read_chunked_body(&mut reader, max_body_len)?
+    } else if let Some(len) = response.header("content-length") {
+        let len = len
+            .parse::<usize>()
+            .map_err(|_| malformed(format!("Invalid Content-Length: {}", len)))?;
+        if len > max_body_len {
+            return Err(body_too_large(max_body_len));
+        }
+        let mut body = vec![0; len];
+        reader.read_exact(&mut body)?;
+        body
+    } else {
+        read_limited(&mut reader, max_body_len)?
+    };
+
+    Ok(response)
+}
+
+fn read_chunked_body<R>(reader: &mut R, max_body_len: usize) -> Result<Vec<u8>, LavaTorrentError>
+where
+    R: BufRead,
+{
+    let mut body = Vec::new();
+    let mut header_len = 0;
+
+    loop {
+        let line = read_line(reader, &mut header_len)?;
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| malformed(format!("Invalid HTTP chunk size: {}", line)))?;
+        if size == 0 {
+            break;
+        }
+        // `size` comes from the server, so it may be large enough to overflow
+        if size > max_body_len - body.len() {
+            return Err(body_too_large(max_body_len));
+        }
+
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        if !read_line(reader, &mut header_len)?.is_empty() {
+            return Err(malformed("HTTP chunk is longer than its size.".to_owned()));
+        }
+    }
+
+    // skip trailers
+    while !read_line(reader, &mut header_len)?.is_empty() {}
+    Ok(body)
+}
+
+fn read_limited<R>(reader: &mut R, max_body_len: usize) -> Result<Vec<u8>, LavaTorrentError>
+where
+    R: Read,
+{
+    let mut body = Vec::new();
+    reader
+        .take(max_body_len as u64 + 1)
+        .read_to_end(&mut body)?;
+
+    if body.len() > max_body_len {
+        Err(body_too_large(max_body_len))
+    } else {
+        Ok(body)
+    }
+}
+
+// Read a CRLF-terminated line, without the CRLF. `total` keeps track of
+// the number of bytes read so far and is used to limit the header size.
+fn read_line<R>(reader: &mut R, total: &mut usize) -> Result<String, LavaTorrentError>
+where
+    R: BufRead,
+{
+    let mut line = Vec::new();
+    let limit = MAX_HEADER_BYTES.saturating_sub(*total) as u64;
+    reader.take(limit).read_until(b'\n', &mut line)?;
+    *total += line.len();
+
+    if line.last() != Some(&b'\n') {
+        return Err(malformed(
+            "HTTP response ended unexpectedly or has oversized headers.".to_owned(),
+        ));
+    }
+    line.pop();
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+
+    String::from_utf8(line).map_err(|_| malformed("HTTP header is not valid UTF-8.".to_owned()))
+}
+
+fn malformed(message: String) -> LavaTorrentError {
+    LavaTorrentError::MalformedResponse(Cow::Owned(message))
+}
+
+fn body_too_large(max_body_len: usize) -> LavaTorrentError {
+    malformed(format!(
+        "HTTP response body exceeds {} bytes.",
+        max_body_len
+    ))
+}
+
+#[cfg(test)]
+pub(crate) mod http_tests {
+    use super::*;
+    use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+    use rustls::{ServerConfig, ServerConnection};
+    use std::net::TcpListener;
+    use std::thread;
+
+    // Serve `response` to a single client and return the server's URL,
+    // along with a handle that returns the request received.
+    pub(crate) fn serve(response: Vec<u8>) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            respond(stream, &response)
+        });
+
+        (url, handle)
+    }
+
+    // Like `serve()`, but over TLS with a self-signed certificate for
+    // `localhost`. A client configuration that trusts it is returned as well.
+    pub(crate) fn serve_tls(
+        response: Vec<u8>,
+    ) -> (String, Arc<ClientConfig>, thread::JoinHandle<String>) {
+        let rcgen::CertifiedKey { cert, key_pair } =
+            rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+        let mut roots = RootCertStore::empty();
+        roots.add(cert.der().clone()).unwrap();
+        let server_config =
+            ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_no_client_auth()
+                .with_single_cert(
+                    vec![cert.der().clone()],
+                    PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key_pair.serialize_der())),
+                )
+                .unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "https://localhost:{}",
+            listener.local_addr().unwrap().port()
+        );
+
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let connection = ServerConnection::new(Arc::new(server_config)).unwrap();
+            let mut stream = StreamOwned::new(connection, stream);
+            let request = respond(&mut stream, &response);
+            stream.conn.send_close_notify();
+            let _ = stream.flush();
+            request
+        });
+
+        (url, Arc::new(client_config(roots)), handle)
+    }
+
+    // Read a request from `stream` and send `response`, returning the request.
+    // Failures (e.g. a rejected TLS handshake) simply end the exchange.
+    fn respond<S>(mut stream: S, response: &[u8]) -> String
+    where
+        S: Read + Write,
+    {
+        let mut request = String::new();
+        let mut reader = BufReader::new(&mut stream);
+        loop {
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(_) if line != "\r\n" && !line.is_empty() => request.push_str(&line),
+                _ => break,
+            }
+        }
+
+        let _ = stream.write_all(response).and_then(|_| stream.flush());
+        request
+    }
+
+    #[test]
+    fn parse_url_ok() {
+        assert_eq!(
+            parse_url("http://example.com/announce?a=1#frag").unwrap(),
+            Url {
+                https: false,
+                host: "example.com",
+                port: 80,
+                target: "/announce?a=1",
+            }
+        );
+        assert_eq!(
+            parse_url("HTTP://example.com:8080").unwrap(),
+            Url {
+                https: false,
+                host: "example.com",
+                port: 8080,
+                target: "/",
+            }
+        );
+        assert_eq!(
+            parse_url("http://[::1]:6969/announce").unwrap(),
+            Url {
+                https: false,
+                host: "[::1]",
+                port: 6969,
+                target: "/announce",
+            }
+        );
+        assert_eq!(
+            parse_url("HTTPS://example.com/announce").unwrap(),
+            Url {
+                https: true,
+                host: "example.com",
+                port: 443,
+                target: "/announce",
+            }
+        );
+    }
+
+    #[test]
+    fn parse_url_unsupported() {
+        match parse_url("udp://example.com/announce") {
+            Err(LavaTorrentError::InvalidArgument(m)) => assert_eq!(
+                m,
+                "Unsupported URL scheme (only http and https are supported): \
+                 udp://example.com/announce"
+            ),
+            _ => panic!(),
+        }
+        assert!(parse_url("example.com/announce").is_err());
+        assert!(parse_url("http://example.com:port/").is_err());
+        assert!(parse_url("http://user@example.com/").is_err());
+    }
+
+    #[test]
+    fn read_response_content_length() {
+        let bytes = b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nX-A: b\r\n\r\nspamextra";
+        let response = read_response(&bytes[..], 4).unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header("x-a"), Some("b"));
+        assert_eq!(response.body, b"spam");
+        assert!(read_response(&bytes[..], 3).is_err());
+    }
+
+    #[test]
+    fn read_response_chunked() {
+        let bytes =
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nsp\r\n2;x=y\r\nam\r\n0\r\n\r\n";
+        let response = read_response(&bytes[..], 4).unwrap();
+
+        assert_eq!(response.body, b"spam");
+        assert!(read_response(&bytes[..], 3).is_err());
+    }
+
+    #[test]
+    fn read_response_chunked_size_overflow() {
+        let bytes = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+            1\r\na\r\nffffffffffffffff\r\n";
+        match read_response(&bytes[..], 1024) {
+            Err(LavaTorrentError::MalformedResponse(m)) => {
+                assert_eq!(m, "HTTP response body exceeds 1024 bytes.")
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn read_response_until_eof() {
+        let bytes = b"HTTP/1.0 404 Not Found\r\n\r\nspam";
+        let response = read_response(&bytes[..], 4).unwrap();
+
+        assert_eq!(response.status, 404);
+        assert_eq!(response.body, b"spam");
+        assert!(read_response(&bytes[..], 3).is_err());
+    }
+
+    #[test]
+    fn read_response_malformed() {
+        assert!(read_response(&b"SPAM\r\n\r\n"[..], 4).is_err());
+        assert!(read_response(&b"HTTP/1.1 200 OK\r\nspam\r\n\r\n"[..], 4).is_err());
+        assert!(read_response(&b"HTTP/1.1 200 OK\r\n"[..], 4).is_err());
+    }
+
+    #[test]
+    fn get_ok() {
+        let (url, handle) = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nspam".to_vec());
+        let response = get(&format!("{}/path?a=1", url), 4).unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"spam");
+        assert!(handle
+            .join()
+            .unwrap()
+            .starts_with("GET /path?a=1 HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn get_redirect() {
+        let (target, handle) = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nspam".to_vec());
+        let (url, _) = serve(
+            format!(
+                "HTTP/1.1 302 Found\r\nLocation: {}/moved\r\nContent-Length: 0\r\n\r\n",
+                target
+            )
+            .into_bytes(),
+        );
+        let response = get(&url, 4).unwrap();
+
+        assert_eq!(response.body, b"spam");
+        assert!(handle
+            .join()
+            .unwrap()
+            .starts_with("GET /moved HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn get_https() {
+        let (url, tls_config, handle) =
+            serve_tls(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nspam".to_vec());
+        let response = get_with(&format!("{}/path", url), 4, &tls_config).unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"spam");
+        assert!(handle.join().unwrap().starts_with(&format!(
+            "GET /path HTTP/1.1\r\nHost: {}\r\n",
+            url.trim_start_matches("https://")
+        )));
+    }
+
+    #[test]
+    fn get_https_untrusted() {
+        let (url, _, _) = serve_tls(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".to_vec());
+
+        // the self-signed certificate is not trusted by default
+        match get(&url, 4) {
+            Err(LavaTorrentError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::InvalidData),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn get_redirect_to_https() {
+        let (target, tls_config, handle) =
+            serve_tls(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nspam".to_vec());
+        let (url, _) = serve(
+            format!(
+                "HTTP/1.1 301 Moved Permanently\r\nLocation: {}/moved\r\n\
+                 Content-Length: 0\r\n\r\n",
+                target
+            )
+            .into_bytes(),
+        );
+        let response = get_with(&url, 4, &tls_config).unwrap();
+
+        assert_eq!(response.body, b"spam");
+        assert!(handle
+            .join()
+            .unwrap()
+            .starts_with("GET /moved HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn get_redirect_scheme_relative() {
+        let (target, handle) = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nspam".to_vec());
+        let (url, _) = serve(
+            format!(
+                "HTTP/1.1 302 Found\r\nLocation: {}/moved\r\nContent-Length: 0\r\n\r\n",
+                target.trim_start_matches("http:")
+            )
+            .into_bytes(),
+        );
+        let response = get(&url, 4).unwrap();
+
+        assert_eq!(response.body, b"spam");
+        assert!(handle
+            .join()
+            .unwrap()
+            .starts_with("GET /moved HTTP/1.1\r\n"));
+    }
+}
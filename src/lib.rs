@@ -4,7 +4,8 @@
 //! # *Quick Start*
 //! Read a torrent ([v1]) and print it and its info hash.
 //!
-//! ```no_run
+#![cfg_attr(feature = "fs", doc = "```no_run")]
+#![cfg_attr(not(feature = "fs"), doc = "```ignore")]
 //! use lava_torrent::torrent::v1::Torrent;
 //!
 //! let torrent = Torrent::read_from_file("sample.torrent").unwrap();
@@ -14,7 +15,8 @@
 //!
 //! Create a torrent ([v1]) from files in a directory and save the *.torrent* file.
 //!
-//! ```no_run
+#![cfg_attr(feature = "fs", doc = "```no_run")]
+#![cfg_attr(not(feature = "fs"), doc = "```ignore")]
 //! use lava_torrent::torrent::v1::TorrentBuilder;
 //!
 //! let torrent = TorrentBuilder::new("dir/", 1048576).build().unwrap();
@@ -23,7 +25,8 @@
 //!
 //! Bencode (de)serialization.
 //!
-//! ```no_run
+#![cfg_attr(feature = "fs", doc = "```no_run")]
+#![cfg_attr(not(feature = "fs"), doc = "```ignore")]
 //! use lava_torrent::bencode::BencodeElem;
 //!
 //! let bytes = "d4:spam4:eggse".as_bytes();
@@ -47,9 +50,38 @@
 //! - torrent parsing/encoding (based on [`BencodeElem`]) => [`Torrent`]
 //! - torrent creation => [`TorrentBuilder`]
 //! - tracker response parsing => [`tracker`]
+//! - UDP tracker packet encoding/decoding => [`tracker::udp`]
+//! - mutable torrent magnet links => [`magnet`]
 //!
 //! ## Feature Flags
-//! None at the moment.
+//! - `fs` (enabled by default): read and write files, build torrents from content
+//!   on disk (e.g. [`TorrentBuilder::build()`]) and verify content on disk
+//!   (e.g. [`Torrent::verify()`]). Implies `rand`.
+//! - `rand` (enabled by default): random values, e.g. for
+//!   [`TorrentBuilder::set_entropy()`] and [`Torrent::shuffle_trackers()`].
+//! - `parallel` (enabled by default): hash pieces on multiple threads when
+//!   building torrents (see [`TorrentBuilder`]) and verifying content.
+//!   Without it, everything is done on the current thread. Implies `fs`.
+//! - `mmap`: read *.torrent* files through memory maps
+//!   (e.g. [`Torrent::read_from_file_mmap()`]).
+//! - `io_uring`: on Linux, read content through io_uring when building torrents
+//!   on a single thread, so that reads spanning many small files are submitted in batches.
+//!   Regular reads are used if the kernel does not support (or allow) io_uring.
+//! - `http`: a minimal HTTP client for announcing to and scraping trackers (see [`tracker`]),
+//!   and for downloading torrents (e.g. [`Torrent::read_from_url()`]).
+//!   `https://` URLs are supported through [`rustls`].
+//! - `udp`: a blocking client for UDP trackers (see [`tracker::udp`]).
+//! - `arbitrary`: implementations of [`arbitrary::Arbitrary`] for [`BencodeElem`],
+//!   [`File`], and [`Torrent`], for fuzzing and property testing (e.g. checking
+//!   that encoding and then reading a torrent gives back the same torrent).
+//!
+//! ## WebAssembly
+//! With default features disabled, [`lava_torrent`] builds for `wasm32-unknown-unknown`.
+//! Parsing works on bytes (e.g. [`Torrent::read_from_bytes()`]), and torrents can be
+//! built from content in memory with [`TorrentBuilder::build_from_bytes()`] and
+//! [`TorrentBuilder::build_from_entries()`], on the current thread.
+//! Random values come from [`rand`], so enabling `rand` on that target also
+//! requires enabling the `js` feature of `getrandom`.
 //!
 //! # *Correctness*
 //! [`lava_torrent`] is written without using any existing parser or parser generator.
@@ -78,9 +110,17 @@
 //! # *Implemented BEPs*
 //! NOTE: Only the parsing/encoding aspects are implemented.
 //! - [BEP 3]
+//! - [BEP 5] \(partial, only implemented the `nodes` field)
 //! - [BEP 9] \(partial, only implemented magnet url v1)
 //! - [BEP 12]
+//! - [BEP 15] \(packet encoding/decoding, plus a client with the `udp` feature)
+//! - [BEP 17]
+//! - [BEP 19]
 //! - [BEP 27]
+//! - [BEP 30]
+//! - [BEP 38]
+//! - [BEP 46] \(partial, only implemented magnet links)
+//! - [BEP 47] \(partial, only implemented the `sha1` field)
 //!
 //! [`lava_torrent`]: index.html
 //! [Apache 2.0]: https://www.apache.org/licenses/LICENSE-2.0
@@ -89,27 +129,59 @@
 //! [v1]: http://bittorrent.org/beps/bep_0003.html
 //! [`lava_torrent::bencode::write::encode_bytes()`]: bencode/write/fn.encode_bytes.html
 //! [`BencodeElem`]: bencode/enum.BencodeElem.html
+//! [`File`]: torrent/v1/struct.File.html
+//! [`arbitrary::Arbitrary`]: https://docs.rs/arbitrary/1/arbitrary/trait.Arbitrary.html
 //! [`Torrent`]: torrent/v1/struct.Torrent.html
+//! [`Torrent::read_from_file_mmap()`]: torrent/v1/struct.Torrent.html#method.read_from_file_mmap
+//! [`Torrent::read_from_url()`]: torrent/v1/struct.Torrent.html#method.read_from_url
 //! [`TorrentBuilder`]: torrent/v1/struct.TorrentBuilder.html
+//! [`Torrent::read_from_bytes()`]: torrent/v1/struct.Torrent.html#method.read_from_bytes
+//! [`Torrent::verify()`]: torrent/v1/struct.Torrent.html#method.verify
+//! [`Torrent::shuffle_trackers()`]: torrent/v1/struct.Torrent.html#method.shuffle_trackers
+//! [`TorrentBuilder::build_from_bytes()`]: torrent/v1/struct.TorrentBuilder.html#method.build_from_bytes
+//! [`TorrentBuilder::build_from_entries()`]: torrent/v1/struct.TorrentBuilder.html#method.build_from_entries
+//! [`TorrentBuilder::build()`]: torrent/v1/struct.TorrentBuilder.html#method.build
+//! [`TorrentBuilder::set_entropy()`]: torrent/v1/struct.TorrentBuilder.html#method.set_entropy
+//! [`rand`]: https://crates.io/crates/rand
+//! [`rustls`]: https://crates.io/crates/rustls
 //! [`tracker`]: tracker/index.html
+//! [`tracker::udp`]: tracker/udp/index.html
+//! [`magnet`]: magnet/index.html
 //! [BitTorrent specification]: http://bittorrent.org/beps/bep_0003.html
 //! [BEP 3]: http://bittorrent.org/beps/bep_0003.html
 //! [`bigint`]: https://github.com/rust-num/num-bigint
 //! [`i64::max_value()`]: https://doc.rust-lang.org/stable/std/primitive.i64.html#method.max_value
+//! [BEP 5]: http://bittorrent.org/beps/bep_0005.html
 //! [BEP 9]: http://bittorrent.org/beps/bep_0009.html
 //! [BEP 12]: http://bittorrent.org/beps/bep_0012.html
+//! [BEP 15]: http://bittorrent.org/beps/bep_0015.html
+//! [BEP 17]: http://bittorrent.org/beps/bep_0017.html
+//! [BEP 19]: http://bittorrent.org/beps/bep_0019.html
 //! [BEP 27]: http://bittorrent.org/beps/bep_0027.html
+//! [BEP 30]: http://bittorrent.org/beps/bep_0030.html
+//! [BEP 38]: http://bittorrent.org/beps/bep_0038.html
+//! [BEP 46]: http://bittorrent.org/beps/bep_0046.html
+//! [BEP 47]: http://bittorrent.org/beps/bep_0047.html
 
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
 extern crate conv;
 extern crate itertools;
 extern crate percent_encoding;
+#[cfg(feature = "rand")]
+extern crate rand;
+#[cfg(feature = "parallel")]
 extern crate rayon;
 extern crate sha1;
 extern crate thiserror;
 
+pub(crate) mod gzip;
+#[cfg(feature = "http")]
+pub(crate) mod http;
 pub(crate) mod util;
 #[macro_use]
 pub mod bencode;
+pub mod magnet;
 pub mod torrent;
 pub mod tracker;
 
@@ -125,16 +197,34 @@ pub enum LavaTorrentError {
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
+    #[doc = "IO error occurred while accessing a file. `offset` is the \
+    byte offset at which the error occurred, if the error occurred \
+    while reading the file's content."]
+    #[error("IO error on \"{}\"{}: {source}", .path.display(), util::display_offset(.offset))]
+    FileIo {
+        path: std::path::PathBuf,
+        offset: Option<u64>,
+        source: std::io::Error,
+    },
+
     #[doc = "The bencode is found to be bad before we can parse \
     the torrent, so the torrent may or may not be malformed. \
-    This is generally unexpected behavior and thus should be handled."]
-    #[error("malformed bencode: {0}")]
-    MalformedBencode(std::borrow::Cow<'static, str>),
+    This is generally unexpected behavior and thus should be handled. \
+    `offset` is the byte offset at which the problem was found, and \
+    `context` contains the (escaped) bytes around `offset`, and \
+    `message` tells what the problem is."]
+    #[error("malformed bencode at offset {offset} (near \"{context}\"): {message}")]
+    MalformedBencode {
+        message: BencodeErrorKind,
+        offset: usize,
+        context: String,
+    },
 
     #[doc = "Bencode is fine, but parsed data is gibberish, so we \
-    can't extract a torrent from it."]
+    can't extract a torrent from it. The `TorrentErrorKind` tells \
+    what is wrong."]
     #[error("malformed torrent: {0}")]
-    MalformedTorrent(std::borrow::Cow<'static, str>),
+    MalformedTorrent(TorrentErrorKind),
 
     #[doc = "Bencode is fine, but parsed data is gibberish, so we \
     can't extract a response from it."]
@@ -155,3 +245,254 @@ pub enum LavaTorrentError {
     #[error("numeric conversion failed: {0}")]
     FailedNumericConv(std::borrow::Cow<'static, str>),
 }
+
+/// What is wrong with malformed bencode.
+/// See [`LavaTorrentError::MalformedBencode`].
+///
+/// The `Display` output is the message that used to be a plain string, which
+/// can still be compared against with `==` (e.g. `kind == "-0 found."`).
+///
+/// [`LavaTorrentError::MalformedBencode`]: enum.LavaTorrentError.html#variant.MalformedBencode
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum BencodeErrorKind {
+    /// The input ended in the middle of an element.
+    UnexpectedEnd,
+    /// The input exceeds [`ReadLimit::Bytes`](bencode/enum.ReadLimit.html#variant.Bytes).
+    TooManyBytes { limit: usize },
+    /// The input exceeds
+    /// [`ReadLimit::TopLevelElements`](bencode/enum.ReadLimit.html#variant.TopLevelElements).
+    TooManyTopLevelElements { limit: usize },
+    /// The input exceeds [`ReadLimit::Elements`](bencode/enum.ReadLimit.html#variant.Elements).
+    TooManyElements,
+    /// A dictionary key is not a string.
+    NonStringKey,
+    /// A dictionary contains the same key more than once.
+    DuplicateKeys,
+    /// A dictionary's keys are not sorted.
+    UnsortedKeys,
+    /// An integer (or a string's length) is not terminated.
+    MissingIntegerDelimiter,
+    /// `i-0e` was found.
+    NegativeZero,
+    /// An integer has a plus sign, which is only rejected when parsing strictly.
+    PlusSign,
+    /// An integer has leading zeros.
+    LeadingZeros,
+    /// An integer cannot be parsed (e.g. it overflows `i64`).
+    InvalidInteger(String),
+    /// An integer (or a string's length) is not valid UTF-8.
+    InvalidUtf8,
+    /// A string's length does not fit into `usize`.
+    StringLengthOverflow,
+}
+
+impl std::fmt::Display for BencodeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            BencodeErrorKind::UnexpectedEnd => write!(f, "Expected more bytes, but none found."),
+            BencodeErrorKind::TooManyBytes { limit } => {
+                write!(f, "Input is larger than the limit of {} bytes.", limit)
+            }
+            BencodeErrorKind::TooManyTopLevelElements { limit } => {
+                write!(f, "Input contains more than {} top-level elements.", limit)
+            }
+            BencodeErrorKind::TooManyElements => {
+                write!(f, "Input contains more elements than allowed.")
+            }
+            BencodeErrorKind::NonStringKey => write!(f, "Non-string dictionary key."),
+            BencodeErrorKind::DuplicateKeys => write!(f, "A dictionary contains duplicate keys."),
+            BencodeErrorKind::UnsortedKeys => write!(f, "A dictionary is not properly sorted."),
+            BencodeErrorKind::MissingIntegerDelimiter => write!(f, "Integer delimiter not found."),
+            BencodeErrorKind::NegativeZero => write!(f, "-0 found."),
+            BencodeErrorKind::PlusSign => write!(f, "Integer with a plus sign found."),
+            BencodeErrorKind::LeadingZeros => write!(f, "Integer with leading zero(s) found."),
+            BencodeErrorKind::InvalidInteger(ref int) => {
+                write!(f, "Input contains invalid integer: {}.", int)
+            }
+            BencodeErrorKind::InvalidUtf8 => write!(f, "Input contains invalid UTF-8."),
+            BencodeErrorKind::StringLengthOverflow => {
+                write!(f, "A string's length does not fit into `usize`.")
+            }
+        }
+    }
+}
+
+/// What is wrong with a malformed torrent.
+/// See [`LavaTorrentError::MalformedTorrent`].
+///
+/// The `Display` output is the message that used to be a plain string, which
+/// can still be compared against with `==` (e.g. `kind == "\"info\" does not exist."`).
+///
+/// # Example
+///
+/// ```
+/// use lava_torrent::torrent::v1::Torrent;
+/// use lava_torrent::{LavaTorrentError, TorrentErrorKind};
+///
+/// match Torrent::read_from_bytes(b"d4:infod6:lengthi1eee") {
+///     Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::MissingField { field })) => {
+///         assert_eq!(field, "name")
+///     }
+///     _ => panic!(),
+/// }
+/// ```
+///
+/// [`LavaTorrentError::MalformedTorrent`]: enum.LavaTorrentError.html#variant.MalformedTorrent
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum TorrentErrorKind {
+    /// A required field does not exist.
+    MissingField {
+        field: std::borrow::Cow<'static, str>,
+    },
+    /// A field does not have the expected type.
+    WrongType {
+        field: std::borrow::Cow<'static, str>,
+        expected: ValueType,
+    },
+    /// A list contains an element that does not have the expected type.
+    WrongElementType {
+        field: std::borrow::Cow<'static, str>,
+        expected: ValueType,
+    },
+    /// A field maps to an empty list or string.
+    EmptyField {
+        field: std::borrow::Cow<'static, str>,
+    },
+    /// A `length` is negative, or the torrent's total length is not positive.
+    InvalidLength { length: i64 },
+    /// `piece length` is not positive.
+    InvalidPieceLength { piece_length: i64 },
+    /// The torrent does not have as many pieces as its length requires.
+    /// See [`ValidationPolicy`].
+    ///
+    /// [`ValidationPolicy`]: torrent/v1/enum.ValidationPolicy.html
+    PieceCountMismatch { pieces: usize, required: usize },
+    /// A file's `path` contains `.` or `..`.
+    InvalidPathComponent { component: String },
+    /// Any other problem.
+    Other(std::borrow::Cow<'static, str>),
+}
+
+/// The type of a bencode value. See [`TorrentErrorKind::WrongType`].
+///
+/// [`TorrentErrorKind::WrongType`]: enum.TorrentErrorKind.html#variant.WrongType
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ValueType {
+    /// A bencode integer.
+    Integer,
+    /// A bencode string that is valid UTF-8.
+    String,
+    /// A bencode string.
+    Bytes,
+    /// A bencode list.
+    List,
+    /// A bencode dictionary.
+    Dictionary,
+}
+
+impl std::fmt::Display for TorrentErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            TorrentErrorKind::MissingField { ref field } => {
+                write!(f, "\"{}\" does not exist.", field)
+            }
+            TorrentErrorKind::WrongType {
+                ref field,
+                expected,
+            } => match expected {
+                ValueType::Integer => write!(f, "\"{}\" does not map to an integer.", field),
+                ValueType::String => write!(
+                    f,
+                    "\"{}\" does not map to a string (or maps to invalid UTF8).",
+                    field
+                ),
+                ValueType::Bytes => {
+                    write!(f, "\"{}\" does not map to a sequence of bytes.", field)
+                }
+                ValueType::List => write!(f, "\"{}\" does not map to a list.", field),
+                ValueType::Dictionary => write!(f, "\"{}\" is not a dictionary.", field),
+            },
+            TorrentErrorKind::WrongElementType {
+                ref field,
+                expected,
+            } => write!(
+                f,
+                "\"{}\" contains a non-{} element.",
+                field,
+                match expected {
+                    ValueType::Integer => "integer",
+                    ValueType::String => "string",
+                    ValueType::Bytes => "bytes",
+                    ValueType::List => "list",
+                    ValueType::Dictionary => "dictionary",
+                }
+            ),
+            TorrentErrorKind::EmptyField { ref field } => write!(f, "\"{}\" is empty.", field),
+            TorrentErrorKind::InvalidLength { length } => {
+                write!(f, "Invalid \"length\": {}.", length)
+            }
+            TorrentErrorKind::InvalidPieceLength { piece_length } => {
+                write!(f, "Invalid \"piece length\": {}.", piece_length)
+            }
+            TorrentErrorKind::PieceCountMismatch { pieces, required } => write!(
+                f,
+                "Torrent has {} pieces but its length requires {}.",
+                pieces, required
+            ),
+            TorrentErrorKind::InvalidPathComponent { ref component } => {
+                write!(f, "\"path\" contains \"{}\".", component)
+            }
+            TorrentErrorKind::Other(ref message) => write!(f, "{}", message),
+        }
+    }
+}
+
+// Compare `value`'s `Display` output with `other` without allocating.
+fn display_eq<T: std::fmt::Display>(value: &T, other: &str) -> bool {
+    use std::fmt::Write;
+
+    struct Matcher<'a>(&'a str);
+
+    impl<'a> Write for Matcher<'a> {
+        fn write_str(&mut self, s: &str) -> std::fmt::Result {
+            match self.0.strip_prefix(s) {
+                Some(rest) => {
+                    self.0 = rest;
+                    Ok(())
+                }
+                None => Err(std::fmt::Error),
+            }
+        }
+    }
+
+    let mut matcher = Matcher(other);
+    write!(matcher, "{}", value).is_ok() && matcher.0.is_empty()
+}
+
+// Allow comparing against messages, as when these were plain strings.
+macro_rules! impl_eq_message {
+    ($kind:ty) => {
+        impl PartialEq<str> for $kind {
+            fn eq(&self, other: &str) -> bool {
+                display_eq(self, other)
+            }
+        }
+
+        impl<'a> PartialEq<&'a str> for $kind {
+            fn eq(&self, other: &&'a str) -> bool {
+                display_eq(self, other)
+            }
+        }
+
+        impl PartialEq<String> for $kind {
+            fn eq(&self, other: &String) -> bool {
+                display_eq(self, other)
+            }
+        }
+    };
+}
+
+impl_eq_message!(BencodeErrorKind);
+impl_eq_message!(TorrentErrorKind);
@@ -0,0 +1,275 @@
+//! Module for magnet links that are not tied to a [`Torrent`].
+//!
+//! Magnet links for regular torrents can be generated with
+//! [`Torrent::magnet_link()`].
+//!
+//! [`Torrent`]: ../torrent/v1/struct.Torrent.html
+//! [`Torrent::magnet_link()`]: ../torrent/v1/struct.Torrent.html#method.magnet_link
+
+use crate::LavaTorrentError;
+use percent_encoding::percent_decode_str;
+use sha1::{Digest, Sha1};
+use std::borrow::Cow;
+use std::fmt::Write;
+
+const PUBLIC_KEY_LENGTH: usize = 32;
+// As defined in BEP 44.
+const MAX_SALT_LENGTH: usize = 64;
+
+/// A magnet link for a mutable torrent, as defined in
+/// [BEP 46](http://bittorrent.org/beps/bep_0046.html),
+/// i.e. `magnet:?xs=urn:btpk:<public key>&s=<salt>`.
+///
+/// Such links point to an item in the DHT (see [`target()`]) that is
+/// signed with the ed25519 `public_key` and contains the info hash of
+/// the latest version of the torrent. Looking up and verifying the item
+/// is left to the application.
+///
+/// [`target()`]: #method.target
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MutableMagnet {
+    /// The ed25519 public key of the publisher.
+    pub public_key: [u8; PUBLIC_KEY_LENGTH],
+    /// An optional salt (at most 64 bytes), which allows the same key
+    /// to be used for multiple torrents.
+    pub salt: Option<Vec<u8>>,
+}
+
+impl MutableMagnet {
+    /// Parse a [BEP 46](http://bittorrent.org/beps/bep_0046.html) magnet link.
+    ///
+    /// Parameters other than `xs` and `s` are ignored. An `xs` parameter
+    /// that is not a `urn:btpk:` is ignored as well, since BEP 9 allows
+    /// `xs` to point to other sources.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lava_torrent::magnet::MutableMagnet;
+    ///
+    /// let link = format!("magnet:?xs=urn:btpk:{}&s=73616c74", "8a".repeat(32));
+    /// let magnet = MutableMagnet::from_link(&link).unwrap();
+    ///
+    /// assert_eq!(magnet.public_key, [0x8a; 32]);
+    /// assert_eq!(magnet.salt, Some(b"salt".to_vec()));
+    /// assert_eq!(magnet.magnet_link(), link);
+    /// ```
+    pub fn from_link(link: &str) -> Result<MutableMagnet, LavaTorrentError> {
+        let query = match link.strip_prefix("magnet:?") {
+            Some(query) => query,
+            None => {
+                return Err(LavaTorrentError::InvalidArgument(Cow::Borrowed(
+                    r#"Magnet link does not start with "magnet:?"."#,
+                )));
+            }
+        };
+
+        let mut public_key = None;
+        let mut salt = None;
+        for param in query.split('&') {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            let value = percent_decode_str(value).decode_utf8().map_err(|_| {
+                LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                    r#"Magnet link parameter "{}" is not valid UTF-8."#,
+                    key
+                )))
+            })?;
+
+            match key {
+                "xs" => {
+                    if let Some(hex) = value.strip_prefix("urn:btpk:") {
+                        let bytes = decode_hex(hex).filter(|b| b.len() == PUBLIC_KEY_LENGTH);
+                        match bytes {
+                            Some(bytes) => public_key = Some(bytes),
+                            None => {
+                                return Err(LavaTorrentError::InvalidArgument(Cow::Borrowed(
+                                    "Public key is not 32 hex-encoded bytes.",
+                                )));
+                            }
+                        }
+                    }
+                }
+                "s" => match decode_hex(&value).filter(|b| b.len() <= MAX_SALT_LENGTH) {
+                    Some(bytes) => salt = Some(bytes),
+                    None => {
+                        return Err(LavaTorrentError::InvalidArgument(Cow::Borrowed(
+                            "Salt is not at most 64 hex-encoded bytes.",
+                        )));
+                    }
+                },
+                _ => (),
+            }
+        }
+
+        match public_key {
+            Some(bytes) => {
+                let mut public_key = [0; PUBLIC_KEY_LENGTH];
+                public_key.copy_from_slice(&bytes);
+                Ok(MutableMagnet { public_key, salt })
+            }
+            None => Err(LavaTorrentError::InvalidArgument(Cow::Borrowed(
+                r#"Magnet link does not contain "xs=urn:btpk:"."#,
+            ))),
+        }
+    }
+
+    /// Generate the magnet link, with the public key and the salt
+    /// (if any) hex-encoded in lowercase.
+    pub fn magnet_link(&self) -> String {
+        let mut link = format!("magnet:?xs=urn:btpk:{}", encode_hex(&self.public_key));
+        if let Some(ref salt) = self.salt {
+            write!(link, "&s={}", encode_hex(salt))
+                .expect("Writing to a String should never fail.");
+        }
+        link
+    }
+
+    /// Calculate the DHT target of the item this link points to, i.e.
+    /// the SHA1 hash of `public_key` followed by `salt`, as defined in
+    /// [BEP 44](http://bittorrent.org/beps/bep_0044.html).
+    pub fn target(&self) -> [u8; 20] {
+        let mut hasher = Sha1::new();
+        hasher.update(self.public_key);
+        if let Some(ref salt) = self.salt {
+            hasher.update(salt);
+        }
+        hasher.finalize().into()
+    }
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Returns `None` if `hex` is not a sequence of hex-encoded bytes.
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    hex.as_bytes()
+        .chunks_exact(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod mutable_magnet_tests {
+    use super::*;
+
+    const PUBLIC_KEY: &str = "8543d3e6115f0f98c944077a4493dcd543e49c739fd998550a1f614ab36ed63e";
+
+    #[test]
+    fn from_link_ok() {
+        let magnet = MutableMagnet::from_link(&format!(
+            "magnet:?dn=sample&xs=urn%3Abtpk%3A{}&s=0A0b&tr=http://example.com/announce",
+            PUBLIC_KEY.to_uppercase()
+        ))
+        .unwrap();
+
+        assert_eq!(encode_hex(&magnet.public_key), PUBLIC_KEY);
+        assert_eq!(magnet.salt, Some(vec![0x0a, 0x0b]));
+    }
+
+    #[test]
+    fn from_link_without_salt() {
+        let magnet = MutableMagnet::from_link(&format!(
+            "magnet:?xs=http://example.com/sample.torrent&xs=urn:btpk:{}",
+            PUBLIC_KEY
+        ))
+        .unwrap();
+
+        assert_eq!(encode_hex(&magnet.public_key), PUBLIC_KEY);
+        assert_eq!(magnet.salt, None);
+    }
+
+    #[test]
+    fn from_link_not_magnet() {
+        match MutableMagnet::from_link("http://example.com") {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, r#"Magnet link does not start with "magnet:?"."#)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn from_link_missing_public_key() {
+        match MutableMagnet::from_link(
+            "magnet:?xt=urn:btih:0000000000000000000000000000000000000000",
+        ) {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, r#"Magnet link does not contain "xs=urn:btpk:"."#)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn from_link_invalid_public_key() {
+        for key in [&PUBLIC_KEY[2..], "zz", &PUBLIC_KEY[1..]] {
+            match MutableMagnet::from_link(&format!("magnet:?xs=urn:btpk:{}", key)) {
+                Err(LavaTorrentError::InvalidArgument(m)) => {
+                    assert_eq!(m, "Public key is not 32 hex-encoded bytes.")
+                }
+                _ => panic!(),
+            }
+        }
+    }
+
+    #[test]
+    fn from_link_invalid_salt() {
+        for salt in ["abc".to_owned(), "00".repeat(65)] {
+            match MutableMagnet::from_link(&format!(
+                "magnet:?xs=urn:btpk:{}&s={}",
+                PUBLIC_KEY, salt
+            )) {
+                Err(LavaTorrentError::InvalidArgument(m)) => {
+                    assert_eq!(m, "Salt is not at most 64 hex-encoded bytes.")
+                }
+                _ => panic!(),
+            }
+        }
+    }
+
+    #[test]
+    fn magnet_link_ok() {
+        let mut magnet = MutableMagnet {
+            public_key: [0xab; 32],
+            salt: None,
+        };
+        assert_eq!(
+            magnet.magnet_link(),
+            format!("magnet:?xs=urn:btpk:{}", "ab".repeat(32))
+        );
+
+        magnet.salt = Some(b"foobar".to_vec());
+        assert_eq!(
+            magnet.magnet_link(),
+            format!("magnet:?xs=urn:btpk:{}&s=666f6f626172", "ab".repeat(32))
+        );
+        assert_eq!(
+            MutableMagnet::from_link(&magnet.magnet_link()).unwrap(),
+            magnet
+        );
+    }
+
+    #[test]
+    fn target_ok() {
+        let mut magnet = MutableMagnet {
+            public_key: [0xab; 32],
+            salt: None,
+        };
+        assert_eq!(magnet.target(), <[u8; 20]>::from(Sha1::digest([0xab; 32])));
+
+        magnet.salt = Some(b"foobar".to_vec());
+        assert_eq!(
+            magnet.target(),
+            <[u8; 20]>::from(Sha1::digest([&[0xab; 32][..], b"foobar"].concat()))
+        );
+    }
+}
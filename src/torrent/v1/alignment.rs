@@ -0,0 +1,218 @@
+use super::*;
+use crate::util;
+
+impl Torrent {
+    /// Check how well each file is aligned to piece boundaries, and how
+    /// many pieces are shared between files.
+    ///
+    /// Padding files (see [`File::is_padding()`]) are reported like any other
+    /// file, but sharing a piece with a padding file is not counted, since
+    /// its content doesn't need to be downloaded. So a torrent built with
+    /// padding between all files has no shared pieces.
+    ///
+    /// An error is returned if the torrent has a `piece length` of 0,
+    /// or if any length is negative.
+    ///
+    /// [`File::is_padding()`]: struct.File.html#method.is_padding
+    pub fn alignment_report(&self) -> Result<AlignmentReport, LavaTorrentError> {
+        let piece_length = util::i64_to_u64(self.info.piece_length)?;
+        if piece_length == 0 {
+            return Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::InvalidPieceLength {
+                    piece_length: self.info.piece_length,
+                },
+            ));
+        }
+
+        let spans = self.spans()?;
+        let content_end = spans.last().map_or(0, |span| span.offset + span.length);
+        let pieces = spans
+            .iter()
+            .map(|span| FilePieces::new(span, piece_length))
+            .collect::<Result<Vec<_>, LavaTorrentError>>()?;
+        let padding: Vec<_> = match self.info.files {
+            Some(ref files) => files.iter().map(File::is_padding).collect(),
+            None => vec![false],
+        };
+
+        // pieces of the files whose content matters, in order (so both
+        // `first_piece` and `last_piece` are non-decreasing)
+        let data_pieces: Vec<_> = pieces
+            .iter()
+            .zip(&padding)
+            .filter(|&(_, &is_padding)| !is_padding)
+            .filter_map(|(pieces, _)| *pieces)
+            .collect();
+        // number of those files that lie in piece `index`
+        let n_data_files = |index: usize| {
+            data_pieces.partition_point(|pieces| pieces.first_piece <= index)
+                - data_pieces.partition_point(|pieces| pieces.last_piece < index)
+        };
+
+        let mut shared_pieces: Vec<_> = data_pieces
+            .windows(2)
+            .filter(|pair| pair[0].last_piece == pair[1].first_piece)
+            .map(|pair| pair[0].last_piece)
+            .collect();
+        shared_pieces.dedup();
+
+        let files = spans
+            .iter()
+            .zip(pieces)
+            .zip(padding)
+            .map(|((span, pieces), is_padding)| {
+                let end = span.offset + span.length;
+                let is_shared = |index| n_data_files(index) > usize::from(!is_padding);
+                FileAlignment {
+                    pieces,
+                    starts_aligned: pieces.is_none() || span.offset % piece_length == 0,
+                    ends_aligned: pieces.is_none() || end % piece_length == 0 || end == content_end,
+                    shared_pieces: pieces.map_or(0, |pieces| {
+                        usize::from(is_shared(pieces.first_piece))
+                            + usize::from(
+                                pieces.last_piece != pieces.first_piece
+                                    && is_shared(pieces.last_piece),
+                            )
+                    }),
+                }
+            })
+            .collect();
+
+        Ok(AlignmentReport {
+            files,
+            shared_pieces: shared_pieces.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod alignment_tests {
+    use super::*;
+
+    // (first and last pieces, starts aligned, ends aligned, shared pieces)
+    type Summary = (Option<(usize, usize)>, bool, bool, usize);
+
+    fn torrent(files: &[(&str, Integer)]) -> Torrent {
+        Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: files.iter().map(|&(_, length)| length).sum(),
+                files: Some(
+                    files
+                        .iter()
+                        .map(|&(path, length)| File {
+                            length,
+                            path: PathBuf::from(path),
+                            extra_fields: None,
+                            raw_extra_fields: None,
+                        })
+                        .collect(),
+                ),
+                name: "sample".to_owned(),
+                piece_length: 4,
+                pieces: Pieces::from(vec![[1; 20]; 5]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        }
+    }
+
+    fn summarize(report: &AlignmentReport) -> Vec<Summary> {
+        report
+            .files
+            .iter()
+            .map(|file| {
+                (
+                    file.pieces
+                        .map(|pieces| (pieces.first_piece, pieces.last_piece)),
+                    file.starts_aligned,
+                    file.ends_aligned,
+                    file.shared_pieces,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn alignment_report_ok() {
+        let torrent = torrent(&[("a", 3), ("b", 0), ("c", 9), ("d", 2), ("e", 5)]);
+        let report = torrent.alignment_report().unwrap();
+
+        assert_eq!(report.shared_pieces, 2);
+        assert_eq!(
+            summarize(&report),
+            vec![
+                (Some((0, 0)), true, false, 1),
+                (None, true, true, 0),
+                (Some((0, 2)), false, true, 1),
+                (Some((3, 3)), true, false, 1),
+                (Some((3, 4)), false, true, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn alignment_report_shared_both_ends() {
+        let torrent = torrent(&[("a", 2), ("b", 4), ("c", 1), ("d", 1)]);
+        let report = torrent.alignment_report().unwrap();
+
+        assert_eq!(report.shared_pieces, 2);
+        assert_eq!(
+            summarize(&report),
+            vec![
+                (Some((0, 0)), true, false, 1),
+                (Some((0, 1)), false, false, 2),
+                (Some((1, 1)), false, false, 1),
+                (Some((1, 1)), false, true, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn alignment_report_padding() {
+        let torrent = torrent(&[("a", 3), (".pad/1", 1), ("c", 8), (".pad/4", 4)]);
+        let report = torrent.alignment_report().unwrap();
+
+        assert_eq!(report.shared_pieces, 0);
+        assert_eq!(
+            summarize(&report),
+            vec![
+                (Some((0, 0)), true, false, 0),
+                (Some((0, 0)), false, true, 1),
+                (Some((1, 2)), true, true, 0),
+                (Some((3, 3)), true, true, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn alignment_report_single_file() {
+        let mut torrent = torrent(&[]);
+        let info = torrent.info.fields_mut();
+        info.files = None;
+        info.length = 5;
+        let report = torrent.alignment_report().unwrap();
+
+        assert_eq!(report.shared_pieces, 0);
+        assert_eq!(summarize(&report), vec![(Some((0, 1)), true, true, 0)]);
+    }
+
+    #[test]
+    fn alignment_report_zero_piece_length() {
+        let mut torrent = torrent(&[("a", 3)]);
+        torrent.info.fields_mut().piece_length = 0;
+        match torrent.alignment_report() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#"Invalid "piece length": 0."#)
+            }
+            _ => panic!(),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use super::*;
+use crate::bencode::arbitrary::arbitrary_dictionary;
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+// Keys that are either written from other fields or validated when reading,
+// which are therefore not generated in `extra_fields`/`extra_info_fields`.
+const RESERVED_FILE_FIELDS: &[&str] = &["length", "path", "attr", "symlink path", "sha1"];
+const RESERVED_FIELDS: &[&str] = &[
+    "announce",
+    "announce-list",
+    "info",
+    "nodes",
+    "url-list",
+    "httpseeds",
+];
+const RESERVED_INFO_FIELDS: &[&str] = &[
+    "files",
+    "length",
+    "name",
+    "piece length",
+    "pieces",
+    "root hash",
+    "similar",
+    "collections",
+];
+
+// Limits that keep generated torrents small enough to be useful for fuzzing.
+const MAX_FILES: usize = 8;
+const MAX_PATH_COMPONENTS: usize = 4;
+const MAX_PIECES: usize = 64;
+const MIN_PIECE_LENGTH_EXP: u32 = 14; // 16 KiB
+const MAX_PIECE_LENGTH_EXP: u32 = 24; // 16 MiB
+
+impl<'a> Arbitrary<'a> for File {
+    /// Generate a file that can be part of a torrent, i.e. its `path` consists
+    /// of 1 or more valid components and its `extra_fields` do not contain
+    /// fields that are written from other fields or validated when reading.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let num_components = u.int_in_range(1..=MAX_PATH_COMPONENTS)?;
+        let path = (0..num_components)
+            .map(|_| arbitrary_component(u))
+            .collect::<Result<PathBuf>>()?;
+
+        Ok(File {
+            length: Integer::from(u.arbitrary::<u32>()?),
+            path,
+            extra_fields: arbitrary_dictionary(u, RESERVED_FILE_FIELDS)?,
+            raw_extra_fields: None,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Torrent {
+    /// Generate a torrent that passes [`ValidationPolicy::Strict`], so that
+    /// encoding and then reading it gives back the same torrent.
+    ///
+    /// Fields that are validated when reading (e.g. BEP 47 file
+    /// attributes) and merkle torrents are not generated.
+    ///
+    /// [`ValidationPolicy::Strict`]: enum.ValidationPolicy.html#variant.Strict
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let piece_length = 1 << u.int_in_range(MIN_PIECE_LENGTH_EXP..=MAX_PIECE_LENGTH_EXP)?;
+        let num_pieces = u.int_in_range(1..=MAX_PIECES)?;
+        let pieces = (0..num_pieces)
+            .map(|_| u.arbitrary())
+            .collect::<Result<Vec<Piece>>>()?;
+        // the last piece is not empty, so the number of pieces matches `length`
+        let length =
+            (num_pieces as Integer - 1) * piece_length + u.int_in_range(1..=piece_length)?;
+
+        let files = if u.arbitrary()? {
+            let num_files = u.int_in_range(1..=MAX_FILES)?;
+            let mut files = (0..num_files)
+                .map(|_| File::arbitrary(u))
+                .collect::<Result<Vec<File>>>()?;
+
+            // split `length` among the files
+            let mut remaining = length;
+            for file in files.iter_mut().skip(1) {
+                file.length = u.int_in_range(0..=remaining)?;
+                remaining -= file.length;
+            }
+            files[0].length = remaining;
+            Some(files)
+        } else {
+            None
+        };
+
+        Ok(Torrent {
+            announce: u.arbitrary()?,
+            announce_list: u.arbitrary()?,
+            nodes: u.arbitrary()?,
+            url_list: u.arbitrary()?,
+            httpseeds: u.arbitrary()?,
+            info: InfoFields {
+                length,
+                files,
+                name: u.arbitrary()?,
+                piece_length,
+                pieces: Pieces::from(pieces),
+                extra_info_fields: arbitrary_dictionary(u, RESERVED_INFO_FIELDS)?,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: arbitrary_dictionary(u, RESERVED_FIELDS)?,
+            raw_extra_fields: None,
+        })
+    }
+}
+
+// Generate a path component that is kept as is when
+// encoding the path and then reading it back.
+fn arbitrary_component(u: &mut Unstructured) -> Result<String> {
+    let component: String = u
+        .arbitrary::<String>()?
+        .chars()
+        .filter(|&c| (c != '/') && (c != '\\'))
+        .collect();
+
+    if component.is_empty() || (component == ".") || (component == "..") {
+        Ok("_".to_owned())
+    } else {
+        Ok(component)
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod torrent_arbitrary_tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{RngCore, SeedableRng};
+
+    #[test]
+    fn arbitrary_round_trip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut data = vec![0; 8192];
+
+        for _ in 0..256 {
+            rng.fill_bytes(&mut data);
+            let torrent = Torrent::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            let encoded = torrent.clone().encode().unwrap();
+
+            assert_eq!(encoded.len(), torrent.encoded_len());
+            assert_eq!(
+                Torrent::read_from_bytes_with_policy(encoded, ValidationPolicy::Strict).unwrap(),
+                torrent
+            );
+        }
+    }
+
+    #[test]
+    fn arbitrary_empty_input() {
+        let torrent = Torrent::arbitrary(&mut Unstructured::new(&[])).unwrap();
+        let encoded = torrent.clone().encode().unwrap();
+
+        assert_eq!(
+            Torrent::read_from_bytes_with_policy(encoded, ValidationPolicy::Strict).unwrap(),
+            torrent
+        );
+    }
+
+    #[test]
+    fn arbitrary_file_path() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut data = vec![0; 1024];
+
+        for _ in 0..64 {
+            rng.fill_bytes(&mut data);
+            let file = File::arbitrary(&mut Unstructured::new(&data)).unwrap();
+
+            assert!(file.path.is_relative());
+            assert!(file
+                .path
+                .components()
+                .all(|c| matches!(c, std::path::Component::Normal(_))));
+        }
+    }
+}
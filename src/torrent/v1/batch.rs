@@ -0,0 +1,251 @@
+use super::build::ThreadPool;
+use super::*;
+use std::sync::atomic::Ordering;
+
+impl BatchBuilder {
+    /// Create a new `BatchBuilder` with no jobs.
+    pub fn new() -> BatchBuilder {
+        BatchBuilder::default()
+    }
+
+    /// Add a job to the batch.
+    ///
+    /// Jobs are processed in the order they are added, and results
+    /// are returned in the same order.
+    pub fn add_job(mut self, builder: TorrentBuilder) -> BatchBuilder {
+        self.builders.push(builder);
+        self
+    }
+
+    /// Change the number of threads shared by all jobs when hashing pieces.
+    ///
+    /// If set to 0, the number of threads used will be equal to the number
+    /// of physical cores. **This is also the default behavior.**
+    ///
+    /// Set this to 1 if you prefer single-threaded hashing. Without the
+    /// `parallel` feature, hashing is always single-threaded.
+    pub fn set_num_threads(self, num_threads: usize) -> BatchBuilder {
+        BatchBuilder {
+            num_threads,
+            ..self
+        }
+    }
+
+    /// Build all jobs in the batch.
+    ///
+    /// A job failing does not affect the other jobs, so each job has its
+    /// own result. `Err` is only returned if the batch as a whole
+    /// cannot be processed (e.g. the worker threads cannot be created).
+    pub fn build(self) -> Result<Vec<Result<Torrent, LavaTorrentError>>, LavaTorrentError> {
+        let thread_pool = self.thread_pool()?;
+
+        Ok(self
+            .builders
+            .into_iter()
+            .map(|mut builder| {
+                builder.validate()?;
+                let (canonicalized_path, torrent) = builder.take_partial_torrent()?;
+                builder.read_content(canonicalized_path, torrent, thread_pool.as_ref())
+            })
+            .collect())
+    }
+
+    /// Like [`build()`], but non-blocking.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use lava_torrent::torrent::v1::{BatchBuilder, TorrentBuilder};
+    ///
+    /// let build = BatchBuilder::new()
+    ///     .add_job(TorrentBuilder::new("dir1/", 1048576))
+    ///     .add_job(TorrentBuilder::new("dir2/", 1048576))
+    ///     .build_non_blocking()
+    ///     .unwrap();
+    ///
+    /// while !build.is_finished() {
+    ///     println!(
+    ///         "batch build progress: {}% (job #1: {}%)",
+    ///         build.get_progress(),
+    ///         build.get_job_progress(0).unwrap(),
+    ///     );
+    ///     std::thread::sleep(std::time::Duration::from_millis(100));
+    /// }
+    ///
+    /// for torrent in build.get_output().unwrap() {
+    ///     println!("{}", torrent.unwrap().info.name);
+    /// }
+    /// ```
+    ///
+    /// [`build()`]: #method.build
+    pub fn build_non_blocking(self) -> Result<BatchBuild, LavaTorrentError> {
+        let jobs = self
+            .builders
+            .iter()
+            .map(|_| (Arc::new(AtomicU64::new(0)), Arc::new(AtomicU64::new(0))))
+            .collect::<Vec<_>>();
+        let n_job_finished = Arc::new(AtomicUsize::new(0));
+        let is_canceled = Arc::new(AtomicBool::new(false));
+        let thread_pool = self.thread_pool()?;
+
+        let torrent_builds = jobs
+            .iter()
+            .map(|(n_piece_processed, n_piece_total)| TorrentBuildInternal {
+                n_piece_processed: n_piece_processed.clone(),
+                n_piece_total: n_piece_total.clone(),
+                is_canceled: is_canceled.clone(),
+                events: Default::default(),
+                files: Default::default(),
+            })
+            .collect::<Vec<_>>();
+        let n_job_finished_internal = n_job_finished.clone();
+
+        let builder_thread = std::thread::spawn(move || {
+            self.builders
+                .into_iter()
+                .zip(torrent_builds)
+                .map(|(mut builder, torrent_build)| {
+                    let output = if torrent_build.is_canceled.load(Ordering::Acquire) {
+                        Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                            "build canceled by client",
+                        )))
+                    } else {
+                        builder.validate().and_then(|_| {
+                            let (canonicalized_path, torrent) = builder.take_partial_torrent()?;
+                            builder.read_content_non_blocking(
+                                canonicalized_path,
+                                torrent,
+                                thread_pool.as_ref(),
+                                torrent_build,
+                            )
+                        })
+                    };
+
+                    n_job_finished_internal.fetch_add(1, Ordering::AcqRel);
+                    output
+                })
+                .collect()
+        });
+
+        Ok(BatchBuild {
+            jobs,
+            n_job_finished,
+            is_canceled,
+            builder_thread: Some(builder_thread),
+        })
+    }
+
+    fn thread_pool(&self) -> Result<Option<ThreadPool>, LavaTorrentError> {
+        // same as `TorrentBuilder`
+        let num_threads = TorrentBuilder {
+            num_threads: self.num_threads,
+            ..Default::default()
+        }
+        .resolve_num_threads();
+
+        TorrentBuilder::thread_pool(num_threads)
+    }
+}
+
+impl BatchBuild {
+    /// Get the current progress of the batch build.
+    ///
+    /// The progress is represented in percentage and returned as an integer.
+    /// It's calculated as the average progress of all jobs.
+    ///
+    /// Similar to [`TorrentBuild::get_progress()`], `get_progress()` returning `100`
+    /// does not mean that [`get_output()`] won't block. Use [`is_finished()`] instead.
+    ///
+    /// [`TorrentBuild::get_progress()`]: struct.TorrentBuild.html#method.get_progress
+    /// [`get_output()`]: #method.get_output
+    /// [`is_finished()`]: #method.is_finished
+    pub fn get_progress(&self) -> u8 {
+        if self.jobs.is_empty() {
+            return 100;
+        }
+
+        let total = (0..self.jobs.len())
+            .filter_map(|index| self.get_job_progress(index))
+            .map(usize::from)
+            .sum::<usize>();
+
+        (total / self.jobs.len()) as u8
+    }
+
+    /// Get the current progress of the job at `index`
+    /// (i.e. the order in which the job was added).
+    ///
+    /// The progress is represented in percentage and returned as an integer.
+    /// `None` is returned if `index` is out of bounds.
+    pub fn get_job_progress(&self, index: usize) -> Option<u8> {
+        let (n_piece_processed, n_piece_total) = self.jobs.get(index)?;
+
+        // jobs that have failed or only contain empty files are also finished
+        if index < self.get_n_job_finished() {
+            return Some(100);
+        }
+
+        let n_piece_total = n_piece_total.load(Ordering::Acquire);
+        // in case the job has not started yet
+        if n_piece_total == 0 {
+            return Some(0);
+        }
+
+        let n_piece_processed = n_piece_processed.load(Ordering::Acquire);
+
+        Some((n_piece_processed * 100 / n_piece_total) as u8)
+    }
+
+    /// Get the number of jobs in the batch.
+    pub fn get_n_job_total(&self) -> usize {
+        self.jobs.len()
+    }
+
+    /// Get the number of jobs that have finished (successfully or not) so far.
+    pub fn get_n_job_finished(&self) -> usize {
+        self.n_job_finished.load(Ordering::Acquire)
+    }
+
+    /// Cancel the batch build.
+    ///
+    /// The job being processed and all jobs that have not been started
+    /// will fail with [`Err(LavaTorrentError::TorrentBuilderFailure)`].
+    /// Jobs that have finished are not affected.
+    ///
+    /// [`Err(LavaTorrentError::TorrentBuilderFailure)`]: ../../enum.LavaTorrentError.html#variant.TorrentBuilderFailure
+    pub fn cancel(&self) {
+        self.is_canceled.store(true, Ordering::Release)
+    }
+
+    /// Retrieve the output of the batch build, i.e. the result of each job.
+    ///
+    /// This function will block if the build has not finished yet. You can
+    /// use [`is_finished()`] to check if the build has finished.
+    ///
+    /// [`is_finished()`]: #method.is_finished
+    pub fn get_output(
+        mut self,
+    ) -> Result<Vec<Result<Torrent, LavaTorrentError>>, LavaTorrentError> {
+        self.builder_thread.take().unwrap().join().map_err(|e| {
+            LavaTorrentError::TorrentBuilderFailure(Cow::Owned(format!(
+                "builder thread has unexpectedly panicked: {:?}",
+                e
+            )))
+        })
+    }
+
+    /// Check if the batch build has finished.
+    pub fn is_finished(&self) -> bool {
+        self.builder_thread.as_ref().unwrap().is_finished()
+    }
+}
+
+impl Drop for BatchBuild {
+    // same as `TorrentBuild`
+    fn drop(&mut self) {
+        self.cancel();
+        if let Some(builder_thread) = self.builder_thread.take() {
+            let _ = builder_thread.join();
+        }
+    }
+}
@@ -1,10 +1,43 @@
+#[cfg(feature = "fs")]
+use super::merkle::extract_root_hash;
+#[cfg(feature = "parallel")]
+use super::sparse::{Holes, SparseFile};
+#[cfg(feature = "fs")]
+use super::spill::PieceSpill;
 use super::*;
+#[cfg(feature = "fs")]
+use crate::bencode::write::{string_len, write_file};
+#[cfg(feature = "fs")]
+use crate::bencode::WriteOptions;
 use crate::util;
+#[cfg(feature = "rand")]
+use rand::Rng;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use sha1::{Digest, Sha1};
-use std::io::{BufReader, Read, Seek};
+#[cfg(feature = "parallel")]
+use std::io::Seek;
+#[cfg(feature = "fs")]
+use std::io::{Read, Write};
+#[cfg(feature = "fs")]
 use std::sync::atomic::Ordering;
-use std::sync::Arc;
+#[cfg(feature = "fs")]
+use std::sync::mpsc::{self, Receiver};
+#[cfg(feature = "fs")]
+use std::sync::{Arc, Mutex, PoisonError};
+
+// Without the `parallel` feature, pieces are always hashed
+// on the current thread, so there is no thread pool to use.
+#[cfg(feature = "parallel")]
+pub(super) type ThreadPool = rayon::ThreadPool;
+#[cfg(all(feature = "fs", not(feature = "parallel")))]
+pub(super) enum ThreadPool {}
+
+// (length, pieces, the file's SHA-1 hash if requested)
+#[cfg(feature = "fs")]
+type ReadFileOutput = (Integer, Pieces, Option<Vec<u8>>);
+// (pieces, each content's SHA-1 hash if requested)
+type HashInMemoryOutput = (Pieces, Vec<Option<Vec<u8>>>);
 
 impl TorrentBuilder {
     /// Create a new `TorrentBuilder` with required fields set.
@@ -32,88 +65,121 @@ impl TorrentBuilder {
     /// If `name` is not set, then the [last component] of `path`
     /// will be used as the `Torrent`'s `name` field.
     ///
+    /// On platforms that support it (e.g. Linux), holes in sparse files
+    /// are detected and hashed as zeros without being read from disk.
+    ///
     /// `build()` **does not** provide comprehensive validation of
     /// any input. Basic cases such as setting `announce` to
     /// an empty string will be detected and `Err` will be returned.
     /// But more complicated cases such as using an invalid url
-    /// as `announce` won't be detected. Again, the caller
-    /// has to ensure that the values given to a `TorrentBuilder`
-    /// are valid.
+    /// as `announce` won't be detected unless [`set_announce_validation()`]
+    /// is enabled. Again, the caller has to ensure that the values
+    /// given to a `TorrentBuilder` are valid.
     ///
+    /// [`set_announce_validation()`]: #method.set_announce_validation
     /// [last component]: https://doc.rust-lang.org/std/path/struct.Path.html#method.file_name
-    pub fn build(self) -> Result<Torrent, LavaTorrentError> {
-        // delegate validation to other methods
-        self.validate_announce()?;
-        self.validate_announce_list()?;
-        self.validate_name()?;
-        self.validate_path()?;
-        self.validate_piece_length()?;
-        self.validate_extra_fields()?;
-        self.validate_extra_info_fields()?;
+    #[cfg(feature = "fs")]
+    pub fn build(mut self) -> Result<Torrent, LavaTorrentError> {
+        self.validate()?;
+        let (canonicalized_path, torrent) = self.take_partial_torrent()?;
 
-        // canonicalize path as it can be neither absolute nor canonicalized
-        let canonicalized_path = self.path.canonicalize()?;
+        // determine the # of threads to use
+        let thread_pool = Self::thread_pool(self.resolve_num_threads())?;
 
-        // if `name` is not yet set, set it to the last component of `path`
-        let name = if let Some(name) = self.name {
-            name
-        } else {
-            util::last_component(&self.path)?
+        self.read_content(canonicalized_path, torrent, thread_pool.as_ref())
+    }
+
+    /// Like [`build()`], but re-reads the content after building and
+    /// checks that every piece hash matches.
+    ///
+    /// This guards against silent read errors and content being modified
+    /// during a (long) build, at the cost of reading all the content twice.
+    /// If the check fails, `Err` will be returned.
+    ///
+    /// [`build()`]: #method.build
+    #[cfg(feature = "fs")]
+    pub fn build_and_verify(self) -> Result<Torrent, LavaTorrentError> {
+        // per-file hashes are not needed for verification
+        let verifier = TorrentBuilder {
+            file_sha1: false,
+            ..self.clone()
         };
 
-        // set `private = 1` in `info` if the torrent is private
-        let mut extra_info_fields = self.extra_info_fields;
-        if self.is_private {
-            extra_info_fields
-                .get_or_insert_with(HashMap::new)
-                .insert("private".to_owned(), BencodeElem::Integer(1));
-        }
+        let torrent = self.build()?;
+        verifier.verify(&torrent)?;
+        Ok(torrent)
+    }
 
-        // determine the # of threads to use
-        let num_threads = if self.num_threads == 0 {
-            num_cpus::get_physical()
-        } else {
-            self.num_threads
-        };
+    /// Like [`build()`], but encodes the torrent and writes it to `dst`
+    /// without holding all piece hashes in memory.
+    ///
+    /// Piece hashes are written to a temporary file in [`std::env::temp_dir()`]
+    /// while hashing, and copied into `pieces` while encoding. Memory usage
+    /// then no longer grows with the number of pieces, which matters for
+    /// content with millions of pieces. The output is the same as that of
+    /// [`build()`] followed by [`Torrent::write_into()`].
+    ///
+    /// Pieces are hashed in order on the current thread (with reads done ahead
+    /// on another thread), so [`set_num_threads()`] has no effect. Merkle
+    /// torrents need all hashes to compute their root, so `Err` will be
+    /// returned if [`set_merkle()`] has been set.
+    ///
+    /// [`build()`]: #method.build
+    /// [`set_num_threads()`]: #method.set_num_threads
+    /// [`set_merkle()`]: #method.set_merkle
+    /// [`Torrent::write_into()`]: struct.Torrent.html#method.write_into
+    #[cfg(feature = "fs")]
+    pub fn build_into<W>(self, dst: &mut W) -> Result<(), LavaTorrentError>
+    where
+        W: Write,
+    {
+        let (torrent, mut spill) = self.build_spilled()?;
+        torrent.write_into_with_pieces(dst, spill.len(), |dst| spill.copy_into(dst))
+    }
 
-        // delegate the actual file reading to other methods
-        if canonicalized_path.metadata()?.is_dir() {
-            let (length, files, pieces) = if num_threads == 1 {
-                Self::read_dir(canonicalized_path, self.piece_length)?
-            } else {
-                Self::read_dir_parallel(canonicalized_path, self.piece_length, num_threads)?
-            };
+    /// Like [`build_into()`], but writes the torrent to the file at `path`.
+    ///
+    /// The file is only created once all pieces have been hashed.
+    ///
+    /// [`build_into()`]: #method.build_into
+    #[cfg(feature = "fs")]
+    pub fn build_into_file<P>(self, path: P) -> Result<(), LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let (torrent, mut spill) = self.build_spilled()?;
+        write_file(path.as_ref(), &WriteOptions::default(), |dst| {
+            torrent.write_into_with_pieces(dst, spill.len(), |dst| spill.copy_into(dst))
+        })
+    }
 
-            Ok(Torrent {
-                announce: self.announce,
-                announce_list: self.announce_list,
-                length,
-                files: Some(files),
-                name,
-                piece_length: self.piece_length,
-                pieces,
-                extra_fields: self.extra_fields,
-                extra_info_fields,
-            })
+    // build a torrent without `pieces`, which are spilled instead
+    #[cfg(feature = "fs")]
+    fn build_spilled(mut self) -> Result<(Torrent, PieceSpill), LavaTorrentError> {
+        self.validate()?;
+        if self.merkle {
+            return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                "TorrentBuilder has `merkle` set, which requires all piece hashes in memory.",
+            )));
+        }
+        let (path, mut torrent) = self.take_partial_torrent()?;
+        let mut spill = PieceSpill::new(std::env::temp_dir())?;
+
+        if path
+            .metadata()
+            .map_err(util::file_io_err(&path, None))?
+            .is_dir()
+        {
+            let (length, files) = self.read_dir_into(path, |piece| spill.push(piece))?;
+            let info = torrent.info.fields_mut();
+            info.length = length;
+            info.files = Some(files);
         } else {
-            let (length, pieces) = if num_threads == 1 {
-                Self::read_file(canonicalized_path, self.piece_length)?
-            } else {
-                Self::read_file_parallel(canonicalized_path, self.piece_length, num_threads)?
-            };
-
-            Ok(Torrent {
-                announce: self.announce,
-                announce_list: self.announce_list,
-                length,
-                files: None,
-                name,
-                piece_length: self.piece_length,
-                pieces,
-                extra_fields: self.extra_fields,
-                extra_info_fields,
-            })
+            let (length, sha1) = self.read_file_into(path, |piece| spill.push(piece))?;
+            torrent.info.fields_mut().length = length;
+            Self::set_info_sha1(&mut torrent, sha1);
         }
+        Ok((torrent, spill))
     }
 
     /// Like [`build()`], but non-blocking.
@@ -135,116 +201,402 @@ impl TorrentBuilder {
     /// ```
     ///
     /// [`build()`]: #method.build
-    pub fn build_non_blocking(self) -> Result<TorrentBuild, LavaTorrentError> {
-        // delegate validation to other methods
+    #[cfg(feature = "fs")]
+    pub fn build_non_blocking(mut self) -> Result<TorrentBuild, LavaTorrentError> {
+        self.validate()?;
+        let (canonicalized_path, torrent) = self.take_partial_torrent()?;
+
+        // determine the # of threads to use
+        let num_threads = self.resolve_num_threads();
+
+        // have another thread handle IO and hashing so that the current thread won't block
+        let n_piece_processed = Arc::new(AtomicU64::new(0));
+        let n_piece_total = Arc::new(AtomicU64::new(0));
+        let is_canceled = Arc::new(AtomicBool::new(false));
+        let events = BuildEventSlot::default();
+        let files = Arc::new(Mutex::new(BuildFiles::default()));
+
+        let torrent_build_internal = TorrentBuildInternal {
+            n_piece_processed: n_piece_processed.clone(),
+            n_piece_total: n_piece_total.clone(),
+            is_canceled: is_canceled.clone(),
+            events: events.clone(),
+            files: files.clone(),
+        };
+
+        let builder_thread = std::thread::spawn(move || {
+            let torrent_build = torrent_build_internal.clone();
+            let output = Self::thread_pool(num_threads).and_then(|thread_pool| {
+                self.read_content_non_blocking(
+                    canonicalized_path,
+                    torrent,
+                    thread_pool.as_ref(),
+                    torrent_build_internal,
+                )
+            });
+
+            torrent_build.finish(&output);
+            output
+        });
+
+        Ok(TorrentBuild {
+            n_piece_processed,
+            n_piece_total,
+            is_canceled,
+            events,
+            files,
+            builder_thread: Some(builder_thread),
+        })
+    }
+
+    /// Like [`build()`], but builds a single-file `Torrent` from
+    /// `content` in memory instead of reading it from `path`.
+    ///
+    /// `path` is only used to determine the default `name`, and pieces are
+    /// always hashed on the current thread. Since neither the file system
+    /// nor threads are needed, this also works on targets that lack
+    /// them (e.g. `wasm32-unknown-unknown`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lava_torrent::torrent::v1::TorrentBuilder;
+    ///
+    /// let torrent = TorrentBuilder::new("hello.txt", 16384)
+    ///     .build_from_bytes("hello world")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(torrent.info.name, "hello.txt");
+    /// assert_eq!(torrent.info.length, 11);
+    /// assert_eq!(torrent.info.pieces.len(), 1);
+    /// ```
+    ///
+    /// [`build()`]: #method.build
+    pub fn build_from_bytes<B>(mut self, content: B) -> Result<Torrent, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        self.validate_metadata()?;
+        let content = content.as_ref();
+        if content.is_empty() && self.zero_length_file_policy != ZeroLengthFilePolicy::Include {
+            return Err(Self::zero_length_file_error(&self.path));
+        }
+
+        let mut torrent = self.take_metadata()?;
+        let (pieces, sha1) = self.hash_in_memory(&[content])?;
+        let info = torrent.info.fields_mut();
+        info.length = util::u64_to_i64(util::usize_to_u64(content.len())?)?;
+        info.pieces = pieces;
+        Self::set_info_sha1(&mut torrent, sha1.into_iter().next().flatten());
+
+        if self.merkle {
+            torrent.set_merkle();
+        }
+        Ok(torrent)
+    }
+
+    /// Like [`build_from_bytes()`], but builds a multi-file `Torrent`
+    /// from `entries`, i.e. `(path, content)` pairs where each `path`
+    /// is relative to the root of the torrent.
+    ///
+    /// As with [`build()`], files are sorted by path and zero-length files
+    /// are handled according to [`set_zero_length_file_policy()`].
+    /// Paths must be unique, and must consist of normal components only
+    /// (i.e. no `..`, `.` or root).
+    ///
+    /// [`build()`]: #method.build
+    /// [`build_from_bytes()`]: #method.build_from_bytes
+    /// [`set_zero_length_file_policy()`]: #method.set_zero_length_file_policy
+    pub fn build_from_entries<I, P, B>(mut self, entries: I) -> Result<Torrent, LavaTorrentError>
+    where
+        I: IntoIterator<Item = (P, B)>,
+        P: AsRef<Path>,
+        B: AsRef<[u8]>,
+    {
+        self.validate_metadata()?;
+        let mut entries = entries
+            .into_iter()
+            .map(|(path, content)| (path.as_ref().to_path_buf(), content))
+            .collect::<Vec<_>>();
+        entries.sort_by(|(p1, _), (p2, _)| p1.cmp(p2));
+        self.validate_entries(&mut entries)?;
+
+        let mut torrent = self.take_metadata()?;
+        let contents = entries
+            .iter()
+            .map(|(_, content)| content.as_ref())
+            .collect::<Vec<_>>();
+        let (pieces, sha1s) = self.hash_in_memory(&contents)?;
+
+        let mut files = Vec::with_capacity(entries.len());
+        for ((path, content), sha1) in entries.into_iter().zip(sha1s) {
+            files.push(File {
+                length: util::u64_to_i64(util::usize_to_u64(content.as_ref().len())?)?,
+                path,
+                extra_fields: sha1.map(Self::file_sha1_field),
+                raw_extra_fields: None,
+            });
+        }
+        torrent.info.fields_mut().length = files.iter().try_fold(0, |acc: Integer, file| {
+            acc.checked_add(file.length)
+                .ok_or(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                    "Total length of entries overflowed in i64.",
+                )))
+        })?;
+        let info = torrent.info.fields_mut();
+        info.files = Some(files);
+        info.pieces = pieces;
+
+        if self.merkle {
+            torrent.set_merkle();
+        }
+        Ok(torrent)
+    }
+
+    // delegate validation to other methods
+    #[cfg(feature = "fs")]
+    pub(super) fn validate(&self) -> Result<(), LavaTorrentError> {
+        self.validate_metadata()?;
+        self.validate_path()
+    }
+
+    // everything but `path`, which in-memory builds don't read from
+    fn validate_metadata(&self) -> Result<(), LavaTorrentError> {
         self.validate_announce()?;
         self.validate_announce_list()?;
+        self.validate_announce_urls()?;
         self.validate_name()?;
-        self.validate_path()?;
         self.validate_piece_length()?;
         self.validate_extra_fields()?;
         self.validate_extra_info_fields()?;
+        self.validate_web_seeds()?;
+        self.validate_httpseeds()?;
+        self.validate_nodes()?;
+        Ok(())
+    }
 
+    // Like `take_metadata()`, but the canonicalized path
+    // to the content is returned as well.
+    #[cfg(feature = "fs")]
+    pub(super) fn take_partial_torrent(&mut self) -> Result<(PathBuf, Torrent), LavaTorrentError> {
         // canonicalize path as it can be neither absolute nor canonicalized
-        let canonicalized_path = self.path.canonicalize()?;
+        let canonicalized_path = self
+            .path
+            .canonicalize()
+            .map_err(util::file_io_err(&self.path, None))?;
 
+        Ok((canonicalized_path, self.take_metadata()?))
+    }
+
+    // Move everything except the content-related fields into a `Torrent`.
+    // Those fields (`length`, `files` and `pieces`) are filled in
+    // after the content has been read.
+    fn take_metadata(&mut self) -> Result<Torrent, LavaTorrentError> {
         // if `name` is not yet set, set it to the last component of `path`
-        let name = if let Some(name) = self.name {
+        let name = if let Some(name) = self.name.take() {
             name
         } else {
             util::last_component(&self.path)?
         };
 
         // set `private = 1` in `info` if the torrent is private
-        let mut extra_info_fields = self.extra_info_fields;
+        let mut extra_info_fields = self.extra_info_fields.take();
         if self.is_private {
             extra_info_fields
                 .get_or_insert_with(HashMap::new)
                 .insert("private".to_owned(), BencodeElem::Integer(1));
         }
 
-        // determine the # of threads to use
-        let num_threads = if self.num_threads == 0 {
-            num_cpus::get_physical()
+        // set `entropy` in `info` so that the info hash becomes unique
+        #[cfg(feature = "rand")]
+        if self.has_entropy {
+            extra_info_fields
+                .get_or_insert_with(HashMap::new)
+                .insert("entropy".to_owned(), BencodeElem::String(Self::entropy()));
+        }
+
+        Ok(Torrent {
+            announce: self.announce.take(),
+            announce_list: self.announce_list.take(),
+            nodes: self.nodes.take(),
+            url_list: self.web_seeds.take(),
+            httpseeds: self.httpseeds.take(),
+            info: InfoFields {
+                length: 0,
+                files: None,
+                name,
+                piece_length: self.piece_length,
+                pieces: Pieces::new(),
+                extra_info_fields,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: self.extra_fields.take(),
+            raw_extra_fields: None,
+        })
+    }
+
+    // delegate the actual file reading to other methods
+    #[cfg(feature = "fs")]
+    pub(super) fn read_content(
+        &self,
+        path: PathBuf,
+        mut torrent: Torrent,
+        thread_pool: Option<&ThreadPool>,
+    ) -> Result<Torrent, LavaTorrentError> {
+        if path
+            .metadata()
+            .map_err(util::file_io_err(&path, None))?
+            .is_dir()
+        {
+            let (length, files, pieces) = match thread_pool {
+                None => self.read_dir(path)?,
+                #[cfg(feature = "parallel")]
+                Some(thread_pool) => self.read_dir_parallel(path, thread_pool)?,
+                #[cfg(not(feature = "parallel"))]
+                Some(thread_pool) => match *thread_pool {},
+            };
+
+            let info = torrent.info.fields_mut();
+            info.length = length;
+            info.files = Some(files);
+            info.pieces = pieces;
         } else {
-            self.num_threads
-        };
+            let (length, pieces, sha1) = match thread_pool {
+                None => self.read_file(path)?,
+                #[cfg(feature = "parallel")]
+                Some(thread_pool) => self.read_file_parallel(path, thread_pool)?,
+                #[cfg(not(feature = "parallel"))]
+                Some(thread_pool) => match *thread_pool {},
+            };
 
-        // have another thread handle IO and hashing so that the current thread won't block
-        let n_piece_processed = Arc::new(AtomicU64::new(0));
-        let n_piece_total = Arc::new(AtomicU64::new(0));
-        let is_canceled = Arc::new(AtomicBool::new(false));
+            let info = torrent.info.fields_mut();
+            info.length = length;
+            info.pieces = pieces;
+            Self::set_info_sha1(&mut torrent, sha1);
+        }
 
-        let torrent_build_internal = TorrentBuildInternal {
-            n_piece_processed: n_piece_processed.clone(),
-            n_piece_total: n_piece_total.clone(),
-            is_canceled: is_canceled.clone(),
-        };
+        if self.merkle {
+            torrent.set_merkle();
+        }
+        Ok(torrent)
+    }
 
-        let builder_thread = std::thread::spawn(move || {
-            if canonicalized_path.metadata()?.is_dir() {
-                let (length, files, pieces) = if num_threads == 1 {
-                    Self::read_dir_non_blocking(
-                        canonicalized_path,
-                        self.piece_length,
-                        torrent_build_internal,
-                    )?
-                } else {
-                    Self::read_dir_parallel_non_blocking(
-                        canonicalized_path,
-                        self.piece_length,
-                        num_threads,
-                        torrent_build_internal,
-                    )?
-                };
+    // like `read_content()`, but reports progress and can be canceled
+    #[cfg(feature = "fs")]
+    pub(super) fn read_content_non_blocking(
+        &self,
+        path: PathBuf,
+        mut torrent: Torrent,
+        thread_pool: Option<&ThreadPool>,
+        torrent_build: TorrentBuildInternal,
+    ) -> Result<Torrent, LavaTorrentError> {
+        if path
+            .metadata()
+            .map_err(util::file_io_err(&path, None))?
+            .is_dir()
+        {
+            let (length, files, pieces) = match thread_pool {
+                None => self.read_dir_non_blocking(path, torrent_build)?,
+                #[cfg(feature = "parallel")]
+                Some(thread_pool) => {
+                    self.read_dir_parallel_non_blocking(path, thread_pool, torrent_build)?
+                }
+                #[cfg(not(feature = "parallel"))]
+                Some(thread_pool) => match *thread_pool {},
+            };
 
-                Ok(Torrent {
-                    announce: self.announce,
-                    announce_list: self.announce_list,
-                    length,
-                    files: Some(files),
-                    name,
-                    piece_length: self.piece_length,
-                    pieces,
-                    extra_fields: self.extra_fields,
-                    extra_info_fields,
-                })
-            } else {
-                let (length, pieces) = if num_threads == 1 {
-                    Self::read_file_non_blocking(
-                        canonicalized_path,
-                        self.piece_length,
-                        torrent_build_internal,
-                    )?
-                } else {
-                    Self::read_file_parallel_non_blocking(
-                        canonicalized_path,
-                        self.piece_length,
-                        num_threads,
-                        torrent_build_internal,
-                    )?
-                };
+            let info = torrent.info.fields_mut();
+            info.length = length;
+            info.files = Some(files);
+            info.pieces = pieces;
+        } else {
+            let (length, pieces, sha1) = match thread_pool {
+                None => self.read_file_non_blocking(path, torrent_build)?,
+                #[cfg(feature = "parallel")]
+                Some(thread_pool) => {
+                    self.read_file_parallel_non_blocking(path, thread_pool, torrent_build)?
+                }
+                #[cfg(not(feature = "parallel"))]
+                Some(thread_pool) => match *thread_pool {},
+            };
 
-                Ok(Torrent {
-                    announce: self.announce,
-                    announce_list: self.announce_list,
-                    length,
-                    files: None,
-                    name,
-                    piece_length: self.piece_length,
-                    pieces,
-                    extra_fields: self.extra_fields,
-                    extra_info_fields,
-                })
-            }
+            let info = torrent.info.fields_mut();
+            info.length = length;
+            info.pieces = pieces;
+            Self::set_info_sha1(&mut torrent, sha1);
+        }
+
+        if self.merkle {
+            torrent.set_merkle();
+        }
+        Ok(torrent)
+    }
+
+    // set `sha1` in `info` if per-file hashes are requested
+    fn set_info_sha1(torrent: &mut Torrent, sha1: Option<Vec<u8>>) {
+        if let Some(sha1) = sha1 {
+            torrent
+                .info
+                .fields_mut()
+                .extra_info_fields
+                .get_or_insert_with(HashMap::new)
+                .insert("sha1".to_owned(), BencodeElem::Bytes(sha1));
+        }
+    }
+
+    // `None` means that pieces should be hashed on the current thread
+    #[cfg(feature = "fs")]
+    pub(super) fn thread_pool(num_threads: usize) -> Result<Option<ThreadPool>, LavaTorrentError> {
+        match num_threads {
+            1 => Ok(None),
+            #[cfg(feature = "parallel")]
+            num_threads => Ok(Some(Self::rayon_thread_pool(num_threads)?)),
+            #[cfg(not(feature = "parallel"))]
+            _ => Ok(None),
+        }
+    }
+
+    // Run `op` in `thread_pool`. If `low_priority_io` is set, the IO priority
+    // of each thread in the pool is lowered while `op` runs (once per thread,
+    // rather than for each read), and restored afterwards since the pool
+    // might be shared by other jobs of a batch.
+    #[cfg(feature = "parallel")]
+    fn install<OP, R>(&self, thread_pool: &ThreadPool, op: OP) -> R
+    where
+        OP: FnOnce() -> R + Send,
+        R: Send,
+    {
+        thread_local! {
+            static PRIORITY: std::cell::RefCell<Option<util::LowIoPriority>> =
+                const { std::cell::RefCell::new(None) };
+        }
+
+        if !self.low_priority_io {
+            return thread_pool.install(op);
+        }
+        thread_pool.broadcast(|_| {
+            PRIORITY.with(|priority| *priority.borrow_mut() = Some(util::LowIoPriority::enter()))
         });
+        let output = thread_pool.install(op);
+        // the guards must be dropped on the threads that created them
+        thread_pool.broadcast(|_| PRIORITY.with(|priority| drop(priority.borrow_mut().take())));
+        output
+    }
 
-        Ok(TorrentBuild {
-            n_piece_processed,
-            n_piece_total,
-            is_canceled,
-            builder_thread: Some(builder_thread),
-        })
+    #[cfg(feature = "parallel")]
+    pub(super) fn rayon_thread_pool(
+        num_threads: usize,
+    ) -> Result<rayon::ThreadPool, LavaTorrentError> {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .map_err(|e| {
+                LavaTorrentError::TorrentBuilderFailure(Cow::Owned(format!(
+                    "failed to create rayon thread pool: {}",
+                    e
+                )))
+            })
     }
 
     /// Set the `announce` field of the `Torrent` to be built.
@@ -276,6 +628,74 @@ impl TorrentBuilder {
         }
     }
 
+    /// Add a tracker to the `Torrent` to be built, in a new tier of its own.
+    ///
+    /// `announce` and `announce_list` are kept consistent:
+    /// - if `announce` is not yet set, it will be set to `url`
+    /// - if `announce` is set but `announce_list` is not, `announce`
+    ///   will become the first tier of `announce_list`
+    ///
+    /// If `url` is already in `announce_list`, this method does nothing.
+    ///
+    /// The caller has to ensure that `url` is valid, as
+    /// this method does not validate its value. If `url`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [`build()`]: #method.build
+    pub fn add_tracker(mut self, url: String) -> TorrentBuilder {
+        let mut announce_list = self.take_announce_list();
+
+        if !announce_list.iter().flatten().any(|u| *u == url) {
+            announce_list.push(vec![url.clone()]);
+        }
+
+        TorrentBuilder {
+            announce: self.announce.or(Some(url)),
+            announce_list: Some(announce_list),
+            ..self
+        }
+    }
+
+    /// Add a tracker to tier `tier` (0-based) of the `Torrent` to be built.
+    ///
+    /// If `tier` does not exist yet, `url` is added in a new tier after
+    /// the last one instead. If `url` is already in `announce_list`,
+    /// this method does nothing.
+    ///
+    /// `announce` and `announce_list` are kept consistent in the
+    /// same way as [`add_tracker()`].
+    ///
+    /// The caller has to ensure that `url` is valid, as
+    /// this method does not validate its value. If `url`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [`build()`]: #method.build
+    /// [`add_tracker()`]: #method.add_tracker
+    pub fn add_tracker_to_tier(mut self, tier: usize, url: String) -> TorrentBuilder {
+        let mut announce_list = self.take_announce_list();
+
+        if !announce_list.iter().flatten().any(|u| *u == url) {
+            match announce_list.get_mut(tier) {
+                Some(tier) => tier.push(url.clone()),
+                None => announce_list.push(vec![url.clone()]),
+            }
+        }
+
+        TorrentBuilder {
+            announce: self.announce.or(Some(url)),
+            announce_list: Some(announce_list),
+            ..self
+        }
+    }
+
+    // if `announce_list` is not set, start a new one with `announce` as its first tier
+    fn take_announce_list(&mut self) -> AnnounceList {
+        match self.announce_list.take() {
+            Some(announce_list) => announce_list,
+            None => self.announce.iter().map(|url| vec![url.clone()]).collect(),
+        }
+    }
+
     /// Set the `name` field of the `Torrent` to be built.
     ///
     /// Calling this method multiple times will simply override previous settings.
@@ -382,12 +802,164 @@ impl TorrentBuilder {
         TorrentBuilder { is_private, ..self }
     }
 
+    /// Add a random `entropy` field to the `info` dictionary of the `Torrent` to be built.
+    ///
+    /// This makes the info hash of the `Torrent` unique even if the same content has
+    /// been built before, which is useful when uploading the same content to
+    /// multiple trackers (cross-seeding). Clients ignore unknown fields
+    /// in `info`, so the content can still be shared between the torrents.
+    ///
+    /// If `entropy` is also set with [`add_extra_info_field()`],
+    /// the random value will override it.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// [`add_extra_info_field()`]: #method.add_extra_info_field
+    #[cfg(feature = "rand")]
+    pub fn set_entropy(self, has_entropy: bool) -> TorrentBuilder {
+        TorrentBuilder {
+            has_entropy,
+            ..self
+        }
+    }
+
+    /// Build a merkle torrent as defined in [BEP 30], i.e. store the
+    /// merkle root of the piece hashes as `root hash` in `info` instead of `pieces`.
+    ///
+    /// This keeps the .torrent file small regardless of the number of pieces,
+    /// but note that only a few clients support merkle torrents.
+    /// See [`Torrent::set_merkle()`] for how the root is computed.
+    ///
+    /// This option is disabled by default.
+    ///
+    /// [BEP 30]: http://bittorrent.org/beps/bep_0030.html
+    /// [`Torrent::set_merkle()`]: struct.Torrent.html#method.set_merkle
+    pub fn set_merkle(self, merkle: bool) -> TorrentBuilder {
+        TorrentBuilder { merkle, ..self }
+    }
+
+    /// Check that `announce` and all urls in `announce_list` are well-formed
+    /// `http`, `https` or `udp` tracker urls when building.
+    ///
+    /// If enabled, [`build()`] and [`build_non_blocking()`] will fail
+    /// before any file is read if an invalid url is found. Note that
+    /// only the syntax of the urls is checked, i.e. the trackers will not be contacted.
+    ///
+    /// This option is disabled by default.
+    ///
+    /// [`build()`]: #method.build
+    /// [`build_non_blocking()`]: #method.build_non_blocking
+    pub fn set_announce_validation(self, validates_announce: bool) -> TorrentBuilder {
+        TorrentBuilder {
+            validates_announce,
+            ..self
+        }
+    }
+
+    /// Set the web seeds of the `Torrent` to be built, as defined in [BEP 19].
+    ///
+    /// The web seeds will be stored in the top-level `url-list` field.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// The caller has to ensure that `web_seeds` is valid, as
+    /// this method does not validate its value. If `web_seeds`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [BEP 19]: http://bittorrent.org/beps/bep_0019.html
+    /// [`build()`]: #method.build
+    pub fn set_web_seeds(self, web_seeds: Vec<String>) -> TorrentBuilder {
+        TorrentBuilder {
+            web_seeds: Some(web_seeds),
+            ..self
+        }
+    }
+
+    /// Set the HTTP seeds of the `Torrent` to be built, as defined in [BEP 17].
+    ///
+    /// The HTTP seeds will be stored in the top-level `httpseeds` field.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// The caller has to ensure that `httpseeds` is valid, as
+    /// this method does not validate its value. If `httpseeds`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [BEP 17]: http://bittorrent.org/beps/bep_0017.html
+    /// [`build()`]: #method.build
+    pub fn set_httpseeds(self, httpseeds: Vec<String>) -> TorrentBuilder {
+        TorrentBuilder {
+            httpseeds: Some(httpseeds),
+            ..self
+        }
+    }
+
+    /// Set the DHT bootstrap nodes of the `Torrent` to be built, as defined in [BEP 5].
+    ///
+    /// Each node is a `(host, port)` pair, where `host` is either an IP
+    /// address or a DNS name. The nodes will be stored in the top-level
+    /// `nodes` field, which is what trackerless torrents are expected to have.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// The caller has to ensure that `nodes` is valid, as
+    /// this method does not validate its value. If `nodes`
+    /// turns out to be invalid, calling [`build()`] later will fail.
+    ///
+    /// [BEP 5]: http://bittorrent.org/beps/bep_0005.html
+    /// [`build()`]: #method.build
+    pub fn set_nodes(self, nodes: Vec<(String, u16)>) -> TorrentBuilder {
+        TorrentBuilder {
+            nodes: Some(nodes),
+            ..self
+        }
+    }
+
+    /// Compute the SHA-1 hash of each file and embed it in the `Torrent`
+    /// to be built, as defined in [BEP 47].
+    ///
+    /// For multi-file torrents the hash is stored as `sha1` in each
+    /// file's dictionary. For single-file torrents it is stored as
+    /// `sha1` in `info`.
+    ///
+    /// Files are hashed as they are read. Since a file's hash has to be
+    /// computed in order, this option makes pieces be hashed on a single
+    /// thread, regardless of [`set_num_threads()`].
+    ///
+    /// This option is disabled by default.
+    ///
+    /// [BEP 47]: http://bittorrent.org/beps/bep_0047.html
+    /// [`set_num_threads()`]: #method.set_num_threads
+    pub fn set_file_sha1(self, file_sha1: bool) -> TorrentBuilder {
+        TorrentBuilder { file_sha1, ..self }
+    }
+
+    /// Change how zero-length files are handled.
+    ///
+    /// The policy applies to the files in `path` if it is a directory.
+    /// If `path` is a zero-length file, then both [`ZeroLengthFilePolicy::Skip`]
+    /// and [`ZeroLengthFilePolicy::Error`] will cause the build to fail,
+    /// since nothing would be left to build a `Torrent` from.
+    ///
+    /// Calling this method multiple times will simply override previous settings.
+    ///
+    /// [`ZeroLengthFilePolicy::Skip`]: enum.ZeroLengthFilePolicy.html#variant.Skip
+    /// [`ZeroLengthFilePolicy::Error`]: enum.ZeroLengthFilePolicy.html#variant.Error
+    pub fn set_zero_length_file_policy(self, policy: ZeroLengthFilePolicy) -> TorrentBuilder {
+        TorrentBuilder {
+            zero_length_file_policy: policy,
+            ..self
+        }
+    }
+
     /// Change the number of threads used when hashing pieces.
     ///
     /// If set to 0, the number of threads used will be equal to the number
     /// of physical cores. **This is also the default behavior.**
     ///
-    /// Set this to 1 if you prefer single-threaded hashing.
+    /// Set this to 1 if you prefer single-threaded hashing. Without the
+    /// `parallel` feature, hashing is always single-threaded.
+    #[cfg(feature = "fs")]
     pub fn set_num_threads(self, num_threads: usize) -> TorrentBuilder {
         TorrentBuilder {
             num_threads,
@@ -395,6 +967,204 @@ impl TorrentBuilder {
         }
     }
 
+    /// Limit the rate at which content is read from disk while building,
+    /// e.g. to avoid starving other IO on the same disk.
+    ///
+    /// The limit applies to all threads of a build combined. Reads are
+    /// throttled in blocks, so the rate is an average rather than a hard cap
+    /// at every instant.
+    ///
+    /// If set to 0, reads are not limited. **This is also the default behavior.**
+    #[cfg(feature = "fs")]
+    pub fn set_max_read_rate(self, bytes_per_sec: u64) -> TorrentBuilder {
+        TorrentBuilder {
+            read_rate_limiter: if bytes_per_sec == 0 {
+                None
+            } else {
+                Some(util::RateLimiter::new(bytes_per_sec))
+            },
+            ..self
+        }
+    }
+
+    /// Read content with a low IO priority while building, so that
+    /// a build in the background doesn't slow down interactive disk access.
+    ///
+    /// This is a hint that is applied to each thread while it reads:
+    /// the idle IO scheduling class (as in `ionice -c 3`) on Linux, and
+    /// background processing mode (which lowers the CPU priority as well)
+    /// on Windows. It has no effect on other platforms, or if the
+    /// priority cannot be changed.
+    ///
+    /// Defaults to `false`.
+    #[cfg(feature = "fs")]
+    pub fn set_low_priority_io(self, low_priority_io: bool) -> TorrentBuilder {
+        TorrentBuilder {
+            low_priority_io,
+            ..self
+        }
+    }
+
+    /// Compute the size (in bytes) of the .torrent file that [`build()`] would produce,
+    /// without reading or hashing any content.
+    ///
+    /// The size is derived from the file count, path lengths and piece count,
+    /// so this is fast even for large content. The result is exact as long as
+    /// the content is not modified before building. This is useful
+    /// for checking the output against a tracker's .torrent size limit,
+    /// and increasing `piece_length` if it's too large.
+    ///
+    /// The same validation as in [`build()`] is performed, and `Err` is returned if it fails.
+    ///
+    /// [`build()`]: #method.build
+    #[cfg(feature = "fs")]
+    pub fn estimate_output_size(&self) -> Result<usize, LavaTorrentError> {
+        self.validate()?;
+        let (path, mut torrent) = self.clone().take_partial_torrent()?;
+
+        let length = if path
+            .metadata()
+            .map_err(util::file_io_err(&path, None))?
+            .is_dir()
+        {
+            let entries = self.list_dir(&path)?;
+            let total_length = entries.iter().fold(0, |acc, &(_, len)| acc + len);
+            let mut files = Vec::with_capacity(entries.len());
+
+            for (entry_path, length) in entries {
+                files.push(File {
+                    length: util::u64_to_i64(length)?,
+                    path: entry_path.strip_prefix(&path).unwrap().to_path_buf(),
+                    extra_fields: self.placeholder_sha1().map(Self::file_sha1_field),
+                    raw_extra_fields: None,
+                });
+            }
+
+            torrent.info.fields_mut().files = Some(files);
+            total_length
+        } else {
+            let length = path
+                .metadata()
+                .map_err(util::file_io_err(&path, None))?
+                .len();
+            if length == 0 && self.zero_length_file_policy != ZeroLengthFilePolicy::Include {
+                return Err(Self::zero_length_file_error(&path));
+            }
+
+            Self::set_info_sha1(&mut torrent, self.placeholder_sha1());
+            length
+        };
+        torrent.info.fields_mut().length = util::u64_to_i64(length)?;
+
+        // `pieces` is replaced by a root hash of fixed length
+        if self.merkle {
+            torrent
+                .info
+                .fields_mut()
+                .extra_info_fields
+                .get_or_insert_with(HashMap::new)
+                .insert(
+                    "root hash".to_owned(),
+                    BencodeElem::Bytes(vec![0; PIECE_STRING_LENGTH]),
+                );
+            return Ok(torrent.encoded_len());
+        }
+
+        // `pieces` is left empty to avoid allocating it, so account for it separately
+        let n_pieces = util::u64_to_usize(length.div_ceil(util::i64_to_u64(self.piece_length)?))?;
+        Ok(torrent.encoded_len() - string_len(0) + string_len(n_pieces * PIECE_STRING_LENGTH))
+    }
+
+    // a hash of the right length, for computing sizes when per-file hashes are requested
+    #[cfg(feature = "fs")]
+    fn placeholder_sha1(&self) -> Option<Vec<u8>> {
+        if self.file_sha1 {
+            Some(vec![0; PIECE_STRING_LENGTH])
+        } else {
+            None
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    pub(super) fn resolve_num_threads(&self) -> usize {
+        // per-file hashes are only computed by the single-threaded path,
+        // which reads content in order
+        if self.file_sha1 {
+            1
+        } else if self.num_threads == 0 {
+            num_cpus::get_physical()
+        } else {
+            self.num_threads
+        }
+    }
+
+    #[cfg(all(feature = "fs", not(feature = "parallel")))]
+    pub(super) fn resolve_num_threads(&self) -> usize {
+        1
+    }
+
+    // re-read the content and compare it against `torrent`
+    #[cfg(feature = "fs")]
+    fn verify(mut self, torrent: &Torrent) -> Result<(), LavaTorrentError> {
+        let (canonicalized_path, partial_torrent) = self.take_partial_torrent()?;
+        let thread_pool = Self::thread_pool(self.resolve_num_threads())?;
+
+        let InfoFields {
+            length,
+            files,
+            pieces,
+            extra_info_fields,
+            ..
+        } = self
+            .read_content(canonicalized_path, partial_torrent, thread_pool.as_ref())?
+            .info
+            .into_mut();
+
+        if length != torrent.info.length {
+            return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                "verification failed: the length of the content has changed.",
+            )));
+        }
+
+        let same_files = match (&files, &torrent.info.files) {
+            (Some(files), Some(expected)) => {
+                files.len() == expected.len()
+                    && files
+                        .iter()
+                        .zip(expected)
+                        .all(|(f, e)| (f.length == e.length) && (f.path == e.path))
+            }
+            (None, None) => true,
+            _ => false,
+        };
+        if !same_files {
+            return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                "verification failed: the list of files has changed.",
+            )));
+        }
+
+        // only merkle torrents have a root hash
+        if extract_root_hash(&extra_info_fields)? != torrent.root_hash()? {
+            return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                "verification failed: the root hash does not match the content on disk.",
+            )));
+        }
+
+        match pieces
+            .iter()
+            .zip(&torrent.info.pieces)
+            .position(|(piece, expected)| piece != expected)
+        {
+            Some(index) => Err(LavaTorrentError::TorrentBuilderFailure(Cow::Owned(
+                format!(
+                    "verification failed: piece [{}] does not match the content on disk.",
+                    index
+                ),
+            ))),
+            None => Ok(()),
+        }
+    }
+
     fn validate_announce(&self) -> Result<(), LavaTorrentError> {
         match self.announce {
             Some(ref announce) => {
@@ -443,6 +1213,72 @@ impl TorrentBuilder {
         }
     }
 
+    fn validate_announce_urls(&self) -> Result<(), LavaTorrentError> {
+        if !self.validates_announce {
+            return Ok(());
+        }
+
+        let announce_list = self.announce_list.iter().flatten().flatten();
+        for url in self.announce.iter().chain(announce_list) {
+            Self::validate_tracker_url(url)?;
+        }
+        Ok(())
+    }
+
+    fn validate_tracker_url(url: &str) -> Result<(), LavaTorrentError> {
+        let invalid = |reason| {
+            Err(LavaTorrentError::TorrentBuilderFailure(Cow::Owned(
+                format!(
+                    "TorrentBuilder has an invalid tracker url \"{}\": {}.",
+                    url, reason
+                ),
+            )))
+        };
+
+        if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return invalid("it contains whitespace or control characters");
+        }
+
+        let (scheme, rest) = match url.split_once("://") {
+            Some(split) => split,
+            None => return invalid("it has no scheme"),
+        };
+        let scheme = scheme.to_ascii_lowercase();
+        if !["http", "https", "udp"].contains(&scheme.as_str()) {
+            return invalid("its scheme is not http, https or udp");
+        }
+
+        // authority = host[:port], possibly followed by path/query/fragment
+        let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+        let (host, port) = if let Some(ipv6) = authority.strip_prefix('[') {
+            match ipv6.split_once(']') {
+                Some((host, "")) => (host, None),
+                Some((host, port)) => match port.strip_prefix(':') {
+                    Some(port) => (host, Some(port)),
+                    None => return invalid("its host is malformed"),
+                },
+                None => return invalid("its host is malformed"),
+            }
+        } else {
+            match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            }
+        };
+
+        if host.is_empty() {
+            return invalid("it has no host");
+        }
+        match port {
+            Some(port) => match port.parse::<u16>() {
+                Ok(port) if port > 0 => Ok(()),
+                _ => invalid("its port is invalid"),
+            },
+            None if scheme == "udp" => invalid("udp trackers require a port"),
+            None => Ok(()),
+        }
+    }
+
     fn validate_name(&self) -> Result<(), LavaTorrentError> {
         if let Some(ref name) = self.name {
             if name.is_empty() {
@@ -457,6 +1293,7 @@ impl TorrentBuilder {
         }
     }
 
+    #[cfg(feature = "fs")]
     fn validate_path(&self) -> Result<(), LavaTorrentError> {
         if self.path.exists() {
             Ok(())
@@ -516,138 +1353,200 @@ impl TorrentBuilder {
                 }
                 Ok(())
             }
-        } else {
-            Ok(())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn validate_web_seeds(&self) -> Result<(), LavaTorrentError> {
+        if let Some(ref web_seeds) = self.web_seeds {
+            if web_seeds.is_empty() {
+                return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                    "TorrentBuilder has `web_seeds` but it's empty.",
+                )));
+            } else if web_seeds.iter().any(String::is_empty) {
+                return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                    "TorrentBuilder has `web_seeds` but it contains a 0-length url.",
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_httpseeds(&self) -> Result<(), LavaTorrentError> {
+        if let Some(ref httpseeds) = self.httpseeds {
+            if httpseeds.is_empty() {
+                return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                    "TorrentBuilder has `httpseeds` but it's empty.",
+                )));
+            } else if httpseeds.iter().any(String::is_empty) {
+                return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                    "TorrentBuilder has `httpseeds` but it contains a 0-length url.",
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_nodes(&self) -> Result<(), LavaTorrentError> {
+        if let Some(ref nodes) = self.nodes {
+            if nodes.is_empty() {
+                return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                    "TorrentBuilder has `nodes` but it's empty.",
+                )));
+            } else if nodes.iter().any(|(host, _)| host.is_empty()) {
+                return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                    "TorrentBuilder has `nodes` but it contains a 0-length host.",
+                )));
+            }
         }
+        Ok(())
+    }
+
+    #[cfg(feature = "fs")]
+    fn read_file<P>(&self, path: P) -> Result<ReadFileOutput, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut pieces = Pieces::new();
+        let (length, sha1) = self.read_file_into(path, |piece| {
+            pieces.push(piece);
+            Ok(())
+        })?;
+        Ok((length, pieces, sha1))
     }
 
-    fn read_file<P>(
+    // like `read_file()`, but each piece's hash is passed to `push_piece()`
+    // (in order) instead of being collected
+    #[cfg(feature = "fs")]
+    pub(super) fn read_file_into<P, F>(
+        &self,
         path: P,
-        piece_length: Integer,
-    ) -> Result<(Integer, Vec<Piece>), LavaTorrentError>
+        mut push_piece: F,
+    ) -> Result<(Integer, Option<Vec<u8>>), LavaTorrentError>
     where
         P: AsRef<Path>,
+        F: FnMut(Piece) -> Result<(), LavaTorrentError>,
     {
         let path = path.as_ref();
-        let length = path.metadata()?.len();
-        let piece_length = util::i64_to_u64(piece_length)?;
+        let length = path
+            .metadata()
+            .map_err(util::file_io_err(path, None))?
+            .len();
+        if length == 0 && self.zero_length_file_policy != ZeroLengthFilePolicy::Include {
+            return Err(Self::zero_length_file_error(path));
+        }
 
         // read file content + calculate pieces/hashes
-        let mut file = BufReader::new(std::fs::File::open(path)?);
-        let mut piece = Vec::with_capacity(util::u64_to_usize(piece_length)?);
-        let mut pieces = Vec::with_capacity(util::u64_to_usize(length / piece_length + 1)?);
-        let mut total_read = 0;
-
-        while total_read < length {
-            let read = file.by_ref().take(piece_length).read_to_end(&mut piece)?;
-            total_read += util::usize_to_u64(read)?;
+        let mut file_hasher = if self.file_sha1 {
+            Some(Sha1::new())
+        } else {
+            None
+        };
 
-            pieces.push(Sha1::digest(&piece).to_vec());
-            piece.clear();
-        }
+        self.read_ahead(
+            &[(path.to_path_buf(), length)],
+            util::i64_to_usize(self.piece_length)?,
+            |piece| {
+                if let Some(ref mut hasher) = file_hasher {
+                    hasher.update(&piece.bytes);
+                }
+                push_piece(Sha1::digest(&piece.bytes).into())
+            },
+        )?;
 
-        Ok((util::u64_to_i64(length)?, pieces))
+        Ok((
+            util::u64_to_i64(length)?,
+            file_hasher.map(|hasher| hasher.finalize().to_vec()),
+        ))
     }
 
+    #[cfg(feature = "parallel")]
     fn read_file_parallel<P>(
+        &self,
         path: P,
-        piece_length: Integer,
-        num_threads: usize,
-    ) -> Result<(Integer, Vec<Piece>), LavaTorrentError>
+        thread_pool: &rayon::ThreadPool,
+    ) -> Result<ReadFileOutput, LavaTorrentError>
     where
         P: AsRef<Path>,
     {
         let path = path.as_ref();
-        let length = path.metadata()?.len();
-        let piece_length_u64 = util::i64_to_u64(piece_length)?;
+        let length = path
+            .metadata()
+            .map_err(util::file_io_err(path, None))?
+            .len();
+        if length == 0 && self.zero_length_file_policy != ZeroLengthFilePolicy::Include {
+            return Err(Self::zero_length_file_error(path));
+        }
+        let piece_length_u64 = util::i64_to_u64(self.piece_length)?;
         let piece_length_usize = util::u64_to_usize(piece_length_u64)?;
         let n_pieces = (length + (piece_length_u64 - 1)) / piece_length_u64;
 
-        let thread_pool = rayon::ThreadPoolBuilder::new()
-            .num_threads(num_threads)
-            .build()
-            .map_err(|e| {
-                LavaTorrentError::TorrentBuilderFailure(Cow::Owned(format!(
-                    "failed to create rayon thread pool: {}",
-                    e
-                )))
-            })?;
-
-        let pieces = thread_pool.install(|| {
+        let holes = Holes::default();
+        let pieces = self.install(thread_pool, || {
             (0_u64..n_pieces)
                 .into_par_iter()
                 .map(|i| {
-                    let mut file = std::fs::File::open(path)?;
+                    let mut file = SparseFile::open_with_holes(path, &holes)
+                        .map_err(util::file_io_err(path, None))?;
                     let mut piece = Vec::with_capacity(piece_length_usize);
-                    file.seek(std::io::SeekFrom::Start(i * piece_length_u64))?;
-                    file.take(piece_length_u64).read_to_end(&mut piece)?;
-                    Ok(Sha1::digest(&piece).to_vec())
+                    let offset = i * piece_length_u64;
+                    let to_read = std::cmp::min(piece_length_u64, length - offset);
+                    file.seek(std::io::SeekFrom::Start(offset))
+                        .map_err(util::file_io_err(path, Some(offset)))?;
+                    self.read_chunk(&mut file, path, offset, to_read, &mut piece)?;
+                    Ok(Sha1::digest(&piece).into())
                 })
-                .collect::<Result<Vec<Vec<u8>>, LavaTorrentError>>()
+                .collect::<Result<Pieces, LavaTorrentError>>()
         })?;
 
-        Ok((util::u64_to_i64(length)?, pieces))
+        // per-file hashes are not computed in parallel, see `resolve_num_threads()`
+        Ok((util::u64_to_i64(length)?, pieces, None))
+    }
+
+    #[cfg(feature = "fs")]
+    fn read_dir<P>(&self, path: P) -> Result<(Integer, Vec<File>, Pieces), LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut pieces = Pieces::new();
+        let (length, files) = self.read_dir_into(path, |piece| {
+            pieces.push(piece);
+            Ok(())
+        })?;
+        Ok((length, files, pieces))
     }
 
-    fn read_dir<P>(
+    // like `read_dir()`, but each piece's hash is passed to `push_piece()`
+    // (in order) instead of being collected
+    #[cfg(feature = "fs")]
+    pub(super) fn read_dir_into<P, F>(
+        &self,
         path: P,
-        piece_length: Integer,
-    ) -> Result<(Integer, Vec<File>, Vec<Piece>), LavaTorrentError>
+        mut push_piece: F,
+    ) -> Result<(Integer, Vec<File>), LavaTorrentError>
     where
         P: AsRef<Path>,
+        F: FnMut(Piece) -> Result<(), LavaTorrentError>,
     {
-        let piece_length_u64 = util::i64_to_u64(piece_length)?;
-        let piece_length_usize = util::i64_to_usize(piece_length)?;
-        let entries = util::list_dir(&path)?;
+        let entries = self.list_dir(&path)?;
         let total_length = entries.iter().fold(0, |acc, &(_, len)| acc + len);
-        let mut files = Vec::with_capacity(entries.len());
-        let mut piece = Vec::with_capacity(piece_length_usize);
-        let mut pieces =
-            Vec::with_capacity(util::u64_to_usize(total_length / piece_length_u64 + 1)?);
-
-        for (entry_path, length) in entries {
-            let mut file = BufReader::new(std::fs::File::open(&entry_path)?);
-            let mut file_remaining = length;
-
-            while file_remaining > 0 {
-                // calculate the # of bytes to read in this iteration
-                let piece_filled = util::usize_to_u64(piece.len())?;
-                let piece_remaining = piece_length_u64 - piece_filled;
-                let to_read = if file_remaining < piece_remaining {
-                    file_remaining
-                } else {
-                    piece_remaining
-                };
-
-                // read bytes
-                file.by_ref().take(to_read).read_to_end(&mut piece)?;
-                file_remaining -= to_read;
-
-                // if piece is completely filled, hash it
-                if piece.len() == piece_length_usize {
-                    pieces.push(Sha1::digest(&piece).to_vec());
-                    piece.clear();
+        let mut file_hashers: Vec<_> = entries
+            .iter()
+            .map(|_| self.file_sha1.then(Sha1::new))
+            .collect();
+
+        self.read_ahead(&entries, util::i64_to_usize(self.piece_length)?, |piece| {
+            for segment in &piece.segments {
+                if let Some(ref mut hasher) = file_hashers[segment.file] {
+                    hasher.update(&piece.bytes[segment.range.clone()]);
                 }
             }
+            push_piece(Sha1::digest(&piece.bytes).into())
+        })?;
 
-            // Unwrap is fine here since path is by definition
-            // a parent to entry_path and path is canonicalized
-            // before this call. Thus this should never fail.
-            files.push(File {
-                length: util::u64_to_i64(length)?,
-                path: entry_path.strip_prefix(&path).unwrap().to_path_buf(),
-                extra_fields: None,
-            });
-        }
-
-        // if piece is empty then the total file size is divisible by the piece length
-        // otherwise the last piece is partially filled and we have to hash it
-        if !piece.is_empty() {
-            pieces.push(Sha1::digest(&piece).to_vec());
-            piece.clear();
-        }
-
-        Ok((util::u64_to_i64(total_length)?, files, pieces))
+        let files = Self::dir_files(&path, entries, file_hashers)?;
+        Ok((util::u64_to_i64(total_length)?, files))
     }
 
     // To parallelize read_dir(), we first find the chunk(s) of file(s) that belong to
@@ -660,17 +1559,18 @@ impl TorrentBuilder {
     //
     // @todo: The current implementation is not very memory efficient for a large dir.
     // In the future it might be wise to switch to an iterator-based implementation.
+    #[cfg(feature = "parallel")]
     fn read_dir_parallel<P>(
+        &self,
         path: P,
-        piece_length: Integer,
-        num_threads: usize,
-    ) -> Result<(Integer, Vec<File>, Vec<Piece>), LavaTorrentError>
+        thread_pool: &rayon::ThreadPool,
+    ) -> Result<(Integer, Vec<File>, Pieces), LavaTorrentError>
     where
         P: AsRef<Path>,
     {
-        let piece_length_u64 = util::i64_to_u64(piece_length)?;
+        let piece_length_u64 = util::i64_to_u64(self.piece_length)?;
         let piece_length_usize = util::u64_to_usize(piece_length_u64)?;
-        let entries = util::list_dir(&path)?;
+        let entries = self.list_dir(&path)?;
         let total_length = entries.iter().fold(0, |acc, &(_, len)| acc + len);
         let n_pieces = (total_length + (piece_length_u64 - 1)) / piece_length_u64;
         let mut pieces = vec![vec![]; util::u64_to_usize(n_pieces)?];
@@ -683,6 +1583,7 @@ impl TorrentBuilder {
 
         for (entry_path, length) in entries {
             let entry_path = Arc::new(entry_path);
+            let holes = Arc::new(Holes::default());
             let mut file_remaining = length;
 
             while file_remaining > 0 {
@@ -699,8 +1600,13 @@ impl TorrentBuilder {
                     piece_remaining
                 };
 
-                // save chunk as (file path, start pos in file, chunk length)
-                piece.push((entry_path.clone(), length - file_remaining, to_allocate));
+                // save chunk as (file path, file holes, start pos in file, chunk length)
+                piece.push((
+                    entry_path.clone(),
+                    holes.clone(),
+                    length - file_remaining,
+                    to_allocate,
+                ));
 
                 // update counters
                 piece_remaining -= to_allocate;
@@ -714,103 +1620,120 @@ impl TorrentBuilder {
                 length: util::u64_to_i64(length)?,
                 path: entry_path.strip_prefix(&path).unwrap().to_path_buf(),
                 extra_fields: None,
+                raw_extra_fields: None,
             });
         }
 
         // hash the pieces
-        let thread_pool = rayon::ThreadPoolBuilder::new()
-            .num_threads(num_threads)
-            .build()
-            .map_err(|e| {
-                LavaTorrentError::TorrentBuilderFailure(Cow::Owned(format!(
-                    "failed to create rayon thread pool: {}",
-                    e
-                )))
-            })?;
-
-        let pieces = thread_pool.install(|| {
+        let pieces = self.install(thread_pool, || {
             pieces
                 .into_par_iter()
                 .map(|chunks| {
                     let mut bytes = Vec::with_capacity(piece_length_usize);
-                    for (file, offset, len) in chunks {
-                        let mut file = std::fs::File::open(file.as_ref())?;
-                        file.seek(std::io::SeekFrom::Start(offset))?;
-                        file.take(len).read_to_end(&mut bytes)?;
+                    for (path, holes, offset, len) in chunks {
+                        let mut file = SparseFile::open_with_holes(path.as_ref(), &holes)
+                            .map_err(util::file_io_err(&path, None))?;
+                        file.seek(std::io::SeekFrom::Start(offset))
+                            .map_err(util::file_io_err(&path, Some(offset)))?;
+                        self.read_chunk(&mut file, &path, offset, len, &mut bytes)?;
                     }
-                    Ok(Sha1::digest(&bytes).to_vec())
+                    Ok(Sha1::digest(&bytes).into())
                 })
-                .collect::<Result<Vec<Vec<u8>>, LavaTorrentError>>()
+                .collect::<Result<Pieces, LavaTorrentError>>()
         })?;
 
         Ok((util::u64_to_i64(total_length)?, files, pieces))
     }
 
+    #[cfg(feature = "fs")]
     fn read_file_non_blocking<P>(
+        &self,
         path: P,
-        piece_length: Integer,
         torrent_build: TorrentBuildInternal,
-    ) -> Result<(Integer, Vec<Piece>), LavaTorrentError>
+    ) -> Result<ReadFileOutput, LavaTorrentError>
     where
         P: AsRef<Path>,
     {
         let path = path.as_ref();
-        let length = path.metadata()?.len();
-        let piece_length = util::i64_to_u64(piece_length)?;
+        let length = path
+            .metadata()
+            .map_err(util::file_io_err(path, None))?
+            .len();
+        if length == 0 && self.zero_length_file_policy != ZeroLengthFilePolicy::Include {
+            return Err(Self::zero_length_file_error(path));
+        }
+        let piece_length = util::i64_to_u64(self.piece_length)?;
         let n_pieces = (length + (piece_length - 1)) / piece_length;
         torrent_build.set_piece_total(n_pieces);
+        torrent_build.set_files(vec![(path.to_path_buf(), length)]);
 
         // read file content + calculate pieces/hashes
-        let mut file = BufReader::new(std::fs::File::open(path)?);
-        let mut piece = Vec::with_capacity(util::u64_to_usize(piece_length)?);
-        let mut pieces = Vec::with_capacity(util::u64_to_usize(n_pieces)?);
-        let mut total_read = 0;
-
-        while total_read < length {
-            if torrent_build.is_canceled() {
-                return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
-                    "build canceled by client",
-                )));
-            }
+        let mut pieces = Pieces::with_capacity(util::u64_to_usize(n_pieces)?);
+        let mut file_hasher = if self.file_sha1 {
+            Some(Sha1::new())
+        } else {
+            None
+        };
 
-            let read = file.by_ref().take(piece_length).read_to_end(&mut piece)?;
-            total_read += util::usize_to_u64(read)?;
+        self.read_ahead(
+            &[(path.to_path_buf(), length)],
+            util::u64_to_usize(piece_length)?,
+            |piece| {
+                if torrent_build.is_canceled() {
+                    return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                        "build canceled by client",
+                    )));
+                }
+                if pieces.is_empty() {
+                    torrent_build.start_file(0);
+                }
 
-            pieces.push(Sha1::digest(&piece).to_vec());
-            piece.clear();
-            torrent_build.inc_piece_processed();
-        }
+                if let Some(ref mut hasher) = file_hasher {
+                    hasher.update(&piece.bytes);
+                }
+                pieces.push(Sha1::digest(&piece.bytes).into());
+                torrent_build.add_file_bytes(0, util::usize_to_u64(piece.bytes.len())?);
+                torrent_build.inc_piece_processed();
+                Ok(())
+            },
+        )?;
 
-        Ok((util::u64_to_i64(length)?, pieces))
+        Ok((
+            util::u64_to_i64(length)?,
+            pieces,
+            file_hasher.map(|hasher| hasher.finalize().to_vec()),
+        ))
     }
 
+    #[cfg(feature = "parallel")]
     fn read_file_parallel_non_blocking<P>(
+        &self,
         path: P,
-        piece_length: Integer,
-        num_threads: usize,
+        thread_pool: &rayon::ThreadPool,
         torrent_build: TorrentBuildInternal,
-    ) -> Result<(Integer, Vec<Piece>), LavaTorrentError>
+    ) -> Result<ReadFileOutput, LavaTorrentError>
     where
         P: AsRef<Path>,
     {
         let path = path.as_ref();
-        let length = path.metadata()?.len();
-        let piece_length_u64 = util::i64_to_u64(piece_length)?;
+        let length = path
+            .metadata()
+            .map_err(util::file_io_err(path, None))?
+            .len();
+        if length == 0 && self.zero_length_file_policy != ZeroLengthFilePolicy::Include {
+            return Err(Self::zero_length_file_error(path));
+        }
+        let piece_length_u64 = util::i64_to_u64(self.piece_length)?;
         let piece_length_usize = util::u64_to_usize(piece_length_u64)?;
         let n_pieces = (length + (piece_length_u64 - 1)) / piece_length_u64;
         torrent_build.set_piece_total(n_pieces);
+        torrent_build.set_files(vec![(path.to_path_buf(), length)]);
+        if length > 0 {
+            torrent_build.start_file(0);
+        }
 
-        let thread_pool = rayon::ThreadPoolBuilder::new()
-            .num_threads(num_threads)
-            .build()
-            .map_err(|e| {
-                LavaTorrentError::TorrentBuilderFailure(Cow::Owned(format!(
-                    "failed to create rayon thread pool: {}",
-                    e
-                )))
-            })?;
-
-        let pieces = thread_pool.install(|| {
+        let holes = Holes::default();
+        let pieces = self.install(thread_pool, || {
             (0_u64..n_pieces)
                 .into_par_iter()
                 .map(|i| {
@@ -819,116 +1742,132 @@ impl TorrentBuilder {
                             "build canceled by client",
                         )))
                     } else {
-                        let mut file = std::fs::File::open(path)?;
+                        let mut file = SparseFile::open_with_holes(path, &holes)
+                            .map_err(util::file_io_err(path, None))?;
                         let mut piece = Vec::with_capacity(piece_length_usize);
-                        file.seek(std::io::SeekFrom::Start(i * piece_length_u64))?;
-                        file.take(piece_length_u64).read_to_end(&mut piece)?;
+                        let offset = i * piece_length_u64;
+                        let to_read = std::cmp::min(piece_length_u64, length - offset);
+                        file.seek(std::io::SeekFrom::Start(offset))
+                            .map_err(util::file_io_err(path, Some(offset)))?;
+                        self.read_chunk(&mut file, path, offset, to_read, &mut piece)?;
+                        torrent_build.add_file_bytes(0, to_read);
                         torrent_build.inc_piece_processed();
-                        Ok(Sha1::digest(&piece).to_vec())
+                        Ok(Sha1::digest(&piece).into())
                     }
                 })
-                .collect::<Result<Vec<Vec<u8>>, LavaTorrentError>>()
+                .collect::<Result<Pieces, LavaTorrentError>>()
         })?;
 
-        Ok((util::u64_to_i64(length)?, pieces))
+        // per-file hashes are not computed in parallel, see `resolve_num_threads()`
+        Ok((util::u64_to_i64(length)?, pieces, None))
     }
 
+    #[cfg(feature = "fs")]
     fn read_dir_non_blocking<P>(
+        &self,
         path: P,
-        piece_length: Integer,
         torrent_build: TorrentBuildInternal,
-    ) -> Result<(Integer, Vec<File>, Vec<Piece>), LavaTorrentError>
+    ) -> Result<(Integer, Vec<File>, Pieces), LavaTorrentError>
     where
         P: AsRef<Path>,
     {
-        let piece_length_u64 = util::i64_to_u64(piece_length)?;
-        let piece_length_usize = util::i64_to_usize(piece_length)?;
-        let entries = util::list_dir(&path)?;
+        let piece_length_u64 = util::i64_to_u64(self.piece_length)?;
+        let piece_length_usize = util::i64_to_usize(self.piece_length)?;
+        let entries = self.list_dir(&path)?;
         let total_length = entries.iter().fold(0, |acc, &(_, len)| acc + len);
         let n_pieces = (total_length + (piece_length_u64 - 1)) / piece_length_u64;
-        let mut files = Vec::with_capacity(entries.len());
-        let mut piece = Vec::with_capacity(piece_length_usize);
-        let mut pieces = Vec::with_capacity(util::u64_to_usize(n_pieces)?);
+        let mut pieces = Pieces::with_capacity(util::u64_to_usize(n_pieces)?);
+        let mut file_hashers: Vec<_> = entries
+            .iter()
+            .map(|_| self.file_sha1.then(Sha1::new))
+            .collect();
+        let mut current_file = None;
         torrent_build.set_piece_total(n_pieces);
+        torrent_build.set_files(entries.clone());
 
-        for (entry_path, length) in entries {
-            let mut file = BufReader::new(std::fs::File::open(&entry_path)?);
-            let mut file_remaining = length;
+        self.read_ahead(&entries, piece_length_usize, |piece| {
+            if torrent_build.is_canceled() {
+                return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                    "build canceled by client",
+                )));
+            }
 
-            while file_remaining > 0 {
-                if torrent_build.is_canceled() {
-                    return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
-                        "build canceled by client",
-                    )));
+            for segment in &piece.segments {
+                if current_file != Some(segment.file) {
+                    current_file = Some(segment.file);
+                    torrent_build.start_file(segment.file);
                 }
-
-                // calculate the # of bytes to read in this iteration
-                let piece_filled = util::usize_to_u64(piece.len())?;
-                let piece_remaining = piece_length_u64 - piece_filled;
-                let to_read = if file_remaining < piece_remaining {
-                    file_remaining
-                } else {
-                    piece_remaining
-                };
-
-                // read bytes
-                file.by_ref().take(to_read).read_to_end(&mut piece)?;
-                file_remaining -= to_read;
-
-                // if piece is completely filled, hash it
-                if piece.len() == piece_length_usize {
-                    pieces.push(Sha1::digest(&piece).to_vec());
-                    piece.clear();
-                    torrent_build.inc_piece_processed();
+                if let Some(ref mut hasher) = file_hashers[segment.file] {
+                    hasher.update(&piece.bytes[segment.range.clone()]);
                 }
+                torrent_build
+                    .add_file_bytes(segment.file, util::usize_to_u64(segment.range.len())?);
             }
-
-            // Unwrap is fine here since path is by definition
-            // a parent to entry_path and path is canonicalized
-            // before this call. Thus this should never fail.
-            files.push(File {
-                length: util::u64_to_i64(length)?,
-                path: entry_path.strip_prefix(&path).unwrap().to_path_buf(),
-                extra_fields: None,
-            });
-        }
-
-        // if piece is empty then the total file size is divisible by the piece length
-        // otherwise the last piece is partially filled and we have to hash it
-        if !piece.is_empty() {
-            pieces.push(Sha1::digest(&piece).to_vec());
-            piece.clear();
+            pieces.push(Sha1::digest(&piece.bytes).into());
             torrent_build.inc_piece_processed();
-        }
+            Ok(())
+        })?;
 
+        let files = Self::dir_files(&path, entries, file_hashers)?;
         Ok((util::u64_to_i64(total_length)?, files, pieces))
     }
 
+    // Turn the entries of the dir at `path` into `File`s.
+    #[cfg(feature = "fs")]
+    fn dir_files<P>(
+        path: P,
+        entries: Vec<(PathBuf, u64)>,
+        file_hashers: Vec<Option<Sha1>>,
+    ) -> Result<Vec<File>, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        entries
+            .into_iter()
+            .zip(file_hashers)
+            .map(|((entry_path, length), file_hasher)| {
+                // Unwrap is fine here since path is by definition
+                // a parent to entry_path and path is canonicalized
+                // before this call. Thus this should never fail.
+                Ok(File {
+                    length: util::u64_to_i64(length)?,
+                    path: entry_path.strip_prefix(&path).unwrap().to_path_buf(),
+                    extra_fields: file_hasher
+                        .map(|hasher| Self::file_sha1_field(hasher.finalize().to_vec())),
+                    raw_extra_fields: None,
+                })
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "parallel")]
     fn read_dir_parallel_non_blocking<P>(
+        &self,
         path: P,
-        piece_length: Integer,
-        num_threads: usize,
+        thread_pool: &rayon::ThreadPool,
         torrent_build: TorrentBuildInternal,
-    ) -> Result<(Integer, Vec<File>, Vec<Piece>), LavaTorrentError>
+    ) -> Result<(Integer, Vec<File>, Pieces), LavaTorrentError>
     where
         P: AsRef<Path>,
     {
-        let piece_length_u64 = util::i64_to_u64(piece_length)?;
+        let piece_length_u64 = util::i64_to_u64(self.piece_length)?;
         let piece_length_usize = util::u64_to_usize(piece_length_u64)?;
-        let entries = util::list_dir(&path)?;
+        let entries = self.list_dir(&path)?;
         let total_length = entries.iter().fold(0, |acc, &(_, len)| acc + len);
         let n_pieces = (total_length + (piece_length_u64 - 1)) / piece_length_u64;
         let mut pieces = vec![vec![]; util::u64_to_usize(n_pieces)?];
         let mut files = Vec::with_capacity(entries.len());
         torrent_build.set_piece_total(n_pieces);
+        torrent_build.set_files(entries.clone());
 
         // find each piece's chunks
         let mut pieces_iter = pieces.iter_mut();
         let mut piece = pieces_iter.next().unwrap();
         let mut piece_remaining = piece_length_u64;
 
-        for (entry_path, length) in entries {
+        for (index, (entry_path, length)) in entries.into_iter().enumerate() {
             let entry_path = Arc::new(entry_path);
+            let holes = Arc::new(Holes::default());
             let mut file_remaining = length;
 
             while file_remaining > 0 {
@@ -951,8 +1890,14 @@ impl TorrentBuilder {
                     piece_remaining
                 };
 
-                // save chunk as (file path, start pos in file, chunk length)
-                piece.push((entry_path.clone(), length - file_remaining, to_allocate));
+                // save chunk as (file index, file path, file holes, start pos, chunk length)
+                piece.push((
+                    index,
+                    entry_path.clone(),
+                    holes.clone(),
+                    length - file_remaining,
+                    to_allocate,
+                ));
 
                 // update counters
                 piece_remaining -= to_allocate;
@@ -966,21 +1911,12 @@ impl TorrentBuilder {
                 length: util::u64_to_i64(length)?,
                 path: entry_path.strip_prefix(&path).unwrap().to_path_buf(),
                 extra_fields: None,
+                raw_extra_fields: None,
             });
         }
 
         // hash the pieces
-        let thread_pool = rayon::ThreadPoolBuilder::new()
-            .num_threads(num_threads)
-            .build()
-            .map_err(|e| {
-                LavaTorrentError::TorrentBuilderFailure(Cow::Owned(format!(
-                    "failed to create rayon thread pool: {}",
-                    e
-                )))
-            })?;
-
-        let pieces = thread_pool.install(|| {
+        let pieces = self.install(thread_pool, || {
             pieces
                 .into_par_iter()
                 .map(|chunks| {
@@ -990,22 +1926,196 @@ impl TorrentBuilder {
                         )))
                     } else {
                         let mut bytes = Vec::with_capacity(piece_length_usize);
-                        for (file, offset, len) in chunks {
-                            let mut file = std::fs::File::open(file.as_ref())?;
-                            file.seek(std::io::SeekFrom::Start(offset))?;
-                            file.take(len).read_to_end(&mut bytes)?;
+                        for (index, path, holes, offset, len) in chunks {
+                            if offset == 0 {
+                                torrent_build.start_file(index);
+                            }
+                            let mut file = SparseFile::open_with_holes(path.as_ref(), &holes)
+                                .map_err(util::file_io_err(&path, None))?;
+                            file.seek(std::io::SeekFrom::Start(offset))
+                                .map_err(util::file_io_err(&path, Some(offset)))?;
+                            self.read_chunk(&mut file, &path, offset, len, &mut bytes)?;
+                            torrent_build.add_file_bytes(index, len);
                         }
                         torrent_build.inc_piece_processed();
-                        Ok(Sha1::digest(&bytes).to_vec())
+                        Ok(Sha1::digest(&bytes).into())
                     }
                 })
-                .collect::<Result<Vec<Vec<u8>>, LavaTorrentError>>()
+                .collect::<Result<Pieces, LavaTorrentError>>()
         })?;
 
         Ok((util::u64_to_i64(total_length)?, files, pieces))
     }
+
+    // list files in `path` while applying `zero_length_file_policy`
+    #[cfg(feature = "fs")]
+    fn list_dir<P>(&self, path: P) -> Result<Vec<(PathBuf, u64)>, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut entries = util::list_dir(path)?;
+
+        match self.zero_length_file_policy {
+            ZeroLengthFilePolicy::Include => (),
+            ZeroLengthFilePolicy::Skip => entries.retain(|&(_, length)| length > 0),
+            ZeroLengthFilePolicy::Error => {
+                if let Some((path, _)) = entries.iter().find(|&&(_, length)| length == 0) {
+                    return Err(Self::zero_length_file_error(path));
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    // check `entries` (sorted by path) and apply `zero_length_file_policy` to them
+    fn validate_entries<B>(&self, entries: &mut Vec<(PathBuf, B)>) -> Result<(), LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        for (path, _) in entries.iter() {
+            let is_normal = |component| matches!(component, std::path::Component::Normal(_));
+            if path.as_os_str().is_empty() || !path.components().all(is_normal) {
+                return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Owned(
+                    format!("[{}] is not a valid relative path.", path.display()),
+                )));
+            }
+        }
+        if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Owned(
+                format!("[{}] appears more than once.", pair[0].0.display()),
+            )));
+        }
+
+        match self.zero_length_file_policy {
+            ZeroLengthFilePolicy::Include => (),
+            ZeroLengthFilePolicy::Skip => {
+                entries.retain(|(_, content)| !content.as_ref().is_empty())
+            }
+            ZeroLengthFilePolicy::Error => {
+                if let Some((path, _)) = entries
+                    .iter()
+                    .find(|(_, content)| content.as_ref().is_empty())
+                {
+                    return Err(Self::zero_length_file_error(path));
+                }
+            }
+        }
+
+        if entries.is_empty() {
+            Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                "TorrentBuilder has no entries to build from.",
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Hash `contents` as if they were concatenated. The SHA-1 hash of
+    // each content is returned as well if per-file hashes are requested.
+    fn hash_in_memory(&self, contents: &[&[u8]]) -> Result<HashInMemoryOutput, LavaTorrentError> {
+        let piece_length = util::i64_to_usize(self.piece_length)?;
+        let total_length = contents.iter().map(|content| content.len()).sum::<usize>();
+        let mut pieces = Pieces::with_capacity(total_length / piece_length + 1);
+        let mut sha1s = Vec::with_capacity(contents.len());
+        let mut hasher = Sha1::new();
+        let mut piece_filled = 0;
+
+        for content in contents {
+            let mut remaining = *content;
+            while !remaining.is_empty() {
+                let (chunk, rest) =
+                    remaining.split_at(std::cmp::min(piece_length - piece_filled, remaining.len()));
+                hasher.update(chunk);
+                piece_filled += chunk.len();
+                remaining = rest;
+
+                if piece_filled == piece_length {
+                    pieces.push(hasher.finalize_reset().into());
+                    piece_filled = 0;
+                }
+            }
+
+            sha1s.push(if self.file_sha1 {
+                Some(Sha1::digest(content).to_vec())
+            } else {
+                None
+            });
+        }
+
+        // the last piece might be partially filled
+        if piece_filled > 0 {
+            pieces.push(hasher.finalize().into());
+        }
+
+        Ok((pieces, sha1s))
+    }
+
+    fn zero_length_file_error(path: &Path) -> LavaTorrentError {
+        LavaTorrentError::TorrentBuilderFailure(Cow::Owned(format!(
+            "[{}] is a zero-length file.",
+            path.display()
+        )))
+    }
+
+    #[cfg(feature = "rand")]
+    pub(super) fn entropy() -> String {
+        rand::thread_rng()
+            .gen::<[u8; 16]>()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    // Read exactly `len` bytes from `file` into `buf`. `file` is expected to
+    // be positioned at `offset` of the file at `path`, which are only used
+    // to report errors.
+    #[cfg(feature = "fs")]
+    pub(super) fn read_chunk<R>(
+        &self,
+        file: R,
+        path: &Path,
+        offset: u64,
+        len: u64,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), LavaTorrentError>
+    where
+        R: Read,
+    {
+        let read = match self.read_rate_limiter {
+            Some(ref limiter) => limiter.reader(file).take(len).read_to_end(buf),
+            None => file.take(len).read_to_end(buf),
+        }
+        .map_err(util::file_io_err(path, Some(offset)))?;
+        let read = util::usize_to_u64(read)?;
+
+        if read == len {
+            Ok(())
+        } else {
+            Err(Self::file_too_short_error(path, offset + read))
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    pub(super) fn file_too_short_error(path: &Path, offset: u64) -> LavaTorrentError {
+        LavaTorrentError::FileIo {
+            path: path.to_path_buf(),
+            offset: Some(offset),
+            source: std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "file is shorter than expected (was it modified during the build?)",
+            ),
+        }
+    }
+
+    fn file_sha1_field(sha1: Vec<u8>) -> Dictionary {
+        let mut extra_fields = HashMap::new();
+        extra_fields.insert("sha1".to_owned(), BencodeElem::Bytes(sha1));
+        extra_fields
+    }
 }
 
+#[cfg(feature = "fs")]
 impl TorrentBuild {
     /// Get the current progress of the torrent build.
     ///
@@ -1078,8 +2188,8 @@ impl TorrentBuild {
 
     /// Cancel the torrent build.
     ///
-    /// `cancel()` does not consume the `TorrentBuild`. If you want, you can call
-    /// [`drop()`] yourself.
+    /// `cancel()` does not consume the `TorrentBuild` and returns immediately.
+    /// Call [`drop()`] instead to also wait for the builder thread to stop.
     ///
     /// Calling [`get_output()`] after `cancel()` will most likely give you an
     /// [`Err(LavaTorrentError::TorrentBuilderFailure)`], but it's also possible
@@ -1107,15 +2217,164 @@ impl TorrentBuild {
         })?
     }
 
-    /// Check if the torrent build has finished.
-    pub fn is_finished(&self) -> bool {
-        self.builder_thread.as_ref().unwrap().is_finished()
+    /// Check if the torrent build has finished.
+    pub fn is_finished(&self) -> bool {
+        self.builder_thread.as_ref().unwrap().is_finished()
+    }
+
+    /// Get the path (on disk) of the file that is being read, if any.
+    ///
+    /// When hashing in parallel, multiple files might be read at the same
+    /// time, in which case the one that was started last is returned.
+    pub fn get_current_file(&self) -> Option<PathBuf> {
+        let build_files = self.files.lock().unwrap_or_else(PoisonError::into_inner);
+        build_files
+            .current
+            .map(|index| build_files.files[index].path.clone())
+    }
+
+    /// Get the progress of each file, in the same order as the
+    /// `Torrent`'s `files` (or a single file for a single-file torrent).
+    ///
+    /// An empty `Vec` is returned if the files have not been listed yet.
+    pub fn get_file_progress(&self) -> Vec<FileProgress> {
+        self.files
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .files
+            .clone()
+    }
+
+    /// Get a receiver of the build's [`BuildEvent`]s, which is an
+    /// alternative to polling [`get_progress()`] (e.g. in event loops).
+    ///
+    /// Events that occurred before this call are not delivered. Calling this
+    /// again replaces the previous receiver, which is then disconnected.
+    /// The receiver is also disconnected after [`BuildEvent::Finished`] or
+    /// [`BuildEvent::Failed`], or right away if the build has already finished.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use lava_torrent::torrent::v1::{BuildEvent, TorrentBuilder};
+    ///
+    /// let build = TorrentBuilder::new("dir/", 1048576).build_non_blocking().unwrap();
+    ///
+    /// for event in build.progress_receiver() {
+    ///     match event {
+    ///         BuildEvent::FileStarted { path } => println!("hashing {}", path.display()),
+    ///         BuildEvent::PieceHashed {
+    ///             n_piece_processed,
+    ///             n_piece_total,
+    ///         } => println!("{}/{} pieces", n_piece_processed, n_piece_total),
+    ///         _ => (),
+    ///     }
+    /// }
+    ///
+    /// let torrent = build.get_output().unwrap();
+    /// ```
+    ///
+    /// [`BuildEvent`]: enum.BuildEvent.html
+    /// [`BuildEvent::Finished`]: enum.BuildEvent.html#variant.Finished
+    /// [`BuildEvent::Failed`]: enum.BuildEvent.html#variant.Failed
+    /// [`get_progress()`]: #method.get_progress
+    pub fn progress_receiver(&self) -> Receiver<BuildEvent> {
+        let (sender, receiver) = mpsc::channel();
+        let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        if !matches!(*events, BuildEventSender::Closed) {
+            *events = BuildEventSender::Connected(sender);
+        }
+        receiver
+    }
+}
+
+#[cfg(feature = "fs")]
+impl FileProgress {
+    /// Check if the file has been read completely.
+    /// This is always `true` for zero-length files.
+    pub fn is_finished(&self) -> bool {
+        self.n_bytes_processed >= self.length
+    }
+}
+
+#[cfg(feature = "fs")]
+impl TorrentBuildInternal {
+    fn inc_piece_processed(&self) {
+        let n_piece_processed = self.n_piece_processed.fetch_add(1, Ordering::AcqRel) + 1;
+        self.send_event(|| BuildEvent::PieceHashed {
+            n_piece_processed,
+            n_piece_total: self.n_piece_total.load(Ordering::Acquire),
+        });
+    }
+
+    // to be called once the files to be read are known
+    fn set_files(&self, files: Vec<(PathBuf, u64)>) {
+        let mut build_files = self.files.lock().unwrap_or_else(PoisonError::into_inner);
+        build_files.files = files
+            .into_iter()
+            .map(|(path, length)| FileProgress {
+                path,
+                length,
+                n_bytes_processed: 0,
+            })
+            .collect();
+    }
+
+    fn start_file(&self, index: usize) {
+        let path = {
+            let mut build_files = self.files.lock().unwrap_or_else(PoisonError::into_inner);
+            build_files.current = Some(index);
+            build_files.files[index].path.clone()
+        };
+        self.send_event(|| BuildEvent::FileStarted { path });
+    }
+
+    fn add_file_bytes(&self, index: usize, n_bytes: u64) {
+        let finished = {
+            let mut build_files = self.files.lock().unwrap_or_else(PoisonError::into_inner);
+            let file = &mut build_files.files[index];
+            file.n_bytes_processed += n_bytes;
+
+            if file.is_finished() {
+                let path = file.path.clone();
+                if build_files.current == Some(index) {
+                    build_files.current = None;
+                }
+                Some(path)
+            } else {
+                None
+            }
+        };
+
+        if let Some(path) = finished {
+            self.send_event(|| BuildEvent::FileFinished { path });
+        }
+    }
+
+    // `event` is only created if someone is listening
+    fn send_event<F>(&self, event: F)
+    where
+        F: FnOnce() -> BuildEvent,
+    {
+        let events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        if let BuildEventSender::Connected(ref sender) = *events {
+            // the receiver might have been dropped, which is fine
+            let _ = sender.send(event());
+        }
     }
-}
 
-impl TorrentBuildInternal {
-    fn inc_piece_processed(&self) {
-        self.n_piece_processed.fetch_add(1, Ordering::AcqRel);
+    // send the final event and disconnect the receiver
+    fn finish(&self, output: &Result<Torrent, LavaTorrentError>) {
+        let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        if let BuildEventSender::Connected(ref sender) = *events {
+            let _ = sender.send(match output {
+                Ok(_) => BuildEvent::Finished,
+                Err(e) => BuildEvent::Failed {
+                    message: e.to_string(),
+                },
+            });
+        }
+        *events = BuildEventSender::Closed;
     }
 
     fn set_piece_total(&self, total: u64) {
@@ -1127,9 +2386,16 @@ impl TorrentBuildInternal {
     }
 }
 
+#[cfg(feature = "fs")]
 impl Drop for TorrentBuild {
+    // cancel and wait for the builder thread, so that it doesn't
+    // keep reading and hashing after the handle is gone
     fn drop(&mut self) {
-        self.cancel()
+        self.cancel();
+        if let Some(builder_thread) = self.builder_thread.take() {
+            // the output of a dropped build is of no interest
+            let _ = builder_thread.join();
+        }
     }
 }
 
@@ -1211,6 +2477,112 @@ mod torrent_builder_tests {
         );
     }
 
+    #[test]
+    fn add_tracker_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.add_tracker("url1".to_owned());
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                announce: Some("url1".to_owned()),
+                announce_list: Some(vec![vec!["url1".to_owned()]]),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder
+            .add_tracker("url2".to_owned())
+            .add_tracker("url1".to_owned());
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                announce: Some("url1".to_owned()),
+                announce_list: Some(vec![vec!["url1".to_owned()], vec!["url2".to_owned()]]),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn add_tracker_with_announce() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .set_announce(Some("url1".to_owned()))
+            .add_tracker("url2".to_owned());
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                announce: Some("url1".to_owned()),
+                announce_list: Some(vec![vec!["url1".to_owned()], vec!["url2".to_owned()]]),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn add_tracker_to_tier_ok() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .add_tracker_to_tier(0, "url1".to_owned())
+            .add_tracker_to_tier(0, "url2".to_owned())
+            .add_tracker_to_tier(1, "url3".to_owned());
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                announce: Some("url1".to_owned()),
+                announce_list: Some(vec![
+                    vec!["url1".to_owned(), "url2".to_owned()],
+                    vec!["url3".to_owned()],
+                ]),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn add_tracker_to_tier_out_of_range() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .add_tracker_to_tier(2, "url1".to_owned())
+            .add_tracker_to_tier(usize::MAX, "url2".to_owned());
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                announce: Some("url1".to_owned()),
+                announce_list: Some(vec![vec!["url1".to_owned()], vec!["url2".to_owned()]]),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+        assert!(builder.validate_announce_list().is_ok());
+    }
+
+    #[test]
+    fn add_tracker_to_tier_duplicate() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .add_tracker_to_tier(0, "url1".to_owned())
+            .add_tracker_to_tier(1, "url2".to_owned())
+            .add_tracker_to_tier(0, "url2".to_owned())
+            .add_tracker_to_tier(1, "url1".to_owned());
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                announce: Some("url1".to_owned()),
+                announce_list: Some(vec![vec!["url1".to_owned()], vec!["url2".to_owned()]]),
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
     #[test]
     fn set_name_ok() {
         let builder = TorrentBuilder::new("dir/", 42);
@@ -1359,26 +2731,285 @@ mod torrent_builder_tests {
     }
 
     #[test]
-    fn set_privacy_ok() {
+    fn set_privacy_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_privacy(true);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                is_private: true,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_privacy(false);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn set_entropy_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_entropy(true);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                has_entropy: true,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_entropy(false);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn validate_entries_invalid_path() {
+        let builder = TorrentBuilder::new("dir", 64);
+
+        for path in ["", "../a", "/a", "./a"] {
+            match builder.validate_entries(&mut vec![(PathBuf::from(path), "content")]) {
+                Err(LavaTorrentError::TorrentBuilderFailure(m)) => {
+                    assert_eq!(m, format!("[{}] is not a valid relative path.", path))
+                }
+                _ => panic!(),
+            }
+        }
+    }
+
+    #[test]
+    fn validate_entries_duplicate() {
+        let mut entries = vec![
+            (PathBuf::from("a"), "1"),
+            (PathBuf::from("b"), "2"),
+            (PathBuf::from("b"), "3"),
+        ];
+
+        match TorrentBuilder::new("dir", 64).validate_entries(&mut entries) {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => {
+                assert_eq!(m, "[b] appears more than once.")
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn validate_entries_empty() {
+        let builder =
+            TorrentBuilder::new("dir", 64).set_zero_length_file_policy(ZeroLengthFilePolicy::Skip);
+
+        match builder.validate_entries(&mut vec![(PathBuf::from("a"), "")]) {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => {
+                assert_eq!(m, "TorrentBuilder has no entries to build from.")
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn hash_in_memory_ok() {
+        let (pieces, sha1s) = TorrentBuilder::new("dir", 4)
+            .set_file_sha1(true)
+            .hash_in_memory(&[b"abc", b"", b"defgh"])
+            .unwrap();
+
+        assert_eq!(
+            pieces,
+            Pieces::from(vec![
+                Sha1::digest(b"abcd").into(),
+                Sha1::digest(b"efgh").into(),
+            ])
+        );
+        assert_eq!(
+            sha1s,
+            vec![
+                Some(Sha1::digest(b"abc").to_vec()),
+                Some(Sha1::digest(b"").to_vec()),
+                Some(Sha1::digest(b"defgh").to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn set_merkle_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_merkle(true);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                merkle: true,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_merkle(false);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn entropy_ok() {
+        let entropy = TorrentBuilder::entropy();
+        assert_eq!(entropy.len(), 32);
+        assert!(entropy.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(entropy, TorrentBuilder::entropy());
+    }
+
+    #[test]
+    fn set_zero_length_file_policy_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_zero_length_file_policy(ZeroLengthFilePolicy::Skip);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                zero_length_file_policy: ZeroLengthFilePolicy::Skip,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_zero_length_file_policy(ZeroLengthFilePolicy::Include);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn set_max_read_rate_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_max_read_rate(1024);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                read_rate_limiter: Some(util::RateLimiter::new(1024)),
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_max_read_rate(0);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn set_low_priority_io_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_low_priority_io(true);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                low_priority_io: true,
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_low_priority_io(false);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "parallel", target_os = "linux"))]
+    fn install_low_priority_io() {
+        // the IO scheduling class of the current thread (3 = idle)
+        fn ioprio_class() -> libc::c_long {
+            unsafe { libc::syscall(libc::SYS_ioprio_get, 1, 0) >> 13 }
+        }
+        let thread_pool = TorrentBuilder::rayon_thread_pool(2).unwrap();
+        let builder = TorrentBuilder::new("dir/", 42).set_low_priority_io(true);
+
+        let classes = builder.install(&thread_pool, || {
+            (0..16)
+                .into_par_iter()
+                .map(|_| ioprio_class())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(classes, vec![3; 16]);
+        assert!(thread_pool
+            .broadcast(|_| ioprio_class())
+            .into_iter()
+            .all(|class| class != 3));
+    }
+
+    #[test]
+    fn set_web_seeds_ok() {
         let builder = TorrentBuilder::new("dir/", 42);
 
-        let builder = builder.set_privacy(true);
+        let builder = builder.set_web_seeds(vec!["url1".to_owned()]);
         assert_eq!(
             builder,
             TorrentBuilder {
                 path: PathBuf::from("dir"),
                 piece_length: 42,
-                is_private: true,
+                web_seeds: Some(vec!["url1".to_owned()]),
                 ..Default::default()
             }
         );
 
-        let builder = builder.set_privacy(false);
+        let builder = builder.set_web_seeds(vec!["url2".to_owned(), "url3".to_owned()]);
         assert_eq!(
             builder,
             TorrentBuilder {
                 path: PathBuf::from("dir"),
                 piece_length: 42,
+                web_seeds: Some(vec!["url2".to_owned(), "url3".to_owned()]),
                 ..Default::default()
             }
         );
@@ -1477,6 +3108,108 @@ mod torrent_builder_tests {
         }
     }
 
+    #[test]
+    fn validate_announce_urls_ok() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .set_announce(Some("http://tracker.example.org/announce".to_owned()))
+            .set_announce_list(vec![
+                vec!["https://tracker.example.org:8443/announce?key=1".to_owned()],
+                vec![
+                    "udp://tracker.example.org:6969".to_owned(),
+                    "udp://[2001:db8::1]:6969/announce".to_owned(),
+                ],
+            ])
+            .set_announce_validation(true);
+
+        builder.validate_announce_urls().unwrap();
+    }
+
+    #[test]
+    fn validate_announce_urls_disabled() {
+        let builder = TorrentBuilder::new("dir/", 42).set_announce(Some("url".to_owned()));
+
+        builder.validate_announce_urls().unwrap();
+    }
+
+    #[test]
+    fn validate_announce_urls_no_scheme() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .set_announce(Some("tracker.example.org/announce".to_owned()))
+            .set_announce_validation(true);
+
+        match builder.validate_announce_urls() {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => assert_eq!(
+                m,
+                "TorrentBuilder has an invalid tracker url \"tracker.example.org/announce\": \
+                 it has no scheme."
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn validate_announce_urls_bad_scheme() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .set_announce_list(vec![vec!["wss://tracker.example.org".to_owned()]])
+            .set_announce_validation(true);
+
+        match builder.validate_announce_urls() {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => assert_eq!(
+                m,
+                "TorrentBuilder has an invalid tracker url \"wss://tracker.example.org\": \
+                 its scheme is not http, https or udp."
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn validate_announce_urls_no_host() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .set_announce(Some("http:///announce".to_owned()))
+            .set_announce_validation(true);
+
+        match builder.validate_announce_urls() {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => assert_eq!(
+                m,
+                "TorrentBuilder has an invalid tracker url \"http:///announce\": it has no host."
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn validate_announce_urls_bad_port() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .set_announce(Some("http://tracker.example.org:99999/announce".to_owned()))
+            .set_announce_validation(true);
+
+        match builder.validate_announce_urls() {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => assert_eq!(
+                m,
+                "TorrentBuilder has an invalid tracker url \
+                 \"http://tracker.example.org:99999/announce\": its port is invalid."
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn validate_announce_urls_udp_no_port() {
+        let builder = TorrentBuilder::new("dir/", 42)
+            .set_announce(Some("udp://tracker.example.org/announce".to_owned()))
+            .set_announce_validation(true);
+
+        match builder.validate_announce_urls() {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => assert_eq!(
+                m,
+                "TorrentBuilder has an invalid tracker url \
+                 \"udp://tracker.example.org/announce\": udp trackers require a port."
+            ),
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn validate_name_ok() {
         let builder = TorrentBuilder::new("dir/", 42).set_name("sample".to_owned());
@@ -1511,6 +3244,7 @@ mod torrent_builder_tests {
     }
 
     #[test]
+    #[cfg(feature = "fs")]
     fn validate_path_ok() {
         let builder = TorrentBuilder::new("./target", 42);
         builder.validate_path().unwrap();
@@ -1519,6 +3253,7 @@ mod torrent_builder_tests {
     }
 
     #[test]
+    #[cfg(feature = "fs")]
     fn validate_path_does_not_exist() {
         let builder = TorrentBuilder::new("./dir", 42);
 
@@ -1532,18 +3267,21 @@ mod torrent_builder_tests {
     }
 
     #[test]
+    #[cfg(feature = "fs")]
     fn validate_path_has_invalid_component() {
         let builder = TorrentBuilder::new("./target/..", 42);
         assert!(builder.validate_path().is_ok())
     }
 
     #[test]
+    #[cfg(feature = "fs")]
     fn validate_path_has_hidden_component() {
         let builder = TorrentBuilder::new("./tests/files/.hidden", 42);
         assert!(builder.validate_path().is_ok());
     }
 
     #[test]
+    #[cfg(feature = "fs")]
     fn validate_path_not_absolute() {
         let builder = TorrentBuilder::new("target/", 42);
         assert!(builder.validate_path().is_ok())
@@ -1658,63 +3396,341 @@ mod torrent_builder_tests {
     }
 
     #[test]
+    fn set_httpseeds_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_httpseeds(vec!["url1".to_owned()]);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                httpseeds: Some(vec!["url1".to_owned()]),
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_httpseeds(vec!["url2".to_owned()]);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                httpseeds: Some(vec!["url2".to_owned()]),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn validate_httpseeds_empty() {
+        let builder = TorrentBuilder::new("dir/", 42).set_httpseeds(vec![]);
+
+        match builder.validate_httpseeds() {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => {
+                assert_eq!(m, "TorrentBuilder has `httpseeds` but it's empty.");
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn validate_httpseeds_empty_url() {
+        let builder = TorrentBuilder::new("dir/", 42).set_httpseeds(vec!["".to_owned()]);
+
+        match builder.validate_httpseeds() {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => assert_eq!(
+                m,
+                "TorrentBuilder has `httpseeds` but it contains a 0-length url."
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn set_nodes_ok() {
+        let builder = TorrentBuilder::new("dir/", 42);
+
+        let builder = builder.set_nodes(vec![("127.0.0.1".to_owned(), 6881)]);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                nodes: Some(vec![("127.0.0.1".to_owned(), 6881)]),
+                ..Default::default()
+            }
+        );
+
+        let builder = builder.set_nodes(vec![("router.example.org".to_owned(), 6882)]);
+        assert_eq!(
+            builder,
+            TorrentBuilder {
+                path: PathBuf::from("dir"),
+                piece_length: 42,
+                nodes: Some(vec![("router.example.org".to_owned(), 6882)]),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn validate_nodes_empty() {
+        let builder = TorrentBuilder::new("dir/", 42).set_nodes(vec![]);
+
+        match builder.validate_nodes() {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => {
+                assert_eq!(m, "TorrentBuilder has `nodes` but it's empty.");
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn validate_nodes_empty_host() {
+        let builder = TorrentBuilder::new("dir/", 42).set_nodes(vec![("".to_owned(), 6881)]);
+
+        match builder.validate_nodes() {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => assert_eq!(
+                m,
+                "TorrentBuilder has `nodes` but it contains a 0-length host."
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn validate_web_seeds_ok() {
+        let builder = TorrentBuilder::new("dir/", 42).set_web_seeds(vec!["url".to_owned()]);
+        let builder2 = builder.clone();
+
+        builder.validate_web_seeds().unwrap();
+        // validation methods should not modify builder
+        assert_eq!(builder, builder2);
+    }
+
+    #[test]
+    fn validate_web_seeds_empty() {
+        let builder = TorrentBuilder::new("dir/", 42).set_web_seeds(vec![]);
+
+        match builder.validate_web_seeds() {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => {
+                assert_eq!(m, "TorrentBuilder has `web_seeds` but it's empty.");
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn validate_web_seeds_empty_url() {
+        let builder = TorrentBuilder::new("dir/", 42).set_web_seeds(vec!["".to_owned()]);
+
+        match builder.validate_web_seeds() {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => assert_eq!(
+                m,
+                "TorrentBuilder has `web_seeds` but it contains a 0-length url."
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn verify_ok() {
+        let builder = TorrentBuilder::new("tests/files/byte_sequence", 64).set_num_threads(1);
+        let torrent = builder.clone().build().unwrap();
+
+        builder.verify(&torrent).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn verify_piece_mismatch() {
+        let builder = TorrentBuilder::new("tests/files/byte_sequence", 64).set_num_threads(1);
+        let mut torrent = builder.clone().build().unwrap();
+        torrent.info.fields_mut().pieces[1][0] ^= 1;
+
+        match builder.verify(&torrent) {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => assert_eq!(
+                m,
+                "verification failed: piece [1] does not match the content on disk."
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn verify_length_mismatch() {
+        let builder = TorrentBuilder::new("tests/files/byte_sequence", 64).set_num_threads(1);
+        let mut torrent = builder.clone().build().unwrap();
+        torrent.info.fields_mut().length += 1;
+
+        match builder.verify(&torrent) {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => assert_eq!(
+                m,
+                "verification failed: the length of the content has changed."
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
     fn read_file_ok() {
         // byte_sequence contains 256 bytes ranging from 0x0 to 0xff
-        let (length, pieces) = TorrentBuilder::read_file("tests/files/byte_sequence", 64).unwrap();
+        let (length, pieces, sha1) = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .read_file("tests/files/byte_sequence")
+            .unwrap();
         assert_eq!(length, 256);
+        assert_eq!(sha1, None);
         assert_eq!(
             pieces,
-            vec![
-                vec![
+            Pieces::from(vec![
+                [
                     198, 19, 141, 81, 79, 250, 33, 53, 191, 206, 14, 208, 184, 250, 198, 86, 105,
                     145, 126, 199,
                 ],
-                vec![
+                [
                     8, 244, 44, 162, 89, 207, 18, 29, 46, 169, 205, 139, 108, 91, 36, 200, 109,
                     115, 61, 183,
                 ],
-                vec![
+                [
                     156, 122, 162, 177, 31, 39, 9, 152, 166, 59, 27, 23, 149, 207, 243, 137, 10,
                     78, 181, 111,
                 ],
-                vec![
+                [
                     185, 161, 57, 156, 18, 128, 41, 140, 193, 70, 116, 118, 156, 255, 135, 160,
                     167, 133, 230, 171,
                 ],
-            ]
+            ])
         );
     }
 
     #[test]
+    #[cfg(feature = "parallel")]
     fn read_file_parallel_ok() {
         // byte_sequence contains 256 bytes ranging from 0x0 to 0xff
-        let (length, pieces) =
-            TorrentBuilder::read_file_parallel("tests/files/byte_sequence", 64, 3).unwrap();
+        let (length, pieces, sha1) = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .read_file_parallel(
+                "tests/files/byte_sequence",
+                &TorrentBuilder::rayon_thread_pool(3).unwrap(),
+            )
+            .unwrap();
         assert_eq!(length, 256);
+        assert_eq!(sha1, None);
         assert_eq!(
             pieces,
-            vec![
-                vec![
+            Pieces::from(vec![
+                [
                     198, 19, 141, 81, 79, 250, 33, 53, 191, 206, 14, 208, 184, 250, 198, 86, 105,
                     145, 126, 199,
                 ],
-                vec![
+                [
                     8, 244, 44, 162, 89, 207, 18, 29, 46, 169, 205, 139, 108, 91, 36, 200, 109,
                     115, 61, 183,
                 ],
-                vec![
+                [
                     156, 122, 162, 177, 31, 39, 9, 152, 166, 59, 27, 23, 149, 207, 243, 137, 10,
                     78, 181, 111,
                 ],
-                vec![
+                [
                     185, 161, 57, 156, 18, 128, 41, 140, 193, 70, 116, 118, 156, 255, 135, 160,
                     167, 133, 230, 171,
                 ],
-            ]
+            ])
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn read_file_sha1_ok() {
+        // byte_sequence contains 256 bytes ranging from 0x0 to 0xff
+        let (_, _, sha1) = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .set_file_sha1(true)
+            .read_file("tests/files/byte_sequence")
+            .unwrap();
+        assert_eq!(
+            sha1,
+            Some(vec![
+                73, 22, 214, 189, 183, 247, 142, 104, 3, 105, 140, 171, 50, 209, 88, 110, 164, 87,
+                223, 200,
+            ])
         );
     }
 
     #[test]
+    #[cfg(feature = "parallel")]
+    fn file_sha1_single_threaded() {
+        // byte_sequence contains 256 bytes ranging from 0x0 to 0xff
+        let builder = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .set_file_sha1(true)
+            .set_num_threads(3);
+        assert_eq!(builder.resolve_num_threads(), 1);
+        assert_eq!(
+            builder
+                .build()
+                .unwrap()
+                .info
+                .into_mut()
+                .extra_info_fields
+                .unwrap()["sha1"],
+            BencodeElem::Bytes(vec![
+                73, 22, 214, 189, 183, 247, 142, 104, 3, 105, 140, 171, 50, 209, 88, 110, 164, 87,
+                223, 200,
+            ])
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn read_file_missing() {
+        match TorrentBuilder::new("tests/files/missing", 64).read_file("tests/files/missing") {
+            Err(LavaTorrentError::FileIo {
+                path,
+                offset,
+                source,
+            }) => {
+                assert_eq!(path, PathBuf::from("tests/files/missing"));
+                assert_eq!(offset, None);
+                assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn read_chunk_ok() {
+        let mut buf = vec![1];
+        TorrentBuilder::default()
+            .read_chunk(&[2, 3, 4][..], Path::new("file"), 0, 2, &mut buf)
+            .unwrap();
+        assert_eq!(buf, vec![1, 2, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn read_chunk_too_short() {
+        let mut buf = Vec::new();
+        match TorrentBuilder::default().read_chunk(
+            &[2, 3, 4][..],
+            Path::new("file"),
+            64,
+            4,
+            &mut buf,
+        ) {
+            Err(e @ LavaTorrentError::FileIo { .. }) => assert_eq!(
+                e.to_string(),
+                "IO error on \"file\" at offset 67: \
+                 file is shorter than expected (was it modified during the build?)"
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
     fn read_file_non_blocking_ok() {
         let n_piece_processed = Arc::new(AtomicU64::new(0));
         let n_piece_total = Arc::new(AtomicU64::new(0));
@@ -1724,12 +3740,13 @@ mod torrent_builder_tests {
             n_piece_processed: n_piece_processed.clone(),
             n_piece_total: n_piece_total.clone(),
             is_canceled: is_canceled.clone(),
+            events: Default::default(),
+            files: Default::default(),
         };
 
-        let (length, pieces) = std::thread::spawn(|| {
-            TorrentBuilder::read_file_non_blocking(
+        let (length, pieces, sha1) = std::thread::spawn(|| {
+            TorrentBuilder::new("tests/files/byte_sequence", 64).read_file_non_blocking(
                 "tests/files/byte_sequence", // byte_sequence contains 256 bytes ranging from 0x0 to 0xff
-                64,
                 torrent_build_internal,
             )
         })
@@ -1742,30 +3759,32 @@ mod torrent_builder_tests {
         assert!(!is_canceled.load(Ordering::Acquire));
 
         assert_eq!(length, 256);
+        assert_eq!(sha1, None);
         assert_eq!(
             pieces,
-            vec![
-                vec![
+            Pieces::from(vec![
+                [
                     198, 19, 141, 81, 79, 250, 33, 53, 191, 206, 14, 208, 184, 250, 198, 86, 105,
                     145, 126, 199,
                 ],
-                vec![
+                [
                     8, 244, 44, 162, 89, 207, 18, 29, 46, 169, 205, 139, 108, 91, 36, 200, 109,
                     115, 61, 183,
                 ],
-                vec![
+                [
                     156, 122, 162, 177, 31, 39, 9, 152, 166, 59, 27, 23, 149, 207, 243, 137, 10,
                     78, 181, 111,
                 ],
-                vec![
+                [
                     185, 161, 57, 156, 18, 128, 41, 140, 193, 70, 116, 118, 156, 255, 135, 160,
                     167, 133, 230, 171,
                 ],
-            ]
+            ])
         );
     }
 
     #[test]
+    #[cfg(feature = "fs")]
     fn read_file_non_blocking_cancel() {
         let n_piece_processed = Arc::new(AtomicU64::new(0));
         let n_piece_total = Arc::new(AtomicU64::new(0));
@@ -1775,13 +3794,14 @@ mod torrent_builder_tests {
             n_piece_processed: n_piece_processed.clone(),
             n_piece_total: n_piece_total.clone(),
             is_canceled: is_canceled.clone(),
+            events: Default::default(),
+            files: Default::default(),
         };
 
         let output = std::thread::spawn(|| {
             std::thread::sleep(std::time::Duration::from_millis(10)); // give main thread some time to cancel
-            TorrentBuilder::read_file_non_blocking(
+            TorrentBuilder::new("tests/files/byte_sequence", 64).read_file_non_blocking(
                 "tests/files/byte_sequence", // byte_sequence contains 256 bytes ranging from 0x0 to 0xff
-                64,
                 torrent_build_internal,
             )
         });
@@ -1801,6 +3821,7 @@ mod torrent_builder_tests {
     }
 
     #[test]
+    #[cfg(feature = "parallel")]
     fn read_file_parallel_non_blocking_ok() {
         let n_piece_processed = Arc::new(AtomicU64::new(0));
         let n_piece_total = Arc::new(AtomicU64::new(0));
@@ -1810,13 +3831,14 @@ mod torrent_builder_tests {
             n_piece_processed: n_piece_processed.clone(),
             n_piece_total: n_piece_total.clone(),
             is_canceled: is_canceled.clone(),
+            events: Default::default(),
+            files: Default::default(),
         };
 
-        let (length, pieces) = std::thread::spawn(|| {
-            TorrentBuilder::read_file_parallel_non_blocking(
+        let (length, pieces, sha1) = std::thread::spawn(|| {
+            TorrentBuilder::new("tests/files/byte_sequence", 64).read_file_parallel_non_blocking(
                 "tests/files/byte_sequence", // byte_sequence contains 256 bytes ranging from 0x0 to 0xff
-                64,
-                3,
+                &TorrentBuilder::rayon_thread_pool(3).unwrap(),
                 torrent_build_internal,
             )
         })
@@ -1829,30 +3851,32 @@ mod torrent_builder_tests {
         assert!(!is_canceled.load(Ordering::Acquire));
 
         assert_eq!(length, 256);
+        assert_eq!(sha1, None);
         assert_eq!(
             pieces,
-            vec![
-                vec![
+            Pieces::from(vec![
+                [
                     198, 19, 141, 81, 79, 250, 33, 53, 191, 206, 14, 208, 184, 250, 198, 86, 105,
                     145, 126, 199,
                 ],
-                vec![
+                [
                     8, 244, 44, 162, 89, 207, 18, 29, 46, 169, 205, 139, 108, 91, 36, 200, 109,
                     115, 61, 183,
                 ],
-                vec![
+                [
                     156, 122, 162, 177, 31, 39, 9, 152, 166, 59, 27, 23, 149, 207, 243, 137, 10,
                     78, 181, 111,
                 ],
-                vec![
+                [
                     185, 161, 57, 156, 18, 128, 41, 140, 193, 70, 116, 118, 156, 255, 135, 160,
                     167, 133, 230, 171,
                 ],
-            ]
+            ])
         );
     }
 
     #[test]
+    #[cfg(feature = "parallel")]
     fn read_file_parallel_non_blocking_cancel() {
         let n_piece_processed = Arc::new(AtomicU64::new(0));
         let n_piece_total = Arc::new(AtomicU64::new(0));
@@ -1862,14 +3886,15 @@ mod torrent_builder_tests {
             n_piece_processed: n_piece_processed.clone(),
             n_piece_total: n_piece_total.clone(),
             is_canceled: is_canceled.clone(),
+            events: Default::default(),
+            files: Default::default(),
         };
 
         let output = std::thread::spawn(|| {
             std::thread::sleep(std::time::Duration::from_millis(10)); // give main thread some time to cancel
-            TorrentBuilder::read_file_parallel_non_blocking(
+            TorrentBuilder::new("tests/files/byte_sequence", 64).read_file_parallel_non_blocking(
                 "tests/files/byte_sequence", // byte_sequence contains 256 bytes ranging from 0x0 to 0xff
-                64,
-                3,
+                &TorrentBuilder::rayon_thread_pool(3).unwrap(),
                 torrent_build_internal,
             )
         });
@@ -1887,4 +3912,36 @@ mod torrent_builder_tests {
         assert_eq!(n_piece_total.load(Ordering::Acquire), 4);
         assert!(is_canceled.load(Ordering::Acquire));
     }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn drop_torrent_build_joins() {
+        // slow enough that the build is still running when dropped
+        let build = TorrentBuilder::new("tests/files", 16384)
+            .set_max_read_rate(100_000)
+            .set_num_threads(1)
+            .build_non_blocking()
+            .unwrap();
+        let n_piece_processed = build.n_piece_processed.clone();
+        let is_canceled = build.is_canceled.clone();
+
+        drop(build);
+
+        // the builder thread has exited and released its references
+        assert!(is_canceled.load(Ordering::Acquire));
+        assert_eq!(Arc::strong_count(&n_piece_processed), 1);
+        assert!(n_piece_processed.load(Ordering::Acquire) < 10);
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn drop_torrent_build_after_output() {
+        let build = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build_non_blocking()
+            .unwrap();
+        let n_piece_processed = build.n_piece_processed.clone();
+
+        assert!(build.get_output().is_ok());
+        assert_eq!(Arc::strong_count(&n_piece_processed), 1);
+    }
 }
@@ -0,0 +1,551 @@
+use super::*;
+use crate::util;
+use std::collections::{BTreeMap, BTreeSet};
+
+// How a file in one torrent relates to a file of the same length in another.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Evidence {
+    Confirmed, // the pieces inside both files match
+    Rejected,  // the pieces inside both files differ
+    Unknown,   // the pieces can't be compared
+}
+
+impl Torrent {
+    /// Pair up the files of `self` and `other`, assuming that both
+    /// torrents (mostly) contain the same content, and that some files
+    /// might have been renamed or moved.
+    ///
+    /// Returns `(index into self's files, index into other's files)` pairs, ordered
+    /// by the former. For single-file torrents, the only valid index is `0`.
+    /// This is useful for migrating a seeded folder to a re-uploaded torrent,
+    /// e.g. by renaming the matched files on disk, followed by a [`verify()`].
+    ///
+    /// Files are matched by length first. If both torrents have the same
+    /// `piece_length` and both files are aligned the same way, the pieces
+    /// that lie entirely within the files are also compared, which can
+    /// confirm or rule out a match. If that's not possible (e.g. for
+    /// files smaller than a piece), a file is only matched if its
+    /// length is unique among the unmatched files of both torrents.
+    ///
+    /// Only unambiguous matches are returned and zero-length files are never matched.
+    ///
+    /// [`verify()`]: #method.verify
+    pub fn match_files(&self, other: &Torrent) -> Result<Vec<(usize, usize)>, LavaTorrentError> {
+        let spans = self.spans()?;
+        let other_spans = other.spans()?;
+        let evidence = |i: usize, j: usize| -> Result<Evidence, LavaTorrentError> {
+            if spans[i].length != other_spans[j].length {
+                return Ok(Evidence::Rejected);
+            }
+
+            match (
+                self.inner_pieces(&spans[i])?,
+                other.inner_pieces(&other_spans[j])?,
+            ) {
+                (Some(a), Some(b)) if self.info.piece_length == other.info.piece_length => {
+                    if spans[i].offset % util::i64_to_u64(self.info.piece_length)?
+                        != other_spans[j].offset % util::i64_to_u64(other.info.piece_length)?
+                    {
+                        Ok(Evidence::Unknown)
+                    } else if a == b {
+                        Ok(Evidence::Confirmed)
+                    } else {
+                        Ok(Evidence::Rejected)
+                    }
+                }
+                _ => Ok(Evidence::Unknown),
+            }
+        };
+
+        let mut matched = vec![false; spans.len()];
+        let mut other_matched = vec![false; other_spans.len()];
+        let mut matches = Vec::new();
+
+        // pass 1: files confirmed by piece hashes
+        for i in (0..spans.len()).filter(|&i| spans[i].length > 0) {
+            let mut confirmed = Vec::new();
+            for j in 0..other_spans.len() {
+                if evidence(i, j)? == Evidence::Confirmed {
+                    confirmed.push(j);
+                }
+            }
+
+            if let [j] = confirmed[..] {
+                if !other_matched[j] {
+                    matched[i] = true;
+                    other_matched[j] = true;
+                    matches.push((i, j));
+                }
+            }
+        }
+
+        // pass 2: the remaining files that have a unique length
+        let mut candidates = Vec::new();
+        for i in (0..spans.len()).filter(|&i| !matched[i] && spans[i].length > 0) {
+            for j in (0..other_spans.len()).filter(|&j| !other_matched[j]) {
+                if evidence(i, j)? == Evidence::Unknown {
+                    candidates.push((i, j));
+                }
+            }
+        }
+        for &(i, j) in &candidates {
+            let is_unique = candidates
+                .iter()
+                .filter(|&&(i2, j2)| i2 == i || j2 == j)
+                .count()
+                == 1;
+            let has_other_length = |length| {
+                (0..spans.len()).any(|i2| i2 != i && !matched[i2] && spans[i2].length == length)
+                    || (0..other_spans.len())
+                        .any(|j2| j2 != j && !other_matched[j2] && other_spans[j2].length == length)
+            };
+
+            if is_unique && !has_other_length(spans[i].length) {
+                matches.push((i, j));
+            }
+        }
+
+        matches.sort_unstable();
+        Ok(matches)
+    }
+
+    /// Describe how `other` differs from `self`.
+    ///
+    /// Everything is compared except the order of trackers, nodes,
+    /// web seeds, HTTP seeds and files. All lists in the output are sorted.
+    pub fn diff(&self, other: &Torrent) -> TorrentDiff {
+        let trackers = self.trackers();
+        let other_trackers = other.trackers();
+        let nodes = self.node_set();
+        let other_nodes = other.node_set();
+        let web_seeds = url_set(&self.url_list);
+        let other_web_seeds = url_set(&other.url_list);
+        let httpseeds = url_set(&self.httpseeds);
+        let other_httpseeds = url_set(&other.httpseeds);
+        let files = self.file_lengths();
+        let other_files = other.file_lengths();
+
+        TorrentDiff {
+            trackers_added: other_trackers.difference(&trackers).cloned().collect(),
+            trackers_removed: trackers.difference(&other_trackers).cloned().collect(),
+            nodes_added: other_nodes.difference(&nodes).cloned().collect(),
+            nodes_removed: nodes.difference(&other_nodes).cloned().collect(),
+            web_seeds_added: other_web_seeds.difference(&web_seeds).cloned().collect(),
+            web_seeds_removed: web_seeds.difference(&other_web_seeds).cloned().collect(),
+            httpseeds_added: other_httpseeds.difference(&httpseeds).cloned().collect(),
+            httpseeds_removed: httpseeds.difference(&other_httpseeds).cloned().collect(),
+            name: if self.info.name != other.info.name {
+                Some((self.info.name.clone(), other.info.name.clone()))
+            } else {
+                None
+            },
+            files_added: other_files
+                .keys()
+                .filter(|path| !files.contains_key(*path))
+                .cloned()
+                .collect(),
+            files_removed: files
+                .keys()
+                .filter(|path| !other_files.contains_key(*path))
+                .cloned()
+                .collect(),
+            files_resized: files
+                .iter()
+                .filter_map(|(path, &length)| match other_files.get(path) {
+                    Some(&other_length) if other_length != length => {
+                        Some((path.clone(), length, other_length))
+                    }
+                    _ => None,
+                })
+                .collect(),
+            piece_length: if self.info.piece_length != other.info.piece_length {
+                Some((self.info.piece_length, other.info.piece_length))
+            } else {
+                None
+            },
+            pieces_changed: self.info.pieces != other.info.pieces,
+            extra_fields: DictionaryDiff::new(&self.extra_fields, &other.extra_fields),
+            extra_info_fields: DictionaryDiff::new(
+                &self.info.extra_info_fields,
+                &other.info.extra_info_fields,
+            ),
+        }
+    }
+
+    fn trackers(&self) -> BTreeSet<String> {
+        self.announce
+            .iter()
+            .chain(self.announce_list.iter().flatten().flatten())
+            .cloned()
+            .collect()
+    }
+
+    fn node_set(&self) -> BTreeSet<(String, u16)> {
+        self.nodes.iter().flatten().cloned().collect()
+    }
+
+    fn file_lengths(&self) -> BTreeMap<PathBuf, Integer> {
+        match self.info.files {
+            Some(ref files) => files
+                .iter()
+                .map(|file| (file.path.clone(), file.length))
+                .collect(),
+            None => std::iter::once((PathBuf::from(&self.info.name), self.info.length)).collect(),
+        }
+    }
+
+    // full-length pieces that lie entirely within `span`, or `None` if there is none
+    fn inner_pieces(&self, span: &Span) -> Result<Option<&[Piece]>, LavaTorrentError> {
+        let piece_length = util::i64_to_u64(self.info.piece_length)?;
+        if piece_length == 0 {
+            return Ok(None);
+        }
+
+        let first = util::u64_to_usize(span.offset.div_ceil(piece_length))?;
+        let last = util::u64_to_usize((span.offset + span.length) / piece_length)?;
+        match self.info.pieces.as_slice().get(first..last) {
+            Some(pieces) if !pieces.is_empty() => Ok(Some(pieces)),
+            _ => Ok(None),
+        }
+    }
+}
+
+// The urls in `urls` (e.g. `url_list`), deduplicated and sorted.
+fn url_set(urls: &Option<Vec<String>>) -> BTreeSet<String> {
+    urls.iter().flatten().cloned().collect()
+}
+
+impl TorrentDiff {
+    /// Check if there are no differences.
+    pub fn is_empty(&self) -> bool {
+        *self == TorrentDiff::default()
+    }
+}
+
+impl DictionaryDiff {
+    fn new(dict: &Option<Dictionary>, other: &Option<Dictionary>) -> DictionaryDiff {
+        let empty = Dictionary::new();
+        let dict = dict.as_ref().unwrap_or(&empty);
+        let other = other.as_ref().unwrap_or(&empty);
+
+        let sorted = |keys: Vec<&String>| keys.into_iter().cloned().sorted().collect();
+        DictionaryDiff {
+            added: sorted(
+                other
+                    .keys()
+                    .filter(|key| !dict.contains_key(*key))
+                    .collect(),
+            ),
+            removed: sorted(
+                dict.keys()
+                    .filter(|key| !other.contains_key(*key))
+                    .collect(),
+            ),
+            changed: sorted(
+                dict.iter()
+                    .filter(|(key, value)| other.get(*key).is_some_and(|v| v != *value))
+                    .map(|(key, _)| key)
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Check if there are no differences.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod file_matching_tests {
+    use super::*;
+    use rand::Rng;
+
+    const OUTPUT_ROOT: &str = "tests/tmp/";
+
+    // create a directory containing `files`, each filled with bytes derived from `seed`
+    fn make_dir(files: &[(&str, usize, u8)]) -> PathBuf {
+        let dir = PathBuf::from(format!(
+            "{}match_files_{}",
+            OUTPUT_ROOT,
+            rand::thread_rng().gen::<u32>()
+        ));
+
+        for &(name, length, seed) in files {
+            let path = dir.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(
+                path,
+                (0..length)
+                    .map(|i| (i as u8).wrapping_mul(7).wrapping_add(seed))
+                    .collect::<Vec<u8>>(),
+            )
+            .unwrap();
+        }
+
+        dir
+    }
+
+    #[test]
+    fn match_files_confirmed_by_pieces() {
+        let dir1 = make_dir(&[("a", 3072, 1), ("b", 5120, 2), ("c", 100, 3)]);
+        let dir2 = make_dir(&[("1_b", 5120, 2), ("2/a", 3072, 1), ("3_c", 100, 3)]);
+        let torrent1 = TorrentBuilder::new(&dir1, 64).build().unwrap();
+        let torrent2 = TorrentBuilder::new(&dir2, 64).build().unwrap();
+        std::fs::remove_dir_all(dir1).unwrap();
+        std::fs::remove_dir_all(dir2).unwrap();
+
+        assert_eq!(
+            torrent1.match_files(&torrent2).unwrap(),
+            vec![(0, 1), (1, 0), (2, 2)]
+        );
+        assert_eq!(
+            torrent2.match_files(&torrent1).unwrap(),
+            vec![(0, 1), (1, 0), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn match_files_by_length() {
+        // different piece lengths => pieces can't be compared
+        let dir1 = make_dir(&[("a", 3072, 1), ("b", 5120, 2), ("c", 100, 3)]);
+        let dir2 = make_dir(&[("1_b", 5120, 2), ("2/a", 3072, 1), ("3_c", 100, 3)]);
+        let torrent1 = TorrentBuilder::new(&dir1, 64).build().unwrap();
+        let torrent2 = TorrentBuilder::new(&dir2, 128).build().unwrap();
+        std::fs::remove_dir_all(dir1).unwrap();
+        std::fs::remove_dir_all(dir2).unwrap();
+
+        assert_eq!(
+            torrent1.match_files(&torrent2).unwrap(),
+            vec![(0, 1), (1, 0), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn match_files_ambiguous_length() {
+        let dir1 = make_dir(&[("a", 3072, 1), ("b", 3072, 2)]);
+        let dir2 = make_dir(&[("x", 3072, 2), ("y", 3072, 1)]);
+        let torrent1 = TorrentBuilder::new(&dir1, 64).build().unwrap();
+        let torrent2 = TorrentBuilder::new(&dir2, 64).build().unwrap();
+        let torrent3 = TorrentBuilder::new(&dir2, 128).build().unwrap();
+        std::fs::remove_dir_all(dir1).unwrap();
+        std::fs::remove_dir_all(dir2).unwrap();
+
+        // pieces tell the files apart
+        assert_eq!(
+            torrent1.match_files(&torrent2).unwrap(),
+            vec![(0, 1), (1, 0)]
+        );
+        // lengths alone can't
+        assert_eq!(torrent1.match_files(&torrent3).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn match_files_rejected_by_pieces() {
+        let dir1 = make_dir(&[("a", 3072, 1), ("b", 5120, 2)]);
+        let dir2 = make_dir(&[("a", 3072, 9), ("b", 5120, 2)]);
+        let torrent1 = TorrentBuilder::new(&dir1, 64).build().unwrap();
+        let torrent2 = TorrentBuilder::new(&dir2, 64).build().unwrap();
+        std::fs::remove_dir_all(dir1).unwrap();
+        std::fs::remove_dir_all(dir2).unwrap();
+
+        assert_eq!(torrent1.match_files(&torrent2).unwrap(), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn match_files_single_file() {
+        let torrent1 = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        let torrent2 = TorrentBuilder::new("tests/files", 64).build().unwrap();
+
+        assert_eq!(torrent1.match_files(&torrent2).unwrap(), vec![(0, 0)]);
+        assert_eq!(torrent2.match_files(&torrent1).unwrap(), vec![(0, 0)]);
+    }
+}
+
+#[cfg(test)]
+mod torrent_diff_tests {
+    use super::*;
+    use std::iter::FromIterator;
+
+    fn torrent() -> Torrent {
+        Torrent {
+            announce: Some("url1".to_owned()),
+            announce_list: Some(vec![vec!["url1".to_owned(), "url2".to_owned()]]),
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 12,
+                files: Some(vec![
+                    File {
+                        length: 2,
+                        path: PathBuf::from("a"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                    File {
+                        length: 10,
+                        path: PathBuf::from("dir/b"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                ]),
+                name: "sample".to_owned(),
+                piece_length: 8,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: Some(HashMap::from_iter(vec![
+                ("comment".to_owned(), BencodeElem::String("old".to_owned())),
+                ("creation date".to_owned(), BencodeElem::Integer(1)),
+            ])),
+            raw_extra_fields: None,
+        }
+    }
+
+    #[test]
+    fn diff_same() {
+        let diff = torrent().diff(&torrent());
+        assert_eq!(diff, TorrentDiff::default());
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn diff_trackers() {
+        let mut other = torrent();
+        other.announce = None;
+        other.announce_list = Some(vec![
+            vec!["url3".to_owned()],
+            vec!["url2".to_owned(), "url1".to_owned()],
+        ]);
+
+        let diff = torrent().diff(&other);
+        assert_eq!(diff.trackers_added, vec!["url3".to_owned()]);
+        assert_eq!(diff.trackers_removed, Vec::<String>::new());
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn diff_nodes() {
+        let mut torrent = torrent();
+        torrent.nodes = Some(vec![
+            ("router1".to_owned(), 6881),
+            ("router2".to_owned(), 6881),
+        ]);
+        let mut other = torrent.clone();
+        other.nodes = Some(vec![
+            ("router2".to_owned(), 6881),
+            ("router1".to_owned(), 6882),
+        ]);
+
+        let diff = torrent.diff(&other);
+        assert_eq!(diff.nodes_added, vec![("router1".to_owned(), 6882)]);
+        assert_eq!(diff.nodes_removed, vec![("router1".to_owned(), 6881)]);
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn diff_web_seeds() {
+        let mut torrent = torrent();
+        torrent.url_list = Some(vec!["seed1".to_owned(), "seed2".to_owned()]);
+        let mut other = torrent.clone();
+        other.url_list = Some(vec!["seed3".to_owned(), "seed1".to_owned()]);
+
+        let diff = torrent.diff(&other);
+        assert_eq!(diff.web_seeds_added, vec!["seed3".to_owned()]);
+        assert_eq!(diff.web_seeds_removed, vec!["seed2".to_owned()]);
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn diff_httpseeds() {
+        let mut other = torrent();
+        other.httpseeds = Some(vec!["seed".to_owned()]);
+
+        let diff = torrent().diff(&other);
+        assert_eq!(diff.httpseeds_added, vec!["seed".to_owned()]);
+        assert_eq!(diff.httpseeds_removed, Vec::<String>::new());
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn diff_files() {
+        let mut other = torrent();
+        other.info.fields_mut().files = Some(vec![
+            File {
+                length: 12,
+                path: PathBuf::from("dir/b"),
+                extra_fields: None,
+                raw_extra_fields: None,
+            },
+            File {
+                length: 3,
+                path: PathBuf::from("c"),
+                extra_fields: None,
+                raw_extra_fields: None,
+            },
+        ]);
+        let info = other.info.fields_mut();
+        info.length = 15;
+        info.piece_length = 16;
+        info.pieces = Pieces::from(vec![[5; 20]]);
+
+        let diff = torrent().diff(&other);
+        assert_eq!(diff.files_added, vec![PathBuf::from("c")]);
+        assert_eq!(diff.files_removed, vec![PathBuf::from("a")]);
+        assert_eq!(diff.files_resized, vec![(PathBuf::from("dir/b"), 10, 12)]);
+        assert_eq!(diff.piece_length, Some((8, 16)));
+        assert!(diff.pieces_changed);
+    }
+
+    #[test]
+    fn diff_single_file() {
+        let mut torrent = torrent();
+        torrent.info.fields_mut().files = None;
+        let mut other = torrent.clone();
+        other.info.fields_mut().name = "renamed".to_owned();
+
+        let diff = torrent.diff(&other);
+        assert_eq!(diff.name, Some(("sample".to_owned(), "renamed".to_owned())));
+        assert_eq!(diff.files_added, vec![PathBuf::from("renamed")]);
+        assert_eq!(diff.files_removed, vec![PathBuf::from("sample")]);
+    }
+
+    #[test]
+    fn diff_extra_fields() {
+        let mut other = torrent();
+        let extra_fields = other.extra_fields.as_mut().unwrap();
+        extra_fields.remove("creation date");
+        extra_fields.insert("comment".to_owned(), BencodeElem::String("new".to_owned()));
+        extra_fields.insert(
+            "created by".to_owned(),
+            BencodeElem::String("me".to_owned()),
+        );
+        other.info.fields_mut().extra_info_fields = Some(HashMap::from_iter(vec![(
+            "private".to_owned(),
+            BencodeElem::Integer(1),
+        )]));
+
+        let diff = torrent().diff(&other);
+        assert_eq!(
+            diff.extra_fields,
+            DictionaryDiff {
+                added: vec!["created by".to_owned()],
+                removed: vec!["creation date".to_owned()],
+                changed: vec!["comment".to_owned()],
+            }
+        );
+        assert_eq!(
+            diff.extra_info_fields,
+            DictionaryDiff {
+                added: vec!["private".to_owned()],
+                removed: vec![],
+                changed: vec![],
+            }
+        );
+    }
+}
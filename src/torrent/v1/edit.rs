@@ -0,0 +1,242 @@
+use super::*;
+use std::ops::Deref;
+
+impl Torrent {
+    /// Wrap `self` in a [`TorrentEditor`], which only allows
+    /// changes that preserve the info hash.
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(feature = "fs", doc = "```no_run")]
+    #[cfg_attr(not(feature = "fs"), doc = "```ignore")]
+    /// use lava_torrent::torrent::v1::Torrent;
+    ///
+    /// let torrent = Torrent::read_from_file("sample.torrent").unwrap();
+    /// let info_hash = torrent.info_hash();
+    ///
+    /// let mut editor = torrent.edit();
+    /// editor.set_announce(Some("udp://tracker.example.com:6969".to_owned()));
+    /// editor.set_comment(Some("re-tagged".to_owned()));
+    /// let torrent = editor.into_torrent();
+    ///
+    /// assert_eq!(torrent.info_hash(), info_hash);
+    /// ```
+    ///
+    /// [`TorrentEditor`]: struct.TorrentEditor.html
+    pub fn edit(self) -> TorrentEditor {
+        TorrentEditor { torrent: self }
+    }
+}
+
+impl TorrentEditor {
+    /// Set the `announce` field, or remove it if `announce` is `None`.
+    pub fn set_announce(&mut self, announce: Option<String>) {
+        self.torrent.announce = announce;
+    }
+
+    /// Set the `announce_list` field, or remove it if `announce_list` is `None`.
+    pub fn set_announce_list(&mut self, announce_list: Option<AnnounceList>) {
+        self.torrent.announce_list = announce_list;
+    }
+
+    /// Add a tracker in a new tier. See [`Torrent::add_tracker()`].
+    ///
+    /// [`Torrent::add_tracker()`]: struct.Torrent.html#method.add_tracker
+    pub fn add_tracker(&mut self, url: String) -> bool {
+        self.torrent.add_tracker(url)
+    }
+
+    /// Add a tracker to a tier. See [`Torrent::add_tracker_to_tier()`].
+    ///
+    /// [`Torrent::add_tracker_to_tier()`]: struct.Torrent.html#method.add_tracker_to_tier
+    pub fn add_tracker_to_tier(&mut self, tier: usize, url: String) -> bool {
+        self.torrent.add_tracker_to_tier(tier, url)
+    }
+
+    /// Remove a tracker. See [`Torrent::remove_tracker()`].
+    ///
+    /// [`Torrent::remove_tracker()`]: struct.Torrent.html#method.remove_tracker
+    pub fn remove_tracker(&mut self, url: &str) -> bool {
+        self.torrent.remove_tracker(url)
+    }
+
+    /// Remove duplicate trackers. See [`Torrent::dedupe_trackers()`].
+    ///
+    /// [`Torrent::dedupe_trackers()`]: struct.Torrent.html#method.dedupe_trackers
+    pub fn dedupe_trackers(&mut self) {
+        self.torrent.dedupe_trackers();
+    }
+
+    /// Merge all trackers into a single tier. See [`Torrent::flatten_trackers()`].
+    ///
+    /// [`Torrent::flatten_trackers()`]: struct.Torrent.html#method.flatten_trackers
+    pub fn flatten_trackers(&mut self) {
+        self.torrent.flatten_trackers();
+    }
+
+    /// Set the `comment` field, or remove it if `comment` is `None`.
+    pub fn set_comment(&mut self, comment: Option<String>) {
+        self.torrent.set_comment(comment);
+    }
+
+    /// Set the `created by` field, or remove it if `created_by` is `None`.
+    pub fn set_created_by(&mut self, created_by: Option<String>) {
+        self.torrent.set_created_by(created_by);
+    }
+
+    /// Set the `creation date` field (in seconds since the UNIX epoch),
+    /// or remove it if `creation_date` is `None`.
+    pub fn set_creation_date(&mut self, creation_date: Option<Integer>) {
+        self.torrent.set_creation_date(creation_date);
+    }
+
+    /// Set the `encoding` field, or remove it if `encoding` is `None`.
+    pub fn set_encoding(&mut self, encoding: Option<String>) {
+        self.torrent.set_encoding(encoding);
+    }
+
+    /// Set the web seeds (`url-list`) as defined in
+    /// [BEP 19](http://bittorrent.org/beps/bep_0019.html),
+    /// or remove them if `web_seeds` is `None`.
+    pub fn set_web_seeds(&mut self, web_seeds: Option<Vec<String>>) {
+        self.torrent.set_web_seeds(web_seeds);
+    }
+
+    /// Set the HTTP seeds (`httpseeds`) as defined in
+    /// [BEP 17](http://bittorrent.org/beps/bep_0017.html),
+    /// or remove them if `httpseeds` is `None`.
+    pub fn set_httpseeds(&mut self, httpseeds: Option<Vec<String>>) {
+        self.torrent.set_httpseeds(httpseeds);
+    }
+
+    /// Set a top-level field not defined in
+    /// [BEP 3](http://bittorrent.org/beps/bep_0003.html), or remove it if `val` is `None`.
+    /// The previous value is returned.
+    ///
+    /// `Err(LavaTorrentError::InvalidArgument)` is returned if `key` is `"info"`,
+    /// as that would replace the `info` dictionary when the torrent is encoded.
+    pub fn set_extra_field(
+        &mut self,
+        key: String,
+        val: Option<BencodeElem>,
+    ) -> Result<Option<BencodeElem>, LavaTorrentError> {
+        if key == "info" {
+            return Err(LavaTorrentError::InvalidArgument(Cow::Borrowed(
+                r#""info" can't be set through a TorrentEditor."#,
+            )));
+        }
+
+        Ok(self.torrent.replace_extra_field(&key, val))
+    }
+
+    /// Return the edited `Torrent`.
+    pub fn into_torrent(self) -> Torrent {
+        self.torrent
+    }
+}
+
+impl Deref for TorrentEditor {
+    type Target = Torrent;
+
+    fn deref(&self) -> &Torrent {
+        &self.torrent
+    }
+}
+
+#[cfg(test)]
+mod torrent_editor_tests {
+    use super::*;
+
+    fn sample() -> Torrent {
+        Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from([(
+                    "private".to_owned(),
+                    BencodeElem::Integer(1),
+                )])),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        }
+    }
+
+    #[test]
+    fn edit_preserves_info_hash() {
+        let torrent = sample();
+        let info_hash = torrent.info_hash();
+
+        let mut editor = torrent.edit();
+        editor.set_announce(None);
+        editor.set_announce_list(Some(vec![vec!["url1".to_owned(), "url2".to_owned()]]));
+        editor.set_comment(Some("comment".to_owned()));
+        editor.set_web_seeds(Some(vec!["http://example.com/".to_owned()]));
+        editor
+            .set_extra_field("created by".to_owned(), Some(bencode_elem!("me")))
+            .unwrap();
+        let torrent = editor.into_torrent();
+
+        assert_eq!(torrent.info_hash(), info_hash);
+        assert_eq!(torrent.announce, None);
+        assert_eq!(
+            torrent.announce_list,
+            Some(vec![vec!["url1".to_owned(), "url2".to_owned()]])
+        );
+        assert_eq!(
+            torrent.url_list,
+            Some(vec!["http://example.com/".to_owned()])
+        );
+        assert_eq!(
+            torrent.extra_fields,
+            Some(HashMap::from([
+                ("comment".to_owned(), bencode_elem!("comment")),
+                ("created by".to_owned(), bencode_elem!("me")),
+            ]))
+        );
+    }
+
+    #[test]
+    fn edit_remove_fields() {
+        let mut editor = sample().edit();
+        editor.set_comment(Some("comment".to_owned()));
+        assert_eq!(
+            editor.set_extra_field("comment".to_owned(), None).unwrap(),
+            Some(bencode_elem!("comment"))
+        );
+        assert_eq!(
+            editor.set_extra_field("comment".to_owned(), None).unwrap(),
+            None
+        );
+        assert_eq!(editor.into_torrent(), sample());
+    }
+
+    #[test]
+    fn edit_info_rejected() {
+        let mut editor = sample().edit();
+        match editor.set_extra_field("info".to_owned(), Some(bencode_elem!({}))) {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, r#""info" can't be set through a TorrentEditor."#)
+            }
+            _ => panic!(),
+        }
+        assert_eq!(editor.into_torrent(), sample());
+    }
+
+    #[test]
+    fn edit_deref() {
+        let editor = sample().edit();
+        assert_eq!(editor.info.name, "sample");
+        assert!(editor.is_private());
+    }
+}
@@ -0,0 +1,124 @@
+use super::*;
+use std::borrow::Borrow;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+impl<T> ByInfoHash<T>
+where
+    T: Borrow<Torrent>,
+{
+    /// Wrap `torrent`, calculating its info hash.
+    pub fn new(torrent: T) -> ByInfoHash<T> {
+        let info_hash = Sha1::digest(torrent.borrow().construct_info().encode()).into();
+        ByInfoHash { torrent, info_hash }
+    }
+
+    /// Get the info hash of the wrapped torrent as a hex string,
+    /// like [`Torrent::info_hash()`] but without recalculating it.
+    ///
+    /// [`Torrent::info_hash()`]: struct.Torrent.html#method.info_hash
+    pub fn info_hash(&self) -> String {
+        format!("{:02x}", self.info_hash.iter().format(""))
+    }
+
+    /// Get the info hash of the wrapped torrent,
+    /// like [`Torrent::info_hash_bytes()`] but without recalculating it.
+    ///
+    /// [`Torrent::info_hash_bytes()`]: struct.Torrent.html#method.info_hash_bytes
+    pub fn info_hash_bytes(&self) -> &[u8] {
+        &self.info_hash
+    }
+
+    /// Unwrap the torrent.
+    pub fn into_inner(self) -> T {
+        self.torrent
+    }
+}
+
+impl<T> Deref for ByInfoHash<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.torrent
+    }
+}
+
+impl<T> PartialEq for ByInfoHash<T> {
+    fn eq(&self, other: &ByInfoHash<T>) -> bool {
+        self.info_hash == other.info_hash
+    }
+}
+
+impl<T> Eq for ByInfoHash<T> {}
+
+impl<T> Hash for ByInfoHash<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.info_hash.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod by_info_hash_tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn torrent() -> Torrent {
+        Torrent {
+            announce: Some("url1".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        }
+    }
+
+    #[test]
+    fn new_ok() {
+        let torrent = torrent();
+        let wrapped = ByInfoHash::new(torrent.clone());
+
+        assert_eq!(wrapped.info_hash(), torrent.info_hash());
+        assert_eq!(wrapped.info_hash_bytes(), &torrent.info_hash_bytes()[..]);
+        assert_eq!(wrapped.info.name, "sample");
+        assert_eq!(wrapped.into_inner(), torrent);
+    }
+
+    #[test]
+    fn eq_ignores_fields_outside_info() {
+        let torrent1 = torrent();
+        let mut torrent2 = torrent();
+        torrent2.announce = Some("url2".to_owned());
+        let mut torrent3 = torrent();
+        torrent3.info.fields_mut().name = "sample3".to_owned();
+
+        assert_ne!(torrent1, torrent2);
+        assert_eq!(ByInfoHash::new(&torrent1), ByInfoHash::new(&torrent2));
+        assert_ne!(ByInfoHash::new(&torrent1), ByInfoHash::new(&torrent3));
+    }
+
+    #[test]
+    fn hash_set_dedup() {
+        let mut torrent2 = torrent();
+        torrent2.announce = None;
+        let mut torrent3 = torrent();
+        torrent3.set_private();
+
+        let unique: HashSet<_> = vec![torrent(), torrent2, torrent3]
+            .into_iter()
+            .map(ByInfoHash::new)
+            .collect();
+        assert_eq!(unique.len(), 2);
+    }
+}
@@ -0,0 +1,249 @@
+use super::*;
+use std::collections::btree_map::Entry;
+
+impl TorrentIndex {
+    /// Create an empty index.
+    pub fn new() -> TorrentIndex {
+        TorrentIndex::default()
+    }
+
+    /// Add `torrent` to the index.
+    ///
+    /// If a torrent with the same info hash is already indexed, that torrent
+    /// is kept and `torrent` is returned instead, i.e. `Some` means that
+    /// `torrent` is a duplicate.
+    pub fn insert(&mut self, torrent: Torrent) -> Option<Torrent> {
+        let info_hash = Sha1::digest(torrent.construct_info().encode()).into();
+        match self.torrents.entry(info_hash) {
+            Entry::Occupied(_) => Some(torrent),
+            Entry::Vacant(entry) => {
+                entry.insert(torrent);
+                None
+            }
+        }
+    }
+
+    /// Read the torrent at `path` and add it to the index.
+    ///
+    /// The return value has the same meaning as in [`insert()`].
+    ///
+    /// [`insert()`]: #method.insert
+    #[cfg(feature = "fs")]
+    pub fn insert_file<P>(&mut self, path: P) -> Result<Option<Torrent>, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(self.insert(Torrent::read_from_file(path)?))
+    }
+
+    /// Remove the torrent with `info_hash` (i.e. the raw 20 bytes,
+    /// see [`Torrent::info_hash_bytes()`]) from the index.
+    ///
+    /// [`Torrent::info_hash_bytes()`]: struct.Torrent.html#method.info_hash_bytes
+    pub fn remove(&mut self, info_hash: &[u8]) -> Option<Torrent> {
+        let info_hash: [u8; 20] = info_hash.try_into().ok()?;
+        self.torrents.remove(&info_hash)
+    }
+
+    /// Get the torrent with `info_hash` (i.e. the raw 20 bytes,
+    /// see [`Torrent::info_hash_bytes()`]).
+    ///
+    /// [`Torrent::info_hash_bytes()`]: struct.Torrent.html#method.info_hash_bytes
+    pub fn get(&self, info_hash: &[u8]) -> Option<&Torrent> {
+        let info_hash: [u8; 20] = info_hash.try_into().ok()?;
+        self.torrents.get(&info_hash)
+    }
+
+    /// Find all torrents whose info hash (as a hex string,
+    /// see [`Torrent::info_hash()`]) starts with `prefix`.
+    ///
+    /// `prefix` is case-insensitive, and can be up to 40 characters long (i.e. a full
+    /// info hash). Matching torrents are returned in ascending order of info hash.
+    /// An error is returned if `prefix` is not a valid hex string.
+    ///
+    /// [`Torrent::info_hash()`]: struct.Torrent.html#method.info_hash
+    pub fn find_by_prefix(&self, prefix: &str) -> Result<Vec<&Torrent>, LavaTorrentError> {
+        let (min, max) = prefix_bounds(prefix).ok_or_else(|| {
+            LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                "\"{}\" is not a valid info hash prefix.",
+                prefix
+            )))
+        })?;
+
+        Ok(self
+            .torrents
+            .range(min..=max)
+            .map(|(_, torrent)| torrent)
+            .collect())
+    }
+
+    /// Get the number of torrents in the index.
+    pub fn len(&self) -> usize {
+        self.torrents.len()
+    }
+
+    /// Check if the index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.torrents.is_empty()
+    }
+
+    /// Iterate over all torrents as `(info hash, torrent)`,
+    /// in ascending order of info hash.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &Torrent)> {
+        self.torrents
+            .iter()
+            .map(|(info_hash, torrent)| (&info_hash[..], torrent))
+    }
+
+    /// Unwrap all torrents, in ascending order of info hash.
+    pub fn into_torrents(self) -> Vec<Torrent> {
+        self.torrents.into_values().collect()
+    }
+}
+
+// Smallest and largest info hashes that start with the hex string `prefix`.
+fn prefix_bounds(prefix: &str) -> Option<([u8; 20], [u8; 20])> {
+    if prefix.len() > 40 {
+        return None;
+    }
+
+    let mut min = [0x00; 20];
+    let mut max = [0xff; 20];
+    for (i, c) in prefix.chars().enumerate() {
+        let nibble = c.to_digit(16)? as u8;
+        let (byte, shift) = (i / 2, if i % 2 == 0 { 4 } else { 0 });
+        min[byte] |= nibble << shift;
+        max[byte] &= !(0x0f << shift) | (nibble << shift);
+    }
+    Some((min, max))
+}
+
+#[cfg(test)]
+mod torrent_index_tests {
+    use super::*;
+
+    fn torrent(name: &str) -> Torrent {
+        Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: name.to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        }
+    }
+
+    #[test]
+    fn insert_ok() {
+        let mut index = TorrentIndex::new();
+        assert!(index.is_empty());
+        assert_eq!(index.insert(torrent("a")), None);
+        assert_eq!(index.insert(torrent("b")), None);
+
+        let mut duplicate = torrent("a");
+        duplicate.announce = Some("url".to_owned());
+        assert_eq!(index.insert(duplicate.clone()), Some(duplicate));
+        assert_eq!(index.len(), 2);
+        assert_eq!(
+            index.get(&torrent("a").info_hash_bytes()),
+            Some(&torrent("a"))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn insert_file_ok() {
+        let mut index = TorrentIndex::new();
+        let path = "tests/files/tails-amd64-3.6.1.torrent";
+        assert!(index.insert_file(path).unwrap().is_none());
+        assert!(index.insert_file(path).unwrap().is_some());
+        assert!(index.insert_file("tests/files/byte_sequence").is_err());
+        assert_eq!(index.len(), 1);
+    }
+
+    #[test]
+    fn get_and_remove() {
+        let mut index = TorrentIndex::new();
+        index.insert(torrent("a"));
+        let info_hash = torrent("a").info_hash_bytes();
+
+        assert_eq!(index.get(&info_hash[..19]), None);
+        assert_eq!(index.remove(&torrent("b").info_hash_bytes()), None);
+        assert_eq!(index.remove(&info_hash), Some(torrent("a")));
+        assert_eq!(index.get(&info_hash), None);
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn find_by_prefix_ok() {
+        let names = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        let mut index = TorrentIndex::new();
+        for name in names {
+            index.insert(torrent(name));
+        }
+
+        for name in names {
+            let info_hash = torrent(name).info_hash();
+            for len in [0, 1, 2, 3, 5, 40] {
+                let prefix = info_hash[..len].to_uppercase();
+                let expected: Vec<_> = names
+                    .iter()
+                    .map(|name| torrent(name))
+                    .filter(|torrent| torrent.info_hash().starts_with(&info_hash[..len]))
+                    .sorted_by_key(|torrent| torrent.info_hash())
+                    .collect();
+                assert_eq!(
+                    index.find_by_prefix(&prefix).unwrap(),
+                    expected.iter().collect::<Vec<_>>()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn find_by_prefix_invalid() {
+        let index = TorrentIndex::new();
+        match index.find_by_prefix("12g") {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, "\"12g\" is not a valid info hash prefix.")
+            }
+            _ => panic!(),
+        }
+        assert!(index.find_by_prefix(&"0".repeat(41)).is_err());
+    }
+
+    #[test]
+    fn iter_ordered() {
+        let mut index = TorrentIndex::new();
+        index.insert(torrent("a"));
+        index.insert(torrent("b"));
+        index.insert(torrent("c"));
+
+        let info_hashes: Vec<_> = index.iter().map(|(info_hash, _)| info_hash).collect();
+        assert!(info_hashes.windows(2).all(|pair| pair[0] < pair[1]));
+        for (info_hash, torrent) in index.iter() {
+            assert_eq!(info_hash, &torrent.info_hash_bytes()[..]);
+        }
+        assert_eq!(index.into_torrents().len(), 3);
+    }
+
+    #[test]
+    fn prefix_bounds_ok() {
+        let (min, max) = prefix_bounds("a1b").unwrap();
+        assert_eq!(min[..3], [0xa1, 0xb0, 0x00]);
+        assert_eq!(max[..3], [0xa1, 0xbf, 0xff]);
+        assert_eq!(prefix_bounds(""), Some(([0x00; 20], [0xff; 20])));
+        assert_eq!(prefix_bounds("é"), None);
+    }
+}
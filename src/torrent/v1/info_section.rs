@@ -0,0 +1,78 @@
+use super::*;
+use std::ops::Deref;
+
+impl InfoSection {
+    /// Take the fields out of `self` so that they can be changed.
+    ///
+    /// Any change to the returned fields changes the info hash.
+    pub fn into_mut(self) -> InfoFields {
+        self.fields
+    }
+
+    // Change the fields in place. Only for the crate's own methods
+    // (e.g. `Torrent::set_private()`) that are documented to change the info hash.
+    pub(super) fn fields_mut(&mut self) -> &mut InfoFields {
+        &mut self.fields
+    }
+}
+
+impl From<InfoFields> for InfoSection {
+    fn from(fields: InfoFields) -> InfoSection {
+        InfoSection { fields }
+    }
+}
+
+impl Deref for InfoSection {
+    type Target = InfoFields;
+
+    fn deref(&self) -> &InfoFields {
+        &self.fields
+    }
+}
+
+#[cfg(test)]
+mod info_section_tests {
+    use super::*;
+
+    fn sample() -> Torrent {
+        Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        }
+    }
+
+    #[test]
+    fn deref_ok() {
+        let torrent = sample();
+        assert_eq!(torrent.info.name, "sample");
+        assert_eq!(torrent.info.pieces.len(), 2);
+    }
+
+    #[test]
+    fn into_mut_ok() {
+        let mut torrent = sample();
+        let info_hash = torrent.info_hash();
+
+        let mut info = torrent.info.into_mut();
+        info.name = "renamed".to_owned();
+        torrent.info = info.into();
+
+        assert_eq!(torrent.info.name, "renamed");
+        assert_ne!(torrent.info_hash(), info_hash);
+    }
+}
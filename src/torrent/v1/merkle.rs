@@ -0,0 +1,214 @@
+use super::*;
+
+impl Torrent {
+    /// Get the root hash of this torrent, as defined in
+    /// [BEP 30](http://bittorrent.org/beps/bep_0030.html).
+    ///
+    /// `self.extra_info_fields["root hash"]` must be a 20-byte string.
+    /// If it does not exist, `Ok(None)` will be returned.
+    pub fn root_hash(&self) -> Result<Option<&[u8]>, LavaTorrentError> {
+        extract_root_hash(&self.info.extra_info_fields)
+    }
+
+    /// Check if this is a merkle torrent as defined in
+    /// [BEP 30](http://bittorrent.org/beps/bep_0030.html),
+    /// i.e. if it has a valid `root hash` instead of `pieces`.
+    ///
+    /// Merkle torrents are written without `pieces`, and since
+    /// `self.pieces` is empty, methods that work with individual
+    /// pieces (e.g. [`verify()`]) are not meaningful for them.
+    ///
+    /// [`verify()`]: #method.verify
+    pub fn is_merkle(&self) -> bool {
+        self.info.pieces.is_empty() && matches!(self.root_hash(), Ok(Some(_)))
+    }
+
+    /// Turn this torrent into a merkle torrent as defined in
+    /// [BEP 30](http://bittorrent.org/beps/bep_0030.html),
+    /// i.e. set `root hash` in `extra_info_fields` to the merkle root
+    /// of `self.pieces` and then clear `self.pieces`.
+    ///
+    /// Note that this changes the info hash. Does nothing if `self.pieces`
+    /// is empty (e.g. if this is already a merkle torrent).
+    pub fn set_merkle(&mut self) {
+        if let Some(root) = self.info.pieces.merkle_root() {
+            self.info
+                .fields_mut()
+                .extra_info_fields
+                .get_or_insert_with(HashMap::new)
+                .insert("root hash".to_owned(), BencodeElem::Bytes(root.to_vec()));
+            self.info.fields_mut().pieces = Pieces::new();
+        }
+    }
+}
+
+impl Pieces {
+    /// Compute the root of the merkle tree whose leaves are these hashes, as
+    /// defined in [BEP 30](http://bittorrent.org/beps/bep_0030.html).
+    ///
+    /// The number of leaves is rounded up to a power of 2 with
+    /// all-zero hashes, and each parent is the SHA1 hash of its
+    /// two children concatenated. Returns `None` if `self` is empty.
+    pub fn merkle_root(&self) -> Option<Piece> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut level = self.as_slice().to_vec();
+        level.resize(level.len().next_power_of_two(), [0; PIECE_STRING_LENGTH]);
+        while level.len() > 1 {
+            level = level
+                .chunks_exact(2)
+                .map(|pair| {
+                    let mut hasher = Sha1::new();
+                    hasher.update(pair[0]);
+                    hasher.update(pair[1]);
+                    hasher.finalize().into()
+                })
+                .collect();
+        }
+        Some(level[0])
+    }
+}
+
+// Get the root hash stored in `extra_info_fields`.
+// Shared by `Torrent::root_hash()` and the validation done when reading torrents.
+pub(super) fn extract_root_hash(
+    extra_info_fields: &Option<Dictionary>,
+) -> Result<Option<&[u8]>, LavaTorrentError> {
+    match extra_info_fields
+        .as_ref()
+        .and_then(|fields| fields.get("root hash"))
+        .map(BencodeElem::as_bytes)
+    {
+        Some(Some(hash)) if hash.len() == PIECE_STRING_LENGTH => Ok(Some(hash)),
+        Some(_) => Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+            Cow::Borrowed(r#""root hash" is not a 20-byte string."#),
+        ))),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod merkle_tests {
+    use super::*;
+
+    fn hash_pair(left: &Piece, right: &Piece) -> Piece {
+        Sha1::digest([&left[..], &right[..]].concat()).into()
+    }
+
+    fn torrent() -> Torrent {
+        Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 1,
+                pieces: Pieces::from(vec![[1; 20], [2; 20], [3; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        }
+    }
+
+    #[test]
+    fn merkle_root_empty() {
+        assert_eq!(Pieces::new().merkle_root(), None);
+    }
+
+    #[test]
+    fn merkle_root_single() {
+        assert_eq!(Pieces::from(vec![[1; 20]]).merkle_root(), Some([1; 20]));
+    }
+
+    #[test]
+    fn merkle_root_padded() {
+        let pieces = Pieces::from(vec![[1; 20], [2; 20], [3; 20]]);
+
+        assert_eq!(
+            pieces.merkle_root(),
+            Some(hash_pair(
+                &hash_pair(&[1; 20], &[2; 20]),
+                &hash_pair(&[3; 20], &[0; 20]),
+            ))
+        );
+    }
+
+    #[test]
+    fn root_hash_ok() {
+        let mut torrent = torrent();
+        assert_eq!(torrent.root_hash().unwrap(), None);
+        assert!(!torrent.is_merkle());
+
+        torrent.set_merkle();
+        let root = Pieces::from(vec![[1; 20], [2; 20], [3; 20]])
+            .merkle_root()
+            .unwrap();
+        assert_eq!(torrent.root_hash().unwrap(), Some(&root[..]));
+        assert!(torrent.info.pieces.is_empty());
+        assert!(torrent.is_merkle());
+    }
+
+    #[test]
+    fn root_hash_malformed() {
+        let mut torrent = torrent();
+        torrent.info.fields_mut().extra_info_fields = Some(HashMap::from([(
+            "root hash".to_owned(),
+            BencodeElem::Bytes(vec![1; 19]),
+        )]));
+        torrent.info.fields_mut().pieces = Pieces::new();
+
+        match torrent.root_hash() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""root hash" is not a 20-byte string."#)
+            }
+            _ => panic!(),
+        }
+        assert!(!torrent.is_merkle());
+    }
+
+    #[test]
+    fn set_merkle_round_trip() {
+        let mut torrent = torrent();
+        torrent.set_merkle();
+        let info_hash = torrent.info_hash();
+
+        let encoded = torrent.clone().encode().unwrap();
+        assert_eq!(encoded.len(), torrent.encoded_len());
+        let decoded = Torrent::read_from_bytes(encoded).unwrap();
+        assert!(decoded.is_merkle());
+        assert_eq!(decoded.info_hash(), info_hash);
+        assert_eq!(decoded, torrent);
+    }
+
+    #[test]
+    fn read_malformed_root_hash() {
+        let bytes = bencode_elem!({
+            (
+                "info",
+                {
+                    ("length", 4),
+                    ("name", "sample"),
+                    ("piece length", 1),
+                    ("root hash", (vec![1; 19])),
+                }
+            ),
+        })
+        .encode();
+
+        match Torrent::read_from_bytes(bytes) {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""root hash" is not a 20-byte string."#)
+            }
+            _ => panic!(),
+        }
+    }
+}
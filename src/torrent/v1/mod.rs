@@ -2,23 +2,61 @@
 //! related parsing/encoding/creation.
 
 use crate::bencode::BencodeElem;
-use crate::LavaTorrentError;
+use crate::util;
+use crate::{LavaTorrentError, TorrentErrorKind, ValueType};
 use itertools::Itertools;
 use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 use sha1::{Digest, Sha1};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicU64};
-use std::sync::Arc;
+#[cfg(feature = "fs")]
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize};
+#[cfg(feature = "fs")]
+use std::sync::mpsc::Sender;
+#[cfg(feature = "fs")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "fs")]
 use std::thread::JoinHandle;
 
+mod alignment;
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "fs")]
+mod batch;
 mod build;
+mod diff;
+mod edit;
+mod identity;
+mod index;
+mod info_section;
+mod merkle;
+mod pieces;
+mod raw_info;
 mod read;
+#[cfg(feature = "fs")]
+mod read_ahead;
+mod resume;
+mod rtorrent;
+#[cfg(feature = "fs")]
+mod sparse;
+#[cfg(feature = "fs")]
+mod spill;
+mod trackers;
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+mod uring;
+mod validate;
+mod verify;
 mod write;
 
 const PIECE_STRING_LENGTH: usize = 20;
+// Fields of `info` that private trackers use to tie a torrent to themselves.
+// `x_cross_seed` is set by cross-seeding tools to keep info hashes unique.
+const TRACKER_INFO_FIELDS: [&str; 3] = ["private", "source", "x_cross_seed"];
+// Top-level fields that clients use to download the content.
+const SCRUB_ALLOWLIST: [&str; 3] = ["httpseeds", "nodes", "url-list"];
 
 // The escaping rules for magnet URIs are not specified in BEP9,
 // so we simply escape '&'. We do not escape space here, since
@@ -29,10 +67,13 @@ const MAGNET_COMPONENT: &AsciiSet = &CONTROLS.add(b'&').add(b'+');
 
 /// Corresponds to a bencode dictionary.
 pub type Dictionary = HashMap<String, BencodeElem>;
+/// Corresponds to a bencode dictionary whose keys are not valid UTF-8,
+/// and thus cannot be held by a [`Dictionary`](type.Dictionary.html).
+pub type RawDictionary = HashMap<Vec<u8>, BencodeElem>;
 /// Corresponds to the `announce-list` in [BEP 12](http://bittorrent.org/beps/bep_0012.html).
 pub type AnnounceList = Vec<Vec<String>>;
 /// A piece in `pieces`--the SHA1 hash of a torrent block.
-pub type Piece = Vec<u8>;
+pub type Piece = [u8; PIECE_STRING_LENGTH];
 /// Corresponds to a bencode integer. The underlying type is `i64`.
 /// Technically a bencode integer has no size limit, but it is not
 /// so in the current implementation. By using a type alias it is
@@ -53,23 +94,84 @@ pub struct File {
     pub path: PathBuf,
     /// Fields not defined in [BEP 3](http://bittorrent.org/beps/bep_0003.html).
     pub extra_fields: Option<Dictionary>,
+    /// Fields whose keys are not valid UTF-8 (and thus can't be in `extra_fields`).
+    pub raw_extra_fields: Option<RawDictionary>,
+}
+
+/// File attributes as defined in [BEP 47](http://bittorrent.org/beps/bep_0047.html).
+///
+/// See [`File::attributes()`].
+///
+/// [`File::attributes()`]: struct.File.html#method.attributes
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FileAttributes {
+    /// The file is a padding file (`p`), whose content should be all zeros.
+    pub padding: bool,
+    /// The file is executable (`x`).
+    pub executable: bool,
+    /// The file is hidden (`h`).
+    pub hidden: bool,
+    /// The file is a symbolic link (`l`). Its target is given by [`File::symlink_path()`].
+    ///
+    /// [`File::symlink_path()`]: struct.File.html#method.symlink_path
+    pub symlink: bool,
 }
 
 /// Everything found in a *.torrent* file.
 ///
 /// Modeled after the specifications
 /// in [BEP 3](http://bittorrent.org/beps/bep_0003.html) and
-/// [BEP 12](http://bittorrent.org/beps/bep_0012.html). Unknown/extension
+/// [BEP 12](http://bittorrent.org/beps/bep_0012.html), along with the `nodes`,
+/// `url-list` and `httpseeds` extensions. Other unknown/extension
 /// fields will be placed in `extra_fields` (if the unknown
 /// fields are found in the `info` dictionary then they are placed in
-/// `extra_info_fields`). If you need any of those extra fields you would
+/// `info.extra_info_fields`). If you need any of those extra fields you would
 /// have to parse it yourself.
+///
+/// Fields of the `info` dictionary are kept in an [`InfoSection`], which
+/// can be read freely but has to be taken apart with [`into_mut()`] to be
+/// changed, since that changes the info hash and breaks cross-seeding with
+/// the original torrent. Use [`edit()`] to get a [`TorrentEditor`], which
+/// only allows changes that preserve the info hash.
+///
+/// [`InfoSection`]: struct.InfoSection.html
+/// [`into_mut()`]: struct.InfoSection.html#method.into_mut
+/// [`edit()`]: #method.edit
+/// [`TorrentEditor`]: struct.TorrentEditor.html
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Torrent {
     /// URL of the torrent's tracker.
     pub announce: Option<String>,
     /// Announce list as defined in [BEP 12](http://bittorrent.org/beps/bep_0012.html).
     pub announce_list: Option<AnnounceList>,
+    /// DHT bootstrap nodes (`[host, port]` pairs) as defined in
+    /// [BEP 5](http://bittorrent.org/beps/bep_0005.html).
+    pub nodes: Option<Vec<(String, u16)>>,
+    /// Web seeds (`url-list`) as defined in
+    /// [BEP 19](http://bittorrent.org/beps/bep_0019.html).
+    pub url_list: Option<Vec<String>>,
+    /// HTTP seeds as defined in [BEP 17](http://bittorrent.org/beps/bep_0017.html).
+    pub httpseeds: Option<Vec<String>>,
+    /// Fields of the `info` dictionary, from which the info hash is calculated.
+    pub info: InfoSection,
+    /// Top-level fields not defined in [BEP 3](http://bittorrent.org/beps/bep_0003.html).
+    pub extra_fields: Option<Dictionary>,
+    /// Top-level fields whose keys are not valid UTF-8
+    /// (and thus can't be in `extra_fields`).
+    pub raw_extra_fields: Option<RawDictionary>,
+}
+
+/// Fields of a torrent's `info` dictionary.
+///
+/// Changing any of them changes the info hash. In a [`Torrent`] they are
+/// wrapped in an [`InfoSection`], which only gives out an `InfoFields`
+/// through [`InfoSection::into_mut()`].
+///
+/// [`Torrent`]: struct.Torrent.html
+/// [`InfoSection`]: struct.InfoSection.html
+/// [`InfoSection::into_mut()`]: struct.InfoSection.html#method.into_mut
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InfoFields {
     /// Total torrent size in bytes (i.e. sum of all files' sizes).
     pub length: Integer,
     /// If the torrent contains only 1 file then `files` is `None`.
@@ -80,11 +182,242 @@ pub struct Torrent {
     /// Block size in bytes.
     pub piece_length: Integer,
     /// SHA1 hashes of each block.
-    pub pieces: Vec<Piece>,
+    pub pieces: Pieces,
+    /// Fields in `info` not defined in [BEP 3](http://bittorrent.org/beps/bep_0003.html).
+    pub extra_info_fields: Option<Dictionary>,
+    /// Fields in `info` whose keys are not valid UTF-8
+    /// (and thus can't be in `extra_info_fields`).
+    pub raw_extra_info_fields: Option<RawDictionary>,
+}
+
+/// [`InfoFields`] that can be read through `Deref`, but not changed in place.
+///
+/// Changing them requires [`into_mut()`], which makes it explicit that the
+/// info hash will change. The changed fields can then be put back with
+/// `From<InfoFields>`.
+///
+/// # Example
+///
+#[cfg_attr(feature = "fs", doc = "```no_run")]
+#[cfg_attr(not(feature = "fs"), doc = "```ignore")]
+/// use lava_torrent::torrent::v1::Torrent;
+///
+/// let mut torrent = Torrent::read_from_file("sample.torrent").unwrap();
+/// println!("{}", torrent.info.name);
+///
+/// // this changes the info hash
+/// let mut info = torrent.info.into_mut();
+/// info.name = "renamed".to_owned();
+/// torrent.info = info.into();
+/// ```
+///
+/// [`InfoFields`]: struct.InfoFields.html
+/// [`into_mut()`]: #method.into_mut
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InfoSection {
+    fields: InfoFields,
+}
+
+/// SHA1 hashes of all blocks of a torrent.
+///
+/// The hashes are stored back-to-back in a single buffer (just like
+/// in *.torrent* files) instead of being allocated one by one, which
+/// matters for large torrents with hundreds of thousands of pieces.
+/// Individual hashes can be accessed as [`Piece`]s by index or through [`iter()`].
+///
+/// [`Piece`]: type.Piece.html
+///
+/// [`iter()`]: #method.iter
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Pieces {
+    hashes: Vec<Piece>,
+}
+
+/// Everything in a torrent except its piece hashes.
+///
+/// Returned by [`Torrent::read_metadata_from_bytes()`] and
+/// [`Torrent::read_metadata_from_file()`], which validate `pieces`
+/// but do not decode it. This is cheaper than reading a full `Torrent`
+/// when only the listing of a torrent is needed (e.g. when indexing
+/// a large number of torrents).
+///
+/// Since the hashes are not kept, the info hash cannot be
+/// computed from `TorrentMetadata`.
+///
+/// [`Torrent::read_metadata_from_bytes()`]: struct.Torrent.html#method.read_metadata_from_bytes
+/// [`Torrent::read_metadata_from_file()`]: struct.Torrent.html#method.read_metadata_from_file
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TorrentMetadata {
+    /// URL of the torrent's tracker.
+    pub announce: Option<String>,
+    /// Announce list as defined in [BEP 12](http://bittorrent.org/beps/bep_0012.html).
+    pub announce_list: Option<AnnounceList>,
+    /// DHT bootstrap nodes (`[host, port]` pairs) as defined in
+    /// [BEP 5](http://bittorrent.org/beps/bep_0005.html).
+    pub nodes: Option<Vec<(String, u16)>>,
+    /// Web seeds (`url-list`) as defined in
+    /// [BEP 19](http://bittorrent.org/beps/bep_0019.html).
+    pub url_list: Option<Vec<String>>,
+    /// HTTP seeds as defined in [BEP 17](http://bittorrent.org/beps/bep_0017.html).
+    pub httpseeds: Option<Vec<String>>,
+    /// Total torrent size in bytes (i.e. sum of all files' sizes).
+    pub length: Integer,
+    /// If the torrent contains only 1 file then `files` is `None`.
+    pub files: Option<Vec<File>>,
+    /// If the torrent contains only 1 file then `name` is the file name.
+    /// Otherwise it's the suggested root directory's name.
+    pub name: String,
+    /// Block size in bytes.
+    pub piece_length: Integer,
+    /// Number of blocks (i.e. number of hashes in `pieces`).
+    pub num_pieces: usize,
     /// Top-level fields not defined in [BEP 3](http://bittorrent.org/beps/bep_0003.html).
     pub extra_fields: Option<Dictionary>,
     /// Fields in `info` not defined in [BEP 3](http://bittorrent.org/beps/bep_0003.html).
     pub extra_info_fields: Option<Dictionary>,
+    /// Top-level fields whose keys are not valid UTF-8
+    /// (and thus can't be in `extra_fields`).
+    pub raw_extra_fields: Option<RawDictionary>,
+    /// Fields in `info` whose keys are not valid UTF-8
+    /// (and thus can't be in `extra_info_fields`).
+    pub raw_extra_info_fields: Option<RawDictionary>,
+}
+
+/// A `Torrent` that can only be modified in ways that preserve its info hash.
+///
+/// Created by [`Torrent::edit()`]. Only top-level fields (i.e. fields
+/// outside of `info`) can be changed, so code that re-tags torrents
+/// (e.g. replacing trackers or adding a comment) can't accidentally
+/// break cross-seeding. The wrapped torrent can be read through `Deref`,
+/// and taken back with [`into_torrent()`].
+///
+/// [`Torrent::edit()`]: struct.Torrent.html#method.edit
+/// [`into_torrent()`]: #method.into_torrent
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TorrentEditor {
+    torrent: Torrent,
+}
+
+/// A `Torrent` (or a reference to one) that is compared and hashed by
+/// its info hash, so that it can be used as a key in a `HashMap` or
+/// `HashSet`, e.g. to deduplicate torrents.
+///
+/// Unlike the derived `PartialEq` of `Torrent`, which compares every field,
+/// torrents that only differ outside of `info` (e.g. in their trackers)
+/// are equal. The info hash is calculated once, when wrapping the torrent.
+/// The wrapped torrent can be read through `Deref`, but not changed,
+/// since that could change its info hash. It can be taken back with
+/// [`into_inner()`].
+///
+/// # Example
+///
+#[cfg_attr(feature = "fs", doc = "```no_run")]
+#[cfg_attr(not(feature = "fs"), doc = "```ignore")]
+/// use lava_torrent::torrent::v1::{ByInfoHash, Torrent};
+/// use std::collections::HashSet;
+///
+/// let torrents = vec![
+///     Torrent::read_from_file("sample1.torrent").unwrap(),
+///     Torrent::read_from_file("sample2.torrent").unwrap(),
+/// ];
+///
+/// let unique: HashSet<_> = torrents.iter().map(ByInfoHash::new).collect();
+/// println!("{} unique torrents", unique.len());
+/// ```
+///
+/// [`into_inner()`]: #method.into_inner
+#[derive(Clone, Debug)]
+pub struct ByInfoHash<T = Torrent> {
+    torrent: T,
+    info_hash: [u8; 20],
+}
+
+/// A `Torrent` whose `info` is backed by the exact bytes it was read from.
+///
+/// When reading a torrent, `info` is parsed into fields, and encoding them
+/// again might not give back the same bytes (e.g. if the original encoding
+/// is not canonical), which changes the info hash. `RawInfoTorrent` keeps
+/// the original bytes of `info`, and uses them verbatim when calculating
+/// the info hash and when encoding, so both are always the same as those
+/// of the original torrent.
+///
+/// The parsed torrent can be read through `Deref`, but not changed, since
+/// changes to `info` would not be reflected in its bytes. It can be taken
+/// back with [`into_torrent()`].
+///
+/// # Example
+///
+#[cfg_attr(feature = "fs", doc = "```no_run")]
+#[cfg_attr(not(feature = "fs"), doc = "```ignore")]
+/// use lava_torrent::torrent::v1::RawInfoTorrent;
+///
+/// let torrent = RawInfoTorrent::read_from_file("sample.torrent").unwrap();
+/// println!("{}: {}", torrent.info.name, torrent.info_hash());
+/// torrent.write_into_file("copy.torrent").unwrap();
+/// ```
+///
+/// [`into_torrent()`]: #method.into_torrent
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RawInfoTorrent {
+    torrent: Torrent,
+    info_bytes: Vec<u8>,
+}
+
+/// A collection of torrents, keyed by info hash.
+///
+/// Each info hash is only indexed once, so duplicates (i.e. torrents
+/// that only differ outside of `info`) are detected when they are
+/// inserted. Torrents can be looked up by their full info hash, or
+/// by a prefix of its hex representation (like abbreviated git hashes).
+///
+/// Only v1 info hashes are supported.
+///
+/// # Example
+///
+#[cfg_attr(feature = "fs", doc = "```no_run")]
+#[cfg_attr(not(feature = "fs"), doc = "```ignore")]
+/// use lava_torrent::torrent::v1::TorrentIndex;
+///
+/// let mut index = TorrentIndex::new();
+/// for path in ["sample1.torrent", "sample2.torrent"] {
+///     if index.insert_file(path).unwrap().is_some() {
+///         println!("{} is a duplicate", path);
+///     }
+/// }
+///
+/// for torrent in index.find_by_prefix("3f9a").unwrap() {
+///     println!("{}", torrent.info.name);
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TorrentIndex {
+    torrents: BTreeMap<[u8; 20], Torrent>,
+}
+
+/// How strictly a torrent is validated when it is read.
+///
+/// See [`Torrent::read_from_bytes_with_policy()`].
+///
+/// [`Torrent::read_from_bytes_with_policy()`]: struct.Torrent.html#method.read_from_bytes_with_policy
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ValidationPolicy {
+    /// Like `Standard`, but the bencode must also be canonical (see
+    /// [`BencodeElem::from_bytes_strict()`]) and the number of pieces must
+    /// exactly match the torrent's length. `name` and `path`s must be valid UTF-8,
+    /// even if `name.utf-8` or `path.utf-8` could be used instead.
+    ///
+    /// [`BencodeElem::from_bytes_strict()`]: ../../bencode/enum.BencodeElem.html#method.from_bytes_strict
+    Strict,
+    /// The torrent's length must be positive and must not exceed the total
+    /// length of its pieces. This is what [`Torrent::read_from_bytes()`] does.
+    ///
+    /// [`Torrent::read_from_bytes()`]: struct.Torrent.html#method.read_from_bytes
+    #[default]
+    Standard,
+    /// The torrent's length is not checked against its pieces, so that
+    /// real-world torrents that violate this rule can still be read.
+    /// Fields must still be well-formed.
+    Lenient,
 }
 
 /// Builder for creating `Torrent`s from files.
@@ -118,13 +451,21 @@ pub struct Torrent {
 /// Therefore these files are ignored for now.
 /// Clients like Deluge and qBittorrent also ignore hidden entries.
 ///
+/// ## Zero-Length Files
+///
+/// By default, zero-length files are included like any other file.
+/// Since some trackers reject torrents with such files, they can
+/// also be skipped or treated as errors instead. See
+/// [`set_zero_length_file_policy()`] and [`ZeroLengthFilePolicy`].
+///
 /// ## Parallel Hashing
 ///
 /// By default, pieces are hashed in parallel. The default level of
 /// parallelism is equal to the number of physical cores. To adjust
 /// the parallelism level or to force single-threaded hashing, use
 /// [`set_num_threads()`]. Note that this setting is **specific to
-/// each builder and not global**.
+/// each builder and not global**. Without the `parallel` feature,
+/// pieces are always hashed on the current thread.
 ///
 /// [`Torrent::read_from_file()`]: struct.Torrent.html#method.read_from_file
 /// [`Torrent::read_from_bytes()`]: struct.Torrent.html#method.read_from_bytes
@@ -132,6 +473,8 @@ pub struct Torrent {
 /// [`set_announce()`]: #method.set_announce
 /// [BEP 47]: http://bittorrent.org/beps/bep_0047.html
 /// [`set_num_threads()`]: #method.set_num_threads
+/// [`set_zero_length_file_policy()`]: #method.set_zero_length_file_policy
+/// [`ZeroLengthFilePolicy`]: enum.ZeroLengthFilePolicy.html
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct TorrentBuilder {
     announce: Option<String>,
@@ -142,27 +485,527 @@ pub struct TorrentBuilder {
     extra_fields: Option<Dictionary>,
     extra_info_fields: Option<Dictionary>,
     is_private: bool,
+    #[cfg(feature = "fs")]
     num_threads: usize,
+    #[cfg(feature = "fs")]
+    read_rate_limiter: Option<util::RateLimiter>,
+    #[cfg(feature = "fs")]
+    low_priority_io: bool,
+    web_seeds: Option<Vec<String>>,
+    httpseeds: Option<Vec<String>>,
+    nodes: Option<Vec<(String, u16)>>,
+    file_sha1: bool,
+    #[cfg(feature = "rand")]
+    has_entropy: bool,
+    merkle: bool,
+    validates_announce: bool,
+    zero_length_file_policy: ZeroLengthFilePolicy,
+}
+
+/// How [`TorrentBuilder`] handles zero-length files.
+///
+/// [`TorrentBuilder`]: struct.TorrentBuilder.html
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ZeroLengthFilePolicy {
+    /// Zero-length files are included in the `Torrent`. This is the default.
+    #[default]
+    Include,
+    /// Zero-length files are left out of the `Torrent`.
+    Skip,
+    /// Building fails if a zero-length file is found.
+    Error,
 }
 
 /// Handle for non-blocking torrent builds.
 ///
+/// Dropping the handle cancels the build and waits for its
+/// worker thread to stop, so no thread is left hashing in the background.
+///
 /// See [`TorrentBuilder::build_non_blocking()`] for an example.
 ///
 /// [`TorrentBuilder::build_non_blocking()`]: struct.TorrentBuilder.html#method.build_non_blocking
+#[cfg(feature = "fs")]
 #[derive(Debug)]
 pub struct TorrentBuild {
     n_piece_processed: Arc<AtomicU64>,
     n_piece_total: Arc<AtomicU64>,
     is_canceled: Arc<AtomicBool>,
+    events: BuildEventSlot,
+    files: Arc<Mutex<BuildFiles>>,
     builder_thread: Option<JoinHandle<Result<Torrent, LavaTorrentError>>>,
 }
 
+/// Progress of a file in a non-blocking torrent build.
+///
+/// See [`TorrentBuild::get_file_progress()`].
+///
+/// [`TorrentBuild::get_file_progress()`]: struct.TorrentBuild.html#method.get_file_progress
+#[cfg(feature = "fs")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileProgress {
+    /// Path of the file on disk.
+    pub path: PathBuf,
+    /// Length of the file.
+    pub length: u64,
+    /// Number of bytes that have been read and hashed so far.
+    pub n_bytes_processed: u64,
+}
+
+/// Progress event of a non-blocking torrent build.
+///
+/// See [`TorrentBuild::progress_receiver()`].
+///
+/// [`TorrentBuild::progress_receiver()`]: struct.TorrentBuild.html#method.progress_receiver
+#[cfg(feature = "fs")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum BuildEvent {
+    /// Reading of the file at `path` (on disk) has started.
+    /// This is not sent for zero-length files, which are not read.
+    FileStarted { path: PathBuf },
+    /// The file at `path` (on disk) has been read completely.
+    /// This is not sent for zero-length files, which are not read.
+    FileFinished { path: PathBuf },
+    /// A piece has been hashed.
+    PieceHashed {
+        n_piece_processed: u64,
+        n_piece_total: u64,
+    },
+    /// The build has succeeded, and [`TorrentBuild::get_output()`]
+    /// will return the `Torrent`.
+    ///
+    /// [`TorrentBuild::get_output()`]: struct.TorrentBuild.html#method.get_output
+    Finished,
+    /// The build has failed (or has been canceled), and [`TorrentBuild::get_output()`]
+    /// will return the error, which `message` describes.
+    ///
+    /// [`TorrentBuild::get_output()`]: struct.TorrentBuild.html#method.get_output
+    Failed { message: String },
+}
+
+/// Builder for creating many `Torrent`s in one go.
+///
+/// Each job is described by a [`TorrentBuilder`], so every `Torrent` can have
+/// its own settings. All jobs share a single pool of worker threads and are
+/// processed one after another, so that they don't compete with each
+/// other for IO. As a result, the `num_threads` setting of each
+/// `TorrentBuilder` is ignored in favor of [`set_num_threads()`].
+///
+/// [`TorrentBuilder`]: struct.TorrentBuilder.html
+/// [`set_num_threads()`]: #method.set_num_threads
+#[cfg(feature = "fs")]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BatchBuilder {
+    builders: Vec<TorrentBuilder>,
+    num_threads: usize,
+}
+
+/// Handle for non-blocking batch builds.
+///
+/// Dropping the handle cancels the batch build and waits for its
+/// worker thread to stop, so no thread is left hashing in the background.
+///
+/// See [`BatchBuilder::build_non_blocking()`] for an example.
+///
+/// [`BatchBuilder::build_non_blocking()`]: struct.BatchBuilder.html#method.build_non_blocking
+#[cfg(feature = "fs")]
+#[derive(Debug)]
+pub struct BatchBuild {
+    jobs: Vec<(Arc<AtomicU64>, Arc<AtomicU64>)>, // (n_piece_processed, n_piece_total)
+    n_job_finished: Arc<AtomicUsize>,
+    is_canceled: Arc<AtomicBool>,
+    builder_thread: Option<JoinHandle<Vec<Result<Torrent, LavaTorrentError>>>>,
+}
+
+/// Handle for non-blocking torrent verifications.
+///
+/// Dropping the handle cancels the verification and waits for its
+/// worker thread to stop, so no thread is left hashing in the background.
+///
+/// See [`Torrent::verify_non_blocking()`] for an example.
+///
+/// [`Torrent::verify_non_blocking()`]: struct.Torrent.html#method.verify_non_blocking
+#[cfg(feature = "fs")]
+#[derive(Debug)]
+pub struct TorrentVerification {
+    n_piece_processed: Arc<AtomicU64>,
+    n_piece_total: Arc<AtomicU64>,
+    is_canceled: Arc<AtomicBool>,
+    verifier_thread: Option<JoinHandle<Result<VerificationReport, LavaTorrentError>>>,
+}
+
+/// Result of verifying a torrent's content against its `pieces`.
+///
+/// See [`Torrent::verify()`].
+///
+/// [`Torrent::verify()`]: struct.Torrent.html#method.verify
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct VerificationReport {
+    /// Indices of the pieces that match the content on disk, in ascending order.
+    pub verified_pieces: Vec<usize>,
+    /// Pieces that do not match the content on disk, ordered by index.
+    pub failed_pieces: Vec<FailedPiece>,
+    /// Total number of bytes in the pieces that matched.
+    pub verified_bytes: u64,
+    /// Total number of bytes in the pieces that did not match.
+    pub failed_bytes: u64,
+}
+
+/// A piece that failed verification.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FailedPiece {
+    /// Index of the piece in `pieces`.
+    pub index: usize,
+    /// Byte range covered by the piece, relative to the start of the content
+    /// (i.e. all files concatenated in order).
+    pub range: Range<u64>,
+    /// Files covered by the piece, as `(index into files, byte range within the file)`.
+    /// For single-file torrents, the file index is always `0`.
+    pub files: Vec<(usize, Range<u64>)>,
+}
+
+/// Result of verifying a contiguous range of a torrent's content.
+///
+/// All ranges are relative to the start of the content
+/// (i.e. all files concatenated in order).
+///
+/// See [`Torrent::verify_range()`].
+///
+/// [`Torrent::verify_range()`]: struct.Torrent.html#method.verify_range
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RangeVerification {
+    /// Indices of the pieces that matched, in ascending order.
+    pub verified_pieces: Vec<usize>,
+    /// Indices of the pieces that did not match, in ascending order.
+    pub failed_pieces: Vec<usize>,
+    /// Bytes at the start of the range that could not be verified,
+    /// since the piece they belong to starts before the range.
+    pub unverified_prefix: Range<u64>,
+    /// Bytes at the end of the range that could not be verified,
+    /// since the piece they belong to ends after the range.
+    pub unverified_suffix: Range<u64>,
+}
+
+/// Result of checking which files of a torrent are present on disk.
+///
+/// See [`Torrent::check_content()`].
+///
+/// [`Torrent::check_content()`]: struct.Torrent.html#method.check_content
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ContentCheck {
+    /// Status of each file, in the same order as `files`.
+    /// For single-file torrents, this contains exactly one element.
+    pub files: Vec<FileStatus>,
+    /// Estimated number of bytes present on disk.
+    pub present_bytes: u64,
+    /// Total number of bytes in the content.
+    pub total_bytes: u64,
+}
+
+/// Status of a file found by [`Torrent::check_content()`].
+///
+/// [`Torrent::check_content()`]: struct.Torrent.html#method.check_content
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FileStatus {
+    /// The file does not exist (or is not a regular file).
+    Missing,
+    /// The file exists but its length differs from the expected one.
+    WrongLength {
+        /// Length of the file on disk.
+        actual: u64,
+    },
+    /// The file exists and has the expected length.
+    Present,
+}
+
+/// Range of pieces that a file lies in.
+///
+/// See [`Torrent::pieces_for_file()`].
+///
+/// [`Torrent::pieces_for_file()`]: struct.Torrent.html#method.pieces_for_file
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FilePieces {
+    /// Index of the first piece that contains data from the file.
+    pub first_piece: usize,
+    /// Index of the last piece that contains data from the file (inclusive).
+    pub last_piece: usize,
+    /// Offset within the first piece at which the file starts.
+    /// If this is not 0, the first piece also contains data from the previous file(s).
+    pub first_piece_offset: u64,
+    /// Offset within the last piece at which the file ends (exclusive).
+    /// If this is less than the size of the last piece, the last piece
+    /// also contains data from the next file(s).
+    pub last_piece_end: u64,
+}
+
+/// How well the files of a torrent are aligned to piece boundaries.
+///
+/// A piece that contains data from multiple files has to be downloaded in
+/// full even if only one of those files is wanted, so poorly aligned torrents
+/// waste bandwidth with selective downloading. Such torrents can be
+/// rebuilt with padding files between the files that share pieces.
+///
+/// See [`Torrent::alignment_report()`].
+///
+/// [`Torrent::alignment_report()`]: struct.Torrent.html#method.alignment_report
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AlignmentReport {
+    /// Alignment of each file, in the same order as `files`.
+    /// For single-file torrents, this contains exactly one element.
+    pub files: Vec<FileAlignment>,
+    /// Number of pieces that contain data from more than one file
+    /// (padding files excluded).
+    pub shared_pieces: usize,
+}
+
+/// Alignment of a single file. See [`AlignmentReport`].
+///
+/// [`AlignmentReport`]: struct.AlignmentReport.html
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FileAlignment {
+    /// Pieces that the file lies in, or `None` if the file is empty.
+    pub pieces: Option<FilePieces>,
+    /// Whether the file starts at the beginning of a piece.
+    /// Always `true` for empty files.
+    pub starts_aligned: bool,
+    /// Whether the file ends at the end of a piece (or at the end of
+    /// the content). Always `true` for empty files.
+    pub ends_aligned: bool,
+    /// Number of the file's pieces (at most 2, i.e. its first and last pieces)
+    /// that also contain data from other files (padding files excluded).
+    pub shared_pieces: usize,
+}
+
+/// [libtorrent](https://www.libtorrent.org/manual-ref.html#fast-resume) fast-resume
+/// data, as saved in the `.fastresume` files of libtorrent-based clients
+/// (e.g. qBittorrent, Deluge).
+///
+/// See [`FastResume::read_from_file()`]. Such data can be generated
+/// with [`Torrent::fast_resume_data()`].
+///
+/// [`FastResume::read_from_file()`]: #method.read_from_file
+/// [`Torrent::fast_resume_data()`]: struct.Torrent.html#method.fast_resume_data
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FastResume {
+    /// Info hash of the torrent (`info-hash`), as raw bytes.
+    pub info_hash: Vec<u8>,
+    /// Name of the torrent (`name`).
+    pub name: Option<String>,
+    /// Directory that contains the content (`save_path`, or
+    /// qBittorrent's `qBt-savePath` if `save_path` is missing).
+    pub save_path: Option<String>,
+    /// Whether each piece has been downloaded (`pieces`), in piece order.
+    /// Empty if the client did not save which pieces it has.
+    pub pieces: Vec<bool>,
+    /// Trackers (`trackers`), grouped into tiers like `announce_list`.
+    pub trackers: AnnounceList,
+    /// All other fields (e.g. `total_uploaded`, the embedded `info`,
+    /// or qBittorrent's `qBt-*` fields).
+    pub extra_fields: Option<Dictionary>,
+}
+
+/// rtorrent's state of a torrent, as saved in the `<info hash>.torrent.rtorrent`
+/// files of its session directory.
+///
+/// Fields that are not listed here are kept in `extra_fields`,
+/// so reading and writing a file back gives the same data.
+///
+/// See [`RtorrentState::read_from_file()`] and [`RtorrentState::into_bencode()`].
+///
+/// [`RtorrentState::read_from_file()`]: #method.read_from_file
+/// [`RtorrentState::into_bencode()`]: #method.into_bencode
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RtorrentState {
+    /// Directory that the content is saved to (`directory`). For multi-file
+    /// torrents, this is the content directory itself, not its parent.
+    pub directory: Option<String>,
+    /// `1` if the torrent is started, `0` if it is stopped (`state`).
+    pub state: Option<Integer>,
+    /// `1` if the content is completely downloaded, `0` otherwise (`complete`).
+    pub complete: Option<Integer>,
+    /// All other fields (e.g. `custom1`, which ruTorrent uses as label).
+    pub extra_fields: Option<Dictionary>,
+}
+
+/// libtorrent's (i.e. rtorrent's library, not to be confused with
+/// [`FastResume`]) resume data, as saved in the
+/// `<info hash>.torrent.libtorrent_resume` files of rtorrent's session directory.
+///
+/// Fields that are not listed here are kept in `extra_fields`,
+/// so reading and writing a file back gives the same data.
+///
+/// See [`RtorrentResume::read_from_file()`] and [`RtorrentResume::into_bencode()`].
+///
+/// [`FastResume`]: struct.FastResume.html
+/// [`RtorrentResume::read_from_file()`]: #method.read_from_file
+/// [`RtorrentResume::into_bencode()`]: #method.into_bencode
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RtorrentResume {
+    /// Which pieces have been downloaded (`bitfield`).
+    pub bitfield: Option<RtorrentBitfield>,
+    /// All other fields (e.g. `files`, which holds the mtime of
+    /// each file, or `trackers`, which holds the state of each tracker).
+    pub extra_fields: Option<Dictionary>,
+}
+
+/// Which pieces have been downloaded, as saved by rtorrent.
+///
+/// See [`RtorrentResume`].
+///
+/// [`RtorrentResume`]: struct.RtorrentResume.html
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RtorrentBitfield {
+    /// All pieces have been downloaded. rtorrent only saves the number of pieces.
+    Complete(Integer),
+    /// One bit per piece, with the most significant bit of the
+    /// first byte being the first piece (like BitTorrent's `bitfield` message).
+    Partial(Vec<u8>),
+}
+
+/// Differences between two `Torrent`s.
+///
+/// See [`Torrent::diff()`]. For single-file torrents, the file's path is
+/// considered to be the torrent's `name`.
+///
+/// [`Torrent::diff()`]: struct.Torrent.html#method.diff
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TorrentDiff {
+    /// Trackers (from `announce` and `announce_list`) only found in the other torrent.
+    pub trackers_added: Vec<String>,
+    /// Trackers (from `announce` and `announce_list`) only found in this torrent.
+    pub trackers_removed: Vec<String>,
+    /// DHT nodes only found in the other torrent.
+    pub nodes_added: Vec<(String, u16)>,
+    /// DHT nodes only found in this torrent.
+    pub nodes_removed: Vec<(String, u16)>,
+    /// Web seeds (from `url_list`) only found in the other torrent.
+    pub web_seeds_added: Vec<String>,
+    /// Web seeds (from `url_list`) only found in this torrent.
+    pub web_seeds_removed: Vec<String>,
+    /// HTTP seeds (from `httpseeds`) only found in the other torrent.
+    pub httpseeds_added: Vec<String>,
+    /// HTTP seeds (from `httpseeds`) only found in this torrent.
+    pub httpseeds_removed: Vec<String>,
+    /// `name` of this torrent and the other torrent, if they differ.
+    pub name: Option<(String, String)>,
+    /// Paths of files only found in the other torrent.
+    pub files_added: Vec<PathBuf>,
+    /// Paths of files only found in this torrent.
+    pub files_removed: Vec<PathBuf>,
+    /// Files found in both torrents but with different lengths,
+    /// as `(path, length in this torrent, length in the other torrent)`.
+    pub files_resized: Vec<(PathBuf, Integer, Integer)>,
+    /// `piece_length` of this torrent and the other torrent, if they differ.
+    pub piece_length: Option<(Integer, Integer)>,
+    /// Whether `pieces` differ.
+    pub pieces_changed: bool,
+    /// Differences in `extra_fields`.
+    pub extra_fields: DictionaryDiff,
+    /// Differences in `extra_info_fields`.
+    pub extra_info_fields: DictionaryDiff,
+}
+
+/// Differences between two [`Dictionary`]s, by key.
+///
+/// [`Dictionary`]: type.Dictionary.html
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DictionaryDiff {
+    /// Keys only found in the other dictionary.
+    pub added: Vec<String>,
+    /// Keys only found in this dictionary.
+    pub removed: Vec<String>,
+    /// Keys found in both dictionaries but with different values.
+    pub changed: Vec<String>,
+}
+
+/// A copy of a torrent for another tracker, along with whether
+/// its info hash changed.
+///
+/// See [`Torrent::clone_for_tracker()`].
+///
+/// [`Torrent::clone_for_tracker()`]: struct.Torrent.html#method.clone_for_tracker
+#[cfg(feature = "rand")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrackerClone {
+    /// The copy, with the new tracker.
+    pub torrent: Torrent,
+    /// Whether the info hash of `torrent` differs from the original's.
+    /// If so, it joins a separate swarm, and clients have to recheck
+    /// the content (e.g. with [`Torrent::verify()`]) before seeding it.
+    ///
+    /// [`Torrent::verify()`]: struct.Torrent.html#method.verify
+    pub info_hash_changed: bool,
+}
+
+/// All problems found by [`Torrent::validate_report()`].
+///
+/// [`Torrent::validate_report()`]: struct.Torrent.html#method.validate_report
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ValidationReport {
+    /// Problems found, in the order they were detected.
+    pub issues: Vec<ValidationIssue>,
+}
+
+/// A single problem found by [`Torrent::validate_report()`].
+///
+/// [`Torrent::validate_report()`]: struct.Torrent.html#method.validate_report
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ValidationIssue {
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// Human-readable description of the problem.
+    pub message: String,
+}
+
+/// Severity of a [`ValidationIssue`].
+///
+/// [`ValidationIssue`]: struct.ValidationIssue.html
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    /// The torrent is usable, but something about it is questionable.
+    Warning,
+    /// The torrent is invalid, and clients will likely reject it.
+    Error,
+}
+
+// Position of a file in the content.
+struct Span {
+    offset: u64,
+    length: u64,
+}
+
+#[cfg(feature = "fs")]
 #[derive(Clone, Debug)]
 struct TorrentBuildInternal {
     n_piece_processed: Arc<AtomicU64>,
     n_piece_total: Arc<AtomicU64>,
     is_canceled: Arc<AtomicBool>,
+    events: BuildEventSlot,
+    files: Arc<Mutex<BuildFiles>>,
+}
+
+// Per-file progress of a non-blocking build.
+#[cfg(feature = "fs")]
+#[derive(Debug, Default)]
+struct BuildFiles {
+    files: Vec<FileProgress>,
+    // index of the file that was started last, unless it has finished
+    current: Option<usize>,
+}
+
+// Where a non-blocking build sends its `BuildEvent`s.
+#[cfg(feature = "fs")]
+type BuildEventSlot = Arc<Mutex<BuildEventSender>>;
+
+#[cfg(feature = "fs")]
+#[derive(Debug, Default)]
+enum BuildEventSender {
+    // nobody is listening (yet)
+    #[default]
+    Disconnected,
+    Connected(Sender<BuildEvent>),
+    // the build has finished, so no more events will be sent
+    Closed,
 }
 
 impl File {
@@ -185,29 +1028,153 @@ impl File {
             )))
         }
     }
-}
 
-impl Torrent {
-    /// Construct the `info` dict based on the fields of `self`.
+    /// Get the attributes of this file as defined in
+    /// [BEP 47](http://bittorrent.org/beps/bep_0047.html).
     ///
-    /// Certain operations on torrents, such as calculating info
-    /// hashes, require the extracted `info` dict. This
-    /// convenience method does that.
+    /// `self.extra_fields["attr"]` must be a string. Unknown attributes are ignored.
+    /// If it does not exist, all attributes will be `false`.
+    pub fn attributes(&self) -> Result<FileAttributes, LavaTorrentError> {
+        match self.extra_field("attr") {
+            Some(BencodeElem::String(attr)) => Ok(FileAttributes {
+                padding: attr.contains('p'),
+                executable: attr.contains('x'),
+                hidden: attr.contains('h'),
+                symlink: attr.contains('l'),
+            }),
+            Some(_) => Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::WrongType {
+                    field: Cow::Borrowed("attr"),
+                    expected: ValueType::String,
+                },
+            )),
+            None => Ok(FileAttributes::default()),
+        }
+    }
+
+    /// Get the target of this file if it's a symbolic link, as defined in
+    /// [BEP 47](http://bittorrent.org/beps/bep_0047.html).
     ///
-    /// Note that the `info` dict
-    /// is constructed each time this method is called (i.e.
-    /// the return value is not cached). If caching is needed
-    /// then the caller should handle that.
+    /// The target is relative to the torrent's root, like `self.path`.
+    /// `self.extra_fields["symlink path"]` must be a list of strings.
+    /// If it does not exist, `Ok(None)` will be returned.
+    pub fn symlink_path(&self) -> Result<Option<PathBuf>, LavaTorrentError> {
+        match self.extra_field("symlink path") {
+            Some(BencodeElem::List(components)) => Ok(Some(
+                components
+                    .iter()
+                    .map(|component| match component {
+                        BencodeElem::String(component) => Ok(component.as_str()),
+                        _ => Err(LavaTorrentError::MalformedTorrent(
+                            TorrentErrorKind::WrongElementType {
+                                field: Cow::Borrowed("symlink path"),
+                                expected: ValueType::String,
+                            },
+                        )),
+                    })
+                    .collect::<Result<PathBuf, LavaTorrentError>>()?,
+            )),
+            Some(_) => Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::WrongType {
+                    field: Cow::Borrowed("symlink path"),
+                    expected: ValueType::List,
+                },
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the SHA1 hash of this file's content, as defined in
+    /// [BEP 47](http://bittorrent.org/beps/bep_0047.html).
     ///
-    /// Since `self` is taken by reference, and the result is
-    /// returned by value, certain values will be cloned. Please
-    /// be aware of this overhead.
-    pub fn construct_info(&self) -> BencodeElem {
-        let mut info: HashMap<String, BencodeElem> = HashMap::new();
+    /// `self.extra_fields["sha1"]` must be a 20-byte string.
+    /// If it does not exist, `Ok(None)` will be returned.
+    pub fn sha1(&self) -> Result<Option<&[u8]>, LavaTorrentError> {
+        match self.extra_field("sha1").map(BencodeElem::as_bytes) {
+            Some(Some(sha1)) if sha1.len() == PIECE_STRING_LENGTH => Ok(Some(sha1)),
+            Some(_) => Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                Cow::Borrowed(r#""sha1" is not a 20-byte string."#),
+            ))),
+            None => Ok(None),
+        }
+    }
 
-        if let Some(ref files) = self.files {
-            info.insert(
-                "files".to_owned(),
+    /// Check if this file is a padding file, i.e. if it has the `p` attribute
+    /// ([BEP 47](http://bittorrent.org/beps/bep_0047.html)) or if it's in a
+    /// top-level `.pad` directory (a convention that predates BEP 47).
+    ///
+    /// A malformed `attr` is treated as having no attributes.
+    pub fn is_padding(&self) -> bool {
+        self.attributes().is_ok_and(|attr| attr.padding)
+            || self
+                .path
+                .components()
+                .next()
+                .is_some_and(|component| component.as_os_str() == ".pad")
+    }
+
+    /// Get the original components of this file's path, if they are not
+    /// what `self.path` holds.
+    ///
+    /// This is the case if the path is not valid UTF-8, in which case
+    /// `self.path` contains either `path.utf-8`, the output of a decoder
+    /// (see [`Torrent::read_from_bytes_with_decoder()`]), or a lossy
+    /// conversion of the original components. The original is kept in
+    /// `self.extra_fields["path"]`, so that encoding the torrent
+    /// preserves its info hash. Otherwise `None` will be returned.
+    ///
+    /// [`Torrent::read_from_bytes_with_decoder()`]: struct.Torrent.html#method.read_from_bytes_with_decoder
+    pub fn raw_path(&self) -> Option<Vec<&[u8]>> {
+        self.extra_field("path")
+            .and_then(BencodeElem::as_list)
+            .and_then(|components| components.iter().map(BencodeElem::as_bytes).collect())
+    }
+
+    fn extra_field(&self, key: &str) -> Option<&BencodeElem> {
+        self.extra_fields
+            .as_ref()
+            .and_then(|fields| fields.get(key))
+    }
+}
+
+impl FilePieces {
+    // `piece_length` must not be 0
+    fn new(span: &Span, piece_length: u64) -> Result<Option<FilePieces>, LavaTorrentError> {
+        if span.length == 0 {
+            return Ok(None);
+        }
+
+        let end = span.offset + span.length;
+        Ok(Some(FilePieces {
+            first_piece: util::u64_to_usize(span.offset / piece_length)?,
+            last_piece: util::u64_to_usize((end - 1) / piece_length)?,
+            first_piece_offset: span.offset % piece_length,
+            last_piece_end: (end - 1) % piece_length + 1,
+        }))
+    }
+}
+
+impl Torrent {
+    /// Construct the `info` dict based on the fields of `self`.
+    ///
+    /// Certain operations on torrents, such as calculating info
+    /// hashes, require the extracted `info` dict. This
+    /// convenience method does that.
+    ///
+    /// Note that the `info` dict
+    /// is constructed each time this method is called (i.e.
+    /// the return value is not cached). If caching is needed
+    /// then the caller should handle that.
+    ///
+    /// Since `self` is taken by reference, and the result is
+    /// returned by value, certain values will be cloned. Please
+    /// be aware of this overhead.
+    pub fn construct_info(&self) -> BencodeElem {
+        let mut info: HashMap<String, BencodeElem> = HashMap::new();
+
+        if let Some(ref files) = self.info.files {
+            info.insert(
+                "files".to_owned(),
                 BencodeElem::List(
                     files
                         .clone()
@@ -217,24 +1184,29 @@ impl Torrent {
                 ),
             );
         } else {
-            info.insert("length".to_owned(), BencodeElem::Integer(self.length));
+            info.insert("length".to_owned(), BencodeElem::Integer(self.info.length));
         }
 
-        info.insert("name".to_owned(), BencodeElem::String(self.name.clone()));
         info.insert(
-            "piece length".to_owned(),
-            BencodeElem::Integer(self.piece_length),
+            "name".to_owned(),
+            BencodeElem::String(self.info.name.clone()),
         );
         info.insert(
-            "pieces".to_owned(),
-            BencodeElem::Bytes(self.pieces.clone().into_iter().flatten().collect()),
+            "piece length".to_owned(),
+            BencodeElem::Integer(self.info.piece_length),
         );
+        if !self.is_merkle() {
+            info.insert(
+                "pieces".to_owned(),
+                BencodeElem::Bytes(self.info.pieces.as_bytes().to_vec()),
+            );
+        }
 
-        if let Some(ref extra_info_fields) = self.extra_info_fields {
+        if let Some(ref extra_info_fields) = self.info.extra_info_fields {
             info.extend(extra_info_fields.clone());
         }
 
-        BencodeElem::Dictionary(info)
+        write::dictionary_elem(info, self.info.raw_extra_info_fields.clone())
     }
 
     /// Calculate the `Torrent`'s info hash as defined in
@@ -276,8 +1248,10 @@ impl Torrent {
     ///
     /// The `x.pe` parameter (for peer addresses) is currently not supported.
     ///
-    /// `self.extra_fields["url-list"]` will be used to construct `ws` parameters.
-    /// It must be either a string or a list of strings.
+    /// `self.url_list` will be used to construct `ws` parameters.
+    ///
+    /// `self.httpseeds` ([BEP 17](http://bittorrent.org/beps/bep_0017.html))
+    /// will be used to construct `as` (acceptable source) parameters.
     pub fn magnet_link(&self) -> Result<String, LavaTorrentError> {
         fn encode_component(from: &str) -> String {
             // percent_encoding escapes space as '%20', which is not accepted
@@ -305,58 +1279,239 @@ impl Torrent {
             String::new()
         };
 
-        let ws = match self
-            .extra_fields
-            .as_ref()
-            .and_then(|fields| fields.get("url-list"))
-        {
-            Some(BencodeElem::String(seed)) => Some(vec![seed]),
-            Some(BencodeElem::List(ref seeds)) => Some(
-                seeds
-                    .iter()
-                    .map(|elem| match elem {
-                        BencodeElem::String(url) => Ok(url),
-                        _ => {
-                            return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                                r#""url-list" is a list but contains a non-string element."#,
-                            )));
-                        }
-                    })
-                    .collect::<Result<Vec<&String>, LavaTorrentError>>()?,
-            ),
-            Some(_) => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""url-list" is neither a string nor a list."#,
-                )));
-            }
-            None => None,
-        };
-        let ws = match ws {
-            Some(ws) => ws
-                .iter()
-                .format_with("", |&url, f| {
-                    f(&format_args!("&ws={}", encode_component(url)))
-                })
-                .to_string(),
-            None => String::new(),
-        };
+        let ws = self
+            .url_list
+            .iter()
+            .flatten()
+            .format_with("", |url, f| {
+                f(&format_args!("&ws={}", encode_component(url)))
+            })
+            .to_string();
+        let xs = self
+            .httpseeds
+            .iter()
+            .flatten()
+            .format_with("", |url, f| {
+                f(&format_args!("&as={}", encode_component(url)))
+            })
+            .to_string();
 
         Ok(format!(
-            "magnet:?xt=urn:btih:{}&dn={}{}{}",
+            "magnet:?xt=urn:btih:{}&dn={}{}{}{}",
             self.info_hash(),
-            self.name,
+            self.info.name,
             tr,
             ws,
+            xs,
         ))
     }
 
+    /// Set the HTTP seeds of this torrent as defined in
+    /// [BEP 17](http://bittorrent.org/beps/bep_0017.html),
+    /// or remove them if `httpseeds` is `None`.
+    pub fn set_httpseeds(&mut self, httpseeds: Option<Vec<String>>) {
+        self.httpseeds = httpseeds;
+    }
+
+    /// Set the web seeds (`url-list`) of this torrent as defined in
+    /// [BEP 19](http://bittorrent.org/beps/bep_0019.html),
+    /// or remove them if `web_seeds` is `None`.
+    pub fn set_web_seeds(&mut self, web_seeds: Option<Vec<String>>) {
+        self.url_list = web_seeds;
+    }
+
+    /// Set the DHT bootstrap nodes of this torrent as defined in
+    /// [BEP 5](http://bittorrent.org/beps/bep_0005.html),
+    /// or remove them if `nodes` is `None`.
+    pub fn set_nodes(&mut self, nodes: Option<Vec<(String, u16)>>) {
+        self.nodes = nodes;
+    }
+
+    /// Get the info hashes of torrents that share files with this torrent,
+    /// as defined in [BEP 38](http://bittorrent.org/beps/bep_0038.html).
+    ///
+    /// `self.extra_info_fields["similar"]` must be a list of 20-byte strings.
+    /// If it does not exist, `Ok(None)` will be returned.
+    pub fn similar(&self) -> Result<Option<Vec<&[u8]>>, LavaTorrentError> {
+        extract_similar(&self.info.extra_info_fields)
+    }
+
+    /// Set the info hashes of torrents that share files with this torrent as defined in
+    /// [BEP 38](http://bittorrent.org/beps/bep_0038.html), or remove them if `similar` is `None`.
+    ///
+    /// Note that `similar` is part of `info`, so this changes the info hash.
+    pub fn set_similar(&mut self, similar: Option<Vec<[u8; PIECE_STRING_LENGTH]>>) {
+        self.replace_extra_info_field(
+            "similar",
+            similar.map(|hashes| {
+                BencodeElem::List(
+                    hashes
+                        .into_iter()
+                        .map(|hash| BencodeElem::Bytes(hash.to_vec()))
+                        .collect(),
+                )
+            }),
+        );
+    }
+
+    /// Get the names of the collections this torrent belongs to, as defined in
+    /// [BEP 38](http://bittorrent.org/beps/bep_0038.html).
+    ///
+    /// `self.extra_info_fields["collections"]` must be a list of strings.
+    /// If it does not exist, `Ok(None)` will be returned.
+    pub fn collections(&self) -> Result<Option<Vec<&str>>, LavaTorrentError> {
+        extract_collections(&self.info.extra_info_fields)
+    }
+
+    /// Set the names of the collections this torrent belongs to as defined in
+    /// [BEP 38](http://bittorrent.org/beps/bep_0038.html),
+    /// or remove them if `collections` is `None`.
+    ///
+    /// Note that `collections` is part of `info`, so this changes the info hash.
+    pub fn set_collections(&mut self, collections: Option<Vec<String>>) {
+        self.replace_extra_info_field(
+            "collections",
+            collections.map(|names| {
+                BencodeElem::List(names.into_iter().map(BencodeElem::String).collect())
+            }),
+        );
+    }
+
+    /// Get the `comment` of this torrent.
+    ///
+    /// `self.extra_fields["comment"]` must be a string.
+    /// If it does not exist, `Ok(None)` will be returned.
+    pub fn comment(&self) -> Result<Option<&str>, LavaTorrentError> {
+        self.extra_string("comment")
+    }
+
+    /// Get the `created by` field of this torrent, i.e. the program that created it.
+    ///
+    /// `self.extra_fields["created by"]` must be a string.
+    /// If it does not exist, `Ok(None)` will be returned.
+    pub fn created_by(&self) -> Result<Option<&str>, LavaTorrentError> {
+        self.extra_string("created by")
+    }
+
+    /// Get the `encoding` of this torrent, i.e. the encoding of its strings.
+    ///
+    /// `self.extra_fields["encoding"]` must be a string.
+    /// If it does not exist, `Ok(None)` will be returned.
+    pub fn encoding(&self) -> Result<Option<&str>, LavaTorrentError> {
+        self.extra_string("encoding")
+    }
+
+    /// Get the `creation date` of this torrent, in seconds since the UNIX epoch.
+    ///
+    /// `self.extra_fields["creation date"]` must be an integer.
+    /// If it does not exist, `Ok(None)` will be returned.
+    pub fn creation_date(&self) -> Result<Option<Integer>, LavaTorrentError> {
+        match self
+            .extra_fields
+            .as_ref()
+            .and_then(|fields| fields.get("creation date"))
+        {
+            Some(&BencodeElem::Integer(date)) => Ok(Some(date)),
+            Some(_) => Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::WrongType {
+                    field: Cow::Borrowed("creation date"),
+                    expected: ValueType::Integer,
+                },
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Set the `comment` of this torrent, or remove it if `comment` is `None`.
+    pub fn set_comment(&mut self, comment: Option<String>) {
+        self.replace_extra_field("comment", comment.map(BencodeElem::String));
+    }
+
+    /// Set the `created by` field of this torrent, or remove it if `created_by` is `None`.
+    pub fn set_created_by(&mut self, created_by: Option<String>) {
+        self.replace_extra_field("created by", created_by.map(BencodeElem::String));
+    }
+
+    /// Set the `encoding` of this torrent, or remove it if `encoding` is `None`.
+    pub fn set_encoding(&mut self, encoding: Option<String>) {
+        self.replace_extra_field("encoding", encoding.map(BencodeElem::String));
+    }
+
+    /// Set the `creation date` of this torrent (in seconds since the UNIX epoch),
+    /// or remove it if `creation_date` is `None`.
+    pub fn set_creation_date(&mut self, creation_date: Option<Integer>) {
+        self.replace_extra_field("creation date", creation_date.map(BencodeElem::Integer));
+    }
+
+    // Get the string stored under `key` in `self.extra_fields`.
+    fn extra_string(&self, key: &str) -> Result<Option<&str>, LavaTorrentError> {
+        match self
+            .extra_fields
+            .as_ref()
+            .and_then(|fields| fields.get(key))
+        {
+            Some(BencodeElem::String(string)) => Ok(Some(string)),
+            Some(_) => Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::WrongType {
+                    field: Cow::Owned(key.to_owned()),
+                    expected: ValueType::String,
+                },
+            )),
+            None => Ok(None),
+        }
+    }
+
+    // Set `key` in `self.extra_fields` to `val`, or remove it if `val` is `None`.
+    // `self.extra_fields` is set to `None` if nothing is left in it.
+    // The previous value is returned.
+    fn replace_extra_field(&mut self, key: &str, val: Option<BencodeElem>) -> Option<BencodeElem> {
+        match val {
+            Some(val) => self
+                .extra_fields
+                .get_or_insert_with(HashMap::new)
+                .insert(key.to_owned(), val),
+            None => {
+                let fields = self.extra_fields.as_mut()?;
+                let removed = fields.remove(key);
+                if fields.is_empty() {
+                    self.extra_fields = None;
+                }
+                removed
+            }
+        }
+    }
+
+    // Like `replace_extra_field()`, but for `extra_info_fields`.
+    fn replace_extra_info_field(
+        &mut self,
+        key: &str,
+        val: Option<BencodeElem>,
+    ) -> Option<BencodeElem> {
+        match val {
+            Some(val) => self
+                .info
+                .fields_mut()
+                .extra_info_fields
+                .get_or_insert_with(HashMap::new)
+                .insert(key.to_owned(), val),
+            None => {
+                let fields = self.info.fields_mut().extra_info_fields.as_mut()?;
+                let removed = fields.remove(key);
+                if fields.is_empty() {
+                    self.info.fields_mut().extra_info_fields = None;
+                }
+                removed
+            }
+        }
+    }
+
     /// Check if this torrent is private as defined in
     /// [BEP 27](http://bittorrent.org/beps/bep_0027.html).
     ///
     /// Returns `true` if `private` maps to a bencode integer `1`.
     /// Returns `false` otherwise.
     pub fn is_private(&self) -> bool {
-        if let Some(ref dict) = self.extra_info_fields {
+        if let Some(ref dict) = self.info.extra_info_fields {
             match dict.get("private") {
                 Some(&BencodeElem::Integer(val)) => val == 1,
                 Some(_) => false,
@@ -366,6 +1521,431 @@ impl Torrent {
             false
         }
     }
+
+    /// Make this torrent private as defined in
+    /// [BEP 27](http://bittorrent.org/beps/bep_0027.html),
+    /// i.e. set `private` to `1` in `extra_info_fields`.
+    ///
+    /// Note that `private` is part of `info`, so this changes the info hash
+    /// (unless the torrent is already private).
+    pub fn set_private(&mut self) {
+        self.info
+            .fields_mut()
+            .extra_info_fields
+            .get_or_insert_with(HashMap::new)
+            .insert("private".to_owned(), BencodeElem::Integer(1));
+    }
+
+    /// Make this torrent public, i.e. remove `private` from `extra_info_fields`.
+    /// `extra_info_fields` is set to `None` if nothing else is left in it.
+    ///
+    /// Note that `private` is part of `info`, so this changes the info hash
+    /// (unless `private` is not present).
+    pub fn clear_private(&mut self) {
+        if let Some(ref mut fields) = self.info.fields_mut().extra_info_fields {
+            fields.remove("private");
+            if fields.is_empty() {
+                self.info.fields_mut().extra_info_fields = None;
+            }
+        }
+    }
+
+    /// Remove the fields of `info` that tie this torrent to a (private)
+    /// tracker, and return the new info hash.
+    ///
+    /// These are `private` (see [`clear_private()`]), `source`, and `x_cross_seed`.
+    /// Since they are part of `info`, the info hash changes unless none of them is
+    /// present. This is meant for re-sharing one's own content publicly,
+    /// e.g. after creating the torrent for a private tracker.
+    ///
+    /// Note that fields outside of `info` are left untouched. In particular,
+    /// tracker URLs (which might contain a passkey) have to be replaced
+    /// separately, e.g. with [`clone_for_tracker()`].
+    ///
+    /// [`clear_private()`]: #method.clear_private
+    /// [`clone_for_tracker()`]: #method.clone_for_tracker
+    pub fn strip_tracker_fields(&mut self) -> String {
+        if let Some(ref mut fields) = self.info.fields_mut().extra_info_fields {
+            for field in TRACKER_INFO_FIELDS {
+                fields.remove(field);
+            }
+            if fields.is_empty() {
+                self.info.fields_mut().extra_info_fields = None;
+            }
+        }
+
+        self.info_hash()
+    }
+
+    /// Remove all non-essential top-level fields (e.g. `comment`, `creation date`,
+    /// and `created by`) from `extra_fields`, and return their keys (sorted).
+    ///
+    /// Only the fields that clients use to download the content are kept,
+    /// i.e. `url-list`, `httpseeds`, and `nodes`. Use [`scrub_with_allowlist()`]
+    /// to choose which fields to keep. `extra_fields` is set to `None` if nothing is
+    /// left in it. `announce`, `announce_list` and `info` are not affected,
+    /// so the info hash stays the same.
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(feature = "fs", doc = "```no_run")]
+    #[cfg_attr(not(feature = "fs"), doc = "```ignore")]
+    /// use lava_torrent::torrent::v1::Torrent;
+    ///
+    /// let mut torrent = Torrent::read_from_file("sample.torrent").unwrap();
+    /// for field in torrent.scrub() {
+    ///     println!("removed {}", field);
+    /// }
+    /// torrent.write_into_file("scrubbed.torrent").unwrap();
+    /// ```
+    ///
+    /// [`scrub_with_allowlist()`]: #method.scrub_with_allowlist
+    pub fn scrub(&mut self) -> Vec<String> {
+        self.scrub_with_allowlist(&SCRUB_ALLOWLIST)
+    }
+
+    /// Like [`scrub()`], but only the top-level fields in `allowlist` are kept.
+    ///
+    /// `nodes`, `url_list` and `httpseeds` are removed as well
+    /// if their keys are not in `allowlist`.
+    ///
+    /// [`scrub()`]: #method.scrub
+    pub fn scrub_with_allowlist(&mut self, allowlist: &[&str]) -> Vec<String> {
+        fn scrub_field<T>(field: &mut Option<T>, key: &str, allowlist: &[&str]) -> Option<String> {
+            if field.is_some() && !allowlist.contains(&key) {
+                *field = None;
+                Some(key.to_owned())
+            } else {
+                None
+            }
+        }
+
+        let mut removed = Vec::new();
+        removed.extend(scrub_field(&mut self.nodes, "nodes", allowlist));
+        removed.extend(scrub_field(&mut self.url_list, "url-list", allowlist));
+        removed.extend(scrub_field(&mut self.httpseeds, "httpseeds", allowlist));
+        if let Some(ref mut fields) = self.extra_fields {
+            fields.retain(|key, _| {
+                let keep = allowlist.contains(&key.as_str());
+                if !keep {
+                    removed.push(key.clone());
+                }
+                keep
+            });
+            if fields.is_empty() {
+                self.extra_fields = None;
+            }
+        }
+
+        removed.sort_unstable();
+        removed
+    }
+
+    /// Set `length` to the total length of `files`, e.g. after files
+    /// have been added or removed, and return the new `length`.
+    ///
+    /// Does nothing for single-file torrents, whose `length` is the file's length.
+    /// `Err(LavaTorrentError::MalformedTorrent)` is returned (and `length` is
+    /// left as is) if a file has a negative length or if the total overflows.
+    ///
+    /// Multi-file torrents are written without `length`,
+    /// so this does not change the info hash.
+    pub fn recompute_length(&mut self) -> Result<Integer, LavaTorrentError> {
+        if let Some(length) = self.total_file_length()? {
+            self.info.fields_mut().length = length;
+        }
+        Ok(self.info.length)
+    }
+
+    /// Check that `length` is the total length of `files`.
+    ///
+    /// This is always the case for torrents that have been read or built,
+    /// but not necessarily after `files` has been changed. Single-file
+    /// torrents always pass. See also [`recompute_length()`].
+    ///
+    /// [`recompute_length()`]: #method.recompute_length
+    pub fn check_length(&self) -> Result<(), LavaTorrentError> {
+        match self.total_file_length()? {
+            Some(length) if length != self.info.length => Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::Other(Cow::Owned(format!(
+                    "length [{}] does not match the total length of files [{}].",
+                    self.info.length, length
+                ))),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    // Sum the lengths of `files`. Returns `None` for single-file torrents.
+    fn total_file_length(&self) -> Result<Option<Integer>, LavaTorrentError> {
+        let files = match self.info.files {
+            Some(ref files) => files,
+            None => return Ok(None),
+        };
+
+        let mut length: Integer = 0;
+        for (index, file) in files.iter().enumerate() {
+            if file.length < 0 {
+                return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                    Cow::Owned(format!("file [{}] has a negative length.", index)),
+                )));
+            }
+            length = length
+                .checked_add(file.length)
+                .ok_or(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                    Cow::Borrowed("Torrent's length overflowed in i64."),
+                )))?;
+        }
+        Ok(Some(length))
+    }
+
+    /// Set `name`, i.e. the name of the file for single-file torrents,
+    /// or the name of the root directory otherwise.
+    ///
+    /// `Err(LavaTorrentError::InvalidArgument)` is returned if `name` is not
+    /// a valid file name (e.g. if it is empty or contains a `/`). The original
+    /// `name` and `name.utf-8` of legacy torrents (see [`read_from_bytes()`])
+    /// are removed from `extra_info_fields`, since they no longer apply.
+    ///
+    /// Note that `name` is part of `info`, so this changes the info hash.
+    ///
+    /// [`read_from_bytes()`]: #method.read_from_bytes
+    pub fn set_name(&mut self, name: String) -> Result<(), LavaTorrentError> {
+        if !validate::is_valid_component(&name) {
+            return Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                "[{}] is not a valid file name.",
+                name
+            ))));
+        }
+
+        self.info.fields_mut().name = name;
+        self.replace_extra_info_field("name", None);
+        self.replace_extra_info_field("name.utf-8", None);
+        Ok(())
+    }
+
+    /// Set the path of the `file_index`-th file to `path`.
+    ///
+    /// For single-file torrents, the only file has index `0` and its path is
+    /// `name`, so `path` must consist of a single component and this is the same
+    /// as [`set_name()`]. Otherwise, the original `path` and `path.utf-8` of
+    /// legacy torrents (see [`File::raw_path()`]) are removed from the file's
+    /// `extra_fields`, since they no longer apply.
+    ///
+    /// `Err(LavaTorrentError::InvalidArgument)` is returned if `file_index`
+    /// is out of bounds, if `path` is not a valid relative path (e.g. if it
+    /// contains `..`), or if another file already has the same path.
+    ///
+    /// Note that paths are part of `info`, so this changes the info hash.
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(feature = "fs", doc = "```no_run")]
+    #[cfg_attr(not(feature = "fs"), doc = "```ignore")]
+    /// use lava_torrent::torrent::v1::Torrent;
+    /// use std::path::PathBuf;
+    ///
+    /// let mut torrent = Torrent::read_from_file("sample.torrent").unwrap();
+    /// torrent.set_name("renamed".to_owned()).unwrap();
+    /// torrent.rename_file(0, PathBuf::from("subdir/file.txt")).unwrap();
+    /// ```
+    ///
+    /// [`set_name()`]: #method.set_name
+    /// [`File::raw_path()`]: struct.File.html#method.raw_path
+    pub fn rename_file(
+        &mut self,
+        file_index: usize,
+        path: PathBuf,
+    ) -> Result<(), LavaTorrentError> {
+        let num_files = self.info.files.as_ref().map_or(1, Vec::len);
+        if file_index >= num_files {
+            return Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                "file index [{}] is out of bounds.",
+                file_index
+            ))));
+        }
+
+        let is_valid = |path: &Path| {
+            (path.iter().count() > 0)
+                && path
+                    .iter()
+                    .all(|component| component.to_str().is_some_and(validate::is_valid_component))
+        };
+        if !is_valid(&path) {
+            return Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                "[{}] is not a valid relative path.",
+                path.display()
+            ))));
+        }
+
+        let files = match self.info.fields_mut().files {
+            Some(ref mut files) => files,
+            None => {
+                return match path.to_str() {
+                    Some(name) if path.iter().count() == 1 => self.set_name(name.to_owned()),
+                    _ => Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                        "[{}] has more than 1 component but the torrent has only 1 file.",
+                        path.display()
+                    )))),
+                };
+            }
+        };
+
+        let is_taken = files
+            .iter()
+            .enumerate()
+            .any(|(index, file)| (index != file_index) && (file.path == path));
+        if is_taken {
+            return Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                "[{}] is already the path of another file.",
+                path.display()
+            ))));
+        }
+
+        let file = &mut files[file_index];
+        file.path = path;
+        if let Some(ref mut fields) = file.extra_fields {
+            fields.remove("path");
+            fields.remove("path.utf-8");
+            if fields.is_empty() {
+                file.extra_fields = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Iterate over the files of this torrent, skipping padding files
+    /// (see [`File::is_padding()`]).
+    ///
+    /// This is what should be shown to end users, as padding files only
+    /// exist to align other files to piece boundaries. Single-file
+    /// torrents have no `files`, so nothing is yielded for them.
+    ///
+    /// [`File::is_padding()`]: struct.File.html#method.is_padding
+    pub fn content_files(&self) -> impl Iterator<Item = &File> {
+        self.info
+            .files
+            .iter()
+            .flatten()
+            .filter(|file| !file.is_padding())
+    }
+
+    /// Get the total size of this torrent's content in bytes,
+    /// excluding padding files (see [`File::is_padding()`]).
+    ///
+    /// Unlike `length`, this is the size of the actual payload
+    /// of torrents whose files are aligned to piece boundaries.
+    ///
+    /// [`File::is_padding()`]: struct.File.html#method.is_padding
+    pub fn content_length(&self) -> Integer {
+        match self.info.files {
+            Some(_) => self.content_files().map(|file| file.length).sum(),
+            None => self.info.length,
+        }
+    }
+
+    /// Get the number of pieces, i.e. `self.pieces.len()`.
+    ///
+    /// For valid torrents, this equals `ceil(length / piece_length)`.
+    pub fn num_pieces(&self) -> usize {
+        self.info.pieces.len()
+    }
+
+    /// Get the size of the last piece in bytes.
+    ///
+    /// All pieces except the last one are `piece_length` bytes long. The last
+    /// piece is shorter if `length` is not a multiple of `piece_length`.
+    /// 0 is returned if `length` or `piece_length` is not positive.
+    pub fn last_piece_length(&self) -> Integer {
+        if self.info.length <= 0 || self.info.piece_length <= 0 {
+            return 0;
+        }
+
+        match self.info.length % self.info.piece_length {
+            0 => self.info.piece_length,
+            remainder => remainder,
+        }
+    }
+
+    /// Get the range of pieces that `files[file_index]` lies in.
+    ///
+    /// For single-file torrents, the only valid index is `0`. `Ok(None)` is
+    /// returned for zero-length files, since they don't lie in any piece.
+    /// An error is returned if `file_index` is out of bounds.
+    ///
+    /// Note that the first and last pieces might be shared with
+    /// other files, which is described by [`FilePieces`].
+    ///
+    /// [`FilePieces`]: struct.FilePieces.html
+    pub fn pieces_for_file(
+        &self,
+        file_index: usize,
+    ) -> Result<Option<FilePieces>, LavaTorrentError> {
+        let spans = self.spans()?;
+        let span = spans.get(file_index).ok_or_else(|| {
+            LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                "file index [{}] is out of bounds.",
+                file_index
+            )))
+        })?;
+
+        let piece_length = util::i64_to_u64(self.info.piece_length)?;
+        if piece_length == 0 {
+            return Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::InvalidPieceLength {
+                    piece_length: self.info.piece_length,
+                },
+            ));
+        }
+
+        FilePieces::new(span, piece_length)
+    }
+
+    fn spans(&self) -> Result<Vec<Span>, LavaTorrentError> {
+        let lengths = match self.info.files {
+            Some(ref files) => files
+                .iter()
+                .map(|file| util::i64_to_u64(file.length))
+                .collect::<Result<Vec<_>, LavaTorrentError>>()?,
+            None => vec![util::i64_to_u64(self.info.length)?],
+        };
+
+        let mut offset = 0;
+        Ok(lengths
+            .into_iter()
+            .map(|length| {
+                let span = Span { offset, length };
+                offset += length;
+                span
+            })
+            .collect())
+    }
+}
+
+// Formats a size as "<n> bytes", followed by the size in
+// the largest fitting binary unit (e.g. "(1.50 MiB)") if it's >= 1 KiB.
+struct Size(Integer);
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+        write!(f, "{} bytes", self.0)?;
+        let mut size = self.0 as f64;
+        let mut unit = None;
+        for candidate in UNITS {
+            if size < 1024.0 {
+                break;
+            }
+            size /= 1024.0;
+            unit = Some(candidate);
+        }
+
+        match unit {
+            Some(unit) => write!(f, " ({:.2} {})", size, unit),
+            None => Ok(()),
+        }
+    }
 }
 
 impl fmt::Display for File {
@@ -373,9 +1953,9 @@ impl fmt::Display for File {
         writeln!(
             f,
             "{}\n\
-             -size: {} bytes",
+             -size: {}",
             self.path.as_path().display(),
-            self.length
+            Size(self.length)
         )?;
 
         if let Some(ref fields) = self.extra_fields {
@@ -393,9 +1973,30 @@ impl fmt::Display for File {
     }
 }
 
+/// The default form lists every field, including all `pieces`.
+/// The alternate form (`{:#}`) is a one-line summary with the name,
+/// size, number of files and trackers, which is more suitable for logs.
 impl fmt::Display for Torrent {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "{}.torrent", self.name)?;
+        if f.alternate() {
+            let num_files = self.info.files.as_ref().map_or(1, Vec::len);
+            let mut seen = HashSet::new();
+            return write!(
+                f,
+                "{}.torrent: {}, {} file{}, trackers: [{}]",
+                self.info.name,
+                Size(self.info.length),
+                num_files,
+                if num_files == 1 { "" } else { "s" },
+                self.announce
+                    .iter()
+                    .chain(self.announce_list.iter().flatten().flatten())
+                    .filter(|tracker| seen.insert(*tracker))
+                    .format(", "),
+            );
+        }
+
+        writeln!(f, "{}.torrent", self.info.name)?;
         if let Some(ref announce) = self.announce {
             writeln!(f, "-announce: {}", announce)?;
         }
@@ -409,13 +2010,31 @@ impl fmt::Display for Torrent {
                 )))
             )?;
         }
-        writeln!(f, "-size: {} bytes", self.length)?;
-        writeln!(f, "-piece length: {} bytes", self.piece_length)?;
-
-        if let Some(ref fields) = self.extra_fields {
-            write!(
+        if let Some(ref nodes) = self.nodes {
+            writeln!(
                 f,
-                "{}",
+                "-nodes: [{}]",
+                nodes
+                    .iter()
+                    .format_with(", ", |(host, port), f| f(&format_args!(
+                        "[{}, {}]",
+                        host, port
+                    )))
+            )?;
+        }
+        if let Some(ref urls) = self.url_list {
+            writeln!(f, "-url-list: [{}]", itertools::join(urls, ", "))?;
+        }
+        if let Some(ref urls) = self.httpseeds {
+            writeln!(f, "-httpseeds: [{}]", itertools::join(urls, ", "))?;
+        }
+        writeln!(f, "-size: {}", Size(self.info.length))?;
+        writeln!(f, "-piece length: {}", Size(self.info.piece_length))?;
+
+        if let Some(ref fields) = self.extra_fields {
+            write!(
+                f,
+                "{}",
                 fields
                     .iter()
                     .sorted_by_key(|&(key, _)| key.as_bytes())
@@ -423,7 +2042,7 @@ impl fmt::Display for Torrent {
             )?;
         }
 
-        if let Some(ref fields) = self.extra_info_fields {
+        if let Some(ref fields) = self.info.extra_info_fields {
             write!(
                 f,
                 "{}",
@@ -434,331 +2053,1868 @@ impl fmt::Display for Torrent {
             )?;
         }
 
-        if let Some(ref files) = self.files {
-            writeln!(f, "-files:")?;
-            for (counter, file) in files.iter().enumerate() {
-                writeln!(f, "[{}] {}", counter + 1, file)?;
+        if let Some(ref files) = self.info.files {
+            writeln!(f, "-files:")?;
+            for (counter, file) in files.iter().enumerate() {
+                writeln!(f, "[{}] {}", counter + 1, file)?;
+            }
+        }
+
+        writeln!(
+            f,
+            "-pieces: [{}]",
+            self.info
+                .pieces
+                .iter()
+                .format_with(", ", |piece, f| f(&format_args!(
+                    "[{:02x}]",
+                    piece.iter().format("")
+                ))),
+        )
+    }
+}
+
+// Get the info hashes stored under `similar` in `extra_info_fields`.
+// Shared by `Torrent::similar()` and the validation done when reading torrents.
+fn extract_similar(
+    extra_info_fields: &Option<Dictionary>,
+) -> Result<Option<Vec<&[u8]>>, LavaTorrentError> {
+    match extra_info_fields
+        .as_ref()
+        .and_then(|fields| fields.get("similar"))
+    {
+        Some(BencodeElem::List(ref hashes)) => Ok(Some(
+            hashes
+                .iter()
+                .map(|hash| match hash.as_bytes() {
+                    Some(hash) if hash.len() == PIECE_STRING_LENGTH => Ok(hash),
+                    _ => Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                        Cow::Borrowed(
+                            r#""similar" contains an element that is not a 20-byte string."#,
+                        ),
+                    ))),
+                })
+                .collect::<Result<Vec<&[u8]>, LavaTorrentError>>()?,
+        )),
+        Some(_) => Err(LavaTorrentError::MalformedTorrent(
+            TorrentErrorKind::WrongType {
+                field: Cow::Borrowed("similar"),
+                expected: ValueType::List,
+            },
+        )),
+        None => Ok(None),
+    }
+}
+
+// Get the names stored under `collections` in `extra_info_fields`.
+// Shared by `Torrent::collections()` and the validation done when reading torrents.
+fn extract_collections(
+    extra_info_fields: &Option<Dictionary>,
+) -> Result<Option<Vec<&str>>, LavaTorrentError> {
+    match extra_info_fields
+        .as_ref()
+        .and_then(|fields| fields.get("collections"))
+    {
+        Some(BencodeElem::List(ref names)) => Ok(Some(
+            names
+                .iter()
+                .map(|name| match name {
+                    BencodeElem::String(name) => Ok(name.as_str()),
+                    _ => Err(LavaTorrentError::MalformedTorrent(
+                        TorrentErrorKind::WrongElementType {
+                            field: Cow::Borrowed("collections"),
+                            expected: ValueType::String,
+                        },
+                    )),
+                })
+                .collect::<Result<Vec<&str>, LavaTorrentError>>()?,
+        )),
+        Some(_) => Err(LavaTorrentError::MalformedTorrent(
+            TorrentErrorKind::WrongType {
+                field: Cow::Borrowed("collections"),
+                expected: ValueType::List,
+            },
+        )),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod file_tests {
+    use super::*;
+
+    #[test]
+    fn absolute_path_ok() {
+        let file = File {
+            length: 42,
+            path: PathBuf::from("dir1/file"),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            file.absolute_path("/root").unwrap(),
+            PathBuf::from("/root/dir1/file")
+        );
+    }
+
+    #[test]
+    fn absolute_path_not_absolute() {
+        let file = File {
+            length: 42,
+            path: PathBuf::from("dir1/file"),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        match file.absolute_path("root") {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, "Joined path is not absolute.");
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn attributes_ok() {
+        let file = File {
+            length: 42,
+            path: PathBuf::from("dir1/file"),
+            extra_fields: Some(HashMap::from([("attr".to_owned(), bencode_elem!("xhq"))])),
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            file.attributes().unwrap(),
+            FileAttributes {
+                padding: false,
+                executable: true,
+                hidden: true,
+                symlink: false,
+            }
+        );
+    }
+
+    #[test]
+    fn attributes_missing() {
+        let file = File {
+            length: 42,
+            path: PathBuf::from("dir1/file"),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(file.attributes().unwrap(), FileAttributes::default());
+    }
+
+    #[test]
+    fn attributes_not_string() {
+        let file = File {
+            length: 42,
+            path: PathBuf::from("dir1/file"),
+            extra_fields: Some(HashMap::from([("attr".to_owned(), bencode_elem!(1))])),
+            raw_extra_fields: None,
+        };
+
+        match file.attributes() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(
+                    m,
+                    r#""attr" does not map to a string (or maps to invalid UTF8)."#
+                )
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn symlink_path_ok() {
+        let file = File {
+            length: 0,
+            path: PathBuf::from("dir1/link"),
+            extra_fields: Some(HashMap::from([
+                ("attr".to_owned(), bencode_elem!("l")),
+                ("symlink path".to_owned(), bencode_elem!(["dir2", "file"])),
+            ])),
+            raw_extra_fields: None,
+        };
+
+        assert!(file.attributes().unwrap().symlink);
+        assert_eq!(
+            file.symlink_path().unwrap(),
+            Some(PathBuf::from("dir2").join("file"))
+        );
+    }
+
+    #[test]
+    fn symlink_path_malformed() {
+        let file = File {
+            length: 0,
+            path: PathBuf::from("dir1/link"),
+            extra_fields: Some(HashMap::from([(
+                "symlink path".to_owned(),
+                bencode_elem!(["dir2", 1]),
+            )])),
+            raw_extra_fields: None,
+        };
+
+        match file.symlink_path() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""symlink path" contains a non-string element."#)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn sha1_ok() {
+        let file = File {
+            length: 42,
+            path: PathBuf::from("dir1/file"),
+            extra_fields: Some(HashMap::from([(
+                "sha1".to_owned(),
+                BencodeElem::Bytes(vec![0xff; 20]),
+            )])),
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(file.sha1().unwrap(), Some(&[0xff; 20][..]));
+    }
+
+    #[test]
+    fn sha1_wrong_length() {
+        let file = File {
+            length: 42,
+            path: PathBuf::from("dir1/file"),
+            extra_fields: Some(HashMap::from([(
+                "sha1".to_owned(),
+                BencodeElem::Bytes(vec![0xff; 19]),
+            )])),
+            raw_extra_fields: None,
+        };
+
+        match file.sha1() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""sha1" is not a 20-byte string."#)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn raw_path_ok() {
+        let file = File {
+            length: 42,
+            path: PathBuf::from("dir1/file\u{fffd}"),
+            extra_fields: Some(HashMap::from([(
+                "path".to_owned(),
+                BencodeElem::List(vec![
+                    BencodeElem::String("dir1".to_owned()),
+                    BencodeElem::Bytes(vec![b'f', b'i', b'l', b'e', 0xff]),
+                ]),
+            )])),
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            file.raw_path(),
+            Some(vec![&b"dir1"[..], &[b'f', b'i', b'l', b'e', 0xff][..]])
+        );
+    }
+
+    #[test]
+    fn raw_path_none() {
+        let file = File {
+            length: 42,
+            path: PathBuf::from("dir1/file"),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(file.raw_path(), None);
+    }
+}
+
+#[cfg(test)]
+mod torrent_tests {
+    use super::*;
+    use std::iter::FromIterator;
+
+    #[test]
+    fn construct_info_ok() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from_iter(
+                    vec![("key".to_owned(), bencode_elem!("val"))].into_iter(),
+                )),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            torrent.construct_info(),
+            bencode_elem!({
+                ("length", 4),
+                ("name", "sample"),
+                ("piece length", 2),
+                ("pieces", ([[1; 20], [2; 20]].concat())),
+                ("key", "val"),
+            }),
+        );
+    }
+
+    #[test]
+    fn info_hash_ok() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            torrent.info_hash(),
+            "3cd707db0a4aef6f22746962743c62ee137bbed3".to_owned(),
+        );
+    }
+
+    #[test]
+    fn magnet_link_ok() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            torrent.magnet_link().unwrap(),
+            "magnet:?xt=urn:btih:3cd707db0a4aef6f22746962743c62ee137bbed3\
+             &dn=sample&tr=url"
+                .to_owned()
+        );
+    }
+
+    #[test]
+    fn magnet_link_with_announce_list() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: Some(vec![
+                vec!["url1".to_owned()],
+                vec!["url2".to_owned(), "url3".to_owned()],
+            ]),
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            torrent.magnet_link().unwrap(),
+            "magnet:?xt=urn:btih:3cd707db0a4aef6f22746962743c62ee137bbed3\
+             &dn=sample&tr=url1&tr=url2&tr=url3"
+                .to_owned()
+        );
+    }
+
+    #[test]
+    fn magnet_link_with_web_seed() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: Some(vec!["https://example.org/path".to_owned()]),
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            torrent.magnet_link().unwrap(),
+            "magnet:?xt=urn:btih:3cd707db0a4aef6f22746962743c62ee137bbed3\
+             &dn=sample&ws=https://example.org/path"
+                .to_owned()
+        );
+    }
+
+    #[test]
+    fn magnet_link_with_web_seeds() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: Some(vec![
+                "https://example.org/path1".to_owned(),
+                "https://example.org/path2".to_owned(),
+            ]),
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            torrent.magnet_link().unwrap(),
+            "magnet:?xt=urn:btih:3cd707db0a4aef6f22746962743c62ee137bbed3\
+             &dn=sample&ws=https://example.org/path1&ws=https://example.org/path2"
+                .to_owned()
+        );
+    }
+
+    #[test]
+    fn magnet_link_with_httpseeds() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: Some(vec![
+                "https://example.org/seed1".to_owned(),
+                "https://example.org/seed2".to_owned(),
+            ]),
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            torrent.magnet_link().unwrap(),
+            "magnet:?xt=urn:btih:3cd707db0a4aef6f22746962743c62ee137bbed3\
+             &dn=sample&as=https://example.org/seed1&as=https://example.org/seed2"
+                .to_owned()
+        );
+    }
+
+    #[test]
+    fn magnet_link_escape() {
+        let torrent = Torrent {
+            announce: Some("https://example.org/path?a=1&b=hello world".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: Some(vec!["https://example.org/path?a=1&b=hello world".to_owned()]),
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            torrent.magnet_link().unwrap(),
+            "magnet:?xt=urn:btih:3cd707db0a4aef6f22746962743c62ee137bbed3\
+             &dn=sample&tr=https://example.org/path?a=1%26b=hello+world\
+             &ws=https://example.org/path?a=1%26b=hello+world"
+                .to_owned()
+        );
+    }
+
+    #[test]
+    fn set_web_seeds_ok() {
+        let mut torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        torrent.set_web_seeds(Some(vec![
+            "https://example.org/file".to_owned(),
+            "https://example.com/file".to_owned(),
+        ]));
+        assert_eq!(
+            torrent.url_list,
+            Some(vec![
+                "https://example.org/file".to_owned(),
+                "https://example.com/file".to_owned(),
+            ])
+        );
+
+        torrent.set_web_seeds(None);
+        assert_eq!(torrent.url_list, None);
+    }
+
+    #[test]
+    fn set_httpseeds_ok() {
+        let mut torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        torrent.set_httpseeds(Some(vec!["https://example.org/seed".to_owned()]));
+        assert_eq!(
+            torrent.httpseeds,
+            Some(vec!["https://example.org/seed".to_owned()])
+        );
+
+        torrent.set_httpseeds(None);
+        assert_eq!(torrent.httpseeds, None);
+    }
+
+    #[test]
+    fn set_nodes_ok() {
+        let mut torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        torrent.set_nodes(Some(vec![
+            ("127.0.0.1".to_owned(), 6881),
+            ("router.example.com".to_owned(), 6882),
+        ]));
+        assert_eq!(
+            torrent.nodes,
+            Some(vec![
+                ("127.0.0.1".to_owned(), 6881),
+                ("router.example.com".to_owned(), 6882),
+            ])
+        );
+
+        torrent.set_nodes(None);
+        assert_eq!(torrent.nodes, None);
+    }
+
+    #[test]
+    fn set_similar_ok() {
+        let mut torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        torrent.set_similar(Some(vec![[3; 20], [4; 20]]));
+        assert_eq!(
+            torrent.similar().unwrap(),
+            Some(vec![&[3; 20][..], &[4; 20][..]])
+        );
+        assert_eq!(
+            torrent.info.extra_info_fields.as_ref().unwrap()["similar"],
+            BencodeElem::List(vec![
+                BencodeElem::Bytes(vec![3; 20]),
+                BencodeElem::Bytes(vec![4; 20]),
+            ])
+        );
+
+        torrent.set_similar(None);
+        assert_eq!(torrent.similar().unwrap(), None);
+        assert_eq!(torrent.info.extra_info_fields, None);
+    }
+
+    #[test]
+    fn similar_not_info_hash() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from([(
+                    "similar".to_owned(),
+                    BencodeElem::List(vec![BencodeElem::Bytes(vec![3; 19])]),
+                )])),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        match torrent.similar() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(
+                m,
+                r#""similar" contains an element that is not a 20-byte string."#
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn similar_not_list() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from([(
+                    "similar".to_owned(),
+                    BencodeElem::Bytes(vec![3; 20]),
+                )])),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        match torrent.similar() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""similar" does not map to a list."#)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn set_collections_ok() {
+        let mut torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        torrent.set_collections(Some(vec!["linux".to_owned(), "isos".to_owned()]));
+        assert_eq!(torrent.collections().unwrap(), Some(vec!["linux", "isos"]));
+        assert_eq!(
+            torrent.info.extra_info_fields.as_ref().unwrap()["collections"],
+            bencode_elem!(["linux", "isos"])
+        );
+
+        torrent.set_collections(None);
+        assert_eq!(torrent.collections().unwrap(), None);
+        assert_eq!(torrent.info.extra_info_fields, None);
+    }
+
+    #[test]
+    fn collections_not_string() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from([(
+                    "collections".to_owned(),
+                    bencode_elem!([42]),
+                )])),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        match torrent.collections() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""collections" contains a non-string element."#)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn is_private_ok() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from_iter(
+                    vec![("private".to_owned(), bencode_elem!(1))].into_iter(),
+                )),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert!(torrent.is_private());
+    }
+
+    #[test]
+    fn is_private_no_extra_fields() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert!(!torrent.is_private());
+    }
+
+    #[test]
+    fn is_private_no_key() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from_iter(
+                    vec![("".to_owned(), bencode_elem!(1))].into_iter(),
+                )),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert!(!torrent.is_private());
+    }
+
+    #[test]
+    fn is_private_incorrect_val_type() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from_iter(
+                    vec![("private".to_owned(), bencode_elem!("1"))].into_iter(),
+                )),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert!(!torrent.is_private());
+    }
+
+    #[test]
+    fn is_private_incorrect_val() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from_iter(
+                    vec![("private".to_owned(), bencode_elem!(2))].into_iter(),
+                )),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert!(!torrent.is_private());
+    }
+
+    #[test]
+    fn set_private_ok() {
+        let mut torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+        let info_hash = torrent.info_hash();
+
+        torrent.set_private();
+        assert!(torrent.is_private());
+        assert_ne!(torrent.info_hash(), info_hash);
+
+        torrent.clear_private();
+        assert!(!torrent.is_private());
+        assert_eq!(torrent.info.extra_info_fields, None);
+        assert_eq!(torrent.info_hash(), info_hash);
+    }
+
+    #[test]
+    fn set_private_incorrect_val() {
+        let mut torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from([
+                    ("private".to_owned(), bencode_elem!("1")),
+                    ("source".to_owned(), bencode_elem!("spam")),
+                ])),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        torrent.set_private();
+        assert!(torrent.is_private());
+
+        torrent.clear_private();
+        assert!(!torrent.is_private());
+        assert_eq!(
+            torrent.info.extra_info_fields,
+            Some(HashMap::from([(
+                "source".to_owned(),
+                bencode_elem!("spam")
+            )]))
+        );
+    }
+
+    #[test]
+    fn strip_tracker_fields_ok() {
+        let mut torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+        let public_info_hash = torrent.info_hash();
+        torrent.info.fields_mut().extra_info_fields = Some(HashMap::from([
+            ("private".to_owned(), bencode_elem!(1)),
+            ("source".to_owned(), bencode_elem!("EX")),
+            ("x_cross_seed".to_owned(), bencode_elem!("abcd")),
+        ]));
+        assert_ne!(torrent.info_hash(), public_info_hash);
+
+        assert_eq!(torrent.strip_tracker_fields(), public_info_hash);
+        assert_eq!(torrent.info.extra_info_fields, None);
+        assert_eq!(torrent.announce, Some("url".to_owned()));
+        assert_eq!(torrent.strip_tracker_fields(), public_info_hash);
+    }
+
+    #[test]
+    fn strip_tracker_fields_keeps_other_fields() {
+        let mut torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from([
+                    ("source".to_owned(), bencode_elem!("EX")),
+                    ("entropy".to_owned(), bencode_elem!("0123")),
+                ])),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        let info_hash = torrent.strip_tracker_fields();
+        assert_eq!(
+            torrent.info.extra_info_fields,
+            Some(HashMap::from([(
+                "entropy".to_owned(),
+                bencode_elem!("0123")
+            )]))
+        );
+        assert_eq!(info_hash, torrent.info_hash());
+    }
+
+    #[test]
+    fn scrub_ok() {
+        let mut torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: Some(vec![("router".to_owned(), 6881)]),
+            url_list: Some(vec!["seed".to_owned()]),
+            httpseeds: Some(vec!["seed".to_owned()]),
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from([(
+                    "source".to_owned(),
+                    bencode_elem!("EX"),
+                )])),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: Some(HashMap::from([
+                ("comment".to_owned(), bencode_elem!("comment")),
+                ("created by".to_owned(), bencode_elem!("me")),
+                ("creation date".to_owned(), bencode_elem!(1)),
+            ])),
+            raw_extra_fields: None,
+        };
+        let info_hash = torrent.info_hash();
+
+        assert_eq!(
+            torrent.scrub(),
+            vec!["comment", "created by", "creation date"]
+        );
+        assert_eq!(torrent.extra_fields, None);
+        assert_eq!(torrent.nodes, Some(vec![("router".to_owned(), 6881)]));
+        assert_eq!(torrent.url_list, Some(vec!["seed".to_owned()]));
+        assert_eq!(torrent.httpseeds, Some(vec!["seed".to_owned()]));
+        assert_eq!(torrent.announce, Some("url".to_owned()));
+        assert_eq!(torrent.info_hash(), info_hash);
+        assert!(torrent.scrub().is_empty());
+    }
+
+    #[test]
+    fn scrub_with_allowlist_ok() {
+        let mut torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: Some(vec![("router".to_owned(), 6881)]),
+            url_list: Some(vec!["seed".to_owned()]),
+            httpseeds: Some(vec!["seed".to_owned()]),
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: Some(HashMap::from([(
+                "comment".to_owned(),
+                bencode_elem!("comment"),
+            )])),
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            torrent.clone().scrub_with_allowlist(&["comment"]),
+            vec!["httpseeds", "nodes", "url-list"]
+        );
+        assert_eq!(
+            torrent.scrub_with_allowlist(&[]),
+            vec!["comment", "httpseeds", "nodes", "url-list"]
+        );
+        assert_eq!(torrent.nodes, None);
+        assert_eq!(torrent.url_list, None);
+        assert_eq!(torrent.httpseeds, None);
+        assert_eq!(torrent.extra_fields, None);
+    }
+
+    fn multi_file_torrent() -> Torrent {
+        Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: Some(vec![
+                    File {
+                        length: 2,
+                        path: PathBuf::from("dir/file1"),
+                        extra_fields: Some(HashMap::from([
+                            ("path".to_owned(), bencode_elem!([(vec![0xff])])),
+                            ("path.utf-8".to_owned(), bencode_elem!(["file1"])),
+                            ("attr".to_owned(), bencode_elem!("x")),
+                        ])),
+                        raw_extra_fields: None,
+                    },
+                    File {
+                        length: 2,
+                        path: PathBuf::from("file2"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                ]),
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        }
+    }
+
+    #[test]
+    fn recompute_length_ok() {
+        let mut torrent = multi_file_torrent();
+        torrent.info.fields_mut().files.as_mut().unwrap()[1].length = 40;
+        match torrent.check_length() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(
+                m,
+                "length [4] does not match the total length of files [42]."
+            ),
+            _ => panic!(),
+        }
+
+        assert_eq!(torrent.recompute_length().unwrap(), 42);
+        assert_eq!(torrent.info.length, 42);
+        torrent.check_length().unwrap();
+    }
+
+    #[test]
+    fn recompute_length_single_file() {
+        let mut torrent = multi_file_torrent();
+        let info = torrent.info.fields_mut();
+        info.files = None;
+        info.length = 42;
+
+        assert_eq!(torrent.recompute_length().unwrap(), 42);
+        torrent.check_length().unwrap();
+    }
+
+    #[test]
+    fn recompute_length_negative() {
+        let mut torrent = multi_file_torrent();
+        torrent.info.fields_mut().files.as_mut().unwrap()[1].length = -1;
+
+        match torrent.recompute_length() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, "file [1] has a negative length.")
+            }
+            _ => panic!(),
+        }
+        assert_eq!(torrent.info.length, 4);
+    }
+
+    #[test]
+    fn recompute_length_overflow() {
+        let mut torrent = multi_file_torrent();
+        torrent.info.fields_mut().files.as_mut().unwrap()[1].length = Integer::MAX;
+
+        match torrent.recompute_length() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, "Torrent's length overflowed in i64.")
+            }
+            _ => panic!(),
+        }
+        assert!(torrent.check_length().is_err());
+        assert_eq!(torrent.info.length, 4);
+    }
+
+    #[test]
+    fn set_name_ok() {
+        let mut torrent = multi_file_torrent();
+        torrent.info.fields_mut().extra_info_fields = Some(HashMap::from([
+            ("name".to_owned(), BencodeElem::Bytes(vec![0xff])),
+            ("name.utf-8".to_owned(), bencode_elem!("sample")),
+        ]));
+        let info_hash = torrent.info_hash();
+
+        torrent.set_name("renamed".to_owned()).unwrap();
+        assert_eq!(torrent.info.name, "renamed");
+        assert_eq!(torrent.info.extra_info_fields, None);
+        assert_ne!(torrent.info_hash(), info_hash);
+    }
+
+    #[test]
+    fn set_name_invalid() {
+        let mut torrent = multi_file_torrent();
+
+        for name in ["", ".", "..", "a/b", "a\\b"] {
+            match torrent.set_name(name.to_owned()) {
+                Err(LavaTorrentError::InvalidArgument(m)) => {
+                    assert_eq!(m, format!("[{}] is not a valid file name.", name))
+                }
+                _ => panic!(),
+            }
+        }
+        assert_eq!(torrent.info.name, "sample");
+    }
+
+    #[test]
+    fn rename_file_ok() {
+        let mut torrent = multi_file_torrent();
+
+        torrent.rename_file(0, PathBuf::from("dir2/file3")).unwrap();
+        let file = &torrent.info.files.as_ref().unwrap()[0];
+        assert_eq!(file.path, PathBuf::from("dir2/file3"));
+        assert_eq!(
+            file.extra_fields,
+            Some(HashMap::from([("attr".to_owned(), bencode_elem!("x"))]))
+        );
+
+        // renaming to the same path is fine
+        torrent.rename_file(1, PathBuf::from("file2")).unwrap();
+        assert_eq!(
+            torrent.info.files.as_ref().unwrap()[1].path,
+            PathBuf::from("file2")
+        );
+    }
+
+    #[test]
+    fn rename_file_single_file() {
+        let mut torrent = multi_file_torrent();
+        torrent.info.fields_mut().files = None;
+
+        torrent.rename_file(0, PathBuf::from("renamed")).unwrap();
+        assert_eq!(torrent.info.name, "renamed");
+
+        match torrent.rename_file(0, PathBuf::from("dir/renamed")) {
+            Err(LavaTorrentError::InvalidArgument(m)) => assert_eq!(
+                m,
+                "[dir/renamed] has more than 1 component but the torrent has only 1 file."
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn rename_file_out_of_bounds() {
+        let mut torrent = multi_file_torrent();
+
+        match torrent.rename_file(2, PathBuf::from("file3")) {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, "file index [2] is out of bounds.")
             }
+            _ => panic!(),
         }
-
-        writeln!(
-            f,
-            "-pieces: [{}]",
-            self.pieces
-                .iter()
-                .format_with(", ", |piece, f| f(&format_args!(
-                    "[{:02x}]",
-                    piece.iter().format("")
-                ))),
-        )
     }
-}
-
-#[cfg(test)]
-mod file_tests {
-    use super::*;
 
     #[test]
-    fn absolute_path_ok() {
-        let file = File {
-            length: 42,
-            path: PathBuf::from("dir1/file"),
-            extra_fields: None,
-        };
+    fn rename_file_invalid_path() {
+        let mut torrent = multi_file_torrent();
 
+        for path in ["", "/file3", "../file3", "./file3"] {
+            match torrent.rename_file(0, PathBuf::from(path)) {
+                Err(LavaTorrentError::InvalidArgument(m)) => {
+                    assert_eq!(m, format!("[{}] is not a valid relative path.", path))
+                }
+                _ => panic!(),
+            }
+        }
         assert_eq!(
-            file.absolute_path("/root").unwrap(),
-            PathBuf::from("/root/dir1/file")
+            torrent.info.files.as_ref().unwrap()[0].path,
+            PathBuf::from("dir/file1")
         );
     }
 
     #[test]
-    fn absolute_path_not_absolute() {
-        let file = File {
-            length: 42,
-            path: PathBuf::from("dir1/file"),
-            extra_fields: None,
-        };
+    fn rename_file_duplicate() {
+        let mut torrent = multi_file_torrent();
 
-        match file.absolute_path("root") {
+        match torrent.rename_file(0, PathBuf::from("file2")) {
             Err(LavaTorrentError::InvalidArgument(m)) => {
-                assert_eq!(m, "Joined path is not absolute.");
+                assert_eq!(m, "[file2] is already the path of another file.")
             }
             _ => panic!(),
         }
     }
-}
-
-#[cfg(test)]
-mod torrent_tests {
-    use super::*;
-    use std::iter::FromIterator;
 
     #[test]
-    fn construct_info_ok() {
-        let torrent = Torrent {
+    fn common_fields_ok() {
+        let mut torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![("key".to_owned(), bencode_elem!("val"))].into_iter(),
-            )),
+            raw_extra_fields: None,
         };
+        assert_eq!(torrent.comment().unwrap(), None);
+        assert_eq!(torrent.created_by().unwrap(), None);
+        assert_eq!(torrent.encoding().unwrap(), None);
+        assert_eq!(torrent.creation_date().unwrap(), None);
 
+        torrent.set_comment(Some("comment".to_owned()));
+        torrent.set_created_by(Some("lava_torrent".to_owned()));
+        torrent.set_encoding(Some("UTF-8".to_owned()));
+        torrent.set_creation_date(Some(1523607302));
+        assert_eq!(torrent.comment().unwrap(), Some("comment"));
+        assert_eq!(torrent.created_by().unwrap(), Some("lava_torrent"));
+        assert_eq!(torrent.encoding().unwrap(), Some("UTF-8"));
+        assert_eq!(torrent.creation_date().unwrap(), Some(1523607302));
         assert_eq!(
-            torrent.construct_info(),
-            bencode_elem!({
-                ("length", 4),
-                ("name", "sample"),
-                ("piece length", 2),
-                ("pieces", (1, 2, 3, 4)),
-                ("key", "val"),
-            }),
+            torrent.extra_fields,
+            Some(HashMap::from([
+                ("comment".to_owned(), bencode_elem!("comment")),
+                ("created by".to_owned(), bencode_elem!("lava_torrent")),
+                ("encoding".to_owned(), bencode_elem!("UTF-8")),
+                ("creation date".to_owned(), bencode_elem!(1523607302_i64)),
+            ]))
         );
+
+        torrent.set_comment(None);
+        torrent.set_created_by(None);
+        torrent.set_encoding(None);
+        torrent.set_creation_date(None);
+        assert_eq!(torrent.extra_fields, None);
     }
 
     #[test]
-    fn info_hash_ok() {
+    fn common_fields_incorrect_val_type() {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: Some(HashMap::from([
+                ("comment".to_owned(), bencode_elem!(1)),
+                ("creation date".to_owned(), bencode_elem!("today")),
+            ])),
+            raw_extra_fields: None,
+        };
+
+        match torrent.comment() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(
+                    m,
+                    r#""comment" does not map to a string (or maps to invalid UTF8)."#
+                )
+            }
+            _ => panic!(),
+        }
+        match torrent.creation_date() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""creation date" does not map to an integer."#)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn content_files_ok() {
+        let file = |path: &str, attr: Option<&str>| File {
+            length: 2,
+            path: PathBuf::from(path),
+            extra_fields: attr
+                .map(|attr| HashMap::from([("attr".to_owned(), bencode_elem!(attr))])),
+            raw_extra_fields: None,
+        };
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 10,
+                files: Some(vec![
+                    file("file1", None),
+                    file(".pad/1", None),
+                    file("file2", Some("x")),
+                    file("padding", Some("p")),
+                    file("dir/.pad", None),
+                ]),
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20], [3; 20], [4; 20], [5; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
         assert_eq!(
-            torrent.info_hash(),
-            "074f42efaf8267f137f114f722d4e7d1dcbfbda5".to_owned(),
+            torrent
+                .content_files()
+                .map(|file| file.path.as_path())
+                .collect::<Vec<_>>(),
+            vec![
+                Path::new("file1"),
+                Path::new("file2"),
+                Path::new("dir/.pad")
+            ]
         );
     }
 
     #[test]
-    fn magnet_link_ok() {
+    fn content_length_ok() {
         let torrent = Torrent {
-            announce: Some("url".to_owned()),
+            announce: None,
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 8,
+                files: Some(vec![
+                    File {
+                        length: 3,
+                        path: PathBuf::from("file1"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                    File {
+                        length: 1,
+                        path: PathBuf::from(".pad/1"),
+                        extra_fields: Some(HashMap::from([(
+                            "attr".to_owned(),
+                            bencode_elem!("p"),
+                        )])),
+                        raw_extra_fields: None,
+                    },
+                    File {
+                        length: 4,
+                        path: PathBuf::from("file2"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                ]),
+                name: "sample".to_owned(),
+                piece_length: 4,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
-        assert_eq!(
-            torrent.magnet_link().unwrap(),
-            "magnet:?xt=urn:btih:074f42efaf8267f137f114f722d4e7d1dcbfbda5\
-             &dn=sample&tr=url"
-                .to_owned()
-        );
+        assert_eq!(torrent.content_length(), 7);
     }
 
     #[test]
-    fn magnet_link_with_announce_list() {
+    fn content_length_single_file() {
         let torrent = Torrent {
-            announce: Some("url".to_owned()),
-            announce_list: Some(vec![
-                vec!["url1".to_owned()],
-                vec!["url2".to_owned(), "url3".to_owned()],
-            ]),
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
-        assert_eq!(
-            torrent.magnet_link().unwrap(),
-            "magnet:?xt=urn:btih:074f42efaf8267f137f114f722d4e7d1dcbfbda5\
-             &dn=sample&tr=url1&tr=url2&tr=url3"
-                .to_owned()
-        );
+        assert_eq!(torrent.content_length(), 4);
     }
 
     #[test]
-    fn magnet_link_with_web_seed() {
+    fn content_files_single_file() {
         let torrent = Torrent {
             announce: None,
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
-            extra_fields: Some(HashMap::from([(
-                "url-list".to_owned(),
-                BencodeElem::String("https://example.org/path".to_owned()),
-            )])),
-            extra_info_fields: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
         };
 
-        assert_eq!(
-            torrent.magnet_link().unwrap(),
-            "magnet:?xt=urn:btih:074f42efaf8267f137f114f722d4e7d1dcbfbda5\
-             &dn=sample&ws=https://example.org/path"
-                .to_owned()
-        );
+        assert_eq!(torrent.content_files().count(), 0);
     }
 
     #[test]
-    fn magnet_link_with_web_seeds() {
+    fn num_pieces_ok() {
         let torrent = Torrent {
             announce: None,
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
-            extra_fields: Some(HashMap::from([(
-                "url-list".to_owned(),
-                BencodeElem::List(vec![
-                    BencodeElem::String("https://example.org/path1".to_owned()),
-                    BencodeElem::String("https://example.org/path2".to_owned()),
-                ]),
-            )])),
-            extra_info_fields: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 5,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20], [3; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
         };
 
-        assert_eq!(
-            torrent.magnet_link().unwrap(),
-            "magnet:?xt=urn:btih:074f42efaf8267f137f114f722d4e7d1dcbfbda5\
-             &dn=sample&ws=https://example.org/path1&ws=https://example.org/path2"
-                .to_owned()
-        );
+        assert_eq!(torrent.num_pieces(), 3);
     }
 
     #[test]
-    fn magnet_link_escape() {
+    fn last_piece_length_short() {
         let torrent = Torrent {
-            announce: Some("https://example.org/path?a=1&b=hello world".to_owned()),
+            announce: None,
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
-            extra_fields: Some(HashMap::from([(
-                "url-list".to_owned(),
-                BencodeElem::String("https://example.org/path?a=1&b=hello world".to_owned()),
-            )])),
-            extra_info_fields: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 5,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20], [3; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
         };
 
-        assert_eq!(
-            torrent.magnet_link().unwrap(),
-            "magnet:?xt=urn:btih:074f42efaf8267f137f114f722d4e7d1dcbfbda5\
-             &dn=sample&tr=https://example.org/path?a=1%26b=hello+world\
-             &ws=https://example.org/path?a=1%26b=hello+world"
-                .to_owned()
-        );
+        assert_eq!(torrent.last_piece_length(), 1);
     }
 
     #[test]
-    fn is_private_ok() {
+    fn last_piece_length_full() {
         let torrent = Torrent {
-            announce: Some("url".to_owned()),
+            announce: None,
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![("private".to_owned(), bencode_elem!(1))].into_iter(),
-            )),
+            raw_extra_fields: None,
         };
 
-        assert!(torrent.is_private());
+        assert_eq!(torrent.last_piece_length(), 2);
     }
 
     #[test]
-    fn is_private_no_extra_fields() {
+    fn last_piece_length_invalid() {
         let torrent = Torrent {
-            announce: Some("url".to_owned()),
+            announce: None,
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 0,
+                pieces: Pieces::new(),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
-        assert!(!torrent.is_private());
+        assert_eq!(torrent.last_piece_length(), 0);
     }
 
     #[test]
-    fn is_private_no_key() {
+    fn pieces_for_file_ok() {
         let torrent = Torrent {
-            announce: Some("url".to_owned()),
+            announce: None,
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 20,
+                files: Some(vec![
+                    File {
+                        length: 3,
+                        path: PathBuf::from("a"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                    File {
+                        length: 0,
+                        path: PathBuf::from("b"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                    File {
+                        length: 9,
+                        path: PathBuf::from("c"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                    File {
+                        length: 8,
+                        path: PathBuf::from("d"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                ]),
+                name: "sample".to_owned(),
+                piece_length: 4,
+                pieces: Pieces::from(vec![[1; 20], [2; 20], [3; 20], [4; 20], [5; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![("".to_owned(), bencode_elem!(1))].into_iter(),
-            )),
+            raw_extra_fields: None,
         };
 
-        assert!(!torrent.is_private());
+        assert_eq!(
+            torrent.pieces_for_file(0).unwrap(),
+            Some(FilePieces {
+                first_piece: 0,
+                last_piece: 0,
+                first_piece_offset: 0,
+                last_piece_end: 3,
+            })
+        );
+        assert_eq!(torrent.pieces_for_file(1).unwrap(), None);
+        assert_eq!(
+            torrent.pieces_for_file(2).unwrap(),
+            Some(FilePieces {
+                first_piece: 0,
+                last_piece: 2,
+                first_piece_offset: 3,
+                last_piece_end: 4,
+            })
+        );
+        assert_eq!(
+            torrent.pieces_for_file(3).unwrap(),
+            Some(FilePieces {
+                first_piece: 3,
+                last_piece: 4,
+                first_piece_offset: 0,
+                last_piece_end: 4,
+            })
+        );
     }
 
     #[test]
-    fn is_private_incorrect_val_type() {
+    fn pieces_for_file_single_file() {
         let torrent = Torrent {
-            announce: Some("url".to_owned()),
+            announce: None,
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 5,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20], [3; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![("private".to_owned(), bencode_elem!("1"))].into_iter(),
-            )),
+            raw_extra_fields: None,
         };
 
-        assert!(!torrent.is_private());
+        assert_eq!(
+            torrent.pieces_for_file(0).unwrap(),
+            Some(FilePieces {
+                first_piece: 0,
+                last_piece: 2,
+                first_piece_offset: 0,
+                last_piece_end: 1,
+            })
+        );
     }
 
     #[test]
-    fn is_private_incorrect_val() {
+    fn pieces_for_file_out_of_bounds() {
         let torrent = Torrent {
-            announce: Some("url".to_owned()),
+            announce: None,
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![("private".to_owned(), bencode_elem!(2))].into_iter(),
-            )),
+            raw_extra_fields: None,
         };
 
-        assert!(!torrent.is_private());
+        match torrent.pieces_for_file(1) {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, "file index [1] is out of bounds.")
+            }
+            _ => panic!(),
+        }
     }
 }
 
@@ -773,6 +3929,7 @@ mod file_display_tests {
             length: 42,
             path: PathBuf::from("dir1/file"),
             extra_fields: None,
+            raw_extra_fields: None,
         };
 
         assert_eq!(
@@ -783,6 +3940,39 @@ mod file_display_tests {
         );
     }
 
+    #[test]
+    fn file_display_human_readable() {
+        let file = File {
+            length: 1536,
+            path: PathBuf::from("dir1/file"),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            file.to_string(),
+            "dir1/file\n\
+             -size: 1536 bytes (1.50 KiB)\n\
+             ========================================\n"
+        );
+    }
+
+    #[test]
+    fn size_display_ok() {
+        assert_eq!(Size(0).to_string(), "0 bytes");
+        assert_eq!(Size(1023).to_string(), "1023 bytes");
+        assert_eq!(Size(1024).to_string(), "1024 bytes (1.00 KiB)");
+        assert_eq!(Size(3 << 20).to_string(), "3145728 bytes (3.00 MiB)");
+        assert_eq!(
+            Size(5 << 30 | 1 << 29).to_string(),
+            "5905580032 bytes (5.50 GiB)"
+        );
+        assert_eq!(
+            Size(Integer::MAX).to_string(),
+            "9223372036854775807 bytes (8.00 EiB)"
+        );
+    }
+
     #[test]
     fn file_display_with_extra_fields() {
         let file = File {
@@ -795,6 +3985,7 @@ mod file_display_tests {
                 ]
                 .into_iter(),
             )),
+            raw_extra_fields: None,
         };
 
         assert_eq!(
@@ -818,13 +4009,21 @@ mod torrent_display_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
         assert_eq!(
@@ -833,7 +4032,7 @@ mod torrent_display_tests {
              -announce: url\n\
              -size: 4 bytes\n\
              -piece length: 2 bytes\n\
-             -pieces: [[0102], [0304]]\n"
+             -pieces: [[0101010101010101010101010101010101010101], [0202020202020202020202020202020202020202]]\n"
         );
     }
 
@@ -845,13 +4044,21 @@ mod torrent_display_tests {
                 vec!["url1".to_owned(), "url2".to_owned()],
                 vec!["url3".to_owned(), "url4".to_owned()],
             ]),
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
         assert_eq!(
@@ -861,7 +4068,7 @@ mod torrent_display_tests {
              -announce-list: [[url1, url2], [url3, url4]]\n\
              -size: 4 bytes\n\
              -piece length: 2 bytes\n\
-             -pieces: [[0102], [0304]]\n"
+             -pieces: [[0101010101010101010101010101010101010101], [0202020202020202020202020202020202020202]]\n"
         );
     }
 
@@ -870,11 +4077,19 @@ mod torrent_display_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: Some(HashMap::from_iter(
                 vec![
                     ("comment2".to_owned(), bencode_elem!("no comment")),
@@ -882,7 +4097,7 @@ mod torrent_display_tests {
                 ]
                 .into_iter(),
             )),
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
         assert_eq!(
@@ -893,7 +4108,7 @@ mod torrent_display_tests {
              -piece length: 2 bytes\n\
              -comment1: \"no comment\"\n\
              -comment2: \"no comment\"\n\
-             -pieces: [[0102], [0304]]\n"
+             -pieces: [[0101010101010101010101010101010101010101], [0202020202020202020202020202020202020202]]\n"
         );
     }
 
@@ -902,19 +4117,27 @@ mod torrent_display_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from_iter(
+                    vec![
+                        ("comment2".to_owned(), bencode_elem!("no comment")),
+                        ("comment1".to_owned(), bencode_elem!("no comment")),
+                    ]
+                    .into_iter(),
+                )),
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![
-                    ("comment2".to_owned(), bencode_elem!("no comment")),
-                    ("comment1".to_owned(), bencode_elem!("no comment")),
-                ]
-                .into_iter(),
-            )),
+            raw_extra_fields: None,
         };
 
         assert_eq!(
@@ -925,7 +4148,7 @@ mod torrent_display_tests {
              -piece length: 2 bytes\n\
              -comment1: \"no comment\"\n\
              -comment2: \"no comment\"\n\
-             -pieces: [[0102], [0304]]\n"
+             -pieces: [[0101010101010101010101010101010101010101], [0202020202020202020202020202020202020202]]\n"
         );
     }
 
@@ -934,24 +4157,34 @@ mod torrent_display_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: Some(vec![
-                File {
-                    length: 2,
-                    path: PathBuf::from("dir1/dir2/file1"),
-                    extra_fields: None,
-                },
-                File {
-                    length: 2,
-                    path: PathBuf::from("dir1/dir2/file2"),
-                    extra_fields: None,
-                },
-            ]),
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: Some(vec![
+                    File {
+                        length: 2,
+                        path: PathBuf::from("dir1/dir2/file1"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                    File {
+                        length: 2,
+                        path: PathBuf::from("dir1/dir2/file2"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                ]),
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
         assert_eq!(
@@ -969,7 +4202,114 @@ mod torrent_display_tests {
              -size: 2 bytes\n\
              ========================================\n\
              \n\
-             -pieces: [[0102], [0304]]\n"
+             -pieces: [[0101010101010101010101010101010101010101], [0202020202020202020202020202020202020202]]\n"
+        );
+    }
+
+    #[test]
+    fn torrent_display_alternate() {
+        let torrent = Torrent {
+            announce: Some("url1".to_owned()),
+            announce_list: Some(vec![
+                vec!["url1".to_owned(), "url2".to_owned()],
+                vec!["url3".to_owned(), "url2".to_owned()],
+            ]),
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            format!("{:#}", torrent),
+            "sample.torrent: 4 bytes, 1 file, trackers: [url1, url2, url3]"
+        );
+    }
+
+    #[test]
+    fn torrent_display_alternate_with_files() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: Some(vec![
+                    File {
+                        length: 2,
+                        path: PathBuf::from("file1"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                    File {
+                        length: 2,
+                        path: PathBuf::from("file2"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                ]),
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            format!("{:#}", torrent),
+            "sample.torrent: 4 bytes, 2 files, trackers: []"
+        );
+    }
+
+    #[test]
+    fn torrent_display_human_readable() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 3 << 20,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2 << 20,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            torrent.to_string(),
+            "sample.torrent\n\
+             -size: 3145728 bytes (3.00 MiB)\n\
+             -piece length: 2097152 bytes (2.00 MiB)\n\
+             -pieces: [[0101010101010101010101010101010101010101], [0202020202020202020202020202020202020202]]\n"
+        );
+        assert_eq!(
+            format!("{:#}", torrent),
+            "sample.torrent: 3145728 bytes (3.00 MiB), 1 file, trackers: []"
         );
     }
 }
@@ -0,0 +1,210 @@
+use super::*;
+#[cfg(feature = "parallel")]
+use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelIterator};
+use std::convert::TryFrom;
+use std::iter::FromIterator;
+use std::ops::{Index, IndexMut};
+
+impl Pieces {
+    /// Create an empty `Pieces`.
+    pub fn new() -> Pieces {
+        Pieces::default()
+    }
+
+    /// Create an empty `Pieces` with space for at least `capacity` hashes.
+    pub fn with_capacity(capacity: usize) -> Pieces {
+        Pieces {
+            hashes: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Create `Pieces` from concatenated hashes (i.e. the
+    /// value of `pieces` in a *.torrent* file).
+    ///
+    /// An error is returned if the length of `bytes` is not a multiple of 20.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Pieces, LavaTorrentError> {
+        match bytes.len() % PIECE_STRING_LENGTH {
+            0 => Ok(Pieces {
+                hashes: bytes
+                    .chunks_exact(PIECE_STRING_LENGTH)
+                    .map(|chunk| Piece::try_from(chunk).unwrap())
+                    .collect(),
+            }),
+            _ => Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                "length of pieces is not a multiple of {}.",
+                PIECE_STRING_LENGTH
+            )))),
+        }
+    }
+
+    /// Get the number of hashes.
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Check if there are no hashes.
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Get the hash at `index`, or `None` if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> Option<&Piece> {
+        self.hashes.get(index)
+    }
+
+    /// Append a hash.
+    pub fn push(&mut self, piece: Piece) {
+        self.hashes.push(piece);
+    }
+
+    /// Keep the first `len` hashes and drop the rest.
+    pub fn truncate(&mut self, len: usize) {
+        self.hashes.truncate(len);
+    }
+
+    /// Iterate over the hashes.
+    pub fn iter(&self) -> std::slice::Iter<'_, Piece> {
+        self.hashes.iter()
+    }
+
+    /// Iterate over the hashes, allowing them to be modified.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Piece> {
+        self.hashes.iter_mut()
+    }
+
+    /// Get all hashes as a slice.
+    pub fn as_slice(&self) -> &[Piece] {
+        &self.hashes
+    }
+
+    /// Get all hashes concatenated.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.hashes.as_flattened()
+    }
+
+    /// Convert into concatenated hashes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.hashes.into_flattened()
+    }
+}
+
+impl Index<usize> for Pieces {
+    type Output = Piece;
+
+    fn index(&self, index: usize) -> &Piece {
+        &self.hashes[index]
+    }
+}
+
+impl IndexMut<usize> for Pieces {
+    fn index_mut(&mut self, index: usize) -> &mut Piece {
+        &mut self.hashes[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a Pieces {
+    type Item = &'a Piece;
+    type IntoIter = std::slice::Iter<'a, Piece>;
+
+    fn into_iter(self) -> std::slice::Iter<'a, Piece> {
+        self.iter()
+    }
+}
+
+impl From<Vec<Piece>> for Pieces {
+    fn from(hashes: Vec<Piece>) -> Pieces {
+        Pieces { hashes }
+    }
+}
+
+impl FromIterator<Piece> for Pieces {
+    fn from_iter<I>(iter: I) -> Pieces
+    where
+        I: IntoIterator<Item = Piece>,
+    {
+        Pieces {
+            hashes: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl FromParallelIterator<Piece> for Pieces {
+    fn from_par_iter<I>(par_iter: I) -> Pieces
+    where
+        I: IntoParallelIterator<Item = Piece>,
+    {
+        Pieces {
+            hashes: par_iter.into_par_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod pieces_tests {
+    use super::*;
+
+    #[test]
+    fn from_bytes_ok() {
+        let pieces = Pieces::from_bytes(&(0..40).collect::<Vec<u8>>()).unwrap();
+
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[0][..], (0..20).collect::<Vec<u8>>()[..]);
+        assert_eq!(pieces[1][..], (20..40).collect::<Vec<u8>>()[..]);
+    }
+
+    #[test]
+    fn from_bytes_invalid_length() {
+        match Pieces::from_bytes(&[0; 21]) {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, "length of pieces is not a multiple of 20.")
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn get_ok() {
+        let pieces = Pieces::from(vec![[1; 20], [2; 20]]);
+
+        assert_eq!(pieces.get(1), Some(&[2; 20]));
+        assert_eq!(pieces.get(2), None);
+        assert_eq!(pieces.get(usize::MAX), None);
+    }
+
+    #[test]
+    fn push_and_truncate() {
+        let mut pieces = Pieces::new();
+        pieces.push([1; 20]);
+        pieces.push([2; 20]);
+        assert_eq!(pieces, Pieces::from(vec![[1; 20], [2; 20]]));
+
+        pieces.truncate(1);
+        assert_eq!(pieces, Pieces::from(vec![[1; 20]]));
+
+        pieces.truncate(0);
+        assert!(pieces.is_empty());
+    }
+
+    #[test]
+    fn index_mut_ok() {
+        let mut pieces = Pieces::from(vec![[1; 20], [2; 20]]);
+        pieces[1][0] = 3;
+
+        assert_eq!(pieces[1][..2], [3, 2]);
+    }
+
+    #[test]
+    fn iter_ok() {
+        let pieces = [[1; 20], [2; 20], [3; 20]]
+            .iter()
+            .copied()
+            .collect::<Pieces>();
+
+        assert_eq!(
+            pieces.iter().map(|piece| piece[0]).collect::<Vec<u8>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(pieces.as_bytes().len(), 60);
+    }
+}
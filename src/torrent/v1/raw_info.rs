@@ -0,0 +1,291 @@
+use super::*;
+#[cfg(feature = "fs")]
+use crate::bencode::write::write_file;
+#[cfg(feature = "fs")]
+use crate::bencode::WriteOptions;
+#[cfg(feature = "fs")]
+use crate::util;
+use std::io::Write;
+use std::ops::{Deref, Range};
+
+impl RawInfoTorrent {
+    /// Like [`Torrent::read_from_bytes()`], but also keeps
+    /// the original bytes of `info`.
+    ///
+    /// [`Torrent::read_from_bytes()`]: struct.Torrent.html#method.read_from_bytes
+    pub fn read_from_bytes<B>(bytes: B) -> Result<RawInfoTorrent, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = Torrent::decompress(bytes.as_ref(), &[])?;
+        let torrent = Torrent::read_from_bytes(&bytes)?;
+        // `bytes` has been parsed successfully, so `info` must be in there
+        let info = info_range(&bytes)
+            .map(|range| bytes[range].to_vec())
+            .ok_or(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::MissingField {
+                    field: Cow::Borrowed("info"),
+                },
+            ))?;
+
+        Ok(RawInfoTorrent {
+            torrent,
+            info_bytes: info,
+        })
+    }
+
+    /// Like [`Torrent::read_from_file()`], but also keeps
+    /// the original bytes of `info`.
+    ///
+    /// [`Torrent::read_from_file()`]: struct.Torrent.html#method.read_from_file
+    #[cfg(feature = "fs")]
+    pub fn read_from_file<P>(path: P) -> Result<RawInfoTorrent, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        Self::read_from_bytes(std::fs::read(path).map_err(util::file_io_err(path, None))?)
+    }
+
+    /// Like [`Torrent::from_info_bytes()`], but also keeps `bytes`.
+    ///
+    /// [`Torrent::from_info_bytes()`]: struct.Torrent.html#method.from_info_bytes
+    pub fn from_info_bytes<B>(
+        bytes: B,
+        trackers: Vec<String>,
+        info_hash: Option<&[u8]>,
+    ) -> Result<RawInfoTorrent, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = bytes.as_ref();
+        Ok(RawInfoTorrent {
+            torrent: Torrent::from_info_bytes(bytes, trackers, info_hash)?,
+            info_bytes: bytes.to_vec(),
+        })
+    }
+
+    /// Get the original bytes of `info`.
+    pub fn info_bytes(&self) -> &[u8] {
+        &self.info_bytes
+    }
+
+    /// Calculate the info hash from the original bytes of `info`.
+    ///
+    /// This might differ from the wrapped torrent's [`Torrent::info_hash()`]
+    /// if the original encoding of `info` is not canonical.
+    ///
+    /// [`Torrent::info_hash()`]: struct.Torrent.html#method.info_hash
+    pub fn info_hash(&self) -> String {
+        format!("{:x}", Sha1::digest(&self.info_bytes))
+    }
+
+    /// Like [`info_hash()`], but returns the raw bytes.
+    ///
+    /// [`info_hash()`]: #method.info_hash
+    pub fn info_hash_bytes(&self) -> Vec<u8> {
+        Sha1::digest(&self.info_bytes).to_vec()
+    }
+
+    /// Encode `self` as bencode and write the result to `dst`.
+    ///
+    /// Fields outside of `info` are encoded from the wrapped torrent,
+    /// while `info` is written exactly as it was read.
+    pub fn write_into<W>(&self, dst: &mut W) -> Result<(), LavaTorrentError>
+    where
+        W: Write,
+    {
+        self.torrent.write_into_with_info(&self.info_bytes, dst)
+    }
+
+    /// Like [`write_into()`], but writes the result to the file at `path`.
+    ///
+    /// As with [`Torrent::write_into_file()`], the file is created if it
+    /// does not exist, and truncated if it does.
+    ///
+    /// [`write_into()`]: #method.write_into
+    /// [`Torrent::write_into_file()`]: struct.Torrent.html#method.write_into_file
+    #[cfg(feature = "fs")]
+    pub fn write_into_file<P>(&self, path: P) -> Result<(), LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        write_file(path.as_ref(), &WriteOptions::default(), |dst| {
+            self.write_into(dst)
+        })
+    }
+
+    /// Like [`write_into()`], but returns the result in a `Vec`.
+    ///
+    /// [`write_into()`]: #method.write_into
+    pub fn encode(&self) -> Result<Vec<u8>, LavaTorrentError> {
+        let mut result = Vec::new();
+        self.write_into(&mut result)?;
+        Ok(result)
+    }
+
+    /// Unwrap the parsed torrent, discarding the original bytes of `info`.
+    pub fn into_torrent(self) -> Torrent {
+        self.torrent
+    }
+}
+
+impl Deref for RawInfoTorrent {
+    type Target = Torrent;
+
+    fn deref(&self) -> &Torrent {
+        &self.torrent
+    }
+}
+
+// Find the value of `info` in the top-level dictionary of a torrent.
+// `bytes` is expected to have been parsed successfully already, so
+// this only makes sure that nothing out of bounds is accessed.
+fn info_range(bytes: &[u8]) -> Option<Range<usize>> {
+    if bytes.first() != Some(&b'd') {
+        return None;
+    }
+
+    let mut pos = 1;
+    while bytes.get(pos)? != &b'e' {
+        let key = string_range(bytes, pos)?;
+        let value_end = skip_value(bytes, key.end)?;
+        if &bytes[key.clone()] == b"info" {
+            return Some(key.end..value_end);
+        }
+        pos = value_end;
+    }
+    None
+}
+
+// Range of the content of the string at `pos`.
+fn string_range(bytes: &[u8], pos: usize) -> Option<Range<usize>> {
+    let delimiter = pos + bytes.get(pos..)?.iter().position(|&b| b == b':')?;
+    let len: usize = std::str::from_utf8(&bytes[pos..delimiter])
+        .ok()?
+        .parse()
+        .ok()?;
+    let end = delimiter.checked_add(1)?.checked_add(len)?;
+
+    if end <= bytes.len() {
+        Some(delimiter + 1..end)
+    } else {
+        None
+    }
+}
+
+// End of the value at `pos`. Nesting is tracked with a counter
+// (instead of recursion) since keys don't need to be told apart.
+fn skip_value(bytes: &[u8], mut pos: usize) -> Option<usize> {
+    let mut depth = 0_usize;
+
+    loop {
+        match *bytes.get(pos)? {
+            b'd' | b'l' => {
+                depth += 1;
+                pos += 1;
+            }
+            b'e' => {
+                depth = depth.checked_sub(1)?;
+                pos += 1;
+            }
+            b'i' => pos += bytes.get(pos..)?.iter().position(|&b| b == b'e')? + 1,
+            _ => pos = string_range(bytes, pos)?.end,
+        }
+
+        if depth == 0 {
+            return Some(pos);
+        }
+    }
+}
+
+#[cfg(test)]
+mod raw_info_torrent_tests {
+    use super::*;
+
+    // `length` is not canonical (i.e. it changes when re-encoded)
+    const TORRENT: &[u8] = b"d8:announce3:url7:comment4:none4:infod6:lengthi+4e\
+        4:name6:sample12:piece lengthi4e6:pieces20:aaaaaaaaaaaaaaaaaaaa\
+        7:unknownli1ed1:ai2eeeee";
+    const INFO: &[u8] = b"d6:lengthi+4e4:name6:sample12:piece lengthi4e\
+        6:pieces20:aaaaaaaaaaaaaaaaaaaa7:unknownli1ed1:ai2eeee";
+
+    #[test]
+    fn read_from_bytes_ok() {
+        let torrent = RawInfoTorrent::read_from_bytes(TORRENT).unwrap();
+
+        assert_eq!(torrent.info_bytes(), INFO);
+        assert_eq!(torrent.info.length, 4);
+        assert_eq!(torrent.info_hash_bytes(), Sha1::digest(INFO).to_vec());
+        assert_eq!(torrent.info_hash(), format!("{:x}", Sha1::digest(INFO)));
+        assert_ne!(torrent.info_hash(), torrent.torrent.info_hash());
+        assert_eq!(
+            torrent.clone().into_torrent(),
+            Torrent::read_from_bytes(TORRENT).unwrap()
+        );
+    }
+
+    #[test]
+    fn read_from_bytes_invalid() {
+        match RawInfoTorrent::read_from_bytes(b"d4:infoi1ee") {
+            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(
+                m,
+                TorrentErrorKind::WrongType {
+                    field: Cow::Borrowed("info"),
+                    expected: ValueType::Dictionary,
+                }
+                .to_string()
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn encode_ok() {
+        let torrent = RawInfoTorrent::read_from_bytes(TORRENT).unwrap();
+        assert_eq!(torrent.encode().unwrap(), TORRENT);
+
+        // a canonical torrent is encoded the same as by `Torrent`
+        let torrent = Torrent::read_from_bytes(TORRENT).unwrap();
+        let encoded = torrent.clone().encode().unwrap();
+        let raw = RawInfoTorrent::read_from_bytes(&encoded).unwrap();
+        assert_eq!(raw.encode().unwrap(), encoded);
+        assert_eq!(raw.info_hash(), torrent.info_hash());
+    }
+
+    #[test]
+    fn from_info_bytes_ok() {
+        let torrent = RawInfoTorrent::from_info_bytes(
+            INFO,
+            vec!["url".to_owned()],
+            Some(&Sha1::digest(INFO)),
+        )
+        .unwrap();
+
+        assert_eq!(torrent.info_bytes(), INFO);
+        assert_eq!(torrent.announce, Some("url".to_owned()));
+        assert_eq!(
+            torrent.encode().unwrap(),
+            [
+                &b"d8:announce3:url13:announce-listll3:urlee4:info"[..],
+                INFO,
+                b"e"
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn info_range_ok() {
+        assert_eq!(info_range(TORRENT), Some(37..37 + INFO.len()));
+        assert_eq!(info_range(b"d4:infoi1ee"), Some(7..10));
+        assert_eq!(info_range(b"d1:a0:4:info0:e"), Some(12..14));
+        assert_eq!(info_range(b"de"), None);
+        assert_eq!(info_range(b"d4:info"), None);
+        assert_eq!(info_range(b"d4:infodee"), Some(7..9));
+        assert_eq!(info_range(b"d4:infod"), None);
+        assert_eq!(info_range(b"d4:info99:e"), None);
+        assert_eq!(info_range(b"li1ee"), None);
+        assert_eq!(info_range(b""), None);
+    }
+}
@@ -1,24 +1,45 @@
+use super::merkle::extract_root_hash;
 use super::*;
-use crate::bencode::BencodeElem;
+use crate::bencode::{BencodeElem, ReadLimit};
+use crate::gzip;
 use crate::util;
 use std::borrow::Cow;
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::io::Read;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::path::PathBuf;
+
+const MAX_DECOMPRESSED_LEN: usize = 256 * 1024 * 1024;
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+#[cfg(feature = "http")]
+const DEFAULT_MAX_URL_BYTES: usize = 64 * 1024 * 1024;
 
 impl File {
     fn extract_file(elem: BencodeElem) -> Result<File, LavaTorrentError> {
-        match elem {
-            BencodeElem::Dictionary(mut dict) => Ok(File {
-                length: Self::extract_file_length(&mut dict)?,
-                path: Self::extract_file_path(&mut dict)?,
-                extra_fields: Self::extract_file_extra_fields(dict),
-            }),
+        let (mut dict, raw_extra_fields) = match elem {
+            BencodeElem::Dictionary(dict) => (dict, None),
+            BencodeElem::RawDictionary(dict) => split_raw_dictionary(dict),
+            BencodeElem::SortedDictionary(dict) => split_raw_dictionary(dict.into_iter().collect()),
             _ => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""files" contains a non-dictionary element."#,
-                )));
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::WrongElementType {
+                        field: Cow::Borrowed("files"),
+                        expected: ValueType::Dictionary,
+                    },
+                ));
             }
-        }
+        };
+
+        Ok(File {
+            length: Self::extract_file_length(&mut dict)?,
+            path: Self::extract_file_path(&mut dict)?,
+            extra_fields: Self::extract_file_extra_fields(dict),
+            raw_extra_fields,
+        })
     }
 
     fn extract_file_length(
@@ -29,68 +50,125 @@ impl File {
                 if len >= 0 {
                     Ok(len)
                 } else {
-                    return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                        r#""length" < 0."#,
-                    )));
+                    return Err(LavaTorrentError::MalformedTorrent(
+                        TorrentErrorKind::InvalidLength { length: len },
+                    ));
                 }
             }
             Some(_) => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""length" does not map to an integer."#,
-                )));
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::WrongType {
+                        field: Cow::Borrowed("length"),
+                        expected: ValueType::Integer,
+                    },
+                ));
             }
             None => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""length" does not exist."#,
-                )));
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::MissingField {
+                        field: Cow::Borrowed("length"),
+                    },
+                ));
             }
         }
     }
 
+    // If a component of `path` is not valid UTF-8 but `path.utf-8` exists,
+    // the latter is used instead. The original `path` is then left in `dict`
+    // (and thus ends up in `extra_fields`), so that it is re-encoded as is.
     fn extract_file_path(
         dict: &mut HashMap<String, BencodeElem>,
     ) -> Result<PathBuf, LavaTorrentError> {
         match dict.remove("path") {
             Some(BencodeElem::List(list)) => {
                 if list.is_empty() {
-                    return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                        r#""path" maps to a 0-length list."#,
-                    )));
-                } else {
-                    let mut path = PathBuf::new();
-                    for component in list {
-                        if let BencodeElem::String(component) = component {
-                            // "Path components exactly matching '.' and '..'
-                            // must be sanitized. This sanitizing step must
-                            // happen after normalizing overlong UTF-8 encodings."
-                            // Rust rejects overlong encodings, so no need to normalize.
-                            if (component == ".") || (component == "..") {
-                                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                                    r#""path" contains "." or ".."."#,
-                                )));
-                            } else {
-                                path.push(component);
+                    return Err(LavaTorrentError::MalformedTorrent(
+                        TorrentErrorKind::EmptyField {
+                            field: Cow::Borrowed("path"),
+                        },
+                    ));
+                }
+
+                match Self::path_from_components(&list) {
+                    Err(e) if list.iter().any(|c| matches!(c, BencodeElem::Bytes(_))) => {
+                        match dict.get("path.utf-8") {
+                            Some(BencodeElem::List(utf8_list)) if !utf8_list.is_empty() => {
+                                let path = Self::path_from_components(utf8_list)?;
+                                dict.insert("path".to_owned(), BencodeElem::List(list));
+                                Ok(path)
+                            }
+                            _ => {
+                                // keep the original so that it can be re-encoded as is
+                                let path = Self::lossy_path_from_components(&list).ok_or(e)??;
+                                dict.insert("path".to_owned(), BencodeElem::List(list));
+                                Ok(path)
                             }
-                        } else {
-                            return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                                r#""path" contains a non-string element."#,
-                            )));
                         }
                     }
-                    Ok(path)
+                    result => result,
                 }
             }
             Some(_) => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""path" does not map to a list."#,
-                )));
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::WrongType {
+                        field: Cow::Borrowed("path"),
+                        expected: ValueType::List,
+                    },
+                ));
             }
             None => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""path" does not exist."#,
-                )));
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::MissingField {
+                        field: Cow::Borrowed("path"),
+                    },
+                ));
+            }
+        }
+    }
+
+    fn path_from_components(list: &[BencodeElem]) -> Result<PathBuf, LavaTorrentError> {
+        let mut path = PathBuf::new();
+        for component in list {
+            if let BencodeElem::String(component) = component {
+                // "Path components exactly matching '.' and '..'
+                // must be sanitized. This sanitizing step must
+                // happen after normalizing overlong UTF-8 encodings."
+                // Rust rejects overlong encodings, so no need to normalize.
+                if (component == ".") || (component == "..") {
+                    return Err(LavaTorrentError::MalformedTorrent(
+                        TorrentErrorKind::InvalidPathComponent {
+                            component: component.clone(),
+                        },
+                    ));
+                } else {
+                    path.push(component);
+                }
+            } else {
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::WrongElementType {
+                        field: Cow::Borrowed("path"),
+                        expected: ValueType::String,
+                    },
+                ));
             }
         }
+        Ok(path)
+    }
+
+    // Like `path_from_components()`, but components that are not valid UTF-8
+    // are converted lossily. Returns `None` if `list` contains a non-string element.
+    fn lossy_path_from_components(
+        list: &[BencodeElem],
+    ) -> Option<Result<PathBuf, LavaTorrentError>> {
+        let lossy = list
+            .iter()
+            .map(|component| {
+                component
+                    .as_bytes()
+                    .map(|bytes| BencodeElem::String(String::from_utf8_lossy(bytes).into_owned()))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self::path_from_components(&lossy))
     }
 
     fn extract_file_extra_fields(dict: HashMap<String, BencodeElem>) -> Option<Dictionary> {
@@ -107,98 +185,723 @@ impl Torrent {
     ///
     /// If `bytes` is missing any required field (e.g. `info`), or if any other
     /// error is encountered (e.g. `IOError`), then `Err(error)` will be returned.
+    ///
+    /// If `name` or a file's `path` is not valid UTF-8 but `name.utf-8` or `path.utf-8`
+    /// exists (as in some old torrents), the latter is used instead. The original
+    /// value is kept in `extra_info_fields` or the file's `extra_fields`, so that
+    /// encoding the torrent preserves its info hash. A `path` without `path.utf-8`
+    /// is converted lossily; its original components are available through
+    /// [`File::raw_path()`].
+    ///
+    /// Fields whose keys are not valid UTF-8 are kept in `raw_extra_fields`,
+    /// `raw_extra_info_fields` or the file's `raw_extra_fields`, for the same reason.
+    ///
+    /// `bytes` are decompressed first if they are gzip-compressed, as served by
+    /// some trackers and found in some archives. The decompressed torrent
+    /// must not exceed 256 MiB. The other `read_*` functions do the same.
+    ///
+    /// [`File::raw_path()`]: struct.File.html#method.raw_path
     pub fn read_from_bytes<B>(bytes: B) -> Result<Torrent, LavaTorrentError>
     where
         B: AsRef<[u8]>,
     {
+        let bytes = Self::decompress(bytes.as_ref(), &[])?;
         Self::from_parsed(BencodeElem::from_bytes(bytes)?)?.validate()
     }
 
+    /// Extract a `Torrent` from an already parsed bencode dictionary,
+    /// e.g. one returned by a tracker's API or embedded in a larger document.
+    ///
+    /// `elem` is validated in the same way as in [`read_from_bytes()`],
+    /// which would otherwise require encoding it first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lava_torrent::bencode::BencodeElem;
+    /// use lava_torrent::torrent::v1::Torrent;
+    /// use std::collections::HashMap;
+    ///
+    /// let info = HashMap::from([
+    ///     ("length".to_owned(), BencodeElem::Integer(4)),
+    ///     ("name".to_owned(), BencodeElem::from("sample")),
+    ///     ("piece length".to_owned(), BencodeElem::Integer(4)),
+    ///     ("pieces".to_owned(), BencodeElem::Bytes(vec![0xff; 20])),
+    /// ]);
+    /// let torrent = HashMap::from([("info".to_owned(), BencodeElem::Dictionary(info))]);
+    /// let mut document = HashMap::from([("torrent".to_owned(), BencodeElem::Dictionary(torrent))]);
+    ///
+    /// let torrent = Torrent::from_bencode(document.remove("torrent").unwrap()).unwrap();
+    /// assert_eq!(torrent.info.name, "sample");
+    /// ```
+    ///
+    /// [`read_from_bytes()`]: #method.read_from_bytes
+    pub fn from_bencode(elem: BencodeElem) -> Result<Torrent, LavaTorrentError> {
+        Self::from_parsed(vec![elem])?.validate()
+    }
+
+    /// Parse a bencoded info dictionary on its own (e.g. as received from peers
+    /// through [BEP 9](http://bittorrent.org/beps/bep_0009.html) metadata exchange)
+    /// and return a `Torrent` with it and `trackers`.
+    ///
+    /// Each tracker is put in its own tier of `announce_list` (as with
+    /// [`add_tracker()`]), which is left empty if there are no `trackers`.
+    ///
+    /// If `info_hash` is given (e.g. from a magnet link), it is compared with
+    /// the SHA1 hash of `bytes`, and `Err` will be returned if they differ.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lava_torrent::torrent::v1::Torrent;
+    ///
+    /// let info = b"d6:lengthi4e4:name6:sample12:piece lengthi4e6:pieces20:aaaaaaaaaaaaaaaaaaaae";
+    /// let trackers = vec!["udp://tracker.example.com:6969".to_owned()];
+    ///
+    /// let torrent = Torrent::from_info_bytes(info, trackers, None).unwrap();
+    /// assert_eq!(torrent.info.name, "sample");
+    /// assert_eq!(torrent.announce.as_deref(), Some("udp://tracker.example.com:6969"));
+    /// ```
+    ///
+    /// [`add_tracker()`]: #method.add_tracker
+    pub fn from_info_bytes<B>(
+        bytes: B,
+        trackers: Vec<String>,
+        info_hash: Option<&[u8]>,
+    ) -> Result<Torrent, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = bytes.as_ref();
+        if let Some(expected) = info_hash {
+            let actual = Sha1::digest(bytes);
+            if actual[..] != *expected {
+                return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                    Cow::Owned(format!(
+                        "Info hash {:02x} does not match the expected {:02x}.",
+                        actual.iter().format(""),
+                        expected.iter().format(""),
+                    )),
+                )));
+            }
+        }
+
+        let mut parsed = BencodeElem::from_bytes(bytes)?;
+        if parsed.len() != 1 {
+            return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                Cow::Owned(format!(
+                    "Info should contain 1 and only 1 top-level element, {} found.",
+                    parsed.len()
+                )),
+            )));
+        }
+        let info = parsed.remove(0);
+        let mut torrent = Self::from_parsed(vec![BencodeElem::Dictionary(HashMap::from([(
+            "info".to_owned(),
+            info,
+        )]))])?
+        .validate()?;
+
+        for url in trackers {
+            torrent.add_tracker(url);
+        }
+        Ok(torrent)
+    }
+
     /// Parse the content of the file at `path` and return the extracted `Torrent`.
     ///
     /// If the file at `path` is missing any required field (e.g. `info`), or if any other
     /// error is encountered (e.g. `IOError`), then `Err(error)` will be returned.
+    ///
+    /// As with [`read_from_bytes()`], gzip-compressed files are decompressed first.
+    ///
+    /// [`read_from_bytes()`]: #method.read_from_bytes
+    #[cfg(feature = "fs")]
     pub fn read_from_file<P>(path: P) -> Result<Torrent, LavaTorrentError>
     where
         P: AsRef<Path>,
     {
-        Self::from_parsed(BencodeElem::from_file(path)?)?.validate()
+        let path = path.as_ref();
+        Self::read_from_bytes(std::fs::read(path).map_err(util::file_io_err(path, None))?)
     }
 
-    // @note: Most of validation is done when bdecoding and parsing torrent,
-    // so there's not much going on here. More validation could be
-    // added in the future if necessary.
-    fn validate(self) -> Result<Torrent, LavaTorrentError> {
-        if let Some(total_piece_length) =
-            util::i64_to_usize(self.piece_length)?.checked_mul(self.pieces.len())
-        {
-            if total_piece_length < util::i64_to_usize(self.length)? {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Owned(format!(
-                    "Total piece length {} < torrent's length {}.",
-                    total_piece_length, self.length,
+    /// Like [`read_from_bytes()`], but the torrent is validated according to `policy`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use lava_torrent::torrent::v1::{Torrent, ValidationPolicy};
+    ///
+    /// let bytes = std::fs::read("sample.torrent").unwrap();
+    /// let torrent = Torrent::read_from_bytes_with_policy(bytes, ValidationPolicy::Lenient).unwrap();
+    /// ```
+    ///
+    /// [`read_from_bytes()`]: #method.read_from_bytes
+    pub fn read_from_bytes_with_policy<B>(
+        bytes: B,
+        policy: ValidationPolicy,
+    ) -> Result<Torrent, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = Self::decompress(bytes.as_ref(), &[])?;
+        let parsed = match policy {
+            ValidationPolicy::Strict => BencodeElem::from_bytes_strict(bytes, &[])?,
+            ValidationPolicy::Standard | ValidationPolicy::Lenient => {
+                BencodeElem::from_bytes(bytes)?
+            }
+        };
+        Self::from_parsed(parsed)?.validate_with_policy(policy)
+    }
+
+    /// Like [`read_from_file()`], but the torrent is validated according to `policy`.
+    ///
+    /// [`read_from_file()`]: #method.read_from_file
+    #[cfg(feature = "fs")]
+    pub fn read_from_file_with_policy<P>(
+        path: P,
+        policy: ValidationPolicy,
+    ) -> Result<Torrent, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        Self::read_from_bytes_with_policy(
+            fs::read(path).map_err(util::file_io_err(path, None))?,
+            policy,
+        )
+    }
+
+    /// Like [`read_from_bytes()`], but `Err(error)` is also returned
+    /// if `bytes` exceeds any of the `limits`.
+    ///
+    /// If `bytes` are gzip-compressed, the `limits` (including
+    /// [`ReadLimit::Bytes`]) apply to the decompressed torrent.
+    ///
+    /// [`read_from_bytes()`]: #method.read_from_bytes
+    /// [`ReadLimit::Bytes`]: ../../bencode/enum.ReadLimit.html#variant.Bytes
+    pub fn read_from_bytes_with_limits<B>(
+        bytes: B,
+        limits: &[ReadLimit],
+    ) -> Result<Torrent, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = Self::decompress(bytes.as_ref(), limits)?;
+        Self::from_parsed(BencodeElem::from_bytes_with_limits(bytes, limits)?)?.validate()
+    }
+
+    /// Like [`read_from_file()`], but `Err(error)` is also returned if the
+    /// content of the file at `path` exceeds any of the `limits`.
+    ///
+    /// As with [`read_from_bytes_with_limits()`], the `limits` apply
+    /// to the decompressed torrent if the file is gzip-compressed.
+    ///
+    /// [`read_from_file()`]: #method.read_from_file
+    /// [`read_from_bytes_with_limits()`]: #method.read_from_bytes_with_limits
+    #[cfg(feature = "fs")]
+    pub fn read_from_file_with_limits<P>(
+        path: P,
+        limits: &[ReadLimit],
+    ) -> Result<Torrent, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let max_len = max_bytes(limits);
+        let mut file = fs::File::open(path).map_err(util::file_io_err(path, None))?;
+        let mut bytes = Vec::new();
+
+        // read 1 more byte so that oversized input can be detected
+        (&mut file)
+            .take(u64::try_from(max_len).unwrap_or(u64::MAX).saturating_add(1))
+            .read_to_end(&mut bytes)
+            .map_err(util::file_io_err(path, None))?;
+        // the limits apply to the decompressed torrent, which may be
+        // larger than the compressed file
+        if bytes.len() > max_len && gzip::is_gzip(&bytes) {
+            file.take(MAX_DECOMPRESSED_LEN as u64)
+                .read_to_end(&mut bytes)
+                .map_err(util::file_io_err(path, None))?;
+        }
+        Self::read_from_bytes_with_limits(bytes, limits)
+    }
+
+    /// Like [`read_from_file()`], but the file at `path` is memory-mapped
+    /// instead of being read into memory first. Requires the `mmap` feature.
+    ///
+    /// # Safety
+    ///
+    /// See [`BencodeElem::from_file_mmap()`].
+    ///
+    /// [`read_from_file()`]: #method.read_from_file
+    /// [`BencodeElem::from_file_mmap()`]: ../../bencode/enum.BencodeElem.html#method.from_file_mmap
+    #[cfg(feature = "mmap")]
+    pub unsafe fn read_from_file_mmap<P>(path: P) -> Result<Torrent, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let file = fs::File::open(path).map_err(util::file_io_err(path, None))?;
+        let bytes = memmap2::Mmap::map(&file).map_err(util::file_io_err(path, None))?;
+
+        Self::read_from_bytes(&bytes[..])
+    }
+
+    /// Download the torrent at `url` and return the extracted `Torrent`.
+    /// Requires the `http` feature.
+    ///
+    /// Both `http://` and `https://` URLs are supported, and HTTPS servers are
+    /// authenticated with the Mozilla root certificates. Redirects are followed,
+    /// including those from `http://` to `https://`. The download
+    /// is limited to 64 MiB, see [`read_from_url_with_limits()`] for a different
+    /// limit. `Err(LavaTorrentError::MalformedResponse)` is returned if the server
+    /// does not respond with `200 OK`, or if the response is obviously not a torrent
+    /// (e.g. `Content-Type: text/html`, which is common for login pages).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use lava_torrent::torrent::v1::Torrent;
+    ///
+    /// let torrent = Torrent::read_from_url("https://example.com/sample.torrent").unwrap();
+    /// println!("{}", torrent);
+    /// ```
+    ///
+    /// [`read_from_url_with_limits()`]: #method.read_from_url_with_limits
+    #[cfg(feature = "http")]
+    pub fn read_from_url(url: &str) -> Result<Torrent, LavaTorrentError> {
+        Self::read_from_url_with_limits(url, &[ReadLimit::Bytes(DEFAULT_MAX_URL_BYTES)])
+    }
+
+    /// Like [`read_from_url()`], but `limits` are enforced while downloading
+    /// and parsing. See [`BencodeElem::from_bytes_with_limits()`].
+    ///
+    /// The download is only limited if `limits` contains a [`ReadLimit::Bytes`].
+    ///
+    /// [`read_from_url()`]: #method.read_from_url
+    /// [`BencodeElem::from_bytes_with_limits()`]: ../../bencode/enum.BencodeElem.html#method.from_bytes_with_limits
+    /// [`ReadLimit::Bytes`]: ../../bencode/enum.ReadLimit.html#variant.Bytes
+    #[cfg(feature = "http")]
+    pub fn read_from_url_with_limits(
+        url: &str,
+        limits: &[ReadLimit],
+    ) -> Result<Torrent, LavaTorrentError> {
+        let response = crate::http::get(url, max_bytes(limits))?;
+        if response.status != 200 {
+            return Err(LavaTorrentError::MalformedResponse(Cow::Owned(format!(
+                "Server returned HTTP status {}.",
+                response.status
+            ))));
+        }
+        if let Some(content_type) = response.header("content-type") {
+            let content_type = content_type.to_ascii_lowercase();
+            if content_type.starts_with("text/")
+                || content_type.contains("html")
+                || content_type.contains("json")
+                || content_type.contains("xml")
+            {
+                return Err(LavaTorrentError::MalformedResponse(Cow::Owned(format!(
+                    "Server returned unexpected Content-Type: {}",
+                    content_type
                 ))));
-            } else if self.length <= 0 {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""length" <= 0."#,
-                )));
-            } else {
-                Ok(self)
             }
+        }
+
+        Self::read_from_bytes_with_limits(response.body, limits)
+    }
+
+    /// Like [`read_from_bytes()`], but `name` and `path`s that are not valid UTF-8
+    /// are transcoded with `decoder`, according to the top-level `encoding` field
+    /// found in some old torrents (e.g. `GBK` or `Shift_JIS`).
+    ///
+    /// `decoder` is called with the value of `encoding` and the bytes to transcode,
+    /// and should return `None` if it can't handle them. It is not called if
+    /// `encoding` does not exist. This crate does not include any charset tables,
+    /// so `decoder` would usually be backed by a crate such as `encoding_rs`.
+    ///
+    /// As with `name.utf-8` and `path.utf-8`, the original values are kept in the
+    /// extra fields, so that encoding the torrent preserves its info hash.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use lava_torrent::torrent::v1::Torrent;
+    ///
+    /// let bytes = std::fs::read("sample.torrent").unwrap();
+    /// let torrent = Torrent::read_from_bytes_with_decoder(bytes, |encoding, bytes| {
+    ///     if encoding.eq_ignore_ascii_case("ISO-8859-1") {
+    ///         Some(bytes.iter().map(|&b| char::from(b)).collect())
+    ///     } else {
+    ///         None
+    ///     }
+    /// })
+    /// .unwrap();
+    /// ```
+    ///
+    /// [`read_from_bytes()`]: #method.read_from_bytes
+    pub fn read_from_bytes_with_decoder<B, F>(
+        bytes: B,
+        decoder: F,
+    ) -> Result<Torrent, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+        F: Fn(&str, &[u8]) -> Option<String>,
+    {
+        let bytes = Self::decompress(bytes.as_ref(), &[])?;
+        Self::from_parsed_with_decoder(BencodeElem::from_bytes(bytes)?, decoder)?.validate()
+    }
+
+    /// Like [`read_from_file()`], but `name` and `path`s that are not valid UTF-8
+    /// are transcoded with `decoder`. See [`read_from_bytes_with_decoder()`].
+    ///
+    /// [`read_from_file()`]: #method.read_from_file
+    /// [`read_from_bytes_with_decoder()`]: #method.read_from_bytes_with_decoder
+    #[cfg(feature = "fs")]
+    pub fn read_from_file_with_decoder<P, F>(
+        path: P,
+        decoder: F,
+    ) -> Result<Torrent, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+        F: Fn(&str, &[u8]) -> Option<String>,
+    {
+        let path = path.as_ref();
+        Self::read_from_bytes_with_decoder(
+            fs::read(path).map_err(util::file_io_err(path, None))?,
+            decoder,
+        )
+    }
+
+    /// Parse `bytes` and return the extracted `TorrentMetadata`.
+    ///
+    /// This is the same as [`read_from_bytes()`], except that the piece hashes
+    /// are only validated and not decoded.
+    ///
+    /// [`read_from_bytes()`]: #method.read_from_bytes
+    pub fn read_metadata_from_bytes<B>(bytes: B) -> Result<TorrentMetadata, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = Self::decompress(bytes.as_ref(), &[])?;
+        Self::metadata_from_parsed(BencodeElem::from_bytes(bytes)?)
+            .and_then(|(metadata, _)| metadata.validate())
+    }
+
+    /// Parse the content of the file at `path` and return the extracted `TorrentMetadata`.
+    ///
+    /// This is the same as [`read_from_file()`], except that the piece hashes
+    /// are only validated and not decoded.
+    ///
+    /// [`read_from_file()`]: #method.read_from_file
+    #[cfg(feature = "fs")]
+    pub fn read_metadata_from_file<P>(path: P) -> Result<TorrentMetadata, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        Self::read_metadata_from_bytes(fs::read(path).map_err(util::file_io_err(path, None))?)
+    }
+
+    fn validate(self) -> Result<Torrent, LavaTorrentError> {
+        let is_merkle = self.info.pieces.is_empty() && self.root_hash()?.is_some();
+        validate_length(
+            self.info.length,
+            self.info.piece_length,
+            self.info.pieces.len(),
+            is_merkle,
+        )?;
+        validate_extra_info_fields(&self.info.extra_info_fields)?;
+        validate_files(&self.info.files)?;
+        Ok(self)
+    }
+
+    fn validate_with_policy(self, policy: ValidationPolicy) -> Result<Torrent, LavaTorrentError> {
+        match policy {
+            ValidationPolicy::Strict => {
+                let torrent = self.validate()?;
+                torrent.validate_utf8()?;
+                // both are positive after `validate()`
+                let n_piece_required = util::i64_to_usize(torrent.info.length)?
+                    .div_ceil(util::i64_to_usize(torrent.info.piece_length)?);
+
+                if !torrent.is_merkle() && (torrent.info.pieces.len() != n_piece_required) {
+                    return Err(LavaTorrentError::MalformedTorrent(
+                        TorrentErrorKind::PieceCountMismatch {
+                            pieces: torrent.info.pieces.len(),
+                            required: n_piece_required,
+                        },
+                    ));
+                }
+                Ok(torrent)
+            }
+            ValidationPolicy::Standard => self.validate(),
+            ValidationPolicy::Lenient => Ok(self),
+        }
+    }
+
+    // Reject torrents whose `name` or `path`s are not valid UTF-8,
+    // even if `name.utf-8` or `path.utf-8` could be used instead.
+    fn validate_utf8(&self) -> Result<(), LavaTorrentError> {
+        if self
+            .info
+            .extra_info_fields
+            .as_ref()
+            .is_some_and(|fields| fields.contains_key("name"))
+        {
+            return Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::WrongType {
+                    field: Cow::Borrowed("name"),
+                    expected: ValueType::String,
+                },
+            ));
+        }
+
+        let has_raw_path = |file: &File| {
+            file.extra_fields
+                .as_ref()
+                .is_some_and(|fields| fields.contains_key("path"))
+        };
+        if self.info.files.iter().flatten().any(has_raw_path) {
+            return Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::WrongElementType {
+                    field: Cow::Borrowed("path"),
+                    expected: ValueType::String,
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    // Decompress `bytes` if they are gzip-compressed, rejecting output that
+    // exceeds a `ReadLimit::Bytes` in `limits`. zstd is only detected, so that
+    // a more helpful error can be returned.
+    pub(super) fn decompress<'a>(
+        bytes: &'a [u8],
+        limits: &[ReadLimit],
+    ) -> Result<Cow<'a, [u8]>, LavaTorrentError> {
+        if gzip::is_gzip(bytes) {
+            let max_len = max_bytes(limits).min(MAX_DECOMPRESSED_LEN);
+            Ok(Cow::Owned(gzip::decompress(bytes, max_len)?))
+        } else if bytes.starts_with(&ZSTD_MAGIC) {
+            Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                Cow::Borrowed("zstd-compressed torrents are not supported."),
+            )))
         } else {
-            return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                "Torrent's total piece length overflowed in usize.",
-            )));
+            Ok(Cow::Borrowed(bytes))
         }
     }
 
-    fn from_parsed(mut parsed: Vec<BencodeElem>) -> Result<Torrent, LavaTorrentError> {
-        if parsed.len() != 1 {
-            return Err(LavaTorrentError::MalformedTorrent(Cow::Owned(format!(
-                "Torrent should contain 1 and only 1 top-level element, {} found.",
-                parsed.len()
-            ))));
+    fn from_parsed(parsed: Vec<BencodeElem>) -> Result<Torrent, LavaTorrentError> {
+        let (metadata, pieces) = Self::metadata_from_parsed(parsed)?;
+
+        Ok(Torrent {
+            announce: metadata.announce,
+            announce_list: metadata.announce_list,
+            nodes: metadata.nodes,
+            url_list: metadata.url_list,
+            httpseeds: metadata.httpseeds,
+            info: InfoFields {
+                length: metadata.length,
+                files: metadata.files,
+                name: metadata.name,
+                piece_length: metadata.piece_length,
+                pieces: Pieces::from_bytes(&pieces)?,
+                extra_info_fields: metadata.extra_info_fields,
+                raw_extra_info_fields: metadata.raw_extra_info_fields,
+            }
+            .into(),
+            extra_fields: metadata.extra_fields,
+            raw_extra_fields: metadata.raw_extra_fields,
+        })
+    }
+
+    fn from_parsed_with_decoder<F>(
+        mut parsed: Vec<BencodeElem>,
+        decoder: F,
+    ) -> Result<Torrent, LavaTorrentError>
+    where
+        F: Fn(&str, &[u8]) -> Option<String>,
+    {
+        let (raw_name, raw_paths) = Self::decode_legacy_strings(&mut parsed, decoder);
+        let mut torrent = Self::from_parsed(parsed)?;
+
+        // keep the originals, like `extract_name()` and `File::extract_file_path()` do
+        if let Some(raw_name) = raw_name {
+            torrent
+                .info
+                .fields_mut()
+                .extra_info_fields
+                .get_or_insert_with(HashMap::new)
+                .insert("name".to_owned(), raw_name);
+        }
+        for (index, raw_path) in raw_paths {
+            if let Some(file) = torrent
+                .info
+                .fields_mut()
+                .files
+                .as_mut()
+                .and_then(|files| files.get_mut(index))
+            {
+                file.extra_fields
+                    .get_or_insert_with(HashMap::new)
+                    .insert("path".to_owned(), raw_path);
+            }
+        }
+
+        Ok(torrent)
+    }
+
+    // Transcode `name` and `path` components in `parsed` that are not valid UTF-8
+    // with `decoder`. Returns the original `name` and `(file index, path)`s that
+    // were replaced.
+    fn decode_legacy_strings<F>(
+        parsed: &mut [BencodeElem],
+        decoder: F,
+    ) -> (Option<BencodeElem>, Vec<(usize, BencodeElem)>)
+    where
+        F: Fn(&str, &[u8]) -> Option<String>,
+    {
+        let mut raw_paths = Vec::new();
+        let dict = match parsed.first_mut() {
+            Some(BencodeElem::Dictionary(dict)) => dict,
+            _ => return (None, raw_paths),
+        };
+        let encoding = match dict.get("encoding") {
+            Some(BencodeElem::String(encoding)) => encoding.clone(),
+            _ => return (None, raw_paths),
+        };
+        let info = match dict.get_mut("info") {
+            Some(BencodeElem::Dictionary(info)) => info,
+            _ => return (None, raw_paths),
+        };
+        let decode = |elem: &BencodeElem| match elem {
+            BencodeElem::Bytes(bytes) => decoder(&encoding, bytes).map(BencodeElem::String),
+            _ => Some(elem.clone()),
+        };
+
+        let raw_name = match info.get("name") {
+            Some(name @ BencodeElem::Bytes(_)) => match decode(name) {
+                Some(name) => info.insert("name".to_owned(), name),
+                None => None,
+            },
+            _ => None,
+        };
+
+        if let Some(BencodeElem::List(files)) = info.get_mut("files") {
+            for (index, file) in files.iter_mut().enumerate() {
+                let file = match file {
+                    BencodeElem::Dictionary(file) => file,
+                    _ => continue,
+                };
+                let path = match file.get("path") {
+                    Some(BencodeElem::List(path))
+                        if path.iter().any(|c| matches!(c, BencodeElem::Bytes(_))) =>
+                    {
+                        path
+                    }
+                    _ => continue,
+                };
+
+                if let Some(path) = path.iter().map(&decode).collect::<Option<Vec<_>>>() {
+                    if let Some(raw_path) = file.insert("path".to_owned(), BencodeElem::List(path))
+                    {
+                        raw_paths.push((index, raw_path));
+                    }
+                }
+            }
         }
 
-        if let BencodeElem::Dictionary(mut parsed) = parsed.remove(0) {
-            // 2nd-level items
-            let announce = Self::extract_announce(&mut parsed)?;
-            let announce_list = Self::extract_announce_list(&mut parsed)?;
-            let info = parsed.remove("info");
-            let extra_fields = Self::extract_extra_fields(parsed);
+        (raw_name, raw_paths)
+    }
+
+    // Extract everything except `pieces`, which is returned as is.
+    fn metadata_from_parsed(
+        mut parsed: Vec<BencodeElem>,
+    ) -> Result<(TorrentMetadata, Vec<u8>), LavaTorrentError> {
+        if parsed.len() != 1 {
+            return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                Cow::Owned(format!(
+                    "Torrent should contain 1 and only 1 top-level element, {} found.",
+                    parsed.len()
+                )),
+            )));
+        }
 
-            match info {
-                Some(BencodeElem::Dictionary(mut info)) => {
-                    // 3rd-level items
-                    // handle `files` separately because `extract_length()` needs it
-                    let files = Self::extract_files(&mut info)?;
+        // keys that are not valid UTF-8 are kept as is, so that
+        // encoding the torrent gives back the original
+        let (mut parsed, raw_extra_fields) = match parsed.remove(0) {
+            BencodeElem::Dictionary(dict) => (dict, None),
+            BencodeElem::RawDictionary(dict) => split_raw_dictionary(dict),
+            BencodeElem::SortedDictionary(dict) => split_raw_dictionary(dict.into_iter().collect()),
+            _ => {
+                return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                    Cow::Borrowed("Torrent's top-level element is not a dictionary."),
+                )));
+            }
+        };
 
-                    Ok(Torrent {
+        // 2nd-level items
+        let announce = Self::extract_announce(&mut parsed)?;
+        let announce_list = Self::extract_announce_list(&mut parsed)?;
+        let nodes = Self::extract_nodes(&mut parsed)?;
+        let url_list = Self::extract_url_list(&mut parsed, "url-list")?;
+        let httpseeds = Self::extract_url_list(&mut parsed, "httpseeds")?;
+        let info = match parsed.remove("info") {
+            Some(BencodeElem::Dictionary(info)) => Some((info, None)),
+            Some(BencodeElem::RawDictionary(info)) => Some(split_raw_dictionary(info)),
+            Some(BencodeElem::SortedDictionary(info)) => {
+                Some(split_raw_dictionary(info.into_iter().collect()))
+            }
+            Some(_) => {
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::WrongType {
+                        field: Cow::Borrowed("info"),
+                        expected: ValueType::Dictionary,
+                    },
+                ));
+            }
+            None => None,
+        };
+        let extra_fields = Self::extract_extra_fields(parsed);
+
+        match info {
+            Some((mut info, raw_extra_info_fields)) => {
+                // 3rd-level items
+                // handle `files` separately because `extract_length()` needs it
+                let files = Self::extract_files(&mut info)?;
+                let length = Self::extract_length(&mut info, &files)?;
+                let name = Self::extract_name(&mut info)?;
+                let piece_length = Self::extract_piece_length(&mut info)?;
+                let pieces = Self::extract_pieces_bytes(&mut info)?;
+
+                Ok((
+                    TorrentMetadata {
                         announce,
                         announce_list,
-                        length: Self::extract_length(&mut info, &files)?,
+                        nodes,
+                        url_list,
+                        httpseeds,
+                        length,
                         files,
-                        name: Self::extract_name(&mut info)?,
-                        piece_length: Self::extract_piece_length(&mut info)?,
-                        pieces: Self::extract_pieces(&mut info)?,
+                        name,
+                        piece_length,
+                        num_pieces: pieces.len() / PIECE_STRING_LENGTH,
                         extra_fields,
                         extra_info_fields: Self::extract_extra_fields(info),
-                    })
-                }
-                Some(_) => {
-                    return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                        r#""info" is not a dictionary."#,
-                    )));
-                }
-                None => {
-                    return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                        r#""info" does not exist."#,
-                    )));
-                }
+                        raw_extra_fields,
+                        raw_extra_info_fields,
+                    },
+                    pieces,
+                ))
+            }
+            None => {
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::MissingField {
+                        field: Cow::Borrowed("info"),
+                    },
+                ));
             }
-        } else {
-            return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                "Torrent's top-level element is not a dictionary.",
-            )));
         }
     }
 
@@ -208,9 +911,12 @@ impl Torrent {
         match dict.remove("announce") {
             Some(BencodeElem::String(url)) => Ok(Some(url)),
             Some(_) => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""announce" does not map to a string (or maps to invalid UTF8)."#,
-                )));
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::WrongType {
+                        field: Cow::Borrowed("announce"),
+                        expected: ValueType::String,
+                    },
+                ));
             }
             None => Ok(None),
         }
@@ -229,9 +935,12 @@ impl Torrent {
                 Ok(Some(announce_list))
             }
             Some(_) => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""announce-list" does not map to a list."#,
-                )));
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::WrongType {
+                        field: Cow::Borrowed("announce-list"),
+                        expected: ValueType::List,
+                    },
+                ));
             }
             // Since BEP 12 is an extension,
             // the existence of `announce-list` is not guaranteed.
@@ -247,31 +956,110 @@ impl Torrent {
                     match url {
                         BencodeElem::String(url) => tier.push(url),
                         _ => {
-                            return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                                r#"A tier within "announce-list" contains a non-string element."#,
-                            )));
+                            return Err(LavaTorrentError::MalformedTorrent(
+                                TorrentErrorKind::Other(Cow::Borrowed(
+                                    r#"A tier within "announce-list" contains a non-string element."#,
+                                )),
+                            ));
                         }
                     }
                 }
                 Ok(tier)
             }
             _ => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""announce-list" contains a non-list element."#,
-                )));
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::WrongElementType {
+                        field: Cow::Borrowed("announce-list"),
+                        expected: ValueType::List,
+                    },
+                ));
             }
         }
     }
 
+    fn extract_nodes(
+        dict: &mut HashMap<String, BencodeElem>,
+    ) -> Result<Option<Vec<(String, u16)>>, LavaTorrentError> {
+        match dict.remove("nodes") {
+            Some(BencodeElem::List(nodes)) => Ok(Some(
+                nodes
+                    .into_iter()
+                    .map(Self::extract_node)
+                    .collect::<Result<Vec<(String, u16)>, LavaTorrentError>>()?,
+            )),
+            Some(_) => Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::WrongType {
+                    field: Cow::Borrowed("nodes"),
+                    expected: ValueType::List,
+                },
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn extract_node(elem: BencodeElem) -> Result<(String, u16), LavaTorrentError> {
+        match elem {
+            BencodeElem::List(pair) => match <[BencodeElem; 2]>::try_from(pair) {
+                Ok([BencodeElem::String(host), BencodeElem::Integer(port)]) => {
+                    match u16::try_from(port) {
+                        Ok(port) => Ok((host, port)),
+                        Err(_) => Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                            Cow::Owned(format!(r#""nodes" contains an invalid port: {}."#, port)),
+                        ))),
+                    }
+                }
+                _ => Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                    Cow::Borrowed(r#""nodes" contains a node that is not a [host, port] pair."#),
+                ))),
+            },
+            _ => Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::WrongElementType {
+                    field: Cow::Borrowed("nodes"),
+                    expected: ValueType::List,
+                },
+            )),
+        }
+    }
+
+    // `key` must map to either a string or a list of strings.
+    // A single string is returned as a list of 1 url.
+    fn extract_url_list(
+        dict: &mut HashMap<String, BencodeElem>,
+        key: &str,
+    ) -> Result<Option<Vec<String>>, LavaTorrentError> {
+        match dict.remove(key) {
+            Some(BencodeElem::String(url)) => Ok(Some(vec![url])),
+            Some(BencodeElem::List(urls)) => Ok(Some(
+                urls.into_iter()
+                    .map(|elem| match elem {
+                        BencodeElem::String(url) => Ok(url),
+                        _ => Err(LavaTorrentError::MalformedTorrent(
+                            TorrentErrorKind::WrongElementType {
+                                field: Cow::Owned(key.to_owned()),
+                                expected: ValueType::String,
+                            },
+                        )),
+                    })
+                    .collect::<Result<Vec<String>, LavaTorrentError>>()?,
+            )),
+            Some(_) => Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                Cow::Owned(format!(r#""{}" is neither a string nor a list."#, key)),
+            ))),
+            None => Ok(None),
+        }
+    }
+
     fn extract_files(
         dict: &mut HashMap<String, BencodeElem>,
     ) -> Result<Option<Vec<File>>, LavaTorrentError> {
         match dict.remove("files") {
             Some(BencodeElem::List(list)) => {
                 if list.is_empty() {
-                    return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                        r#""files" maps to an empty list."#,
-                    )));
+                    return Err(LavaTorrentError::MalformedTorrent(
+                        TorrentErrorKind::EmptyField {
+                            field: Cow::Borrowed("files"),
+                        },
+                    ));
                 } else {
                     let mut files = Vec::new();
                     for file in list {
@@ -281,9 +1069,12 @@ impl Torrent {
                 }
             }
             Some(_) => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""files" does not map to a list."#,
-                )));
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::WrongType {
+                        field: Cow::Borrowed("files"),
+                        expected: ValueType::List,
+                    },
+                ));
             }
             None => Ok(None),
         }
@@ -296,17 +1087,20 @@ impl Torrent {
         match dict.remove("length") {
             Some(BencodeElem::Integer(len)) => {
                 if files.is_some() {
-                    return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                        r#"Both "length" and "files" exist."#,
+                    return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                        Cow::Borrowed(r#"Both "length" and "files" exist."#),
                     )));
                 } else {
                     Ok(len)
                 }
             }
             Some(_) => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""length" does not map to an integer."#,
-                )));
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::WrongType {
+                        field: Cow::Borrowed("length"),
+                        expected: ValueType::Integer,
+                    },
+                ));
             }
             None => {
                 if let Some(ref files) = *files {
@@ -317,34 +1111,52 @@ impl Torrent {
                                 length = sum;
                             }
                             None => {
-                                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                                    r#"Torrent's length overflowed in i64."#,
-                                )));
+                                return Err(LavaTorrentError::MalformedTorrent(
+                                    TorrentErrorKind::Other(Cow::Borrowed(
+                                        r#"Torrent's length overflowed in i64."#,
+                                    )),
+                                ));
                             }
                         }
                     }
                     Ok(length)
                 } else {
-                    return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                        r#"Neither "length" nor "files" exists."#,
+                    return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                        Cow::Borrowed(r#"Neither "length" nor "files" exists."#),
                     )));
                 }
             }
         }
     }
 
+    // Like `File::extract_file_path()`, `name.utf-8` is used if `name` is not valid
+    // UTF-8, in which case the original `name` ends up in `extra_info_fields`.
     fn extract_name(dict: &mut HashMap<String, BencodeElem>) -> Result<String, LavaTorrentError> {
-        match dict.remove("name") {
-            Some(BencodeElem::String(name)) => Ok(name),
-            Some(_) => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""name" does not map to a string (or maps to invalid UTF8)."#,
-                )));
+        let utf8_name = dict
+            .get("name.utf-8")
+            .and_then(BencodeElem::as_str)
+            .map(str::to_owned);
+
+        match (dict.remove("name"), utf8_name) {
+            (Some(BencodeElem::String(name)), _) => Ok(name),
+            (Some(raw @ BencodeElem::Bytes(_)), Some(name)) => {
+                dict.insert("name".to_owned(), raw);
+                Ok(name)
             }
-            None => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""name" does not exist."#,
-                )));
+            (Some(_), _) => {
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::WrongType {
+                        field: Cow::Borrowed("name"),
+                        expected: ValueType::String,
+                    },
+                ));
+            }
+            (None, _) => {
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::MissingField {
+                        field: Cow::Borrowed("name"),
+                    },
+                ));
             }
         }
     }
@@ -357,54 +1169,74 @@ impl Torrent {
                 if len > 0 {
                     Ok(len)
                 } else {
-                    return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                        r#""piece length" <= 0."#,
-                    )));
+                    return Err(LavaTorrentError::MalformedTorrent(
+                        TorrentErrorKind::InvalidPieceLength { piece_length: len },
+                    ));
                 }
             }
             Some(_) => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""piece length" does not map to an integer."#,
-                )));
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::WrongType {
+                        field: Cow::Borrowed("piece length"),
+                        expected: ValueType::Integer,
+                    },
+                ));
             }
             None => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""piece length" does not exist."#,
-                )));
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::MissingField {
+                        field: Cow::Borrowed("piece length"),
+                    },
+                ));
             }
         }
     }
 
-    fn extract_pieces(
+    fn extract_pieces_bytes(
         dict: &mut HashMap<String, BencodeElem>,
-    ) -> Result<Vec<Piece>, LavaTorrentError> {
-        match dict.remove("pieces") {
+    ) -> Result<Vec<u8>, LavaTorrentError> {
+        // `pieces` is decoded as a string if it happens to be valid UTF-8
+        let pieces = match dict.remove("pieces") {
+            Some(BencodeElem::String(string)) => Some(BencodeElem::Bytes(string.into_bytes())),
+            pieces => pieces,
+        };
+
+        match pieces {
             Some(BencodeElem::Bytes(bytes)) => {
                 if bytes.is_empty() {
-                    return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                        r#""pieces" maps to an empty sequence."#,
-                    )));
+                    return Err(LavaTorrentError::MalformedTorrent(
+                        TorrentErrorKind::EmptyField {
+                            field: Cow::Borrowed("pieces"),
+                        },
+                    ));
                 } else if (bytes.len() % PIECE_STRING_LENGTH) != 0 {
-                    return Err(LavaTorrentError::MalformedTorrent(Cow::Owned(format!(
-                        r#""pieces"' length is not a multiple of {}."#,
-                        PIECE_STRING_LENGTH,
-                    ))));
+                    return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                        Cow::Owned(format!(
+                            r#""pieces"' length is not a multiple of {}."#,
+                            PIECE_STRING_LENGTH,
+                        )),
+                    )));
                 } else {
-                    Ok(bytes
-                        .chunks(PIECE_STRING_LENGTH)
-                        .map(|chunk| chunk.to_vec())
-                        .collect())
+                    Ok(bytes)
                 }
             }
             Some(_) => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""pieces" does not map to a sequence of bytes."#,
-                )));
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::WrongType {
+                        field: Cow::Borrowed("pieces"),
+                        expected: ValueType::Bytes,
+                    },
+                ));
             }
+            // merkle torrents (BEP 30) have `root hash` instead,
+            // which is validated along with `extra_info_fields`
+            None if dict.contains_key("root hash") => Ok(Vec::new()),
             None => {
-                return Err(LavaTorrentError::MalformedTorrent(Cow::Borrowed(
-                    r#""pieces" does not exist."#,
-                )));
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::MissingField {
+                        field: Cow::Borrowed("pieces"),
+                    },
+                ));
             }
         }
     }
@@ -418,6 +1250,122 @@ impl Torrent {
     }
 }
 
+/// Same as [`Torrent::from_bencode()`].
+///
+/// [`Torrent::from_bencode()`]: struct.Torrent.html#method.from_bencode
+impl TryFrom<BencodeElem> for Torrent {
+    type Error = LavaTorrentError;
+
+    fn try_from(elem: BencodeElem) -> Result<Torrent, LavaTorrentError> {
+        Torrent::from_bencode(elem)
+    }
+}
+
+impl TorrentMetadata {
+    fn validate(self) -> Result<TorrentMetadata, LavaTorrentError> {
+        let is_merkle =
+            (self.num_pieces == 0) && extract_root_hash(&self.extra_info_fields)?.is_some();
+        validate_length(self.length, self.piece_length, self.num_pieces, is_merkle)?;
+        validate_extra_info_fields(&self.extra_info_fields)?;
+        validate_files(&self.files)?;
+        Ok(self)
+    }
+}
+
+// The smallest `ReadLimit::Bytes` in `limits`, or `usize::MAX` if there is none.
+fn max_bytes(limits: &[ReadLimit]) -> usize {
+    limits
+        .iter()
+        .filter_map(|limit| match *limit {
+            ReadLimit::Bytes(max) => Some(max),
+            _ => None,
+        })
+        .min()
+        .unwrap_or(usize::MAX)
+}
+
+// Split a dictionary into the entries whose keys are valid UTF-8
+// and those whose keys aren't (if any).
+fn split_raw_dictionary(dict: RawDictionary) -> (Dictionary, Option<RawDictionary>) {
+    let mut raw = HashMap::new();
+    let dict = dict
+        .into_iter()
+        .filter_map(|(key, value)| match String::from_utf8(key) {
+            Ok(key) => Some((key, value)),
+            Err(e) => {
+                raw.insert(e.into_bytes(), value);
+                None
+            }
+        })
+        .collect();
+
+    (dict, if raw.is_empty() { None } else { Some(raw) })
+}
+
+// Check that well-known fields in `info` have the structure
+// their accessors (e.g. `Torrent::similar()`) expect.
+fn validate_extra_info_fields(
+    extra_info_fields: &Option<Dictionary>,
+) -> Result<(), LavaTorrentError> {
+    extract_similar(extra_info_fields)?;
+    extract_collections(extra_info_fields)?;
+    Ok(())
+}
+
+// Check that the BEP 47 fields of each file have the structure
+// their accessors (e.g. `File::attributes()`) expect.
+fn validate_files(files: &Option<Vec<File>>) -> Result<(), LavaTorrentError> {
+    for file in files.iter().flatten() {
+        file.attributes()?;
+        file.symlink_path()?;
+        file.sha1()?;
+    }
+    Ok(())
+}
+
+// @note: Most of validation is done when bdecoding and parsing torrent,
+// so there's not much going on here. More validation could be
+// added in the future if necessary.
+fn validate_length(
+    length: Integer,
+    piece_length: Integer,
+    num_pieces: usize,
+    is_merkle: bool,
+) -> Result<(), LavaTorrentError> {
+    // merkle torrents have no `pieces` to check `length` against
+    if is_merkle && (length > 0) {
+        return Ok(());
+    }
+
+    if piece_length <= 0 {
+        return Err(LavaTorrentError::MalformedTorrent(
+            TorrentErrorKind::InvalidPieceLength { piece_length },
+        ));
+    }
+
+    let piece_length = util::i64_to_usize(piece_length)?;
+    if let Some(total_piece_length) = piece_length.checked_mul(num_pieces) {
+        if total_piece_length < util::i64_to_usize(length)? {
+            return Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::PieceCountMismatch {
+                    pieces: num_pieces,
+                    required: util::i64_to_usize(length)?.div_ceil(piece_length),
+                },
+            ));
+        } else if length <= 0 {
+            return Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::InvalidLength { length },
+            ));
+        } else {
+            Ok(())
+        }
+    } else {
+        return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+            Cow::Borrowed("Torrent's total piece length overflowed in usize."),
+        )));
+    }
+}
+
 #[cfg(test)]
 mod file_read_tests {
     use super::*;
@@ -439,6 +1387,7 @@ mod file_read_tests {
                 extra_fields: Some(HashMap::from_iter(
                     vec![("comment".to_owned(), bencode_elem!("no comment"))].into_iter()
                 )),
+                raw_extra_fields: None,
             }
         );
     }
@@ -455,6 +1404,26 @@ mod file_read_tests {
         }
     }
 
+    #[test]
+    fn extract_file_raw_dict() {
+        let file = BencodeElem::RawDictionary(HashMap::from([
+            (b"length".to_vec(), bencode_elem!(4)),
+            (b"path".to_vec(), bencode_elem!(["dir", "file"])),
+            (b"md5sum".to_vec(), bencode_elem!("0")),
+            (vec![0xff, 0xfe], bencode_elem!(1)),
+        ]));
+
+        assert_eq!(
+            File::extract_file(file).unwrap(),
+            File {
+                length: 4,
+                path: PathBuf::from("dir/file"),
+                extra_fields: Some(HashMap::from([("md5sum".to_owned(), bencode_elem!("0"))])),
+                raw_extra_fields: Some(HashMap::from([(vec![0xff, 0xfe], bencode_elem!(1))])),
+            }
+        );
+    }
+
     #[test]
     fn extract_file_length_ok() {
         let mut dict =
@@ -468,7 +1437,9 @@ mod file_read_tests {
             HashMap::from_iter(vec![("length".to_owned(), bencode_elem!(-1))].into_iter());
 
         match File::extract_file_length(&mut dict) {
-            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(m, r#""length" < 0."#),
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, TorrentErrorKind::InvalidLength { length: -1 })
+            }
             _ => panic!(),
         }
     }
@@ -542,7 +1513,12 @@ mod file_read_tests {
 
         match File::extract_file_path(&mut dict) {
             Err(LavaTorrentError::MalformedTorrent(m)) => {
-                assert_eq!(m, r#""path" maps to a 0-length list."#);
+                assert_eq!(
+                    m,
+                    TorrentErrorKind::EmptyField {
+                        field: Cow::Borrowed("path")
+                    }
+                );
             }
             _ => panic!(),
         }
@@ -555,7 +1531,7 @@ mod file_read_tests {
                 "path".to_owned(),
                 BencodeElem::List(vec![
                     BencodeElem::String("root".to_owned()),
-                    BencodeElem::Bytes(".bashrc".as_bytes().to_vec()),
+                    BencodeElem::Integer(42),
                 ]),
             )]
             .into_iter(),
@@ -569,6 +1545,82 @@ mod file_read_tests {
         }
     }
 
+    #[test]
+    fn extract_file_path_utf8_fallback() {
+        let raw_path = BencodeElem::List(vec![
+            BencodeElem::String("root".to_owned()),
+            BencodeElem::Bytes(vec![0xff, 0xfe]),
+        ]);
+        let mut dict = HashMap::from([
+            ("path".to_owned(), raw_path.clone()),
+            ("path.utf-8".to_owned(), bencode_elem!(["root", "file"])),
+        ]);
+
+        assert_eq!(
+            File::extract_file_path(&mut dict).unwrap(),
+            PathBuf::from("root/file")
+        );
+        // the original is kept so that the info hash is preserved
+        assert_eq!(dict["path"], raw_path);
+    }
+
+    #[test]
+    fn extract_file_path_utf8_fallback_invalid() {
+        let mut dict = HashMap::from([
+            (
+                "path".to_owned(),
+                BencodeElem::List(vec![BencodeElem::Bytes(vec![0xff, 0xfe])]),
+            ),
+            ("path.utf-8".to_owned(), bencode_elem!(["root", ".."])),
+        ]);
+
+        match File::extract_file_path(&mut dict) {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(
+                    m,
+                    TorrentErrorKind::InvalidPathComponent {
+                        component: "..".to_owned()
+                    }
+                );
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn extract_file_path_lossy() {
+        let raw_path = BencodeElem::List(vec![
+            BencodeElem::String("root".to_owned()),
+            BencodeElem::Bytes(vec![b'a', 0xff]),
+        ]);
+        let mut dict = HashMap::from([("path".to_owned(), raw_path.clone())]);
+
+        assert_eq!(
+            File::extract_file_path(&mut dict).unwrap(),
+            PathBuf::from("root/a\u{fffd}")
+        );
+        // the original is kept so that the info hash is preserved
+        assert_eq!(dict["path"], raw_path);
+    }
+
+    #[test]
+    fn extract_file_path_lossy_invalid() {
+        let mut dict = HashMap::from([(
+            "path".to_owned(),
+            BencodeElem::List(vec![
+                BencodeElem::Bytes(vec![0xff, 0xfe]),
+                BencodeElem::Integer(42),
+            ]),
+        )]);
+
+        match File::extract_file_path(&mut dict) {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""path" contains a non-string element."#);
+            }
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn extract_file_path_component_invalid() {
         let mut dict = HashMap::from_iter(
@@ -584,7 +1636,12 @@ mod file_read_tests {
 
         match File::extract_file_path(&mut dict) {
             Err(LavaTorrentError::MalformedTorrent(m)) => {
-                assert_eq!(m, r#""path" contains "." or ".."."#);
+                assert_eq!(
+                    m,
+                    TorrentErrorKind::InvalidPathComponent {
+                        component: ".".to_owned()
+                    }
+                );
             }
             _ => panic!(),
         }
@@ -605,7 +1662,12 @@ mod file_read_tests {
 
         match File::extract_file_path(&mut dict) {
             Err(LavaTorrentError::MalformedTorrent(m)) => {
-                assert_eq!(m, r#""path" contains "." or ".."."#);
+                assert_eq!(
+                    m,
+                    TorrentErrorKind::InvalidPathComponent {
+                        component: "..".to_owned()
+                    }
+                );
             }
             _ => panic!(),
         }
@@ -643,13 +1705,21 @@ mod torrent_read_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
         // use `clone()` here so we can test that `torrent` is not modified
@@ -662,18 +1732,237 @@ mod torrent_read_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 6,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 6,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        match torrent.validate() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, "Torrent has 2 pieces but its length requires 3.");
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn metadata_validate_length_mismatch() {
+        let metadata = TorrentMetadata {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            length: 6,
+            files: None,
+            name: "sample".to_owned(),
+            piece_length: 2,
+            num_pieces: 2,
+            extra_fields: None,
+            extra_info_fields: None,
+            raw_extra_fields: None,
+            raw_extra_info_fields: None,
+        };
+
+        match metadata.validate() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, "Torrent has 2 pieces but its length requires 3.");
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn validate_with_policy_lenient() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 6,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert!(torrent
+            .clone()
+            .validate_with_policy(ValidationPolicy::Standard)
+            .is_err());
+        assert_eq!(
+            torrent
+                .clone()
+                .validate_with_policy(ValidationPolicy::Lenient)
+                .unwrap(),
+            torrent
+        );
+    }
+
+    #[test]
+    fn validate_with_policy_strict() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 3,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20], [3; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            torrent
+                .clone()
+                .validate_with_policy(ValidationPolicy::Standard)
+                .unwrap(),
+            torrent
+        );
+        match torrent.validate_with_policy(ValidationPolicy::Strict) {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, "Torrent has 3 pieces but its length requires 2.");
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn validate_similar_malformed() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from([(
+                    "similar".to_owned(),
+                    bencode_elem!(["not an info hash"]),
+                )])),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            torrent
+                .clone()
+                .validate_with_policy(ValidationPolicy::Lenient)
+                .unwrap(),
+            torrent
+        );
+        match torrent.validate() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(
+                m,
+                r#""similar" contains an element that is not a 20-byte string."#
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn validate_collections_malformed() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from([(
+                    "collections".to_owned(),
+                    bencode_elem!("linux"),
+                )])),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        match torrent.validate() {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""collections" does not map to a list."#)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn validate_file_attributes_malformed() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: Some(vec![File {
+                    length: 4,
+                    path: PathBuf::from("file"),
+                    extra_fields: Some(HashMap::from([("attr".to_owned(), bencode_elem!(["x"]))])),
+                    raw_extra_fields: None,
+                }]),
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
         match torrent.validate() {
             Err(LavaTorrentError::MalformedTorrent(m)) => {
-                assert_eq!(m, "Total piece length 4 < torrent's length 6.");
+                assert_eq!(
+                    m,
+                    r#""attr" does not map to a string (or maps to invalid UTF8)."#
+                )
             }
             _ => panic!(),
         }
@@ -684,17 +1973,25 @@ mod torrent_read_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 0,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 0,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
         match torrent.validate() {
-            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(m, r#""length" <= 0."#),
+            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(m, r#"Invalid "length": 0."#),
             _ => panic!(),
         }
     }
@@ -704,13 +2001,21 @@ mod torrent_read_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 1,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: i64::max_value(),
-            pieces: vec![vec![1, 2], vec![3, 4], vec![5, 6]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 1,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: i64::max_value(),
+                pieces: Pieces::from(vec![[1; 20], [2; 20], [3; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
         match torrent.validate() {
@@ -742,16 +2047,24 @@ mod torrent_read_tests {
             Torrent {
                 announce: Some("url".to_owned()),
                 announce_list: None,
-                length: 2,
-                files: None,
-                name: "??".to_owned(),
-                piece_length: 2,
-                pieces: vec![vec![
-                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c,
-                    0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13,
-                ]],
+                nodes: None,
+                url_list: None,
+                httpseeds: None,
+                info: InfoFields {
+                    length: 2,
+                    files: None,
+                    name: "??".to_owned(),
+                    piece_length: 2,
+                    pieces: Pieces::from(vec![[
+                        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b,
+                        0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13,
+                    ]]),
+                    extra_info_fields: None,
+                    raw_extra_info_fields: None,
+                }
+                .into(),
                 extra_fields: None,
-                extra_info_fields: None,
+                raw_extra_fields: None,
             }
         );
     }
@@ -808,6 +2121,18 @@ mod torrent_read_tests {
         }
     }
 
+    #[test]
+    fn from_parsed_info_missing_kind() {
+        let dict = vec![bencode_elem!({ ("announce", "url") })];
+
+        match Torrent::from_parsed(dict) {
+            Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::MissingField { field })) => {
+                assert_eq!(field, "info");
+            }
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn from_parsed_info_not_dict() {
         // "announce" is needed here because it is parsed before "info"
@@ -822,6 +2147,88 @@ mod torrent_read_tests {
         }
     }
 
+    #[test]
+    fn from_parsed_info_raw_dict() {
+        let parsed = vec![BencodeElem::Dictionary(HashMap::from_iter(vec![
+            ("announce".to_owned(), bencode_elem!("url")),
+            (
+                "info".to_owned(),
+                BencodeElem::RawDictionary(HashMap::from([
+                    (b"length".to_vec(), bencode_elem!(4)),
+                    (b"name".to_vec(), bencode_elem!("sample")),
+                    (b"piece length".to_vec(), bencode_elem!(2)),
+                    (
+                        b"pieces".to_vec(),
+                        BencodeElem::Bytes([[1; 20], [2; 20]].concat()),
+                    ),
+                    (vec![0xff, 0xfe], bencode_elem!(1)),
+                ])),
+            ),
+        ]))];
+
+        let torrent = Torrent::from_parsed(parsed).unwrap();
+        assert_eq!(torrent.info.name, "sample");
+        assert_eq!(torrent.info.extra_info_fields, None);
+        assert_eq!(
+            torrent.info.raw_extra_info_fields,
+            Some(HashMap::from([(vec![0xff, 0xfe], bencode_elem!(1))]))
+        );
+        assert_eq!(torrent.raw_extra_fields, None);
+    }
+
+    #[test]
+    fn from_parsed_top_level_raw_dict() {
+        let parsed = vec![BencodeElem::RawDictionary(HashMap::from([
+            (
+                b"info".to_vec(),
+                bencode_elem!({
+                    ("length", 4),
+                    ("name", "sample"),
+                    ("piece length", 2),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
+                }),
+            ),
+            (vec![0xff, 0xfe], bencode_elem!(1)),
+        ]))];
+
+        let torrent = Torrent::from_parsed(parsed).unwrap();
+        assert_eq!(torrent.info.name, "sample");
+        assert_eq!(
+            torrent.raw_extra_fields,
+            Some(HashMap::from([(vec![0xff, 0xfe], bencode_elem!(1))]))
+        );
+        assert_eq!(torrent.info.raw_extra_info_fields, None);
+    }
+
+    #[test]
+    fn read_from_bytes_raw_dict_round_trip() {
+        let info = [
+            b"d5:filesld6:lengthi4e4:pathl1:ae1:\xffi3eee".as_ref(),
+            b"4:name6:sample12:piece lengthi2e6:pieces40:",
+            &[[1; 20], [2; 20]].concat(),
+            b"2:\xff\xfei1ee",
+        ]
+        .concat();
+        let bytes = [b"d4:info".as_ref(), &info, b"2:\xfe\xffi2ee"].concat();
+
+        let torrent = Torrent::read_from_bytes(&bytes).unwrap();
+        assert_eq!(
+            torrent.raw_extra_fields,
+            Some(HashMap::from([(vec![0xfe, 0xff], bencode_elem!(2))]))
+        );
+        assert_eq!(
+            torrent.info.raw_extra_info_fields,
+            Some(HashMap::from([(vec![0xff, 0xfe], bencode_elem!(1))]))
+        );
+        assert_eq!(
+            torrent.info.files.as_ref().unwrap()[0].raw_extra_fields,
+            Some(HashMap::from([(vec![0xff], bencode_elem!(3))]))
+        );
+        assert_eq!(torrent.info_hash_bytes(), Sha1::digest(&info).to_vec());
+        assert_eq!(torrent.encoded_len(), bytes.len());
+        assert_eq!(torrent.encode().unwrap(), bytes);
+    }
+
     #[test]
     fn extract_announce_ok() {
         let mut dict =
@@ -933,6 +2340,142 @@ mod torrent_read_tests {
         }
     }
 
+    #[test]
+    fn extract_nodes_ok() {
+        let mut dict = HashMap::from([(
+            "nodes".to_owned(),
+            bencode_elem!([["127.0.0.1", 6881], ["router.example.org", 6882]]),
+        )]);
+
+        assert_eq!(
+            Torrent::extract_nodes(&mut dict).unwrap(),
+            Some(vec![
+                ("127.0.0.1".to_owned(), 6881),
+                ("router.example.org".to_owned(), 6882),
+            ])
+        );
+        assert!(dict.is_empty());
+    }
+
+    #[test]
+    fn extract_nodes_missing() {
+        let mut dict = HashMap::new();
+        assert_eq!(Torrent::extract_nodes(&mut dict).unwrap(), None);
+    }
+
+    #[test]
+    fn extract_nodes_not_list() {
+        let mut dict = HashMap::from([("nodes".to_owned(), bencode_elem!("127.0.0.1"))]);
+
+        match Torrent::extract_nodes(&mut dict) {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""nodes" does not map to a list."#);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn extract_nodes_not_pair() {
+        let mut dict = HashMap::from([("nodes".to_owned(), bencode_elem!([["127.0.0.1"]]))]);
+
+        match Torrent::extract_nodes(&mut dict) {
+            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(
+                m,
+                r#""nodes" contains a node that is not a [host, port] pair."#
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn extract_nodes_invalid_port() {
+        let mut dict = HashMap::from([("nodes".to_owned(), bencode_elem!([["127.0.0.1", 65536]]))]);
+
+        match Torrent::extract_nodes(&mut dict) {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""nodes" contains an invalid port: 65536."#);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn extract_url_list_ok() {
+        let mut dict = HashMap::from([(
+            "url-list".to_owned(),
+            bencode_elem!(["https://example.org/", "https://example.com/"]),
+        )]);
+
+        assert_eq!(
+            Torrent::extract_url_list(&mut dict, "url-list").unwrap(),
+            Some(vec![
+                "https://example.org/".to_owned(),
+                "https://example.com/".to_owned(),
+            ])
+        );
+        assert!(dict.is_empty());
+    }
+
+    #[test]
+    fn extract_url_list_string() {
+        let mut dict =
+            HashMap::from([("url-list".to_owned(), bencode_elem!("https://example.org/"))]);
+
+        assert_eq!(
+            Torrent::extract_url_list(&mut dict, "url-list").unwrap(),
+            Some(vec!["https://example.org/".to_owned()])
+        );
+    }
+
+    #[test]
+    fn extract_url_list_missing() {
+        let mut dict = HashMap::new();
+        assert_eq!(
+            Torrent::extract_url_list(&mut dict, "url-list").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn extract_url_list_not_list() {
+        let mut dict = HashMap::from([("url-list".to_owned(), bencode_elem!(1))]);
+
+        match Torrent::extract_url_list(&mut dict, "url-list") {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""url-list" is neither a string nor a list."#)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn extract_url_list_httpseeds_not_list() {
+        let mut dict = HashMap::from([("httpseeds".to_owned(), bencode_elem!({}))]);
+
+        match Torrent::extract_url_list(&mut dict, "httpseeds") {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""httpseeds" is neither a string nor a list."#)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn extract_url_list_url_not_string() {
+        let mut dict = HashMap::from([(
+            "url-list".to_owned(),
+            bencode_elem!(["https://example.org/file", 1]),
+        )]);
+
+        match Torrent::extract_url_list(&mut dict, "url-list") {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""url-list" contains a non-string element."#)
+            }
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn extract_files_ok() {
         let mut dict = HashMap::from_iter(
@@ -957,6 +2500,7 @@ mod torrent_read_tests {
                 extra_fields: Some(HashMap::from_iter(
                     vec![("comment".to_owned(), bencode_elem!("no comment"))].into_iter()
                 )),
+                raw_extra_fields: None,
             }
         );
     }
@@ -985,7 +2529,12 @@ mod torrent_read_tests {
 
         match Torrent::extract_files(&mut dict) {
             Err(LavaTorrentError::MalformedTorrent(m)) => {
-                assert_eq!(m, r#""files" maps to an empty list."#);
+                assert_eq!(
+                    m,
+                    TorrentErrorKind::EmptyField {
+                        field: Cow::Borrowed("files")
+                    }
+                );
             }
             _ => panic!(),
         }
@@ -1006,6 +2555,7 @@ mod torrent_read_tests {
             length: 100,
             path: PathBuf::new(),
             extra_fields: None,
+            raw_extra_fields: None,
         }]);
 
         match Torrent::extract_length(&mut dict, &files) {
@@ -1048,6 +2598,7 @@ mod torrent_read_tests {
             length: 100,
             path: PathBuf::new(),
             extra_fields: None,
+            raw_extra_fields: None,
         }]);
 
         assert_eq!(Torrent::extract_length(&mut dict, &files).unwrap(), 100);
@@ -1061,11 +2612,13 @@ mod torrent_read_tests {
                 length: 1,
                 path: PathBuf::new(),
                 extra_fields: None,
+                raw_extra_fields: None,
             },
             File {
                 length: i64::max_value(),
                 path: PathBuf::new(),
                 extra_fields: None,
+                raw_extra_fields: None,
             },
         ]);
 
@@ -1107,6 +2660,18 @@ mod torrent_read_tests {
         }
     }
 
+    #[test]
+    fn extract_name_utf8_fallback() {
+        let mut dict = HashMap::from([
+            ("name".to_owned(), BencodeElem::Bytes(vec![0xff, 0xfe])),
+            ("name.utf-8".to_owned(), bencode_elem!("name")),
+        ]);
+
+        assert_eq!(Torrent::extract_name(&mut dict).unwrap(), "name".to_owned());
+        // the original is kept so that the info hash is preserved
+        assert_eq!(dict["name"], BencodeElem::Bytes(vec![0xff, 0xfe]));
+    }
+
     #[test]
     fn extract_name_missing() {
         let mut dict = HashMap::new();
@@ -1158,14 +2723,14 @@ mod torrent_read_tests {
 
         match Torrent::extract_piece_length(&mut dict) {
             Err(LavaTorrentError::MalformedTorrent(m)) => {
-                assert_eq!(m, r#""piece length" <= 0."#);
+                assert_eq!(m, TorrentErrorKind::InvalidPieceLength { piece_length: 0 });
             }
             _ => panic!(),
         }
     }
 
     #[test]
-    fn extract_pieces_ok() {
+    fn extract_pieces_bytes_ok() {
         let mut dict = HashMap::from_iter(
             vec![(
                 "pieces".to_owned(),
@@ -1177,12 +2742,13 @@ mod torrent_read_tests {
             .into_iter(),
         );
 
-        let pieces = Torrent::extract_pieces(&mut dict).unwrap();
+        let pieces =
+            Pieces::from_bytes(&Torrent::extract_pieces_bytes(&mut dict).unwrap()).unwrap();
         assert_eq!(pieces.len(), 1);
         assert_eq!(pieces[0].len(), PIECE_STRING_LENGTH);
         assert_eq!(
             pieces[0],
-            vec![
+            [
                 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
                 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13,
             ]
@@ -1190,11 +2756,11 @@ mod torrent_read_tests {
     }
 
     #[test]
-    fn extract_pieces_not_bytes() {
+    fn extract_pieces_bytes_not_bytes() {
         let mut dict =
-            HashMap::from_iter(vec![("pieces".to_owned(), bencode_elem!("???"))].into_iter());
+            HashMap::from_iter(vec![("pieces".to_owned(), bencode_elem!(1))].into_iter());
 
-        match Torrent::extract_pieces(&mut dict) {
+        match Torrent::extract_pieces_bytes(&mut dict) {
             Err(LavaTorrentError::MalformedTorrent(m)) => {
                 assert_eq!(m, r#""pieces" does not map to a sequence of bytes."#);
             }
@@ -1203,10 +2769,39 @@ mod torrent_read_tests {
     }
 
     #[test]
-    fn extract_pieces_missing() {
+    fn extract_pieces_bytes_not_bytes_kind() {
+        let mut dict = HashMap::from_iter(vec![("pieces".to_owned(), bencode_elem!(1))]);
+
+        match Torrent::extract_pieces_bytes(&mut dict) {
+            Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::WrongType {
+                field,
+                expected,
+            })) => {
+                assert_eq!(field, "pieces");
+                assert_eq!(expected, ValueType::Bytes);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn extract_pieces_bytes_utf8() {
+        let mut dict = HashMap::from([(
+            "pieces".to_owned(),
+            BencodeElem::String("a".repeat(PIECE_STRING_LENGTH)),
+        )]);
+
+        assert_eq!(
+            Torrent::extract_pieces_bytes(&mut dict).unwrap(),
+            vec![b'a'; PIECE_STRING_LENGTH]
+        );
+    }
+
+    #[test]
+    fn extract_pieces_bytes_missing() {
         let mut dict = HashMap::new();
 
-        match Torrent::extract_pieces(&mut dict) {
+        match Torrent::extract_pieces_bytes(&mut dict) {
             Err(LavaTorrentError::MalformedTorrent(m)) => {
                 assert_eq!(m, r#""pieces" does not exist."#);
             }
@@ -1215,20 +2810,33 @@ mod torrent_read_tests {
     }
 
     #[test]
-    fn extract_pieces_empty() {
+    fn extract_pieces_bytes_merkle() {
+        let mut dict = HashMap::from([("root hash".to_owned(), BencodeElem::Bytes(vec![1; 20]))]);
+
+        assert!(Torrent::extract_pieces_bytes(&mut dict).unwrap().is_empty());
+        assert!(dict.contains_key("root hash"));
+    }
+
+    #[test]
+    fn extract_pieces_bytes_empty() {
         let mut dict =
             HashMap::from_iter(vec![("pieces".to_owned(), bencode_elem!(()))].into_iter());
 
-        match Torrent::extract_pieces(&mut dict) {
+        match Torrent::extract_pieces_bytes(&mut dict) {
             Err(LavaTorrentError::MalformedTorrent(m)) => {
-                assert_eq!(m, r#""pieces" maps to an empty sequence."#);
+                assert_eq!(
+                    m,
+                    TorrentErrorKind::EmptyField {
+                        field: Cow::Borrowed("pieces")
+                    }
+                );
             }
             _ => panic!(),
         }
     }
 
     #[test]
-    fn extract_pieces_invalid_length() {
+    fn extract_pieces_bytes_invalid_length() {
         let mut dict = HashMap::from_iter(
             vec![(
                 "pieces".to_owned(),
@@ -1240,7 +2848,7 @@ mod torrent_read_tests {
             .into_iter(),
         );
 
-        match Torrent::extract_pieces(&mut dict) {
+        match Torrent::extract_pieces_bytes(&mut dict) {
             Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(
                 m,
                 format!(
@@ -1268,4 +2876,184 @@ mod torrent_read_tests {
     fn extract_extra_fields_none() {
         assert_eq!(Torrent::extract_extra_fields(HashMap::new()), None)
     }
+
+    #[test]
+    fn from_bencode_ok() {
+        let elem = bencode_elem!({
+            ("info", {
+                ("length", 4),
+                ("name", "sample"),
+                ("piece length", 2),
+                ("pieces", ([[1; 20], [2; 20]].concat())),
+            }),
+            ("comment", "none"),
+        });
+
+        let torrent = Torrent::from_bencode(elem).unwrap();
+        assert_eq!(torrent.info.name, "sample");
+        assert_eq!(
+            torrent.extra_fields,
+            Some(HashMap::from([(
+                "comment".to_owned(),
+                bencode_elem!("none")
+            )]))
+        );
+    }
+
+    #[test]
+    fn from_bencode_sorted_dict() {
+        let elem = bencode_elem!({
+            ("info", {
+                ("files", [{ ("length", 4), ("path", ["file"]) }]),
+                ("name", "sample"),
+                ("piece length", 2),
+                ("pieces", ([[1; 20], [2; 20]].concat())),
+            }),
+            ("comment", "none"),
+        });
+
+        assert_eq!(
+            Torrent::from_bencode(elem.clone().into_sorted()).unwrap(),
+            Torrent::from_bencode(elem).unwrap()
+        );
+    }
+
+    #[test]
+    fn from_bencode_raw_dict() {
+        let elem = BencodeElem::RawDictionary(HashMap::from([(vec![0xff], bencode_elem!(1))]));
+
+        match Torrent::from_bencode(elem) {
+            Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::MissingField { field })) => {
+                assert_eq!(field, "info");
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn from_info_bytes_ok() {
+        let info = bencode_elem!({
+            ("length", 4),
+            ("name", "sample"),
+            ("piece length", 2),
+            ("pieces", ([[1; 20], [2; 20]].concat())),
+        })
+        .encode();
+        let trackers = vec!["url1".to_owned(), "url2".to_owned(), "url1".to_owned()];
+        let info_hash = Sha1::digest(&info);
+
+        let torrent = Torrent::from_info_bytes(&info, trackers, Some(&info_hash)).unwrap();
+        assert_eq!(torrent.info.name, "sample");
+        assert_eq!(torrent.announce, Some("url1".to_owned()));
+        assert_eq!(
+            torrent.announce_list,
+            Some(vec![vec!["url1".to_owned()], vec!["url2".to_owned()]])
+        );
+        assert_eq!(torrent.extra_fields, None);
+        assert_eq!(torrent.info_hash_bytes(), info_hash.to_vec());
+
+        let torrent = Torrent::from_info_bytes(&info, vec![], None).unwrap();
+        assert_eq!(torrent.announce, None);
+        assert_eq!(torrent.announce_list, None);
+    }
+
+    #[test]
+    fn from_info_bytes_info_hash_mismatch() {
+        let info = bencode_elem!({
+            ("length", 4),
+            ("name", "sample"),
+            ("piece length", 4),
+            ("pieces", ([1; 20].to_vec())),
+        })
+        .encode();
+
+        match Torrent::from_info_bytes(info, vec![], Some(&[0xab; 20])) {
+            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(
+                m,
+                format!(
+                    "Info hash 036b42187541fe5a615a625c49d7f1cc7092cad7 does not match the expected {}.",
+                    "ab".repeat(20)
+                )
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn from_info_bytes_invalid() {
+        match Torrent::from_info_bytes(b"i1ei2e", vec![], None) {
+            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(
+                m,
+                "Info should contain 1 and only 1 top-level element, 2 found."
+            ),
+            _ => panic!(),
+        }
+
+        match Torrent::from_info_bytes(b"i1e", vec![], None) {
+            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(
+                m,
+                TorrentErrorKind::WrongType {
+                    field: Cow::Borrowed("info"),
+                    expected: ValueType::Dictionary,
+                }
+                .to_string()
+            ),
+            _ => panic!(),
+        }
+
+        match Torrent::from_info_bytes(b"d4:name6:samplee", vec![], None) {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#"Neither "length" nor "files" exists."#)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn try_from_bencode_elem_ok() {
+        let elem = bencode_elem!({
+            ("announce", "url"),
+            ("info", {
+                ("length", 4),
+                ("name", "sample"),
+                ("piece length", 2),
+                ("pieces", ([[1; 20], [2; 20]].concat())),
+            })
+        });
+
+        let torrent = Torrent::try_from(elem.clone()).unwrap();
+        assert_eq!(torrent, Torrent::read_from_bytes(elem.encode()).unwrap());
+        assert_eq!(BencodeElem::from(torrent), elem);
+    }
+
+    #[test]
+    fn try_from_bencode_elem_invalid() {
+        match Torrent::try_from(bencode_elem!([])) {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, "Torrent's top-level element is not a dictionary.")
+            }
+            _ => panic!(),
+        }
+
+        let elem = bencode_elem!({
+            ("info", {
+                ("length", 4),
+                ("name", "sample"),
+                ("piece length", 1),
+                ("pieces", ([[1; 20], [2; 20]].concat())),
+            })
+        });
+        match Torrent::try_from(elem) {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(
+                    m,
+                    TorrentErrorKind::PieceCountMismatch {
+                        pieces: 2,
+                        required: 4
+                    }
+                )
+            }
+            _ => panic!(),
+        }
+    }
 }
@@ -0,0 +1,421 @@
+use super::sparse::SparseFile;
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+use super::sparse::{self, Holes};
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+use super::uring::{ReadOp, Ring};
+use super::*;
+use crate::util;
+use std::io::{BufReader, Read};
+use std::ops::Range;
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+use std::os::unix::{fs::FileExt, io::AsRawFd};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+use std::sync::Arc;
+
+// max # of reads submitted at once with io_uring
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+const RING_ENTRIES: u32 = 64;
+
+// a file opened by `read_pieces_uring()`, along with its holes
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+type OpenFile = (std::fs::File, Arc<[(u64, u64)]>);
+
+// A piece read from disk, along with the part(s) of it
+// that belong to each file (in the order they were read).
+pub(super) struct PieceData {
+    pub(super) bytes: Vec<u8>,
+    pub(super) segments: Vec<Segment>,
+}
+
+pub(super) struct Segment {
+    // index into the `files` passed to `read_ahead()`
+    pub(super) file: usize,
+    // range of `PieceData::bytes`
+    pub(super) range: Range<usize>,
+}
+
+impl PieceData {
+    fn with_capacity(piece_length: usize) -> PieceData {
+        PieceData {
+            bytes: Vec::with_capacity(piece_length),
+            segments: Vec::new(),
+        }
+    }
+
+    // reuse a piece that has been consumed already if possible
+    fn recycle(recycle_receiver: &Receiver<PieceData>, piece_length: usize) -> PieceData {
+        match recycle_receiver.try_recv() {
+            Ok(mut recycled) => {
+                recycled.bytes.clear();
+                recycled.segments.clear();
+                recycled
+            }
+            Err(_) => PieceData::with_capacity(piece_length),
+        }
+    }
+}
+
+impl TorrentBuilder {
+    // Read `files` back to back, split into pieces of `piece_length` bytes
+    // (the last piece might be shorter), and call `consume` on each piece.
+    //
+    // Reading is done on a separate thread that reads piece N+1 while
+    // `consume` is processing piece N, so that disk reads and hashing
+    // overlap. The two threads hand buffers back and forth, so at most
+    // two pieces are in memory at any given time.
+    //
+    // If `consume` fails, reading stops and its error is returned.
+    pub(super) fn read_ahead<F>(
+        &self,
+        files: &[(PathBuf, u64)],
+        piece_length: usize,
+        mut consume: F,
+    ) -> Result<(), LavaTorrentError>
+    where
+        F: FnMut(&PieceData) -> Result<(), LavaTorrentError>,
+    {
+        std::thread::scope(|scope| {
+            // rendezvous channel: the reader blocks until its piece is taken
+            let (sender, receiver) = mpsc::sync_channel(0);
+            let (recycle_sender, recycle_receiver) = mpsc::channel();
+
+            scope.spawn(move || {
+                // all reads are done on this thread, which exits afterwards
+                let _priority = self.low_priority_io.then(util::LowIoPriority::enter);
+                let result = self.read_pieces(files, piece_length, &sender, &recycle_receiver);
+                if let Err(e) = result {
+                    // if this fails then `consume` has failed already
+                    let _ = sender.send(Err(e));
+                }
+            });
+
+            // `receiver` is dropped on return, which stops the reader
+            for piece in receiver {
+                let piece = piece?;
+                consume(&piece)?;
+                let _ = recycle_sender.send(piece);
+            }
+            Ok(())
+        })
+    }
+
+    fn read_pieces(
+        &self,
+        files: &[(PathBuf, u64)],
+        piece_length: usize,
+        sender: &SyncSender<Result<PieceData, LavaTorrentError>>,
+        recycle_receiver: &Receiver<PieceData>,
+    ) -> Result<(), LavaTorrentError> {
+        #[cfg(all(feature = "io_uring", target_os = "linux"))]
+        if let Ok(ring) = Ring::new(RING_ENTRIES) {
+            return self.read_pieces_uring(ring, files, piece_length, sender, recycle_receiver);
+        }
+
+        let piece_length_u64 = util::usize_to_u64(piece_length)?;
+        let mut piece = PieceData::with_capacity(piece_length);
+
+        for (index, (path, length)) in files.iter().enumerate() {
+            if *length == 0 {
+                continue;
+            }
+            let mut file =
+                BufReader::new(SparseFile::open(path).map_err(util::file_io_err(path, None))?);
+            let mut file_read = 0;
+
+            while file_read < *length {
+                let piece_filled = piece.bytes.len();
+                let piece_remaining = piece_length_u64 - util::usize_to_u64(piece_filled)?;
+                let to_read = std::cmp::min(length - file_read, piece_remaining);
+
+                self.read_chunk(file.by_ref(), path, file_read, to_read, &mut piece.bytes)?;
+                piece.segments.push(Segment {
+                    file: index,
+                    range: piece_filled..piece.bytes.len(),
+                });
+                file_read += to_read;
+
+                if piece.bytes.len() == piece_length {
+                    let next = PieceData::recycle(recycle_receiver, piece_length);
+                    if sender
+                        .send(Ok(std::mem::replace(&mut piece, next)))
+                        .is_err()
+                    {
+                        return Ok(()); // `consume` has failed, nothing left to do
+                    }
+                }
+            }
+        }
+
+        // the last piece is partially filled unless the total
+        // length is divisible by the piece length
+        if !piece.bytes.is_empty() {
+            let _ = sender.send(Ok(piece));
+        }
+        Ok(())
+    }
+
+    // Like the regular path of `read_pieces()`, but all reads of a piece
+    // are submitted to `ring` at once instead of being done one by one,
+    // which saves a lot of syscalls when pieces span many small files.
+    // As with `SparseFile`, holes in sparse files are not read from disk.
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    fn read_pieces_uring(
+        &self,
+        mut ring: Ring,
+        files: &[(PathBuf, u64)],
+        piece_length: usize,
+        sender: &SyncSender<Result<PieceData, LavaTorrentError>>,
+        recycle_receiver: &Receiver<PieceData>,
+    ) -> Result<(), LavaTorrentError> {
+        let piece_length_u64 = util::usize_to_u64(piece_length)?;
+        let mut piece = PieceData::with_capacity(piece_length);
+        // the files that `piece` is read from, along with their holes
+        // (the last of which might continue in the next piece)
+        let mut open_files = Vec::new();
+        // (index into `open_files`, offset in file) of each segment of `piece`
+        let mut reads = Vec::new();
+
+        for (index, (path, length)) in files.iter().enumerate() {
+            if *length == 0 {
+                continue;
+            }
+            let file = std::fs::File::open(path).map_err(util::file_io_err(path, None))?;
+            let holes = Holes::default()
+                .get_or_locate(&file)
+                .map_err(util::file_io_err(path, None))?;
+            open_files.push((file, holes));
+            let mut file_read = 0;
+
+            while file_read < *length {
+                let piece_filled = piece.bytes.len();
+                let piece_remaining = piece_length_u64 - util::usize_to_u64(piece_filled)?;
+                let to_read = std::cmp::min(length - file_read, piece_remaining);
+
+                piece
+                    .bytes
+                    .resize(piece_filled + util::u64_to_usize(to_read)?, 0);
+                piece.segments.push(Segment {
+                    file: index,
+                    range: piece_filled..piece.bytes.len(),
+                });
+                reads.push((open_files.len() - 1, file_read));
+                file_read += to_read;
+
+                if piece.bytes.len() == piece_length {
+                    self.fill_piece(&mut ring, files, &open_files, &reads, &mut piece)?;
+                    reads.clear();
+                    let last = open_files.pop();
+                    open_files.clear();
+                    open_files.extend(last);
+
+                    let next = PieceData::recycle(recycle_receiver, piece_length);
+                    if sender
+                        .send(Ok(std::mem::replace(&mut piece, next)))
+                        .is_err()
+                    {
+                        return Ok(()); // `consume` has failed, nothing left to do
+                    }
+                }
+            }
+        }
+
+        if !piece.bytes.is_empty() {
+            self.fill_piece(&mut ring, files, &open_files, &reads, &mut piece)?;
+            let _ = sender.send(Ok(piece));
+        }
+        Ok(())
+    }
+
+    // Read the segments of `piece`, where `reads` has the
+    // (index into `open_files`, offset in file) of each segment.
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    fn fill_piece(
+        &self,
+        ring: &mut Ring,
+        files: &[(PathBuf, u64)],
+        open_files: &[OpenFile],
+        reads: &[(usize, u64)],
+        piece: &mut PieceData,
+    ) -> Result<(), LavaTorrentError> {
+        if let Some(ref limiter) = self.read_rate_limiter {
+            limiter.consume(util::usize_to_u64(piece.bytes.len())?);
+        }
+
+        // `piece.bytes` is zeroed when resized, so holes are simply skipped
+        let mut ops = Vec::with_capacity(reads.len());
+        // (index into `open_files`, index into `files`) of each op
+        let mut op_files = Vec::with_capacity(reads.len());
+        let mut unfilled = &mut piece.bytes[..];
+        for (segment, &(file, offset)) in piece.segments.iter().zip(reads) {
+            let (mut buf, rest) = std::mem::take(&mut unfilled).split_at_mut(segment.range.len());
+            unfilled = rest;
+
+            let (ref open_file, ref holes) = open_files[file];
+            let mut buf_start = offset;
+            for range in sparse::data_ranges(holes, offset, util::usize_to_u64(buf.len())?) {
+                let (_, data) = std::mem::take(&mut buf)
+                    .split_at_mut(util::u64_to_usize(range.start - buf_start)?);
+                let (data, rest) = data.split_at_mut(util::u64_to_usize(range.end - range.start)?);
+                ops.push(ReadOp::new(open_file.as_raw_fd(), range.start, data));
+                op_files.push((file, segment.file));
+                buf = rest;
+                buf_start = range.end;
+            }
+        }
+        if ops.is_empty() {
+            return Ok(()); // the whole piece is in holes
+        }
+        ring.read(&mut ops).map_err(|e| LavaTorrentError::FileIo {
+            path: files[op_files[0].1].0.clone(),
+            offset: None,
+            source: e,
+        })?;
+
+        for (op, (file, path_index)) in ops.into_iter().zip(op_files) {
+            let path = &files[path_index].0;
+            let mut read = op
+                .result
+                .unwrap()
+                .map_err(util::file_io_err(path, Some(op.offset)))?;
+
+            // short reads are rare, so just finish them synchronously
+            while read < op.buf.len() {
+                let offset = op.offset + util::usize_to_u64(read)?;
+                match open_files[file].0.read_at(&mut op.buf[read..], offset) {
+                    Ok(0) => return Err(Self::file_too_short_error(path, offset)),
+                    Ok(n) => read += n,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                    Err(e) => return Err(util::file_io_err(path, Some(offset))(e)),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod read_ahead_tests {
+    use super::*;
+
+    // (bytes, [(file, range)])
+    type ReadPiece = (Vec<u8>, Vec<(usize, Range<usize>)>);
+
+    fn read_all(
+        files: &[(PathBuf, u64)],
+        piece_length: usize,
+    ) -> Result<Vec<ReadPiece>, LavaTorrentError> {
+        let mut pieces = Vec::new();
+        TorrentBuilder::default().read_ahead(files, piece_length, |piece| {
+            pieces.push((
+                piece.bytes.clone(),
+                piece
+                    .segments
+                    .iter()
+                    .map(|s| (s.file, s.range.clone()))
+                    .collect(),
+            ));
+            Ok(())
+        })?;
+        Ok(pieces)
+    }
+
+    #[test]
+    fn read_ahead_single_file() {
+        let files = vec![(PathBuf::from("tests/files/byte_sequence"), 256)];
+        let pieces = read_all(&files, 100).unwrap();
+        let expected: Vec<u8> = (0..=255).collect();
+
+        assert_eq!(pieces.len(), 3);
+        assert_eq!(pieces[0], (expected[..100].to_vec(), vec![(0, 0..100)]));
+        assert_eq!(pieces[1], (expected[100..200].to_vec(), vec![(0, 0..100)]));
+        assert_eq!(pieces[2], (expected[200..].to_vec(), vec![(0, 0..56)]));
+    }
+
+    #[test]
+    fn read_ahead_multiple_files() {
+        let files = vec![
+            (PathBuf::from("tests/files/byte_sequence"), 256),
+            (PathBuf::from("tests/files/byte_sequence"), 0),
+            (PathBuf::from("tests/files/byte_sequence"), 256),
+        ];
+        let pieces = read_all(&files, 200).unwrap();
+        let expected: Vec<u8> = (0..=255).chain(0..=255).collect();
+
+        assert_eq!(pieces.len(), 3);
+        assert_eq!(pieces[0], (expected[..200].to_vec(), vec![(0, 0..200)]));
+        assert_eq!(
+            pieces[1],
+            (expected[200..400].to_vec(), vec![(0, 0..56), (2, 56..200)])
+        );
+        assert_eq!(pieces[2], (expected[400..].to_vec(), vec![(2, 0..112)]));
+    }
+
+    #[test]
+    fn read_ahead_consume_failure() {
+        let files = vec![(PathBuf::from("tests/files/byte_sequence"), 256)];
+        let mut n_consumed = 0;
+        let result = TorrentBuilder::default().read_ahead(&files, 1, |_| {
+            n_consumed += 1;
+            if n_consumed == 3 {
+                Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                    "stop",
+                )))
+            } else {
+                Ok(())
+            }
+        });
+
+        match result {
+            Err(LavaTorrentError::TorrentBuilderFailure(m)) => assert_eq!(m, "stop"),
+            _ => panic!(),
+        }
+        assert_eq!(n_consumed, 3);
+    }
+
+    #[test]
+    fn read_ahead_file_too_short() {
+        let files = vec![(PathBuf::from("tests/files/byte_sequence"), 300)];
+
+        match read_all(&files, 100) {
+            Err(LavaTorrentError::FileIo { path, offset, .. }) => {
+                assert_eq!(path, PathBuf::from("tests/files/byte_sequence"));
+                assert_eq!(offset, Some(256));
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn read_ahead_sparse_file() {
+        use std::io::{Seek, SeekFrom, Write};
+
+        std::fs::create_dir_all("tests/tmp/").unwrap();
+        let path = PathBuf::from(format!(
+            "tests/tmp/read_ahead_sparse_{}",
+            std::process::id()
+        ));
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.seek(SeekFrom::Start(1 << 20)).unwrap();
+        file.write_all(b"data").unwrap();
+        file.set_len(3 << 20).unwrap();
+        drop(file);
+
+        let files = vec![
+            (path.clone(), 3 << 20),
+            (PathBuf::from("tests/files/byte_sequence"), 256),
+        ];
+        let pieces = read_all(&files, 1 << 20);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut expected = vec![0; 3 << 20];
+        expected[(1 << 20)..(1 << 20) + 4].copy_from_slice(b"data");
+        expected.extend(0..=255);
+        let bytes: Vec<u8> = pieces
+            .unwrap()
+            .into_iter()
+            .flat_map(|(bytes, _)| bytes)
+            .collect();
+        assert_eq!(bytes, expected);
+    }
+}
@@ -0,0 +1,447 @@
+use super::*;
+#[cfg(feature = "fs")]
+use crate::util;
+use std::convert::TryFrom;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PIECE_HAVE: u8 = 0x1;
+const FILE_FORMAT: &str = "libtorrent resume file";
+
+impl Torrent {
+    /// Generate [libtorrent](https://www.libtorrent.org/manual-ref.html#fast-resume)
+    /// fast-resume data from the output of [`verify()`].
+    ///
+    /// The output can be encoded and saved as a `.fastresume` file, or passed
+    /// to libtorrent's `read_resume_data()`, so that libtorrent-based clients
+    /// (e.g. qBittorrent, Deluge) can start seeding the content without
+    /// rechecking it. Pieces in `report.verified_pieces` are marked as
+    /// downloaded and all other pieces are marked as missing.
+    ///
+    /// `save_path` is the directory that *contains* the content (i.e. the parent
+    /// of the `path` given to [`verify()`]), since that is what libtorrent expects.
+    /// It must be valid UTF-8.
+    ///
+    /// The `info` dict is embedded in the output, so the resume data can also be
+    /// loaded without the *.torrent* file.
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(feature = "fs", doc = "```no_run")]
+    #[cfg_attr(not(feature = "fs"), doc = "```ignore")]
+    /// use lava_torrent::torrent::v1::Torrent;
+    ///
+    /// let torrent = Torrent::read_from_file("sample.torrent").unwrap();
+    /// let report = torrent.verify("downloads/sample").unwrap();
+    ///
+    /// if report.is_intact() {
+    ///     torrent
+    ///         .fast_resume_data("downloads", &report)
+    ///         .unwrap()
+    ///         .write_into_file("sample.fastresume")
+    ///         .unwrap();
+    /// }
+    /// ```
+    ///
+    /// [`verify()`]: #method.verify
+    pub fn fast_resume_data<P>(
+        &self,
+        save_path: P,
+        report: &VerificationReport,
+    ) -> Result<BencodeElem, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let save_path = save_path
+            .as_ref()
+            .to_str()
+            .ok_or(LavaTorrentError::InvalidArgument(Cow::Borrowed(
+                "save_path is not valid UTF-8.",
+            )))?;
+
+        let mut pieces = vec![0; self.info.pieces.len()];
+        for &index in &report.verified_pieces {
+            match pieces.get_mut(index) {
+                Some(piece) => *piece = PIECE_HAVE,
+                None => {
+                    return Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                        "report has a verified piece [{}] that is out of bounds.",
+                        index
+                    ))))
+                }
+            }
+        }
+        let is_complete = pieces.iter().all(|&piece| piece == PIECE_HAVE);
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .and_then(|duration| i64::try_from(duration.as_secs()).ok())
+            .unwrap_or(0);
+
+        let mut resume = HashMap::new();
+        resume.insert(
+            "file-format".to_owned(),
+            BencodeElem::String(FILE_FORMAT.to_owned()),
+        );
+        resume.insert("file-version".to_owned(), BencodeElem::Integer(1));
+        resume.insert(
+            "info-hash".to_owned(),
+            BencodeElem::Bytes(self.info_hash_bytes()),
+        );
+        resume.insert("info".to_owned(), self.construct_info());
+        resume.insert(
+            "name".to_owned(),
+            BencodeElem::String(self.info.name.clone()),
+        );
+        resume.insert(
+            "save_path".to_owned(),
+            BencodeElem::String(save_path.to_owned()),
+        );
+        resume.insert("pieces".to_owned(), BencodeElem::Bytes(pieces));
+        resume.insert(
+            "allocation".to_owned(),
+            BencodeElem::String("sparse".to_owned()),
+        );
+        resume.insert("seed_mode".to_owned(), BencodeElem::Integer(0));
+        resume.insert("paused".to_owned(), BencodeElem::Integer(0));
+        resume.insert("auto_managed".to_owned(), BencodeElem::Integer(1));
+        resume.insert("total_uploaded".to_owned(), BencodeElem::Integer(0));
+        resume.insert("total_downloaded".to_owned(), BencodeElem::Integer(0));
+        resume.insert("added_time".to_owned(), BencodeElem::Integer(now));
+        resume.insert(
+            "completed_time".to_owned(),
+            BencodeElem::Integer(if is_complete { now } else { 0 }),
+        );
+
+        Ok(BencodeElem::Dictionary(resume))
+    }
+}
+
+impl FastResume {
+    /// Parse `bytes` and return the extracted `FastResume`.
+    ///
+    /// `bytes` must contain a dictionary with `info-hash`. If any field has
+    /// an unexpected type (e.g. `pieces` is not a string), or if `file-format`
+    /// is present but not "libtorrent resume file", then `Err(error)` will
+    /// be returned.
+    pub fn read_from_bytes<B>(bytes: B) -> Result<FastResume, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        let mut parsed = BencodeElem::from_bytes(bytes)?;
+        if parsed.len() != 1 {
+            return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                Cow::Owned(format!(
+                    "Fast-resume data should contain 1 and only 1 top-level element, {} found.",
+                    parsed.len()
+                )),
+            )));
+        }
+
+        Self::from_bencode(parsed.remove(0))
+    }
+
+    /// Like [`read_from_bytes()`], but reads the `.fastresume` file at `path`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use lava_torrent::torrent::v1::FastResume;
+    ///
+    /// let resume = FastResume::read_from_file("sample.fastresume").unwrap();
+    /// let n_have = resume.pieces.iter().filter(|&&have| have).count();
+    /// println!("{} of {} pieces downloaded", n_have, resume.pieces.len());
+    /// ```
+    ///
+    /// [`read_from_bytes()`]: #method.read_from_bytes
+    #[cfg(feature = "fs")]
+    pub fn read_from_file<P>(path: P) -> Result<FastResume, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        Self::read_from_bytes(std::fs::read(path).map_err(util::file_io_err(path, None))?)
+    }
+
+    /// Like [`read_from_bytes()`], but extracts the fast-resume data
+    /// from an already parsed `elem`, which must be a dictionary.
+    ///
+    /// [`read_from_bytes()`]: #method.read_from_bytes
+    pub fn from_bencode(elem: BencodeElem) -> Result<FastResume, LavaTorrentError> {
+        let mut dict = match elem {
+            BencodeElem::Dictionary(dict) => dict,
+            _ => {
+                return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                    Cow::Borrowed("Fast-resume data doesn't contain a dictionary."),
+                )));
+            }
+        };
+
+        match dict.remove("file-format") {
+            Some(BencodeElem::String(ref format)) if format == FILE_FORMAT => (),
+            Some(_) => {
+                return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                    Cow::Borrowed(r#""file-format" is not "libtorrent resume file"."#),
+                )));
+            }
+            None => (),
+        }
+
+        let info_hash = match dict.remove("info-hash") {
+            Some(BencodeElem::Bytes(bytes)) => bytes,
+            Some(BencodeElem::String(string)) => string.into_bytes(),
+            Some(_) => return Err(wrong_type("info-hash", ValueType::Bytes)),
+            None => {
+                return Err(LavaTorrentError::MalformedTorrent(
+                    TorrentErrorKind::MissingField {
+                        field: Cow::Borrowed("info-hash"),
+                    },
+                ));
+            }
+        };
+        let name = extract_string(&mut dict, "name")?;
+        let save_path = match extract_string(&mut dict, "save_path")? {
+            Some(save_path) => Some(save_path),
+            // keep `qBt-savePath` in `extra_fields` since it is not moved
+            None => match dict.get("qBt-savePath") {
+                Some(BencodeElem::String(save_path)) => Some(save_path.clone()),
+                Some(_) => return Err(wrong_type("qBt-savePath", ValueType::String)),
+                None => None,
+            },
+        };
+        let pieces = match dict.remove("pieces") {
+            Some(ref elem) => match elem.as_bytes() {
+                Some(bytes) => bytes.iter().map(|&b| b & PIECE_HAVE != 0).collect(),
+                None => return Err(wrong_type("pieces", ValueType::Bytes)),
+            },
+            None => Vec::new(),
+        };
+        let trackers = match dict.remove("trackers") {
+            Some(BencodeElem::List(tiers)) => tiers
+                .into_iter()
+                .map(extract_tracker_tier)
+                .collect::<Result<AnnounceList, LavaTorrentError>>()?,
+            Some(_) => return Err(wrong_type("trackers", ValueType::List)),
+            None => Vec::new(),
+        };
+
+        Ok(FastResume {
+            info_hash,
+            name,
+            save_path,
+            pieces,
+            trackers,
+            extra_fields: if dict.is_empty() { None } else { Some(dict) },
+        })
+    }
+}
+
+fn extract_string(
+    dict: &mut HashMap<String, BencodeElem>,
+    field: &'static str,
+) -> Result<Option<String>, LavaTorrentError> {
+    match dict.remove(field) {
+        Some(BencodeElem::String(string)) => Ok(Some(string)),
+        Some(_) => Err(wrong_type(field, ValueType::String)),
+        None => Ok(None),
+    }
+}
+
+fn extract_tracker_tier(elem: BencodeElem) -> Result<Vec<String>, LavaTorrentError> {
+    match elem {
+        BencodeElem::List(urls) => urls
+            .into_iter()
+            .map(|url| match url {
+                BencodeElem::String(url) => Ok(url),
+                _ => Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                    Cow::Borrowed(r#"A tier within "trackers" contains a non-string element."#),
+                ))),
+            })
+            .collect(),
+        _ => Err(LavaTorrentError::MalformedTorrent(
+            TorrentErrorKind::WrongElementType {
+                field: Cow::Borrowed("trackers"),
+                expected: ValueType::List,
+            },
+        )),
+    }
+}
+
+fn wrong_type(field: &'static str, expected: ValueType) -> LavaTorrentError {
+    LavaTorrentError::MalformedTorrent(TorrentErrorKind::WrongType {
+        field: Cow::Borrowed(field),
+        expected,
+    })
+}
+
+#[cfg(test)]
+mod fast_resume_tests {
+    use super::*;
+
+    #[cfg(feature = "fs")]
+    fn resume_dict(elem: BencodeElem) -> HashMap<String, BencodeElem> {
+        match elem {
+            BencodeElem::Dictionary(dict) => dict,
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn fast_resume_data_complete() {
+        let torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        let report = torrent.verify("tests/files/byte_sequence").unwrap();
+        let resume = resume_dict(torrent.fast_resume_data("tests/files", &report).unwrap());
+
+        assert_eq!(
+            resume["file-format"],
+            BencodeElem::String("libtorrent resume file".to_owned())
+        );
+        assert_eq!(
+            resume["info-hash"],
+            BencodeElem::Bytes(torrent.info_hash_bytes())
+        );
+        assert_eq!(resume["info"], torrent.construct_info());
+        assert_eq!(
+            resume["save_path"],
+            BencodeElem::String("tests/files".to_owned())
+        );
+        assert_eq!(resume["pieces"], BencodeElem::Bytes(vec![1; 4]));
+        assert_ne!(resume["completed_time"], BencodeElem::Integer(0));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn fast_resume_data_partial() {
+        let mut torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        torrent.info.fields_mut().pieces[2][0] ^= 1;
+        let report = torrent.verify("tests/files/byte_sequence").unwrap();
+        let resume = resume_dict(torrent.fast_resume_data("tests/files", &report).unwrap());
+
+        assert_eq!(resume["pieces"], BencodeElem::Bytes(vec![1, 1, 0, 1]));
+        assert_eq!(resume["completed_time"], BencodeElem::Integer(0));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn fast_resume_data_piece_out_of_bounds() {
+        let torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        let report = VerificationReport {
+            verified_pieces: vec![4],
+            ..VerificationReport::default()
+        };
+
+        match torrent.fast_resume_data("tests/files", &report) {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, "report has a verified piece [4] that is out of bounds.")
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn read_fast_resume_round_trip() {
+        let mut torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        torrent.info.fields_mut().pieces[2][0] ^= 1;
+        let report = torrent.verify("tests/files/byte_sequence").unwrap();
+        let bytes = torrent
+            .fast_resume_data("tests/files", &report)
+            .unwrap()
+            .encode();
+        let resume = FastResume::read_from_bytes(bytes).unwrap();
+
+        assert_eq!(resume.info_hash, torrent.info_hash_bytes());
+        assert_eq!(resume.name, Some(torrent.info.name.clone()));
+        assert_eq!(resume.save_path, Some("tests/files".to_owned()));
+        assert_eq!(resume.pieces, vec![true, true, false, true]);
+        assert!(resume.trackers.is_empty());
+
+        let extra_fields = resume.extra_fields.unwrap();
+        assert_eq!(extra_fields["info"], torrent.construct_info());
+        assert_eq!(extra_fields["file-version"], BencodeElem::Integer(1));
+        assert!(!extra_fields.contains_key("file-format"));
+    }
+
+    #[test]
+    fn read_fast_resume_qbittorrent() {
+        let resume = FastResume::read_from_bytes(
+            b"d9:info-hash20:aaaaaaaaaaaaaaaaaaaa6:pieces4:\x01\x00\x03\x02\
+              12:qBt-category0:12:qBt-savePath9:/download8:trackersll3:url4:url2el4:url3eee",
+        )
+        .unwrap();
+
+        assert_eq!(resume.info_hash, vec![b'a'; 20]);
+        assert_eq!(resume.name, None);
+        assert_eq!(resume.save_path, Some("/download".to_owned()));
+        assert_eq!(resume.pieces, vec![true, false, true, false]);
+        assert_eq!(
+            resume.trackers,
+            vec![
+                vec!["url".to_owned(), "url2".to_owned()],
+                vec!["url3".to_owned()]
+            ]
+        );
+        assert_eq!(resume.extra_fields.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn read_fast_resume_missing_info_hash() {
+        match FastResume::read_from_bytes(b"d9:save_path1:ae") {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""info-hash" does not exist."#)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn read_fast_resume_wrong_type() {
+        match FastResume::read_from_bytes(b"d9:info-hash1:a6:piecesi1ee") {
+            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(
+                m,
+                TorrentErrorKind::WrongType {
+                    field: Cow::Borrowed("pieces"),
+                    expected: ValueType::Bytes,
+                }
+                .to_string()
+            ),
+            _ => panic!(),
+        }
+
+        match FastResume::read_from_bytes(b"d9:info-hash1:a8:trackersl3:urlee") {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""trackers" contains a non-list element."#)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn read_fast_resume_wrong_file_format() {
+        match FastResume::read_from_bytes(b"d11:file-format3:foo9:info-hash1:ae") {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, r#""file-format" is not "libtorrent resume file"."#)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn read_fast_resume_not_dictionary() {
+        match FastResume::read_from_bytes(b"le") {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, "Fast-resume data doesn't contain a dictionary.")
+            }
+            _ => panic!(),
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use super::*;
+#[cfg(feature = "fs")]
+use crate::util;
+
+impl RtorrentState {
+    /// Parse `bytes` (i.e. the content of a `.torrent.rtorrent` file)
+    /// and return the extracted `RtorrentState`.
+    ///
+    /// If `bytes` does not contain a dictionary, or if any field has
+    /// an unexpected type, then `Err(error)` will be returned.
+    pub fn read_from_bytes<B>(bytes: B) -> Result<RtorrentState, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        let mut dict = read_dictionary(bytes.as_ref())?;
+
+        Ok(RtorrentState {
+            directory: extract_string(&mut dict, "directory")?,
+            state: extract_integer(&mut dict, "state")?,
+            complete: extract_integer(&mut dict, "complete")?,
+            extra_fields: if dict.is_empty() { None } else { Some(dict) },
+        })
+    }
+
+    /// Like [`read_from_bytes()`], but reads the file at `path`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use lava_torrent::torrent::v1::RtorrentState;
+    ///
+    /// let session = "/home/user/.session/3F9AAC158C7DE8DFCAB171EA58A17AABDF7FBC93";
+    /// let state = RtorrentState::read_from_file(format!("{}.torrent.rtorrent", session)).unwrap();
+    /// println!("saved to {:?}", state.directory);
+    /// ```
+    ///
+    /// [`read_from_bytes()`]: #method.read_from_bytes
+    #[cfg(feature = "fs")]
+    pub fn read_from_file<P>(path: P) -> Result<RtorrentState, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        Self::read_from_bytes(std::fs::read(path).map_err(util::file_io_err(path, None))?)
+    }
+
+    /// Convert `self` into a `BencodeElem`, which can then be
+    /// written to a `.torrent.rtorrent` file.
+    pub fn into_bencode(self) -> BencodeElem {
+        let mut dict = self.extra_fields.unwrap_or_default();
+        if let Some(directory) = self.directory {
+            dict.insert("directory".to_owned(), BencodeElem::String(directory));
+        }
+        if let Some(state) = self.state {
+            dict.insert("state".to_owned(), BencodeElem::Integer(state));
+        }
+        if let Some(complete) = self.complete {
+            dict.insert("complete".to_owned(), BencodeElem::Integer(complete));
+        }
+        BencodeElem::Dictionary(dict)
+    }
+}
+
+impl RtorrentResume {
+    /// Parse `bytes` (i.e. the content of a `.torrent.libtorrent_resume` file)
+    /// and return the extracted `RtorrentResume`.
+    ///
+    /// If `bytes` does not contain a dictionary, or if any field has
+    /// an unexpected type, then `Err(error)` will be returned.
+    pub fn read_from_bytes<B>(bytes: B) -> Result<RtorrentResume, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        let mut dict = read_dictionary(bytes.as_ref())?;
+
+        let bitfield = match dict.remove("bitfield") {
+            Some(BencodeElem::Integer(n_pieces)) => Some(RtorrentBitfield::Complete(n_pieces)),
+            Some(BencodeElem::Bytes(bits)) => Some(RtorrentBitfield::Partial(bits)),
+            Some(BencodeElem::String(bits)) => Some(RtorrentBitfield::Partial(bits.into_bytes())),
+            Some(_) => return Err(wrong_type("bitfield", ValueType::Bytes)),
+            None => None,
+        };
+
+        Ok(RtorrentResume {
+            bitfield,
+            extra_fields: if dict.is_empty() { None } else { Some(dict) },
+        })
+    }
+
+    /// Like [`read_from_bytes()`], but reads the file at `path`.
+    ///
+    /// [`read_from_bytes()`]: #method.read_from_bytes
+    #[cfg(feature = "fs")]
+    pub fn read_from_file<P>(path: P) -> Result<RtorrentResume, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        Self::read_from_bytes(std::fs::read(path).map_err(util::file_io_err(path, None))?)
+    }
+
+    /// Convert `self` into a `BencodeElem`, which can then be
+    /// written to a `.torrent.libtorrent_resume` file.
+    pub fn into_bencode(self) -> BencodeElem {
+        let mut dict = self.extra_fields.unwrap_or_default();
+        match self.bitfield {
+            Some(RtorrentBitfield::Complete(n_pieces)) => {
+                dict.insert("bitfield".to_owned(), BencodeElem::Integer(n_pieces));
+            }
+            Some(RtorrentBitfield::Partial(bits)) => {
+                dict.insert("bitfield".to_owned(), BencodeElem::Bytes(bits));
+            }
+            None => (),
+        }
+        BencodeElem::Dictionary(dict)
+    }
+}
+
+impl RtorrentBitfield {
+    /// Create a bitfield from whether each piece has been downloaded
+    /// (e.g. [`FastResume::pieces`]), in piece order.
+    ///
+    /// [`FastResume::pieces`]: struct.FastResume.html#structfield.pieces
+    pub fn from_pieces(pieces: &[bool]) -> RtorrentBitfield {
+        if !pieces.is_empty() && pieces.iter().all(|&have| have) {
+            if let Ok(n_pieces) = Integer::try_from(pieces.len()) {
+                return RtorrentBitfield::Complete(n_pieces);
+            }
+        }
+
+        RtorrentBitfield::Partial(
+            pieces
+                .chunks(8)
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .filter(|&(_, &have)| have)
+                        .fold(0, |byte, (i, _)| byte | (0x80 >> i))
+                })
+                .collect(),
+        )
+    }
+
+    /// Check whether the piece at `index` has been downloaded.
+    pub fn has_piece(&self, index: usize) -> bool {
+        match *self {
+            RtorrentBitfield::Complete(n_pieces) => {
+                Integer::try_from(index).is_ok_and(|index| index < n_pieces)
+            }
+            RtorrentBitfield::Partial(ref bits) => bits
+                .get(index / 8)
+                .is_some_and(|&byte| byte & (0x80 >> (index % 8)) != 0),
+        }
+    }
+}
+
+fn read_dictionary(bytes: &[u8]) -> Result<Dictionary, LavaTorrentError> {
+    let mut parsed = BencodeElem::from_bytes(bytes)?;
+    if parsed.len() != 1 {
+        return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+            Cow::Owned(format!(
+                "rtorrent session file should contain 1 and only 1 top-level element, {} found.",
+                parsed.len()
+            )),
+        )));
+    }
+
+    match parsed.remove(0) {
+        BencodeElem::Dictionary(dict) => Ok(dict),
+        _ => Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+            Cow::Borrowed("rtorrent session file doesn't contain a dictionary."),
+        ))),
+    }
+}
+
+fn extract_string(
+    dict: &mut Dictionary,
+    field: &'static str,
+) -> Result<Option<String>, LavaTorrentError> {
+    match dict.remove(field) {
+        Some(BencodeElem::String(string)) => Ok(Some(string)),
+        Some(_) => Err(wrong_type(field, ValueType::String)),
+        None => Ok(None),
+    }
+}
+
+fn extract_integer(
+    dict: &mut Dictionary,
+    field: &'static str,
+) -> Result<Option<Integer>, LavaTorrentError> {
+    match dict.remove(field) {
+        Some(BencodeElem::Integer(int)) => Ok(Some(int)),
+        Some(_) => Err(wrong_type(field, ValueType::Integer)),
+        None => Ok(None),
+    }
+}
+
+fn wrong_type(field: &'static str, expected: ValueType) -> LavaTorrentError {
+    LavaTorrentError::MalformedTorrent(TorrentErrorKind::WrongType {
+        field: Cow::Borrowed(field),
+        expected,
+    })
+}
+
+#[cfg(test)]
+mod rtorrent_tests {
+    use super::*;
+
+    #[test]
+    fn rtorrent_state_round_trip() {
+        let bytes: &[u8] = b"d8:completei1e7:custom15:movie9:directory10:/downloads\
+            5:statei1e13:timestamp.newi1700000000ee";
+        let state = RtorrentState::read_from_bytes(bytes).unwrap();
+
+        assert_eq!(state.directory, Some("/downloads".to_owned()));
+        assert_eq!(state.state, Some(1));
+        assert_eq!(state.complete, Some(1));
+        assert_eq!(
+            state.extra_fields.as_ref().unwrap()["custom1"],
+            BencodeElem::String("movie".to_owned())
+        );
+        assert_eq!(state.into_bencode().encode(), bytes);
+    }
+
+    #[test]
+    fn rtorrent_state_wrong_type() {
+        match RtorrentState::read_from_bytes(b"d5:state1:1e") {
+            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(
+                m,
+                TorrentErrorKind::WrongType {
+                    field: Cow::Borrowed("state"),
+                    expected: ValueType::Integer,
+                }
+                .to_string()
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn rtorrent_state_not_dictionary() {
+        match RtorrentState::read_from_bytes(b"i1e") {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, "rtorrent session file doesn't contain a dictionary.")
+            }
+            _ => panic!(),
+        }
+
+        match RtorrentState::read_from_bytes(b"dede") {
+            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(
+                m,
+                "rtorrent session file should contain 1 and only 1 top-level element, 2 found."
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn rtorrent_resume_round_trip() {
+        let bytes: &[u8] = b"d8:bitfield2:\xa0\x805:filesld9:completedi3e5:mtimei1700000000eee\
+            8:trackersd3:urld7:enabledi1eeee";
+        let resume = RtorrentResume::read_from_bytes(bytes).unwrap();
+
+        assert_eq!(
+            resume.bitfield,
+            Some(RtorrentBitfield::Partial(vec![0xa0, 0x80]))
+        );
+        assert_eq!(resume.extra_fields.as_ref().unwrap().len(), 2);
+        assert_eq!(resume.into_bencode().encode(), bytes);
+
+        let bytes: &[u8] = b"d8:bitfieldi10ee";
+        let resume = RtorrentResume::read_from_bytes(bytes).unwrap();
+        assert_eq!(resume.bitfield, Some(RtorrentBitfield::Complete(10)));
+        assert_eq!(resume.extra_fields, None);
+        assert_eq!(resume.into_bencode().encode(), bytes);
+    }
+
+    #[test]
+    fn rtorrent_resume_wrong_type() {
+        match RtorrentResume::read_from_bytes(b"d8:bitfieldlee") {
+            Err(LavaTorrentError::MalformedTorrent(m)) => assert_eq!(
+                m,
+                TorrentErrorKind::WrongType {
+                    field: Cow::Borrowed("bitfield"),
+                    expected: ValueType::Bytes,
+                }
+                .to_string()
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn bitfield_from_pieces() {
+        assert_eq!(
+            RtorrentBitfield::from_pieces(&[true; 10]),
+            RtorrentBitfield::Complete(10)
+        );
+        assert_eq!(
+            RtorrentBitfield::from_pieces(&[
+                true, false, true, false, false, false, false, false, true
+            ]),
+            RtorrentBitfield::Partial(vec![0xa0, 0x80])
+        );
+        assert_eq!(
+            RtorrentBitfield::from_pieces(&[]),
+            RtorrentBitfield::Partial(vec![])
+        );
+    }
+
+    #[test]
+    fn bitfield_has_piece() {
+        let bitfield = RtorrentBitfield::Partial(vec![0xa0, 0x80]);
+        let pieces: Vec<_> = (0..17).map(|index| bitfield.has_piece(index)).collect();
+        assert_eq!(
+            pieces,
+            vec![
+                true, false, true, false, false, false, false, false, true, false, false, false,
+                false, false, false, false, false
+            ]
+        );
+
+        let bitfield = RtorrentBitfield::Complete(3);
+        assert!(bitfield.has_piece(2));
+        assert!(!bitfield.has_piece(3));
+    }
+}
@@ -0,0 +1,314 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+use std::ops::Range;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+// A file reader that is aware of holes in sparse files.
+//
+// Holes are located when the file is first opened (via SEEK_HOLE/SEEK_DATA
+// where available). Reading from a hole yields zeros without touching
+// the disk. On platforms/filesystems that don't support hole detection,
+// this simply behaves like a regular `File`.
+pub(super) struct SparseFile {
+    file: File,
+    holes: Arc<[(u64, u64)]>, // sorted, non-overlapping [start, end) ranges
+    position: u64,
+    needs_seek: bool, // `file`'s cursor is out of sync with `position`
+}
+
+// Holes of a file, located by whichever `SparseFile` opens it first.
+// Sharing this between readers of the same file (e.g. one per piece)
+// avoids scanning the file for holes each time it is opened.
+#[derive(Debug, Default)]
+pub(super) struct Holes(OnceLock<Arc<[(u64, u64)]>>);
+
+impl SparseFile {
+    pub(super) fn open<P>(path: P) -> io::Result<SparseFile>
+    where
+        P: AsRef<Path>,
+    {
+        Self::open_with_holes(path, &Holes::default())
+    }
+
+    // like `open()`, but reuses `holes` if they have been located already
+    pub(super) fn open_with_holes<P>(path: P, holes: &Holes) -> io::Result<SparseFile>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path)?;
+        let holes = holes.get_or_locate(&file)?;
+
+        Ok(SparseFile {
+            file,
+            holes,
+            position: 0,
+            needs_seek: false,
+        })
+    }
+}
+
+impl Holes {
+    // the holes of `file`, which are located unless that has been done already
+    pub(super) fn get_or_locate(&self, file: &File) -> io::Result<Arc<[(u64, u64)]>> {
+        match self.0.get() {
+            Some(holes) => Ok(holes.clone()),
+            None => {
+                let length = file.metadata()?.len();
+                Ok(self
+                    .0
+                    .get_or_init(|| Arc::from(find_holes(file, length)))
+                    .clone())
+            }
+        }
+    }
+}
+
+// Split the `len` bytes at `offset` into the ranges that are not in `holes`,
+// i.e. the parts that actually have to be read from disk.
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+pub(super) fn data_ranges(holes: &[(u64, u64)], offset: u64, len: u64) -> Vec<Range<u64>> {
+    let end = offset + len;
+    let mut ranges = Vec::new();
+    let mut position = offset;
+
+    // holes that end before `offset` don't matter
+    for &(start, hole_end) in &holes[holes.partition_point(|&(_, end)| end <= offset)..] {
+        if start >= end {
+            break;
+        }
+        if start > position {
+            ranges.push(position..start);
+        }
+        position = std::cmp::max(position, hole_end);
+    }
+    if position < end {
+        ranges.push(position..end);
+    }
+    ranges
+}
+
+impl Read for SparseFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        // find the first hole that ends after the current position
+        let i = self.holes.partition_point(|&(_, end)| end <= self.position);
+
+        match self.holes.get(i) {
+            // inside a hole => fill with zeros
+            Some(&(start, end)) if start <= self.position => {
+                let n = std::cmp::min(buf.len() as u64, end - self.position) as usize;
+                buf[..n].iter_mut().for_each(|b| *b = 0);
+                self.position += n as u64;
+                self.needs_seek = true;
+                Ok(n)
+            }
+            // inside data => read from disk, but stop at the next hole
+            next_hole => {
+                if self.needs_seek {
+                    self.file.seek(SeekFrom::Start(self.position))?;
+                    self.needs_seek = false;
+                }
+
+                let n = match next_hole {
+                    Some(&(start, _)) => {
+                        std::cmp::min(buf.len() as u64, start - self.position) as usize
+                    }
+                    None => buf.len(),
+                };
+                let read = self.file.read(&mut buf[..n])?;
+                self.position += read as u64;
+                Ok(read)
+            }
+        }
+    }
+}
+
+impl Seek for SparseFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        // `file`'s cursor might lag behind `position` after reading a hole
+        let pos = match pos {
+            SeekFrom::Current(offset) => SeekFrom::Start(
+                self.position
+                    .checked_add_signed(offset)
+                    .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?,
+            ),
+            pos => pos,
+        };
+        self.position = self.file.seek(pos)?;
+        self.needs_seek = false;
+        Ok(self.position)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+fn find_holes(file: &File, length: u64) -> Vec<(u64, u64)> {
+    use std::convert::TryFrom;
+    use std::os::unix::io::AsRawFd;
+
+    // any failure simply means that holes won't be skipped
+    let seek = |offset: u64, whence| -> Option<Result<u64, i32>> {
+        let offset = libc::off_t::try_from(offset).ok()?;
+        // SAFETY: `lseek()` only operates on the file descriptor,
+        // which is valid for as long as `file` is alive.
+        let result = unsafe { libc::lseek(file.as_raw_fd(), offset, whence) };
+        if result < 0 {
+            Some(Err(io::Error::last_os_error().raw_os_error().unwrap_or(0)))
+        } else {
+            Some(Ok(u64::try_from(result).ok()?))
+        }
+    };
+
+    let mut holes = Vec::new();
+    let mut offset = 0;
+
+    while offset < length {
+        let start = match seek(offset, libc::SEEK_HOLE) {
+            Some(Ok(start)) => start,
+            _ => return Vec::new(),
+        };
+        if start >= length {
+            break;
+        }
+
+        let end = match seek(start, libc::SEEK_DATA) {
+            Some(Ok(end)) => std::cmp::min(end, length),
+            Some(Err(libc::ENXIO)) => length, // no data after `start`
+            _ => return Vec::new(),
+        };
+        if end <= start {
+            return Vec::new();
+        }
+        holes.push((start, end));
+        offset = end;
+    }
+
+    // restore the cursor since SEEK_HOLE/SEEK_DATA moved it
+    match seek(0, libc::SEEK_SET) {
+        Some(Ok(_)) => holes,
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+fn find_holes(_file: &File, _length: u64) -> Vec<(u64, u64)> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod sparse_file_tests {
+    // @note: the behavior of `SparseFile` when reading holes
+    // depends on the filesystem, so the tests here use
+    // fabricated holes in addition to real files.
+    use super::*;
+    use std::io::Write;
+
+    const OUTPUT_ROOT: &str = "tests/tmp/";
+
+    fn fake_sparse_file(holes: Vec<(u64, u64)>) -> SparseFile {
+        SparseFile {
+            // byte_sequence contains 256 bytes ranging from 0x0 to 0xff
+            file: File::open("tests/files/byte_sequence").unwrap(),
+            holes: Arc::from(holes),
+            position: 0,
+            needs_seek: false,
+        }
+    }
+
+    #[test]
+    fn read_no_holes() {
+        let mut bytes = Vec::new();
+        fake_sparse_file(vec![]).read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, (0..=255).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn read_with_holes() {
+        let mut bytes = Vec::new();
+        fake_sparse_file(vec![(0, 16), (100, 200)])
+            .read_to_end(&mut bytes)
+            .unwrap();
+
+        let mut expected = (0..=255).collect::<Vec<u8>>();
+        expected[0..16].iter_mut().for_each(|b| *b = 0);
+        expected[100..200].iter_mut().for_each(|b| *b = 0);
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn seek_and_read_with_holes() {
+        let mut file = fake_sparse_file(vec![(100, 200)]);
+        let mut bytes = Vec::new();
+
+        file.seek(SeekFrom::Start(90)).unwrap();
+        file.by_ref().take(20).read_to_end(&mut bytes).unwrap();
+        assert_eq!(
+            bytes,
+            vec![90, 91, 92, 93, 94, 95, 96, 97, 98, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        bytes.clear();
+        file.seek(SeekFrom::Start(198)).unwrap();
+        file.by_ref().take(4).read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, vec![0, 0, 200, 201]);
+    }
+
+    #[test]
+    fn seek_current_after_hole() {
+        let mut file = fake_sparse_file(vec![(100, 200)]);
+        let mut bytes = Vec::new();
+
+        // the underlying file's cursor stays at 100 while reading the hole
+        file.by_ref().take(150).read_to_end(&mut bytes).unwrap();
+        assert_eq!(file.seek(SeekFrom::Current(60)).unwrap(), 210);
+
+        bytes.clear();
+        file.by_ref().take(2).read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, vec![210, 211]);
+        assert!(file.seek(SeekFrom::Current(-1000)).is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    fn data_ranges_ok() {
+        let holes = [(10, 20), (30, 40)];
+
+        assert_eq!(data_ranges(&holes, 0, 50), vec![0..10, 20..30, 40..50]);
+        assert_eq!(data_ranges(&holes, 15, 20), vec![20..30]);
+        assert_eq!(data_ranges(&holes, 12, 5), vec![]);
+        assert_eq!(data_ranges(&holes, 40, 5), vec![40..45]);
+        assert_eq!(data_ranges(&[], 5, 5), vec![5..10]);
+    }
+
+    #[test]
+    fn read_real_sparse_file() {
+        std::fs::create_dir_all(OUTPUT_ROOT).unwrap();
+        let path = format!("{}sparse_file_{}", OUTPUT_ROOT, std::process::id());
+        let mut file = File::create(&path).unwrap();
+        file.seek(SeekFrom::Start(1 << 20)).unwrap();
+        file.write_all(b"data").unwrap();
+        file.set_len(2 << 20).unwrap();
+        drop(file);
+
+        let holes = Holes::default();
+        let mut bytes = Vec::new();
+        SparseFile::open_with_holes(&path, &holes)
+            .unwrap()
+            .read_to_end(&mut bytes)
+            .unwrap();
+
+        let mut expected = vec![0; 2 << 20];
+        expected[(1 << 20)..(1 << 20) + 4].copy_from_slice(b"data");
+        assert_eq!(bytes, expected);
+
+        // the holes located above are reused
+        let located = holes.0.get().unwrap().clone();
+        let file = SparseFile::open_with_holes(&path, &holes).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(Arc::ptr_eq(&file.holes, &located));
+    }
+}
@@ -0,0 +1,128 @@
+use super::*;
+use crate::util;
+use rand::Rng;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+
+// Piece hashes that are written to a temporary file as they are pushed
+// instead of being kept in memory. The file is removed when dropped.
+pub(super) struct PieceSpill {
+    path: PathBuf,
+    file: BufWriter<std::fs::File>,
+    len: usize,
+}
+
+impl PieceSpill {
+    // create the temporary file in `dir`
+    pub(super) fn new<P>(dir: P) -> Result<PieceSpill, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = dir.as_ref().join(format!(
+            "lava_torrent-{:016x}.pieces",
+            rand::thread_rng().gen::<u64>()
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(util::file_io_err(&path, None))?;
+
+        Ok(PieceSpill {
+            path,
+            file: BufWriter::new(file),
+            len: 0,
+        })
+    }
+
+    pub(super) fn push(&mut self, piece: Piece) -> Result<(), LavaTorrentError> {
+        self.file
+            .write_all(&piece)
+            .map_err(util::file_io_err(&self.path, None))?;
+        self.len += 1;
+        Ok(())
+    }
+
+    // number of hashes pushed
+    pub(super) fn len(&self) -> usize {
+        self.len
+    }
+
+    // Write all hashes (concatenated) to `dst`.
+    pub(super) fn copy_into<W>(&mut self, dst: &mut W) -> Result<(), LavaTorrentError>
+    where
+        W: Write,
+    {
+        self.file
+            .flush()
+            .map_err(util::file_io_err(&self.path, None))?;
+        let file = self.file.get_mut();
+        file.seek(SeekFrom::Start(0))
+            .map_err(util::file_io_err(&self.path, None))?;
+
+        let expected = util::usize_to_u64(self.len * PIECE_STRING_LENGTH)?;
+        let copied = std::io::copy(&mut file.take(expected), dst)?;
+        if copied == expected {
+            Ok(())
+        } else {
+            Err(LavaTorrentError::FileIo {
+                path: self.path.clone(),
+                offset: Some(copied),
+                source: std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "temporary file of piece hashes is shorter than expected",
+                ),
+            })
+        }
+    }
+}
+
+impl Drop for PieceSpill {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod piece_spill_tests {
+    use super::*;
+
+    #[test]
+    fn push_and_copy_into() {
+        let mut spill = PieceSpill::new(std::env::temp_dir()).unwrap();
+        spill.push([1; 20]).unwrap();
+        spill.push([2; 20]).unwrap();
+        assert_eq!(spill.len(), 2);
+
+        let mut copied = Vec::new();
+        spill.copy_into(&mut copied).unwrap();
+        assert_eq!(copied, [[1; 20], [2; 20]].concat());
+
+        // copying again gives the same result
+        spill.push([3; 20]).unwrap();
+        let mut copied = Vec::new();
+        spill.copy_into(&mut copied).unwrap();
+        assert_eq!(copied, [[1; 20], [2; 20], [3; 20]].concat());
+    }
+
+    #[test]
+    fn drop_removes_file() {
+        let spill = PieceSpill::new(std::env::temp_dir()).unwrap();
+        let path = spill.path.clone();
+        assert!(path.exists());
+
+        drop(spill);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn new_missing_dir() {
+        match PieceSpill::new("target/no_such_dir") {
+            Err(LavaTorrentError::FileIo { path, .. }) => {
+                assert!(path.starts_with("target/no_such_dir"))
+            }
+            _ => panic!(),
+        }
+    }
+}
@@ -0,0 +1,617 @@
+use super::*;
+#[cfg(feature = "rand")]
+use rand::seq::SliceRandom;
+use std::collections::HashSet;
+
+impl Torrent {
+    /// Add `url` to `announce_list`, in a new tier of its own.
+    ///
+    /// If `announce_list` does not exist yet, it is created from `announce`
+    /// first. Afterwards, `announce` is set to the first tracker in
+    /// `announce_list` if it's not already in there, as recommended by
+    /// [BEP 12](http://bittorrent.org/beps/bep_0012.html) for
+    /// compatibility with clients that ignore `announce_list`.
+    ///
+    /// Returns `false` (and does nothing) if `url` is already a tracker.
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(feature = "fs", doc = "```no_run")]
+    #[cfg_attr(not(feature = "fs"), doc = "```ignore")]
+    /// use lava_torrent::torrent::v1::Torrent;
+    ///
+    /// let mut torrent = Torrent::read_from_file("sample.torrent").unwrap();
+    /// torrent.add_tracker("udp://backup.example.com:6969/announce".to_owned());
+    /// torrent.remove_tracker("http://dead.example.com/announce");
+    /// ```
+    pub fn add_tracker(&mut self, url: String) -> bool {
+        self.add_tracker_to_tier(usize::MAX, url)
+    }
+
+    /// Add `url` to tier `tier` (0-based) of `announce_list`.
+    ///
+    /// If `tier` does not exist yet, `url` is added in a new tier after
+    /// the last one instead. `announce` and `announce_list` are kept
+    /// consistent in the same way as [`add_tracker()`].
+    ///
+    /// Returns `false` (and does nothing) if `url` is already a tracker.
+    ///
+    /// [`add_tracker()`]: #method.add_tracker
+    pub fn add_tracker_to_tier(&mut self, tier: usize, url: String) -> bool {
+        if self.has_tracker(&url) {
+            return false;
+        }
+
+        let announce = self.announce.clone();
+        let list = self
+            .announce_list
+            .get_or_insert_with(|| announce.into_iter().map(|url| vec![url]).collect());
+        match list.get_mut(tier) {
+            Some(tier) => tier.push(url),
+            None => list.push(vec![url]),
+        }
+
+        self.sync_announce();
+        true
+    }
+
+    /// Remove `url` from `announce` and all tiers of `announce_list`.
+    ///
+    /// Tiers that become empty are removed, as is `announce_list` if it
+    /// becomes empty. If `announce` is removed, it is replaced by the first
+    /// remaining tracker in `announce_list` (if any).
+    ///
+    /// Returns `false` if `url` is not a tracker.
+    pub fn remove_tracker(&mut self, url: &str) -> bool {
+        if !self.has_tracker(url) {
+            return false;
+        }
+
+        if self.announce.as_deref() == Some(url) {
+            self.announce = None;
+        }
+        if let Some(ref mut list) = self.announce_list {
+            for tier in list.iter_mut() {
+                tier.retain(|tracker| tracker != url);
+            }
+        }
+
+        self.sync_announce();
+        true
+    }
+
+    /// Remove duplicate trackers from `announce_list`, keeping only the
+    /// first occurrence of each. Tiers that become empty are removed.
+    pub fn dedupe_trackers(&mut self) {
+        if let Some(ref mut list) = self.announce_list {
+            let mut seen = HashSet::new();
+            for tier in list.iter_mut() {
+                tier.retain(|tracker| seen.insert(tracker.clone()));
+            }
+        }
+
+        self.sync_announce();
+    }
+
+    /// Merge all trackers (`announce` followed by each tier of `announce_list`)
+    /// into a single tier, removing duplicates.
+    ///
+    /// Clients try the trackers of a tier in random order, so this spreads
+    /// the load across all trackers instead of preferring the first tier.
+    /// Does nothing if there are no trackers.
+    pub fn flatten_trackers(&mut self) {
+        let mut seen = HashSet::new();
+        let tier: Vec<String> = self
+            .announce
+            .iter()
+            .chain(self.announce_list.iter().flatten().flatten())
+            .filter(|tracker| seen.insert(*tracker))
+            .cloned()
+            .collect();
+
+        if !tier.is_empty() {
+            self.announce_list = Some(vec![tier]);
+        }
+        self.sync_announce();
+    }
+
+    /// Get the trackers grouped by tier, with the trackers of each tier
+    /// in random order, as clients are supposed to do on load
+    /// ([BEP 12](http://bittorrent.org/beps/bep_0012.html)).
+    ///
+    /// If `announce_list` does not exist, `announce` (if any) is the only tier.
+    /// The order of the tiers is preserved.
+    #[cfg(feature = "rand")]
+    pub fn shuffled_trackers(&self) -> Vec<Vec<&str>> {
+        let mut tiers: Vec<Vec<&str>> = match self.announce_list {
+            Some(ref list) => list
+                .iter()
+                .map(|tier| tier.iter().map(String::as_str).collect())
+                .collect(),
+            None => self.announce.iter().map(|url| vec![url.as_str()]).collect(),
+        };
+
+        let mut rng = rand::thread_rng();
+        for tier in tiers.iter_mut() {
+            tier.shuffle(&mut rng);
+        }
+        tiers
+    }
+
+    /// Randomize the order of trackers within each tier of `announce_list`.
+    /// See [`shuffled_trackers()`].
+    ///
+    /// [`shuffled_trackers()`]: #method.shuffled_trackers
+    #[cfg(feature = "rand")]
+    pub fn shuffle_trackers(&mut self) {
+        let mut rng = rand::thread_rng();
+        for tier in self.announce_list.iter_mut().flatten() {
+            tier.shuffle(&mut rng);
+        }
+    }
+
+    /// Copy this torrent for cross-seeding on another tracker, i.e. replace
+    /// all trackers with `announce` (`announce_list` is removed) and, if `source`
+    /// is given, set `source` in `extra_info_fields`.
+    ///
+    /// Private trackers usually require `source` to be their name, which also
+    /// gives the copy its own info hash. If the torrent has an `entropy` field
+    /// (see [`TorrentBuilder::set_entropy()`]), it is replaced by a new random
+    /// value for the same reason. Whether the info hash actually changed is
+    /// reported in the returned [`TrackerClone`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use lava_torrent::torrent::v1::Torrent;
+    ///
+    /// let torrent = Torrent::read_from_file("sample.torrent").unwrap();
+    /// let clone = torrent.clone_for_tracker(
+    ///     "https://tracker.example.com/announce".to_owned(),
+    ///     Some("EXAMPLE".to_owned()),
+    /// );
+    ///
+    /// if clone.info_hash_changed {
+    ///     println!("new info hash: {}", clone.torrent.info_hash());
+    /// }
+    /// ```
+    ///
+    /// [`TorrentBuilder::set_entropy()`]: struct.TorrentBuilder.html#method.set_entropy
+    /// [`TrackerClone`]: struct.TrackerClone.html
+    #[cfg(feature = "rand")]
+    pub fn clone_for_tracker(&self, announce: String, source: Option<String>) -> TrackerClone {
+        let mut torrent = self.clone();
+        torrent.announce = Some(announce);
+        torrent.announce_list = None;
+
+        if let Some(source) = source {
+            torrent.replace_extra_info_field("source", Some(BencodeElem::String(source)));
+        }
+        let has_entropy = torrent
+            .info
+            .extra_info_fields
+            .as_ref()
+            .is_some_and(|fields| fields.contains_key("entropy"));
+        if has_entropy {
+            torrent.replace_extra_info_field(
+                "entropy",
+                Some(BencodeElem::String(TorrentBuilder::entropy())),
+            );
+        }
+
+        let info_hash_changed = torrent.info_hash_bytes() != self.info_hash_bytes();
+        TrackerClone {
+            torrent,
+            info_hash_changed,
+        }
+    }
+
+    /// Merge the trackers (`announce` and `announce_list`), web seeds
+    /// (`url-list`) and DHT nodes (`nodes`) of `other` into `self`.
+    ///
+    /// This is meant for torrents of the same content that are obtained from
+    /// different sources, so an error is returned (and nothing is changed)
+    /// if the info hashes of `self` and `other` differ.
+    ///
+    /// The trackers of each tier of `other` are added to the same tier of `self`
+    /// (see [`add_tracker_to_tier()`]). Web seeds and nodes of `other` are appended
+    /// to those of `self`. Duplicates are skipped, and all other fields
+    /// of `self` are left untouched.
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(feature = "fs", doc = "```no_run")]
+    #[cfg_attr(not(feature = "fs"), doc = "```ignore")]
+    /// use lava_torrent::torrent::v1::Torrent;
+    ///
+    /// let mut torrent = Torrent::read_from_file("sample1.torrent").unwrap();
+    /// let other = Torrent::read_from_file("sample2.torrent").unwrap();
+    /// torrent.merge_metadata(&other).unwrap();
+    /// ```
+    ///
+    /// [`add_tracker_to_tier()`]: #method.add_tracker_to_tier
+    pub fn merge_metadata(&mut self, other: &Torrent) -> Result<(), LavaTorrentError> {
+        let info_hash = self.info_hash();
+        let other_info_hash = other.info_hash();
+        if info_hash != other_info_hash {
+            return Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                "torrents have different info hashes ({} and {}).",
+                info_hash, other_info_hash
+            ))));
+        }
+
+        let url_list = merge_lists(self.url_list.clone(), other.url_list.clone());
+        let nodes = merge_lists(self.nodes.clone(), other.nodes.clone());
+
+        for (tier, urls) in other.announce_list.iter().flatten().enumerate() {
+            for url in urls {
+                self.add_tracker_to_tier(tier, url.clone());
+            }
+        }
+        if let Some(ref announce) = other.announce {
+            self.add_tracker_to_tier(0, announce.clone());
+        }
+        if url_list.is_some() {
+            self.url_list = url_list;
+        }
+        if nodes.is_some() {
+            self.nodes = nodes;
+        }
+
+        Ok(())
+    }
+
+    fn has_tracker(&self, url: &str) -> bool {
+        self.announce.as_deref() == Some(url)
+            || self
+                .announce_list
+                .iter()
+                .flatten()
+                .flatten()
+                .any(|tracker| tracker == url)
+    }
+
+    // Drop empty tiers (and an empty `announce_list`), and make sure
+    // that `announce` is one of the trackers in `announce_list`.
+    fn sync_announce(&mut self) {
+        if let Some(ref mut list) = self.announce_list {
+            list.retain(|tier| !tier.is_empty());
+        }
+        if self.announce_list.as_ref().is_some_and(Vec::is_empty) {
+            self.announce_list = None;
+        }
+
+        if let Some(ref list) = self.announce_list {
+            let listed = self
+                .announce
+                .as_ref()
+                .is_some_and(|announce| list.iter().flatten().any(|tracker| tracker == announce));
+            if !listed {
+                self.announce = list.first().and_then(|tier| tier.first()).cloned();
+            }
+        }
+    }
+}
+
+// `Some` if `other` has anything that `list` doesn't.
+fn merge_lists<T>(list: Option<Vec<T>>, other: Option<Vec<T>>) -> Option<Vec<T>>
+where
+    T: PartialEq,
+{
+    let mut list = list.unwrap_or_default();
+    let mut is_changed = false;
+    for item in other.into_iter().flatten() {
+        if !list.contains(&item) {
+            list.push(item);
+            is_changed = true;
+        }
+    }
+
+    if is_changed {
+        Some(list)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tracker_tests {
+    use super::*;
+
+    fn torrent() -> Torrent {
+        Torrent {
+            announce: Some("url1".to_owned()),
+            announce_list: Some(vec![
+                vec!["url1".to_owned(), "url2".to_owned()],
+                vec!["url3".to_owned()],
+            ]),
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        }
+    }
+
+    fn tiers(tiers: &[&[&str]]) -> Option<AnnounceList> {
+        Some(
+            tiers
+                .iter()
+                .map(|tier| tier.iter().map(|url| url.to_string()).collect())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn add_tracker_ok() {
+        let mut torrent = torrent();
+
+        assert!(torrent.add_tracker("url4".to_owned()));
+        assert_eq!(
+            torrent.announce_list,
+            tiers(&[&["url1", "url2"], &["url3"], &["url4"]])
+        );
+        assert_eq!(torrent.announce, Some("url1".to_owned()));
+    }
+
+    #[test]
+    fn add_tracker_to_tier_ok() {
+        let mut torrent = torrent();
+
+        assert!(torrent.add_tracker_to_tier(1, "url4".to_owned()));
+        assert!(torrent.add_tracker_to_tier(5, "url5".to_owned()));
+        assert_eq!(
+            torrent.announce_list,
+            tiers(&[&["url1", "url2"], &["url3", "url4"], &["url5"]])
+        );
+        assert_eq!(torrent.announce, Some("url1".to_owned()));
+    }
+
+    #[test]
+    fn add_tracker_duplicate() {
+        let mut torrent = torrent();
+
+        assert!(!torrent.add_tracker("url3".to_owned()));
+        assert!(!torrent.add_tracker_to_tier(0, "url3".to_owned()));
+        assert_eq!(torrent, self::torrent());
+    }
+
+    #[test]
+    fn add_tracker_announce_only() {
+        let mut torrent = torrent();
+        torrent.announce_list = None;
+
+        assert!(torrent.add_tracker_to_tier(0, "url2".to_owned()));
+        assert_eq!(torrent.announce_list, tiers(&[&["url1", "url2"]]));
+        assert_eq!(torrent.announce, Some("url1".to_owned()));
+    }
+
+    #[test]
+    fn add_tracker_no_trackers() {
+        let mut torrent = torrent();
+        torrent.announce = None;
+        torrent.announce_list = None;
+
+        assert!(torrent.add_tracker("url2".to_owned()));
+        assert_eq!(torrent.announce_list, tiers(&[&["url2"]]));
+        assert_eq!(torrent.announce, Some("url2".to_owned()));
+    }
+
+    #[test]
+    fn remove_tracker_ok() {
+        let mut torrent = torrent();
+
+        assert!(torrent.remove_tracker("url3"));
+        assert_eq!(torrent.announce_list, tiers(&[&["url1", "url2"]]));
+        assert_eq!(torrent.announce, Some("url1".to_owned()));
+    }
+
+    #[test]
+    fn remove_tracker_announce() {
+        let mut torrent = torrent();
+
+        assert!(torrent.remove_tracker("url1"));
+        assert_eq!(torrent.announce_list, tiers(&[&["url2"], &["url3"]]));
+        assert_eq!(torrent.announce, Some("url2".to_owned()));
+    }
+
+    #[test]
+    fn remove_tracker_last() {
+        let mut torrent = torrent();
+        torrent.announce_list = None;
+
+        assert!(torrent.remove_tracker("url1"));
+        assert_eq!(torrent.announce_list, None);
+        assert_eq!(torrent.announce, None);
+    }
+
+    #[test]
+    fn remove_tracker_missing() {
+        let mut torrent = torrent();
+
+        assert!(!torrent.remove_tracker("url4"));
+        assert_eq!(torrent, self::torrent());
+    }
+
+    #[test]
+    fn dedupe_trackers_ok() {
+        let mut torrent = torrent();
+        torrent.announce_list = tiers(&[&["url1", "url2", "url1"], &["url2"], &["url3"]]);
+
+        torrent.dedupe_trackers();
+        assert_eq!(torrent, self::torrent());
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn shuffled_trackers_ok() {
+        let mut torrent = torrent();
+        torrent.announce_list = tiers(&[&["url1", "url2", "url3", "url4"], &["url5"]]);
+
+        let mut shuffled = torrent.shuffled_trackers();
+        assert_eq!(shuffled.len(), 2);
+        assert_eq!(shuffled[1], vec!["url5"]);
+        shuffled[0].sort_unstable();
+        assert_eq!(shuffled[0], vec!["url1", "url2", "url3", "url4"]);
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn shuffled_trackers_announce_only() {
+        let mut torrent = torrent();
+        torrent.announce_list = None;
+
+        assert_eq!(torrent.shuffled_trackers(), vec![vec!["url1"]]);
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn shuffle_trackers_ok() {
+        let mut torrent = torrent();
+
+        torrent.shuffle_trackers();
+        let mut list = torrent.announce_list.unwrap();
+        list[0].sort_unstable();
+        assert_eq!(Some(list), self::torrent().announce_list);
+        assert_eq!(torrent.announce, Some("url1".to_owned()));
+    }
+
+    #[test]
+    fn flatten_trackers_ok() {
+        let mut torrent = torrent();
+        torrent.announce = Some("url0".to_owned());
+
+        torrent.flatten_trackers();
+        assert_eq!(
+            torrent.announce_list,
+            tiers(&[&["url0", "url1", "url2", "url3"]])
+        );
+        assert_eq!(torrent.announce, Some("url0".to_owned()));
+    }
+
+    #[test]
+    fn flatten_trackers_no_trackers() {
+        let mut torrent = torrent();
+        torrent.announce = None;
+        torrent.announce_list = None;
+
+        torrent.flatten_trackers();
+        assert_eq!(torrent.announce_list, None);
+        assert_eq!(torrent.announce, None);
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn clone_for_tracker_ok() {
+        let torrent = torrent();
+        let clone = torrent.clone_for_tracker("url4".to_owned(), None);
+
+        assert_eq!(clone.torrent.announce, Some("url4".to_owned()));
+        assert_eq!(clone.torrent.announce_list, None);
+        assert_eq!(clone.torrent.info.extra_info_fields, None);
+        assert!(!clone.info_hash_changed);
+        assert_eq!(clone.torrent.info_hash(), torrent.info_hash());
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn clone_for_tracker_source() {
+        let torrent = torrent();
+        let clone = torrent.clone_for_tracker("url4".to_owned(), Some("EX".to_owned()));
+
+        assert_eq!(
+            clone.torrent.info.extra_info_fields,
+            Some(HashMap::from([("source".to_owned(), bencode_elem!("EX"))]))
+        );
+        assert!(clone.info_hash_changed);
+        assert_ne!(clone.torrent.info_hash(), torrent.info_hash());
+
+        // same source again
+        let clone2 = clone
+            .torrent
+            .clone_for_tracker("url5".to_owned(), Some("EX".to_owned()));
+        assert!(!clone2.info_hash_changed);
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn clone_for_tracker_entropy() {
+        let mut torrent = torrent();
+        torrent.info.fields_mut().extra_info_fields = Some(HashMap::from([(
+            "entropy".to_owned(),
+            bencode_elem!("0123"),
+        )]));
+        let clone = torrent.clone_for_tracker("url4".to_owned(), None);
+
+        let entropy = &clone.torrent.info.extra_info_fields.as_ref().unwrap()["entropy"];
+        assert_ne!(entropy, &bencode_elem!("0123"));
+        assert_eq!(entropy.as_str().unwrap().len(), 32);
+        assert!(clone.info_hash_changed);
+    }
+
+    #[test]
+    fn merge_metadata_ok() {
+        let mut torrent1 = torrent();
+        torrent1.set_web_seeds(Some(vec!["seed1".to_owned()]));
+        let mut torrent2 = torrent();
+        torrent2.announce = Some("url4".to_owned());
+        torrent2.announce_list = tiers(&[&["url4", "url2"], &["url5"], &["url6"]]);
+        torrent2.set_web_seeds(Some(vec!["seed2".to_owned(), "seed1".to_owned()]));
+        torrent2.set_nodes(Some(vec![("node".to_owned(), 6881)]));
+
+        torrent1.merge_metadata(&torrent2).unwrap();
+        assert_eq!(torrent1.announce, Some("url1".to_owned()));
+        assert_eq!(
+            torrent1.announce_list,
+            tiers(&[&["url1", "url2", "url4"], &["url3", "url5"], &["url6"]])
+        );
+        assert_eq!(
+            torrent1.url_list,
+            Some(vec!["seed1".to_owned(), "seed2".to_owned()])
+        );
+        assert_eq!(torrent1.nodes, Some(vec![("node".to_owned(), 6881)]));
+    }
+
+    #[test]
+    fn merge_metadata_nothing_new() {
+        let mut torrent1 = torrent();
+        torrent1.url_list = Some(vec!["seed".to_owned()]);
+        let mut torrent2 = torrent1.clone();
+        torrent2.announce_list = None;
+        let expected = torrent1.clone();
+
+        torrent1.merge_metadata(&torrent2).unwrap();
+        assert_eq!(torrent1, expected);
+    }
+
+    #[test]
+    fn merge_metadata_different_info_hash() {
+        let mut torrent1 = torrent();
+        let mut torrent2 = torrent();
+        torrent2.info.fields_mut().name = "other".to_owned();
+        torrent2.announce = Some("url4".to_owned());
+
+        match torrent1.merge_metadata(&torrent2) {
+            Err(LavaTorrentError::InvalidArgument(m)) => assert_eq!(
+                m,
+                format!(
+                    "torrents have different info hashes ({} and {}).",
+                    torrent().info_hash(),
+                    torrent2.info_hash()
+                )
+            ),
+            _ => panic!(),
+        }
+        assert_eq!(torrent1, torrent());
+    }
+}
@@ -0,0 +1,388 @@
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+// A minimal io_uring (see io_uring(7)) that can only read into buffers,
+// just enough to batch the reads of a piece into a single syscall.
+
+const IORING_OFF_SQ_RING: libc::off_t = 0;
+const IORING_OFF_CQ_RING: libc::off_t = 0x0800_0000;
+const IORING_OFF_SQES: libc::off_t = 0x1000_0000;
+const IORING_ENTER_GETEVENTS: libc::c_uint = 1;
+const IORING_FEAT_RW_CUR_POS: u32 = 1 << 3; // implies IORING_OP_READ (Linux 5.6)
+const IORING_OP_READ: u8 = 22;
+
+// The layouts below mirror <linux/io_uring.h>, so not all fields are used.
+
+#[repr(C)]
+#[derive(Default)]
+#[allow(dead_code)]
+struct SqRingOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    flags: u32,
+    dropped: u32,
+    array: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+#[allow(dead_code)]
+struct CqRingOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    overflow: u32,
+    cqes: u32,
+    flags: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+#[allow(dead_code)]
+struct Params {
+    sq_entries: u32,
+    cq_entries: u32,
+    flags: u32,
+    sq_thread_cpu: u32,
+    sq_thread_idle: u32,
+    features: u32,
+    wq_fd: u32,
+    resv: [u32; 3],
+    sq_off: SqRingOffsets,
+    cq_off: CqRingOffsets,
+}
+
+#[repr(C)]
+#[derive(Default)]
+#[allow(dead_code)]
+struct SubmissionEntry {
+    opcode: u8,
+    flags: u8,
+    ioprio: u16,
+    fd: i32,
+    off: u64,
+    addr: u64,
+    len: u32,
+    rw_flags: u32,
+    user_data: u64,
+    buf_index: u16,
+    personality: u16,
+    splice_fd_in: i32,
+    addr3: u64,
+    pad: u64,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct CompletionEntry {
+    user_data: u64,
+    res: i32,
+    flags: u32,
+}
+
+// A shared memory region of the ring, unmapped when dropped.
+struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+impl Mmap {
+    fn new(fd: RawFd, len: usize, offset: libc::off_t) -> io::Result<Mmap> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_POPULATE,
+                fd,
+                offset,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(Mmap { ptr, len })
+        }
+    }
+
+    // `offset` comes from the kernel, so it is within the mapping
+    unsafe fn at<T>(&self, offset: u32) -> *mut T {
+        self.ptr.cast::<u8>().add(offset as usize).cast()
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+// A read of `buf.len()` bytes at `offset` of `fd`. `result` is set once the
+// read completes, and might be short (e.g. if the file has been truncated).
+pub(super) struct ReadOp<'a> {
+    pub(super) fd: RawFd,
+    pub(super) offset: u64,
+    pub(super) buf: &'a mut [u8],
+    pub(super) result: Option<io::Result<usize>>,
+}
+
+impl<'a> ReadOp<'a> {
+    pub(super) fn new(fd: RawFd, offset: u64, buf: &'a mut [u8]) -> ReadOp<'a> {
+        ReadOp {
+            fd,
+            offset,
+            buf,
+            result: None,
+        }
+    }
+}
+
+pub(super) struct Ring {
+    params: Params,
+    sq: Mmap,
+    cq: Mmap,
+    sqes: Mmap,
+    // declared last so that it is closed after the memory is unmapped
+    fd: OwnedFd,
+}
+
+// The raw pointers are only used through `&mut Ring`.
+unsafe impl Send for Ring {}
+
+impl Ring {
+    // Fails if io_uring is not supported by the kernel,
+    // or not allowed (e.g. by a seccomp filter).
+    pub(super) fn new(entries: u32) -> io::Result<Ring> {
+        let mut params = Params::default();
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_setup,
+                entries as libc::c_long,
+                &mut params as *mut Params,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd as RawFd) };
+        if params.features & IORING_FEAT_RW_CUR_POS == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "IORING_OP_READ is not supported",
+            ));
+        }
+
+        let sq_len = params.sq_off.array as usize + params.sq_entries as usize * 4;
+        let cq_len = params.cq_off.cqes as usize
+            + params.cq_entries as usize * std::mem::size_of::<CompletionEntry>();
+        let sqes_len = params.sq_entries as usize * std::mem::size_of::<SubmissionEntry>();
+        Ok(Ring {
+            sq: Mmap::new(fd.as_raw_fd(), sq_len, IORING_OFF_SQ_RING)?,
+            cq: Mmap::new(fd.as_raw_fd(), cq_len, IORING_OFF_CQ_RING)?,
+            sqes: Mmap::new(fd.as_raw_fd(), sqes_len, IORING_OFF_SQES)?,
+            params,
+            fd,
+        })
+    }
+
+    // Perform all `ops`, submitting as many of them at once as the ring allows.
+    // An error is only returned if the ring itself fails, in which
+    // case it should not be used anymore. Errors of individual
+    // reads are stored in their `ReadOp::result`.
+    pub(super) fn read(&mut self, ops: &mut [ReadOp]) -> io::Result<()> {
+        for batch in ops.chunks_mut(self.params.sq_entries as usize) {
+            self.read_batch(batch)?;
+        }
+        Ok(())
+    }
+
+    fn read_batch(&mut self, ops: &mut [ReadOp]) -> io::Result<()> {
+        let n_ops = ops.len();
+
+        unsafe {
+            let sq_off = &self.params.sq_off;
+            let tail = &*self.sq.at::<AtomicU32>(sq_off.tail);
+            let mask = *self.sq.at::<u32>(sq_off.ring_mask);
+            let array = self.sq.at::<u32>(sq_off.array);
+            let sqes = self.sqes.ptr.cast::<SubmissionEntry>();
+            let mut next_tail = tail.load(Ordering::Relaxed);
+
+            for (i, op) in ops.iter_mut().enumerate() {
+                let index = next_tail & mask;
+                sqes.add(index as usize).write(SubmissionEntry {
+                    opcode: IORING_OP_READ,
+                    fd: op.fd,
+                    off: op.offset,
+                    addr: op.buf.as_mut_ptr() as u64,
+                    // longer reads end up short, which callers handle anyway
+                    len: u32::try_from(op.buf.len()).unwrap_or(u32::MAX),
+                    user_data: i as u64,
+                    ..Default::default()
+                });
+                array.add(index as usize).write(index);
+                next_tail = next_tail.wrapping_add(1);
+            }
+            tail.store(next_tail, Ordering::Release);
+        }
+
+        let mut n_submitted = 0;
+        let mut n_completed = 0;
+        while n_completed < n_ops {
+            match self.enter(n_ops - n_submitted, n_ops - n_completed) {
+                Ok(n) => n_submitted += n,
+                Err(e) if is_transient(&e) => (),
+                Err(e) => {
+                    self.discard_unsubmitted();
+                    // the submitted reads are not done with their buffers yet,
+                    // so they must complete before the buffers are given back
+                    while n_completed < n_submitted {
+                        if self.enter(0, n_submitted - n_completed).is_err() {
+                            // completions are still posted without `io_uring_enter()`
+                            std::thread::yield_now();
+                        }
+                        n_completed += self.reap(ops);
+                    }
+                    return Err(e);
+                }
+            }
+            n_completed += self.reap(ops);
+        }
+        Ok(())
+    }
+
+    // Submit `to_submit` entries and wait for `min_complete` completions,
+    // returning how many entries have been submitted.
+    fn enter(&mut self, to_submit: usize, min_complete: usize) -> io::Result<usize> {
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_enter,
+                self.fd.as_raw_fd(),
+                to_submit as libc::c_uint,
+                min_complete as libc::c_uint,
+                IORING_ENTER_GETEVENTS,
+                std::ptr::null::<libc::sigset_t>(),
+                0 as libc::size_t,
+            )
+        };
+        if ret < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(ret as usize)
+        }
+    }
+
+    // Drop the entries that have been queued but not consumed by the
+    // kernel yet, so that they can't be submitted after their buffers
+    // have been given back.
+    fn discard_unsubmitted(&mut self) {
+        unsafe {
+            let sq_off = &self.params.sq_off;
+            let head = (*self.sq.at::<AtomicU32>(sq_off.head)).load(Ordering::Acquire);
+            (*self.sq.at::<AtomicU32>(sq_off.tail)).store(head, Ordering::Release);
+        }
+    }
+
+    // Store the results of completed reads, returning how many there were.
+    fn reap(&mut self, ops: &mut [ReadOp]) -> usize {
+        unsafe {
+            let cq_off = &self.params.cq_off;
+            let head = &*self.cq.at::<AtomicU32>(cq_off.head);
+            let tail = (*self.cq.at::<AtomicU32>(cq_off.tail)).load(Ordering::Acquire);
+            let mask = *self.cq.at::<u32>(cq_off.ring_mask);
+            let cqes = self.cq.at::<CompletionEntry>(cq_off.cqes);
+            let mut next_head = head.load(Ordering::Relaxed);
+            let mut n_reaped = 0;
+
+            while next_head != tail {
+                let cqe = &*cqes.add((next_head & mask) as usize);
+                ops[cqe.user_data as usize].result = Some(if cqe.res < 0 {
+                    Err(io::Error::from_raw_os_error(-cqe.res))
+                } else {
+                    Ok(cqe.res as usize)
+                });
+                next_head = next_head.wrapping_add(1);
+                n_reaped += 1;
+            }
+            head.store(next_head, Ordering::Release);
+            n_reaped
+        }
+    }
+}
+
+fn is_transient(e: &io::Error) -> bool {
+    matches!(
+        e.raw_os_error(),
+        Some(libc::EINTR) | Some(libc::EAGAIN) | Some(libc::EBUSY)
+    )
+}
+
+#[cfg(test)]
+mod ring_tests {
+    use super::*;
+
+    #[test]
+    fn read_ok() {
+        // io_uring might not be available (e.g. in containers)
+        let mut ring = match Ring::new(2) {
+            Ok(ring) => ring,
+            Err(_) => return,
+        };
+        let file = std::fs::File::open("tests/files/byte_sequence").unwrap();
+        let mut bufs = vec![vec![0; 10]; 5];
+        let mut ops: Vec<_> = bufs
+            .iter_mut()
+            .enumerate()
+            .map(|(i, buf)| ReadOp::new(file.as_raw_fd(), (i * 60) as u64, buf))
+            .collect();
+        ring.read(&mut ops).unwrap();
+
+        let results: Vec<_> = ops
+            .into_iter()
+            .map(|op| op.result.unwrap().unwrap())
+            .collect();
+        assert_eq!(results, vec![10, 10, 10, 10, 10]);
+        for (i, buf) in bufs.iter().enumerate() {
+            let expected: Vec<u8> = (i as u8 * 60..i as u8 * 60 + 10).collect();
+            assert_eq!(buf, &expected);
+        }
+    }
+
+    #[test]
+    fn read_short_and_failed() {
+        let mut ring = match Ring::new(4) {
+            Ok(ring) => ring,
+            Err(_) => return,
+        };
+        let file = std::fs::File::open("tests/files/byte_sequence").unwrap();
+        let mut buf1 = vec![0; 10];
+        let mut buf2 = vec![0; 10];
+        let mut ops = vec![
+            ReadOp::new(file.as_raw_fd(), 250, &mut buf1),
+            ReadOp::new(-1, 0, &mut buf2),
+        ];
+        ring.read(&mut ops).unwrap();
+
+        assert_eq!(ops[0].result.as_ref().unwrap().as_ref().unwrap(), &6);
+        assert_eq!(
+            ops[1]
+                .result
+                .as_ref()
+                .unwrap()
+                .as_ref()
+                .unwrap_err()
+                .raw_os_error(),
+            Some(libc::EBADF)
+        );
+        assert_eq!(buf1[..6], [250, 251, 252, 253, 254, 255]);
+    }
+}
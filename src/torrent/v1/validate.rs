@@ -0,0 +1,646 @@
+use super::*;
+
+const TRACKER_SCHEMES: &[&str] = &["http", "https", "udp", "ws", "wss"];
+// Each piece adds 20 bytes to the metadata,
+// so this already means 2 MB of hashes.
+const MAX_RECOMMENDED_PIECES: usize = 100_000;
+// Top-level fields that are only meaningful in `info`.
+const INFO_ONLY_FIELDS: &[&str] = &[
+    "collections",
+    "files",
+    "length",
+    "name",
+    "piece length",
+    "pieces",
+    "private",
+    "root hash",
+    "similar",
+    "source",
+];
+// `info` fields that are only meaningful at the top level.
+const TOP_LEVEL_ONLY_FIELDS: &[&str] = &[
+    "announce",
+    "announce-list",
+    "comment",
+    "created by",
+    "creation date",
+    "encoding",
+    "httpseeds",
+    "nodes",
+    "url-list",
+];
+
+impl Torrent {
+    /// Check the torrent for problems and report all of them at once.
+    ///
+    /// Unlike [`read_from_bytes()`], which stops at the first error,
+    /// this collects every problem found (e.g. length mismatches, invalid
+    /// tracker URLs, unsafe or conflicting file paths) along with its [`Severity`], which
+    /// is useful for showing users everything that is wrong with a torrent.
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(feature = "fs", doc = "```no_run")]
+    #[cfg_attr(not(feature = "fs"), doc = "```ignore")]
+    /// use lava_torrent::torrent::v1::Torrent;
+    ///
+    /// let torrent = Torrent::read_from_file("sample.torrent").unwrap();
+    ///
+    /// for issue in torrent.validate_report().issues {
+    ///     println!("{:?}: {}", issue.severity, issue.message);
+    /// }
+    /// ```
+    ///
+    /// [`read_from_bytes()`]: #method.read_from_bytes
+    /// [`Severity`]: enum.Severity.html
+    pub fn validate_report(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        self.validate_name(&mut report);
+        self.validate_pieces(&mut report);
+        self.validate_files(&mut report);
+        self.validate_paths(&mut report);
+        self.validate_trackers(&mut report);
+
+        report
+    }
+
+    /// Check the torrent against best practices.
+    ///
+    /// Unlike [`validate_report()`], all issues returned have [`Severity::Warning`],
+    /// as they do not make the torrent invalid. Examples include a piece length that
+    /// is not a power of 2, or a torrent that has neither trackers nor DHT nodes.
+    ///
+    /// [`validate_report()`]: #method.validate_report
+    /// [`Severity::Warning`]: enum.Severity.html#variant.Warning
+    pub fn lint(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        let has_trackers = self.announce.is_some()
+            || self
+                .announce_list
+                .iter()
+                .flatten()
+                .any(|tier| !tier.is_empty());
+        if !has_trackers && self.nodes.is_none() {
+            report.warning("torrent has neither trackers nor DHT nodes.".to_owned());
+        }
+
+        if (self.info.piece_length > 0) && !(self.info.piece_length as u64).is_power_of_two() {
+            report.warning(format!(
+                "piece length [{}] is not a power of 2.",
+                self.info.piece_length
+            ));
+        }
+        if self.info.pieces.len() > MAX_RECOMMENDED_PIECES {
+            report.warning(format!(
+                "torrent has {} pieces, consider using a larger piece length.",
+                self.info.pieces.len()
+            ));
+        }
+
+        if looks_absolute(Path::new(&self.info.name)) {
+            report.warning(format!(
+                "name [{}] looks like an absolute path.",
+                self.info.name
+            ));
+        }
+        for (index, file) in self.info.files.iter().flatten().enumerate() {
+            if looks_absolute(&file.path) {
+                report.warning(format!(
+                    "file [{}] has a path [{}] that looks absolute.",
+                    index,
+                    file.path.display()
+                ));
+            }
+        }
+
+        let misplaced = |fields: &Option<Dictionary>, keys: &[&str]| {
+            fields
+                .iter()
+                .flat_map(|fields| fields.keys())
+                .filter(|key| keys.contains(&key.as_str()))
+                .sorted()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        for key in misplaced(&self.extra_fields, INFO_ONLY_FIELDS) {
+            report.warning(format!("[{}] should be in info instead of top level.", key));
+        }
+        for key in misplaced(&self.info.extra_info_fields, TOP_LEVEL_ONLY_FIELDS) {
+            report.warning(format!(
+                "[{}] should be at top level instead of in info.",
+                key
+            ));
+        }
+
+        report
+    }
+
+    fn validate_name(&self, report: &mut ValidationReport) {
+        if self.info.name.is_empty() {
+            report.error("name is empty.".to_owned());
+        } else if !is_valid_component(&self.info.name) {
+            report.error(format!(
+                "name [{}] is not a valid file name.",
+                self.info.name
+            ));
+        }
+    }
+
+    fn validate_pieces(&self, report: &mut ValidationReport) {
+        if self.info.length <= 0 {
+            report.error(format!("length [{}] is not positive.", self.info.length));
+        }
+        if self.info.piece_length <= 0 {
+            report.error(format!(
+                "piece length [{}] is not positive.",
+                self.info.piece_length
+            ));
+        }
+        if let Err(LavaTorrentError::MalformedTorrent(m)) = self.root_hash() {
+            report.error(m.to_string());
+        }
+        // merkle torrents have a root hash instead of pieces
+        if self.is_merkle() {
+            return;
+        }
+        if self.info.pieces.is_empty() {
+            report.error("pieces is empty.".to_owned());
+        }
+        if (self.info.length <= 0) || (self.info.piece_length <= 0) || self.info.pieces.is_empty() {
+            return;
+        }
+
+        let n_piece_required = (self.info.length as u64).div_ceil(self.info.piece_length as u64);
+        let n_piece = self.info.pieces.len() as u64;
+        if n_piece < n_piece_required {
+            report.error(format!(
+                "torrent has {} pieces but its length requires {}.",
+                n_piece, n_piece_required
+            ));
+        } else if n_piece > n_piece_required {
+            report.warning(format!(
+                "torrent has {} pieces but its length only requires {}.",
+                n_piece, n_piece_required
+            ));
+        }
+    }
+
+    fn validate_files(&self, report: &mut ValidationReport) {
+        let files = match self.info.files {
+            Some(ref files) => files,
+            None => return,
+        };
+        if files.is_empty() {
+            report.error("files is empty.".to_owned());
+            return;
+        }
+
+        let mut length: Option<Integer> = Some(0);
+        for (index, file) in files.iter().enumerate() {
+            if file.length < 0 {
+                report.error(format!("file [{}] has a negative length.", index));
+            }
+            length = length.and_then(|length| length.checked_add(file.length));
+
+            if file.path.as_os_str().is_empty() {
+                report.error(format!("file [{}] has an empty path.", index));
+            } else if file.path.has_root() {
+                report.error(format!(
+                    "file [{}] has an absolute path [{}].",
+                    index,
+                    file.path.display()
+                ));
+            } else if !file
+                .path
+                .iter()
+                .all(|component| is_valid_component(&component.to_string_lossy()))
+            {
+                report.error(format!(
+                    "file [{}] has an invalid path [{}].",
+                    index,
+                    file.path.display()
+                ));
+            }
+        }
+
+        match length {
+            Some(length) if length != self.info.length => report.error(format!(
+                "length [{}] does not match the total length of files [{}].",
+                self.info.length, length
+            )),
+            Some(_) => (),
+            None => report.error("total length of files overflowed in i64.".to_owned()),
+        }
+    }
+
+    // Check for files that can't all be created on disk, i.e. files with the same
+    // path, and files whose path is also used as a directory by other files.
+    // Paths that only conflict when case is ignored (e.g. on Windows and macOS)
+    // are warnings. Padding files are ignored, since they are usually not created.
+    fn validate_paths(&self, report: &mut ValidationReport) {
+        let files: Vec<_> = self
+            .info
+            .files
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, file)| !file.is_padding())
+            .map(|(index, file)| (index, file.path.as_path()))
+            .collect();
+
+        // the first file with each path / that each directory is found in
+        let mut paths = HashMap::new();
+        let mut dirs = HashMap::new();
+        let mut folded_paths = HashMap::new();
+        let mut folded_dirs = HashMap::new();
+        for &(index, path) in &files {
+            paths.entry(path).or_insert(index);
+            folded_paths.entry(fold_case(path)).or_insert(index);
+            for dir in path
+                .ancestors()
+                .skip(1)
+                .take_while(|dir| !dir.as_os_str().is_empty())
+            {
+                dirs.entry(dir).or_insert(index);
+                folded_dirs.entry(fold_case(dir)).or_insert(index);
+            }
+        }
+
+        for &(index, path) in &files {
+            let folded = fold_case(path);
+            let first = paths[path];
+            if first != index {
+                report.error(format!(
+                    "file [{}] has the same path [{}] as file [{}].",
+                    index,
+                    path.display(),
+                    first
+                ));
+            } else if folded_paths[&folded] != index {
+                report.warning(format!(
+                    "file [{}] has a path [{}] that only differs in case from file [{}].",
+                    index,
+                    path.display(),
+                    folded_paths[&folded]
+                ));
+            }
+
+            if let Some(other) = dirs.get(path) {
+                report.error(format!(
+                    "file [{}] has a path [{}] that file [{}] uses as a directory.",
+                    index,
+                    path.display(),
+                    other
+                ));
+            } else if let Some(other) = folded_dirs.get(&folded) {
+                report.warning(format!(
+                    "file [{}] has a path [{}] that file [{}] uses as a directory (ignoring case).",
+                    index,
+                    path.display(),
+                    other
+                ));
+            }
+        }
+    }
+
+    fn validate_trackers(&self, report: &mut ValidationReport) {
+        let urls = self.announce.iter().chain(
+            self.announce_list
+                .iter()
+                .flat_map(|announce_list| announce_list.iter().flatten()),
+        );
+
+        for url in urls {
+            match url.split_once("://") {
+                Some((scheme, host)) if !host.is_empty() => {
+                    if !TRACKER_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()) {
+                        report.warning(format!("tracker URL [{}] has an unsupported scheme.", url));
+                    }
+                }
+                _ => report.error(format!("tracker URL [{}] is invalid.", url)),
+            }
+        }
+
+        if let Some(ref announce_list) = self.announce_list {
+            if announce_list.iter().any(|tier| tier.is_empty()) {
+                report.warning("announce list contains an empty tier.".to_owned());
+            }
+        }
+    }
+}
+
+impl ValidationReport {
+    /// Check if no problem with [`Severity::Error`] has been found.
+    ///
+    /// [`Severity::Error`]: enum.Severity.html#variant.Error
+    pub fn is_valid(&self) -> bool {
+        self.issues
+            .iter()
+            .all(|issue| issue.severity != Severity::Error)
+    }
+
+    fn error(&mut self, message: String) {
+        self.issues.push(ValidationIssue {
+            severity: Severity::Error,
+            message,
+        });
+    }
+
+    fn warning(&mut self, message: String) {
+        self.issues.push(ValidationIssue {
+            severity: Severity::Warning,
+            message,
+        });
+    }
+}
+
+pub(super) fn is_valid_component(component: &str) -> bool {
+    !component.is_empty()
+        && (component != ".")
+        && (component != "..")
+        && !component.contains(['/', '\\', '\0'])
+}
+
+// `path` as compared by case-insensitive file systems (roughly).
+fn fold_case(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
+// Check for paths that are not absolute on this platform but would be
+// on others (or to a shell), e.g. "C:", "~" or "\\server".
+fn looks_absolute(path: &Path) -> bool {
+    let first = match path.iter().next() {
+        Some(first) => first.to_string_lossy(),
+        None => return false,
+    };
+    let bytes = first.as_bytes();
+
+    path.has_root()
+        || first.starts_with('~')
+        || first.starts_with('\\')
+        || ((bytes.len() >= 2) && bytes[0].is_ascii_alphabetic() && (bytes[1] == b':'))
+}
+
+#[cfg(test)]
+mod validation_report_tests {
+    use super::*;
+
+    fn torrent() -> Torrent {
+        Torrent {
+            announce: Some("udp://tracker.example.com:6969".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: Some(vec![
+                    File {
+                        length: 2,
+                        path: PathBuf::from("dir/file1"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                    File {
+                        length: 2,
+                        path: PathBuf::from("file2"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                ]),
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        }
+    }
+
+    fn messages(report: &ValidationReport, severity: Severity) -> Vec<&str> {
+        report
+            .issues
+            .iter()
+            .filter(|issue| issue.severity == severity)
+            .map(|issue| issue.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn validate_report_ok() {
+        let report = torrent().validate_report();
+
+        assert!(report.issues.is_empty());
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn validate_report_all_errors() {
+        let mut torrent = torrent();
+        let info = torrent.info.fields_mut();
+        info.name = "..".to_owned();
+        info.length = 6;
+        torrent.announce = Some("tracker.example.com".to_owned());
+        if let Some(ref mut files) = torrent.info.fields_mut().files {
+            files[0].path = PathBuf::from("dir/../file1");
+            files[1].path = PathBuf::from("/file2");
+        }
+        let report = torrent.validate_report();
+
+        assert_eq!(
+            messages(&report, Severity::Error),
+            vec![
+                "name [..] is not a valid file name.",
+                "torrent has 2 pieces but its length requires 3.",
+                "file [0] has an invalid path [dir/../file1].",
+                "file [1] has an absolute path [/file2].",
+                "length [6] does not match the total length of files [4].",
+                "tracker URL [tracker.example.com] is invalid.",
+            ]
+        );
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn validate_report_not_positive() {
+        let mut torrent = torrent();
+        let info = torrent.info.fields_mut();
+        info.length = 0;
+        info.piece_length = 0;
+        info.pieces = Pieces::new();
+        info.files = None;
+        let report = torrent.validate_report();
+
+        assert_eq!(
+            messages(&report, Severity::Error),
+            vec![
+                "length [0] is not positive.",
+                "piece length [0] is not positive.",
+                "pieces is empty.",
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_report_merkle() {
+        let mut torrent = torrent();
+        torrent.set_merkle();
+        assert!(torrent.validate_report().is_valid());
+
+        torrent
+            .info
+            .fields_mut()
+            .extra_info_fields
+            .as_mut()
+            .unwrap()
+            .insert("root hash".to_owned(), BencodeElem::Bytes(vec![1; 19]));
+        assert_eq!(
+            messages(&torrent.validate_report(), Severity::Error),
+            vec![
+                r#""root hash" is not a 20-byte string."#,
+                "pieces is empty.",
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_report_warnings() {
+        let mut torrent = torrent();
+        torrent.info.fields_mut().pieces.push([3; 20]);
+        torrent.announce_list = Some(vec![vec!["ftp://tracker.example.com".to_owned()], vec![]]);
+        let report = torrent.validate_report();
+
+        assert_eq!(
+            messages(&report, Severity::Warning),
+            vec![
+                "torrent has 3 pieces but its length only requires 2.",
+                "tracker URL [ftp://tracker.example.com] has an unsupported scheme.",
+                "announce list contains an empty tier.",
+            ]
+        );
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn validate_report_path_conflicts() {
+        let mut torrent = torrent();
+        let paths = [
+            "dir/file1", // 0
+            "dir",       // 1: directory of 0
+            "dir/file1", // 2: same as 0
+            "DIR/File1", // 3: same as 0 ignoring case
+            "Dir",       // 4: directory of 0 ignoring case
+            "file2",     // 5
+        ];
+        torrent.info.fields_mut().files = Some(
+            paths
+                .iter()
+                .map(|path| File {
+                    length: 1,
+                    path: PathBuf::from(path),
+                    extra_fields: None,
+                    raw_extra_fields: None,
+                })
+                .collect(),
+        );
+        let info = torrent.info.fields_mut();
+        info.length = 6;
+        info.pieces = Pieces::from(vec![[1; 20]; 3]);
+        let report = torrent.validate_report();
+
+        assert_eq!(
+            messages(&report, Severity::Error),
+            vec![
+                "file [1] has a path [dir] that file [0] uses as a directory.",
+                "file [2] has the same path [dir/file1] as file [0].",
+            ]
+        );
+        assert_eq!(
+            messages(&report, Severity::Warning),
+            vec![
+                "file [3] has a path [DIR/File1] that only differs in case from file [0].",
+                "file [4] has a path [Dir] that only differs in case from file [1].",
+                "file [4] has a path [Dir] that file [0] uses as a directory (ignoring case).",
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_report_path_conflicts_padding() {
+        let mut torrent = torrent();
+        let padding = File {
+            length: 1,
+            path: PathBuf::from(".pad/1"),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+        if let Some(ref mut files) = torrent.info.fields_mut().files {
+            files[0].length = 1;
+            files.insert(1, padding.clone());
+            files.push(padding);
+        }
+        let info = torrent.info.fields_mut();
+        info.length = 5;
+        info.pieces = Pieces::from(vec![[1; 20]; 3]);
+
+        assert!(torrent.validate_report().issues.is_empty());
+    }
+
+    #[test]
+    fn lint_ok() {
+        assert!(torrent().lint().issues.is_empty());
+    }
+
+    #[test]
+    fn lint_warnings() {
+        let mut torrent = torrent();
+        torrent.announce = None;
+        torrent.announce_list = Some(vec![vec![]]);
+        let info = torrent.info.fields_mut();
+        info.piece_length = 3;
+        info.name = "C:".to_owned();
+        if let Some(ref mut files) = torrent.info.fields_mut().files {
+            files[0].path = PathBuf::from("~/file1");
+            files[1].path = PathBuf::from("\\\\server\\file2");
+        }
+        torrent.extra_fields = Some(HashMap::from_iter(vec![
+            ("source".to_owned(), BencodeElem::String("A".to_owned())),
+            ("private".to_owned(), BencodeElem::Integer(1)),
+            ("comment".to_owned(), BencodeElem::String("A".to_owned())),
+        ]));
+        torrent.info.fields_mut().extra_info_fields = Some(HashMap::from_iter(vec![(
+            "announce".to_owned(),
+            BencodeElem::String("udp://tracker.example.com".to_owned()),
+        )]));
+        let report = torrent.lint();
+
+        assert_eq!(
+            messages(&report, Severity::Warning),
+            vec![
+                "torrent has neither trackers nor DHT nodes.",
+                "piece length [3] is not a power of 2.",
+                "name [C:] looks like an absolute path.",
+                "file [0] has a path [~/file1] that looks absolute.",
+                "file [1] has a path [\\\\server\\file2] that looks absolute.",
+                "[private] should be in info instead of top level.",
+                "[source] should be in info instead of top level.",
+                "[announce] should be at top level instead of in info.",
+            ]
+        );
+        assert_eq!(report.issues.len(), 8);
+    }
+
+    #[test]
+    fn lint_nodes_without_trackers() {
+        let mut torrent = torrent();
+        torrent.announce = None;
+        torrent.nodes = Some(vec![]);
+
+        assert!(torrent.lint().issues.is_empty());
+    }
+}
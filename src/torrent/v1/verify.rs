@@ -0,0 +1,1185 @@
+#[cfg(feature = "fs")]
+use super::sparse::{Holes, SparseFile};
+use super::*;
+use crate::util;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::io::Read;
+#[cfg(feature = "fs")]
+use std::io::Seek;
+#[cfg(feature = "fs")]
+use std::sync::atomic::Ordering;
+
+// Everything needed to verify a torrent's content, detached
+// from the `Torrent` so that it can be moved to other threads.
+struct Content {
+    files: Vec<(PathBuf, u64)>, // (path, length) of each file in torrent order
+    #[cfg(feature = "fs")]
+    holes: Vec<Holes>, // holes of each file, located when first read
+    offsets: Vec<u64>,          // offset of each file in the content
+    length: u64,
+    piece_length: u64,
+    pieces: Pieces,
+    #[cfg_attr(not(feature = "fs"), allow(dead_code))]
+    targets: Vec<usize>, // indices of the pieces to verify
+}
+
+impl Torrent {
+    /// Check whether the content at `path` matches the torrent's `pieces`.
+    ///
+    /// The returned [`VerificationReport`] lists the pieces that failed
+    /// verification, along with the files they cover, so that
+    /// only those pieces need to be downloaded again.
+    ///
+    /// `path` is the content itself: for single-file torrents it is the file,
+    /// and for multi-file torrents it is the directory that contains `files`
+    /// (i.e. usually a directory named after `name`). This mirrors
+    /// the `path` given to [`TorrentBuilder::new()`].
+    ///
+    /// Pieces are hashed in parallel, using as many threads as there
+    /// are physical cores. Missing or truncated files simply cause the
+    /// pieces they belong to to fail verification. Other IO errors
+    /// (e.g. permission denied) will result in `Err`.
+    ///
+    /// [`VerificationReport`]: struct.VerificationReport.html
+    /// [`TorrentBuilder::new()`]: struct.TorrentBuilder.html#method.new
+    #[cfg(feature = "fs")]
+    pub fn verify<P>(&self, path: P) -> Result<VerificationReport, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let content = self.content(path, None)?;
+        let torrent_verification = TorrentBuildInternal {
+            n_piece_processed: Arc::new(AtomicU64::new(0)),
+            n_piece_total: Arc::new(AtomicU64::new(0)),
+            is_canceled: Arc::new(AtomicBool::new(false)),
+            events: Default::default(),
+            files: Default::default(),
+        };
+
+        content.verify(&torrent_verification)
+    }
+
+    /// Like [`verify()`], but non-blocking.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use lava_torrent::torrent::v1::Torrent;
+    ///
+    /// let torrent = Torrent::read_from_file("sample.torrent").unwrap();
+    /// let verification = torrent.verify_non_blocking("dir/").unwrap();
+    ///
+    /// while !verification.is_finished() {
+    ///     println!("verification progress: {}%", verification.get_progress());
+    ///     std::thread::sleep(std::time::Duration::from_millis(100));
+    /// }
+    ///
+    /// assert!(verification.get_output().unwrap().is_intact());
+    /// ```
+    ///
+    /// [`verify()`]: #method.verify
+    #[cfg(feature = "fs")]
+    pub fn verify_non_blocking<P>(&self, path: P) -> Result<TorrentVerification, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::verify_content_non_blocking(self.content(path, None)?)
+    }
+
+    /// Check whether the files at `file_indices` (i.e. indices into `files`) match
+    /// the torrent's `pieces`. Only the pieces that overlap those files are hashed,
+    /// which makes this much cheaper than [`verify()`] for spot checks on huge
+    /// multi-file torrents.
+    ///
+    /// Note that pieces can span multiple files, so the pieces of
+    /// neighboring files might be partially read as well. For single-file
+    /// torrents, the only valid index is `0`.
+    ///
+    /// `path` has the same meaning as in [`verify()`].
+    ///
+    /// [`verify()`]: #method.verify
+    #[cfg(feature = "fs")]
+    pub fn verify_files<P>(
+        &self,
+        path: P,
+        file_indices: &[usize],
+    ) -> Result<VerificationReport, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let content = self.content(path, Some(file_indices))?;
+        let torrent_verification = TorrentBuildInternal {
+            n_piece_processed: Arc::new(AtomicU64::new(0)),
+            n_piece_total: Arc::new(AtomicU64::new(0)),
+            is_canceled: Arc::new(AtomicBool::new(false)),
+            events: Default::default(),
+            files: Default::default(),
+        };
+
+        content.verify(&torrent_verification)
+    }
+
+    /// Like [`verify_files()`], but non-blocking.
+    ///
+    /// [`verify_files()`]: #method.verify_files
+    #[cfg(feature = "fs")]
+    pub fn verify_files_non_blocking<P>(
+        &self,
+        path: P,
+        file_indices: &[usize],
+    ) -> Result<TorrentVerification, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::verify_content_non_blocking(self.content(path, Some(file_indices))?)
+    }
+
+    /// Cheaply check how much of the content at `path` is present, without hashing.
+    ///
+    /// Only file metadata is examined: each file is reported as missing,
+    /// as having the wrong length, or as present with the right length.
+    /// This is useful as a quick pre-check before the much more expensive [`verify()`],
+    /// but a file with the right length can still be incomplete or corrupted
+    /// (e.g. if it has been preallocated by a client).
+    ///
+    /// `path` has the same meaning as in [`verify()`].
+    ///
+    /// [`verify()`]: #method.verify
+    #[cfg(feature = "fs")]
+    pub fn check_content<P>(&self, path: P) -> Result<ContentCheck, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut check = ContentCheck::default();
+
+        for (path, length) in self.local_files(path)? {
+            let status = match std::fs::metadata(&path) {
+                Ok(metadata) if metadata.is_file() => {
+                    if metadata.len() == length {
+                        FileStatus::Present
+                    } else {
+                        FileStatus::WrongLength {
+                            actual: metadata.len(),
+                        }
+                    }
+                }
+                Ok(_) => FileStatus::Missing,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => FileStatus::Missing,
+                Err(e) => return Err(util::file_io_err(&path, None)(e)),
+            };
+
+            check.present_bytes += match status {
+                FileStatus::Present => length,
+                // a shorter file is likely still being downloaded sequentially
+                FileStatus::WrongLength { actual } if actual < length => actual,
+                _ => 0,
+            };
+            check.total_bytes += length;
+            check.files.push(status);
+        }
+
+        Ok(check)
+    }
+
+    /// Like [`verify()`], but reads the content from `reader` instead of from disk.
+    ///
+    /// `reader` must yield the content itself, i.e. all files concatenated
+    /// in order (or just the file for single-file torrents). Pieces are
+    /// hashed as they are read, so only one piece is kept in memory at
+    /// a time. This makes it possible to verify content that is not
+    /// stored as plain files, e.g. content streamed from a pipe or
+    /// extracted on the fly from an archive.
+    ///
+    /// If `reader` ends early, all pieces from that point on fail verification.
+    /// Reading stops at the end of the content, so any trailing data is left
+    /// unread. IO errors returned by `reader` will result in `Err`.
+    ///
+    /// [`verify()`]: #method.verify
+    pub fn verify_reader<R>(&self, reader: R) -> Result<VerificationReport, LavaTorrentError>
+    where
+        R: Read,
+    {
+        // paths are not used when reading from `reader`
+        self.content("", None)?.verify_reader(reader)
+    }
+
+    /// Check whether `data` is the piece at `index`.
+    ///
+    /// `data` must be exactly as long as the piece, i.e. `piece_length` bytes,
+    /// or [`last_piece_length()`] bytes for the last piece. `false` is returned
+    /// if it is not, or if `index` is out of bounds.
+    ///
+    /// [`last_piece_length()`]: #method.last_piece_length
+    pub fn verify_piece(&self, index: usize, data: &[u8]) -> bool {
+        match (self.info.pieces.get(index), self.piece_size(index)) {
+            (Some(piece), Some(size)) if size == data.len() as u64 => {
+                Sha1::digest(data).as_slice() == piece
+            }
+            _ => false,
+        }
+    }
+
+    /// Check the pieces that lie entirely within `data`, which is the part of
+    /// the content (i.e. all files concatenated in order) that starts at `offset`.
+    ///
+    /// Pieces that are only partially covered by `data` can't be checked,
+    /// so the bytes that belong to them are reported as unverified. This
+    /// makes it possible to validate content as it is streamed or downloaded
+    /// in arbitrary chunks, e.g. by keeping the unverified suffix of a chunk
+    /// around and prepending it to the next one.
+    ///
+    /// An error is returned if `data` extends past the end of the content.
+    pub fn verify_range(
+        &self,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<RangeVerification, LavaTorrentError> {
+        let length = util::i64_to_u64(self.info.length)?;
+        let piece_length = util::i64_to_u64(self.info.piece_length)?;
+        if piece_length == 0 {
+            return Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::InvalidPieceLength {
+                    piece_length: self.info.piece_length,
+                },
+            ));
+        }
+
+        let end = offset
+            .checked_add(util::usize_to_u64(data.len())?)
+            .filter(|&end| end <= length)
+            .ok_or_else(|| {
+                LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                    "range starting at {} with {} bytes is out of bounds ({} bytes of content).",
+                    offset,
+                    data.len(),
+                    length
+                )))
+            })?;
+
+        let mut verification = RangeVerification::default();
+        let mut index = util::u64_to_usize(offset.div_ceil(piece_length))?;
+        let mut verified_start = None;
+        let mut verified_end = offset;
+        loop {
+            let start = util::usize_to_u64(index)? * piece_length;
+            let piece_end = std::cmp::min(start + piece_length, length);
+            if start >= end || piece_end > end {
+                break;
+            }
+
+            let piece =
+                util::u64_to_usize(start - offset)?..util::u64_to_usize(piece_end - offset)?;
+            if self.verify_piece(index, &data[piece]) {
+                verification.verified_pieces.push(index);
+            } else {
+                verification.failed_pieces.push(index);
+            }
+            verified_start.get_or_insert(start);
+            verified_end = piece_end;
+            index += 1;
+        }
+
+        match verified_start {
+            Some(verified_start) => {
+                verification.unverified_prefix = offset..verified_start;
+                verification.unverified_suffix = verified_end..end;
+            }
+            // no piece lies entirely within `data`
+            None => {
+                verification.unverified_prefix = offset..end;
+                verification.unverified_suffix = end..end;
+            }
+        }
+
+        Ok(verification)
+    }
+
+    // Length of the piece at `index` according to `length` and `piece_length`.
+    // `None` if there is no such piece (or if the torrent is malformed).
+    fn piece_size(&self, index: usize) -> Option<u64> {
+        let length = u64::try_from(self.info.length).ok()?;
+        let piece_length = u64::try_from(self.info.piece_length)
+            .ok()
+            .filter(|&piece_length| piece_length > 0)?;
+
+        let start = u64::try_from(index).ok()?.checked_mul(piece_length)?;
+        if start < length {
+            Some(std::cmp::min(piece_length, length - start))
+        } else {
+            None
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    fn verify_content_non_blocking(
+        content: Content,
+    ) -> Result<TorrentVerification, LavaTorrentError> {
+        let n_piece_processed = Arc::new(AtomicU64::new(0));
+        let n_piece_total = Arc::new(AtomicU64::new(0));
+        let is_canceled = Arc::new(AtomicBool::new(false));
+
+        let torrent_verification = TorrentBuildInternal {
+            n_piece_processed: n_piece_processed.clone(),
+            n_piece_total: n_piece_total.clone(),
+            is_canceled: is_canceled.clone(),
+            events: Default::default(),
+            files: Default::default(),
+        };
+
+        let verifier_thread = std::thread::spawn(move || content.verify(&torrent_verification));
+
+        Ok(TorrentVerification {
+            n_piece_processed,
+            n_piece_total,
+            is_canceled,
+            verifier_thread: Some(verifier_thread),
+        })
+    }
+
+    // `file_indices` being `None` means that all pieces will be verified
+    fn content<P>(
+        &self,
+        path: P,
+        file_indices: Option<&[usize]>,
+    ) -> Result<Content, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let files = self.local_files(path)?;
+
+        let mut offsets = Vec::with_capacity(files.len());
+        let mut length = 0;
+        for &(_, file_length) in &files {
+            offsets.push(length);
+            length += file_length;
+        }
+
+        let piece_length = util::i64_to_u64(self.info.piece_length)?;
+        if piece_length == 0 {
+            return Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::InvalidPieceLength {
+                    piece_length: self.info.piece_length,
+                },
+            ));
+        }
+
+        let n_pieces = util::usize_to_u64(self.info.pieces.len())?;
+        let expected_n_pieces = length.div_ceil(piece_length);
+        if n_pieces != expected_n_pieces {
+            return Err(LavaTorrentError::MalformedTorrent(
+                TorrentErrorKind::PieceCountMismatch {
+                    pieces: self.info.pieces.len(),
+                    required: util::u64_to_usize(expected_n_pieces)?,
+                },
+            ));
+        }
+
+        let targets = match file_indices {
+            Some(file_indices) => {
+                let mut targets = Vec::new();
+                for &index in file_indices {
+                    let (_, length) = files.get(index).ok_or_else(|| {
+                        LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                            "file index [{}] is out of bounds.",
+                            index
+                        )))
+                    })?;
+                    let span = Span {
+                        offset: offsets[index],
+                        length: *length,
+                    };
+
+                    if let Some(pieces) = FilePieces::new(&span, piece_length)? {
+                        targets.extend(pieces.first_piece..=pieces.last_piece);
+                    }
+                }
+                targets.sort_unstable();
+                targets.dedup();
+                targets
+            }
+            None => (0..self.info.pieces.len()).collect(),
+        };
+
+        Ok(Content {
+            #[cfg(feature = "fs")]
+            holes: files.iter().map(|_| Holes::default()).collect(),
+            files,
+            offsets,
+            length,
+            piece_length,
+            pieces: self.info.pieces.clone(),
+            targets,
+        })
+    }
+
+    // (path, length) of each file on disk, in torrent order
+    fn local_files<P>(&self, path: P) -> Result<Vec<(PathBuf, u64)>, LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        match self.info.files {
+            Some(ref files) => files
+                .iter()
+                .map(|file| Ok((path.join(&file.path), util::i64_to_u64(file.length)?)))
+                .collect(),
+            None => Ok(vec![(
+                path.to_path_buf(),
+                util::i64_to_u64(self.info.length)?,
+            )]),
+        }
+    }
+}
+
+impl Content {
+    #[cfg(feature = "fs")]
+    fn verify(
+        &self,
+        torrent_verification: &TorrentBuildInternal,
+    ) -> Result<VerificationReport, LavaTorrentError> {
+        torrent_verification
+            .n_piece_total
+            .store(util::usize_to_u64(self.targets.len())?, Ordering::Release);
+
+        let verify_piece = |&index: &usize| {
+            if torrent_verification.is_canceled.load(Ordering::Acquire) {
+                return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Borrowed(
+                    "verification canceled by client",
+                )));
+            }
+
+            let matches = self.verify_piece(index)?;
+            torrent_verification
+                .n_piece_processed
+                .fetch_add(1, Ordering::AcqRel);
+            Ok((index, matches))
+        };
+
+        #[cfg(feature = "parallel")]
+        let results =
+            TorrentBuilder::rayon_thread_pool(num_cpus::get_physical())?.install(|| {
+                self.targets
+                    .par_iter()
+                    .map(verify_piece)
+                    .collect::<Result<Vec<_>, LavaTorrentError>>()
+            })?;
+        #[cfg(not(feature = "parallel"))]
+        let results = self
+            .targets
+            .iter()
+            .map(verify_piece)
+            .collect::<Result<Vec<_>, LavaTorrentError>>()?;
+
+        let mut report = VerificationReport::default();
+        for (index, matches) in results {
+            self.add_to_report(&mut report, index, matches)?;
+        }
+
+        Ok(report)
+    }
+
+    // Verify all pieces against `reader`, which yields the content itself.
+    // Only one piece is kept in memory at a time.
+    fn verify_reader<R>(&self, mut reader: R) -> Result<VerificationReport, LavaTorrentError>
+    where
+        R: Read,
+    {
+        let mut report = VerificationReport::default();
+        let mut bytes = Vec::with_capacity(util::u64_to_usize(self.piece_length)?);
+        let mut is_eof = false;
+
+        for index in 0..self.pieces.len() {
+            let range = self.piece_range(index)?;
+            let len = range.end - range.start;
+
+            bytes.clear();
+            if !is_eof {
+                let read = (&mut reader).take(len).read_to_end(&mut bytes)?;
+                is_eof = util::usize_to_u64(read)? != len;
+            }
+
+            // once the content ends, all remaining pieces fail
+            let matches = !is_eof && Sha1::digest(&bytes).as_slice() == self.pieces[index];
+            self.add_to_report(&mut report, index, matches)?;
+        }
+
+        Ok(report)
+    }
+
+    fn add_to_report(
+        &self,
+        report: &mut VerificationReport,
+        index: usize,
+        matches: bool,
+    ) -> Result<(), LavaTorrentError> {
+        let range = self.piece_range(index)?;
+        if matches {
+            report.verified_pieces.push(index);
+            report.verified_bytes += range.end - range.start;
+        } else {
+            report.failed_bytes += range.end - range.start;
+            report.failed_pieces.push(FailedPiece {
+                index,
+                files: self.piece_spans(&range),
+                range,
+            });
+        }
+        Ok(())
+    }
+
+    // hash the piece at `index` and compare it against the expected hash
+    #[cfg(feature = "fs")]
+    fn verify_piece(&self, index: usize) -> Result<bool, LavaTorrentError> {
+        let range = self.piece_range(index)?;
+        let mut bytes = Vec::with_capacity(util::u64_to_usize(range.end - range.start)?);
+
+        for (file_index, file_range) in self.piece_spans(&range) {
+            let path = &self.files[file_index].0;
+            let mut file = match SparseFile::open_with_holes(path, &self.holes[file_index]) {
+                Ok(file) => file,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+                Err(e) => return Err(util::file_io_err(path, None)(e)),
+            };
+
+            let offset = file_range.start;
+            let len = file_range.end - file_range.start;
+            file.seek(std::io::SeekFrom::Start(offset))
+                .map_err(util::file_io_err(path, Some(offset)))?;
+            let read = file
+                .take(len)
+                .read_to_end(&mut bytes)
+                .map_err(util::file_io_err(path, Some(offset)))?;
+            if util::usize_to_u64(read)? != len {
+                return Ok(false); // file is truncated
+            }
+        }
+
+        Ok(Sha1::digest(&bytes).as_slice() == self.pieces[index])
+    }
+
+    // byte range of the piece at `index` within the content
+    fn piece_range(&self, index: usize) -> Result<Range<u64>, LavaTorrentError> {
+        let start = util::usize_to_u64(index)? * self.piece_length;
+        let end = std::cmp::min(start + self.piece_length, self.length);
+        Ok(start..end)
+    }
+
+    // files covered by `range` (of the content), as (file index, range within the file)
+    fn piece_spans(&self, range: &Range<u64>) -> Vec<(usize, Range<u64>)> {
+        // the last file that starts at or before `range.start`
+        let first = self
+            .offsets
+            .partition_point(|&offset| offset <= range.start)
+            - 1;
+
+        self.files
+            .iter()
+            .zip(&self.offsets)
+            .enumerate()
+            .skip(first)
+            .take_while(|(_, (_, &file_start))| file_start < range.end)
+            .filter_map(|(i, ((_, file_length), &file_start))| {
+                let start = range.start.saturating_sub(file_start);
+                let end = std::cmp::min(file_start + file_length, range.end) - file_start;
+                if start < end {
+                    Some((i, start..end))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl VerificationReport {
+    /// Check if all verified pieces match the content on disk.
+    pub fn is_intact(&self) -> bool {
+        self.failed_pieces.is_empty()
+    }
+}
+
+impl RangeVerification {
+    /// Check if none of the verified pieces failed.
+    ///
+    /// Note that this says nothing about the unverified bytes.
+    pub fn is_intact(&self) -> bool {
+        self.failed_pieces.is_empty()
+    }
+}
+
+impl ContentCheck {
+    /// Check if all files are present with the right length.
+    pub fn is_complete(&self) -> bool {
+        self.files
+            .iter()
+            .all(|status| *status == FileStatus::Present)
+    }
+
+    /// Get the estimated completion of the content.
+    ///
+    /// The completion is represented in percentage and returned as an integer.
+    /// It's calculated as `present_bytes / total_bytes * 100`.
+    pub fn get_progress(&self) -> u8 {
+        if self.total_bytes == 0 {
+            return 100;
+        }
+
+        (self.present_bytes * 100 / self.total_bytes) as u8
+    }
+}
+
+#[cfg(feature = "fs")]
+impl TorrentVerification {
+    /// Get the current progress of the verification.
+    ///
+    /// The progress is represented in percentage and returned as an integer.
+    /// It's calculated as `number_of_pieces_processed / number_of_pieces_total * 100`.
+    ///
+    /// Similar to [`TorrentBuild::get_progress()`], `get_progress()` returning `100`
+    /// does not mean that [`get_output()`] won't block. Use [`is_finished()`] instead.
+    ///
+    /// [`TorrentBuild::get_progress()`]: struct.TorrentBuild.html#method.get_progress
+    /// [`get_output()`]: #method.get_output
+    /// [`is_finished()`]: #method.is_finished
+    pub fn get_progress(&self) -> u8 {
+        let n_piece_total = self.n_piece_total.load(Ordering::Acquire);
+
+        // in case get_progress() is called before n_piece_total is initialized
+        if n_piece_total == 0 {
+            return 0;
+        }
+
+        let n_piece_processed = self.n_piece_processed.load(Ordering::Acquire);
+
+        (n_piece_processed * 100 / n_piece_total) as u8
+    }
+
+    /// Get the number of pieces that have been verified so far.
+    pub fn get_n_piece_processed(&self) -> u64 {
+        self.n_piece_processed.load(Ordering::Acquire)
+    }
+
+    /// Get the total number of pieces to be verified.
+    ///
+    /// Note that 0 will be returned if the actual value has not been calculated yet.
+    pub fn get_n_piece_total(&self) -> u64 {
+        self.n_piece_total.load(Ordering::Acquire)
+    }
+
+    /// Cancel the verification.
+    ///
+    /// Calling [`get_output()`] after `cancel()` will most likely give you an
+    /// [`Err(LavaTorrentError::TorrentBuilderFailure)`].
+    ///
+    /// [`get_output()`]: #method.get_output
+    /// [`Err(LavaTorrentError::TorrentBuilderFailure)`]: ../../enum.LavaTorrentError.html#variant.TorrentBuilderFailure
+    pub fn cancel(&self) {
+        self.is_canceled.store(true, Ordering::Release)
+    }
+
+    /// Retrieve the output of the verification, i.e. the [`VerificationReport`].
+    ///
+    /// This function will block if the verification has not finished yet. You can
+    /// use [`is_finished()`] to check if the verification has finished.
+    ///
+    /// [`VerificationReport`]: struct.VerificationReport.html
+    /// [`is_finished()`]: #method.is_finished
+    pub fn get_output(mut self) -> Result<VerificationReport, LavaTorrentError> {
+        self.verifier_thread.take().unwrap().join().map_err(|e| {
+            LavaTorrentError::TorrentBuilderFailure(Cow::Owned(format!(
+                "verifier thread has unexpectedly panicked: {:?}",
+                e
+            )))
+        })?
+    }
+
+    /// Check if the verification has finished.
+    pub fn is_finished(&self) -> bool {
+        self.verifier_thread.as_ref().unwrap().is_finished()
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Drop for TorrentVerification {
+    // same as `TorrentBuild`
+    fn drop(&mut self) {
+        self.cancel();
+        if let Some(verifier_thread) = self.verifier_thread.take() {
+            let _ = verifier_thread.join();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod torrent_verification_tests {
+    use super::*;
+
+    #[test]
+    fn verify_single_file_ok() {
+        let torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        assert!(torrent
+            .verify("tests/files/byte_sequence")
+            .unwrap()
+            .is_intact());
+    }
+
+    #[test]
+    fn verify_multi_file_ok() {
+        let torrent = TorrentBuilder::new("tests/files", 1024).build().unwrap();
+        assert!(torrent.verify("tests/files").unwrap().is_intact());
+    }
+
+    #[test]
+    fn verify_piece_mismatch() {
+        let mut torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        torrent.info.fields_mut().pieces[3][0] ^= 1;
+
+        assert!(!torrent
+            .verify("tests/files/byte_sequence")
+            .unwrap()
+            .is_intact());
+    }
+
+    #[test]
+    fn verify_report() {
+        let mut torrent = TorrentBuilder::new("tests/files", 1024).build().unwrap();
+        torrent.info.fields_mut().pieces[0][0] ^= 1;
+
+        // byte_sequence (256 bytes) is followed by symlink (62300 bytes)
+        let report = torrent.verify("tests/files").unwrap();
+        let length = util::i64_to_u64(torrent.info.length).unwrap();
+        assert_eq!(
+            report.failed_pieces,
+            vec![FailedPiece {
+                index: 0,
+                range: 0..1024,
+                files: vec![(0, 0..256), (1, 0..768)],
+            }]
+        );
+        assert_eq!(report.failed_bytes, 1024);
+        assert_eq!(report.verified_bytes, length - 1024);
+        assert!(!report.is_intact());
+    }
+
+    #[test]
+    fn verify_report_last_piece() {
+        let mut torrent = TorrentBuilder::new("tests/files", 1024).build().unwrap();
+        let last_piece = torrent.info.pieces.len() - 1;
+        let last_file = torrent.info.files.as_ref().unwrap().len() - 1;
+        torrent.info.fields_mut().pieces[last_piece][0] ^= 1;
+
+        // the content is 218872 bytes long, so the last piece is only 760 bytes
+        let report = torrent.verify("tests/files").unwrap();
+        assert_eq!(
+            report.failed_pieces,
+            vec![FailedPiece {
+                index: last_piece,
+                range: 218112..218872,
+                files: vec![(last_file, 61540..62300)],
+            }]
+        );
+        assert_eq!(report.verified_pieces, (0..last_piece).collect::<Vec<_>>());
+        assert_eq!(report.failed_bytes, 760);
+        assert_eq!(report.verified_bytes, 218112);
+    }
+
+    #[test]
+    fn verify_missing_file() {
+        let mut torrent = TorrentBuilder::new("tests/files", 1024).build().unwrap();
+        torrent.info.fields_mut().files.as_mut().unwrap()[0].path = PathBuf::from("missing");
+
+        assert!(!torrent.verify("tests/files").unwrap().is_intact());
+    }
+
+    #[test]
+    fn verify_truncated_file() {
+        let mut torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        // pretend that the file should have been longer
+        torrent.info.fields_mut().length += 64;
+        torrent.info.fields_mut().pieces.push([0; 20]);
+
+        assert!(!torrent
+            .verify("tests/files/byte_sequence")
+            .unwrap()
+            .is_intact());
+    }
+
+    #[test]
+    fn verify_n_pieces_mismatch() {
+        let mut torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        torrent.info.fields_mut().pieces.truncate(3);
+
+        match torrent.verify("tests/files/byte_sequence") {
+            Err(LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, "Torrent has 3 pieces but its length requires 4.")
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn verify_files_ok() {
+        let torrent = TorrentBuilder::new("tests/files", 1024).build().unwrap();
+        let n_files = torrent.info.files.as_ref().unwrap().len();
+
+        assert!(torrent
+            .verify_files("tests/files", &(0..n_files).collect::<Vec<_>>())
+            .unwrap()
+            .is_intact());
+    }
+
+    #[test]
+    fn verify_files_only_checks_overlapping_pieces() {
+        let mut torrent = TorrentBuilder::new("tests/files", 1024).build().unwrap();
+        let last_file = torrent.info.files.as_ref().unwrap().len() - 1;
+        let last_piece = torrent.info.pieces.len() - 1;
+        torrent.info.fields_mut().pieces[last_piece][0] ^= 1;
+
+        assert!(torrent
+            .verify_files("tests/files", &[0])
+            .unwrap()
+            .is_intact());
+        assert!(!torrent
+            .verify_files("tests/files", &[last_file])
+            .unwrap()
+            .is_intact());
+        assert!(!torrent
+            .verify_files("tests/files", &[0, last_file])
+            .unwrap()
+            .is_intact());
+    }
+
+    #[test]
+    fn verify_files_ignores_other_missing_files() {
+        let mut torrent = TorrentBuilder::new("tests/files", 1024).build().unwrap();
+        let last_file = torrent.info.files.as_ref().unwrap().len() - 1;
+        torrent.info.fields_mut().files.as_mut().unwrap()[0].path = PathBuf::from("missing");
+
+        assert!(torrent
+            .verify_files("tests/files", &[last_file])
+            .unwrap()
+            .is_intact());
+        assert!(!torrent
+            .verify_files("tests/files", &[0])
+            .unwrap()
+            .is_intact());
+    }
+
+    #[test]
+    fn verify_files_single_file() {
+        let torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        assert!(torrent
+            .verify_files("tests/files/byte_sequence", &[0])
+            .unwrap()
+            .is_intact());
+    }
+
+    #[test]
+    fn verify_files_index_out_of_bounds() {
+        let torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+
+        match torrent.verify_files("tests/files/byte_sequence", &[1]) {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, "file index [1] is out of bounds.")
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn verify_files_non_blocking_ok() {
+        let torrent = TorrentBuilder::new("tests/files", 1024).build().unwrap();
+        let verification = torrent
+            .verify_files_non_blocking("tests/files", &[1])
+            .unwrap();
+
+        assert!(verification.get_output().unwrap().is_intact());
+    }
+
+    #[test]
+    fn verify_piece_ok() {
+        let data = std::fs::read("tests/files/byte_sequence").unwrap();
+        let torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+
+        for (index, piece) in data.chunks(64).enumerate() {
+            assert!(torrent.verify_piece(index, piece));
+        }
+        assert!(!torrent.verify_piece(0, &data[64..128]));
+        assert!(!torrent.verify_piece(0, &data[..63]));
+        assert!(!torrent.verify_piece(4, &[]));
+    }
+
+    #[test]
+    fn verify_piece_last_piece() {
+        let data = std::fs::read("tests/files/byte_sequence").unwrap();
+        let mut torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        // 256 bytes with pieces of 96 bytes: 96 + 96 + 64
+        let info = torrent.info.fields_mut();
+        info.piece_length = 96;
+        info.pieces = Pieces::new();
+        for piece in data.chunks(96) {
+            info.pieces.push(Sha1::digest(piece).into());
+        }
+
+        assert!(torrent.verify_piece(2, &data[192..]));
+        // the last piece is not padded
+        let mut padded = data[192..].to_vec();
+        padded.resize(96, 0);
+        assert!(!torrent.verify_piece(2, &padded));
+        assert!(!torrent.verify_piece(3, &[]));
+    }
+
+    #[test]
+    fn verify_range_ok() {
+        let data = std::fs::read("tests/files/byte_sequence").unwrap();
+        let torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+
+        let verification = torrent.verify_range(0, &data).unwrap();
+        assert_eq!(verification.verified_pieces, vec![0, 1, 2, 3]);
+        assert_eq!(verification.unverified_prefix, 0..0);
+        assert_eq!(verification.unverified_suffix, 256..256);
+        assert!(verification.is_intact());
+
+        let verification = torrent.verify_range(10, &data[10..200]).unwrap();
+        assert_eq!(
+            verification,
+            RangeVerification {
+                verified_pieces: vec![1, 2],
+                failed_pieces: vec![],
+                unverified_prefix: 10..64,
+                unverified_suffix: 192..200,
+            }
+        );
+    }
+
+    #[test]
+    fn verify_range_no_full_piece() {
+        let data = std::fs::read("tests/files/byte_sequence").unwrap();
+        let torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+
+        let verification = torrent.verify_range(70, &data[70..100]).unwrap();
+        assert_eq!(
+            verification,
+            RangeVerification {
+                verified_pieces: vec![],
+                failed_pieces: vec![],
+                unverified_prefix: 70..100,
+                unverified_suffix: 100..100,
+            }
+        );
+
+        let verification = torrent.verify_range(256, &[]).unwrap();
+        assert_eq!(verification.unverified_prefix, 256..256);
+    }
+
+    #[test]
+    fn verify_range_last_piece() {
+        let mut torrent = TorrentBuilder::new("tests/files", 1024).build().unwrap();
+        let last_piece = torrent.info.pieces.len() - 1;
+        torrent.info.fields_mut().pieces[last_piece - 1][0] ^= 1;
+
+        // the content is 218872 bytes long, so the last piece is only 760 bytes
+        let mut data = vec![0; 2000];
+        data[1240..].copy_from_slice(&std::fs::read("tests/files/symlink").unwrap()[61540..]);
+        let verification = torrent.verify_range(216872, &data).unwrap();
+        assert_eq!(
+            verification,
+            RangeVerification {
+                verified_pieces: vec![last_piece],
+                failed_pieces: vec![last_piece - 1],
+                unverified_prefix: 216872..217088,
+                unverified_suffix: 218872..218872,
+            }
+        );
+        assert!(!verification.is_intact());
+    }
+
+    #[test]
+    fn verify_range_out_of_bounds() {
+        let torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+
+        match torrent.verify_range(250, &[0; 7]) {
+            Err(LavaTorrentError::InvalidArgument(m)) => assert_eq!(
+                m,
+                "range starting at 250 with 7 bytes is out of bounds (256 bytes of content)."
+            ),
+            _ => panic!(),
+        }
+        assert!(torrent.verify_range(u64::MAX, &[0; 7]).is_err());
+    }
+
+    #[test]
+    fn verify_reader_ok() {
+        let torrent = TorrentBuilder::new("tests/files", 1024).build().unwrap();
+        let mut content = Vec::new();
+        for file in torrent.info.files.as_ref().unwrap() {
+            content.extend(std::fs::read(Path::new("tests/files").join(&file.path)).unwrap());
+        }
+        content.extend(b"trailing data");
+
+        let mut reader = &content[..];
+        let report = torrent.verify_reader(&mut reader).unwrap();
+        assert!(report.is_intact());
+        assert_eq!(report.verified_pieces.len(), torrent.info.pieces.len());
+        assert_eq!(report.verified_bytes, 218872);
+        assert_eq!(reader, b"trailing data");
+    }
+
+    #[test]
+    fn verify_reader_mismatch() {
+        let mut content = std::fs::read("tests/files/byte_sequence").unwrap();
+        let torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        content[100] ^= 1;
+
+        let report = torrent.verify_reader(&content[..]).unwrap();
+        assert_eq!(report.verified_pieces, vec![0, 2, 3]);
+        assert_eq!(
+            report.failed_pieces,
+            vec![FailedPiece {
+                index: 1,
+                range: 64..128,
+                files: vec![(0, 64..128)],
+            }]
+        );
+    }
+
+    #[test]
+    fn verify_reader_too_short() {
+        let content = std::fs::read("tests/files/byte_sequence").unwrap();
+        let torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+
+        let report = torrent.verify_reader(&content[..150]).unwrap();
+        assert_eq!(report.verified_pieces, vec![0, 1]);
+        assert_eq!(
+            report
+                .failed_pieces
+                .iter()
+                .map(|piece| piece.index)
+                .collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(report.failed_bytes, 128);
+    }
+
+    #[test]
+    fn verify_reader_io_error() {
+        struct FailingReader;
+
+        impl Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("broken pipe"))
+            }
+        }
+
+        let torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        match torrent.verify_reader(FailingReader) {
+            Err(LavaTorrentError::Io(e)) => assert_eq!(e.to_string(), "broken pipe"),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn check_content_complete() {
+        let torrent = TorrentBuilder::new("tests/files", 1024).build().unwrap();
+        let check = torrent.check_content("tests/files").unwrap();
+
+        assert_eq!(check.files, vec![FileStatus::Present; 4]);
+        assert_eq!(check.present_bytes, 218872);
+        assert_eq!(check.total_bytes, 218872);
+        assert_eq!(check.get_progress(), 100);
+        assert!(check.is_complete());
+    }
+
+    #[test]
+    fn check_content_incomplete() {
+        let mut torrent = TorrentBuilder::new("tests/files", 1024).build().unwrap();
+        let files = torrent.info.fields_mut().files.as_mut().unwrap();
+        files[0].path = PathBuf::from("missing"); // 256 bytes
+        files[1].length += 100; // 62300 bytes on disk
+        files[2].length -= 16; // 94016 bytes on disk
+
+        let check = torrent.check_content("tests/files").unwrap();
+        assert_eq!(
+            check.files,
+            vec![
+                FileStatus::Missing,
+                FileStatus::WrongLength { actual: 62300 },
+                FileStatus::WrongLength { actual: 94016 },
+                FileStatus::Present,
+            ]
+        );
+        assert_eq!(check.present_bytes, 62300 + 62300);
+        assert_eq!(check.total_bytes, 256 + 62400 + 94000 + 62300);
+        assert_eq!(check.get_progress(), 56);
+        assert!(!check.is_complete());
+    }
+
+    #[test]
+    fn check_content_single_file_missing() {
+        let torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        let check = torrent.check_content("tests/files/missing").unwrap();
+
+        assert_eq!(check.files, vec![FileStatus::Missing]);
+        assert_eq!(check.present_bytes, 0);
+        assert_eq!(check.get_progress(), 0);
+    }
+
+    #[test]
+    fn check_content_dir_instead_of_file() {
+        let torrent = TorrentBuilder::new("tests/files/byte_sequence", 64)
+            .build()
+            .unwrap();
+        let check = torrent.check_content("tests/files").unwrap();
+
+        assert_eq!(check.files, vec![FileStatus::Missing]);
+    }
+
+    #[test]
+    fn verify_non_blocking_ok() {
+        let torrent = TorrentBuilder::new("tests/files", 1024).build().unwrap();
+        let verification = torrent.verify_non_blocking("tests/files").unwrap();
+
+        assert!(verification.get_output().unwrap().is_intact());
+    }
+
+    #[test]
+    fn drop_verification_joins() {
+        let torrent = TorrentBuilder::new("tests/files", 1024).build().unwrap();
+        let verification = torrent.verify_non_blocking("tests/files").unwrap();
+        let n_piece_processed = verification.n_piece_processed.clone();
+
+        drop(verification);
+        assert_eq!(Arc::strong_count(&n_piece_processed), 1);
+    }
+}
@@ -1,7 +1,12 @@
 use super::*;
+#[cfg(feature = "fs")]
+use crate::bencode::write::write_file;
+use crate::bencode::write::{dictionary_len, integer_len, string_len, write_bytes};
 use crate::bencode::BencodeElem;
+#[cfg(feature = "fs")]
+use crate::bencode::WriteOptions;
 use crate::LavaTorrentError;
-use std::io::{BufWriter, Write};
+use std::io::Write;
 
 impl File {
     pub(crate) fn into_bencode_elem(self) -> BencodeElem {
@@ -22,7 +27,25 @@ impl File {
             result.extend(extra_fields);
         }
 
-        BencodeElem::Dictionary(result)
+        dictionary_elem(result, self.raw_extra_fields)
+    }
+
+    fn encoded_len(&self) -> usize {
+        let mut entries: HashMap<&[u8], usize> = HashMap::new();
+
+        entries.insert(b"length", integer_len(self.length));
+        entries.insert(
+            b"path",
+            2 + self
+                .path
+                .iter()
+                .map(|component| string_len(component.to_string_lossy().len()))
+                .sum::<usize>(),
+        );
+        extend_entries(&mut entries, &self.extra_fields);
+        extend_raw_entries(&mut entries, &self.raw_extra_fields);
+
+        dictionary_len(entries.into_iter().map(|(key, len)| (key.len(), len)))
     }
 }
 
@@ -32,8 +55,80 @@ impl Torrent {
     where
         W: Write,
     {
+        self.into_bencode_elem().write_into(dst)
+    }
+
+    // Like `write_into()`, but `pieces` is written by `write_pieces()` (which must
+    // write exactly `n_pieces` hashes) instead of coming from `self.pieces`.
+    // This allows writing pieces that are not held in memory.
+    #[cfg(feature = "fs")]
+    pub(super) fn write_into_with_pieces<W, F>(
+        self,
+        dst: &mut W,
+        n_pieces: usize,
+        write_pieces: F,
+    ) -> Result<(), LavaTorrentError>
+    where
+        W: Write,
+        F: FnOnce(&mut W) -> Result<(), LavaTorrentError>,
+    {
+        let mut dict = into_raw_dictionary(self.into_bencode_elem());
+        let mut info = into_raw_dictionary(dict.remove(b"info".as_ref()).unwrap());
+        info.remove(b"pieces".as_ref());
+
+        write_dictionary_with(&dict, b"info", dst, |dst| {
+            write_dictionary_with(&info, b"pieces", dst, |dst| {
+                let len = n_pieces * PIECE_STRING_LENGTH;
+                dst.write_all(format!("{}:", len).as_bytes())?;
+                write_pieces(dst)
+            })
+        })
+    }
+
+    // Like `write_into()`, but `info` is written as is
+    // instead of being encoded from `self`'s fields.
+    pub(super) fn write_into_with_info<W>(
+        &self,
+        info: &[u8],
+        dst: &mut W,
+    ) -> Result<(), LavaTorrentError>
+    where
+        W: Write,
+    {
+        let top_level = Torrent {
+            announce: self.announce.clone(),
+            announce_list: self.announce_list.clone(),
+            nodes: self.nodes.clone(),
+            url_list: self.url_list.clone(),
+            httpseeds: self.httpseeds.clone(),
+            info: InfoFields {
+                length: 0,
+                files: None,
+                name: String::new(),
+                piece_length: 0,
+                pieces: Pieces::new(),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: self.extra_fields.clone(),
+            raw_extra_fields: self.raw_extra_fields.clone(),
+        };
+        let mut dict = into_raw_dictionary(top_level.into_bencode_elem());
+        dict.remove(b"info".as_ref());
+
+        write_dictionary_with(&dict, b"info", dst, |dst| {
+            dst.write_all(info)?;
+            Ok(())
+        })
+    }
+
+    fn into_bencode_elem(self) -> BencodeElem {
         let mut result: HashMap<String, BencodeElem> = HashMap::new();
         let mut info: HashMap<String, BencodeElem> = HashMap::new();
+        let is_merkle = self.is_merkle();
+
+        let fields = self.info.into_mut();
 
         if let Some(announce) = self.announce {
             result.insert("announce".to_owned(), BencodeElem::String(announce));
@@ -56,7 +151,38 @@ impl Torrent {
             );
         }
 
-        if let Some(files) = self.files {
+        if let Some(nodes) = self.nodes {
+            result.insert(
+                "nodes".to_owned(),
+                BencodeElem::List(
+                    nodes
+                        .into_iter()
+                        .map(|(host, port)| {
+                            BencodeElem::List(vec![
+                                BencodeElem::String(host),
+                                BencodeElem::Integer(Integer::from(port)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            );
+        }
+
+        if let Some(url_list) = self.url_list {
+            result.insert(
+                "url-list".to_owned(),
+                BencodeElem::List(url_list.into_iter().map(BencodeElem::String).collect()),
+            );
+        }
+
+        if let Some(httpseeds) = self.httpseeds {
+            result.insert(
+                "httpseeds".to_owned(),
+                BencodeElem::List(httpseeds.into_iter().map(BencodeElem::String).collect()),
+            );
+        }
+
+        if let Some(files) = fields.files {
             info.insert(
                 "files".to_owned(),
                 BencodeElem::List(
@@ -67,30 +193,36 @@ impl Torrent {
                 ),
             );
         } else {
-            info.insert("length".to_owned(), BencodeElem::Integer(self.length));
+            info.insert("length".to_owned(), BencodeElem::Integer(fields.length));
         }
 
-        info.insert("name".to_owned(), BencodeElem::String(self.name));
+        info.insert("name".to_owned(), BencodeElem::String(fields.name));
         info.insert(
             "piece length".to_owned(),
-            BencodeElem::Integer(self.piece_length),
-        );
-        info.insert(
-            "pieces".to_owned(),
-            BencodeElem::Bytes(self.pieces.into_iter().flatten().collect()),
+            BencodeElem::Integer(fields.piece_length),
         );
+        // merkle torrents have `root hash` (in `extra_info_fields`) instead
+        if !is_merkle {
+            info.insert(
+                "pieces".to_owned(),
+                BencodeElem::Bytes(fields.pieces.into_bytes()),
+            );
+        }
 
-        if let Some(extra_info_fields) = self.extra_info_fields {
+        if let Some(extra_info_fields) = fields.extra_info_fields {
             info.extend(extra_info_fields);
         }
 
-        result.insert("info".to_owned(), BencodeElem::Dictionary(info));
+        result.insert(
+            "info".to_owned(),
+            dictionary_elem(info, fields.raw_extra_info_fields),
+        );
 
         if let Some(extra_fields) = self.extra_fields {
             result.extend(extra_fields);
         }
 
-        BencodeElem::Dictionary(result).write_into(dst)
+        dictionary_elem(result, self.raw_extra_fields)
     }
 
     /// Encode `self` as bencode and write the result to `path`.
@@ -103,22 +235,213 @@ impl Torrent {
     /// Note: it is the client's responsibility to ensure
     /// that all directories in `path` actually exist (e.g.
     /// by calling [`create_dir_all`](https://doc.rust-lang.org/std/fs/fn.create_dir_all.html)).
+    #[cfg(feature = "fs")]
     pub fn write_into_file<P>(self, path: P) -> Result<(), LavaTorrentError>
     where
         P: AsRef<Path>,
     {
-        let file = std::fs::File::create(&path)?;
-        self.write_into(&mut BufWriter::new(&file))?;
-        file.sync_all()?;
-        Ok(())
+        self.write_into_file_with_options(path, &WriteOptions::default())
+    }
+
+    /// Like [`write_into_file()`], but with custom options.
+    ///
+    /// Set `durable` in [`WriteOptions`] if the file must survive power loss
+    /// immediately after this method returns.
+    ///
+    /// [`write_into_file()`]: #method.write_into_file
+    /// [`WriteOptions`]: ../../bencode/struct.WriteOptions.html
+    #[cfg(feature = "fs")]
+    pub fn write_into_file_with_options<P>(
+        self,
+        path: P,
+        options: &WriteOptions,
+    ) -> Result<(), LavaTorrentError>
+    where
+        P: AsRef<Path>,
+    {
+        write_file(path.as_ref(), options, |dst| self.write_into(dst))
     }
 
     /// Encode `self` as bencode and return the result in a `Vec`.
     pub fn encode(self) -> Result<Vec<u8>, LavaTorrentError> {
-        let mut result = Vec::new();
+        let mut result = Vec::with_capacity(self.encoded_len());
         self.write_into(&mut result)?;
         Ok(result)
     }
+
+    /// Compute the length of the bencode encoding of `self`, without encoding it.
+    ///
+    /// This is equal to the length of the `Vec` returned by [`encode()`],
+    /// and is useful for preallocating buffers or checking the size
+    /// of a .torrent file against a limit (e.g. a tracker's) before writing it.
+    ///
+    /// [`encode()`]: #method.encode
+    pub fn encoded_len(&self) -> usize {
+        let mut entries: HashMap<&[u8], usize> = HashMap::new();
+        let mut info: HashMap<&[u8], usize> = HashMap::new();
+
+        if let Some(ref announce) = self.announce {
+            entries.insert(b"announce", string_len(announce.len()));
+        }
+
+        if let Some(ref list) = self.announce_list {
+            entries.insert(
+                b"announce-list",
+                2 + list
+                    .iter()
+                    .map(|tier| 2 + tier.iter().map(|url| string_len(url.len())).sum::<usize>())
+                    .sum::<usize>(),
+            );
+        }
+
+        if let Some(ref nodes) = self.nodes {
+            entries.insert(
+                b"nodes",
+                2 + nodes
+                    .iter()
+                    .map(|(host, port)| {
+                        2 + string_len(host.len()) + integer_len(Integer::from(*port))
+                    })
+                    .sum::<usize>(),
+            );
+        }
+
+        if let Some(ref url_list) = self.url_list {
+            entries.insert(
+                b"url-list",
+                2 + url_list
+                    .iter()
+                    .map(|url| string_len(url.len()))
+                    .sum::<usize>(),
+            );
+        }
+
+        if let Some(ref httpseeds) = self.httpseeds {
+            entries.insert(
+                b"httpseeds",
+                2 + httpseeds
+                    .iter()
+                    .map(|url| string_len(url.len()))
+                    .sum::<usize>(),
+            );
+        }
+
+        if let Some(ref files) = self.info.files {
+            info.insert(
+                b"files",
+                2 + files.iter().map(File::encoded_len).sum::<usize>(),
+            );
+        } else {
+            info.insert(b"length", integer_len(self.info.length));
+        }
+
+        info.insert(b"name", string_len(self.info.name.len()));
+        info.insert(b"piece length", integer_len(self.info.piece_length));
+        if !self.is_merkle() {
+            info.insert(b"pieces", string_len(self.info.pieces.as_bytes().len()));
+        }
+        extend_entries(&mut info, &self.info.extra_info_fields);
+        extend_raw_entries(&mut info, &self.info.raw_extra_info_fields);
+
+        entries.insert(
+            b"info",
+            dictionary_len(info.into_iter().map(|(key, len)| (key.len(), len))),
+        );
+        extend_entries(&mut entries, &self.extra_fields);
+        extend_raw_entries(&mut entries, &self.raw_extra_fields);
+
+        dictionary_len(entries.into_iter().map(|(key, len)| (key.len(), len)))
+    }
+}
+
+/// Convert a `Torrent` into its complete bencode dictionary,
+/// i.e. what [`Torrent::encode()`] encodes.
+///
+/// [`Torrent::encode()`]: struct.Torrent.html#method.encode
+impl From<Torrent> for BencodeElem {
+    fn from(torrent: Torrent) -> BencodeElem {
+        torrent.into_bencode_elem()
+    }
+}
+
+// Mirror `HashMap::extend()` in `into_bencode_elem()`: extra fields replace existing entries.
+// Like `write_dictionary()`, but with an extra entry `key` (which must not be in `dict`)
+// whose value is written by `write_value()`.
+fn write_dictionary_with<W, F>(
+    dict: &RawDictionary,
+    key: &[u8],
+    dst: &mut W,
+    write_value: F,
+) -> Result<(), LavaTorrentError>
+where
+    W: Write,
+    F: FnOnce(&mut W) -> Result<(), LavaTorrentError>,
+{
+    let mut sorted = dict
+        .keys()
+        .map(Vec::as_slice)
+        .chain(std::iter::once(key))
+        .collect::<Vec<&[u8]>>();
+    sorted.sort();
+
+    let mut write_value = Some(write_value);
+    dst.write_all(b"d")?;
+    for k in sorted {
+        write_bytes(k, dst)?;
+        match dict.get(k) {
+            Some(val) => val.write_into(dst)?,
+            None => (write_value.take().unwrap())(dst)?,
+        }
+    }
+    dst.write_all(b"e")?;
+    Ok(())
+}
+
+fn extend_entries<'a>(entries: &mut HashMap<&'a [u8], usize>, fields: &'a Option<Dictionary>) {
+    if let Some(fields) = fields {
+        entries.extend(
+            fields
+                .iter()
+                .map(|(key, value)| (key.as_bytes(), value.encoded_len())),
+        );
+    }
+}
+
+fn extend_raw_entries<'a>(
+    entries: &mut HashMap<&'a [u8], usize>,
+    fields: &'a Option<RawDictionary>,
+) {
+    if let Some(fields) = fields {
+        entries.extend(
+            fields
+                .iter()
+                .map(|(key, value)| (key.as_slice(), value.encoded_len())),
+        );
+    }
+}
+
+// Combine `dict` with the fields whose keys are not valid UTF-8 (if any).
+pub(super) fn dictionary_elem(dict: Dictionary, raw: Option<RawDictionary>) -> BencodeElem {
+    match raw {
+        Some(raw) => BencodeElem::RawDictionary(
+            dict.into_iter()
+                .map(|(key, value)| (key.into_bytes(), value))
+                .chain(raw)
+                .collect(),
+        ),
+        None => BencodeElem::Dictionary(dict),
+    }
+}
+
+fn into_raw_dictionary(elem: BencodeElem) -> RawDictionary {
+    match elem {
+        BencodeElem::Dictionary(dict) => dict
+            .into_iter()
+            .map(|(key, value)| (key.into_bytes(), value))
+            .collect(),
+        BencodeElem::RawDictionary(dict) => dict,
+        _ => unreachable!(),
+    }
 }
 
 #[cfg(test)]
@@ -132,6 +455,7 @@ mod file_write_tests {
             length: 42,
             path: PathBuf::from("dir1/dir2/file"),
             extra_fields: None,
+            raw_extra_fields: None,
         };
 
         assert_eq!(
@@ -148,6 +472,7 @@ mod file_write_tests {
             extra_fields: Some(HashMap::from_iter(
                 vec![("comment".to_owned(), bencode_elem!("no comment"))].into_iter(),
             )),
+            raw_extra_fields: None,
         };
 
         assert_eq!(
@@ -173,13 +498,21 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
         let mut result = Vec::new();
 
@@ -192,13 +525,49 @@ mod torrent_write_tests {
                     ("length", 4),
                     ("name", "sample"),
                     ("piece length", 2),
-                    ("pieces", (1, 2, 3, 4)),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
                 })
             })
             .encode()
         );
     }
 
+    #[test]
+    fn into_bencode_elem_ok() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            BencodeElem::from(torrent),
+            bencode_elem!({
+                ("announce", "url"),
+                ("info", {
+                    ("length", 4),
+                    ("name", "sample"),
+                    ("piece length", 2),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
+                })
+            })
+        );
+    }
+
     #[test]
     fn write_with_announce_list() {
         let torrent = Torrent {
@@ -207,13 +576,21 @@ mod torrent_write_tests {
                 vec!["url1".to_owned(), "url2".to_owned()],
                 vec!["url3".to_owned(), "url4".to_owned()],
             ]),
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
         let mut result = Vec::new();
 
@@ -227,7 +604,127 @@ mod torrent_write_tests {
                     ("length", 4),
                     ("name", "sample"),
                     ("piece length", 2),
-                    ("pieces", (1, 2, 3, 4)),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
+                })
+            })
+            .encode()
+        );
+    }
+
+    #[test]
+    fn write_with_nodes() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: Some(vec![
+                ("127.0.0.1".to_owned(), 6881),
+                ("router.example.org".to_owned(), 6882),
+            ]),
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+        let mut result = Vec::new();
+
+        torrent.write_into(&mut result).unwrap();
+        assert_eq!(
+            result,
+            bencode_elem!({
+                ("nodes", [["127.0.0.1", 6881_i64], ["router.example.org", 6882_i64]]),
+                ("info", {
+                    ("length", 4),
+                    ("name", "sample"),
+                    ("piece length", 2),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
+                })
+            })
+            .encode()
+        );
+    }
+
+    #[test]
+    fn write_with_url_list() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: Some(vec!["https://example.org/".to_owned()]),
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+        let mut result = Vec::new();
+
+        torrent.write_into(&mut result).unwrap();
+        assert_eq!(
+            result,
+            bencode_elem!({
+                ("url-list", ["https://example.org/"]),
+                ("info", {
+                    ("length", 4),
+                    ("name", "sample"),
+                    ("piece length", 2),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
+                })
+            })
+            .encode()
+        );
+    }
+
+    #[test]
+    fn write_with_httpseeds() {
+        let torrent = Torrent {
+            announce: None,
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: Some(vec!["https://example.org/".to_owned()]),
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: None,
+            raw_extra_fields: None,
+        };
+        let mut result = Vec::new();
+
+        torrent.write_into(&mut result).unwrap();
+        assert_eq!(
+            result,
+            bencode_elem!({
+                ("httpseeds", ["https://example.org/"]),
+                ("info", {
+                    ("length", 4),
+                    ("name", "sample"),
+                    ("piece length", 2),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
                 })
             })
             .encode()
@@ -239,11 +736,19 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: Some(HashMap::from_iter(
                 vec![
                     ("comment2".to_owned(), bencode_elem!("no comment")),
@@ -251,7 +756,7 @@ mod torrent_write_tests {
                 ]
                 .into_iter(),
             )),
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
         let mut result = Vec::new();
 
@@ -266,7 +771,7 @@ mod torrent_write_tests {
                     ("length", 4),
                     ("name", "sample"),
                     ("piece length", 2),
-                    ("pieces", (1, 2, 3, 4)),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
                 })
             })
             .encode()
@@ -278,19 +783,27 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from_iter(
+                    vec![
+                        ("comment2".to_owned(), bencode_elem!("no comment")),
+                        ("comment1".to_owned(), bencode_elem!("no comment")),
+                    ]
+                    .into_iter(),
+                )),
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![
-                    ("comment2".to_owned(), bencode_elem!("no comment")),
-                    ("comment1".to_owned(), bencode_elem!("no comment")),
-                ]
-                .into_iter(),
-            )),
+            raw_extra_fields: None,
         };
         let mut result = Vec::new();
 
@@ -305,7 +818,7 @@ mod torrent_write_tests {
                     ("length", 4),
                     ("name", "sample"),
                     ("piece length", 2),
-                    ("pieces", (1, 2, 3, 4)),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
                 })
             })
             .encode()
@@ -317,24 +830,34 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: Some(vec![
-                File {
-                    length: 2,
-                    path: PathBuf::from("dir1/dir2/file1"),
-                    extra_fields: None,
-                },
-                File {
-                    length: 2,
-                    path: PathBuf::from("dir1/dir2/file2"),
-                    extra_fields: None,
-                },
-            ]),
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: Some(vec![
+                    File {
+                        length: 2,
+                        path: PathBuf::from("dir1/dir2/file1"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                    File {
+                        length: 2,
+                        path: PathBuf::from("dir1/dir2/file2"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                ]),
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
         let mut result = Vec::new();
 
@@ -350,7 +873,7 @@ mod torrent_write_tests {
                     ]),
                     ("name", "sample"),
                     ("piece length", 2),
-                    ("pieces", (1, 2, 3, 4)),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
                 })
             })
             .encode()
@@ -362,13 +885,21 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
         assert_eq!(
@@ -379,7 +910,7 @@ mod torrent_write_tests {
                     ("length", 4),
                     ("name", "sample"),
                     ("piece length", 2),
-                    ("pieces", (1, 2, 3, 4)),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
                 })
             })
             .encode()
@@ -394,13 +925,21 @@ mod torrent_write_tests {
                 vec!["url1".to_owned(), "url2".to_owned()],
                 vec!["url3".to_owned(), "url4".to_owned()],
             ]),
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
         assert_eq!(
@@ -412,7 +951,7 @@ mod torrent_write_tests {
                     ("length", 4),
                     ("name", "sample"),
                     ("piece length", 2),
-                    ("pieces", (1, 2, 3, 4)),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
                 })
             })
             .encode()
@@ -424,11 +963,19 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: Some(HashMap::from_iter(
                 vec![
                     ("comment2".to_owned(), bencode_elem!("no comment")),
@@ -436,7 +983,7 @@ mod torrent_write_tests {
                 ]
                 .into_iter(),
             )),
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
         assert_eq!(
@@ -449,7 +996,7 @@ mod torrent_write_tests {
                     ("length", 4),
                     ("name", "sample"),
                     ("piece length", 2),
-                    ("pieces", (1, 2, 3, 4)),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
                 })
             })
             .encode()
@@ -461,19 +1008,27 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: None,
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from_iter(
+                    vec![
+                        ("comment2".to_owned(), bencode_elem!("no comment")),
+                        ("comment1".to_owned(), bencode_elem!("no comment")),
+                    ]
+                    .into_iter(),
+                )),
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: Some(HashMap::from_iter(
-                vec![
-                    ("comment2".to_owned(), bencode_elem!("no comment")),
-                    ("comment1".to_owned(), bencode_elem!("no comment")),
-                ]
-                .into_iter(),
-            )),
+            raw_extra_fields: None,
         };
 
         assert_eq!(
@@ -486,7 +1041,7 @@ mod torrent_write_tests {
                     ("length", 4),
                     ("name", "sample"),
                     ("piece length", 2),
-                    ("pieces", (1, 2, 3, 4)),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
                 })
             })
             .encode()
@@ -498,24 +1053,34 @@ mod torrent_write_tests {
         let torrent = Torrent {
             announce: Some("url".to_owned()),
             announce_list: None,
-            length: 4,
-            files: Some(vec![
-                File {
-                    length: 2,
-                    path: PathBuf::from("dir1/dir2/file1"),
-                    extra_fields: None,
-                },
-                File {
-                    length: 2,
-                    path: PathBuf::from("dir1/dir2/file2"),
-                    extra_fields: None,
-                },
-            ]),
-            name: "sample".to_owned(),
-            piece_length: 2,
-            pieces: vec![vec![1, 2], vec![3, 4]],
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: Some(vec![
+                    File {
+                        length: 2,
+                        path: PathBuf::from("dir1/dir2/file1"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                    File {
+                        length: 2,
+                        path: PathBuf::from("dir1/dir2/file2"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                ]),
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: None,
+                raw_extra_info_fields: None,
+            }
+            .into(),
             extra_fields: None,
-            extra_info_fields: None,
+            raw_extra_fields: None,
         };
 
         assert_eq!(
@@ -529,10 +1094,97 @@ mod torrent_write_tests {
                     ]),
                     ("name", "sample"),
                     ("piece length", 2),
-                    ("pieces", (1, 2, 3, 4)),
+                    ("pieces", ([[1; 20], [2; 20]].concat())),
                 })
             })
             .encode()
         );
     }
+
+    #[test]
+    fn encoded_len_ok() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: Some(vec![
+                vec!["url1".to_owned(), "url2".to_owned()],
+                vec!["url3".to_owned()],
+            ]),
+            nodes: Some(vec![("127.0.0.1".to_owned(), 6881)]),
+            url_list: Some(vec!["https://example.org/".to_owned()]),
+            httpseeds: Some(vec!["https://example.com/".to_owned()]),
+            info: InfoFields {
+                length: 4,
+                files: Some(vec![
+                    File {
+                        length: 2,
+                        path: PathBuf::from("dir1/dir2/file1"),
+                        extra_fields: Some(HashMap::from_iter(vec![(
+                            "md5sum".to_owned(),
+                            bencode_elem!("abcdef"),
+                        )])),
+                        raw_extra_fields: None,
+                    },
+                    File {
+                        length: 2,
+                        path: PathBuf::from("dir1/dir2/file2"),
+                        extra_fields: None,
+                        raw_extra_fields: None,
+                    },
+                ]),
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from_iter(vec![(
+                    "private".to_owned(),
+                    bencode_elem!(1_i64),
+                )])),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: Some(HashMap::from_iter(vec![(
+                "comment".to_owned(),
+                bencode_elem!("no comment"),
+            )])),
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            torrent.encoded_len(),
+            torrent.clone().encode().unwrap().len()
+        );
+    }
+
+    #[test]
+    fn encoded_len_with_overridden_fields() {
+        let torrent = Torrent {
+            announce: Some("url".to_owned()),
+            announce_list: None,
+            nodes: None,
+            url_list: None,
+            httpseeds: None,
+            info: InfoFields {
+                length: 4,
+                files: None,
+                name: "sample".to_owned(),
+                piece_length: 2,
+                pieces: Pieces::from(vec![[1; 20], [2; 20]]),
+                extra_info_fields: Some(HashMap::from_iter(vec![(
+                    "name".to_owned(),
+                    bencode_elem!(["not", "a", "string"]),
+                )])),
+                raw_extra_info_fields: None,
+            }
+            .into(),
+            extra_fields: Some(HashMap::from_iter(vec![(
+                "announce".to_owned(),
+                bencode_elem!("a much longer url"),
+            )])),
+            raw_extra_fields: None,
+        };
+
+        assert_eq!(
+            torrent.encoded_len(),
+            torrent.clone().encode().unwrap().len()
+        );
+    }
 }
@@ -0,0 +1,244 @@
+use super::*;
+use crate::http;
+use crate::torrent::v1::Torrent;
+use percent_encoding::{percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::fmt::Write;
+
+// Escape everything except unreserved characters (RFC 3986).
+pub(super) const QUERY_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+// Tracker responses are small, this is only a safeguard.
+pub(super) const MAX_RESPONSE_LEN: usize = 16 * 1024 * 1024;
+
+/// Parameters of an HTTP announce, as defined in
+/// [BEP 3](http://bittorrent.org/beps/bep_0003.html) and
+/// [BEP 23](http://www.bittorrent.org/beps/bep_0023.html).
+///
+/// Requires the `http` feature.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AnnounceRequest {
+    /// SHA1 hash of the torrent's `info`.
+    pub info_hash: [u8; 20],
+    /// ID of the client.
+    pub peer_id: [u8; 20],
+    /// Port the client is listening on.
+    pub port: u16,
+    /// Total number of bytes uploaded.
+    pub uploaded: u64,
+    /// Total number of bytes downloaded.
+    pub downloaded: u64,
+    /// Number of bytes the client still has to download.
+    pub left: u64,
+    /// `None` for regular announces.
+    pub event: Option<AnnounceEvent>,
+    /// Whether to ask for compact peer info
+    /// ([BEP 23](http://www.bittorrent.org/beps/bep_0023.html)).
+    pub compact: bool,
+    /// Number of peers the client would like to receive.
+    pub numwant: Option<u32>,
+    /// `tracker id` returned by a previous announce.
+    pub tracker_id: Option<String>,
+}
+
+impl AnnounceRequest {
+    /// Create an `AnnounceRequest` for a client that is about to start
+    /// downloading `torrent` (i.e. `left` is the torrent's length and
+    /// `event` is `Started`).
+    pub fn new(torrent: &Torrent, peer_id: [u8; 20], port: u16) -> AnnounceRequest {
+        let mut info_hash = [0; 20];
+        info_hash.copy_from_slice(&torrent.info_hash_bytes());
+
+        AnnounceRequest {
+            info_hash,
+            peer_id,
+            port,
+            uploaded: 0,
+            downloaded: 0,
+            left: u64::try_from(torrent.info.length).unwrap_or(0),
+            event: Some(AnnounceEvent::Started),
+            compact: true,
+            numwant: None,
+            tracker_id: None,
+        }
+    }
+
+    /// Append the request's parameters to the query string of `announce`,
+    /// for users who prefer to send the request with their own HTTP client.
+    pub fn url(&self, announce: &str) -> String {
+        let mut url = announce.to_owned();
+        url.push(if announce.contains('?') { '&' } else { '?' });
+
+        write!(
+            url,
+            "info_hash={}&peer_id={}&port={}&uploaded={}&downloaded={}&left={}&compact={}",
+            percent_encode(&self.info_hash, QUERY_COMPONENT),
+            percent_encode(&self.peer_id, QUERY_COMPONENT),
+            self.port,
+            self.uploaded,
+            self.downloaded,
+            self.left,
+            u8::from(self.compact),
+        )
+        .expect("Writing to a String should never fail.");
+        if let Some(event) = self.event {
+            write!(url, "&event={}", event.as_str())
+                .expect("Writing to a String should never fail.");
+        }
+        if let Some(numwant) = self.numwant {
+            write!(url, "&numwant={}", numwant).expect("Writing to a String should never fail.");
+        }
+        if let Some(ref tracker_id) = self.tracker_id {
+            write!(
+                url,
+                "&trackerid={}",
+                percent_encode(tracker_id.as_bytes(), QUERY_COMPONENT)
+            )
+            .expect("Writing to a String should never fail.");
+        }
+
+        url
+    }
+}
+
+/// Send `request` to the tracker at `announce` and parse its response.
+/// Requires the `http` feature.
+///
+/// Both `http://` and `https://` trackers are supported. HTTPS trackers are
+/// authenticated with the Mozilla root certificates. To use an HTTP client
+/// of your choice instead, see [`AnnounceRequest::url()`] and parse the
+/// response with [`TrackerResponse::from_bytes()`].
+///
+/// # Example
+///
+/// ```no_run
+/// use lava_torrent::torrent::v1::Torrent;
+/// use lava_torrent::tracker::{self, AnnounceRequest};
+///
+/// let torrent = Torrent::read_from_file("sample.torrent").unwrap();
+/// let request = AnnounceRequest::new(&torrent, *b"-LT0000-123456789012", 6881);
+/// let response = tracker::announce(torrent.announce.as_ref().unwrap(), &request).unwrap();
+/// println!("{}", response);
+/// ```
+///
+/// [`AnnounceRequest::url()`]: struct.AnnounceRequest.html#method.url
+/// [`TrackerResponse::from_bytes()`]: enum.TrackerResponse.html#method.from_bytes
+pub fn announce(
+    announce: &str,
+    request: &AnnounceRequest,
+) -> Result<TrackerResponse, LavaTorrentError> {
+    TrackerResponse::from_bytes(get_ok(&request.url(announce))?)
+}
+
+// Send a GET request and return the body if the status is 200.
+pub(super) fn get_ok(url: &str) -> Result<Vec<u8>, LavaTorrentError> {
+    let response = http::get(url, MAX_RESPONSE_LEN)?;
+    if response.status == 200 {
+        Ok(response.body)
+    } else {
+        Err(LavaTorrentError::MalformedResponse(Cow::Owned(format!(
+            "Tracker returned HTTP status {}.",
+            response.status
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod announce_tests {
+    use super::*;
+    use crate::http::http_tests::{serve, serve_tls};
+
+    fn request() -> AnnounceRequest {
+        AnnounceRequest {
+            info_hash: [0xff; 20],
+            peer_id: *b"-LT0000-abc.~_ 12345",
+            port: 6881,
+            uploaded: 1,
+            downloaded: 2,
+            left: 3,
+            event: Some(AnnounceEvent::Started),
+            compact: true,
+            numwant: Some(50),
+            tracker_id: Some("a&b".to_owned()),
+        }
+    }
+
+    #[test]
+    fn url_ok() {
+        assert_eq!(
+            request().url("http://example.com/announce"),
+            "http://example.com/announce?info_hash=".to_owned()
+                + &"%FF".repeat(20)
+                + "&peer_id=-LT0000-abc.~_%2012345&port=6881&uploaded=1&downloaded=2&left=3\
+                   &compact=1&event=started&numwant=50&trackerid=a%26b"
+        );
+    }
+
+    #[test]
+    fn url_with_query() {
+        let mut request = request();
+        request.event = None;
+        request.numwant = None;
+        request.tracker_id = None;
+        request.compact = false;
+
+        assert_eq!(
+            request.url("http://example.com/announce?passkey=1"),
+            "http://example.com/announce?passkey=1&info_hash=".to_owned()
+                + &"%FF".repeat(20)
+                + "&peer_id=-LT0000-abc.~_%2012345&port=6881&uploaded=1&downloaded=2&left=3\
+                   &compact=0"
+        );
+    }
+
+    #[test]
+    fn announce_ok() {
+        let body = b"d8:intervali1800e5:peers6:\x7f\x00\x00\x01\x1a\xe1e";
+        let mut response =
+            format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len()).into_bytes();
+        response.extend_from_slice(body);
+        let (url, handle) = serve(response);
+
+        match announce(&format!("{}/announce", url), &request()).unwrap() {
+            TrackerResponse::Success {
+                interval, peers, ..
+            } => {
+                assert_eq!(interval, 1800);
+                assert_eq!(peers.len(), 1);
+                assert_eq!(peers[0].addr, "127.0.0.1:6881".parse().unwrap());
+            }
+            _ => panic!(),
+        }
+        assert!(handle
+            .join()
+            .unwrap()
+            .starts_with("GET /announce?info_hash=%FF"));
+    }
+
+    #[test]
+    fn announce_http_error() {
+        let (url, _) = serve(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_vec());
+
+        match announce(&format!("{}/announce", url), &request()) {
+            Err(LavaTorrentError::MalformedResponse(m)) => {
+                assert_eq!(m, "Tracker returned HTTP status 404.")
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn announce_https() {
+        let (url, _, _) = serve_tls(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".to_vec());
+
+        // the request is sent over TLS, and fails since the
+        // test server's certificate is not trusted
+        match announce(&format!("{}/announce", url), &request()) {
+            Err(LavaTorrentError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::InvalidData),
+            _ => panic!(),
+        }
+    }
+}
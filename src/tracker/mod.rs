@@ -1,12 +1,22 @@
 //! Module containing structs for tracker responses.
 //!
-//! At the moment, `lava_torrent` does not handle communication
+//! By default, `lava_torrent` does not handle communication
 //! with trackers. Users will have to send requests themselves and
 //! pass the received responses to `lava_torrent` for parsing.
+//! [`scrape_url()`] can be used to find out where to send scrape requests.
+//!
+//! With the `http` feature, [`announce()`] and [`scrape()`] can be used to
+//! communicate with `http://` trackers directly. UDP trackers are handled
+//! in [`udp`].
+//!
+//! [`scrape_url()`]: fn.scrape_url.html
+//! [`announce()`]: fn.announce.html
+//! [`scrape()`]: fn.scrape.html
+//! [`udp`]: udp/index.html
 
 use crate::bencode::BencodeElem;
 use crate::torrent::v1::{Dictionary, Integer};
-use crate::LavaTorrentError;
+use crate::{LavaTorrentError, TorrentErrorKind};
 use itertools::Itertools;
 use std::borrow::Cow;
 use std::collections::HashMap;
@@ -14,6 +24,18 @@ use std::convert::TryInto;
 use std::fmt;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
+pub mod udp;
+
+#[cfg(feature = "http")]
+mod announce;
+#[cfg(feature = "http")]
+mod scrape;
+
+#[cfg(feature = "http")]
+pub use self::announce::{announce, AnnounceRequest};
+#[cfg(feature = "http")]
+pub use self::scrape::{scrape, scrape_request_url};
+
 /// Peer information returned in a tracker response.
 ///
 /// Modeled after the specifications in
@@ -66,6 +88,18 @@ pub enum TrackerResponse {
     },
 }
 
+/// The `event` of an announce, as defined in
+/// [BEP 3](http://bittorrent.org/beps/bep_0003.html).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AnnounceEvent {
+    /// The download has just started.
+    Started,
+    /// The download has completed.
+    Completed,
+    /// The client is shutting down gracefully.
+    Stopped,
+}
+
 /// Swarm metadata returned in a tracker scrape response.
 ///
 /// Modeled after the specifications in
@@ -97,6 +131,17 @@ pub struct TrackerScrapeResponse {
     pub extra_fields: Option<Dictionary>,
 }
 
+impl AnnounceEvent {
+    #[cfg(feature = "http")]
+    fn as_str(self) -> &'static str {
+        match self {
+            AnnounceEvent::Started => "started",
+            AnnounceEvent::Completed => "completed",
+            AnnounceEvent::Stopped => "stopped",
+        }
+    }
+}
+
 impl Peer {
     /// Go through `dict` and return the extracted `Peer`.
     ///
@@ -200,10 +245,12 @@ impl TrackerResponse {
     {
         let mut parsed = BencodeElem::from_bytes(bytes)?;
         if parsed.len() != 1 {
-            return Err(LavaTorrentError::MalformedTorrent(Cow::Owned(format!(
-                "Tracker response should contain 1 and only 1 top-level element, {} found.",
-                parsed.len()
-            ))));
+            return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                Cow::Owned(format!(
+                    "Tracker response should contain 1 and only 1 top-level element, {} found.",
+                    parsed.len()
+                )),
+            )));
         }
         let mut parsed = match parsed.remove(0) {
             BencodeElem::Dictionary(dict) => dict,
@@ -410,10 +457,12 @@ impl TrackerScrapeResponse {
     {
         let mut parsed = BencodeElem::from_bytes(bytes)?;
         if parsed.len() != 1 {
-            return Err(LavaTorrentError::MalformedTorrent(Cow::Owned(format!(
+            return Err(LavaTorrentError::MalformedTorrent(TorrentErrorKind::Other(
+                Cow::Owned(format!(
                 "Tracker scrape response should contain 1 and only 1 top-level element, {} found.",
                 parsed.len()
-            ))));
+            )),
+            )));
         }
         let mut parsed = match parsed.remove(0) {
             BencodeElem::Dictionary(dict) => dict,
@@ -461,6 +510,47 @@ impl TrackerScrapeResponse {
     }
 }
 
+/// Derive a tracker's scrape URL from its announce URL.
+///
+/// For HTTP(S) trackers, this follows the convention described in
+/// [BEP 48](http://www.bittorrent.org/beps/bep_0048.html): the last
+/// path segment must begin with `announce`, which is replaced by `scrape`
+/// (e.g. `http://example.com/x/announce.php?passkey=1` becomes
+/// `http://example.com/x/scrape.php?passkey=1`). UDP trackers
+/// ([BEP 15](http://www.bittorrent.org/beps/bep_0015.html)) are
+/// scraped at the announce URL itself, which is returned as is.
+///
+/// `None` is returned if the tracker does not support scraping,
+/// or if `announce` is not a URL.
+pub fn scrape_url(announce: &str) -> Option<String> {
+    const ANNOUNCE: &str = "announce";
+
+    let (scheme, after_scheme) = announce.split_once("://")?;
+    if scheme.eq_ignore_ascii_case("udp") {
+        return Some(announce.to_owned());
+    } else if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return None;
+    }
+
+    // the path starts after the host and ends before the query/fragment
+    let path_start = announce.len() - after_scheme.len() + after_scheme.find('/')?;
+    let path_end = announce.find(['?', '#']).unwrap_or(announce.len());
+    if path_end < path_start {
+        return None;
+    }
+
+    let segment_start = announce[..path_end].rfind('/')? + 1;
+    if announce[segment_start..path_end].starts_with(ANNOUNCE) {
+        Some(format!(
+            "{}scrape{}",
+            &announce[..segment_start],
+            &announce[segment_start + ANNOUNCE.len()..]
+        ))
+    } else {
+        None
+    }
+}
+
 impl fmt::Display for Peer {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if let Some(ref id) = self.id {
@@ -582,3 +672,43 @@ impl fmt::Display for TrackerScrapeResponse {
 }
 
 // @todo: add unit tests
+
+#[cfg(test)]
+mod scrape_url_tests {
+    use super::*;
+
+    #[test]
+    fn scrape_url_ok() {
+        assert_eq!(
+            scrape_url("http://example.com/announce"),
+            Some("http://example.com/scrape".to_owned())
+        );
+        assert_eq!(
+            scrape_url("https://example.com/x/announce.php?passkey=1"),
+            Some("https://example.com/x/scrape.php?passkey=1".to_owned())
+        );
+        assert_eq!(
+            scrape_url("http://example.com:8080/announce?a=/announce"),
+            Some("http://example.com:8080/scrape?a=/announce".to_owned())
+        );
+    }
+
+    #[test]
+    fn scrape_url_udp() {
+        assert_eq!(
+            scrape_url("udp://example.com:6969/announce"),
+            Some("udp://example.com:6969/announce".to_owned())
+        );
+    }
+
+    #[test]
+    fn scrape_url_unsupported() {
+        assert_eq!(scrape_url("http://example.com/a"), None);
+        assert_eq!(scrape_url("http://example.com/announce/x"), None);
+        assert_eq!(scrape_url("http://example.com/xannounce"), None);
+        assert_eq!(scrape_url("http://announce"), None);
+        assert_eq!(scrape_url("http://example.com?/announce"), None);
+        assert_eq!(scrape_url("wss://example.com/announce"), None);
+        assert_eq!(scrape_url("announce"), None);
+    }
+}
@@ -0,0 +1,146 @@
+use super::announce::{get_ok, QUERY_COMPONENT};
+use super::*;
+use percent_encoding::percent_encode;
+
+/// Build the URL for scraping `info_hashes` from the tracker at `announce`,
+/// for users who prefer to send the request with their own HTTP client.
+/// Requires the `http` feature.
+///
+/// `None` is returned if the tracker does not support scraping
+/// (see [`scrape_url()`]), or if it is not an HTTP(S) tracker.
+///
+/// [`scrape_url()`]: fn.scrape_url.html
+pub fn scrape_request_url(announce: &str, info_hashes: &[[u8; 20]]) -> Option<String> {
+    if !is_http(announce) {
+        return None;
+    }
+    let mut url = scrape_url(announce)?;
+
+    for info_hash in info_hashes {
+        url.push(if url.contains('?') { '&' } else { '?' });
+        url.push_str("info_hash=");
+        url.extend(percent_encode(info_hash, QUERY_COMPONENT));
+    }
+    Some(url)
+}
+
+/// Ask the tracker at `announce` about the swarms of `info_hashes`
+/// and parse its response. Requires the `http` feature.
+///
+/// If `info_hashes` is empty, trackers may return all swarms they know about.
+/// The scrape URL is derived from `announce` with [`scrape_url()`].
+/// As with [`announce()`], both `http://` and `https://` trackers are
+/// supported. UDP trackers are scraped with [`udp::scrape()`] instead.
+///
+/// # Example
+///
+/// ```no_run
+/// use lava_torrent::torrent::v1::Torrent;
+/// use lava_torrent::tracker;
+///
+/// let torrent = Torrent::read_from_file("sample.torrent").unwrap();
+/// let mut info_hash = [0; 20];
+/// info_hash.copy_from_slice(&torrent.info_hash_bytes());
+///
+/// let response = tracker::scrape(torrent.announce.as_ref().unwrap(), &[info_hash]).unwrap();
+/// if let Some(swarm) = response.files.get(&info_hash[..]) {
+///     println!("seeders: {}, leechers: {}", swarm.complete, swarm.incomplete);
+/// }
+/// ```
+///
+/// [`scrape_url()`]: fn.scrape_url.html
+/// [`announce()`]: fn.announce.html
+/// [`udp::scrape()`]: udp/fn.scrape.html
+pub fn scrape(
+    announce: &str,
+    info_hashes: &[[u8; 20]],
+) -> Result<TrackerScrapeResponse, LavaTorrentError> {
+    if !is_http(announce) {
+        return Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+            "Not an HTTP(S) tracker, use `tracker::udp::scrape()` for UDP trackers: {}",
+            announce
+        ))));
+    }
+
+    match scrape_request_url(announce, info_hashes) {
+        Some(url) => TrackerScrapeResponse::from_bytes(get_ok(&url)?),
+        None => Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+            "Tracker does not support scraping: {}",
+            announce
+        )))),
+    }
+}
+
+fn is_http(url: &str) -> bool {
+    url.split_once("://").is_some_and(|(scheme, _)| {
+        scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https")
+    })
+}
+
+#[cfg(test)]
+mod scrape_tests {
+    use super::*;
+    use crate::http::http_tests::serve;
+
+    #[test]
+    fn scrape_request_url_ok() {
+        assert_eq!(
+            scrape_request_url("http://example.com/announce?a=1", &[[0xff; 20], [b'a'; 20]]),
+            Some(
+                "http://example.com/scrape?a=1&info_hash=".to_owned()
+                    + &"%FF".repeat(20)
+                    + "&info_hash="
+                    + &"a".repeat(20)
+            )
+        );
+        assert_eq!(
+            scrape_request_url("http://example.com/announce", &[]),
+            Some("http://example.com/scrape".to_owned())
+        );
+        assert_eq!(scrape_request_url("http://example.com/a", &[]), None);
+        assert_eq!(scrape_request_url("udp://example.com:6969", &[]), None);
+    }
+
+    #[test]
+    fn scrape_ok() {
+        let mut body = b"d5:filesd20:".to_vec();
+        body.extend_from_slice(&[0xff; 20]);
+        body.extend_from_slice(b"d8:completei5e10:downloadedi50e10:incompletei10eeee");
+        let mut response =
+            format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len()).into_bytes();
+        response.extend_from_slice(&body);
+        let (url, handle) = serve(response);
+
+        let response = scrape(&format!("{}/announce", url), &[[0xff; 20]]).unwrap();
+        let swarm = &response.files[&vec![0xff; 20]];
+        assert_eq!(swarm.complete, 5);
+        assert_eq!(swarm.incomplete, 10);
+        assert_eq!(swarm.downloaded, 50);
+        assert!(handle
+            .join()
+            .unwrap()
+            .starts_with("GET /scrape?info_hash=%FF"));
+    }
+
+    #[test]
+    fn scrape_unsupported() {
+        match scrape("http://example.com/a", &[[0xff; 20]]) {
+            Err(LavaTorrentError::InvalidArgument(m)) => {
+                assert_eq!(m, "Tracker does not support scraping: http://example.com/a")
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn scrape_udp() {
+        match scrape("udp://example.com:6969", &[[0xff; 20]]) {
+            Err(LavaTorrentError::InvalidArgument(m)) => assert_eq!(
+                m,
+                "Not an HTTP(S) tracker, use `tracker::udp::scrape()` for UDP trackers: \
+                 udp://example.com:6969"
+            ),
+            _ => panic!(),
+        }
+    }
+}
@@ -0,0 +1,731 @@
+//! Packets of the UDP tracker protocol ([BEP 15](http://bittorrent.org/beps/bep_0015.html)).
+//!
+//! Requests can be encoded with e.g. [`AnnounceRequest::encode()`], and
+//! responses can be decoded with [`Response::from_bytes()`]. With the `udp`
+//! feature, [`announce()`] and [`scrape()`] can be used to communicate with
+//! UDP trackers directly.
+//!
+//! [`AnnounceRequest::encode()`]: struct.AnnounceRequest.html#method.encode
+//! [`Response::from_bytes()`]: enum.Response.html#method.from_bytes
+//! [`announce()`]: fn.announce.html
+//! [`scrape()`]: fn.scrape.html
+
+use super::{AnnounceEvent, SwarmMetadata};
+use crate::LavaTorrentError;
+use std::borrow::Cow;
+use std::convert::TryInto;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Magic constant that identifies the protocol in a connect request.
+pub const PROTOCOL_ID: u64 = 0x0417_2710_1980;
+
+const ACTION_CONNECT: u32 = 0;
+const ACTION_ANNOUNCE: u32 = 1;
+const ACTION_SCRAPE: u32 = 2;
+const ACTION_ERROR: u32 = 3;
+
+/// A request for a connection ID, which is required by all other requests.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ConnectRequest {
+    /// Random value chosen by the client to match the response.
+    pub transaction_id: u32,
+}
+
+/// An announce over UDP.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AnnounceRequest {
+    /// Connection ID from a [`Response::Connect`].
+    ///
+    /// [`Response::Connect`]: enum.Response.html#variant.Connect
+    pub connection_id: u64,
+    /// Random value chosen by the client to match the response.
+    pub transaction_id: u32,
+    /// SHA1 hash of the torrent's `info`.
+    pub info_hash: [u8; 20],
+    /// ID of the client.
+    pub peer_id: [u8; 20],
+    /// Total number of bytes downloaded.
+    pub downloaded: u64,
+    /// Number of bytes the client still has to download.
+    pub left: u64,
+    /// Total number of bytes uploaded.
+    pub uploaded: u64,
+    /// `None` for regular announces.
+    pub event: Option<AnnounceEvent>,
+    /// Random value that identifies the client across IP changes.
+    pub key: u32,
+    /// Number of peers the client would like to receive,
+    /// or `None` to let the tracker decide.
+    pub num_want: Option<u32>,
+    /// Port the client is listening on.
+    pub port: u16,
+}
+
+/// A scrape over UDP.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScrapeRequest {
+    /// Connection ID from a [`Response::Connect`].
+    ///
+    /// [`Response::Connect`]: enum.Response.html#variant.Connect
+    pub connection_id: u64,
+    /// Random value chosen by the client to match the response.
+    pub transaction_id: u32,
+    /// Info hashes of the torrents to scrape.
+    pub info_hashes: Vec<[u8; 20]>,
+}
+
+/// A response from a UDP tracker.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Response {
+    Connect {
+        /// `transaction_id` of the request.
+        transaction_id: u32,
+        /// ID to use in subsequent requests.
+        connection_id: u64,
+    },
+    Announce {
+        /// `transaction_id` of the request.
+        transaction_id: u32,
+        /// The number of seconds the client should wait before re-announcing.
+        interval: u32,
+        /// Number of non-seeder peers.
+        leechers: u32,
+        /// Number of seeders.
+        seeders: u32,
+        /// Peers of the swarm.
+        peers: Vec<SocketAddr>,
+    },
+    Scrape {
+        /// `transaction_id` of the request.
+        transaction_id: u32,
+        /// Swarm metadata, in the same order as the requested info hashes.
+        swarms: Vec<SwarmMetadata>,
+    },
+    Error {
+        /// `transaction_id` of the request.
+        transaction_id: u32,
+        /// Error message.
+        message: String,
+    },
+}
+
+impl AnnounceEvent {
+    fn to_udp(event: Option<AnnounceEvent>) -> u32 {
+        match event {
+            None => 0,
+            Some(AnnounceEvent::Completed) => 1,
+            Some(AnnounceEvent::Started) => 2,
+            Some(AnnounceEvent::Stopped) => 3,
+        }
+    }
+}
+
+impl ConnectRequest {
+    /// Encode `self` into a packet.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut packet = Vec::with_capacity(16);
+        packet.extend_from_slice(&PROTOCOL_ID.to_be_bytes());
+        packet.extend_from_slice(&ACTION_CONNECT.to_be_bytes());
+        packet.extend_from_slice(&self.transaction_id.to_be_bytes());
+        packet
+    }
+}
+
+impl AnnounceRequest {
+    /// Encode `self` into a packet.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut packet = Vec::with_capacity(98);
+        packet.extend_from_slice(&self.connection_id.to_be_bytes());
+        packet.extend_from_slice(&ACTION_ANNOUNCE.to_be_bytes());
+        packet.extend_from_slice(&self.transaction_id.to_be_bytes());
+        packet.extend_from_slice(&self.info_hash);
+        packet.extend_from_slice(&self.peer_id);
+        packet.extend_from_slice(&self.downloaded.to_be_bytes());
+        packet.extend_from_slice(&self.left.to_be_bytes());
+        packet.extend_from_slice(&self.uploaded.to_be_bytes());
+        packet.extend_from_slice(&AnnounceEvent::to_udp(self.event).to_be_bytes());
+        packet.extend_from_slice(&0_u32.to_be_bytes()); // IP address, 0 = sender's
+        packet.extend_from_slice(&self.key.to_be_bytes());
+        packet.extend_from_slice(
+            &self
+                .num_want
+                .map_or(-1, |n| i32::try_from(n).unwrap_or(i32::MAX))
+                .to_be_bytes(),
+        );
+        packet.extend_from_slice(&self.port.to_be_bytes());
+        packet
+    }
+}
+
+impl ScrapeRequest {
+    /// Encode `self` into a packet.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut packet = Vec::with_capacity(16 + self.info_hashes.len() * 20);
+        packet.extend_from_slice(&self.connection_id.to_be_bytes());
+        packet.extend_from_slice(&ACTION_SCRAPE.to_be_bytes());
+        packet.extend_from_slice(&self.transaction_id.to_be_bytes());
+        for info_hash in &self.info_hashes {
+            packet.extend_from_slice(info_hash);
+        }
+        packet
+    }
+}
+
+impl Response {
+    /// Parse `bytes` and return the extracted `Response`.
+    ///
+    /// Peers in announce responses are IPv6 addresses if the announce was
+    /// sent over IPv6, which must be indicated with `ipv6`.
+    pub fn from_bytes<B>(bytes: B, ipv6: bool) -> Result<Response, LavaTorrentError>
+    where
+        B: AsRef<[u8]>,
+    {
+        let bytes = bytes.as_ref();
+        if bytes.len() < 8 {
+            return Err(LavaTorrentError::MalformedResponse(Cow::Borrowed(
+                "UDP tracker response is shorter than 8 bytes.",
+            )));
+        }
+        let action = read_u32(bytes, 0);
+        let transaction_id = read_u32(bytes, 4);
+        let body = &bytes[8..];
+
+        match action {
+            ACTION_CONNECT if body.len() >= 8 => Ok(Response::Connect {
+                transaction_id,
+                connection_id: u64::from_be_bytes(body[..8].try_into().unwrap()),
+            }),
+            ACTION_ANNOUNCE if body.len() >= 12 => {
+                let peer_len = if ipv6 { 18 } else { 6 };
+                let peers = &body[12..];
+                if peers.len() % peer_len != 0 {
+                    return Err(LavaTorrentError::MalformedResponse(Cow::Owned(format!(
+                        "UDP announce response contains a partial peer ({} trailing bytes).",
+                        peers.len() % peer_len
+                    ))));
+                }
+
+                Ok(Response::Announce {
+                    transaction_id,
+                    interval: read_u32(body, 0),
+                    leechers: read_u32(body, 4),
+                    seeders: read_u32(body, 8),
+                    peers: peers.chunks(peer_len).map(read_peer).collect(),
+                })
+            }
+            ACTION_SCRAPE if body.len() % 12 == 0 => Ok(Response::Scrape {
+                transaction_id,
+                swarms: body
+                    .chunks(12)
+                    .map(|swarm| SwarmMetadata {
+                        complete: i64::from(read_u32(swarm, 0)),
+                        downloaded: i64::from(read_u32(swarm, 4)),
+                        incomplete: i64::from(read_u32(swarm, 8)),
+                        extra_fields: None,
+                    })
+                    .collect(),
+            }),
+            ACTION_ERROR => Ok(Response::Error {
+                transaction_id,
+                message: String::from_utf8_lossy(body).into_owned(),
+            }),
+            ACTION_CONNECT | ACTION_ANNOUNCE | ACTION_SCRAPE => {
+                Err(LavaTorrentError::MalformedResponse(Cow::Owned(format!(
+                    "UDP tracker response has an invalid length ({} bytes) for action {}.",
+                    bytes.len(),
+                    action
+                ))))
+            }
+            _ => Err(LavaTorrentError::MalformedResponse(Cow::Owned(format!(
+                "UDP tracker response has an unknown action {}.",
+                action
+            )))),
+        }
+    }
+
+    /// Get the `transaction_id` of `self`.
+    pub fn transaction_id(&self) -> u32 {
+        match *self {
+            Response::Connect { transaction_id, .. }
+            | Response::Announce { transaction_id, .. }
+            | Response::Scrape { transaction_id, .. }
+            | Response::Error { transaction_id, .. } => transaction_id,
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_peer(bytes: &[u8]) -> SocketAddr {
+    let (ip, port) = bytes.split_at(bytes.len() - 2);
+    let port = u16::from_be_bytes(port.try_into().unwrap());
+
+    match ip.len() {
+        4 => SocketAddr::new(Ipv4Addr::new(ip[0], ip[1], ip[2], ip[3]).into(), port),
+        _ => {
+            let ip: [u8; 16] = ip.try_into().unwrap();
+            SocketAddr::new(Ipv6Addr::from(ip).into(), port)
+        }
+    }
+}
+
+#[cfg(feature = "udp")]
+pub use self::client::{announce, scrape};
+
+#[cfg(feature = "udp")]
+mod client {
+    use super::*;
+    use rand::Rng;
+    use std::io;
+    use std::net::{ToSocketAddrs, UdpSocket};
+    use std::time::Duration;
+
+    // BEP 15 suggests waiting 15 * 2 ^ n seconds for the n-th retry (up to n = 8),
+    // which adds up to more than an hour. We give up much earlier.
+    const MAX_RETRIES: u32 = 2;
+    const BASE_TIMEOUT: Duration = Duration::from_secs(15);
+    const MAX_PACKET_LEN: usize = 64 * 1024;
+
+    /// Send `request` to the UDP tracker at `announce` (e.g.
+    /// `udp://tracker.example.com:6969/announce`) and return its response.
+    /// Requires the `udp` feature.
+    ///
+    /// `request.connection_id` and `request.transaction_id` are
+    /// filled in automatically. A tracker error is returned as
+    /// `Err(LavaTorrentError::MalformedResponse)`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use lava_torrent::torrent::v1::Torrent;
+    /// use lava_torrent::tracker::udp::{self, AnnounceRequest};
+    /// use lava_torrent::tracker::AnnounceEvent;
+    ///
+    /// let torrent = Torrent::read_from_file("sample.torrent").unwrap();
+    /// let mut info_hash = [0; 20];
+    /// info_hash.copy_from_slice(&torrent.info_hash_bytes());
+    ///
+    /// let request = AnnounceRequest {
+    ///     connection_id: 0,
+    ///     transaction_id: 0,
+    ///     info_hash,
+    ///     peer_id: *b"-LT0000-123456789012",
+    ///     downloaded: 0,
+    ///     left: torrent.info.length as u64,
+    ///     uploaded: 0,
+    ///     event: Some(AnnounceEvent::Started),
+    ///     key: 42,
+    ///     num_want: None,
+    ///     port: 6881,
+    /// };
+    /// let response = udp::announce(torrent.announce.as_ref().unwrap(), &request).unwrap();
+    /// println!("{:?}", response);
+    /// ```
+    pub fn announce(
+        announce: &str,
+        request: &AnnounceRequest,
+    ) -> Result<Response, LavaTorrentError> {
+        let (socket, ipv6) = connect_socket(announce)?;
+        let connection_id = connect(&socket)?;
+
+        let mut request = request.clone();
+        request.connection_id = connection_id;
+        request.transaction_id = rand::thread_rng().gen();
+        send(&socket, &request.encode(), request.transaction_id, ipv6)
+    }
+
+    /// Ask the UDP tracker at `announce` about the swarms of `info_hashes`,
+    /// returning their metadata in the same order. Requires the `udp` feature.
+    ///
+    /// A tracker error is returned as `Err(LavaTorrentError::MalformedResponse)`.
+    pub fn scrape(
+        announce: &str,
+        info_hashes: &[[u8; 20]],
+    ) -> Result<Vec<SwarmMetadata>, LavaTorrentError> {
+        let (socket, ipv6) = connect_socket(announce)?;
+        let connection_id = connect(&socket)?;
+
+        let request = ScrapeRequest {
+            connection_id,
+            transaction_id: rand::thread_rng().gen(),
+            info_hashes: info_hashes.to_vec(),
+        };
+        match send(&socket, &request.encode(), request.transaction_id, ipv6)? {
+            Response::Scrape { swarms, .. } if swarms.len() == info_hashes.len() => Ok(swarms),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    fn connect(socket: &UdpSocket) -> Result<u64, LavaTorrentError> {
+        let request = ConnectRequest {
+            transaction_id: rand::thread_rng().gen(),
+        };
+        match send(socket, &request.encode(), request.transaction_id, false)? {
+            Response::Connect { connection_id, .. } => Ok(connection_id),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    // Send `packet` until a response with `transaction_id` is received.
+    fn send(
+        socket: &UdpSocket,
+        packet: &[u8],
+        transaction_id: u32,
+        ipv6: bool,
+    ) -> Result<Response, LavaTorrentError> {
+        let mut buf = vec![0; MAX_PACKET_LEN];
+
+        for n in 0..=MAX_RETRIES {
+            socket.send(packet)?;
+            socket.set_read_timeout(Some(BASE_TIMEOUT * 2_u32.pow(n)))?;
+
+            loop {
+                let len = match socket.recv(&mut buf) {
+                    Ok(len) => len,
+                    Err(e)
+                        if e.kind() == io::ErrorKind::WouldBlock
+                            || e.kind() == io::ErrorKind::TimedOut =>
+                    {
+                        break;
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+
+                // ignore stray/malformed packets
+                match Response::from_bytes(&buf[..len], ipv6) {
+                    Ok(Response::Error {
+                        transaction_id: id,
+                        message,
+                    }) if id == transaction_id => {
+                        return Err(LavaTorrentError::MalformedResponse(Cow::Owned(format!(
+                            "Tracker error: {}",
+                            message
+                        ))));
+                    }
+                    Ok(response) if response.transaction_id() == transaction_id => {
+                        return Ok(response);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Err(io::Error::new(io::ErrorKind::TimedOut, "UDP tracker did not respond.").into())
+    }
+
+    // Create a socket connected to the tracker at `url`.
+    // Returns whether the tracker is reached through IPv6.
+    fn connect_socket(url: &str) -> Result<(UdpSocket, bool), LavaTorrentError> {
+        let authority = match url.split_once("://") {
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case("udp") => {
+                rest.split(['/', '?']).next().unwrap_or_default()
+            }
+            _ => {
+                return Err(LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                    "Not a UDP tracker URL: {}",
+                    url
+                ))));
+            }
+        };
+        let addr = authority
+            .to_socket_addrs()
+            .map_err(|_| {
+                LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                    "Invalid or unresolvable UDP tracker URL: {}",
+                    url
+                )))
+            })?
+            .next()
+            .ok_or_else(|| {
+                LavaTorrentError::InvalidArgument(Cow::Owned(format!(
+                    "Failed to resolve UDP tracker URL: {}",
+                    url
+                )))
+            })?;
+
+        let socket = if addr.is_ipv6() {
+            UdpSocket::bind("[::]:0")?
+        } else {
+            UdpSocket::bind("0.0.0.0:0")?
+        };
+        socket.connect(addr)?;
+        Ok((socket, addr.is_ipv6()))
+    }
+
+    fn unexpected_response() -> LavaTorrentError {
+        LavaTorrentError::MalformedResponse(Cow::Borrowed(
+            "UDP tracker sent an unexpected response.",
+        ))
+    }
+
+    #[cfg(test)]
+    mod udp_client_tests {
+        use super::*;
+        use std::thread;
+
+        // Answer a connect request and then one more request with `response`
+        // (after replacing its transaction ID).
+        fn serve(mut response: Vec<u8>) -> (String, thread::JoinHandle<Vec<u8>>) {
+            let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let url = format!("udp://{}/announce", socket.local_addr().unwrap());
+
+            let handle = thread::spawn(move || {
+                let mut buf = [0; 1024];
+                let (len, client) = socket.recv_from(&mut buf).unwrap();
+                assert_eq!(len, 16);
+                assert_eq!(&buf[..8], &PROTOCOL_ID.to_be_bytes());
+                let mut connect = vec![0; 8];
+                connect[4..].copy_from_slice(&buf[12..16]);
+                connect.extend_from_slice(&42_u64.to_be_bytes());
+                socket.send_to(&connect, client).unwrap();
+
+                let (len, client) = socket.recv_from(&mut buf).unwrap();
+                response[4..8].copy_from_slice(&buf[12..16]);
+                socket.send_to(&response, client).unwrap();
+                buf[..len].to_vec()
+            });
+
+            (url, handle)
+        }
+
+        #[test]
+        fn announce_ok() {
+            let mut response = vec![0, 0, 0, 1, 0, 0, 0, 0];
+            response.extend_from_slice(&[0, 0, 7, 8, 0, 0, 0, 1, 0, 0, 0, 2]);
+            response.extend_from_slice(&[127, 0, 0, 1, 0x1a, 0xe1]);
+            let (url, handle) = serve(response);
+
+            let request = AnnounceRequest {
+                connection_id: 0,
+                transaction_id: 0,
+                info_hash: [0xff; 20],
+                peer_id: [b'a'; 20],
+                downloaded: 0,
+                left: 0,
+                uploaded: 0,
+                event: None,
+                key: 0,
+                num_want: None,
+                port: 6881,
+            };
+            match announce(&url, &request).unwrap() {
+                Response::Announce {
+                    interval, peers, ..
+                } => {
+                    assert_eq!(interval, 1800);
+                    assert_eq!(peers, vec!["127.0.0.1:6881".parse().unwrap()]);
+                }
+                _ => panic!(),
+            }
+
+            let sent = handle.join().unwrap();
+            assert_eq!(sent.len(), 98);
+            assert_eq!(&sent[..8], &42_u64.to_be_bytes());
+        }
+
+        #[test]
+        fn scrape_ok() {
+            let mut response = vec![0, 0, 0, 2, 0, 0, 0, 0];
+            response.extend_from_slice(&[0, 0, 0, 5, 0, 0, 0, 50, 0, 0, 0, 10]);
+            let (url, _) = serve(response);
+
+            let swarms = scrape(&url, &[[0xff; 20]]).unwrap();
+            assert_eq!(swarms.len(), 1);
+            assert_eq!(swarms[0].complete, 5);
+            assert_eq!(swarms[0].downloaded, 50);
+            assert_eq!(swarms[0].incomplete, 10);
+        }
+
+        #[test]
+        fn scrape_tracker_error() {
+            let mut response = vec![0, 0, 0, 3, 0, 0, 0, 0];
+            response.extend_from_slice(b"unknown torrent");
+            let (url, _) = serve(response);
+
+            match scrape(&url, &[[0xff; 20]]) {
+                Err(LavaTorrentError::MalformedResponse(m)) => {
+                    assert_eq!(m, "Tracker error: unknown torrent")
+                }
+                _ => panic!(),
+            }
+        }
+
+        #[test]
+        fn not_udp_url() {
+            assert!(scrape("http://example.com/announce", &[]).is_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod udp_tests {
+    use super::*;
+
+    #[test]
+    fn connect_request_encode() {
+        assert_eq!(
+            ConnectRequest {
+                transaction_id: 0x01020304
+            }
+            .encode(),
+            vec![0, 0, 0x04, 0x17, 0x27, 0x10, 0x19, 0x80, 0, 0, 0, 0, 1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn announce_request_encode() {
+        let packet = AnnounceRequest {
+            connection_id: 1,
+            transaction_id: 2,
+            info_hash: [0xff; 20],
+            peer_id: [b'a'; 20],
+            downloaded: 3,
+            left: 4,
+            uploaded: 5,
+            event: Some(AnnounceEvent::Started),
+            key: 6,
+            num_want: None,
+            port: 6881,
+        }
+        .encode();
+
+        assert_eq!(packet.len(), 98);
+        assert_eq!(
+            &packet[..16],
+            &[0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 2]
+        );
+        assert_eq!(&packet[16..36], &[0xff; 20]);
+        assert_eq!(&packet[36..56], &[b'a'; 20]);
+        assert_eq!(&packet[56..64], &3_u64.to_be_bytes());
+        assert_eq!(&packet[64..72], &4_u64.to_be_bytes());
+        assert_eq!(&packet[72..80], &5_u64.to_be_bytes());
+        assert_eq!(&packet[80..84], &2_u32.to_be_bytes());
+        assert_eq!(&packet[84..88], &0_u32.to_be_bytes());
+        assert_eq!(&packet[88..92], &6_u32.to_be_bytes());
+        assert_eq!(&packet[92..96], &(-1_i32).to_be_bytes());
+        assert_eq!(&packet[96..], &6881_u16.to_be_bytes());
+    }
+
+    #[test]
+    fn scrape_request_encode() {
+        let packet = ScrapeRequest {
+            connection_id: 1,
+            transaction_id: 2,
+            info_hashes: vec![[0xff; 20], [0xfe; 20]],
+        }
+        .encode();
+
+        assert_eq!(packet.len(), 56);
+        assert_eq!(
+            &packet[..16],
+            &[0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 2]
+        );
+        assert_eq!(&packet[16..36], &[0xff; 20]);
+        assert_eq!(&packet[36..], &[0xfe; 20]);
+    }
+
+    #[test]
+    fn connect_response_ok() {
+        let bytes = [0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 42];
+
+        assert_eq!(
+            Response::from_bytes(bytes, false).unwrap(),
+            Response::Connect {
+                transaction_id: 9,
+                connection_id: 42,
+            }
+        );
+    }
+
+    #[test]
+    fn announce_response_ipv4() {
+        let mut bytes = vec![0, 0, 0, 1, 0, 0, 0, 9, 0, 0, 7, 8, 0, 0, 0, 1, 0, 0, 0, 2];
+        bytes.extend_from_slice(&[127, 0, 0, 1, 0x1a, 0xe1, 10, 0, 0, 1, 0, 80]);
+
+        assert_eq!(
+            Response::from_bytes(&bytes, false).unwrap(),
+            Response::Announce {
+                transaction_id: 9,
+                interval: 1800,
+                leechers: 1,
+                seeders: 2,
+                peers: vec![
+                    "127.0.0.1:6881".parse().unwrap(),
+                    "10.0.0.1:80".parse().unwrap()
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn announce_response_ipv6() {
+        let mut bytes = vec![0, 0, 0, 1, 0, 0, 0, 9, 0, 0, 7, 8, 0, 0, 0, 1, 0, 0, 0, 2];
+        bytes.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        bytes.extend_from_slice(&[0x1a, 0xe1]);
+
+        match Response::from_bytes(&bytes, true).unwrap() {
+            Response::Announce { peers, .. } => {
+                assert_eq!(peers, vec!["[::1]:6881".parse().unwrap()]);
+            }
+            _ => panic!(),
+        }
+        assert!(Response::from_bytes(&bytes[..bytes.len() - 1], true).is_err());
+    }
+
+    #[test]
+    fn scrape_response_ok() {
+        let bytes = [0, 0, 0, 2, 0, 0, 0, 9, 0, 0, 0, 5, 0, 0, 0, 50, 0, 0, 0, 10];
+
+        assert_eq!(
+            Response::from_bytes(bytes, false).unwrap(),
+            Response::Scrape {
+                transaction_id: 9,
+                swarms: vec![SwarmMetadata {
+                    complete: 5,
+                    incomplete: 10,
+                    downloaded: 50,
+                    extra_fields: None,
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn error_response_ok() {
+        let mut bytes = vec![0, 0, 0, 3, 0, 0, 0, 9];
+        bytes.extend_from_slice(b"spam");
+
+        assert_eq!(
+            Response::from_bytes(&bytes, false).unwrap(),
+            Response::Error {
+                transaction_id: 9,
+                message: "spam".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn response_malformed() {
+        match Response::from_bytes([0, 0, 0, 0], false) {
+            Err(LavaTorrentError::MalformedResponse(m)) => {
+                assert_eq!(m, "UDP tracker response is shorter than 8 bytes.")
+            }
+            _ => panic!(),
+        }
+        match Response::from_bytes([0, 0, 0, 0, 0, 0, 0, 9, 0], false) {
+            Err(LavaTorrentError::MalformedResponse(m)) => assert_eq!(
+                m,
+                "UDP tracker response has an invalid length (9 bytes) for action 0."
+            ),
+            _ => panic!(),
+        }
+        match Response::from_bytes([0, 0, 0, 4, 0, 0, 0, 9], false) {
+            Err(LavaTorrentError::MalformedResponse(m)) => {
+                assert_eq!(m, "UDP tracker response has an unknown action 4.")
+            }
+            _ => panic!(),
+        }
+    }
+}
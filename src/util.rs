@@ -1,7 +1,16 @@
+use crate::bencode::DuplicateKeyPolicy;
 use crate::LavaTorrentError;
 use std::borrow::Cow;
 use std::convert::TryFrom;
-use std::path::{Path, PathBuf};
+#[cfg(feature = "fs")]
+use std::io::Read;
+use std::path::Path;
+#[cfg(feature = "fs")]
+use std::path::PathBuf;
+#[cfg(feature = "fs")]
+use std::sync::{Arc, Mutex, PoisonError};
+#[cfg(feature = "fs")]
+use std::time::{Duration, Instant};
 
 pub(crate) fn u64_to_usize(src: u64) -> Result<usize, LavaTorrentError> {
     usize::try_from(src).map_err(|_| {
@@ -42,26 +51,53 @@ pub(crate) fn u64_to_i64(src: u64) -> Result<i64, LavaTorrentError> {
 // this method is recursive, i.e. entries in subdirectories
 // are also returned
 //
+// symbolic links are followed, and an error is returned
+// if they form a loop
+//
 // *nix hidden files/dirs are ignored
 //
 // returned vec is sorted by path
+#[cfg(feature = "fs")]
 pub(crate) fn list_dir<P>(path: P) -> Result<Vec<(PathBuf, u64)>, LavaTorrentError>
 where
     P: AsRef<Path>,
 {
+    let dir = path.as_ref();
+    let metadata = dir.metadata().map_err(file_io_err(dir, None))?;
+    let mut ancestors = vec![dir_id(dir, &metadata)?];
+
+    list_dir_inner(dir, &mut ancestors)
+}
+
+// `ancestors` contains the ids of `dir` and all its parents up
+// to the root of the walk, so that loops can be detected
+#[cfg(feature = "fs")]
+fn list_dir_inner(
+    dir: &Path,
+    ancestors: &mut Vec<DirId>,
+) -> Result<Vec<(PathBuf, u64)>, LavaTorrentError> {
     let mut entries = Vec::new();
 
-    for entry in path.as_ref().read_dir()? {
-        let entry = entry?;
+    for entry in dir.read_dir().map_err(file_io_err(dir, None))? {
+        let entry = entry.map_err(file_io_err(dir, None))?;
         let path = entry.path();
-        let metadata = path.metadata()?;
+        let metadata = path.metadata().map_err(file_io_err(&path, None))?;
 
         if last_component(&path)?.starts_with('.') {
             continue;
         } // hidden files/dirs are ignored
 
         if metadata.is_dir() {
-            entries.extend(list_dir(path)?);
+            let id = dir_id(&path, &metadata)?;
+            if ancestors.contains(&id) {
+                return Err(LavaTorrentError::TorrentBuilderFailure(Cow::Owned(
+                    format!("[{}] is a symbolic link loop.", path.display()),
+                )));
+            }
+
+            ancestors.push(id);
+            entries.extend(list_dir_inner(&path, ancestors)?);
+            ancestors.pop();
         } else {
             entries.push((path, metadata.len()));
         }
@@ -71,6 +107,159 @@ where
     Ok(entries)
 }
 
+// identifies a directory regardless of the path used to reach it
+#[cfg(all(feature = "fs", unix))]
+type DirId = (u64, u64);
+#[cfg(all(feature = "fs", not(unix)))]
+type DirId = PathBuf;
+
+#[cfg(all(feature = "fs", unix))]
+fn dir_id(_path: &Path, metadata: &std::fs::Metadata) -> Result<DirId, LavaTorrentError> {
+    use std::os::unix::fs::MetadataExt;
+
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(all(feature = "fs", not(unix)))]
+fn dir_id(path: &Path, _metadata: &std::fs::Metadata) -> Result<DirId, LavaTorrentError> {
+    path.canonicalize().map_err(file_io_err(path, None))
+}
+
+// fsync the directory containing `path`, so that the entry of a newly
+// created file survives power loss (directories can't be synced on Windows)
+#[cfg(all(feature = "fs", unix))]
+pub(crate) fn sync_parent_dir(path: &Path) -> Result<(), LavaTorrentError> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    std::fs::File::open(parent)
+        .and_then(|dir| dir.sync_all())
+        .map_err(file_io_err(parent, None))
+}
+
+#[cfg(all(feature = "fs", not(unix)))]
+pub(crate) fn sync_parent_dir(_path: &Path) -> Result<(), LavaTorrentError> {
+    Ok(())
+}
+
+// Lowers the IO priority of the current thread until dropped. This is
+// only a hint, so failures are ignored (and nothing is restored then).
+#[cfg(feature = "fs")]
+pub(crate) struct LowIoPriority {
+    #[cfg(target_os = "linux")]
+    previous: Option<libc::c_long>,
+    #[cfg(windows)]
+    entered: bool,
+}
+
+#[cfg(all(feature = "fs", target_os = "linux"))]
+impl LowIoPriority {
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1; // with `who` = 0, the current thread
+    const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+    const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+
+    // like `ionice -c 3`
+    pub(crate) fn enter() -> LowIoPriority {
+        let previous = unsafe { libc::syscall(libc::SYS_ioprio_get, Self::IOPRIO_WHO_PROCESS, 0) };
+        let idle = Self::IOPRIO_CLASS_IDLE << Self::IOPRIO_CLASS_SHIFT;
+
+        if (previous >= 0) && Self::set(idle) {
+            LowIoPriority {
+                previous: Some(previous),
+            }
+        } else {
+            LowIoPriority { previous: None }
+        }
+    }
+
+    fn set(ioprio: libc::c_long) -> bool {
+        unsafe { libc::syscall(libc::SYS_ioprio_set, Self::IOPRIO_WHO_PROCESS, 0, ioprio) == 0 }
+    }
+}
+
+#[cfg(all(feature = "fs", target_os = "linux"))]
+impl Drop for LowIoPriority {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous {
+            Self::set(previous);
+        }
+    }
+}
+
+#[cfg(all(feature = "fs", windows))]
+mod win32 {
+    use std::os::raw::{c_int, c_void};
+
+    pub(super) const THREAD_MODE_BACKGROUND_BEGIN: c_int = 0x0001_0000;
+    pub(super) const THREAD_MODE_BACKGROUND_END: c_int = 0x0002_0000;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub(super) fn GetCurrentThread() -> *mut c_void;
+        pub(super) fn SetThreadPriority(thread: *mut c_void, priority: c_int) -> c_int;
+    }
+}
+
+#[cfg(all(feature = "fs", windows))]
+impl LowIoPriority {
+    // background processing mode, which lowers both IO and CPU priority
+    pub(crate) fn enter() -> LowIoPriority {
+        // fails if the thread is already in background mode,
+        // in which case it must not be ended when dropped
+        let entered = unsafe {
+            win32::SetThreadPriority(
+                win32::GetCurrentThread(),
+                win32::THREAD_MODE_BACKGROUND_BEGIN,
+            ) != 0
+        };
+        LowIoPriority { entered }
+    }
+}
+
+#[cfg(all(feature = "fs", windows))]
+impl Drop for LowIoPriority {
+    fn drop(&mut self) {
+        if self.entered {
+            unsafe {
+                win32::SetThreadPriority(
+                    win32::GetCurrentThread(),
+                    win32::THREAD_MODE_BACKGROUND_END,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "fs", not(any(target_os = "linux", windows))))]
+impl LowIoPriority {
+    pub(crate) fn enter() -> LowIoPriority {
+        LowIoPriority {}
+    }
+}
+
+// returns a closure that attaches `path` and `offset` to an IO error,
+// to be used with `map_err()`
+#[cfg(feature = "fs")]
+pub(crate) fn file_io_err(
+    path: &Path,
+    offset: Option<u64>,
+) -> impl FnOnce(std::io::Error) -> LavaTorrentError + '_ {
+    move |source| LavaTorrentError::FileIo {
+        path: path.to_path_buf(),
+        offset,
+        source,
+    }
+}
+
+pub(crate) fn display_offset(offset: &Option<u64>) -> String {
+    match offset {
+        Some(offset) => format!(" at offset {}", offset),
+        None => String::new(),
+    }
+}
+
 pub(crate) fn last_component<P>(path: P) -> Result<String, LavaTorrentError>
 where
     P: AsRef<Path>,
@@ -85,10 +274,16 @@ where
     }
 }
 
+const CONTEXT_LENGTH: usize = 16;
+
 pub(crate) struct ByteBuffer<'a> {
     bytes: &'a [u8],
-    position: usize, // current cursor position
-    length: usize,   // total buffer length
+    position: usize,      // current cursor position
+    length: usize,        // total buffer length
+    elements_left: usize, // number of elements that can still be taken
+    strict: bool,         // whether only canonical bencode is accepted
+    duplicate_keys: DuplicateKeyPolicy,
+    sorted_dictionaries: bool, // whether dictionaries are parsed as `SortedDictionary`s
 }
 
 impl<'a> ByteBuffer<'a> {
@@ -97,6 +292,54 @@ impl<'a> ByteBuffer<'a> {
             bytes,
             position: 0,
             length: bytes.len(),
+            elements_left: usize::MAX,
+            strict: false,
+            duplicate_keys: DuplicateKeyPolicy::default(),
+            sorted_dictionaries: false,
+        }
+    }
+
+    pub(crate) fn set_element_limit(&mut self, max_elements: usize) {
+        self.elements_left = max_elements;
+    }
+
+    pub(crate) fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub(crate) fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    pub(crate) fn set_duplicate_keys(&mut self, duplicate_keys: DuplicateKeyPolicy) {
+        self.duplicate_keys = duplicate_keys;
+    }
+
+    pub(crate) fn duplicate_keys(&self) -> DuplicateKeyPolicy {
+        if self.strict {
+            DuplicateKeyPolicy::Error
+        } else {
+            self.duplicate_keys
+        }
+    }
+
+    pub(crate) fn set_sorted_dictionaries(&mut self, sorted_dictionaries: bool) {
+        self.sorted_dictionaries = sorted_dictionaries;
+    }
+
+    pub(crate) fn sorted_dictionaries(&self) -> bool {
+        self.sorted_dictionaries
+    }
+
+    // Account for a newly decoded element.
+    // Return `false` if the element limit has been reached.
+    pub(crate) fn take_element(&mut self) -> bool {
+        match self.elements_left.checked_sub(1) {
+            Some(left) => {
+                self.elements_left = left;
+                true
+            }
+            None => false,
         }
     }
 
@@ -122,6 +365,13 @@ impl<'a> ByteBuffer<'a> {
     pub(crate) fn is_empty(&self) -> bool {
         self.position >= self.length
     }
+
+    // Bytes around `position`, escaped so that they can be displayed.
+    pub(crate) fn context(&self, position: usize) -> String {
+        let start = position.saturating_sub(CONTEXT_LENGTH).min(self.length);
+        let end = position.saturating_add(CONTEXT_LENGTH).min(self.length);
+        self.bytes[start..end].escape_ascii().to_string()
+    }
 }
 
 impl<'a> Iterator for ByteBuffer<'a> {
@@ -137,11 +387,97 @@ impl<'a> Iterator for ByteBuffer<'a> {
     }
 }
 
+// Limits the rate at which bytes are read, shared by all threads of a build.
+// Clones share the same budget.
+#[cfg(feature = "fs")]
+#[derive(Clone, Debug)]
+pub(crate) struct RateLimiter {
+    bytes_per_sec: u64,
+    // the time at which the next read may start
+    next: Arc<Mutex<Option<Instant>>>,
+}
+
+#[cfg(feature = "fs")]
+impl RateLimiter {
+    // reads are split into blocks of (at most) this size
+    // so that large pieces do not cause long bursts
+    const BLOCK_SIZE: usize = 64 * 1024;
+
+    // `bytes_per_sec` must be positive
+    pub(crate) fn new(bytes_per_sec: u64) -> RateLimiter {
+        RateLimiter {
+            bytes_per_sec,
+            next: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // Account for `n` bytes that have been read, sleeping
+    // if previous reads have used up the budget.
+    pub(crate) fn consume(&self, n: u64) {
+        let delay = {
+            let mut next = self.next.lock().unwrap_or_else(PoisonError::into_inner);
+            let now = Instant::now();
+            let start = match *next {
+                Some(next) if next > now => next,
+                _ => now,
+            };
+            *next = Some(start + Duration::from_secs_f64(n as f64 / self.bytes_per_sec as f64));
+            start - now
+        };
+
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+    }
+
+    // Wrap `reader` so that reading from it is limited by `self`.
+    pub(crate) fn reader<R>(&self, reader: R) -> RateLimitedReader<'_, R>
+    where
+        R: Read,
+    {
+        RateLimitedReader {
+            inner: reader,
+            limiter: self,
+        }
+    }
+}
+
+// Limiters are compared by rate only.
+#[cfg(feature = "fs")]
+impl PartialEq for RateLimiter {
+    fn eq(&self, other: &RateLimiter) -> bool {
+        self.bytes_per_sec == other.bytes_per_sec
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Eq for RateLimiter {}
+
+#[cfg(feature = "fs")]
+pub(crate) struct RateLimitedReader<'a, R> {
+    inner: R,
+    limiter: &'a RateLimiter,
+}
+
+#[cfg(feature = "fs")]
+impl<'a, R> Read for RateLimitedReader<'a, R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(RateLimiter::BLOCK_SIZE);
+        let read = self.inner.read(&mut buf[..len])?;
+        self.limiter.consume(read as u64);
+        Ok(read)
+    }
+}
+
 #[cfg(test)]
 mod util_tests {
     use super::*;
 
     #[test]
+    #[cfg(feature = "fs")]
     fn list_dir_ok() {
         assert_eq!(
             list_dir("tests/files").unwrap(),
@@ -160,14 +496,35 @@ mod util_tests {
     }
 
     #[test]
+    #[cfg(feature = "fs")]
     fn list_dir_with_subdir() {
         assert_eq!(
             list_dir("src/torrent").unwrap(),
             vec![
                 "src/torrent/mod.rs",
+                "src/torrent/v1/alignment.rs",
+                "src/torrent/v1/arbitrary.rs",
+                "src/torrent/v1/batch.rs",
                 "src/torrent/v1/build.rs",
+                "src/torrent/v1/diff.rs",
+                "src/torrent/v1/edit.rs",
+                "src/torrent/v1/identity.rs",
+                "src/torrent/v1/index.rs",
+                "src/torrent/v1/info_section.rs",
+                "src/torrent/v1/merkle.rs",
                 "src/torrent/v1/mod.rs",
+                "src/torrent/v1/pieces.rs",
+                "src/torrent/v1/raw_info.rs",
                 "src/torrent/v1/read.rs",
+                "src/torrent/v1/read_ahead.rs",
+                "src/torrent/v1/resume.rs",
+                "src/torrent/v1/rtorrent.rs",
+                "src/torrent/v1/sparse.rs",
+                "src/torrent/v1/spill.rs",
+                "src/torrent/v1/trackers.rs",
+                "src/torrent/v1/uring.rs",
+                "src/torrent/v1/validate.rs",
+                "src/torrent/v1/verify.rs",
                 "src/torrent/v1/write.rs",
             ]
             .iter()
@@ -287,6 +644,18 @@ mod byte_buffer_tests {
         assert_eq!(buffer.pos(), 3);
     }
 
+    #[test]
+    fn byte_buffer_element_limit_test() {
+        let bytes = vec![1, 2, 3];
+        let mut buffer = ByteBuffer::new(&bytes);
+        buffer.set_element_limit(2);
+
+        assert!(buffer.take_element());
+        assert!(buffer.take_element());
+        assert!(!buffer.take_element());
+        assert!(!buffer.take_element());
+    }
+
     #[test]
     fn byte_buffer_iterator_test() {
         let bytes = vec![1, 2, 3];
@@ -304,3 +673,77 @@ mod byte_buffer_tests {
         assert_eq!(bytes, output);
     }
 }
+
+#[cfg(all(test, feature = "fs"))]
+mod rate_limiter_tests {
+    use super::*;
+
+    #[test]
+    fn consume_ok() {
+        let limiter = RateLimiter::new(1000);
+        let start = Instant::now();
+
+        // the first read is not delayed, the rest wait for the earlier ones
+        limiter.consume(100);
+        limiter.consume(100);
+        limiter.consume(100);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn consume_shared_by_clones() {
+        let limiter = RateLimiter::new(1000);
+        let clone = limiter.clone();
+        let start = Instant::now();
+
+        limiter.consume(100);
+        clone.consume(100);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn reader_ok() {
+        let bytes = vec![7; RateLimiter::BLOCK_SIZE * 2 + 1];
+        let limiter = RateLimiter::new(u64::MAX);
+        let mut reader = limiter.reader(&bytes[..]);
+        let mut buf = vec![0; bytes.len()];
+
+        assert_eq!(reader.read(&mut buf).unwrap(), RateLimiter::BLOCK_SIZE);
+        let mut read = Vec::new();
+        reader.read_to_end(&mut read).unwrap();
+        assert_eq!(read.len(), RateLimiter::BLOCK_SIZE + 1);
+    }
+
+    #[test]
+    fn eq_by_rate() {
+        assert_eq!(RateLimiter::new(10), RateLimiter::new(10));
+        assert_ne!(RateLimiter::new(10), RateLimiter::new(20));
+    }
+}
+
+#[cfg(all(test, feature = "fs", target_os = "linux"))]
+mod low_io_priority_tests {
+    use super::*;
+
+    fn ioprio() -> libc::c_long {
+        unsafe { libc::syscall(libc::SYS_ioprio_get, LowIoPriority::IOPRIO_WHO_PROCESS, 0) }
+    }
+
+    #[test]
+    fn enter_and_drop() {
+        // run on a new thread as the priority is per-thread
+        std::thread::spawn(|| {
+            let previous = ioprio();
+            let guard = LowIoPriority::enter();
+
+            assert_eq!(
+                ioprio() >> LowIoPriority::IOPRIO_CLASS_SHIFT,
+                LowIoPriority::IOPRIO_CLASS_IDLE
+            );
+            drop(guard);
+            assert_eq!(ioprio(), previous);
+        })
+        .join()
+        .unwrap();
+    }
+}
@@ -1,8 +1,12 @@
+#![cfg(feature = "fs")]
+
 extern crate lava_torrent;
 extern crate rand;
 
 use lava_torrent::bencode::BencodeElem;
-use lava_torrent::torrent::v1::{Integer, Torrent, TorrentBuilder};
+use lava_torrent::torrent::v1::{
+    BatchBuilder, BuildEvent, Integer, Torrent, TorrentBuilder, ZeroLengthFilePolicy,
+};
 use lava_torrent::LavaTorrentError;
 use rand::Rng;
 
@@ -176,6 +180,132 @@ fn build_single_file_parallel_non_blocking_cancel() {
     }
 }
 
+#[test]
+fn build_and_verify_ok() {
+    let torrent = TorrentBuilder::new("tests/files", PIECE_LENGTH)
+        .build_and_verify()
+        .unwrap();
+    assert_eq!(
+        torrent,
+        TorrentBuilder::new("tests/files", PIECE_LENGTH)
+            .build()
+            .unwrap()
+    );
+}
+
+#[test]
+fn build_from_bytes_ok() {
+    let content = std::fs::read("tests/files/byte_sequence").unwrap();
+
+    for piece_length in [64, 128, PIECE_LENGTH] {
+        let builder = TorrentBuilder::new("tests/files/byte_sequence", piece_length)
+            .set_announce(Some("udp://tracker.example.com:6969/announce".to_owned()))
+            .set_file_sha1(true);
+
+        assert_eq!(
+            builder.clone().build_from_bytes(&content).unwrap(),
+            builder.build().unwrap()
+        );
+    }
+}
+
+#[test]
+fn build_from_entries_ok() {
+    let builder = TorrentBuilder::new("tests/files", 128)
+        .set_file_sha1(true)
+        .set_merkle(true);
+    let torrent = builder.clone().build().unwrap();
+    let mut entries = torrent
+        .info
+        .files
+        .iter()
+        .flatten()
+        .map(|file| {
+            let content = std::fs::read(std::path::Path::new("tests/files").join(&file.path));
+            (file.path.clone(), content.unwrap())
+        })
+        .collect::<Vec<_>>();
+    entries.reverse();
+
+    assert_eq!(builder.build_from_entries(entries).unwrap(), torrent);
+}
+
+#[test]
+fn build_from_entries_zero_length() {
+    let entries = vec![("a", "content"), ("b", "")];
+
+    let torrent = TorrentBuilder::new("dir", PIECE_LENGTH)
+        .set_zero_length_file_policy(ZeroLengthFilePolicy::Skip)
+        .build_from_entries(entries.clone())
+        .unwrap();
+    assert_eq!(torrent.info.into_mut().files.unwrap().len(), 1);
+
+    match TorrentBuilder::new("dir", PIECE_LENGTH)
+        .set_zero_length_file_policy(ZeroLengthFilePolicy::Error)
+        .build_from_entries(entries)
+    {
+        Err(LavaTorrentError::TorrentBuilderFailure(m)) => {
+            assert_eq!(m, "[b] is a zero-length file.")
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn build_merkle_ok() {
+    let torrent = TorrentBuilder::new("tests/files", PIECE_LENGTH)
+        .set_merkle(true)
+        .build_and_verify()
+        .unwrap();
+    let pieces = TorrentBuilder::new("tests/files", PIECE_LENGTH)
+        .build()
+        .unwrap()
+        .info
+        .into_mut()
+        .pieces;
+
+    assert!(torrent.is_merkle());
+    assert_eq!(
+        torrent.root_hash().unwrap(),
+        Some(&pieces.merkle_root().unwrap()[..])
+    );
+    assert_eq!(
+        Torrent::read_from_bytes(torrent.clone().encode().unwrap()).unwrap(),
+        torrent
+    );
+}
+
+#[test]
+fn estimate_output_size_ok() {
+    let builders = vec![
+        TorrentBuilder::new("tests/files", PIECE_LENGTH),
+        TorrentBuilder::new("tests/files", PIECE_LENGTH)
+            .set_announce(Some(
+                "udp://tracker.coppersurfer.tk:6969/announce".to_owned(),
+            ))
+            .set_privacy(true)
+            .set_entropy(true)
+            .set_file_sha1(true),
+        TorrentBuilder::new("tests/files/byte_sequence", PIECE_LENGTH),
+        TorrentBuilder::new("tests/files/byte_sequence", 2 * PIECE_LENGTH).set_file_sha1(true),
+        TorrentBuilder::new("tests/files", PIECE_LENGTH).set_merkle(true),
+    ];
+
+    for builder in builders {
+        let size = builder.estimate_output_size().unwrap();
+        let torrent = builder.set_num_threads(1).build().unwrap();
+        assert_eq!(size, torrent.encode().unwrap().len());
+    }
+}
+
+#[test]
+fn build_then_verify_ok() {
+    let torrent = TorrentBuilder::new("tests/files", PIECE_LENGTH)
+        .build()
+        .unwrap();
+    assert!(torrent.verify("tests/files").unwrap().is_intact());
+}
+
 #[test]
 fn build_multi_file_ok() {
     let output_name = rand_file_name() + ".torrent";
@@ -259,6 +389,116 @@ fn build_multi_file_non_blocking_cancel() {
     }
 }
 
+#[test]
+fn build_non_blocking_progress_receiver() {
+    for num_threads in [1, 4] {
+        // slow enough that the receiver is connected before most events
+        let build = TorrentBuilder::new("tests/files", 16384)
+            .set_max_read_rate(400_000)
+            .set_num_threads(num_threads)
+            .build_non_blocking()
+            .unwrap();
+        let events: Vec<_> = build.progress_receiver().into_iter().collect();
+
+        assert_eq!(events.last(), Some(&BuildEvent::Finished));
+        assert!(events.iter().any(|event| match event {
+            BuildEvent::FileStarted { path } =>
+                path.ends_with("ubuntu-16.04.4-desktop-amd64.iso.torrent"),
+            _ => false,
+        }));
+        let n_piece_total = build.get_n_piece_total();
+        assert_eq!(
+            events
+                .iter()
+                .filter_map(|event| match *event {
+                    BuildEvent::PieceHashed {
+                        n_piece_processed,
+                        n_piece_total: total,
+                    } => {
+                        assert_eq!(total, n_piece_total);
+                        Some(n_piece_processed)
+                    }
+                    _ => None,
+                })
+                .max(),
+            Some(n_piece_total)
+        );
+        assert!(build.get_output().is_ok());
+    }
+}
+
+#[test]
+fn build_non_blocking_file_progress() {
+    for num_threads in [1, 4] {
+        let build = TorrentBuilder::new("tests/files", 16384)
+            .set_max_read_rate(400_000)
+            .set_num_threads(num_threads)
+            .build_non_blocking()
+            .unwrap();
+        let receiver = build.progress_receiver();
+
+        let mut current_files = Vec::new();
+        while !build.is_finished() {
+            if let Some(path) = build.get_current_file() {
+                current_files.push(path);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(current_files
+            .iter()
+            .any(|path| path.ends_with("ubuntu-16.04.4-desktop-amd64.iso.torrent")));
+        assert_eq!(build.get_current_file(), None);
+        assert!(receiver.into_iter().any(|event| match event {
+            BuildEvent::FileFinished { path } => {
+                path.ends_with("ubuntu-16.04.4-desktop-amd64.iso.torrent")
+            }
+            _ => false,
+        }));
+
+        let file_progress = build.get_file_progress();
+        let torrent = build.get_output().unwrap();
+        let files = torrent.info.into_mut().files.unwrap();
+        assert_eq!(file_progress.len(), files.len());
+        for (progress, file) in file_progress.iter().zip(files) {
+            assert!(progress.path.ends_with(&file.path));
+            assert_eq!(progress.length, file.length as u64);
+            assert_eq!(progress.n_bytes_processed, progress.length);
+            assert!(progress.is_finished());
+        }
+    }
+}
+
+#[test]
+fn build_non_blocking_progress_receiver_cancel() {
+    let build = TorrentBuilder::new("tests/files", 16384)
+        .set_max_read_rate(100_000)
+        .set_num_threads(1)
+        .build_non_blocking()
+        .unwrap();
+    let receiver = build.progress_receiver();
+    build.cancel();
+
+    assert_eq!(
+        receiver.into_iter().last(),
+        Some(BuildEvent::Failed {
+            message: "failed to build torrent: build canceled by client".to_owned()
+        })
+    );
+}
+
+#[test]
+fn build_non_blocking_progress_receiver_after_finish() {
+    let build = TorrentBuilder::new("tests/files/byte_sequence", 64)
+        .build_non_blocking()
+        .unwrap();
+    while !build.is_finished() {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    assert!(build.progress_receiver().recv().is_err());
+    assert!(build.get_output().is_ok());
+}
+
 #[test]
 fn build_multi_file_parallel_ok() {
     let output_name = rand_file_name() + ".torrent";
@@ -339,6 +579,127 @@ fn build_multi_file_parallel_non_blocking_cancel() {
     }
 }
 
+#[test]
+fn build_zero_length_file_policy() {
+    let root = std::path::PathBuf::from(rand_file_name() + "_zero_length");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("empty"), b"").unwrap();
+    std::fs::write(root.join("file"), b"content").unwrap();
+
+    let torrent = TorrentBuilder::new(&root, PIECE_LENGTH).build().unwrap();
+    assert_eq!(torrent.info.into_mut().files.unwrap().len(), 2);
+
+    let torrent = TorrentBuilder::new(&root, PIECE_LENGTH)
+        .set_zero_length_file_policy(ZeroLengthFilePolicy::Skip)
+        .build()
+        .unwrap();
+    let files = torrent.info.into_mut().files.unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, std::path::PathBuf::from("file"));
+
+    match TorrentBuilder::new(&root, PIECE_LENGTH)
+        .set_zero_length_file_policy(ZeroLengthFilePolicy::Error)
+        .build()
+    {
+        Err(LavaTorrentError::TorrentBuilderFailure(m)) => {
+            assert!(m.ends_with("empty] is a zero-length file."))
+        }
+        _ => panic!(),
+    }
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn build_batch_ok() {
+    let outputs = BatchBuilder::new()
+        .add_job(TorrentBuilder::new("tests/files", PIECE_LENGTH))
+        .add_job(TorrentBuilder::new("tests/files", 42))
+        .add_job(TorrentBuilder::new(
+            "tests/files/byte_sequence",
+            PIECE_LENGTH,
+        ))
+        .build()
+        .unwrap();
+
+    assert_eq!(outputs.len(), 3);
+    assert_eq!(
+        outputs[0].as_ref().unwrap(),
+        &TorrentBuilder::new("tests/files", PIECE_LENGTH)
+            .build()
+            .unwrap(),
+    );
+    match outputs[1] {
+        Err(LavaTorrentError::TorrentBuilderFailure(ref m)) => assert_eq!(
+            m,
+            "TorrentBuilder has `piece_length` that is not a power of 2."
+        ),
+        _ => panic!(),
+    }
+    assert_eq!(
+        outputs[2].as_ref().unwrap(),
+        &TorrentBuilder::new("tests/files/byte_sequence", PIECE_LENGTH)
+            .build()
+            .unwrap(),
+    );
+}
+
+#[test]
+fn build_batch_non_blocking_ok() {
+    let build = BatchBuilder::new()
+        .add_job(TorrentBuilder::new("tests/files", PIECE_LENGTH))
+        .add_job(TorrentBuilder::new(
+            "tests/files/byte_sequence",
+            PIECE_LENGTH,
+        ))
+        .set_num_threads(2)
+        .build_non_blocking()
+        .unwrap();
+    assert_eq!(build.get_n_job_total(), 2);
+
+    while !build.is_finished() {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert_eq!(build.get_n_job_finished(), 2);
+    assert_eq!(build.get_progress(), 100);
+    assert_eq!(build.get_job_progress(1), Some(100));
+    assert_eq!(build.get_job_progress(2), None);
+
+    let outputs = build.get_output().unwrap();
+    assert_eq!(
+        outputs[0].as_ref().unwrap(),
+        &TorrentBuilder::new("tests/files", PIECE_LENGTH)
+            .build()
+            .unwrap(),
+    );
+    assert_eq!(
+        outputs[1].as_ref().unwrap(),
+        &TorrentBuilder::new("tests/files/byte_sequence", PIECE_LENGTH)
+            .build()
+            .unwrap(),
+    );
+}
+
+#[test]
+fn build_batch_non_blocking_cancel() {
+    let build = (0..16)
+        .fold(BatchBuilder::new(), |batch, _| {
+            batch.add_job(TorrentBuilder::new("tests/files", PIECE_LENGTH))
+        })
+        .build_non_blocking()
+        .unwrap();
+    build.cancel();
+
+    // jobs that have finished before `cancel()` are not affected,
+    // but the last one should definitely have been canceled
+    match build.get_output().unwrap().pop().unwrap() {
+        Err(LavaTorrentError::TorrentBuilderFailure(m)) => {
+            assert_eq!(m, "build canceled by client")
+        }
+        _ => panic!(),
+    }
+}
+
 #[test]
 fn build_with_name() {
     let output_name = rand_file_name() + ".torrent";
@@ -354,11 +715,173 @@ fn build_with_name() {
         .unwrap();
 
     assert_eq!(
-        Torrent::read_from_file(output_name).unwrap().name,
+        Torrent::read_from_file(output_name).unwrap().info.name,
         "file".to_owned(),
     );
 }
 
+#[test]
+fn build_with_web_seeds() {
+    let output_name = rand_file_name() + ".torrent";
+
+    TorrentBuilder::new("tests/files/tails-amd64-3.6.1.torrent", PIECE_LENGTH)
+        .set_web_seeds(vec![
+            "https://example.org/path1".to_owned(),
+            "https://example.org/path2".to_owned(),
+        ])
+        .build()
+        .unwrap()
+        .write_into_file(&output_name)
+        .unwrap();
+
+    let torrent = Torrent::read_from_file(output_name).unwrap();
+    assert_eq!(
+        torrent.url_list,
+        Some(vec![
+            "https://example.org/path1".to_owned(),
+            "https://example.org/path2".to_owned(),
+        ]),
+    );
+}
+
+#[test]
+fn build_with_file_sha1() {
+    let torrent = TorrentBuilder::new("tests/files", PIECE_LENGTH)
+        .set_file_sha1(true)
+        .set_num_threads(1)
+        .build()
+        .unwrap();
+    let torrent_parallel = TorrentBuilder::new("tests/files", PIECE_LENGTH)
+        .set_file_sha1(true)
+        .set_num_threads(4)
+        .build()
+        .unwrap();
+    assert_eq!(torrent, torrent_parallel);
+
+    let files = torrent.info.into_mut().files.unwrap();
+    assert!(files.iter().all(|file| file
+        .extra_fields
+        .as_ref()
+        .is_some_and(|fields| fields.contains_key("sha1"))));
+
+    // byte_sequence contains 256 bytes ranging from 0x0 to 0xff
+    let byte_sequence = files
+        .iter()
+        .find(|file| file.path == std::path::Path::new("byte_sequence"))
+        .unwrap();
+    assert_eq!(
+        byte_sequence.extra_fields.as_ref().unwrap().get("sha1"),
+        Some(&BencodeElem::Bytes(vec![
+            73, 22, 214, 189, 183, 247, 142, 104, 3, 105, 140, 171, 50, 209, 88, 110, 164, 87, 223,
+            200,
+        ])),
+    );
+}
+
+#[test]
+fn build_with_max_read_rate() {
+    let torrent = TorrentBuilder::new("tests/files", PIECE_LENGTH)
+        .set_num_threads(1)
+        .build()
+        .unwrap();
+
+    // tests/files contains ~160 KB, all but the last piece of which have to wait
+    for num_threads in [1, 4] {
+        let start = std::time::Instant::now();
+        let throttled = TorrentBuilder::new("tests/files", PIECE_LENGTH)
+            .set_max_read_rate(400_000)
+            .set_num_threads(num_threads)
+            .build()
+            .unwrap();
+        assert!(start.elapsed() >= std::time::Duration::from_millis(250));
+        assert_eq!(throttled, torrent);
+    }
+}
+
+#[test]
+fn build_with_low_priority_io() {
+    for num_threads in [1, 4] {
+        assert_eq!(
+            TorrentBuilder::new("tests/files", PIECE_LENGTH)
+                .set_file_sha1(true)
+                .set_low_priority_io(true)
+                .set_num_threads(num_threads)
+                .build()
+                .unwrap(),
+            TorrentBuilder::new("tests/files", PIECE_LENGTH)
+                .set_file_sha1(true)
+                .set_num_threads(num_threads)
+                .build()
+                .unwrap()
+        );
+    }
+}
+
+#[test]
+fn build_into_ok() {
+    for path in ["tests/files", "tests/files/tails-amd64-3.6.1.torrent"] {
+        let builder = TorrentBuilder::new(path, 1024)
+            .set_announce(Some("url".to_owned()))
+            .add_extra_field("comment".to_owned(), BencodeElem::String("c".to_owned()))
+            .add_extra_info_field("source".to_owned(), BencodeElem::String("s".to_owned()))
+            .set_privacy(true)
+            .set_file_sha1(true);
+
+        let mut encoded = Vec::new();
+        builder.clone().build_into(&mut encoded).unwrap();
+        assert_eq!(encoded, builder.build().unwrap().encode().unwrap());
+    }
+}
+
+#[test]
+fn build_into_file_ok() {
+    let output_name = rand_file_name() + ".torrent";
+    let builder = TorrentBuilder::new("tests/files", PIECE_LENGTH);
+
+    builder.clone().build_into_file(&output_name).unwrap();
+    assert_eq!(
+        Torrent::read_from_file(&output_name).unwrap(),
+        builder.build().unwrap()
+    );
+}
+
+#[test]
+fn build_into_merkle() {
+    let mut encoded = Vec::new();
+    match TorrentBuilder::new("tests/files", PIECE_LENGTH)
+        .set_merkle(true)
+        .build_into(&mut encoded)
+    {
+        Err(LavaTorrentError::TorrentBuilderFailure(m)) => assert_eq!(
+            m,
+            "TorrentBuilder has `merkle` set, which requires all piece hashes in memory."
+        ),
+        _ => panic!(),
+    }
+    assert!(encoded.is_empty());
+}
+
+#[test]
+fn build_with_entropy() {
+    let torrent = TorrentBuilder::new("tests/files/byte_sequence", PIECE_LENGTH)
+        .set_entropy(true)
+        .build()
+        .unwrap();
+    let torrent_2 = TorrentBuilder::new("tests/files/byte_sequence", PIECE_LENGTH)
+        .set_entropy(true)
+        .build()
+        .unwrap();
+
+    assert_eq!(torrent.info.pieces, torrent_2.info.pieces);
+    assert!(torrent
+        .info
+        .extra_info_fields
+        .as_ref()
+        .unwrap()
+        .contains_key("entropy"));
+    assert_ne!(torrent.info_hash(), torrent_2.info_hash());
+}
+
 #[test]
 fn build_private() {
     let output_name = rand_file_name() + ".torrent";
@@ -411,6 +934,24 @@ fn build_symbolic_link() {
     );
 }
 
+#[cfg(unix)]
+#[test]
+fn build_symbolic_link_loop() {
+    let root = std::path::PathBuf::from(rand_file_name() + "_loop");
+    std::fs::create_dir_all(root.join("dir")).unwrap();
+    std::fs::write(root.join("dir").join("file"), b"content").unwrap();
+    std::os::unix::fs::symlink("..", root.join("dir").join("link")).unwrap();
+
+    match TorrentBuilder::new(&root, PIECE_LENGTH).build() {
+        Err(LavaTorrentError::TorrentBuilderFailure(m)) => {
+            assert!(m.ends_with("link] is a symbolic link loop."))
+        }
+        _ => panic!(),
+    }
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
 #[test]
 fn build_nested_dir_ok() {
     let output_name = rand_file_name() + ".torrent";
@@ -3,7 +3,9 @@ extern crate lava_torrent;
 
 use conv::ValueFrom;
 use lava_torrent::bencode::BencodeElem;
-use lava_torrent::torrent::v1::{File, Torrent};
+#[cfg(any(feature = "fs", feature = "http"))]
+use lava_torrent::bencode::ReadLimit;
+use lava_torrent::torrent::v1::{File, Torrent, ValidationPolicy};
 use std::collections::HashMap;
 use std::io::{BufReader, Read};
 use std::iter::FromIterator;
@@ -27,18 +29,21 @@ fn read_from_bytes() {
             vec!["http://ipv6.torrent.ubuntu.com:6969/announce".to_owned()],
         ]),
     );
-    assert_eq!(parsed.length, 1_624_211_456);
-    assert_eq!(parsed.files, None);
-    assert_eq!(parsed.name, "ubuntu-16.04.4-desktop-amd64.iso".to_owned());
-    assert_eq!(parsed.piece_length, 524_288);
+    assert_eq!(parsed.info.length, 1_624_211_456);
+    assert_eq!(parsed.info.files, None);
+    assert_eq!(
+        parsed.info.name,
+        "ubuntu-16.04.4-desktop-amd64.iso".to_owned()
+    );
+    assert_eq!(parsed.info.piece_length, 524_288);
     // Check the entire `pieces` vec is not very meaningful here...
     // So we check its length instead: len(pieces) == ceil(torrent_size / block_size).
     // The EPSILON comparison comes from
     // https://rust-lang-nursery.github.io/rust-clippy/v0.0.189/index.html#float_cmp
     assert!(
-        (f64::value_from(parsed.pieces.len()).unwrap()
-            - (f64::value_from(parsed.length).unwrap()
-                / f64::value_from(parsed.piece_length).unwrap())
+        (f64::value_from(parsed.info.pieces.len()).unwrap()
+            - (f64::value_from(parsed.info.length).unwrap()
+                / f64::value_from(parsed.info.piece_length).unwrap())
             .ceil())
         .abs()
             < f64::EPSILON
@@ -59,7 +64,7 @@ fn read_from_bytes() {
             .into_iter()
         )),
     );
-    assert_eq!(parsed.extra_info_fields, None);
+    assert_eq!(parsed.info.extra_info_fields, None);
     assert_eq!(
         parsed.info_hash(),
         "778ce280b595e57780ff083f2eb6f897dfa4a4ee".to_owned()
@@ -76,6 +81,7 @@ fn read_from_bytes() {
 }
 
 #[test]
+#[cfg(feature = "fs")]
 fn read_from_file() {
     let file = std::fs::File::open("tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent").unwrap();
     let mut bytes = Vec::new();
@@ -87,6 +93,27 @@ fn read_from_file() {
     );
 }
 
+#[test]
+#[cfg(feature = "fs")]
+fn read_from_file_missing() {
+    match Torrent::read_from_file("tests/files/missing.torrent") {
+        Err(lava_torrent::LavaTorrentError::FileIo { path, source, .. }) => {
+            assert_eq!(path, PathBuf::from("tests/files/missing.torrent"));
+            assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+        }
+        _ => panic!(),
+    }
+
+    match BencodeElem::from_file("tests/files/missing.torrent") {
+        Err(e @ lava_torrent::LavaTorrentError::FileIo { .. }) => {
+            assert!(e
+                .to_string()
+                .starts_with("IO error on \"tests/files/missing.torrent\": "));
+        }
+        _ => panic!(),
+    }
+}
+
 #[test]
 fn read_from_bytes_multiple_files() {
     let file = std::fs::File::open("tests/files/tails-amd64-3.6.1.torrent").unwrap();
@@ -108,32 +135,34 @@ fn read_from_bytes_multiple_files() {
             ],
         ]),
     );
-    assert_eq!(parsed.length, 1_225_568_484);
+    assert_eq!(parsed.info.length, 1_225_568_484);
     assert_eq!(
-        parsed.files,
+        parsed.info.files,
         Some(vec![
             File {
                 length: 1_225_568_256,
                 path: PathBuf::from("tails-amd64-3.6.1.iso"),
                 extra_fields: None,
+                raw_extra_fields: None,
             },
             File {
                 length: 228,
                 path: PathBuf::from("tails-amd64-3.6.1.iso.sig"),
                 extra_fields: None,
+                raw_extra_fields: None,
             },
         ])
     );
-    assert_eq!(parsed.name, "tails-amd64-3.6.1".to_owned());
-    assert_eq!(parsed.piece_length, 262_144);
+    assert_eq!(parsed.info.name, "tails-amd64-3.6.1".to_owned());
+    assert_eq!(parsed.info.piece_length, 262_144);
     // Check the entire `pieces` vec is not very meaningful here...
     // So we check its length instead: len(pieces) == ceil(torrent_size / block_size).
     // The EPSILON comparison comes from
     // https://rust-lang-nursery.github.io/rust-clippy/v0.0.189/index.html#float_cmp
     assert!(
-        (f64::value_from(parsed.pieces.len()).unwrap()
-            - (f64::value_from(parsed.length).unwrap()
-                / f64::value_from(parsed.piece_length).unwrap())
+        (f64::value_from(parsed.info.pieces.len()).unwrap()
+            - (f64::value_from(parsed.info.length).unwrap()
+                / f64::value_from(parsed.info.piece_length).unwrap())
             .ceil())
         .abs()
             < f64::EPSILON
@@ -154,7 +183,7 @@ fn read_from_bytes_multiple_files() {
             .into_iter()
         )),
     );
-    assert_eq!(parsed.extra_info_fields, None);
+    assert_eq!(parsed.info.extra_info_fields, None);
     assert_eq!(
         parsed.info_hash(),
         "a2a8d9b1ba0b1ac3d1ffa8062e02c0f9c23de31a".to_owned()
@@ -172,6 +201,7 @@ fn read_from_bytes_multiple_files() {
 }
 
 #[test]
+#[cfg(feature = "fs")]
 fn read_from_files_multiple_files() {
     let file = std::fs::File::open("tests/files/tails-amd64-3.6.1.torrent").unwrap();
     let mut bytes = Vec::new();
@@ -182,3 +212,329 @@ fn read_from_files_multiple_files() {
         Torrent::read_from_file("tests/files/tails-amd64-3.6.1.torrent").unwrap(),
     );
 }
+
+#[test]
+#[cfg(feature = "fs")]
+fn read_metadata_from_file() {
+    let torrent = Torrent::read_from_file("tests/files/tails-amd64-3.6.1.torrent").unwrap();
+    let metadata =
+        Torrent::read_metadata_from_file("tests/files/tails-amd64-3.6.1.torrent").unwrap();
+
+    assert_eq!(metadata.announce, torrent.announce);
+    assert_eq!(metadata.announce_list, torrent.announce_list);
+    assert_eq!(metadata.length, torrent.info.length);
+    assert_eq!(metadata.files, torrent.info.files);
+    assert_eq!(metadata.name, torrent.info.name);
+    assert_eq!(metadata.piece_length, torrent.info.piece_length);
+    assert_eq!(metadata.num_pieces, torrent.info.pieces.len());
+    assert_eq!(metadata.extra_fields, torrent.extra_fields);
+    assert_eq!(metadata.extra_info_fields, torrent.info.extra_info_fields);
+}
+
+#[test]
+#[cfg(feature = "fs")]
+fn read_metadata_from_bytes() {
+    let file = std::fs::File::open("tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent").unwrap();
+    let mut bytes = Vec::new();
+    BufReader::new(file).read_to_end(&mut bytes).unwrap();
+
+    assert_eq!(
+        Torrent::read_metadata_from_bytes(bytes).unwrap(),
+        Torrent::read_metadata_from_file("tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent")
+            .unwrap(),
+    );
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn read_from_file_mmap() {
+    assert_eq!(
+        unsafe { Torrent::read_from_file_mmap("tests/files/tails-amd64-3.6.1.torrent") }.unwrap(),
+        Torrent::read_from_file("tests/files/tails-amd64-3.6.1.torrent").unwrap(),
+    );
+}
+
+#[test]
+#[cfg(feature = "fs")]
+fn read_from_file_with_limits() {
+    let path = "tests/files/tails-amd64-3.6.1.torrent";
+    let length = usize::try_from(std::fs::metadata(path).unwrap().len()).unwrap();
+
+    assert_eq!(
+        Torrent::read_from_file_with_limits(path, &[ReadLimit::Bytes(length)]).unwrap(),
+        Torrent::read_from_file(path).unwrap(),
+    );
+    assert!(Torrent::read_from_file_with_limits(path, &[ReadLimit::Bytes(length - 1)]).is_err());
+    assert!(Torrent::read_from_file_with_limits(path, &[ReadLimit::Elements(10)]).is_err());
+}
+
+#[test]
+#[cfg(feature = "fs")]
+fn read_canonical_file_strict() {
+    let path = "tests/files/tails-amd64-3.6.1.torrent";
+
+    assert_eq!(
+        BencodeElem::from_file_strict(path, &[]).unwrap(),
+        BencodeElem::from_file(path).unwrap(),
+    );
+}
+
+#[test]
+#[cfg(feature = "fs")]
+fn read_from_file_with_policy() {
+    let path = "tests/files/tails-amd64-3.6.1.torrent";
+
+    for &policy in &[
+        ValidationPolicy::Strict,
+        ValidationPolicy::Standard,
+        ValidationPolicy::Lenient,
+    ] {
+        assert_eq!(
+            Torrent::read_from_file_with_policy(path, policy).unwrap(),
+            Torrent::read_from_file(path).unwrap(),
+        );
+    }
+}
+
+#[test]
+fn read_with_utf8_fallback() {
+    let mut bytes =
+        b"d4:infod5:filesld6:lengthi4e4:pathl2:\xff\xfee10:path.utf-8l4:fileeee".to_vec();
+    bytes.extend_from_slice(b"4:name2:\xff\xfe10:name.utf-84:name12:piece lengthi2e6:pieces40:");
+    bytes.extend_from_slice(&[0xff; 40]);
+    bytes.extend_from_slice(b"ee");
+
+    let torrent = Torrent::read_from_bytes(&bytes).unwrap();
+    assert_eq!(torrent.info.name, "name");
+    assert_eq!(
+        torrent.info.files.as_ref().unwrap()[0].path,
+        PathBuf::from("file")
+    );
+    // re-encoding preserves the original (invalid) name and path
+    assert_eq!(torrent.encode().unwrap(), bytes);
+
+    assert!(Torrent::read_from_bytes_with_policy(&bytes, ValidationPolicy::Strict).is_err());
+}
+
+#[test]
+fn read_with_decoder() {
+    let mut bytes =
+        b"d8:encoding10:ISO-8859-14:infod5:filesld6:lengthi4e4:pathl6:r\xe9sum\xe9e".to_vec();
+    bytes.extend_from_slice(b"ee4:name4:caf\xe912:piece lengthi2e6:pieces40:");
+    bytes.extend_from_slice(&[0xff; 40]);
+    bytes.extend_from_slice(b"ee");
+    let latin1 = |encoding: &str, bytes: &[u8]| {
+        if encoding == "ISO-8859-1" {
+            Some(bytes.iter().map(|&b| char::from(b)).collect())
+        } else {
+            None
+        }
+    };
+
+    let torrent = Torrent::read_from_bytes_with_decoder(&bytes, latin1).unwrap();
+    assert_eq!(torrent.info.name, "café");
+    assert_eq!(
+        torrent.info.files.as_ref().unwrap()[0].path,
+        PathBuf::from("résumé")
+    );
+    // re-encoding preserves the original name and path
+    assert_eq!(torrent.encode().unwrap(), bytes);
+
+    // decoder can't handle the encoding
+    assert!(Torrent::read_from_bytes_with_decoder(&bytes, |_, _| None).is_err());
+}
+
+#[test]
+fn read_with_raw_path() {
+    let mut bytes = b"d4:infod5:filesld6:lengthi4e4:pathl3:dir2:\xff\xfeeee".to_vec();
+    bytes.extend_from_slice(b"4:name4:name12:piece lengthi2e6:pieces40:");
+    bytes.extend_from_slice(&[0xff; 40]);
+    bytes.extend_from_slice(b"ee");
+
+    let torrent = Torrent::read_from_bytes(&bytes).unwrap();
+    let file = &torrent.info.files.as_ref().unwrap()[0];
+    assert_eq!(file.path, PathBuf::from("dir/\u{fffd}\u{fffd}"));
+    assert_eq!(file.raw_path(), Some(vec![&b"dir"[..], &[0xff, 0xfe][..]]));
+    // re-encoding preserves the original path
+    assert_eq!(torrent.encode().unwrap(), bytes);
+
+    assert!(Torrent::read_from_bytes_with_policy(&bytes, ValidationPolicy::Strict).is_err());
+}
+
+// Serve `response` to a single client and return the URL to request.
+#[cfg(feature = "http")]
+fn serve(response: Vec<u8>) -> String {
+    use std::io::{BufRead, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/sample.torrent", listener.local_addr().unwrap());
+
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 2 {
+            line.clear();
+        }
+        stream.write_all(&response).unwrap();
+    });
+
+    url
+}
+
+#[cfg(feature = "http")]
+fn torrent_response(content_type: &str) -> (Vec<u8>, Vec<u8>) {
+    let body = std::fs::read("tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent").unwrap();
+    let mut response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+        content_type,
+        body.len()
+    )
+    .into_bytes();
+    response.extend_from_slice(&body);
+    (response, body)
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn read_from_url() {
+    let (response, body) = torrent_response("application/x-bittorrent");
+    let url = serve(response);
+
+    assert_eq!(
+        Torrent::read_from_url(&url).unwrap(),
+        Torrent::read_from_bytes(body).unwrap()
+    );
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn read_from_url_gzip() {
+    let body = std::fs::read("tests/samples/files.torrent.gz").unwrap();
+    let mut response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/x-bittorrent\r\nContent-Length: {}\r\n\r\n",
+        body.len()
+    )
+    .into_bytes();
+    response.extend_from_slice(&body);
+    let url = serve(response);
+
+    assert_eq!(
+        Torrent::read_from_url(&url).unwrap(),
+        Torrent::read_from_bytes(std::fs::read("tests/samples/files.torrent").unwrap()).unwrap()
+    );
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn read_from_url_too_large() {
+    let (response, _) = torrent_response("application/x-bittorrent");
+    let url = serve(response);
+
+    assert!(Torrent::read_from_url_with_limits(&url, &[ReadLimit::Bytes(1024)]).is_err());
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn read_from_url_html() {
+    let (response, _) = torrent_response("text/html; charset=utf-8");
+    let url = serve(response);
+
+    match Torrent::read_from_url(&url) {
+        Err(lava_torrent::LavaTorrentError::MalformedResponse(m)) => assert_eq!(
+            m,
+            "Server returned unexpected Content-Type: text/html; charset=utf-8"
+        ),
+        _ => panic!(),
+    }
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn read_from_url_redirect_to_https() {
+    let url = serve(
+        b"HTTP/1.1 302 Found\r\nLocation: https://127.0.0.1:1/sample.torrent\r\n\
+          Content-Length: 0\r\n\r\n"
+            .to_vec(),
+    );
+
+    // the redirect is followed (nothing listens on port 1)
+    match Torrent::read_from_url(&url) {
+        Err(lava_torrent::LavaTorrentError::Io(e)) => {
+            assert_eq!(e.kind(), std::io::ErrorKind::ConnectionRefused)
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn read_from_url_not_found() {
+    let url = serve(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_vec());
+
+    match Torrent::read_from_url(&url) {
+        Err(lava_torrent::LavaTorrentError::MalformedResponse(m)) => {
+            assert_eq!(m, "Server returned HTTP status 404.")
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+#[cfg(feature = "fs")]
+fn read_from_file_gzip() {
+    assert_eq!(
+        Torrent::read_from_file("tests/samples/files.torrent.gz").unwrap(),
+        Torrent::read_from_file("tests/samples/files.torrent").unwrap()
+    );
+}
+
+#[test]
+#[cfg(feature = "fs")]
+fn read_from_file_with_limits_gzip() {
+    let path = "tests/samples/files.torrent.gz";
+    let length = usize::try_from(
+        std::fs::metadata("tests/samples/files.torrent")
+            .unwrap()
+            .len(),
+    )
+    .unwrap();
+
+    // the limit applies to the decompressed torrent, not the file
+    assert_eq!(
+        Torrent::read_from_file_with_limits(path, &[ReadLimit::Bytes(length)]).unwrap(),
+        Torrent::read_from_file("tests/samples/files.torrent").unwrap(),
+    );
+    for limit in [length - 1, 16] {
+        match Torrent::read_from_file_with_limits(path, &[ReadLimit::Bytes(limit)]) {
+            Err(lava_torrent::LavaTorrentError::MalformedTorrent(m)) => {
+                assert_eq!(m, format!("Decompressed data exceeds {} bytes.", limit))
+            }
+            _ => panic!(),
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "fs")]
+fn read_from_file_with_policy_gzip() {
+    assert_eq!(
+        Torrent::read_from_file_with_policy(
+            "tests/samples/files.torrent.gz",
+            ValidationPolicy::Strict
+        )
+        .unwrap(),
+        Torrent::read_from_file("tests/samples/files.torrent").unwrap()
+    );
+}
+
+#[test]
+fn read_from_bytes_zstd() {
+    match Torrent::read_from_bytes([0x28, 0xb5, 0x2f, 0xfd, 0x00]) {
+        Err(lava_torrent::LavaTorrentError::MalformedTorrent(m)) => {
+            assert_eq!(m, "zstd-compressed torrents are not supported.")
+        }
+        _ => panic!(),
+    }
+}
@@ -1,7 +1,9 @@
+#![cfg(feature = "fs")]
+
 extern crate lava_torrent;
 extern crate rand;
 
-use lava_torrent::bencode::BencodeElem;
+use lava_torrent::bencode::{BencodeElem, WriteOptions};
 use rand::Rng;
 use std::collections::HashMap;
 use std::iter::FromIterator;
@@ -23,6 +25,19 @@ fn bencode_elem_write_string_to_file_ok() {
     assert_eq!(original, duplicate[0]);
 }
 
+#[test]
+fn bencode_elem_write_to_file_durable_ok() {
+    let original = BencodeElem::String("spam".to_owned());
+    let output = rand_file_name();
+
+    original
+        .write_into_file_with_options(&output, &WriteOptions { durable: true })
+        .unwrap();
+    let duplicate = BencodeElem::from_file(&output).unwrap();
+    assert_eq!(duplicate.len(), 1);
+    assert_eq!(original, duplicate[0]);
+}
+
 #[test]
 fn bencode_elem_write_bytes_to_file_ok() {
     let original = BencodeElem::Bytes(vec![0xff, 0xfe, 0xfd, 0xfc]);
@@ -75,3 +90,17 @@ fn bencode_elem_write_dictionary_to_file_ok() {
     assert_eq!(duplicate.len(), 1);
     assert_eq!(original, duplicate[0]);
 }
+
+#[test]
+fn bencode_elem_write_raw_dictionary_to_file_ok() {
+    let original = BencodeElem::List(vec![BencodeElem::RawDictionary(HashMap::from_iter(vec![
+        (vec![0xff, 0xfe], BencodeElem::Integer(42)),
+        (b"cow".to_vec(), BencodeElem::String("moo".to_owned())),
+    ]))]);
+    let output = rand_file_name();
+
+    original.write_into_file(&output).unwrap();
+    let duplicate = BencodeElem::from_file(&output).unwrap();
+    assert_eq!(duplicate.len(), 1);
+    assert_eq!(original, duplicate[0]);
+}
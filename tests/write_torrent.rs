@@ -1,6 +1,9 @@
+#![cfg(feature = "fs")]
+
 extern crate lava_torrent;
 extern crate rand;
 
+use lava_torrent::bencode::WriteOptions;
 use lava_torrent::torrent::v1::Torrent;
 use rand::Rng;
 use std::fs::File;
@@ -23,6 +26,20 @@ fn encode_torrent_ok() {
     assert_eq!(encoded, bytes);
 }
 
+#[test]
+fn encoded_len_ok() {
+    for path in &[
+        "tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent",
+        "tests/files/tails-amd64-3.6.1.torrent",
+    ] {
+        let torrent = Torrent::read_from_file(path).unwrap();
+        assert_eq!(
+            torrent.encoded_len(),
+            std::fs::metadata(path).unwrap().len() as usize
+        );
+    }
+}
+
 #[test]
 fn write_torrent_to_file_ok() {
     let file = File::open("tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent").unwrap();
@@ -36,6 +53,34 @@ fn write_torrent_to_file_ok() {
     assert_eq!(original, duplicate);
 }
 
+#[test]
+fn write_torrent_to_file_durable_ok() {
+    let output = rand_file_name();
+    let original =
+        Torrent::read_from_file("tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent").unwrap();
+    original
+        .clone()
+        .write_into_file_with_options(&output, &WriteOptions { durable: true })
+        .unwrap();
+    let duplicate = Torrent::read_from_file(&output).unwrap();
+    assert_eq!(original, duplicate);
+}
+
+#[test]
+fn write_torrent_to_file_missing_dir() {
+    let output = OUTPUT_ROOT.to_owned() + "missing/output.torrent";
+    let torrent =
+        Torrent::read_from_file("tests/files/ubuntu-16.04.4-desktop-amd64.iso.torrent").unwrap();
+
+    match torrent.write_into_file(&output) {
+        Err(lava_torrent::LavaTorrentError::FileIo { path, source, .. }) => {
+            assert_eq!(path, std::path::PathBuf::from(output));
+            assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+        }
+        _ => panic!(),
+    }
+}
+
 #[test]
 fn encode_torrent_multiple_files() {
     let file = File::open("tests/files/tails-amd64-3.6.1.torrent").unwrap();